@@ -30,7 +30,8 @@ use rusoto_kinesis::{
 };
 use rusoto_s3::{
     AbortMultipartUploadError, CompleteMultipartUploadError, CreateMultipartUploadError,
-    DeleteObjectError, GetObjectError, HeadObjectError, PutObjectError, UploadPartError,
+    DeleteObjectError, DeleteObjectsError, GetObjectError, HeadObjectError, ListObjectsV2Error,
+    PutObjectError, UploadPartError,
 };
 
 use crate::retry::Retryable;
@@ -90,6 +91,12 @@ impl Retryable for DeleteObjectError {
     }
 }
 
+impl Retryable for DeleteObjectsError {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
 impl Retryable for UploadPartError {
     fn is_retryable(&self) -> bool {
         false
@@ -126,6 +133,12 @@ impl Retryable for HeadObjectError {
     }
 }
 
+impl Retryable for ListObjectsV2Error {
+    fn is_retryable(&self) -> bool {
+        false
+    }
+}
+
 #[cfg(feature = "kinesis")]
 impl Retryable for GetRecordsError {
     fn is_retryable(&self) -> bool {
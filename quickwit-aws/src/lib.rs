@@ -20,6 +20,7 @@
 use std::time::Duration;
 
 use anyhow::Context;
+use hyper::client::HttpConnector;
 use hyper_rustls::HttpsConnectorBuilder;
 use once_cell::sync::OnceCell;
 use rusoto_core::credential::{AutoRefreshingProvider, ChainProvider, ProvideAwsCredentials};
@@ -36,10 +37,21 @@ const CREDENTIAL_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// Returns a hyper http client.
 pub fn get_http_client() -> HttpClient {
+    get_http_client_with_connect_timeout(None)
+}
+
+/// Returns a hyper http client giving up on establishing a connection after `connect_timeout_opt`,
+/// if set.
+pub fn get_http_client_with_connect_timeout(connect_timeout_opt: Option<Duration>) -> HttpClient {
     let mut http_config: HttpConfig = HttpConfig::default();
     // We experience an issue similar to https://github.com/hyperium/hyper/issues/2312.
     // It seems like the setting below solved it.
     http_config.pool_idle_timeout(POOL_IDLE_TIMEOUT);
+    let mut http_connector = HttpConnector::new();
+    // The connector must accept `https` URIs, the TLS layer being handled by the wrapping
+    // connector.
+    http_connector.enforce_http(false);
+    http_connector.set_connect_timeout(connect_timeout_opt);
     let builder = HttpsConnectorBuilder::new();
     let builder = builder.with_native_roots();
     let connector = builder
@@ -51,7 +63,7 @@ pub fn get_http_client() -> HttpClient {
         // (Besides, HTTP2 would be awesome but rusoto does not leverage
         // multiplexing anyway.)
         .enable_http1()
-        .build();
+        .wrap_connector(http_connector);
     HttpClient::from_connector_with_config(connector, http_config)
 }
 
@@ -75,6 +75,22 @@ impl Default for RetryParams {
     }
 }
 
+impl RetryParams {
+    /// Computes the delay to wait for after the `attempt_count`-th failed attempt. The delay is
+    /// drawn uniformly between zero and a ceiling that doubles with every attempt up to
+    /// `max_delay`, so that the clients failing at the same time do not retry in lockstep.
+    pub fn compute_delay(&self, attempt_count: usize) -> Duration {
+        let ceiling_ms = (self.base_delay.as_millis() as u64)
+            .saturating_mul(2u64.saturating_pow(attempt_count as u32))
+            .min(self.max_delay.as_millis() as u64);
+        if ceiling_ms == 0 {
+            return Duration::ZERO;
+        }
+        let delay_ms = rand::thread_rng().gen_range(0..ceiling_ms);
+        Duration::from_millis(delay_ms)
+    }
+}
+
 /// Retry with exponential backoff and full jitter. Implementation and default values originate from
 /// the Java SDK. See also: <https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/>.
 pub async fn retry<F, U, E, Fut>(retry_params: &RetryParams, f: F) -> Result<U, E>
@@ -106,18 +122,15 @@ where
                     return Err(error);
                 }
 
-                let ceiling_ms = (retry_params.base_delay.as_millis() as u64
-                    * 2u64.pow(attempt_count as u32))
-                .min(retry_params.max_delay.as_millis() as u64);
-                let delay_ms = rand::thread_rng().gen_range(0..ceiling_ms);
+                let delay = retry_params.compute_delay(attempt_count);
                 debug!(
                     attempt_count = %attempt_count,
-                    delay_ms = %delay_ms,
+                    delay_ms = %delay.as_millis(),
                     error = ?error,
                     "Request failed, retrying"
                 );
 
-                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                tokio::time::sleep(delay).await;
             }
         }
     }
@@ -125,7 +138,9 @@ where
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
     use std::sync::RwLock;
+    use std::time::Duration;
 
     use futures::future::ready;
 
@@ -139,6 +154,37 @@ mod tests {
         .await
     }
 
+    #[test]
+    fn test_retry_params_compute_delay() {
+        let retry_params = RetryParams {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(2_000),
+            max_attempts: 10,
+        };
+        for (attempt_count, expected_ceiling_ms) in [
+            (1, 200),
+            (2, 400),
+            (3, 800),
+            (4, 1_600),
+            (5, 2_000),
+            (64, 2_000),
+        ] {
+            let delays: HashSet<Duration> = (0..100)
+                .map(|_| retry_params.compute_delay(attempt_count))
+                .collect();
+            assert!(delays
+                .iter()
+                .all(|delay| *delay < Duration::from_millis(expected_ceiling_ms)));
+            // The delays are jittered.
+            assert!(delays.len() > 1);
+        }
+        let retry_params = RetryParams {
+            base_delay: Duration::ZERO,
+            ..retry_params
+        };
+        assert_eq!(retry_params.compute_delay(1), Duration::ZERO);
+    }
+
     #[tokio::test]
     async fn test_retry_accepts_ok() {
         assert_eq!(simulate_retries(vec![Ok(())]).await, Ok(()));
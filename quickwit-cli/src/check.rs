@@ -0,0 +1,148 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{arg, ArgMatches, Command};
+use quickwit_common::print_checklist;
+use quickwit_common::uri::Uri;
+use quickwit_indexing::check_source_connectivity;
+use quickwit_metastore::quickwit_metastore_uri_resolver;
+use quickwit_storage::quickwit_storage_uri_resolver;
+use tracing::debug;
+
+use crate::error::{CliError, CliErrorKind};
+use crate::{load_quickwit_config, parse_config_uri};
+
+pub fn build_check_command<'a>() -> Command<'a> {
+    Command::new("check")
+        .about("Checks that the node can reach its metastore, the storage of its indexes, and the sources of an index.")
+        .args(&[
+            arg!(--index <INDEX> "ID of the index whose storage and sources are checked. If unspecified, the storage of the default index root URI is checked.")
+                .required(false),
+            arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
+                .env("QW_DATA_DIR")
+                .required(false),
+        ])
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct CheckCliCommand {
+    pub config_uri: Uri,
+    pub data_dir: Option<PathBuf>,
+    pub index_id: Option<String>,
+}
+
+impl CheckCliCommand {
+    pub fn parse_cli_args(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = parse_config_uri(matches)?;
+        let data_dir = matches.value_of("data-dir").map(PathBuf::from);
+        let index_id = matches.value_of("index").map(String::from);
+        Ok(Self {
+            config_uri,
+            data_dir,
+            index_id,
+        })
+    }
+
+    pub async fn execute(self) -> anyhow::Result<()> {
+        check_connectivity_cli(self).await
+    }
+}
+
+async fn check_connectivity_cli(args: CheckCliCommand) -> anyhow::Result<()> {
+    debug!(args = ?args, "check-connectivity");
+    let config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
+
+    let mut check_item_names = Vec::new();
+    let mut check_item_results = Vec::new();
+
+    let metastore_res = match quickwit_metastore_uri_resolver()
+        .resolve(&config.metastore_uri)
+        .await
+    {
+        Ok(metastore) => metastore
+            .list_indexes_metadatas()
+            .await
+            .map(|_| metastore)
+            .map_err(anyhow::Error::from),
+        Err(error) => Err(anyhow::Error::from(error)),
+    };
+    check_item_names.push(format!("metastore `{}`", config.metastore_uri));
+    let metastore_opt = match metastore_res {
+        Ok(metastore) => {
+            check_item_results.push(Ok(()));
+            Some(metastore)
+        }
+        Err(error) => {
+            check_item_results.push(Err(error));
+            None
+        }
+    };
+    // The index metadata, hence the index storage and sources, cannot be fetched without a
+    // reachable metastore.
+    if let Some(metastore) = metastore_opt {
+        let (storage_uri, storage_config_opt, sources) = if let Some(index_id) = &args.index_id {
+            let index_metadata = metastore.index_metadata(index_id).await.with_context(|| {
+                format!("Failed to fetch the metadata of index `{}`.", index_id)
+            })?;
+            let sources = index_metadata.sources.into_values().collect();
+            (
+                index_metadata.index_uri,
+                index_metadata.storage_config,
+                sources,
+            )
+        } else {
+            (config.default_index_root_uri.clone(), None, Vec::new())
+        };
+        let storage_check_res = match quickwit_storage_uri_resolver()
+            .resolve_with_storage_config(&storage_uri, storage_config_opt.as_ref())
+        {
+            Ok(storage) => storage.check().await,
+            Err(error) => Err(anyhow::Error::from(error)),
+        };
+        check_item_names.push(format!("storage `{}`", storage_uri));
+        check_item_results.push(storage_check_res);
+
+        for source_config in sources {
+            check_item_names.push(format!("source `{}`", source_config.source_id));
+            check_item_results.push(check_source_connectivity(&source_config).await);
+        }
+    }
+
+    let num_failed_checks = check_item_results
+        .iter()
+        .filter(|check_item_res| check_item_res.is_err())
+        .count();
+    let checks: Vec<(&str, anyhow::Result<()>)> = check_item_names
+        .iter()
+        .map(String::as_str)
+        .zip(check_item_results)
+        .collect();
+    print_checklist(&checks);
+
+    if num_failed_checks > 0 {
+        return Err(CliError::new(
+            CliErrorKind::Connectivity,
+            anyhow::anyhow!("{} connectivity check(s) failed.", num_failed_checks),
+        )
+        .into());
+    }
+    Ok(())
+}
@@ -22,10 +22,14 @@ use clap::{Arg, ArgMatches, Command};
 use quickwit_config::DEFAULT_QW_CONFIG_PATH;
 use tracing::Level;
 
+use crate::check::{build_check_command, CheckCliCommand};
+use crate::config::{build_config_command, ConfigCliCommand};
+use crate::error::CliError;
 use crate::index::{build_index_command, IndexCliCommand};
 use crate::service::{build_run_command, RunCliCommand};
 use crate::source::{build_source_command, SourceCliCommand};
 use crate::split::{build_split_command, SplitCliCommand};
+use crate::QW_CONFIG_ENV_KEY;
 
 pub fn build_cli<'a>() -> Command<'a> {
     Command::new("Quickwit")
@@ -33,7 +37,7 @@ pub fn build_cli<'a>() -> Command<'a> {
             Arg::new("config")
                 .long("config")
                 .help("Config file location")
-                .env("QW_CONFIG")
+                .env(QW_CONFIG_ENV_KEY)
                 .default_value(DEFAULT_QW_CONFIG_PATH)
                 .global(true),
         )
@@ -41,6 +45,8 @@ pub fn build_cli<'a>() -> Command<'a> {
         .subcommand(build_index_command().display_order(2))
         .subcommand(build_source_command().display_order(3))
         .subcommand(build_split_command().display_order(4))
+        .subcommand(build_config_command().display_order(5))
+        .subcommand(build_check_command().display_order(6))
         .arg_required_else_help(true)
         .disable_help_subcommand(true)
         .subcommand_required(true)
@@ -48,6 +54,8 @@ pub fn build_cli<'a>() -> Command<'a> {
 
 #[derive(Debug, PartialEq)]
 pub enum CliCommand {
+    Check(CheckCliCommand),
+    Config(ConfigCliCommand),
     Run(RunCliCommand),
     Index(IndexCliCommand),
     Split(SplitCliCommand),
@@ -57,6 +65,8 @@ pub enum CliCommand {
 impl CliCommand {
     pub fn default_log_level(&self) -> Level {
         match self {
+            CliCommand::Check(_) => Level::ERROR,
+            CliCommand::Config(_) => Level::ERROR,
             CliCommand::Run(_) => Level::INFO,
             CliCommand::Index(subcommand) => subcommand.default_log_level(),
             CliCommand::Source(_) => Level::ERROR,
@@ -69,6 +79,8 @@ impl CliCommand {
             .subcommand()
             .ok_or_else(|| anyhow::anyhow!("Failed to parse command arguments."))?;
         match subcommand {
+            "check" => CheckCliCommand::parse_cli_args(submatches).map(CliCommand::Check),
+            "config" => ConfigCliCommand::parse_cli_args(submatches).map(CliCommand::Config),
             "index" => IndexCliCommand::parse_cli_args(submatches).map(CliCommand::Index),
             "run" => RunCliCommand::parse_cli_args(submatches).map(CliCommand::Run),
             "source" => SourceCliCommand::parse_cli_args(submatches).map(CliCommand::Source),
@@ -77,12 +89,15 @@ impl CliCommand {
         }
     }
 
-    pub async fn execute(self) -> anyhow::Result<()> {
-        match self {
+    pub async fn execute(self) -> Result<(), CliError> {
+        let result = match self {
+            CliCommand::Check(subcommand) => subcommand.execute().await,
+            CliCommand::Config(subcommand) => subcommand.execute().await,
             CliCommand::Index(subcommand) => subcommand.execute().await,
             CliCommand::Run(subcommand) => subcommand.execute().await,
             CliCommand::Source(subcommand) => subcommand.execute().await,
             CliCommand::Split(subcommand) => subcommand.execute().await,
-        }
+        };
+        result.map_err(CliError::from)
     }
 }
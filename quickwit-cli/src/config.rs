@@ -0,0 +1,142 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use clap::{arg, ArgMatches, Command};
+use quickwit_common::print_checklist;
+use quickwit_common::uri::Uri;
+use tracing::debug;
+
+use crate::{load_quickwit_config, parse_config_uri};
+
+pub fn build_config_command<'a>() -> Command<'a> {
+    Command::new("config")
+        .about("Performs operations on the node config file (validate).")
+        .subcommand(
+            Command::new("validate")
+                .about("Validates the node config file without starting a node.")
+                .args(&[
+                    arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
+                        .env("QW_DATA_DIR")
+                        .required(false),
+                    arg!(--strict "Turns the warnings about an unset cluster ID or an empty peer seed list into errors."),
+                ])
+            )
+        .arg_required_else_help(true)
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct ValidateConfigArgs {
+    pub config_uri: Uri,
+    pub data_dir: Option<PathBuf>,
+    pub strict: bool,
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConfigCliCommand {
+    Validate(ValidateConfigArgs),
+}
+
+impl ConfigCliCommand {
+    pub fn parse_cli_args(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let (subcommand, submatches) = matches
+            .subcommand()
+            .ok_or_else(|| anyhow::anyhow!("Failed to parse sub-matches."))?;
+        match subcommand {
+            "validate" => Self::parse_validate_args(submatches),
+            _ => bail!("Subcommand `{}` is not implemented.", subcommand),
+        }
+    }
+
+    fn parse_validate_args(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let config_uri = parse_config_uri(matches)?;
+        let data_dir = matches.value_of("data-dir").map(PathBuf::from);
+        let strict = matches.is_present("strict");
+        Ok(Self::Validate(ValidateConfigArgs {
+            config_uri,
+            data_dir,
+            strict,
+        }))
+    }
+
+    pub async fn execute(self) -> anyhow::Result<()> {
+        match self {
+            Self::Validate(args) => validate_config_cli(args).await,
+        }
+    }
+}
+
+async fn validate_config_cli(args: ValidateConfigArgs) -> anyhow::Result<()> {
+    debug!(args = ?args, "validate-config");
+    let config = load_quickwit_config(&args.config_uri, args.data_dir.clone())
+        .await
+        .with_context(|| format!("Config `{}` is invalid.", args.config_uri))?;
+
+    let mut check_item_names = Vec::new();
+    let mut check_item_results = Vec::new();
+
+    for (peer_seed, resolution_res) in config.resolve_peer_seeds().await {
+        match resolution_res {
+            Ok(peer_seed_addr) => {
+                check_item_names.push(format!(
+                    "peer seed `{}` resolved to `{}`",
+                    peer_seed, peer_seed_addr
+                ));
+                check_item_results.push(Ok(()));
+            }
+            Err(error) => {
+                check_item_names.push(format!("peer seed `{}`", peer_seed));
+                check_item_results.push(Err(error));
+            }
+        }
+    }
+    for warning in config.validation_warnings() {
+        if args.strict {
+            check_item_names.push(warning);
+            check_item_results.push(Err(anyhow::anyhow!(
+                "Warnings are treated as errors in strict mode."
+            )));
+        } else {
+            println!("Warning: {}", warning);
+        }
+    }
+    let num_failed_checks = check_item_results
+        .iter()
+        .filter(|check_item_res| check_item_res.is_err())
+        .count();
+    if !check_item_names.is_empty() {
+        let checks: Vec<(&str, anyhow::Result<()>)> = check_item_names
+            .iter()
+            .map(String::as_str)
+            .zip(check_item_results)
+            .collect();
+        print_checklist(&checks);
+    }
+    if num_failed_checks > 0 {
+        bail!(
+            "Config `{}` failed {} validation check(s).",
+            args.config_uri,
+            num_failed_checks
+        );
+    }
+    println!("Config `{}` is valid.", args.config_uri);
+    Ok(())
+}
@@ -0,0 +1,201 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+use quickwit_config::IdentifierError;
+use quickwit_core::IndexServiceError;
+use quickwit_metastore::{MetastoreError, MetastoreResolverError};
+use quickwit_storage::{StorageError, StorageErrorKind, StorageResolverError};
+use thiserror::Error;
+
+/// Category of a CLI failure. Each category maps to a distinct process exit code so that scripts
+/// can react to a failure without parsing the error message.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum CliErrorKind {
+    /// Any failure that does not fall into a more specific category.
+    #[error("Command failed.")]
+    Internal,
+    /// The command arguments or a config file are missing or invalid.
+    #[error("Invalid command arguments or config.")]
+    Config,
+    /// The metastore rejected the operation or a requested resource does not exist.
+    #[error("Metastore operation failed.")]
+    Metastore,
+    /// A metastore, storage, or source could not be reached.
+    #[error("Connectivity error.")]
+    Connectivity,
+}
+
+impl CliErrorKind {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliErrorKind::Internal => 1,
+            CliErrorKind::Config => 2,
+            CliErrorKind::Metastore => 3,
+            CliErrorKind::Connectivity => 4,
+        }
+    }
+}
+
+/// Error returned by [`crate::cli::CliCommand::execute`].
+#[derive(Debug, Error)]
+#[error("{kind}")]
+pub struct CliError {
+    kind: CliErrorKind,
+    #[source]
+    cause: anyhow::Error,
+}
+
+impl CliError {
+    pub fn new(kind: CliErrorKind, cause: anyhow::Error) -> Self {
+        Self { kind, cause }
+    }
+
+    pub fn config(cause: anyhow::Error) -> Self {
+        Self::new(CliErrorKind::Config, cause)
+    }
+
+    pub fn kind(&self) -> CliErrorKind {
+        self.kind
+    }
+
+    pub fn cause(&self) -> &anyhow::Error {
+        &self.cause
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.kind.exit_code()
+    }
+}
+
+impl From<anyhow::Error> for CliError {
+    fn from(cause: anyhow::Error) -> Self {
+        let kind = classify_error(&cause);
+        Self { kind, cause }
+    }
+}
+
+/// Walks the error chain, outermost error first, and returns the category of the first error that
+/// can be classified.
+fn classify_error(error: &anyhow::Error) -> CliErrorKind {
+    for cause in error.chain() {
+        if let Some(cli_error) = cause.downcast_ref::<CliError>() {
+            return cli_error.kind;
+        }
+        if let Some(metastore_error) = cause.downcast_ref::<MetastoreError>() {
+            return classify_metastore_error(metastore_error);
+        }
+        if let Some(resolver_error) = cause.downcast_ref::<MetastoreResolverError>() {
+            return match resolver_error {
+                MetastoreResolverError::InvalidUri(_)
+                | MetastoreResolverError::ProtocolUnsupported(_) => CliErrorKind::Config,
+                MetastoreResolverError::FailedToOpenMetastore(metastore_error) => {
+                    classify_metastore_error(metastore_error)
+                }
+            };
+        }
+        if let Some(resolver_error) = cause.downcast_ref::<StorageResolverError>() {
+            return match resolver_error {
+                StorageResolverError::InvalidUri { .. }
+                | StorageResolverError::ProtocolUnsupported { .. } => CliErrorKind::Config,
+                StorageResolverError::FailedToOpenStorage { .. } => CliErrorKind::Connectivity,
+            };
+        }
+        if let Some(storage_error) = cause.downcast_ref::<StorageError>() {
+            match storage_error.kind() {
+                StorageErrorKind::DoesNotExist => return CliErrorKind::Metastore,
+                StorageErrorKind::Unauthorized | StorageErrorKind::Service => {
+                    return CliErrorKind::Connectivity
+                }
+                StorageErrorKind::InternalError | StorageErrorKind::Io => {}
+            }
+        }
+        if let Some(IndexServiceError::InvalidIndexConfig(_)) =
+            cause.downcast_ref::<IndexServiceError>()
+        {
+            return CliErrorKind::Config;
+        }
+        if cause.is::<IdentifierError>() {
+            return CliErrorKind::Config;
+        }
+    }
+    CliErrorKind::Internal
+}
+
+fn classify_metastore_error(metastore_error: &MetastoreError) -> CliErrorKind {
+    match metastore_error {
+        MetastoreError::ConnectionError { .. } => CliErrorKind::Connectivity,
+        _ => CliErrorKind::Metastore,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Context;
+
+    use super::*;
+
+    #[test]
+    fn test_cli_error_kind_exit_codes() {
+        assert_eq!(CliErrorKind::Internal.exit_code(), 1);
+        assert_eq!(CliErrorKind::Config.exit_code(), 2);
+        assert_eq!(CliErrorKind::Metastore.exit_code(), 3);
+        assert_eq!(CliErrorKind::Connectivity.exit_code(), 4);
+    }
+
+    #[test]
+    fn test_cli_error_classification() {
+        let error = anyhow::anyhow!("Something went wrong.");
+        assert_eq!(CliError::from(error).kind(), CliErrorKind::Internal);
+
+        let error = anyhow::Error::from(MetastoreError::IndexDoesNotExist {
+            index_id: "my-index".to_string(),
+        })
+        .context("Failed to fetch index metadata.");
+        assert_eq!(CliError::from(error).kind(), CliErrorKind::Metastore);
+
+        let error = anyhow::Error::from(MetastoreError::ConnectionError {
+            message: "Connection refused.".to_string(),
+        });
+        assert_eq!(CliError::from(error).kind(), CliErrorKind::Connectivity);
+
+        let error = anyhow::Error::from(MetastoreResolverError::ProtocolUnsupported(
+            "ftp".to_string(),
+        ));
+        assert_eq!(CliError::from(error).kind(), CliErrorKind::Config);
+
+        let error = anyhow::Error::from(
+            StorageErrorKind::Service.with_error(anyhow::anyhow!("Service unavailable.")),
+        );
+        assert_eq!(CliError::from(error).kind(), CliErrorKind::Connectivity);
+
+        let error = anyhow::Error::from(
+            StorageErrorKind::DoesNotExist.with_error(anyhow::anyhow!("Key not found.")),
+        );
+        assert_eq!(CliError::from(error).kind(), CliErrorKind::Metastore);
+    }
+
+    #[test]
+    fn test_cli_error_classification_prefers_outermost_tag() {
+        let storage_error = StorageErrorKind::DoesNotExist.with_error(anyhow::anyhow!("No file."));
+        let error = anyhow::Error::from(CliError::config(anyhow::Error::from(storage_error)))
+            .context("Failed to load Quickwit config.");
+        let cli_error = CliError::from(error);
+        assert_eq!(cli_error.kind(), CliErrorKind::Config);
+        assert_eq!(cli_error.exit_code(), 2);
+    }
+}
@@ -17,42 +17,60 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::{HashSet, VecDeque};
-use std::io::{stdout, Stdout, Write};
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::io::{stderr, stdout, BufWriter, Seek, SeekFrom, Stderr, Stdout, Write};
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 use std::{env, fmt, io};
 
 use anyhow::{bail, Context};
+use byte_unit::Byte;
 use clap::{arg, ArgMatches, Command};
 use colored::Colorize;
+use futures::TryStreamExt;
+use glob::Pattern;
 use humantime::format_duration;
 use itertools::Itertools;
 use quickwit_actors::{ActorHandle, ObservationType, Universe};
 use quickwit_common::uri::Uri;
 use quickwit_common::GREEN_COLOR;
 use quickwit_config::{
-    IndexConfig, IndexerConfig, SourceConfig, SourceParams, CLI_INGEST_SOURCE_ID,
+    build_doc_mapper, IndexConfig, IndexerConfig, MergePolicy, SourceConfig, SourceParams,
+    CLI_INGEST_SOURCE_ID,
 };
 use quickwit_core::{clear_cache_directory, remove_indexing_directory, IndexService};
 use quickwit_doc_mapper::tag_pruning::match_tag_field_name;
-use quickwit_indexing::actors::{IndexingPipeline, IndexingService};
+use quickwit_doc_mapper::QueryExplanation;
+use quickwit_indexing::actors::{IndexingPipeline, IndexingService, INDEXING_DIR_NAME};
 use quickwit_indexing::models::{
-    DetachPipeline, IndexingStatistics, SpawnMergePipeline, SpawnPipeline,
+    CancelMerges, DetachPipeline, DocstoreCompression, IndexingStatistics, SpawnMergePipeline,
+    SpawnPipeline,
+};
+use quickwit_metastore::{
+    list_splits_stream, quickwit_metastore_uri_resolver, IndexMetadata, SplitState,
+};
+use quickwit_proto::{
+    SearchRequest, SearchResponse, SortOrder, SplitIdAndFooterOffsets, SplitOrder,
+};
+use quickwit_search::{
+    single_node_explain_query, single_node_fetch_plan, single_node_schema_compat,
+    single_node_search, FieldSchemaIncompatibility, SearchResponseRest, SplitExporter,
+    SplitFetchPlan,
 };
-use quickwit_metastore::{quickwit_metastore_uri_resolver, IndexMetadata, Split, SplitState};
-use quickwit_proto::{SearchRequest, SearchResponse};
-use quickwit_search::{single_node_search, SearchResponseRest};
 use quickwit_storage::{load_file, quickwit_storage_uri_resolver};
 use quickwit_telemetry::payload::TelemetryEvent;
 use tabled::{Table, Tabled};
 use thousands::Separable;
-use tracing::{debug, warn, Level};
+use time::format_description::well_known::Rfc3339;
+use time::{format_description, OffsetDateTime, PrimitiveDateTime};
+use tracing::{debug, info, warn, Level};
 
+use crate::error::CliError;
 use crate::stats::{mean, percentile, std_deviation};
 use crate::{
-    load_quickwit_config, make_table, parse_duration_with_unit, run_index_checklist,
-    THROUGHPUT_WINDOW_SIZE,
+    load_quickwit_config, make_table, parse_config_uri, parse_duration_with_unit,
+    prompt_confirmation, run_index_checklist, THROUGHPUT_WINDOW_SIZE,
 };
 
 pub fn build_index_command<'a>() -> Command<'a> {
@@ -64,7 +82,14 @@ pub fn build_index_command<'a>() -> Command<'a> {
                 .alias("ls")
                 .args(&[
                     arg!(--"metastore-uri" <METASTORE_URI> "Metastore URI. Override the `metastore_uri` parameter defined in the config file. Defaults to file-backed, but could be Amazon S3 or PostgreSQL.")
-                        .required(false)
+                        .required(false),
+                    arg!(--filter <GLOB> "Only lists the indexes whose ID matches the glob pattern, e.g. `logs-*`.")
+                        .required(false),
+                    arg!(--limit <LIMIT> "Maximum number of indexes listed.")
+                        .required(false),
+                    arg!(--offset <OFFSET> "Number of indexes, sorted by ID, skipped before the first index listed.")
+                        .default_value("0")
+                        .required(false),
                 ])
             )
         .subcommand(
@@ -93,6 +118,18 @@ pub fn build_index_command<'a>() -> Command<'a> {
                         .required(false),
                     arg!(--"keep-cache" "Does not clear local cache directory upon completion.")
                         .required(false),
+                    arg!(--"throughput-window" <SECONDS> "Number of seconds over which the reported throughput is averaged.")
+                        .default_value("5")
+                        .required(false),
+                    arg!(--"dry-run" "Parses and validates the documents against the doc mapping without indexing them.")
+                        .required(false),
+                    arg!(--"progress-format" <PROGRESS_FORMAT> "Format of the progress reports: `pretty` prints a status line to stdout, `json` writes one JSON object per report to stderr.")
+                        .default_value("pretty")
+                        .required(false),
+                    arg!(--"docstore-compression" <COMPRESSION> "Compression of the docstore (`none`, `lz4` or `zstd`) used instead of the one of the index config for this run.")
+                        .required(false),
+                    arg!(--"docstore-compression-level" <LEVEL> "Zstd compression level of the docstore used instead of the `docstore_compression_level` of the index config for this run. Only valid with `zstd` compression.")
+                        .required(false),
                 ])
             )
         .subcommand(
@@ -122,18 +159,101 @@ pub fn build_index_command<'a>() -> Command<'a> {
                     arg!(--"start-offset" <OFFSET> "Offset in the global result set of the first hit returned.")
                         .default_value("0")
                         .required(false),
-                    arg!(--"search-fields" <FIELD_NAME> "List of fields that Quickwit will search into if the user query does not explicitly target a field in the query. It overrides the default search fields defined in the index config. Space-separated list, e.g. \"field1 field2\". ")
+                    arg!(--count "Only reports the number of documents matching the query, without collecting nor fetching any hit. Overrides `--max-hits`.")
+                        .required(false),
+                    arg!(--"search-fields" <FIELD_NAME> "List of fields that Quickwit will search into if the user query does not explicitly target a field in the query. It overrides the default search fields defined in the index config. Space-separated list, e.g. \"field1 field2\". Glob patterns such as \"attr_*\" are expanded against the indexed text fields of the index schema. Prefix a field with \"-\" to exclude it, the listed fields then amend the default search fields instead of overriding them, e.g. \"--search-fields title --search-fields=-body\" searches the default fields plus title minus body. ")
                         .multiple_values(true)
+                        .multiple_occurrences(true)
+                        .required(false),
+                    arg!(--fields <FIELD_NAME> "List of fields to return in the hits even though they are not stored, reconstructed from their fast field values or, for text fields indexed with positions, from their terms. Space-separated list, e.g. \"field1 field2\".")
+                        .multiple_values(true)
+                        .required(false),
+                    arg!(--"snippet-fields" <FIELD_NAME> "List of text fields for which highlighted snippets of the hits are returned. Space-separated list, e.g. \"field1 field2\".")
+                        .multiple_values(true)
+                        .required(false),
+                    arg!(--"snippet-max-fragments" <MAX_FRAGMENTS> "Maximum number of fragments of a snippet (default: 3). Use `<FIELD_NAME>=<MAX_FRAGMENTS>` to override it for a snippet field, e.g. \"--snippet-max-fragments 2 --snippet-max-fragments title=1\".")
+                        .multiple_occurrences(true)
+                        .required(false),
+                    arg!(--"snippet-fragment-size" <FRAGMENT_SIZE> "Maximum number of characters of a snippet fragment (default: 100). Use `<FIELD_NAME>=<FRAGMENT_SIZE>` to override it for a snippet field, e.g. \"--snippet-fragment-size title=50\".")
+                        .multiple_occurrences(true)
+                        .required(false),
+                    arg!(--"start-timestamp" <TIMESTAMP> "Filters out documents before that timestamp, expressed as a Unix timestamp in seconds or an RFC3339 datetime (time-series indexes only).")
+                        .required(false),
+                    arg!(--"require-tag" <TAG> "Only searches the splits that may hold documents with this tag, formatted as `field_name:value`, e.g. \"--require-tag tenant:acme\". Can be repeated. Splits are pruned on their tags only: documents are not filtered.")
+                        .multiple_occurrences(true)
+                        .required(false),
+                    arg!(--"end-timestamp" <TIMESTAMP> "Filters out documents after that timestamp, expressed as a Unix timestamp in seconds or an RFC3339 datetime (time-series indexes only).")
+                        .required(false),
+                    arg!(--"explain-bytes-per-hit" "Reports the number of bytes fetched from the storage and the average number of bytes fetched per returned hit.")
+                        .required(false),
+                    arg!(--"explain-split-selection-order" "Reports the order in which the splits were selected for search.")
+                        .required(false),
+                    arg!(--"explain-doc-mapper-mode" "Reports the doc mapping mode (lenient, strict, or dynamic) of the index.")
+                        .required(false),
+                    arg!(--"explain-io-wait" "Reports the time spent awaiting storage IO and the time spent in CPU-intensive collection.")
+                        .required(false),
+                    arg!(--"explain-query-depth" "Reports the depth and the number of clauses of the parsed query.")
+                        .required(false),
+                    arg!(--"explain-sort-field-coverage" "Reports the number of searched splits containing and missing the sort field.")
                         .required(false),
-                    arg!(--"start-timestamp" <TIMESTAMP> "Filters out documents before that timestamp (time-series indexes only).")
+                    arg!(--"exclude-dynamic-fields" "Leaves the fields captured by the dynamic mapping out of the returned hits.")
                         .required(false),
-                    arg!(--"end-timestamp" <TIMESTAMP> "Filters out documents after that timestamp (time-series indexes only).")
+                    arg!(--"explain-fetch-plan" "Prints the storage fetches planned for each split instead of running the search.")
+                        .required(false),
+                    arg!(--"explain-schema-compat" "Prints the fields indexed with different options (indexed, fast, tokenizer) across the searched splits instead of running the search.")
+                        .required(false),
+                    arg!(--"explain" "Prints the parsed query, the effective search fields, and the rewritten query instead of running the search.")
+                        .required(false),
+                    arg!(--"split-order" <SPLIT_ORDER> "Order in which the splits are searched: `time-desc`, `time-asc`, `size-asc`, or `default`.")
+                        .default_value("default")
+                        .required(false),
+                    arg!(--"sort-by-field" <FIELD_NAME> "Fast field used to sort the hits. Must be supplied along with `--sort-order`.")
+                        .required(false),
+                    arg!(--"sort-order" <SORT_ORDER> "Order in which the hits are sorted: `asc` or `desc`. Must be supplied along with `--sort-by-field`.")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("export")
+                .about("Exports the documents matching a query to files.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index"),
+                    arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
+                        .env("QW_DATA_DIR")
+                        .required(false),
+                    arg!(--query <QUERY> "Query expressed in natural query language ((barack AND obama) OR \"president of united states\"). Learn more on https://quickwit.io/docs/reference/search-language."),
+                    arg!(--"start-timestamp" <TIMESTAMP> "Filters out documents before that timestamp, expressed as a Unix timestamp in seconds or an RFC3339 datetime (time-series indexes only).")
+                        .required(false),
+                    arg!(--"end-timestamp" <TIMESTAMP> "Filters out documents after that timestamp, expressed as a Unix timestamp in seconds or an RFC3339 datetime (time-series indexes only).")
+                        .required(false),
+                    arg!(--"output-dir" <OUTPUT_DIR> "Directory where the exported files are written. Running the export again with the same output directory resumes it."),
+                    arg!(--format <FORMAT> "Format of the exported files: `json`, one JSON document per line, or `csv`.")
+                        .default_value("json")
+                        .required(false),
+                    arg!(--"max-file-size" <MAX_FILE_SIZE> "Size beyond which the export moves on to a new file, e.g. `100MB`.")
+                        .default_value("100MB")
                         .required(false),
                 ])
             )
         .subcommand(
             Command::new("merge")
                 .about("Merges an index.")
+                .args(&[
+                    arg!(--index <INDEX> "ID of the target index"),
+                    arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
+                        .env("QW_DATA_DIR")
+                        .required(false),
+                    arg!(--"merge-factor" <MERGE_FACTOR> "Minimum number of splits merged together. Overrides the `merge_factor` of the index config for this run.")
+                        .required(false),
+                    arg!(--"max-merge-factor" <MAX_MERGE_FACTOR> "Maximum number of splits merged together. Overrides the `max_merge_factor` of the index config for this run.")
+                        .required(false),
+                    arg!(--"max-merge-docs" <MAX_MERGE_DOCS> "Number of documents above which a split is no longer merged. Overrides the `split_num_docs_target` of the index config for this run.")
+                        .required(false),
+                ])
+            )
+        .subcommand(
+            Command::new("merge-cancel")
+                .about("Cancels the merge or demux running for an index. The operations already underway complete before the merge stops.")
                 .args(&[
                     arg!(--index <INDEX> "ID of the target index"),
                     arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
@@ -162,8 +282,14 @@ pub fn build_index_command<'a>() -> Command<'a> {
                     arg!(--"grace-period" <GRACE_PERIOD> "Threshold period after which stale staged splits are garbage collected.")
                         .default_value("1h")
                         .required(false),
+                    arg!(--"delete-orphans" "Also garbage collects the split files of the index storage that belong to no split of the metastore, such as the leftovers of aborted uploads, once they are older than the grace period.")
+                        .required(false),
                     arg!(--"dry-run" "Executes the command in dry run mode and only displays the list of splits candidates for garbage collection.")
                         .required(false),
+                    arg!(--force "Garbage collects all the staged splits regardless of their age, i.e. with a grace period of zero. This can delete the splits of an indexing in progress.")
+                        .required(false),
+                    arg!(--yes "Does not ask for confirmation before a forced garbage collection.")
+                        .required(false),
                 ])
             )
         .subcommand(
@@ -204,6 +330,19 @@ pub struct IngestDocsArgs {
     pub data_dir: Option<PathBuf>,
     pub overwrite: bool,
     pub clear_cache: bool,
+    pub throughput_window_secs: usize,
+    pub dry_run: bool,
+    pub progress_format: ProgressFormat,
+    pub docstore_compression_opt: Option<DocstoreCompression>,
+}
+
+/// Format of the progress reports of `index ingest`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressFormat {
+    /// A human-readable status line written to stdout.
+    Pretty,
+    /// One JSON object per report written to stderr.
+    Json,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -213,9 +352,62 @@ pub struct SearchIndexArgs {
     pub aggregation: Option<String>,
     pub max_hits: usize,
     pub start_offset: usize,
+    pub count: bool,
     pub search_fields: Option<Vec<String>>,
+    pub fields: Option<Vec<String>>,
+    pub snippet_fields: Option<Vec<String>>,
+    pub snippet_max_fragments: Option<u32>,
+    pub snippet_max_fragments_per_field: HashMap<String, u32>,
+    pub snippet_fragment_size: Option<u32>,
+    pub snippet_fragment_size_per_field: HashMap<String, u32>,
     pub start_timestamp: Option<i64>,
     pub end_timestamp: Option<i64>,
+    pub required_tags: Vec<String>,
+    pub explain_bytes_per_hit: bool,
+    pub explain_split_selection_order: bool,
+    pub explain_doc_mapper_mode: bool,
+    pub explain_io_wait: bool,
+    pub explain_query_depth: bool,
+    pub explain_sort_field_coverage: bool,
+    pub exclude_dynamic_fields: bool,
+    pub explain_fetch_plan: bool,
+    pub explain_schema_compat: bool,
+    pub explain: bool,
+    pub split_order: SplitOrder,
+    pub sort_by_field: Option<String>,
+    pub sort_order: Option<SortOrder>,
+    pub config_uri: Uri,
+    pub data_dir: Option<PathBuf>,
+}
+
+/// Format of the files written by `index export`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One JSON document per line.
+    Json,
+    /// One comma-separated record per document, preceded by a header listing the fields of the
+    /// doc mapping.
+    Csv,
+}
+
+impl ExportFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ExportIndexArgs {
+    pub index_id: String,
+    pub query: String,
+    pub start_timestamp: Option<i64>,
+    pub end_timestamp: Option<i64>,
+    pub output_dir: PathBuf,
+    pub format: ExportFormat,
+    pub max_file_num_bytes: u64,
     pub config_uri: Uri,
     pub data_dir: Option<PathBuf>,
 }
@@ -232,7 +424,10 @@ pub struct DeleteIndexArgs {
 pub struct GarbageCollectIndexArgs {
     pub index_id: String,
     pub grace_period: Duration,
+    pub delete_orphans: bool,
     pub dry_run: bool,
+    pub force: bool,
+    pub assume_yes: bool,
     pub config_uri: Uri,
     pub data_dir: Option<PathBuf>,
 }
@@ -242,12 +437,25 @@ pub struct MergeOrDemuxArgs {
     pub index_id: String,
     pub config_uri: Uri,
     pub data_dir: Option<PathBuf>,
+    pub merge_factor_opt: Option<usize>,
+    pub max_merge_factor_opt: Option<usize>,
+    pub max_merge_docs_opt: Option<usize>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct CancelMergeArgs {
+    pub index_id: String,
+    pub config_uri: Uri,
+    pub data_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ListIndexesArgs {
     pub config_uri: Uri,
     pub metastore_uri: Option<Uri>,
+    pub filter: Option<Pattern>,
+    pub limit: Option<usize>,
+    pub offset: usize,
 }
 
 #[derive(Debug, PartialEq)]
@@ -258,15 +466,17 @@ pub enum IndexCliCommand {
     Delete(DeleteIndexArgs),
     Demux(MergeOrDemuxArgs),
     Merge(MergeOrDemuxArgs),
+    CancelMerge(CancelMergeArgs),
     GarbageCollect(GarbageCollectIndexArgs),
     Ingest(IngestDocsArgs),
     Search(SearchIndexArgs),
+    Export(ExportIndexArgs),
 }
 
 impl IndexCliCommand {
     pub fn default_log_level(&self) -> Level {
         match self {
-            Self::Search(_) => Level::ERROR,
+            Self::Search(_) | Self::Export(_) => Level::ERROR,
             _ => Level::INFO,
         }
     }
@@ -280,7 +490,9 @@ impl IndexCliCommand {
             "create" => Self::parse_create_args(submatches),
             "delete" => Self::parse_delete_args(submatches),
             "search" => Self::parse_search_args(submatches),
+            "export" => Self::parse_export_args(submatches),
             "merge" => Self::parse_merge_args(submatches),
+            "merge-cancel" => Self::parse_cancel_merge_args(submatches),
             "demux" => Self::parse_demux_args(submatches),
             "describe" => Self::parse_describe_args(submatches),
             "gc" => Self::parse_garbage_collect_args(submatches),
@@ -290,19 +502,34 @@ impl IndexCliCommand {
     }
 
     fn parse_list_args(matches: &ArgMatches) -> anyhow::Result<Self> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
 
         let metastore_uri = matches
             .value_of("metastore-uri")
             .map(Uri::try_new)
             .transpose()?;
 
+        let filter = matches
+            .value_of("filter")
+            .map(|pattern| {
+                Pattern::new(pattern)
+                    .with_context(|| format!("Failed to parse index ID filter `{}`.", pattern))
+            })
+            .transpose()?;
+
+        let limit = if matches.is_present("limit") {
+            Some(matches.value_of_t::<usize>("limit")?)
+        } else {
+            None
+        };
+        let offset = matches.value_of_t::<usize>("offset")?;
+
         Ok(Self::List(ListIndexesArgs {
             config_uri,
             metastore_uri,
+            filter,
+            limit,
+            offset,
         }))
     }
 
@@ -311,10 +538,7 @@ impl IndexCliCommand {
             .value_of("index")
             .expect("`index` is a required arg.")
             .to_string();
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         Ok(Self::Describe(DescribeIndexArgs {
             config_uri,
@@ -328,10 +552,7 @@ impl IndexCliCommand {
             .value_of("index-config")
             .map(Uri::try_new)
             .expect("`index-config` is a required arg.")?;
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         let overwrite = matches.is_present("overwrite");
 
@@ -355,13 +576,31 @@ impl IndexCliCommand {
         } else {
             None
         };
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         let overwrite = matches.is_present("overwrite");
         let clear_cache = !matches.is_present("keep-cache");
+        let throughput_window_secs = matches.value_of_t::<usize>("throughput-window")?;
+        if throughput_window_secs == 0 {
+            bail!("`--throughput-window` must be greater than zero.");
+        }
+        let dry_run = matches.is_present("dry-run");
+        if dry_run && overwrite {
+            bail!("`--dry-run` and `--overwrite` cannot be used together.");
+        }
+        let progress_format = matches
+            .value_of("progress-format")
+            .map(parse_progress_format)
+            .expect("`progress-format` should have a default value.")?;
+        let docstore_compression_level_opt = if matches.is_present("docstore-compression-level") {
+            Some(matches.value_of_t::<i32>("docstore-compression-level")?)
+        } else {
+            None
+        };
+        let docstore_compression_opt = parse_docstore_compression(
+            matches.value_of("docstore-compression"),
+            docstore_compression_level_opt,
+        )?;
 
         Ok(Self::Ingest(IngestDocsArgs {
             index_id,
@@ -370,6 +609,10 @@ impl IndexCliCommand {
             config_uri,
             data_dir,
             clear_cache,
+            throughput_window_secs,
+            dry_run,
+            progress_format,
+            docstore_compression_opt,
         }))
     }
 
@@ -384,25 +627,78 @@ impl IndexCliCommand {
             .to_string();
         let aggregation = matches.value_of("aggregation").map(|el| el.to_string());
 
-        let max_hits = matches.value_of_t::<usize>("max-hits")?;
+        let count = matches.is_present("count");
+        let max_hits = if count {
+            0
+        } else {
+            matches.value_of_t::<usize>("max-hits")?
+        };
         let start_offset = matches.value_of_t::<usize>("start-offset")?;
         let search_fields = matches
             .values_of("search-fields")
             .map(|values| values.map(|value| value.to_string()).collect());
-        let start_timestamp = if matches.is_present("start-timestamp") {
-            Some(matches.value_of_t::<i64>("start-timestamp")?)
-        } else {
-            None
-        };
-        let end_timestamp = if matches.is_present("end-timestamp") {
-            Some(matches.value_of_t::<i64>("end-timestamp")?)
-        } else {
-            None
-        };
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let fields = matches
+            .values_of("fields")
+            .map(|values| values.map(|value| value.to_string()).collect());
+        let snippet_fields = matches
+            .values_of("snippet-fields")
+            .map(|values| values.map(|value| value.to_string()).collect());
+        let (snippet_max_fragments, snippet_max_fragments_per_field) =
+            parse_snippet_option(matches, "snippet-max-fragments")?;
+        let (snippet_fragment_size, snippet_fragment_size_per_field) =
+            parse_snippet_option(matches, "snippet-fragment-size")?;
+        if snippet_fields.is_none()
+            && (matches.is_present("snippet-max-fragments")
+                || matches.is_present("snippet-fragment-size"))
+        {
+            bail!(
+                "`--snippet-max-fragments` and `--snippet-fragment-size` require `--snippet-fields`."
+            );
+        }
+        let start_timestamp = matches
+            .value_of("start-timestamp")
+            .map(|arg| parse_timestamp(arg, "start"))
+            .transpose()?;
+        let end_timestamp = matches
+            .value_of("end-timestamp")
+            .map(|arg| parse_timestamp(arg, "end"))
+            .transpose()?;
+        let required_tags: Vec<String> = matches
+            .values_of("require-tag")
+            .map(|values| values.map(|value| value.to_string()).collect())
+            .unwrap_or_default();
+        if let Some(invalid_tag) = required_tags.iter().find(|required_tag| {
+            !matches!(required_tag.split_once(':'), Some((field_name, _)) if !field_name.is_empty())
+        }) {
+            bail!(
+                "Tag `{}` passed to `--require-tag` must be formatted as `field_name:value`.",
+                invalid_tag
+            );
+        }
+        let explain_bytes_per_hit = matches.is_present("explain-bytes-per-hit");
+        let explain_split_selection_order = matches.is_present("explain-split-selection-order");
+        let explain_doc_mapper_mode = matches.is_present("explain-doc-mapper-mode");
+        let explain_io_wait = matches.is_present("explain-io-wait");
+        let explain_query_depth = matches.is_present("explain-query-depth");
+        let explain_sort_field_coverage = matches.is_present("explain-sort-field-coverage");
+        let exclude_dynamic_fields = matches.is_present("exclude-dynamic-fields");
+        let explain_fetch_plan = matches.is_present("explain-fetch-plan");
+        let explain_schema_compat = matches.is_present("explain-schema-compat");
+        let explain = matches.is_present("explain");
+        let split_order = parse_split_order(
+            matches
+                .value_of("split-order")
+                .expect("`split-order` has a default value."),
+        )?;
+        let sort_by_field = matches.value_of("sort-by-field").map(|el| el.to_string());
+        let sort_order = matches
+            .value_of("sort-order")
+            .map(parse_sort_order)
+            .transpose()?;
+        if sort_by_field.is_some() != sort_order.is_some() {
+            bail!("`--sort-by-field` and `--sort-order` must be supplied together.");
+        }
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         Ok(Self::Search(SearchIndexArgs {
             index_id,
@@ -410,9 +706,74 @@ impl IndexCliCommand {
             aggregation,
             max_hits,
             start_offset,
+            count,
             search_fields,
+            fields,
+            snippet_fields,
+            snippet_max_fragments,
+            snippet_max_fragments_per_field,
+            snippet_fragment_size,
+            snippet_fragment_size_per_field,
             start_timestamp,
             end_timestamp,
+            required_tags,
+            explain_bytes_per_hit,
+            explain_split_selection_order,
+            explain_doc_mapper_mode,
+            explain_io_wait,
+            explain_query_depth,
+            explain_sort_field_coverage,
+            exclude_dynamic_fields,
+            explain_fetch_plan,
+            explain_schema_compat,
+            explain,
+            split_order,
+            sort_by_field,
+            sort_order,
+            config_uri,
+            data_dir,
+        }))
+    }
+
+    fn parse_export_args(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let index_id = matches
+            .value_of("index")
+            .expect("`index` is a required arg.")
+            .to_string();
+        let query = matches
+            .value_of("query")
+            .context("`query` is a required arg.")?
+            .to_string();
+        let start_timestamp = matches
+            .value_of("start-timestamp")
+            .map(|arg| parse_timestamp(arg, "start"))
+            .transpose()?;
+        let end_timestamp = matches
+            .value_of("end-timestamp")
+            .map(|arg| parse_timestamp(arg, "end"))
+            .transpose()?;
+        let output_dir = matches
+            .value_of("output-dir")
+            .map(PathBuf::from)
+            .expect("`output-dir` is a required arg.");
+        let format = matches
+            .value_of("format")
+            .map(parse_export_format)
+            .expect("`format` should have a default value.")?;
+        let max_file_num_bytes = matches
+            .value_of("max-file-size")
+            .map(parse_max_file_size)
+            .expect("`max-file-size` should have a default value.")?;
+        let config_uri = parse_config_uri(matches)?;
+        let data_dir = matches.value_of("data-dir").map(PathBuf::from);
+        Ok(Self::Export(ExportIndexArgs {
+            index_id,
+            query,
+            start_timestamp,
+            end_timestamp,
+            output_dir,
+            format,
+            max_file_num_bytes,
             config_uri,
             data_dir,
         }))
@@ -423,15 +784,44 @@ impl IndexCliCommand {
             .value_of("index")
             .context("'index-id' is a required arg.")?
             .to_string();
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
+        let merge_factor_opt = if matches.is_present("merge-factor") {
+            Some(matches.value_of_t::<usize>("merge-factor")?)
+        } else {
+            None
+        };
+        let max_merge_factor_opt = if matches.is_present("max-merge-factor") {
+            Some(matches.value_of_t::<usize>("max-merge-factor")?)
+        } else {
+            None
+        };
+        let max_merge_docs_opt = if matches.is_present("max-merge-docs") {
+            Some(matches.value_of_t::<usize>("max-merge-docs")?)
+        } else {
+            None
+        };
         Ok(Self::Merge(MergeOrDemuxArgs {
             index_id,
             config_uri,
             data_dir,
+            merge_factor_opt,
+            max_merge_factor_opt,
+            max_merge_docs_opt,
+        }))
+    }
+
+    fn parse_cancel_merge_args(matches: &ArgMatches) -> anyhow::Result<Self> {
+        let index_id = matches
+            .value_of("index")
+            .context("'index-id' is a required arg.")?
+            .to_string();
+        let config_uri = parse_config_uri(matches)?;
+        let data_dir = matches.value_of("data-dir").map(PathBuf::from);
+        Ok(Self::CancelMerge(CancelMergeArgs {
+            index_id,
+            config_uri,
+            data_dir,
         }))
     }
 
@@ -440,15 +830,15 @@ impl IndexCliCommand {
             .value_of("index")
             .context("'index-id' is a required arg.")?
             .to_string();
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         Ok(Self::Demux(MergeOrDemuxArgs {
             index_id,
             config_uri,
             data_dir,
+            merge_factor_opt: None,
+            max_merge_factor_opt: None,
+            max_merge_docs_opt: None,
         }))
     }
 
@@ -457,20 +847,30 @@ impl IndexCliCommand {
             .value_of("index")
             .expect("`index` is a required arg.")
             .to_string();
-        let grace_period = matches
-            .value_of("grace-period")
-            .map(parse_duration_with_unit)
-            .expect("`grace-period` should have a default value.")?;
+        let force = matches.is_present("force");
+        let grace_period = if force {
+            if matches.occurrences_of("grace-period") > 0 {
+                bail!("`--force` and `--grace-period` cannot be used together.");
+            }
+            Duration::ZERO
+        } else {
+            matches
+                .value_of("grace-period")
+                .map(parse_duration_with_unit)
+                .expect("`grace-period` should have a default value.")?
+        };
+        let delete_orphans = matches.is_present("delete-orphans");
         let dry_run = matches.is_present("dry-run");
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let assume_yes = matches.is_present("yes");
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         Ok(Self::GarbageCollect(GarbageCollectIndexArgs {
             index_id,
             grace_period,
+            delete_orphans,
             dry_run,
+            force,
+            assume_yes,
             config_uri,
             data_dir,
         }))
@@ -482,10 +882,7 @@ impl IndexCliCommand {
             .expect("`index` is a required arg.")
             .to_string();
         let dry_run = matches.is_present("dry-run");
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         Ok(Self::Delete(DeleteIndexArgs {
             index_id,
@@ -502,8 +899,10 @@ impl IndexCliCommand {
             Self::Describe(args) => describe_index_cli(args).await,
             Self::Ingest(args) => ingest_docs_cli(args).await,
             Self::Search(args) => search_index_cli(args).await,
+            Self::Export(args) => export_index_cli(args).await,
             Self::Merge(args) => merge_or_demux_cli(args, true, false).await,
             Self::Demux(args) => merge_or_demux_cli(args, false, true).await,
+            Self::CancelMerge(args) => cancel_merge_cli(args).await,
             Self::GarbageCollect(args) => garbage_collect_index_cli(args).await,
             Self::Delete(args) => delete_index_cli(args).await,
         }
@@ -517,24 +916,57 @@ pub async fn list_index_cli(args: ListIndexesArgs) -> anyhow::Result<()> {
     let metastore_uri = args.metastore_uri.unwrap_or(quickwit_config.metastore_uri);
     let metastore = metastore_uri_resolver.resolve(&metastore_uri).await?;
     let indexes = metastore.list_indexes_metadatas().await?;
-    let index_table = make_list_indexes_table(indexes);
+    let (index_table, num_indexes_listed, num_indexes) =
+        make_list_indexes_table(indexes, args.filter.as_ref(), args.offset, args.limit);
 
     println!();
     println!("{}", index_table);
+    if num_indexes_listed < num_indexes {
+        println!(
+            "Showing {} of {} indexes (use --offset to page).",
+            num_indexes_listed, num_indexes
+        );
+    }
     println!();
     Ok(())
 }
 
-fn make_list_indexes_table<I>(indexes: I) -> Table
-where I: IntoIterator<Item = IndexMetadata> {
+/// Builds the table of the indexes matching the filter, sorted by index ID, and returns it along
+/// with the number of indexes listed in the table and the total number of matching indexes.
+fn make_list_indexes_table<I>(
+    indexes: I,
+    filter_opt: Option<&Pattern>,
+    offset: usize,
+    limit_opt: Option<usize>,
+) -> (Table, usize, usize)
+where
+    I: IntoIterator<Item = IndexMetadata>,
+{
     let rows = indexes
         .into_iter()
+        .filter(|index| {
+            filter_opt
+                .map(|filter| filter.matches(&index.index_id))
+                .unwrap_or(true)
+        })
         .map(|index| IndexRow {
             index_id: index.index_id,
             index_uri: index.index_uri,
         })
-        .sorted_by(|left, right| left.index_id.cmp(&right.index_id));
-    make_table("Indexes", rows, false)
+        .sorted_by(|left, right| left.index_id.cmp(&right.index_id))
+        .collect_vec();
+    let num_indexes = rows.len();
+    let rows = rows
+        .into_iter()
+        .skip(offset)
+        .take(limit_opt.unwrap_or(usize::MAX))
+        .collect_vec();
+    let num_indexes_listed = rows.len();
+    (
+        make_table("Indexes", rows, false),
+        num_indexes_listed,
+        num_indexes,
+    )
 }
 
 #[derive(Tabled)]
@@ -545,6 +977,9 @@ struct IndexRow {
     index_uri: Uri,
 }
 
+/// Number of splits fetched from the metastore at once when describing an index.
+const DESCRIBE_SPLITS_PAGE_SIZE: usize = 1_000;
+
 pub async fn describe_index_cli(args: DescribeIndexArgs) -> anyhow::Result<()> {
     debug!(args = ?args, "describe");
     let metastore_uri_resolver = quickwit_metastore_uri_resolver();
@@ -553,21 +988,46 @@ pub async fn describe_index_cli(args: DescribeIndexArgs) -> anyhow::Result<()> {
         .resolve(&quickwit_config.metastore_uri)
         .await?;
     let index_metadata = metastore.index_metadata(&args.index_id).await?;
-    let splits = metastore
-        .list_splits(&args.index_id, SplitState::Published, None, None)
-        .await?;
-
-    let splits_num_docs = splits
-        .iter()
-        .map(|split| split.split_metadata.num_docs)
-        .sorted()
-        .collect_vec();
+    let demux_field_name_opt = index_metadata.indexing_settings.demux_field.as_ref();
+    // Splits are streamed page by page and only the figures needed for the stats are kept, so
+    // that large indexes are never held in memory all at once.
+    let mut splits_num_docs = Vec::new();
+    let mut splits_bytes = Vec::new();
+    let mut splits_demux_info = Vec::new();
+    let mut time_min: Option<i64> = None;
+    let mut time_max: Option<i64> = None;
+    let mut splits_stream = list_splits_stream(
+        &*metastore,
+        &args.index_id,
+        SplitState::Published,
+        DESCRIBE_SPLITS_PAGE_SIZE,
+    );
+    while let Some(split) = splits_stream.try_next().await? {
+        let split_metadata = split.split_metadata;
+        splits_num_docs.push(split_metadata.num_docs);
+        splits_bytes.push((split_metadata.footer_offsets.end / 1_000_000) as usize);
+        if let Some(time_range) = split_metadata.time_range {
+            time_min = Some(time_min.map_or(*time_range.start(), |time_min| {
+                time_min.min(*time_range.start())
+            }));
+            time_max = Some(time_max.map_or(*time_range.end(), |time_max| {
+                time_max.max(*time_range.end())
+            }));
+        }
+        if let Some(demux_field_name) = demux_field_name_opt {
+            splits_demux_info.push(SplitDemuxInfo {
+                demux_num_ops: split_metadata.demux_num_ops,
+                demux_values: split_metadata
+                    .tags
+                    .into_iter()
+                    .filter(|tag| match_tag_field_name(demux_field_name, tag))
+                    .collect(),
+            });
+        }
+    }
+    splits_num_docs.sort_unstable();
+    splits_bytes.sort_unstable();
     let total_num_docs = splits_num_docs.iter().sum::<usize>();
-    let splits_bytes = splits
-        .iter()
-        .map(|split| (split.split_metadata.footer_offsets.end / 1_000_000) as usize)
-        .sorted()
-        .collect_vec();
     let total_bytes = splits_bytes.iter().sum::<usize>();
 
     println!();
@@ -586,7 +1046,7 @@ pub async fn describe_index_cli(args: DescribeIndexArgs) -> anyhow::Result<()> {
     println!(
         "{:<35} {}",
         "Number of published splits:".color(GREEN_COLOR),
-        splits.len()
+        splits_num_docs.len()
     );
     println!(
         "{:<35} {}",
@@ -604,18 +1064,6 @@ pub async fn describe_index_cli(args: DescribeIndexArgs) -> anyhow::Result<()> {
             "Timestamp field:".color(GREEN_COLOR),
             timestamp_field_name
         );
-        let time_min = splits
-            .iter()
-            .map(|split| split.split_metadata.time_range.clone())
-            .filter(|time_range| time_range.is_some())
-            .map(|time_range| *time_range.unwrap().start())
-            .min();
-        let time_max = splits
-            .iter()
-            .map(|split| split.split_metadata.time_range.clone())
-            .filter(|time_range| time_range.is_some())
-            .map(|time_range| *time_range.unwrap().start())
-            .max();
         println!(
             "{:<35} {:?} -> {:?}",
             "Timestamp range:".color(GREEN_COLOR),
@@ -624,7 +1072,7 @@ pub async fn describe_index_cli(args: DescribeIndexArgs) -> anyhow::Result<()> {
         );
     }
 
-    if splits.is_empty() {
+    if splits_num_docs.is_empty() {
         return Ok(());
     }
 
@@ -637,28 +1085,28 @@ pub async fn describe_index_cli(args: DescribeIndexArgs) -> anyhow::Result<()> {
     println!("Size in MB stats:");
     print_descriptive_stats(&splits_bytes);
 
-    if let Some(demux_field_name) = &index_metadata.indexing_settings.demux_field {
-        show_demux_stats(demux_field_name, &splits).await;
+    if let Some(demux_field_name) = demux_field_name_opt {
+        show_demux_stats(demux_field_name, &splits_demux_info).await;
     }
 
     println!();
     Ok(())
 }
 
-pub async fn show_demux_stats(demux_field_name: &str, splits: &[Split]) {
+/// Demux figures of a published split, kept in place of the whole split when describing an index.
+pub struct SplitDemuxInfo {
+    demux_num_ops: usize,
+    /// Tags of the split on the demux field.
+    demux_values: HashSet<String>,
+}
+
+pub async fn show_demux_stats(demux_field_name: &str, splits_demux_info: &[SplitDemuxInfo]) {
     println!();
     println!("3. Demux stats");
     println!("===============================================================================");
-    let demux_uniq_values: HashSet<String> = splits
+    let demux_uniq_values: HashSet<&String> = splits_demux_info
         .iter()
-        .flat_map(|split| {
-            split
-                .split_metadata
-                .tags
-                .iter()
-                .filter(|tag| match_tag_field_name(demux_field_name, tag))
-                .cloned()
-        })
+        .flat_map(|split_demux_info| split_demux_info.demux_values.iter())
         .collect();
     println!(
         "{:<35} {}",
@@ -675,40 +1123,25 @@ pub async fn show_demux_stats(demux_field_name: &str, splits: &[Split]) {
     println!("-------------------------------------------------");
     let mut split_counts_per_demux_values = Vec::new();
     for demux_value in demux_uniq_values {
-        let split_count = splits
+        let split_count = splits_demux_info
             .iter()
-            .filter(|split| split.split_metadata.tags.contains(&demux_value))
+            .filter(|split_demux_info| split_demux_info.demux_values.contains(demux_value))
             .count();
         split_counts_per_demux_values.push(split_count);
     }
     print_descriptive_stats(&split_counts_per_demux_values);
 
-    let (non_demuxed_splits, demuxed_splits): (Vec<_>, Vec<_>) = splits
+    let (non_demuxed_splits, demuxed_splits): (Vec<_>, Vec<_>) = splits_demux_info
         .iter()
-        .cloned()
-        .partition(|split| split.split_metadata.demux_num_ops == 0);
+        .partition(|split_demux_info| split_demux_info.demux_num_ops == 0);
     let non_demuxed_split_demux_values_counts = non_demuxed_splits
         .iter()
-        .map(|split| {
-            split
-                .split_metadata
-                .tags
-                .iter()
-                .filter(|tag| match_tag_field_name(demux_field_name, tag))
-                .count()
-        })
+        .map(|split_demux_info| split_demux_info.demux_values.len())
         .sorted()
         .collect_vec();
     let demuxed_split_demux_values_counts = demuxed_splits
         .iter()
-        .map(|split| {
-            split
-                .split_metadata
-                .tags
-                .iter()
-                .filter(|tag| match_tag_field_name(demux_field_name, tag))
-                .count()
-        })
+        .map(|split_demux_info| split_demux_info.demux_values.len())
         .sorted()
         .collect_vec();
     println!();
@@ -770,8 +1203,12 @@ pub async fn create_index_cli(args: CreateIndexArgs) -> anyhow::Result<()> {
     quickwit_telemetry::send_telemetry_event(TelemetryEvent::Create).await;
 
     let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
-    let file_content = load_file(&args.index_config_uri).await?;
-    let index_config = IndexConfig::load(&args.index_config_uri, file_content.as_slice()).await?;
+    let file_content = load_file(&args.index_config_uri)
+        .await
+        .map_err(CliError::config)?;
+    let index_config = IndexConfig::load(&args.index_config_uri, file_content.as_slice())
+        .await
+        .map_err(CliError::config)?;
     let index_id = index_config.index_id.clone();
     let metastore_uri_resolver = quickwit_metastore_uri_resolver();
     let metastore = metastore_uri_resolver
@@ -835,6 +1272,8 @@ pub async fn ingest_docs_cli(args: IngestDocsArgs) -> anyhow::Result<()> {
         .ask_for_res(SpawnPipeline {
             index_id: args.index_id.clone(),
             source,
+            dry_run: args.dry_run,
+            docstore_compression_opt: args.docstore_compression_opt,
         })
         .await?;
     let pipeline_handle = indexing_server_mailbox
@@ -852,8 +1291,14 @@ pub async fn ingest_docs_cli(args: IngestDocsArgs) -> anyhow::Result<()> {
             eof_shortcut
         );
     }
-    let statistics =
-        start_statistics_reporting_loop(pipeline_handle, args.input_path_opt.is_none()).await?;
+    let statistics = start_statistics_reporting_loop(
+        pipeline_handle,
+        args.input_path_opt.is_none(),
+        args.throughput_window_secs,
+        args.dry_run,
+        args.progress_format,
+    )
+    .await?;
     if statistics.num_published_splits > 0 {
         println!(
             "Now, you can query the index with the following command:\nquickwit index search \
@@ -874,36 +1319,309 @@ pub async fn ingest_docs_cli(args: IngestDocsArgs) -> anyhow::Result<()> {
 
     match statistics.num_invalid_docs {
         0 => Ok(()),
+        _ if args.dry_run => bail!("Some documents are invalid."),
         _ => bail!("Failed to ingest all the documents."),
     }
 }
 
+fn parse_split_order(split_order: &str) -> anyhow::Result<SplitOrder> {
+    match split_order {
+        "default" => Ok(SplitOrder::Default),
+        "time-desc" => Ok(SplitOrder::TimeDesc),
+        "time-asc" => Ok(SplitOrder::TimeAsc),
+        "size-asc" => Ok(SplitOrder::SizeAsc),
+        _ => bail!(
+            "Unknown split order `{}`. Allowed values are `time-desc`, `time-asc`, `size-asc`, \
+             and `default`.",
+            split_order
+        ),
+    }
+}
+
+/// Parses a timestamp option value into a Unix timestamp in seconds. The value is either a Unix
+/// timestamp in seconds or an RFC3339 datetime, e.g. `2021-12-19T16:39:57Z`.
+fn parse_timestamp(timestamp_arg: &str, option_name: &str) -> anyhow::Result<i64> {
+    if let Ok(timestamp) = timestamp_arg.parse::<i64>() {
+        return Ok(timestamp);
+    }
+    if let Ok(datetime) = OffsetDateTime::parse(timestamp_arg, &Rfc3339) {
+        return Ok(datetime.unix_timestamp());
+    }
+    let description = format_description::parse("[year]-[month]-[day]T[hour]:[minute]:[second]")?;
+    if PrimitiveDateTime::parse(timestamp_arg, &description).is_ok() {
+        bail!(
+            "Failed to parse --{}-timestamp option parameter `{}`: the datetime has no UTC \
+             offset. Append `Z` or an offset such as `+02:00` to the datetime.",
+            option_name,
+            timestamp_arg
+        );
+    }
+    bail!(
+        "Failed to parse --{}-timestamp option parameter `{}`. Supported formats are a Unix \
+         timestamp in seconds, e.g. `1639931997`, or an RFC3339 datetime, e.g. \
+         `2021-12-19T16:39:57Z`.",
+        option_name,
+        timestamp_arg
+    );
+}
+
+/// Parses the values of a snippet option, each value being either `<VALUE>`, which applies to all
+/// the snippet fields, or `<FIELD_NAME>=<VALUE>`, which overrides it for a snippet field.
+fn parse_snippet_option(
+    matches: &ArgMatches,
+    arg_name: &str,
+) -> anyhow::Result<(Option<u32>, HashMap<String, u32>)> {
+    let mut value_opt = None;
+    let mut per_field_values = HashMap::new();
+    for arg_value in matches.values_of(arg_name).into_iter().flatten() {
+        let (field_name_opt, value) = match arg_value.split_once('=') {
+            Some((field_name, value)) => (Some(field_name), value),
+            None => (None, arg_value),
+        };
+        let value = value.parse::<u32>().with_context(|| {
+            format!(
+                "Failed to parse `--{}` value `{}`, expected `<VALUE>` or `<FIELD_NAME>=<VALUE>`.",
+                arg_name, arg_value
+            )
+        })?;
+        if let Some(field_name) = field_name_opt {
+            per_field_values.insert(field_name.to_string(), value);
+        } else {
+            value_opt = Some(value);
+        }
+    }
+    Ok((value_opt, per_field_values))
+}
+
+fn parse_sort_order(sort_order: &str) -> anyhow::Result<SortOrder> {
+    match sort_order {
+        "asc" => Ok(SortOrder::Asc),
+        "desc" => Ok(SortOrder::Desc),
+        _ => bail!(
+            "Unknown sort order `{}`. Allowed values are `asc` and `desc`.",
+            sort_order
+        ),
+    }
+}
+
+fn parse_progress_format(progress_format: &str) -> anyhow::Result<ProgressFormat> {
+    match progress_format {
+        "pretty" => Ok(ProgressFormat::Pretty),
+        "json" => Ok(ProgressFormat::Json),
+        _ => bail!(
+            "Unknown progress format `{}`. Allowed values are `pretty` and `json`.",
+            progress_format
+        ),
+    }
+}
+
+/// Parses the docstore compression overrides of `index ingest`. A compression level alone
+/// implies zstd compression.
+fn parse_docstore_compression(
+    compression_opt: Option<&str>,
+    compression_level_opt: Option<i32>,
+) -> anyhow::Result<Option<DocstoreCompression>> {
+    let docstore_compression = match (compression_opt, compression_level_opt) {
+        (None, None) => return Ok(None),
+        (Some("none"), None) => DocstoreCompression::None,
+        (Some("lz4"), None) => DocstoreCompression::Lz4,
+        (Some("zstd") | None, compression_level_opt) => DocstoreCompression::Zstd {
+            compression_level: compression_level_opt,
+        },
+        (Some(compression @ ("none" | "lz4")), Some(_)) => bail!(
+            "`--docstore-compression-level` is only valid with `zstd` compression, not `{}`.",
+            compression
+        ),
+        (Some(compression), _) => bail!(
+            "Unknown docstore compression `{}`. Allowed values are `none`, `lz4` and `zstd`.",
+            compression
+        ),
+    };
+    Ok(Some(docstore_compression))
+}
+
+fn parse_export_format(format: &str) -> anyhow::Result<ExportFormat> {
+    match format {
+        "json" => Ok(ExportFormat::Json),
+        "csv" => Ok(ExportFormat::Csv),
+        _ => bail!(
+            "Unknown export format `{}`. Allowed values are `json` and `csv`.",
+            format
+        ),
+    }
+}
+
+fn parse_max_file_size(max_file_size: &str) -> anyhow::Result<u64> {
+    let max_file_num_bytes = Byte::from_str(max_file_size)
+        .map_err(|_| {
+            anyhow::anyhow!(
+                "Failed to parse `--max-file-size` value `{}`, expected a size such as `100MB`.",
+                max_file_size
+            )
+        })?
+        .get_bytes() as u64;
+    if max_file_num_bytes == 0 {
+        bail!("`--max-file-size` must be greater than zero.");
+    }
+    Ok(max_file_num_bytes)
+}
+
+/// Checks that the sort by field exists in the index schema and is a fast field.
+fn validate_sort_by_field(
+    index_metadata: &IndexMetadata,
+    sort_by_field: &str,
+) -> anyhow::Result<()> {
+    let doc_mapper = build_doc_mapper(
+        &index_metadata.doc_mapping,
+        &index_metadata.search_settings,
+        &index_metadata.indexing_settings,
+    )?;
+    let schema = doc_mapper.schema();
+    let field = schema.get_field(sort_by_field).with_context(|| {
+        format!(
+            "Sort by field `{}` does not exist in index `{}`.",
+            sort_by_field, index_metadata.index_id
+        )
+    })?;
+    if !schema.get_field_entry(field).is_fast() {
+        bail!(
+            "Sort by field `{}` is not a fast field. Add the fast property to the field in the \
+             index config.",
+            sort_by_field
+        );
+    }
+    Ok(())
+}
+
 pub async fn search_index(args: SearchIndexArgs) -> anyhow::Result<SearchResponse> {
     debug!(args = ?args, "search-index");
-    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
+    if let Some(aggregation) = args.aggregation.as_ref() {
+        validate_aggregation_json(aggregation)?;
+    }
+    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir.clone()).await?;
     let storage_uri_resolver = quickwit_storage_uri_resolver();
     let metastore_uri_resolver = quickwit_metastore_uri_resolver();
     let metastore = metastore_uri_resolver
         .resolve(&quickwit_config.metastore_uri)
         .await?;
-    let search_request = SearchRequest {
-        index_id: args.index_id,
+    if let Some(sort_by_field) = args.sort_by_field.as_ref() {
+        let index_metadata = metastore.index_metadata(&args.index_id).await?;
+        validate_sort_by_field(&index_metadata, sort_by_field)?;
+    }
+    let search_request = search_request_from_args(&args);
+    let search_response: SearchResponse =
+        single_node_search(&search_request, &*metastore, storage_uri_resolver.clone()).await?;
+    Ok(search_response)
+}
+
+/// Checks that the aggregation request is well-formed JSON, so that syntax errors are reported
+/// with their location. The semantic validation of the request is left to the search layer.
+fn validate_aggregation_json(aggregation: &str) -> Result<(), CliError> {
+    serde_json::from_str::<serde_json::Value>(aggregation)
+        .map(|_| ())
+        .map_err(|error| {
+            let message = format!(
+                "Invalid aggregation JSON at line {}, column {}.",
+                error.line(),
+                error.column()
+            );
+            CliError::config(anyhow::Error::new(error).context(message))
+        })
+}
+
+fn search_request_from_args(args: &SearchIndexArgs) -> SearchRequest {
+    SearchRequest {
+        index_id: args.index_id.clone(),
         query: args.query.clone(),
-        search_fields: args.search_fields.unwrap_or_default(),
+        search_fields: args.search_fields.clone().unwrap_or_default(),
         start_timestamp: args.start_timestamp,
         end_timestamp: args.end_timestamp,
         max_hits: args.max_hits as u64,
         start_offset: args.start_offset as u64,
-        sort_order: None,
-        sort_by_field: None,
-        aggregation_request: args.aggregation,
-    };
-    let search_response: SearchResponse =
-        single_node_search(&search_request, &*metastore, storage_uri_resolver.clone()).await?;
-    Ok(search_response)
+        sort_order: args.sort_order.map(|sort_order| sort_order as i32),
+        sort_by_field: args.sort_by_field.clone(),
+        aggregation_request: args.aggregation.clone(),
+        explain_bytes_per_hit: args.explain_bytes_per_hit,
+        split_order: args.split_order as i32,
+        explain_split_selection_order: args.explain_split_selection_order,
+        explain_doc_mapper_mode: args.explain_doc_mapper_mode,
+        explain_io_wait: args.explain_io_wait,
+        explain_query_depth: args.explain_query_depth,
+        explain_sort_field_coverage: args.explain_sort_field_coverage,
+        exclude_dynamic_fields: args.exclude_dynamic_fields,
+        reconstruct_fields: args.fields.clone().unwrap_or_default(),
+        snippet_fields: args.snippet_fields.clone().unwrap_or_default(),
+        snippet_max_fragments: args.snippet_max_fragments,
+        snippet_fragment_size: args.snippet_fragment_size,
+        snippet_max_fragments_per_field: args.snippet_max_fragments_per_field.clone(),
+        snippet_fragment_size_per_field: args.snippet_fragment_size_per_field.clone(),
+        count_only: args.count,
+        required_tags: args.required_tags.clone(),
+    }
+}
+
+/// Plans the storage fetches of the search, without running it.
+pub async fn search_index_fetch_plan(args: SearchIndexArgs) -> anyhow::Result<Vec<SplitFetchPlan>> {
+    debug!(args = ?args, "search-index-fetch-plan");
+    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir.clone()).await?;
+    let metastore_uri_resolver = quickwit_metastore_uri_resolver();
+    let metastore = metastore_uri_resolver
+        .resolve(&quickwit_config.metastore_uri)
+        .await?;
+    let search_request = search_request_from_args(&args);
+    let fetch_plans = single_node_fetch_plan(&search_request, &*metastore).await?;
+    Ok(fetch_plans)
+}
+
+pub async fn search_index_schema_compat(
+    args: SearchIndexArgs,
+) -> anyhow::Result<Vec<FieldSchemaIncompatibility>> {
+    debug!(args = ?args, "search-index-schema-compat");
+    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir.clone()).await?;
+    let storage_uri_resolver = quickwit_storage_uri_resolver();
+    let metastore_uri_resolver = quickwit_metastore_uri_resolver();
+    let metastore = metastore_uri_resolver
+        .resolve(&quickwit_config.metastore_uri)
+        .await?;
+    let search_request = search_request_from_args(&args);
+    let incompatibilities =
+        single_node_schema_compat(&search_request, &*metastore, storage_uri_resolver.clone())
+            .await?;
+    Ok(incompatibilities)
+}
+
+/// Explains how the query is parsed and rewritten, without running the search.
+pub async fn search_index_explain_query(args: SearchIndexArgs) -> anyhow::Result<QueryExplanation> {
+    debug!(args = ?args, "search-index-explain-query");
+    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir.clone()).await?;
+    let metastore_uri_resolver = quickwit_metastore_uri_resolver();
+    let metastore = metastore_uri_resolver
+        .resolve(&quickwit_config.metastore_uri)
+        .await?;
+    let search_request = search_request_from_args(&args);
+    let explanation = single_node_explain_query(&search_request, &*metastore).await?;
+    Ok(explanation)
 }
 
 pub async fn search_index_cli(args: SearchIndexArgs) -> anyhow::Result<()> {
+    if args.explain {
+        let explanation = search_index_explain_query(args).await?;
+        let explanation_json = serde_json::to_string_pretty(&explanation)?;
+        println!("{}", explanation_json);
+        return Ok(());
+    }
+    if args.explain_fetch_plan {
+        let fetch_plans = search_index_fetch_plan(args).await?;
+        let fetch_plans_json = serde_json::to_string_pretty(&fetch_plans)?;
+        println!("{}", fetch_plans_json);
+        return Ok(());
+    }
+    if args.explain_schema_compat {
+        let incompatibilities = search_index_schema_compat(args).await?;
+        let incompatibilities_json = serde_json::to_string_pretty(&incompatibilities)?;
+        println!("{}", incompatibilities_json);
+        return Ok(());
+    }
     let search_response: SearchResponse = search_index(args).await?;
     let search_response_rest = SearchResponseRest::try_from(search_response)?;
     let search_response_json = serde_json::to_string_pretty(&search_response_rest)?;
@@ -911,6 +1629,386 @@ pub async fn search_index_cli(args: SearchIndexArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Number of documents fetched at once by `index export`.
+const EXPORT_PAGE_SIZE: usize = 1_000;
+
+/// Name of the file recording the progress of an export in its output directory.
+const EXPORT_CHECKPOINT_FILE_NAME: &str = "export-checkpoint.json";
+
+/// Exports the documents matching the query, one split after the other. The list of splits is
+/// pinned when the export starts, and the documents of a split are fetched page by page, in the
+/// order of their address, so that the result set never has to fit in memory. The progress of the
+/// export is checkpointed in the output directory after each page: running the export again with
+/// the same output directory resumes it from the same split and document.
+pub async fn export_index_cli(args: ExportIndexArgs) -> anyhow::Result<()> {
+    debug!(args = ?args, "export-index");
+    let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir.clone()).await?;
+    let storage_uri_resolver = quickwit_storage_uri_resolver();
+    let metastore_uri_resolver = quickwit_metastore_uri_resolver();
+    let metastore = metastore_uri_resolver
+        .resolve(&quickwit_config.metastore_uri)
+        .await?;
+    let index_metadata = metastore.index_metadata(&args.index_id).await?;
+    let csv_field_names = index_metadata
+        .doc_mapping
+        .field_mappings
+        .iter()
+        .map(|field_mapping| field_mapping.name.clone())
+        .collect();
+    let export_params = serde_json::json!({
+        "index_id": args.index_id,
+        "query": args.query,
+        "start_timestamp": args.start_timestamp,
+        "end_timestamp": args.end_timestamp,
+        "format": args.format.as_str(),
+    });
+    let search_request = SearchRequest {
+        index_id: args.index_id.clone(),
+        query: args.query.clone(),
+        start_timestamp: args.start_timestamp,
+        end_timestamp: args.end_timestamp,
+        ..Default::default()
+    };
+    let split_exporter =
+        SplitExporter::new(search_request, &*metastore, storage_uri_resolver).await?;
+    let mut export_writer = ExportWriter::open(
+        &args.output_dir,
+        export_params,
+        args.format,
+        args.max_file_num_bytes,
+        csv_field_names,
+    )?;
+    if export_writer.splits_opt.is_none() {
+        export_writer.splits_opt = Some(split_exporter.list_splits(&*metastore).await?);
+        export_writer.checkpoint()?;
+    } else if export_writer.num_exported_docs > 0 {
+        println!(
+            "Resuming the export after the {} documents already exported.",
+            export_writer.num_exported_docs.separate_with_commas()
+        );
+    }
+    let splits = export_writer
+        .splits_opt
+        .clone()
+        .expect("The splits should be pinned.");
+    let mut stdout_handle = stdout();
+    let start_time = Instant::now();
+    let mut throughput_calculator = ThroughputCalculator::new(start_time, THROUGHPUT_WINDOW_SIZE);
+    let mut last_report_time = start_time;
+    while let Some(split) = splits.get(export_writer.split_ord) {
+        let doc_addresses = split_exporter
+            .collect_doc_addresses(split)
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to read split `{}`. The splits of the index may have been merged or \
+                     deleted since the export started: remove `{}` to start the export over.",
+                    split.split_id,
+                    args.output_dir.join(EXPORT_CHECKPOINT_FILE_NAME).display()
+                )
+            })?;
+        let remaining_doc_addresses = doc_addresses
+            .get(export_writer.num_split_exported_docs..)
+            .unwrap_or_default();
+        for doc_addresses_page in remaining_doc_addresses.chunks(EXPORT_PAGE_SIZE) {
+            let docs = split_exporter.fetch_docs(split, doc_addresses_page).await?;
+            for doc_json in &docs {
+                export_writer.write_doc(doc_json)?;
+            }
+            export_writer.checkpoint()?;
+            if last_report_time.elapsed() >= Duration::from_secs(1) {
+                display_export_statistics(
+                    &mut stdout_handle,
+                    &mut throughput_calculator,
+                    &export_writer,
+                )?;
+                last_report_time = Instant::now();
+            }
+        }
+        export_writer.start_next_split();
+        export_writer.checkpoint()?;
+    }
+    display_export_statistics(
+        &mut stdout_handle,
+        &mut throughput_calculator,
+        &export_writer,
+    )?;
+    println!();
+    println!(
+        "Exported {} documents to `{}` in {}.",
+        export_writer.num_exported_docs.separate_with_commas(),
+        args.output_dir.display(),
+        format_duration(Duration::from_secs(start_time.elapsed().as_secs()))
+    );
+    Ok(())
+}
+
+/// Writes the exported documents to files of bounded size and records the progress of the export
+/// in a checkpoint file, see [`EXPORT_CHECKPOINT_FILE_NAME`].
+struct ExportWriter {
+    output_dir: PathBuf,
+    /// Parameters of the export, used to check that a resumed export is the same export.
+    export_params: serde_json::Value,
+    format: ExportFormat,
+    max_file_num_bytes: u64,
+    /// Fields written in the CSV files, in order.
+    csv_field_names: Vec<String>,
+    /// File currently written, opened on the first document written to it.
+    file_opt: Option<BufWriter<File>>,
+    file_ordinal: u64,
+    file_num_bytes: u64,
+    /// Splits the documents are exported from, pinned when the export starts.
+    splits_opt: Option<Vec<SplitIdAndFooterOffsets>>,
+    /// Position in `splits_opt` of the split being exported.
+    split_ord: usize,
+    /// Number of documents of the split being exported that were already exported.
+    num_split_exported_docs: usize,
+    num_exported_docs: u64,
+    /// Number of bytes written since the export was started or resumed.
+    num_written_bytes: u64,
+}
+
+impl ExportWriter {
+    /// Opens the writer, restoring the progress recorded in the output directory, if any.
+    fn open(
+        output_dir: &Path,
+        export_params: serde_json::Value,
+        format: ExportFormat,
+        max_file_num_bytes: u64,
+        csv_field_names: Vec<String>,
+    ) -> anyhow::Result<Self> {
+        std::fs::create_dir_all(output_dir).with_context(|| {
+            format!(
+                "Failed to create output directory `{}`.",
+                output_dir.display()
+            )
+        })?;
+        let mut export_writer = Self {
+            output_dir: output_dir.to_path_buf(),
+            export_params,
+            format,
+            max_file_num_bytes,
+            csv_field_names,
+            file_opt: None,
+            file_ordinal: 0,
+            file_num_bytes: 0,
+            splits_opt: None,
+            split_ord: 0,
+            num_split_exported_docs: 0,
+            num_exported_docs: 0,
+            num_written_bytes: 0,
+        };
+        let checkpoint_path = output_dir.join(EXPORT_CHECKPOINT_FILE_NAME);
+        if checkpoint_path.exists() {
+            export_writer.restore_checkpoint(&checkpoint_path)?;
+        }
+        // The files following the current file are left over from an interrupted export: they are
+        // written again.
+        let mut next_file_ordinal = export_writer.file_ordinal + 1;
+        while export_writer.file_path(next_file_ordinal).exists() {
+            std::fs::remove_file(export_writer.file_path(next_file_ordinal))?;
+            next_file_ordinal += 1;
+        }
+        Ok(export_writer)
+    }
+
+    /// Restores the progress recorded in the checkpoint of a previous run of the export.
+    fn restore_checkpoint(&mut self, checkpoint_path: &Path) -> anyhow::Result<()> {
+        let checkpoint_json = std::fs::read_to_string(checkpoint_path)?;
+        let checkpoint: serde_json::Value =
+            serde_json::from_str(&checkpoint_json).with_context(|| {
+                format!(
+                    "Failed to parse export checkpoint `{}`.",
+                    checkpoint_path.display()
+                )
+            })?;
+        if checkpoint["params"] != self.export_params {
+            bail!(
+                "Output directory `{}` holds the files of another export. Use another output \
+                 directory or remove `{}` to start the export over.",
+                self.output_dir.display(),
+                checkpoint_path.display()
+            );
+        }
+        let read_counter = |key: &str| {
+            checkpoint[key].as_u64().with_context(|| {
+                format!(
+                    "Export checkpoint `{}` is missing `{}`.",
+                    checkpoint_path.display(),
+                    key
+                )
+            })
+        };
+        self.num_exported_docs = read_counter("num_exported_docs")?;
+        self.file_ordinal = read_counter("file_ordinal")?;
+        self.file_num_bytes = read_counter("file_num_bytes")?;
+        self.split_ord = read_counter("split_ord")? as usize;
+        self.num_split_exported_docs = read_counter("num_split_exported_docs")? as usize;
+        let splits = serde_json::from_value(checkpoint["splits"].clone()).with_context(|| {
+            format!(
+                "Export checkpoint `{}` is missing `splits`.",
+                checkpoint_path.display()
+            )
+        })?;
+        self.splits_opt = Some(splits);
+        Ok(())
+    }
+
+    fn file_path(&self, file_ordinal: u64) -> PathBuf {
+        self.output_dir.join(format!(
+            "export-{:05}.{}",
+            file_ordinal,
+            self.format.as_str()
+        ))
+    }
+
+    fn num_files(&self) -> u64 {
+        if self.file_ordinal == 0 && self.file_num_bytes == 0 {
+            0
+        } else {
+            self.file_ordinal + 1
+        }
+    }
+
+    fn write_doc(&mut self, doc_json: &str) -> anyhow::Result<()> {
+        let line = match self.format {
+            ExportFormat::Json => format!("{}\n", doc_json),
+            ExportFormat::Csv => {
+                let doc: serde_json::Value = serde_json::from_str(doc_json)?;
+                let values = self
+                    .csv_field_names
+                    .iter()
+                    .map(|field_name| csv_value(&doc[field_name]))
+                    .collect_vec();
+                csv_record(&values)
+            }
+        };
+        // A file always holds at least one document, even if it exceeds the max file size.
+        if self.file_num_bytes > 0
+            && self.file_num_bytes + line.len() as u64 > self.max_file_num_bytes
+        {
+            if let Some(mut file) = self.file_opt.take() {
+                file.flush()?;
+            }
+            self.file_ordinal += 1;
+            self.file_num_bytes = 0;
+        }
+        self.write_to_file(line.as_bytes())?;
+        self.num_split_exported_docs += 1;
+        self.num_exported_docs += 1;
+        Ok(())
+    }
+
+    /// Moves on to the next split, once all the documents of the current split are exported.
+    fn start_next_split(&mut self) {
+        self.split_ord += 1;
+        self.num_split_exported_docs = 0;
+    }
+
+    fn write_to_file(&mut self, bytes: &[u8]) -> anyhow::Result<()> {
+        if self.file_opt.is_none() {
+            let file_path = self.file_path(self.file_ordinal);
+            let file = if self.file_num_bytes > 0 {
+                // Drops the documents written after the checkpoint.
+                let mut file = OpenOptions::new().write(true).open(&file_path)?;
+                file.set_len(self.file_num_bytes)?;
+                file.seek(SeekFrom::End(0))?;
+                file
+            } else {
+                File::create(&file_path)?
+            };
+            self.file_opt = Some(BufWriter::new(file));
+            if self.file_num_bytes == 0 && self.format == ExportFormat::Csv {
+                let header = csv_record(&self.csv_field_names);
+                self.write_to_file(header.as_bytes())?;
+            }
+        }
+        self.file_opt
+            .as_mut()
+            .expect("The file should be open.")
+            .write_all(bytes)?;
+        self.file_num_bytes += bytes.len() as u64;
+        self.num_written_bytes += bytes.len() as u64;
+        Ok(())
+    }
+
+    /// Flushes the current file and records the progress of the export.
+    fn checkpoint(&mut self) -> anyhow::Result<()> {
+        if let Some(file) = self.file_opt.as_mut() {
+            file.flush()?;
+            file.get_ref().sync_data()?;
+        }
+        let checkpoint = serde_json::json!({
+            "params": self.export_params,
+            "splits": self.splits_opt,
+            "split_ord": self.split_ord,
+            "num_split_exported_docs": self.num_split_exported_docs,
+            "num_exported_docs": self.num_exported_docs,
+            "file_ordinal": self.file_ordinal,
+            "file_num_bytes": self.file_num_bytes,
+        });
+        let checkpoint_path = self.output_dir.join(EXPORT_CHECKPOINT_FILE_NAME);
+        let tmp_checkpoint_path = checkpoint_path.with_extension("json.tmp");
+        std::fs::write(
+            &tmp_checkpoint_path,
+            serde_json::to_vec_pretty(&checkpoint)?,
+        )?;
+        std::fs::rename(&tmp_checkpoint_path, &checkpoint_path)?;
+        Ok(())
+    }
+}
+
+/// Formats a field value of a document for a CSV record: strings are written as is, missing
+/// values as empty strings, and other values as JSON.
+fn csv_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::Null => String::new(),
+        serde_json::Value::String(text) => text.clone(),
+        _ => value.to_string(),
+    }
+}
+
+/// Builds a CSV record, quoting the values that contain a comma, a double quote, or a line break.
+fn csv_record(values: &[String]) -> String {
+    let mut record = values
+        .iter()
+        .map(|value| {
+            if value.contains(&[',', '"', '\n', '\r'][..]) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.clone()
+            }
+        })
+        .join(",");
+    record.push('\n');
+    record
+}
+
+/// Validates the effective merge policy of a merge run whose policy is overridden from the command
+/// line.
+fn check_merge_policy_overrides(
+    merge_policy: &MergePolicy,
+    split_num_docs_target: usize,
+) -> anyhow::Result<()> {
+    if merge_policy.merge_factor < 2 {
+        bail!(
+            "The merge factor must be at least 2, got {}.",
+            merge_policy.merge_factor
+        );
+    }
+    if merge_policy.max_merge_factor < merge_policy.merge_factor {
+        bail!(
+            "The max merge factor ({}) must be superior or equal to the merge factor ({}).",
+            merge_policy.max_merge_factor,
+            merge_policy.merge_factor
+        );
+    }
+    if split_num_docs_target == 0 {
+        bail!("`--max-merge-docs` must be greater than zero.");
+    }
+    Ok(())
+}
+
 pub async fn merge_or_demux_cli(
     args: MergeOrDemuxArgs,
     merge_enabled: bool,
@@ -926,9 +2024,42 @@ pub async fn merge_or_demux_cli(
     let metastore = metastore_uri_resolver
         .resolve(&config.metastore_uri)
         .await?;
+    let mut merge_policy_opt = None;
+    let mut split_num_docs_target_opt = None;
+    if args.merge_factor_opt.is_some()
+        || args.max_merge_factor_opt.is_some()
+        || args.max_merge_docs_opt.is_some()
+    {
+        let indexing_settings = metastore
+            .index_metadata(&args.index_id)
+            .await?
+            .indexing_settings;
+        let merge_policy = MergePolicy {
+            merge_factor: args
+                .merge_factor_opt
+                .unwrap_or(indexing_settings.merge_policy.merge_factor),
+            max_merge_factor: args
+                .max_merge_factor_opt
+                .unwrap_or(indexing_settings.merge_policy.max_merge_factor),
+            ..indexing_settings.merge_policy
+        };
+        let split_num_docs_target = args
+            .max_merge_docs_opt
+            .unwrap_or(indexing_settings.split_num_docs_target);
+        check_merge_policy_overrides(&merge_policy, split_num_docs_target)?;
+        println!(
+            "Merging with a merge factor of {}, a max merge factor of {}, and a max number of \
+             docs per split of {}.",
+            merge_policy.merge_factor,
+            merge_policy.max_merge_factor,
+            split_num_docs_target.separate_with_commas()
+        );
+        merge_policy_opt = Some(merge_policy);
+        split_num_docs_target_opt = Some(split_num_docs_target);
+    }
     let storage_resolver = quickwit_storage_uri_resolver().clone();
     let indexing_server = IndexingService::new(
-        config.data_dir_path,
+        config.data_dir_path.clone(),
         indexer_config,
         metastore,
         storage_resolver,
@@ -941,18 +2072,114 @@ pub async fn merge_or_demux_cli(
             index_id: args.index_id.clone(),
             merge_enabled,
             demux_enabled,
+            merge_policy_opt,
+            split_num_docs_target_opt,
         })
         .await?;
     let pipeline_handle = indexing_server_mailbox
         .ask_for_res(DetachPipeline { pipeline_id })
         .await?;
-    let (pipeline_exit_status, _pipeline_statistics) = pipeline_handle.join().await;
+    let merge_registration = MergeRegistration::register(&config.data_dir_path, &args.index_id)?;
+    let pipeline_mailbox = pipeline_handle.mailbox().clone();
+    let pipeline_join = pipeline_handle.join();
+    tokio::pin!(pipeline_join);
+    let mut check_cancellation_interval = tokio::time::interval(Duration::from_secs(1));
+    let mut is_cancelled = false;
+    let (pipeline_exit_status, _pipeline_statistics) = loop {
+        tokio::select! {
+            pipeline_exit = &mut pipeline_join => break pipeline_exit,
+            _ = check_cancellation_interval.tick() => {
+                if !is_cancelled && merge_registration.is_cancelled() {
+                    info!(index_id = %args.index_id, "Merge cancellation requested.");
+                    if let Err(error) = pipeline_mailbox.ask(CancelMerges).await {
+                        warn!(error = ?error, "Failed to cancel merge.");
+                    }
+                    is_cancelled = true;
+                }
+            }
+        }
+    };
     if !pipeline_exit_status.is_success() {
         bail!(pipeline_exit_status);
     }
     Ok(())
 }
 
+/// Name of the file registering a running merge in the indexing directory of the index.
+const MERGE_REGISTRATION_FILE_NAME: &str = "merge.running";
+
+/// Name of the file requesting the running merge of an index to stop.
+const MERGE_CANCELLATION_FILE_NAME: &str = "merge.cancel";
+
+/// Registers a merge running in this process, so that `quickwit index merge-cancel` can target
+/// it. The registration is removed when dropped.
+struct MergeRegistration {
+    registration_path: PathBuf,
+    cancellation_path: PathBuf,
+}
+
+impl MergeRegistration {
+    fn register(data_dir_path: &Path, index_id: &str) -> anyhow::Result<Self> {
+        let index_indexing_dir_path = data_dir_path.join(INDEXING_DIR_NAME).join(index_id);
+        std::fs::create_dir_all(&index_indexing_dir_path)?;
+        let registration = Self {
+            registration_path: index_indexing_dir_path.join(MERGE_REGISTRATION_FILE_NAME),
+            cancellation_path: index_indexing_dir_path.join(MERGE_CANCELLATION_FILE_NAME),
+        };
+        // A cancellation left over by a previous merge should not stop this one.
+        if registration.cancellation_path.exists() {
+            std::fs::remove_file(&registration.cancellation_path)?;
+        }
+        std::fs::write(
+            &registration.registration_path,
+            std::process::id().to_string(),
+        )
+        .with_context(|| {
+            format!(
+                "Failed to register merge in `{}`.",
+                registration.registration_path.display()
+            )
+        })?;
+        Ok(registration)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancellation_path.exists()
+    }
+}
+
+impl Drop for MergeRegistration {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.registration_path);
+        let _ = std::fs::remove_file(&self.cancellation_path);
+    }
+}
+
+pub async fn cancel_merge_cli(args: CancelMergeArgs) -> anyhow::Result<()> {
+    debug!(args = ?args, "cancel-merge");
+    let config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
+    let index_indexing_dir_path = config
+        .data_dir_path
+        .join(INDEXING_DIR_NAME)
+        .join(&args.index_id);
+    if !index_indexing_dir_path
+        .join(MERGE_REGISTRATION_FILE_NAME)
+        .exists()
+    {
+        bail!("No merge is running for index `{}`.", args.index_id);
+    }
+    tokio::fs::write(
+        index_indexing_dir_path.join(MERGE_CANCELLATION_FILE_NAME),
+        b"",
+    )
+    .await?;
+    println!(
+        "The merge of index `{}` will stop once the operations underway complete.",
+        args.index_id
+    );
+    Ok(())
+}
+
 pub async fn delete_index_cli(args: DeleteIndexArgs) -> anyhow::Result<()> {
     debug!(args = ?args, "delete-index");
     quickwit_telemetry::send_telemetry_event(TelemetryEvent::Delete).await;
@@ -996,6 +2223,23 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
     debug!(args = ?args, "garbage-collect-index");
     quickwit_telemetry::send_telemetry_event(TelemetryEvent::GarbageCollect).await;
 
+    if args.force && !args.dry_run {
+        println!(
+            "{}",
+            "Warning: a forced garbage collection deletes all the staged splits of the index, \
+             including the splits of an indexing in progress that are about to be published."
+                .yellow()
+        );
+        if !args.assume_yes
+            && !prompt_confirmation(&format!(
+                "Garbage collect index `{}` with a grace period of zero?",
+                args.index_id
+            ))?
+        {
+            println!("Garbage collection aborted.");
+            return Ok(());
+        }
+    }
     let quickwit_config = load_quickwit_config(&args.config_uri, args.data_dir).await?;
     let metastore = quickwit_metastore_uri_resolver()
         .resolve(&quickwit_config.metastore_uri)
@@ -1006,7 +2250,12 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
         quickwit_config.default_index_root_uri,
     );
     let deleted_files = index_service
-        .garbage_collect_index(&args.index_id, args.grace_period, args.dry_run)
+        .garbage_collect_index(
+            &args.index_id,
+            args.grace_period,
+            args.delete_orphans,
+            args.dry_run,
+        )
         .await?;
     if deleted_files.is_empty() {
         println!("No dangling files to garbage collect.");
@@ -1016,7 +2265,10 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
     if args.dry_run {
         println!("The following files will be garbage collected.");
         for file_entry in deleted_files {
-            println!(" - {}", file_entry.file_name);
+            println!(
+                " - {} ({} bytes)",
+                file_entry.file_name, file_entry.file_size_in_bytes
+            );
         }
         return Ok(());
     }
@@ -1038,10 +2290,14 @@ pub async fn garbage_collect_index_cli(args: GarbageCollectIndexArgs) -> anyhow:
 pub async fn start_statistics_reporting_loop(
     pipeline_handle: ActorHandle<IndexingPipeline>,
     is_stdin: bool,
+    throughput_window_secs: usize,
+    dry_run: bool,
+    progress_format: ProgressFormat,
 ) -> anyhow::Result<IndexingStatistics> {
     let mut stdout_handle = stdout();
+    let mut stderr_handle = stderr();
     let start_time = Instant::now();
-    let mut throughput_calculator = ThroughputCalculator::new(start_time);
+    let mut throughput_calculator = ThroughputCalculator::new(start_time, throughput_window_secs);
     let mut report_interval = tokio::time::interval(Duration::from_secs(1));
 
     loop {
@@ -1055,11 +2311,18 @@ pub async fn start_statistics_reporting_loop(
 
         // Let's not display live statistics to allow screen to scroll.
         if observation.state.num_docs > 0 {
-            display_statistics(
-                &mut stdout_handle,
-                &mut throughput_calculator,
-                &observation.state,
-            )?;
+            match progress_format {
+                ProgressFormat::Pretty => display_statistics(
+                    &mut stdout_handle,
+                    &mut throughput_calculator,
+                    &observation.state,
+                )?,
+                ProgressFormat::Json => display_statistics_json(
+                    &mut stderr_handle,
+                    &mut throughput_calculator,
+                    &observation.state,
+                )?,
+            }
         }
 
         if observation.obs_type == ObservationType::PostMortem {
@@ -1077,16 +2340,36 @@ pub async fn start_statistics_reporting_loop(
     }
 
     if is_stdin {
-        display_statistics(
-            &mut stdout_handle,
-            &mut throughput_calculator,
-            &pipeline_statistics,
-        )?;
+        match progress_format {
+            ProgressFormat::Pretty => display_statistics(
+                &mut stdout_handle,
+                &mut throughput_calculator,
+                &pipeline_statistics,
+            )?,
+            ProgressFormat::Json => display_statistics_json(
+                &mut stderr_handle,
+                &mut throughput_calculator,
+                &pipeline_statistics,
+            )?,
+        }
     }
     // display end of task report
     println!();
     let secs = Duration::from_secs(start_time.elapsed().as_secs());
-    if pipeline_statistics.num_invalid_docs == 0 {
+    if dry_run {
+        let num_valid_docs = pipeline_statistics.num_docs - pipeline_statistics.num_invalid_docs;
+        let num_missing_fields = pipeline_statistics.num_missing_fields;
+        let num_parse_errors = pipeline_statistics.num_invalid_docs - num_missing_fields;
+        println!(
+            "Dry run: validated {} documents in {}. {} would be indexed, {} failed to parse, {} \
+             miss a required field. Nothing was indexed.",
+            pipeline_statistics.num_docs.separate_with_commas(),
+            format_duration(secs),
+            num_valid_docs.separate_with_commas(),
+            num_parse_errors.separate_with_commas(),
+            num_missing_fields.separate_with_commas(),
+        );
+    } else if pipeline_statistics.num_invalid_docs == 0 {
         println!(
             "Indexed {} documents in {}.",
             pipeline_statistics.num_docs.separate_with_commas(),
@@ -1165,34 +2448,102 @@ fn display_statistics(
     Ok(())
 }
 
+/// Writes the indexing statistics as a single-line JSON object, for consumption by other programs.
+fn display_statistics_json(
+    stderr: &mut Stderr,
+    throughput_calculator: &mut ThroughputCalculator,
+    statistics: &IndexingStatistics,
+) -> anyhow::Result<()> {
+    let elapsed_secs = throughput_calculator.elapsed_time().as_secs();
+    let throughput_mb_s = throughput_calculator.calculate(statistics.total_bytes_processed);
+    let progress = serde_json::json!({
+        "num_docs": statistics.num_docs,
+        "num_invalid_docs": statistics.num_invalid_docs,
+        "num_published_splits": statistics.num_published_splits,
+        "total_bytes_processed": statistics.total_bytes_processed,
+        "throughput_mb_s": throughput_mb_s,
+        "elapsed_secs": elapsed_secs,
+    });
+    writeln!(stderr, "{}", progress)?;
+    stderr.flush()?;
+    Ok(())
+}
+
+fn display_export_statistics(
+    stdout: &mut Stdout,
+    throughput_calculator: &mut ThroughputCalculator,
+    export_writer: &ExportWriter,
+) -> anyhow::Result<()> {
+    let elapsed_duration = time::Duration::try_from(throughput_calculator.elapsed_time())?;
+    let elapsed_time = format!(
+        "{:02}:{:02}:{:02}",
+        elapsed_duration.whole_hours(),
+        elapsed_duration.whole_minutes() % 60,
+        elapsed_duration.whole_seconds() % 60
+    );
+    let throughput_mb_s = throughput_calculator.calculate(export_writer.num_written_bytes);
+    let mut printer = Printer { stdout };
+    printer.print_header("Num docs")?;
+    printer.print_value(format_args!("{:>7}", export_writer.num_exported_docs))?;
+    printer.print_header("Files")?;
+    printer.print_value(format_args!("{:>3}", export_writer.num_files()))?;
+    printer.print_header("Output size")?;
+    printer.print_value(format_args!(
+        "{:>5}MB",
+        export_writer.num_written_bytes / 1_000_000
+    ))?;
+    printer.print_header("Thrghput")?;
+    printer.print_value(format_args!("{:>5.2}MB/s", throughput_mb_s))?;
+    printer.print_header("Time")?;
+    printer.print_value(format_args!("{}\n", elapsed_time))?;
+    printer.flush()?;
+    Ok(())
+}
+
 /// ThroughputCalculator is used to calculate throughput.
 struct ThroughputCalculator {
     /// Stores the time series of processed bytes value.
     processed_bytes_values: VecDeque<(Instant, u64)>,
+    /// Number of values of the time series over which the throughput is calculated.
+    window_size: usize,
     /// Store the time this calculator started
     start_time: Instant,
 }
 
 impl ThroughputCalculator {
-    /// Creates new instance.
-    pub fn new(start_time: Instant) -> Self {
-        let processed_bytes_values: VecDeque<(Instant, u64)> = (0..THROUGHPUT_WINDOW_SIZE)
-            .map(|_| (start_time, 0u64))
-            .collect();
+    /// Creates new instance. The throughput is calculated over the last `window_size` values,
+    /// i.e. over `window_size` seconds when calculated every second.
+    pub fn new(start_time: Instant, window_size: usize) -> Self {
+        assert!(
+            window_size > 0,
+            "The window size must be greater than zero."
+        );
+        let processed_bytes_values: VecDeque<(Instant, u64)> =
+            (0..window_size).map(|_| (start_time, 0u64)).collect();
         Self {
             processed_bytes_values,
+            window_size,
             start_time,
         }
     }
 
     /// Calculates the throughput.
     pub fn calculate(&mut self, current_processed_bytes: u64) -> f64 {
-        self.processed_bytes_values.pop_front();
         let current_instant = Instant::now();
-        let (first_instant, first_processed_bytes) = *self.processed_bytes_values.front().unwrap();
+        // The oldest value is read before the current value is pushed, so that the window is
+        // never empty, even if it holds a single value.
+        let (first_instant, first_processed_bytes) = *self
+            .processed_bytes_values
+            .front()
+            .expect("The window should never be empty.");
         let elapsed_time = (current_instant - first_instant).as_millis() as f64 / 1_000f64;
         self.processed_bytes_values
             .push_back((current_instant, current_processed_bytes));
+        if self.processed_bytes_values.len() > self.window_size {
+            self.processed_bytes_values.pop_front();
+        }
+        // The elapsed time is floored to one second to avoid dividing by zero, or reporting
+        // meaningless throughputs, when the values are calculated in quick succession.
         (current_processed_bytes - first_processed_bytes) as f64
             / 1_000_000f64
             / elapsed_time.max(1f64) as f64
@@ -1202,3 +2553,64 @@ impl ThroughputCalculator {
         self.start_time.elapsed()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_merge_policy_overrides() {
+        let merge_policy = MergePolicy::default();
+        check_merge_policy_overrides(&merge_policy, 10_000_000).unwrap();
+
+        let merge_policy = MergePolicy {
+            merge_factor: 4,
+            max_merge_factor: 4,
+            ..Default::default()
+        };
+        check_merge_policy_overrides(&merge_policy, 10_000_000).unwrap();
+
+        let merge_policy = MergePolicy {
+            merge_factor: 1,
+            ..Default::default()
+        };
+        let error = check_merge_policy_overrides(&merge_policy, 10_000_000).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The merge factor must be at least 2, got 1."
+        );
+
+        let merge_policy = MergePolicy {
+            merge_factor: 20,
+            max_merge_factor: 12,
+            ..Default::default()
+        };
+        let error = check_merge_policy_overrides(&merge_policy, 10_000_000).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The max merge factor (12) must be superior or equal to the merge factor (20)."
+        );
+
+        let error = check_merge_policy_overrides(&MergePolicy::default(), 0).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`--max-merge-docs` must be greater than zero."
+        );
+    }
+
+    #[test]
+    fn test_throughput_calculator() {
+        // The values are calculated in quick succession: the elapsed time is floored to one
+        // second.
+        let mut throughput_calculator = ThroughputCalculator::new(Instant::now(), 1);
+        assert_eq!(throughput_calculator.calculate(1_000_000), 1.0);
+        assert_eq!(throughput_calculator.calculate(3_000_000), 2.0);
+        assert_eq!(throughput_calculator.calculate(3_000_000), 0.0);
+
+        let mut throughput_calculator = ThroughputCalculator::new(Instant::now(), 3);
+        assert_eq!(throughput_calculator.calculate(1_000_000), 1.0);
+        assert_eq!(throughput_calculator.calculate(3_000_000), 3.0);
+        assert_eq!(throughput_calculator.calculate(3_000_000), 3.0);
+        assert_eq!(throughput_calculator.calculate(4_000_000), 3.0);
+    }
+}
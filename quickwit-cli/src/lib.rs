@@ -17,14 +17,16 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::io::{self, Write};
 use std::path::PathBuf;
 use std::time::Duration;
 
 use anyhow::bail;
+use clap::ArgMatches;
 use once_cell::sync::Lazy;
 use quickwit_common::run_checklist;
 use quickwit_common::uri::Uri;
-use quickwit_config::{QuickwitConfig, SourceConfig};
+use quickwit_config::{QuickwitConfig, SourceConfig, DEFAULT_QW_CONFIG_PATH};
 use quickwit_indexing::check_source_connectivity;
 use quickwit_metastore::quickwit_metastore_uri_resolver;
 use quickwit_storage::{load_file, quickwit_storage_uri_resolver};
@@ -33,16 +35,26 @@ use tabled::object::Rows;
 use tabled::{Alignment, Header, Modify, Rotate, Style, Table, Tabled};
 use tracing::info;
 
+use crate::error::CliError;
+
+pub mod check;
 pub mod cli;
+pub mod config;
+pub mod error;
 pub mod index;
 pub mod service;
 pub mod source;
 pub mod split;
 pub mod stats;
 
-/// Throughput calculation window size.
+/// Throughput calculation window size, in seconds. Default value of the `--throughput-window`
+/// option of `index ingest`.
 const THROUGHPUT_WINDOW_SIZE: usize = 5;
 
+/// This environment variable can be set to specify the config file location when `--config` is
+/// omitted.
+pub const QW_CONFIG_ENV_KEY: &str = "QW_CONFIG";
+
 /// This environment variable can be set to send telemetry events to a jaeger instance.
 pub const QW_JAEGER_ENABLED_ENV_KEY: &str = "QW_JAEGER_ENABLED";
 
@@ -74,13 +86,37 @@ async fn load_quickwit_config(
     config_uri: &Uri,
     data_dir_path_opt: Option<PathBuf>,
 ) -> anyhow::Result<QuickwitConfig> {
-    let config_content = load_file(config_uri).await?;
-    let config =
-        QuickwitConfig::load(config_uri, config_content.as_slice(), data_dir_path_opt).await?;
+    let config_content = load_file(config_uri).await.map_err(CliError::config)?;
+    let config = QuickwitConfig::load(config_uri, config_content.as_slice(), data_dir_path_opt)
+        .await
+        .map_err(CliError::config)?;
     info!(config_uri=%config_uri, config=?config, "Loaded Quickwit config.");
     Ok(config)
 }
 
+/// Returns the config file location of a command, resolved in the following order: the `--config`
+/// flag, the `QW_CONFIG` environment variable, and finally [`DEFAULT_QW_CONFIG_PATH`]. An empty
+/// value is treated as unset.
+pub fn parse_config_uri(matches: &ArgMatches) -> anyhow::Result<Uri> {
+    // The global `config` arg reads `QW_CONFIG` and defaults to `DEFAULT_QW_CONFIG_PATH`, so the
+    // first two steps are carried out by clap.
+    let config_uri = matches
+        .value_of("config")
+        .filter(|config_uri| !config_uri.is_empty())
+        .unwrap_or(DEFAULT_QW_CONFIG_PATH);
+    Uri::try_new(config_uri)
+}
+
+/// Asks the user a yes/no question on stdin. Any answer other than `y` or `yes`, including an
+/// empty answer or a closed stdin, is a no.
+pub fn prompt_confirmation(prompt: &str) -> anyhow::Result<bool> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 /// Runs connectivity checks for a given `metastore_uri` and `index_id`.
 /// Optionaly, it takes a `SourceConfig` that will be checked instead
 /// of the index's sources.
@@ -96,7 +132,10 @@ pub async fn run_index_checklist(
 
     let index_metadata = metastore.index_metadata(index_id).await?;
     let storage_uri_resolver = quickwit_storage_uri_resolver();
-    let storage = storage_uri_resolver.resolve(&index_metadata.index_uri)?;
+    let storage = storage_uri_resolver.resolve_with_storage_config(
+        &index_metadata.index_uri,
+        index_metadata.storage_config.as_ref(),
+    )?;
     checks.push(("storage", storage.check().await));
 
     if let Some(source_config) = source_to_check {
@@ -137,7 +176,11 @@ pub fn make_table<T: Tabled>(
 mod tests {
     use std::time::Duration;
 
-    use super::parse_duration_with_unit;
+    use quickwit_common::uri::Uri;
+    use quickwit_config::DEFAULT_QW_CONFIG_PATH;
+
+    use super::{parse_config_uri, parse_duration_with_unit};
+    use crate::cli::build_cli;
 
     #[test]
     fn test_parse_duration_with_unit() -> anyhow::Result<()> {
@@ -159,4 +202,31 @@ mod tests {
         assert!(parse_duration_with_unit("1h30").is_err());
         Ok(())
     }
+
+    #[test]
+    fn test_parse_config_uri() -> anyhow::Result<()> {
+        let matches = build_cli().no_binary_name(true).try_get_matches_from(vec![
+            "index",
+            "list",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let (_, index_matches) = matches.subcommand().unwrap();
+        let (_, list_matches) = index_matches.subcommand().unwrap();
+        assert_eq!(
+            parse_config_uri(list_matches)?,
+            Uri::try_new("file:///config.yaml")?
+        );
+
+        let matches = build_cli()
+            .no_binary_name(true)
+            .try_get_matches_from(vec!["index", "list", "--config", ""])?;
+        let (_, index_matches) = matches.subcommand().unwrap();
+        let (_, list_matches) = index_matches.subcommand().unwrap();
+        assert_eq!(
+            parse_config_uri(list_matches)?,
+            Uri::try_new(DEFAULT_QW_CONFIG_PATH)?
+        );
+        Ok(())
+    }
 }
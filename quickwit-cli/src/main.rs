@@ -24,6 +24,7 @@ use anyhow::Context;
 use opentelemetry::global;
 use opentelemetry::sdk::propagation::TraceContextPropagator;
 use quickwit_cli::cli::{build_cli, CliCommand};
+use quickwit_cli::error::CliErrorKind;
 use quickwit_cli::QW_JAEGER_ENABLED_ENV_KEY;
 use quickwit_cluster::QuickwitService;
 use quickwit_common::metrics::new_gauge;
@@ -131,7 +132,7 @@ fn runtime_configuration_for_cmd(command: &CliCommand) -> Option<RuntimesConfigu
             }
         }
         CliCommand::Index(_) => Some(RuntimesConfiguration::default()),
-        CliCommand::Split(_) | CliCommand::Source(_) => None,
+        CliCommand::Config(_) | CliCommand::Split(_) | CliCommand::Source(_) => None,
     }
 }
 
@@ -161,7 +162,7 @@ async fn main() -> anyhow::Result<()> {
         Ok(command) => command,
         Err(err) => {
             eprintln!("Failed to parse command arguments: {:?}", err);
-            std::process::exit(1);
+            std::process::exit(CliErrorKind::Config.exit_code());
         }
     };
 
@@ -180,8 +181,8 @@ async fn main() -> anyhow::Result<()> {
     );
 
     let return_code: i32 = if let Err(err) = command.execute().await {
-        eprintln!("Command failed: {:?}", err);
-        1
+        eprintln!("{} {:?}", err, err.cause());
+        err.exit_code()
     } else {
         0
     };
@@ -199,24 +200,46 @@ fn about_text() -> String {
     let mut about_text = String::from(
         "Index your dataset on object storage & make it searchable from the command line.\n  Find more information at https://quickwit.io/docs\n\n",
     );
+    about_text += &exit_codes_text();
     if quickwit_telemetry::is_telemetry_enabled() {
         about_text += "Telemetry: enabled";
     }
     about_text
 }
 
+/// Return the list of exit codes returned by the CLI on failure.
+fn exit_codes_text() -> String {
+    let mut exit_codes_text = String::from("Exit codes:\n  0: success\n");
+    for (kind, description) in [
+        (CliErrorKind::Internal, "internal or unclassified error"),
+        (CliErrorKind::Config, "invalid command arguments or config"),
+        (CliErrorKind::Metastore, "metastore or not found error"),
+        (CliErrorKind::Connectivity, "connectivity error"),
+    ] {
+        exit_codes_text += &format!("  {}: {}\n", kind.exit_code(), description);
+    }
+    exit_codes_text += "\n";
+    exit_codes_text
+}
+
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
     use std::path::PathBuf;
     use std::time::Duration;
 
+    use quickwit_cli::check::CheckCliCommand;
     use quickwit_cli::cli::{build_cli, CliCommand};
+    use quickwit_cli::config::{ConfigCliCommand, ValidateConfigArgs};
     use quickwit_cli::index::{
-        CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, GarbageCollectIndexArgs,
-        IndexCliCommand, IngestDocsArgs, MergeOrDemuxArgs, SearchIndexArgs,
+        CancelMergeArgs, CreateIndexArgs, DeleteIndexArgs, DescribeIndexArgs, ExportFormat,
+        ExportIndexArgs, GarbageCollectIndexArgs, IndexCliCommand, IngestDocsArgs, ListIndexesArgs,
+        MergeOrDemuxArgs, ProgressFormat, SearchIndexArgs,
     };
     use quickwit_cli::split::{DescribeSplitArgs, ExtractSplitArgs, SplitCliCommand};
     use quickwit_common::uri::Uri;
+    use quickwit_indexing::models::DocstoreCompression;
+    use quickwit_proto::{SortOrder, SplitOrder};
 
     #[test]
     fn test_parse_create_args() -> anyhow::Result<()> {
@@ -292,6 +315,10 @@ mod tests {
                     overwrite: false,
                     data_dir: None,
                     clear_cache: true,
+                    throughput_window_secs: 5,
+                    dry_run: false,
+                    progress_format: ProgressFormat::Pretty,
+                    docstore_compression_opt: None,
                 })) if &index_id == "wikipedia"
                        && config_uri == Uri::try_new("file:///config.yaml").unwrap()
         ));
@@ -317,10 +344,198 @@ mod tests {
                     input_path_opt: None,
                     overwrite: true,
                     data_dir: None,
-                    clear_cache: false
+                    clear_cache: false,
+                    throughput_window_secs: 5,
+                    dry_run: false,
+                    progress_format: ProgressFormat::Pretty,
+                    docstore_compression_opt: None,
                 })) if &index_id == "wikipedia"
                         && config_uri == Uri::try_new("file:///config.yaml").unwrap()
         ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--throughput-window",
+            "1",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Ingest(IngestDocsArgs {
+                throughput_window_secs: 1,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--throughput-window",
+            "0",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`--throughput-window` must be greater than zero."));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--dry-run",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Ingest(IngestDocsArgs {
+                overwrite: false,
+                dry_run: true,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--dry-run",
+            "--overwrite",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`--dry-run` and `--overwrite` cannot be used together."));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--progress-format",
+            "json",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Ingest(IngestDocsArgs {
+                progress_format: ProgressFormat::Json,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--progress-format",
+            "yaml",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown progress format `yaml`."));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--docstore-compression",
+            "lz4",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Ingest(IngestDocsArgs {
+                docstore_compression_opt: Some(DocstoreCompression::Lz4),
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--docstore-compression-level",
+            "12",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Ingest(IngestDocsArgs {
+                docstore_compression_opt: Some(DocstoreCompression::Zstd {
+                    compression_level: Some(12)
+                }),
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--docstore-compression",
+            "none",
+            "--docstore-compression-level",
+            "12",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error.to_string().contains(
+            "`--docstore-compression-level` is only valid with `zstd` compression, not `none`."
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "ingest",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--docstore-compression",
+            "gzip",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown docstore compression `gzip`."));
         Ok(())
     }
 
@@ -345,12 +560,29 @@ mod tests {
                 query,
                 max_hits: 20,
                 start_offset: 0,
+                count: false,
                 search_fields: None,
+                fields: None,
+                snippet_fields: None,
+                snippet_max_fragments: None,
+                snippet_fragment_size: None,
                 start_timestamp: None,
                 end_timestamp: None,
+                required_tags,
                 aggregation: None,
+                explain_bytes_per_hit: false,
+                explain_split_selection_order: false,
+                explain_doc_mapper_mode: false,
+                explain_io_wait: false,
+                explain_query_depth: false,
+                explain_sort_field_coverage: false,
+                exclude_dynamic_fields: false,
+                explain_fetch_plan: false,
+                explain_schema_compat: false,
+                explain: false,
+                split_order: SplitOrder::Default,
                 ..
-            })) if &index_id == "wikipedia" && &query == "Barack Obama"
+            })) if &index_id == "wikipedia" && &query == "Barack Obama" && required_tags.is_empty()
         ));
 
         let app = build_cli().no_binary_name(true);
@@ -369,9 +601,27 @@ mod tests {
             "0",
             "--end-timestamp",
             "1",
+            "--require-tag",
+            "tenant:acme",
+            "--require-tag",
+            "lang:fr",
             "--search-fields",
             "title",
             "url",
+            "--fields",
+            "body",
+            "--explain-bytes-per-hit",
+            "--explain-split-selection-order",
+            "--explain-doc-mapper-mode",
+            "--explain-io-wait",
+            "--explain-query-depth",
+            "--explain-sort-field-coverage",
+            "--exclude-dynamic-fields",
+            "--explain-fetch-plan",
+            "--explain-schema-compat",
+            "--explain",
+            "--split-order",
+            "time-desc",
             "--config",
             "/config.yaml",
         ])?;
@@ -385,173 +635,773 @@ mod tests {
                 aggregation: None,
                 max_hits: 50,
                 start_offset: 100,
+                count: false,
                 search_fields: Some(field_names),
+                fields: Some(reconstruct_field_names),
+                snippet_fields: None,
+                snippet_max_fragments: None,
+                snippet_max_fragments_per_field,
+                snippet_fragment_size: None,
+                snippet_fragment_size_per_field,
                 start_timestamp: Some(0),
                 end_timestamp: Some(1),
+                required_tags,
+                explain_bytes_per_hit: true,
+                explain_split_selection_order: true,
+                explain_doc_mapper_mode: true,
+                explain_io_wait: true,
+                explain_query_depth: true,
+                explain_sort_field_coverage: true,
+                exclude_dynamic_fields: true,
+                explain_fetch_plan: true,
+                explain_schema_compat: true,
+                explain: true,
+                split_order: SplitOrder::TimeDesc,
+                sort_by_field: None,
+                sort_order: None,
                 config_uri: _config_uri,
                 data_dir: None,
             })) if &index_id == "wikipedia"
                   && query == "Barack Obama"
+                  && required_tags == vec!["tenant:acme".to_string(), "lang:fr".to_string()]
                   && field_names == vec!["title".to_string(), "url".to_string()]
+                  && reconstruct_field_names == vec!["body".to_string()]
+                  && snippet_max_fragments_per_field.is_empty()
+                  && snippet_fragment_size_per_field.is_empty()
         ));
         Ok(())
     }
 
     #[test]
-    fn test_parse_delete_args() -> anyhow::Result<()> {
+    fn test_parse_search_count_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "delete",
+            "search",
             "--index",
             "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--max-hits",
+            "50",
+            "--count",
+            "--start-timestamp",
+            "0",
             "--config",
             "/config.yaml",
         ])?;
         let command = CliCommand::parse_cli_args(&matches)?;
         assert!(matches!(
             command,
-            CliCommand::Index(IndexCliCommand::Delete(DeleteIndexArgs {
-                index_id,
-                dry_run: false,
+            CliCommand::Index(IndexCliCommand::Search(SearchIndexArgs {
+                max_hits: 0,
+                count: true,
+                start_timestamp: Some(0),
                 ..
-            })) if &index_id == "wikipedia"
+            }))
         ));
+        Ok(())
+    }
 
+    #[test]
+    fn test_parse_search_timestamp_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "delete",
+            "search",
             "--index",
             "wikipedia",
-            "--dry-run",
+            "--query",
+            "Barack Obama",
+            "--start-timestamp",
+            "2021-12-19T16:39:57Z",
+            "--end-timestamp",
+            "1639931998",
             "--config",
             "/config.yaml",
         ])?;
         let command = CliCommand::parse_cli_args(&matches)?;
         assert!(matches!(
             command,
-            CliCommand::Index(IndexCliCommand::Delete(DeleteIndexArgs {
-                index_id,
-                dry_run: true,
+            CliCommand::Index(IndexCliCommand::Search(SearchIndexArgs {
+                start_timestamp: Some(1639931997),
+                end_timestamp: Some(1639931998),
                 ..
-            })) if &index_id == "wikipedia"
+            }))
         ));
-        Ok(())
-    }
 
-    #[test]
-    fn test_parse_garbage_collect_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "gc",
+            "search",
             "--index",
             "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--start-timestamp",
+            "2021-12-19T18:39:57+02:00",
             "--config",
             "/config.yaml",
         ])?;
         let command = CliCommand::parse_cli_args(&matches)?;
         assert!(matches!(
             command,
-            CliCommand::Index(IndexCliCommand::GarbageCollect(GarbageCollectIndexArgs {
-                index_id,
-                grace_period,
-                dry_run: false,
+            CliCommand::Index(IndexCliCommand::Search(SearchIndexArgs {
+                start_timestamp: Some(1639931997),
+                end_timestamp: None,
                 ..
-            })) if &index_id == "wikipedia" && grace_period == Duration::from_secs(60 * 60)
+            }))
         ));
 
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "gc",
+            "search",
             "--index",
             "wikipedia",
-            "--grace-period",
-            "5m",
+            "--query",
+            "Barack Obama",
+            "--start-timestamp",
+            "2021-12-19T16:39:57",
             "--config",
             "/config.yaml",
-            "--dry-run",
         ])?;
-        let command = CliCommand::parse_cli_args(&matches)?;
-        let expected_config_uri = Uri::try_new("file:///config.yaml").unwrap();
-        assert!(matches!(
-            command,
-            CliCommand::Index(IndexCliCommand::GarbageCollect(GarbageCollectIndexArgs {
-                index_id,
-                grace_period,
-                config_uri,
-                dry_run: true,
-                data_dir: None,
-            })) if &index_id == "wikipedia" && grace_period == Duration::from_secs(5 * 60) && config_uri == expected_config_uri
-        ));
-        Ok(())
-    }
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error.to_string().contains("the datetime has no UTC offset"));
 
-    #[test]
-    fn test_parse_merge_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "merge",
+            "search",
             "--index",
             "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--end-timestamp",
+            "yesterday",
             "--config",
             "/config.yaml",
         ])?;
-        let command = CliCommand::parse_cli_args(&matches)?;
-        assert!(matches!(
-            command,
-            CliCommand::Index(IndexCliCommand::Merge(MergeOrDemuxArgs {
-                index_id,
-                ..
-            })) if &index_id == "wikipedia"
-        ));
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Failed to parse --end-timestamp option parameter `yesterday`."));
         Ok(())
     }
 
     #[test]
-    fn test_parse_demux_args() -> anyhow::Result<()> {
+    fn test_parse_search_snippet_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "demux",
+            "search",
             "--index",
             "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--snippet-fields",
+            "title",
+            "body",
+            "--snippet-max-fragments",
+            "2",
+            "--snippet-max-fragments",
+            "title=1",
+            "--snippet-fragment-size",
+            "title=50",
             "--config",
-            "quickwit.yaml",
+            "/config.yaml",
         ])?;
         let command = CliCommand::parse_cli_args(&matches)?;
         assert!(matches!(
             command,
-            CliCommand::Index(IndexCliCommand::Demux(MergeOrDemuxArgs {
-                index_id,
+            CliCommand::Index(IndexCliCommand::Search(SearchIndexArgs {
+                snippet_fields: Some(snippet_field_names),
+                snippet_max_fragments: Some(2),
+                snippet_max_fragments_per_field,
+                snippet_fragment_size: None,
+                snippet_fragment_size_per_field,
                 ..
-            })) if &index_id == "wikipedia"
+            })) if snippet_field_names == vec!["title".to_string(), "body".to_string()]
+                  && snippet_max_fragments_per_field == HashMap::from([("title".to_string(), 1)])
+                  && snippet_fragment_size_per_field == HashMap::from([("title".to_string(), 50)])
         ));
-        Ok(())
-    }
 
-    #[test]
-    fn test_parse_describe_index_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
         let matches = app.try_get_matches_from(vec![
             "index",
-            "describe",
+            "search",
             "--index",
             "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--snippet-fragment-size",
+            "50",
             "--config",
-            "quickwit.yaml",
+            "/config.yaml",
         ])?;
-        let command = CliCommand::parse_cli_args(&matches)?;
-        assert!(matches!(
-            command,
-            CliCommand::Index(IndexCliCommand::Describe(DescribeIndexArgs {
-                index_id,
-                ..
-            })) if &index_id == "wikipedia"
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error.to_string().contains(
+            "`--snippet-max-fragments` and `--snippet-fragment-size` require `--snippet-fields`."
         ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "search",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--snippet-fields",
+            "title",
+            "--snippet-max-fragments",
+            "title:1",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Failed to parse `--snippet-max-fragments` value `title:1`"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_search_sort_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "search",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--sort-by-field",
+            "timestamp",
+            "--sort-order",
+            "asc",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Search(SearchIndexArgs {
+                sort_by_field: Some(sort_by_field),
+                sort_order: Some(SortOrder::Asc),
+                ..
+            })) if &sort_by_field == "timestamp"
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "search",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--sort-by-field",
+            "timestamp",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`--sort-by-field` and `--sort-order` must be supplied together."));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "search",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--sort-by-field",
+            "timestamp",
+            "--sort-order",
+            "up",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error.to_string().contains("Unknown sort order `up`."));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_delete_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "delete",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Delete(DeleteIndexArgs {
+                index_id,
+                dry_run: false,
+                ..
+            })) if &index_id == "wikipedia"
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "delete",
+            "--index",
+            "wikipedia",
+            "--dry-run",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Delete(DeleteIndexArgs {
+                index_id,
+                dry_run: true,
+                ..
+            })) if &index_id == "wikipedia"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_export_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "export",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--output-dir",
+            "/export",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_command = CliCommand::Index(IndexCliCommand::Export(ExportIndexArgs {
+            index_id: "wikipedia".to_string(),
+            query: "Barack Obama".to_string(),
+            start_timestamp: None,
+            end_timestamp: None,
+            output_dir: PathBuf::from("/export"),
+            format: ExportFormat::Json,
+            max_file_num_bytes: 100_000_000,
+            config_uri: Uri::try_new("file:///config.yaml").unwrap(),
+            data_dir: None,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "export",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--start-timestamp",
+            "2021-12-19T16:39:57Z",
+            "--end-timestamp",
+            "1639931998",
+            "--output-dir",
+            "/export",
+            "--format",
+            "csv",
+            "--max-file-size",
+            "1KB",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Export(ExportIndexArgs {
+                start_timestamp: Some(1639931997),
+                end_timestamp: Some(1639931998),
+                format: ExportFormat::Csv,
+                max_file_num_bytes: 1_000,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "export",
+            "--index",
+            "wikipedia",
+            "--query",
+            "Barack Obama",
+            "--output-dir",
+            "/export",
+            "--format",
+            "parquet",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Unknown export format `parquet`."));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_garbage_collect_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "gc",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::GarbageCollect(GarbageCollectIndexArgs {
+                index_id,
+                grace_period,
+                delete_orphans: false,
+                dry_run: false,
+                ..
+            })) if &index_id == "wikipedia" && grace_period == Duration::from_secs(60 * 60)
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "gc",
+            "--index",
+            "wikipedia",
+            "--grace-period",
+            "5m",
+            "--config",
+            "/config.yaml",
+            "--delete-orphans",
+            "--dry-run",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_config_uri = Uri::try_new("file:///config.yaml").unwrap();
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::GarbageCollect(GarbageCollectIndexArgs {
+                index_id,
+                grace_period,
+                config_uri,
+                delete_orphans: true,
+                dry_run: true,
+                force: false,
+                assume_yes: false,
+                data_dir: None,
+            })) if &index_id == "wikipedia" && grace_period == Duration::from_secs(5 * 60) && config_uri == expected_config_uri
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "gc",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--force",
+            "--yes",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::GarbageCollect(GarbageCollectIndexArgs {
+                grace_period,
+                force: true,
+                assume_yes: true,
+                ..
+            })) if grace_period == Duration::ZERO
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "gc",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--force",
+            "--grace-period",
+            "5m",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`--force` and `--grace-period` cannot be used together."));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_merge_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "merge",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Merge(MergeOrDemuxArgs {
+                index_id,
+                merge_factor_opt: None,
+                max_merge_factor_opt: None,
+                max_merge_docs_opt: None,
+                ..
+            })) if &index_id == "wikipedia"
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "merge",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--merge-factor",
+            "4",
+            "--max-merge-factor",
+            "20",
+            "--max-merge-docs",
+            "50000000",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Merge(MergeOrDemuxArgs {
+                merge_factor_opt: Some(4),
+                max_merge_factor_opt: Some(20),
+                max_merge_docs_opt: Some(50_000_000),
+                ..
+            }))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_cancel_merge_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "merge-cancel",
+            "--index",
+            "wikipedia",
+            "--config",
+            "/config.yaml",
+            "--data-dir",
+            "/data",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_config_uri = Uri::try_new("file:///config.yaml").unwrap();
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::CancelMerge(CancelMergeArgs {
+                index_id,
+                config_uri,
+                data_dir: Some(data_dir),
+            })) if &index_id == "wikipedia" && config_uri == expected_config_uri && data_dir == PathBuf::from("/data")
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_demux_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "demux",
+            "--index",
+            "wikipedia",
+            "--config",
+            "quickwit.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Demux(MergeOrDemuxArgs {
+                index_id,
+                ..
+            })) if &index_id == "wikipedia"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_list_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "list",
+            "--config",
+            "/config.yaml",
+            "--filter",
+            "logs-*",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::List(ListIndexesArgs {
+                filter: Some(filter),
+                ..
+            })) if filter.matches("logs-app") && !filter.matches("traces-app")
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "list",
+            "--config",
+            "/config.yaml",
+            "--filter",
+            "logs-[",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Failed to parse index ID filter `logs-[`."));
+
+        let app = build_cli().no_binary_name(true);
+        let matches =
+            app.try_get_matches_from(vec!["index", "list", "--config", "/config.yaml"])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::List(ListIndexesArgs {
+                limit: None,
+                offset: 0,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "list",
+            "--config",
+            "/config.yaml",
+            "--limit",
+            "50",
+            "--offset",
+            "100",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::List(ListIndexesArgs {
+                limit: Some(50),
+                offset: 100,
+                ..
+            }))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_describe_index_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "index",
+            "describe",
+            "--index",
+            "wikipedia",
+            "--config",
+            "quickwit.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Index(IndexCliCommand::Describe(DescribeIndexArgs {
+                index_id,
+                ..
+            })) if &index_id == "wikipedia"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_check_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec!["check", "--config", "/config.yaml"])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_command = CliCommand::Check(CheckCliCommand {
+            config_uri: Uri::try_new("file:///config.yaml").unwrap(),
+            data_dir: None,
+            index_id: None,
+        });
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "check",
+            "--config",
+            "/config.yaml",
+            "--index",
+            "wikipedia",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_command = CliCommand::Check(CheckCliCommand {
+            config_uri: Uri::try_new("file:///config.yaml").unwrap(),
+            data_dir: None,
+            index_id: Some("wikipedia".to_string()),
+        });
+        assert_eq!(command, expected_command);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_config_validate_args() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches =
+            app.try_get_matches_from(vec!["config", "validate", "--config", "/config.yaml"])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_command = CliCommand::Config(ConfigCliCommand::Validate(ValidateConfigArgs {
+            config_uri: Uri::try_new("file:///config.yaml").unwrap(),
+            data_dir: None,
+            strict: false,
+        }));
+        assert_eq!(command, expected_command);
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "config",
+            "validate",
+            "--config",
+            "/config.yaml",
+            "--data-dir",
+            "/qwdata",
+            "--strict",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        let expected_command = CliCommand::Config(ConfigCliCommand::Validate(ValidateConfigArgs {
+            config_uri: Uri::try_new("file:///config.yaml").unwrap(),
+            data_dir: Some(PathBuf::from("/qwdata")),
+            strict: true,
+        }));
+        assert_eq!(command, expected_command);
         Ok(())
     }
 
@@ -603,8 +1453,9 @@ mod tests {
                 index_id,
                 split_id,
                 target_dir,
+                verify_only: false,
                 ..
-            })) if &index_id == "wikipedia" && &split_id == "ABC" && target_dir == PathBuf::from("datadir")
+            })) if &index_id == "wikipedia" && &split_id == "ABC" && target_dir == Some(PathBuf::from("datadir"))
         ));
         Ok(())
     }
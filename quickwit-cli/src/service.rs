@@ -29,7 +29,7 @@ use quickwit_serve::serve_quickwit;
 use quickwit_telemetry::payload::TelemetryEvent;
 use tracing::debug;
 
-use crate::load_quickwit_config;
+use crate::{load_quickwit_config, parse_config_uri};
 
 pub fn build_run_command<'a>() -> Command<'a> {
     Command::new("run")
@@ -65,10 +65,7 @@ pub struct RunCliCommand {
 
 impl RunCliCommand {
     pub fn parse_cli_args(matches: &ArgMatches) -> anyhow::Result<Self> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir_path = matches.value_of("data-dir").map(PathBuf::from);
         let services: HashSet<QuickwitService> =
             if let Some(service_str) = matches.value_of("service") {
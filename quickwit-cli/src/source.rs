@@ -29,7 +29,8 @@ use quickwit_storage::load_file;
 use serde_json::Value;
 use tabled::{Table, Tabled};
 
-use crate::{load_quickwit_config, make_table};
+use crate::error::{CliError, CliErrorKind};
+use crate::{load_quickwit_config, make_table, parse_config_uri};
 
 pub fn build_source_command<'a>() -> Command<'a> {
     Command::new("source")
@@ -42,6 +43,14 @@ pub fn build_source_command<'a>() -> Command<'a> {
                     arg!(--"source-config" <SOURCE_CONFIG> "Path to source config file. Please, refer to the documentation for more details."),
                 ])
             )
+        .subcommand(
+            Command::new("update")
+                .about("Replaces the parameters of a source, keeping its checkpoint.")
+                .args(&[
+                    arg!(--index <INDEX_ID> "ID of the target index"),
+                    arg!(--"source-config" <SOURCE_CONFIG> "Path to the new source config file. The source ID and type must match the ones of the existing source."),
+                ])
+            )
         .subcommand(
             Command::new("delete")
                 .about("Deletes a source from an index.")
@@ -75,6 +84,13 @@ pub struct CreateSourceArgs {
     pub source_config_uri: Uri,
 }
 
+#[derive(Debug, PartialEq)]
+pub struct UpdateSourceArgs {
+    pub config_uri: Uri,
+    pub index_id: String,
+    pub source_config_uri: Uri,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct DeleteSourceArgs {
     pub config_uri: Uri,
@@ -98,6 +114,7 @@ pub struct ListSourcesArgs {
 #[derive(Debug, PartialEq)]
 pub enum SourceCliCommand {
     CreateSource(CreateSourceArgs),
+    UpdateSource(UpdateSourceArgs),
     DeleteSource(DeleteSourceArgs),
     DescribeSource(DescribeSourceArgs),
     ListSources(ListSourcesArgs),
@@ -107,6 +124,7 @@ impl SourceCliCommand {
     pub async fn execute(self) -> anyhow::Result<()> {
         match self {
             Self::CreateSource(args) => create_source_cli(args).await,
+            Self::UpdateSource(args) => update_source_cli(args).await,
             Self::DeleteSource(args) => delete_source_cli(args).await,
             Self::DescribeSource(args) => describe_source_cli(args).await,
             Self::ListSources(args) => list_sources_cli(args).await,
@@ -119,6 +137,7 @@ impl SourceCliCommand {
             .ok_or_else(|| anyhow::anyhow!("Failed to parse source subcommand arguments."))?;
         match subcommand {
             "create" => Self::parse_create_args(submatches).map(Self::CreateSource),
+            "update" => Self::parse_update_args(submatches).map(Self::UpdateSource),
             "delete" => Self::parse_delete_args(submatches).map(Self::DeleteSource),
             "describe" => Self::parse_describe_args(submatches).map(Self::DescribeSource),
             "list" => Self::parse_list_args(submatches).map(Self::ListSources),
@@ -127,10 +146,7 @@ impl SourceCliCommand {
     }
 
     fn parse_create_args(matches: &ArgMatches) -> anyhow::Result<CreateSourceArgs> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let index_id = matches
             .value_of("index")
             .map(String::from)
@@ -146,11 +162,25 @@ impl SourceCliCommand {
         })
     }
 
-    fn parse_delete_args(matches: &ArgMatches) -> anyhow::Result<DeleteSourceArgs> {
-        let config_uri = matches
-            .value_of("config")
+    fn parse_update_args(matches: &ArgMatches) -> anyhow::Result<UpdateSourceArgs> {
+        let config_uri = parse_config_uri(matches)?;
+        let index_id = matches
+            .value_of("index")
+            .map(String::from)
+            .expect("`index` is a required arg.");
+        let source_config_uri = matches
+            .value_of("source-config")
             .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+            .expect("`source-config` is a required arg.")?;
+        Ok(UpdateSourceArgs {
+            config_uri,
+            index_id,
+            source_config_uri,
+        })
+    }
+
+    fn parse_delete_args(matches: &ArgMatches) -> anyhow::Result<DeleteSourceArgs> {
+        let config_uri = parse_config_uri(matches)?;
         let index_id = matches
             .value_of("index")
             .map(String::from)
@@ -167,10 +197,7 @@ impl SourceCliCommand {
     }
 
     fn parse_describe_args(matches: &ArgMatches) -> anyhow::Result<DescribeSourceArgs> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let index_id = matches
             .value_of("index")
             .map(String::from)
@@ -187,10 +214,7 @@ impl SourceCliCommand {
     }
 
     fn parse_list_args(matches: &ArgMatches) -> anyhow::Result<ListSourcesArgs> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let index_id = matches
             .value_of("index")
             .map(String::from)
@@ -207,11 +231,16 @@ async fn create_source_cli(args: CreateSourceArgs) -> anyhow::Result<()> {
     let metastore = quickwit_metastore_uri_resolver()
         .resolve(&qw_config.metastore_uri)
         .await?;
-    let source_config_content = load_file(&args.source_config_uri).await?;
-    let source =
-        SourceConfig::load(&args.source_config_uri, source_config_content.as_slice()).await?;
+    let source_config_content = load_file(&args.source_config_uri)
+        .await
+        .map_err(CliError::config)?;
+    let source = SourceConfig::load(&args.source_config_uri, source_config_content.as_slice())
+        .await
+        .map_err(CliError::config)?;
     let source_id = source.source_id.clone();
-    check_source_connectivity(&source).await?;
+    check_source_connectivity(&source)
+        .await
+        .map_err(|error| CliError::new(CliErrorKind::Connectivity, error))?;
 
     metastore.add_source(&args.index_id, source).await?;
     println!(
@@ -221,6 +250,30 @@ async fn create_source_cli(args: CreateSourceArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn update_source_cli(args: UpdateSourceArgs) -> anyhow::Result<()> {
+    let qw_config = load_quickwit_config(&args.config_uri, None).await?;
+    let metastore = quickwit_metastore_uri_resolver()
+        .resolve(&qw_config.metastore_uri)
+        .await?;
+    let source_config_content = load_file(&args.source_config_uri)
+        .await
+        .map_err(CliError::config)?;
+    let source = SourceConfig::load(&args.source_config_uri, source_config_content.as_slice())
+        .await
+        .map_err(CliError::config)?;
+    let source_id = source.source_id.clone();
+    check_source_connectivity(&source)
+        .await
+        .map_err(|error| CliError::new(CliErrorKind::Connectivity, error))?;
+
+    metastore.update_source(&args.index_id, source).await?;
+    println!(
+        "Source `{}` successfully updated for index `{}`.",
+        source_id, args.index_id
+    );
+    Ok(())
+}
+
 async fn delete_source_cli(args: DeleteSourceArgs) -> anyhow::Result<()> {
     let config = load_quickwit_config(&args.config_uri, None).await?;
     let metastore = quickwit_metastore_uri_resolver()
@@ -425,6 +478,31 @@ mod tests {
         assert_eq!(command, expected_command);
     }
 
+    #[test]
+    fn test_parse_update_source_args() {
+        let app = build_cli().no_binary_name(true);
+        let matches = app
+            .try_get_matches_from(vec![
+                "source",
+                "update",
+                "--index",
+                "hdfs-logs",
+                "--source-config",
+                "/source-conf.yaml",
+                "--config",
+                "/conf.yaml",
+            ])
+            .unwrap();
+        let command = CliCommand::parse_cli_args(&matches).unwrap();
+        let expected_command =
+            CliCommand::Source(SourceCliCommand::UpdateSource(UpdateSourceArgs {
+                config_uri: Uri::try_new("file:///conf.yaml").unwrap(),
+                index_id: "hdfs-logs".to_string(),
+                source_config_uri: Uri::try_new("file:///source-conf.yaml").unwrap(),
+            }));
+        assert_eq!(command, expected_command);
+    }
+
     #[test]
     fn test_parse_delete_source_args() {
         let app = build_cli().no_binary_name(true);
@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context};
 use clap::{arg, Arg, ArgMatches, Command};
@@ -28,13 +28,13 @@ use quickwit_directories::{
     get_hotcache_from_split, read_split_footer, BundleDirectory, HotDirectory,
 };
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
-use quickwit_metastore::{quickwit_metastore_uri_resolver, Split, SplitState};
-use quickwit_storage::{quickwit_storage_uri_resolver, BundleStorage, Storage};
+use quickwit_metastore::{quickwit_metastore_uri_resolver, Split, SplitMetadata, SplitState};
+use quickwit_storage::{quickwit_storage_uri_resolver, BundleStorage, OwnedBytes, Storage};
 use tabled::{Table, Tabled};
 use time::{format_description, Date, OffsetDateTime, PrimitiveDateTime};
 use tracing::debug;
 
-use crate::{load_quickwit_config, make_table};
+use crate::{load_quickwit_config, make_table, parse_config_uri};
 
 pub fn build_split_command<'a>() -> Command<'a> {
     Command::new("split")
@@ -46,7 +46,8 @@ pub fn build_split_command<'a>() -> Command<'a> {
                     arg!(--index <INDEX> "Target index ID")
                         .display_order(1)
                         .required(true),
-                    arg!(--states <SPLIT_STATES> "Selects the splits whose states are included in this comma-separated list of states. Possible values are `staged`, `published`, and `marked`.")
+                    arg!(--states <SPLIT_STATES> "Selects the splits whose states are included in this comma-separated list of states. Possible values are `staged`, `published`, and `marked-for-deletion` (or `marked`).")
+                        .alias("state")
                         .display_order(2)
                         .required(false)
                         .use_value_delimiter(true),
@@ -59,13 +60,21 @@ pub fn build_split_command<'a>() -> Command<'a> {
                     arg!(--"end-date" <END_DATE> "Selects the splits that contain documents before this date (time-series indexes only).")
                         .display_order(5)
                         .required(false),
-                    arg!(--tags <TAGS> "Selects the splits whose tags are all included in this comma-separated list of tags.")
+                    arg!(--"start-timestamp" <START_TIMESTAMP> "Selects the splits that contain documents after this Unix timestamp in seconds (time-series indexes only).")
                         .display_order(6)
                         .required(false)
+                        .conflicts_with("start-date"),
+                    arg!(--"end-timestamp" <END_TIMESTAMP> "Selects the splits that contain documents before this Unix timestamp in seconds (time-series indexes only).")
+                        .display_order(7)
+                        .required(false)
+                        .conflicts_with("end-date"),
+                    arg!(--tags <TAGS> "Selects the splits whose tags are all included in this comma-separated list of tags.")
+                        .display_order(8)
+                        .required(false)
                         .use_value_delimiter(true),
                     Arg::new("mark-for-deletion")
                         .alias("mark")
-                        .display_order(7)
+                        .display_order(9)
                         .long("mark-for-deletion")
                         .help("Marks the selected splits for deletion.")
                 ])
@@ -76,7 +85,10 @@ pub fn build_split_command<'a>() -> Command<'a> {
                 .args(&[
                     arg!(--index <INDEX> "ID of the target index"),
                     arg!(--split <SPLIT> "ID of the target split"),
-                    arg!(--"target-dir" <TARGET_DIR> "Directory to extract the split to."),
+                    arg!(--"target-dir" <TARGET_DIR> "Directory to extract the split to.")
+                        .required_unless_present("verify-only")
+                        .conflicts_with("verify-only"),
+                    arg!(--"verify-only" "Downloads and checks the integrity of the split without extracting it."),
                     arg!(--"data-dir" <DATA_DIR> "Where data is persisted. Override data-dir defined in config file, default is `./qwdata`.")
                         .env("QW_DATA_DIR")
                         .required(false),
@@ -145,7 +157,8 @@ pub struct ExtractSplitArgs {
     pub data_dir: Option<PathBuf>,
     pub index_id: String,
     pub split_id: String,
-    pub target_dir: PathBuf,
+    pub target_dir: Option<PathBuf>,
+    pub verify_only: bool,
 }
 
 #[derive(Debug, PartialEq)]
@@ -171,10 +184,7 @@ impl SplitCliCommand {
     }
 
     fn parse_list_args(matches: &ArgMatches) -> anyhow::Result<Self> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let index_id = matches
             .value_of("index")
             .map(String::from)
@@ -192,14 +202,22 @@ impl SplitCliCommand {
             .value_of("create-date")
             .map(|arg| parse_date(arg, "create"))
             .transpose()?;
-        let start_date = matches
-            .value_of("start-date")
-            .map(|arg| parse_date(arg, "start"))
-            .transpose()?;
-        let end_date = matches
-            .value_of("end-date")
-            .map(|arg| parse_date(arg, "end"))
-            .transpose()?;
+        let start_date = if let Some(arg) = matches.value_of("start-timestamp") {
+            Some(parse_timestamp(arg, "start")?)
+        } else {
+            matches
+                .value_of("start-date")
+                .map(|arg| parse_date(arg, "start"))
+                .transpose()?
+        };
+        let end_date = if let Some(arg) = matches.value_of("end-timestamp") {
+            Some(parse_timestamp(arg, "end")?)
+        } else {
+            matches
+                .value_of("end-date")
+                .map(|arg| parse_date(arg, "end"))
+                .transpose()?
+        };
         let tags = matches.values_of("tags").map(|values| {
             TagFilterAst::And(
                 values
@@ -226,10 +244,7 @@ impl SplitCliCommand {
     }
 
     fn parse_mark_for_deletion_args(matches: &ArgMatches) -> anyhow::Result<Self> {
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let index_id = matches
             .value_of("index")
             .map(String::from)
@@ -257,10 +272,7 @@ impl SplitCliCommand {
             .value_of("split")
             .map(String::from)
             .expect("`split` is a required arg.");
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
+        let config_uri = parse_config_uri(matches)?;
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         let verbose = matches.is_present("verbose");
 
@@ -282,20 +294,16 @@ impl SplitCliCommand {
             .value_of("split")
             .map(String::from)
             .expect("`split` is a required arg.");
-        let config_uri = matches
-            .value_of("config")
-            .map(Uri::try_new)
-            .expect("`config` is a required arg.")?;
-        let target_dir = matches
-            .value_of("target-dir")
-            .map(PathBuf::from)
-            .expect("`target-dir` is a required arg.");
+        let config_uri = parse_config_uri(matches)?;
+        let target_dir = matches.value_of("target-dir").map(PathBuf::from);
+        let verify_only = matches.is_present("verify-only");
         let data_dir = matches.value_of("data-dir").map(PathBuf::from);
         Ok(Self::Extract(ExtractSplitArgs {
             config_uri,
             index_id,
             split_id,
             target_dir,
+            verify_only,
             data_dir,
         }))
     }
@@ -318,15 +326,46 @@ async fn list_split_cli(args: ListSplitArgs) -> anyhow::Result<()> {
     let metastore = metastore_uri_resolver
         .resolve(&quickwit_config.metastore_uri)
         .await?;
-    let splits = metastore.list_all_splits(&args.index_id).await?;
-
+    // The state, time range, and tags filters are pushed down to the metastore. The metastore
+    // time range is half-open whereas `--end-date` and `--end-timestamp` are inclusive.
+    let split_states = args.split_states.unwrap_or_else(|| {
+        vec![
+            SplitState::Staged,
+            SplitState::Published,
+            SplitState::MarkedForDeletion,
+        ]
+    });
+    let time_range_opt = match (args.start_date, args.end_date) {
+        (None, None) => None,
+        (start_date_opt, end_date_opt) => {
+            let start_ts = start_date_opt
+                .map(OffsetDateTime::unix_timestamp)
+                .unwrap_or(i64::MIN);
+            let end_ts = end_date_opt
+                .map(|end_date| end_date.unix_timestamp().saturating_add(1))
+                .unwrap_or(i64::MAX);
+            Some(start_ts..end_ts)
+        }
+    };
+    let mut splits = Vec::new();
+    for split_state in split_states.into_iter().unique_by(SplitState::as_str) {
+        let splits_in_state = metastore
+            .list_splits(
+                &args.index_id,
+                split_state,
+                time_range_opt.clone(),
+                args.tags.clone(),
+            )
+            .await?;
+        splits.extend(splits_in_state);
+    }
     let filtered_splits = filter_splits(
         splits,
-        args.split_states,
-        args.start_date.map(OffsetDateTime::unix_timestamp),
-        args.end_date.map(OffsetDateTime::unix_timestamp),
+        None,
         args.create_date.map(OffsetDateTime::unix_timestamp),
-        args.tags,
+        None,
+        None,
+        None,
     );
     let table = make_split_table(&filtered_splits, "Splits");
     println!("{table}");
@@ -384,7 +423,10 @@ async fn describe_split_cli(args: DescribeSplitArgs) -> anyhow::Result<()> {
         .resolve(&quickwit_config.metastore_uri)
         .await?;
     let index_metadata = metastore.index_metadata(&args.index_id).await?;
-    let index_storage = storage_uri_resolver.resolve(&index_metadata.index_uri)?;
+    let index_storage = storage_uri_resolver.resolve_with_storage_config(
+        &index_metadata.index_uri,
+        index_metadata.storage_config.as_ref(),
+    )?;
 
     let split_metadata = metastore
         .list_all_splits(&args.index_id)
@@ -447,17 +489,45 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
         .resolve(&quickwit_config.metastore_uri)
         .await?;
     let index_metadata = metastore.index_metadata(&args.index_id).await?;
-    let index_storage = storage_uri_resolver.resolve(&index_metadata.index_uri)?;
+    let index_storage = storage_uri_resolver.resolve_with_storage_config(
+        &index_metadata.index_uri,
+        index_metadata.storage_config.as_ref(),
+    )?;
+    let split = metastore
+        .list_all_splits(&args.index_id)
+        .await?
+        .into_iter()
+        .find(|split| split.split_id() == args.split_id)
+        .with_context(|| {
+            format!(
+                "Could not find split metadata in metastore {}",
+                args.split_id
+            )
+        })?;
     let split_file = PathBuf::from(format!("{}.split", args.split_id));
+    // The split is downloaded in memory, so nothing is left behind on disk in `--verify-only` mode.
     let split_data = index_storage.get_all(split_file.as_path()).await?;
+    verify_split_data(&split.split_metadata, &split_data)?;
+
+    let target_dir = match args.target_dir {
+        Some(target_dir) if !args.verify_only => target_dir,
+        _ => {
+            println!(
+                "Split `{}` is intact ({} bytes).",
+                args.split_id,
+                split_data.len()
+            );
+            return Ok(());
+        }
+    };
     let (_hotcache_bytes, bundle_storage) = BundleStorage::open_from_split_data_with_owned_bytes(
         index_storage,
         split_file,
         split_data,
     )?;
-    std::fs::create_dir_all(&args.target_dir)?;
+    std::fs::create_dir_all(&target_dir)?;
     for path in bundle_storage.iter_files() {
-        let mut out_path = args.target_dir.to_owned();
+        let mut out_path = target_dir.to_owned();
         out_path.push(path);
         println!("Copying {:?}", out_path);
         bundle_storage.copy_to_file(path, &out_path).await?;
@@ -466,6 +536,55 @@ async fn extract_split_cli(args: ExtractSplitArgs) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Checks that a split file downloaded from the storage is consistent with the footer offsets
+/// recorded in the metastore and that its bundle footer can be read back.
+fn verify_split_data(
+    split_metadata: &SplitMetadata,
+    split_data: &OwnedBytes,
+) -> anyhow::Result<()> {
+    let split_id = split_metadata.split_id();
+    let footer_offsets = &split_metadata.footer_offsets;
+    let split_num_bytes = split_data.len() as u64;
+    if split_num_bytes != footer_offsets.end {
+        bail!(
+            "Split `{}` is corrupted: the split file is {} bytes long but the metastore records a \
+             footer ending at byte {}.",
+            split_id,
+            split_num_bytes,
+            footer_offsets.end
+        );
+    }
+    if footer_offsets.start >= footer_offsets.end {
+        bail!(
+            "Split `{}` is corrupted: the metastore records invalid footer offsets `{:?}`.",
+            split_id,
+            footer_offsets
+        );
+    }
+    let split_footer = split_data.slice(footer_offsets.start as usize..footer_offsets.end as usize);
+    let file_sizes = BundleDirectory::get_stats_split(split_footer).with_context(|| {
+        format!(
+            "Split `{}` is corrupted: failed to read its footer.",
+            split_id
+        )
+    })?;
+    let bundled_num_bytes: u64 = file_sizes
+        .iter()
+        .filter(|(path, _)| path != Path::new("hotcache"))
+        .map(|(_, num_bytes)| num_bytes)
+        .sum();
+    if bundled_num_bytes > footer_offsets.start {
+        bail!(
+            "Split `{}` is corrupted: its footer references {} bytes of files but the footer \
+             starts at byte {}.",
+            split_id,
+            bundled_num_bytes,
+            footer_offsets.start
+        );
+    }
+    Ok(())
+}
+
 fn filter_splits(
     splits: Vec<Split>,
     split_states_opt: Option<Vec<SplitState>>,
@@ -577,14 +696,28 @@ fn parse_date(date_arg: &str, option_name: &str) -> anyhow::Result<OffsetDateTim
     );
 }
 
+fn parse_timestamp(timestamp_arg: &str, option_name: &str) -> anyhow::Result<OffsetDateTime> {
+    timestamp_arg
+        .parse::<i64>()
+        .ok()
+        .and_then(|timestamp| OffsetDateTime::from_unix_timestamp(timestamp).ok())
+        .with_context(|| {
+            format!(
+                "Failed to parse --{}-timestamp option parameter `{}`. Expected a Unix timestamp \
+                 in seconds.",
+                option_name, timestamp_arg
+            )
+        })
+}
+
 fn parse_split_state(split_state_arg: &str) -> anyhow::Result<SplitState> {
     let split_state = match split_state_arg.to_lowercase().as_ref() {
         "staged" => SplitState::Staged,
         "published" => SplitState::Published,
-        "marked" => SplitState::MarkedForDeletion,
+        "marked" | "marked-for-deletion" => SplitState::MarkedForDeletion,
         _ => bail!(format!(
             "Failed to parse split state `{}`. Possible values are `staged`, `published`, and \
-             `marked`.",
+             `marked-for-deletion`.",
             split_state_arg
         )),
     };
@@ -681,6 +814,74 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_list_split_args_with_timestamps() -> anyhow::Result<()> {
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "split",
+            "list",
+            "--config",
+            "config.yaml",
+            "--index",
+            "hdfs",
+            "--state",
+            "marked-for-deletion",
+            "--start-timestamp",
+            "1608768000",
+            "--end-timestamp",
+            "1608900120",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+
+        let expected_split_states = Some(vec![SplitState::MarkedForDeletion]);
+        let expected_start_date = Some(datetime!(2020-12-24 00:00 UTC));
+        let expected_end_date = Some(datetime!(2020-12-25 12:42 UTC));
+        assert!(matches!(
+            command,
+            CliCommand::Split(SplitCliCommand::List(ListSplitArgs {
+                split_states,
+                create_date: None,
+                start_date,
+                end_date,
+                tags: None,
+                mark_for_deletion: false,
+                ..
+            })) if split_states == expected_split_states
+                   && start_date == expected_start_date
+                   && end_date == expected_end_date
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "split",
+            "list",
+            "--config",
+            "config.yaml",
+            "--index",
+            "hdfs",
+            "--start-timestamp",
+            "yesterday",
+        ])?;
+        let error = CliCommand::parse_cli_args(&matches).unwrap_err();
+        assert!(error.to_string().contains("--start-timestamp"));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "split",
+            "list",
+            "--config",
+            "config.yaml",
+            "--index",
+            "hdfs",
+            "--start-date",
+            "2020-12-24",
+            "--start-timestamp",
+            "1608768000",
+        ]);
+        assert!(matches.is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_parse_split_mark_for_deletion_args() -> anyhow::Result<()> {
         let app = build_cli().no_binary_name(true);
@@ -756,12 +957,71 @@ mod tests {
                 index_id,
                 split_id,
                 target_dir,
+                verify_only: false,
                 ..
-            })) if &index_id == "wikipedia" && &split_id == "ABC" && target_dir == PathBuf::from("/datadir")
+            })) if &index_id == "wikipedia" && &split_id == "ABC" && target_dir == Some(PathBuf::from("/datadir"))
         ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "split",
+            "extract",
+            "--index",
+            "wikipedia",
+            "--split",
+            "ABC",
+            "--verify-only",
+            "--config",
+            "file:///config.yaml",
+        ])?;
+        let command = CliCommand::parse_cli_args(&matches)?;
+        assert!(matches!(
+            command,
+            CliCommand::Split(SplitCliCommand::Extract(ExtractSplitArgs {
+                target_dir: None,
+                verify_only: true,
+                ..
+            }))
+        ));
+
+        let app = build_cli().no_binary_name(true);
+        let matches = app.try_get_matches_from(vec![
+            "split",
+            "extract",
+            "--index",
+            "wikipedia",
+            "--split",
+            "ABC",
+            "--config",
+            "file:///config.yaml",
+        ]);
+        assert!(matches.is_err());
         Ok(())
     }
 
+    #[test]
+    fn test_verify_split_data() {
+        let split_data = OwnedBytes::new(vec![0u8; 30]);
+        let mut split_metadata = SplitMetadata {
+            split_id: "split".to_string(),
+            footer_offsets: 10..20,
+            ..Default::default()
+        };
+        let error = verify_split_data(&split_metadata, &split_data).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Split `split` is corrupted: the split file is 30 bytes long but the metastore records \
+             a footer ending at byte 20."
+        );
+        split_metadata.footer_offsets = 30..30;
+        let error = verify_split_data(&split_metadata, &split_data).unwrap_err();
+        assert!(error.to_string().contains("invalid footer offsets"));
+
+        split_metadata.footer_offsets = 10..30;
+        let error = verify_split_data(&split_metadata, &split_data).unwrap_err();
+        assert!(error.to_string().contains("failed to read its footer"));
+    }
+
     fn make_split(
         split_id: &str,
         split_state: SplitState,
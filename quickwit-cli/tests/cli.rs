@@ -34,6 +34,7 @@ use quickwit_config::CLI_INGEST_SOURCE_ID;
 use quickwit_core::get_cache_directory_path;
 use quickwit_indexing::actors::INDEXING_DIR_NAME;
 use quickwit_metastore::{quickwit_metastore_uri_resolver, Metastore};
+use quickwit_proto::SplitOrder;
 use serde_json::{json, Number, Value};
 use serial_test::serial;
 use tokio::time::{sleep, Duration};
@@ -143,6 +144,87 @@ async fn test_cmd_create() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_cmd_config_validate() -> Result<()> {
+    let index_id = append_random_suffix("test-config-validate-cmd");
+    let test_env = create_test_env(index_id, TestStorageType::LocalFileSystem)?;
+
+    make_command(
+        format!(
+            "config validate --config {}",
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+        "Warning: Peer seed list is empty.",
+    ))
+    .stdout(predicate::str::contains("is valid."));
+
+    make_command(
+        format!(
+            "config validate --strict --config {}",
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains("failed 2 validation check(s)"));
+
+    make_command("config validate --config ./config-does-not-exist.yaml")
+        .assert()
+        .failure()
+        .code(2)
+        .stderr(predicate::str::contains("is invalid"));
+    Ok(())
+}
+
+#[test]
+fn test_cmd_check() -> Result<()> {
+    let index_id = append_random_suffix("test-check-cmd");
+    let test_env = create_test_env(index_id, TestStorageType::LocalFileSystem)?;
+    create_logs_index(&test_env);
+
+    make_command(
+        format!(
+            "check --config {}",
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stderr(predicate::str::contains("metastore"))
+    .stderr(predicate::str::contains("storage"));
+
+    make_command(
+        format!(
+            "check --index {} --config {}",
+            test_env.index_id,
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stderr(predicate::str::contains(test_env.index_uri.as_str()));
+
+    make_command(
+        format!(
+            "check --index index-does-not-exist --config {}",
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .failure()
+    .code(3);
+    Ok(())
+}
+
 #[test]
 fn test_cmd_create_on_existing_index() -> Result<()> {
     let index_id = append_random_suffix("test-create-cmd--index-already-exists");
@@ -159,6 +241,7 @@ fn test_cmd_create_on_existing_index() -> Result<()> {
     )
     .assert()
     .failure()
+    .code(3)
     .stderr(predicate::str::contains("already exists"));
 
     Ok(())
@@ -275,6 +358,59 @@ fn test_cmd_ingest_simple() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_cmd_ingest_dry_run() -> Result<()> {
+    let index_id = append_random_suffix("test-index-dry-run");
+    let test_env = create_test_env(index_id, TestStorageType::LocalFileSystem)?;
+    create_logs_index(&test_env);
+
+    make_command(
+        format!(
+            "index ingest --index {} --input-path {} --config {} --dry-run",
+            test_env.index_id,
+            test_env.resource_files["logs"].display(),
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Dry run: validated"))
+    .stdout(predicate::str::contains("Nothing was indexed."))
+    .stdout(predicate::str::contains("Now, you can query the index").not());
+
+    let splits = test_env
+        .metastore()
+        .await?
+        .list_all_splits(&test_env.index_id)
+        .await?;
+    assert!(splits.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_cmd_ingest_progress_format_json() -> Result<()> {
+    let index_id = append_random_suffix("test-index-progress-json");
+    let test_env = create_test_env(index_id, TestStorageType::LocalFileSystem)?;
+    create_logs_index(&test_env);
+
+    make_command(
+        format!(
+            "index ingest --index {} --input-path {} --config {} --progress-format json",
+            test_env.index_id,
+            test_env.resource_files["logs"].display(),
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Indexed"))
+    .stderr(predicate::str::contains(r#""num_docs":"#))
+    .stderr(predicate::str::contains(r#""throughput_mb_s":"#));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_cmd_search_aggregation() -> Result<()> {
     let index_id = append_random_suffix("test-search-cmd");
@@ -305,9 +441,30 @@ async fn test_cmd_search_aggregation() -> Result<()> {
         aggregation: Some(serde_json::to_string(&aggregation).unwrap()),
         max_hits: 10,
         start_offset: 0,
+        count: false,
         search_fields: Some(vec!["city".to_string()]),
+        fields: None,
+        snippet_fields: None,
+        snippet_max_fragments: None,
+        snippet_max_fragments_per_field: Default::default(),
+        snippet_fragment_size: None,
+        snippet_fragment_size_per_field: Default::default(),
         start_timestamp: None,
         end_timestamp: None,
+        required_tags: Vec::new(),
+        explain_bytes_per_hit: false,
+        explain_split_selection_order: false,
+        explain_doc_mapper_mode: false,
+        explain_io_wait: false,
+        explain_query_depth: false,
+        explain_sort_field_coverage: false,
+        exclude_dynamic_fields: false,
+        explain_fetch_plan: false,
+        explain_schema_compat: false,
+        explain: false,
+        split_order: SplitOrder::Default,
+        sort_by_field: None,
+        sort_order: None,
         config_uri: Uri::try_new(&test_env.resource_files["config"].display().to_string()).unwrap(),
         data_dir: None,
     };
@@ -423,6 +580,108 @@ fn test_cmd_search() -> Result<()> {
         result["num_hits"] == Value::Number(Number::from(0i64))
     }));
 
+    // search with malformed aggregation JSON
+    crate::helpers::make_command_with_list_of_args(&[
+        "index",
+        "search",
+        "--index",
+        &test_env.index_id,
+        "--config",
+        &test_env.resource_files["config"].display().to_string(),
+        "--query",
+        "level:info",
+        "--aggregation",
+        "{ not json",
+    ])
+    .assert()
+    .failure()
+    .code(2)
+    .stderr(predicate::str::contains(
+        "Invalid aggregation JSON at line 1, column 3.",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_cmd_export() -> Result<()> {
+    let index_id = append_random_suffix("test-export-cmd");
+    let test_env = create_test_env(index_id, TestStorageType::LocalFileSystem)?;
+    create_logs_index(&test_env);
+
+    ingest_docs(test_env.resource_files["logs"].as_path(), &test_env);
+
+    let json_output_dir = test_env.data_dir_path.join("export-json");
+    make_command(
+        format!(
+            "index export --index {} --config {} --query level:info --output-dir {}",
+            test_env.index_id,
+            test_env.resource_files["config"].display(),
+            json_output_dir.display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("Exported 2 documents"));
+    let exported_json = std::fs::read_to_string(json_output_dir.join("export-00000.json"))?;
+    let mut exported_events: Vec<String> = exported_json
+        .lines()
+        .map(|line| {
+            let doc: Value = serde_json::from_str(line).unwrap();
+            doc["event"].as_str().unwrap().to_string()
+        })
+        .collect();
+    exported_events.sort();
+    assert_eq!(exported_events, vec!["biz".to_string(), "foo".to_string()]);
+
+    // Each CSV file holds a single document because it exceeds the max file size.
+    let csv_output_dir = test_env.data_dir_path.join("export-csv");
+    let export_csv_command = format!(
+        "index export --index {} --config {} --query city:paris --output-dir {} --format csv \
+         --max-file-size 1B",
+        test_env.index_id,
+        test_env.resource_files["config"].display(),
+        csv_output_dir.display(),
+    );
+    make_command(&export_csv_command)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Exported 3 documents"));
+    for file_ordinal in 0..3 {
+        let exported_csv = std::fs::read_to_string(
+            csv_output_dir.join(format!("export-{:05}.csv", file_ordinal)),
+        )?;
+        let lines: Vec<&str> = exported_csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "ts,level,event,device,city");
+    }
+    assert!(!csv_output_dir.join("export-00003.csv").exists());
+
+    // Running the export again skips the documents already exported.
+    make_command(&export_csv_command)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Resuming the export after the 3 documents already exported.",
+        ))
+        .stdout(predicate::str::contains("Exported 3 documents"));
+    assert!(!csv_output_dir.join("export-00003.csv").exists());
+
+    make_command(
+        format!(
+            "index export --index {} --config {} --query city:london --output-dir {} --format csv",
+            test_env.index_id,
+            test_env.resource_files["config"].display(),
+            csv_output_dir.display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .failure()
+    .stderr(predicate::str::contains(
+        "holds the files of another export",
+    ));
     Ok(())
 }
 
@@ -512,6 +771,42 @@ async fn test_cmd_delete_simple() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_cmd_garbage_collect_force() -> Result<()> {
+    let index_id = append_random_suffix("test-gc-cmd-force");
+    let test_env = create_test_env(index_id, TestStorageType::LocalFileSystem)?;
+    create_logs_index(&test_env);
+
+    make_command(
+        format!(
+            "index gc --index {} --config {} --force",
+            test_env.index_id,
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .write_stdin("n\n")
+    .assert()
+    .success()
+    .stdout(predicate::str::contains("a forced garbage collection"))
+    .stdout(predicate::str::contains("Garbage collection aborted."));
+
+    make_command(
+        format!(
+            "index gc --index {} --config {} --force --yes",
+            test_env.index_id,
+            test_env.resource_files["config"].display(),
+        )
+        .as_str(),
+    )
+    .assert()
+    .success()
+    .stdout(predicate::str::contains(
+        "No dangling files to garbage collect",
+    ));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_cmd_garbage_collect_no_grace() -> Result<()> {
     let index_id = append_random_suffix("test-gc-cmd--no-grace-period");
@@ -283,6 +283,11 @@ impl Cluster {
         chitchat_guard.self_node_state().set(key, value);
     }
 
+    /// Returns whether the node has left the cluster or shut down its gossip.
+    pub fn has_left(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
     /// Leave the cluster.
     pub async fn leave(&self) {
         info!(self_addr = ?self.gossip_listen_addr, "Leaving the cluster.");
@@ -22,17 +22,23 @@ mod error;
 
 use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::bail;
 use chitchat::transport::UdpTransport;
 use chitchat::FailureDetectorConfig;
+use quickwit_common::net::HostAddr;
 use quickwit_config::QuickwitConfig;
+use tracing::info;
 
 pub use crate::cluster::{
     create_cluster_for_test, grpc_addr_from_listen_addr_for_test, Cluster, ClusterState, Member,
 };
 pub use crate::error::{ClusterError, ClusterResult};
 
+/// Interval at which the peer seeds that could not be resolved at startup are re-resolved.
+const PEER_SEED_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
 fn unix_timestamp() -> u64 {
     let duration_since_epoch = std::time::SystemTime::now()
         .duration_since(std::time::SystemTime::UNIX_EPOCH)
@@ -79,17 +85,53 @@ pub async fn start_cluster_service(
         quickwit_config.gossip_advertise_addr,
     );
 
+    let peer_seed_addrs = quickwit_config.peer_seed_addrs().await?;
     let cluster = Cluster::join(
         member,
         services,
         quickwit_config.gossip_listen_addr,
         quickwit_config.cluster_id.clone(),
         quickwit_config.grpc_advertise_addr,
-        quickwit_config.peer_seed_addrs().await?,
+        peer_seed_addrs.addrs,
         FailureDetectorConfig::default(),
         &UdpTransport,
     )
     .await?;
+    spawn_peer_seed_refresh_loop(peer_seed_addrs.unresolved_addrs);
 
     Ok(Arc::new(cluster))
 }
+
+/// Periodically re-resolves the peer seeds that could not be resolved at startup until they all
+/// resolve. The unresolved peer seeds are handed over to Chitchat anyway, which gossips with them
+/// as soon as they become resolvable: this loop only reports when that happens.
+fn spawn_peer_seed_refresh_loop(mut unresolved_peer_seed_addrs: Vec<String>) {
+    if unresolved_peer_seed_addrs.is_empty() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(PEER_SEED_REFRESH_INTERVAL);
+        // The first tick completes immediately.
+        interval.tick().await;
+
+        while !unresolved_peer_seed_addrs.is_empty() {
+            interval.tick().await;
+            let mut still_unresolved_peer_seed_addrs = Vec::new();
+
+            for peer_seed_addr_str in unresolved_peer_seed_addrs {
+                let resolution_res = match HostAddr::parse_with_default_port(&peer_seed_addr_str, 0)
+                {
+                    Ok(peer_seed_addr) => peer_seed_addr.resolve().await,
+                    Err(error) => Err(error),
+                };
+                match resolution_res {
+                    Ok(socket_addr) => {
+                        info!(peer_seed = %peer_seed_addr_str, socket_addr = %socket_addr, "Resolved peer seed address.");
+                    }
+                    Err(_) => still_unresolved_peer_seed_addrs.push(peer_seed_addr_str),
+                }
+            }
+            unresolved_peer_seed_addrs = still_unresolved_peer_seed_addrs;
+        }
+    });
+}
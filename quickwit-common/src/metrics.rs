@@ -18,7 +18,12 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use prometheus::{Encoder, HistogramOpts, Opts, TextEncoder};
-pub use prometheus::{Histogram, HistogramTimer, IntCounter, IntGauge};
+pub use prometheus::{
+    Histogram, HistogramTimer, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+};
+use tracing::error;
+use warp::http::header::CONTENT_TYPE;
+use warp::http::StatusCode;
 
 pub fn new_counter(name: &str, description: &str, namespace: &str) -> IntCounter {
     let counter_opts = Opts::new(name, description).namespace(namespace);
@@ -27,6 +32,19 @@ pub fn new_counter(name: &str, description: &str, namespace: &str) -> IntCounter
     counter
 }
 
+pub fn new_counter_vec(
+    name: &str,
+    description: &str,
+    namespace: &str,
+    label_names: &[&str],
+) -> IntCounterVec {
+    let counter_opts = Opts::new(name, description).namespace(namespace);
+    let counter_vec =
+        IntCounterVec::new(counter_opts, label_names).expect("Failed to create counter vec");
+    prometheus::register(Box::new(counter_vec.clone())).expect("Failed to register counter vec");
+    counter_vec
+}
+
 pub fn new_histogram(name: &str, description: &str, namespace: &str) -> Histogram {
     let histogram_opts = HistogramOpts::new(name, description).namespace(namespace);
     let histogram = Histogram::with_opts(histogram_opts).expect("Failed to create counter");
@@ -34,6 +52,34 @@ pub fn new_histogram(name: &str, description: &str, namespace: &str) -> Histogra
     histogram
 }
 
+pub fn new_histogram_with_buckets(
+    name: &str,
+    description: &str,
+    namespace: &str,
+    buckets: Vec<f64>,
+) -> Histogram {
+    let histogram_opts = HistogramOpts::new(name, description)
+        .namespace(namespace)
+        .buckets(buckets);
+    let histogram = Histogram::with_opts(histogram_opts).expect("Failed to create histogram");
+    prometheus::register(Box::new(histogram.clone())).expect("Failed to register histogram");
+    histogram
+}
+
+pub fn new_histogram_vec(
+    name: &str,
+    description: &str,
+    namespace: &str,
+    label_names: &[&str],
+) -> HistogramVec {
+    let histogram_opts = HistogramOpts::new(name, description).namespace(namespace);
+    let histogram_vec =
+        HistogramVec::new(histogram_opts, label_names).expect("Failed to create histogram vec");
+    prometheus::register(Box::new(histogram_vec.clone()))
+        .expect("Failed to register histogram vec");
+    histogram_vec
+}
+
 pub fn new_gauge(name: &str, description: &str, namespace: &str) -> IntGauge {
     let gauge_opts = Opts::new(name, description).namespace(namespace);
     let gauge = IntGauge::with_opts(gauge_opts).expect("Failed to create gauge");
@@ -41,12 +87,43 @@ pub fn new_gauge(name: &str, description: &str, namespace: &str) -> IntGauge {
     gauge
 }
 
+pub fn new_gauge_vec(
+    name: &str,
+    description: &str,
+    namespace: &str,
+    label_names: &[&str],
+) -> IntGaugeVec {
+    let gauge_opts = Opts::new(name, description).namespace(namespace);
+    let gauge_vec = IntGaugeVec::new(gauge_opts, label_names).expect("Failed to create gauge vec");
+    prometheus::register(Box::new(gauge_vec.clone())).expect("Failed to register gauge vec");
+    gauge_vec
+}
+
+/// Renders the metrics of the global registry, to which all the metrics created with the
+/// functions of this module are registered, in the Prometheus text exposition format.
 pub fn metrics_handler() -> impl warp::Reply {
     let metric_families = prometheus::gather();
     let mut buffer = Vec::new();
     let encoder = TextEncoder::new();
-    let _ = encoder.encode(&metric_families, &mut buffer); // TODO avoid ignoring the error.
-    String::from_utf8_lossy(&buffer).to_string()
+    if let Err(error) = encoder.encode(&metric_families, &mut buffer) {
+        error!(error = ?error, "Failed to encode the metrics.");
+        return warp::reply::with_status(
+            warp::reply::with_header(
+                "Failed to encode the metrics.".to_string(),
+                CONTENT_TYPE,
+                "text/plain",
+            ),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        );
+    }
+    warp::reply::with_status(
+        warp::reply::with_header(
+            String::from_utf8_lossy(&buffer).to_string(),
+            CONTENT_TYPE,
+            encoder.format_type(),
+        ),
+        StatusCode::OK,
+    )
 }
 
 pub fn create_gauge_guard(gauge: &'static IntGauge) -> GaugeGuard {
@@ -61,3 +138,27 @@ impl Drop for GaugeGuard {
         self.0.dec();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use warp::Reply;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_metrics_handler() {
+        let gauge = new_gauge("test_metrics_handler_gauge", "Gauge for tests.", "quickwit");
+        gauge.set(42);
+        let response = metrics_handler().into_response();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "text/plain; version=0.0.4"
+        );
+        let body = warp::hyper::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("quickwit_test_metrics_handler_gauge 42"));
+    }
+}
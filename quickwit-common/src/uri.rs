@@ -97,7 +97,8 @@ impl FromStr for Protocol {
             "file" => Ok(Protocol::File),
             "postgres" | "postgresql" => Ok(Protocol::PostgreSQL),
             "ram" => Ok(Protocol::Ram),
-            "s3" => Ok(Protocol::S3),
+            // `s3+minio` designates a MinIO server reached through the S3 API.
+            "s3" | "s3+minio" => Ok(Protocol::S3),
             "azure" => Ok(Protocol::Azure),
             _ => bail!("Unknown URI protocol `{}`.", protocol),
         }
@@ -483,6 +484,10 @@ mod tests {
         assert_eq!(Uri::for_test("file:///home").protocol(), Protocol::File);
         assert_eq!(Uri::for_test("ram:///in-memory").protocol(), Protocol::Ram);
         assert_eq!(Uri::for_test("s3://bucket/key").protocol(), Protocol::S3);
+        assert_eq!(
+            Uri::for_test("s3+minio://bucket/key").protocol(),
+            Protocol::S3
+        );
         assert_eq!(
             Uri::for_test("azure://account/bucket/key").protocol(),
             Protocol::Azure
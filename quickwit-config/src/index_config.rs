@@ -27,9 +27,9 @@ use json_comments::StripComments;
 use quickwit_common::uri::{Extension, Uri};
 use quickwit_doc_mapper::{
     DefaultDocMapperBuilder, DocMapper, FieldMappingEntry, ModeType, QuickwitJsonOptions, SortBy,
-    SortByConfig, SortOrder,
+    SortByConfig, SortOrder, TimestampPrecision, TypeConflictPolicy,
 };
-use serde::de::{Error, IgnoredAny};
+use serde::de::Error;
 use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::source_config::SourceConfig;
@@ -53,34 +53,37 @@ pub struct DocMapping {
     pub mode: ModeType,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub dynamic_mapping: Option<QuickwitJsonOptions>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_conflict_policy: Option<TypeConflictPolicy>,
     #[serde(default, skip_serializing_if = "String::is_empty")]
     pub partition_key: String,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct IndexingResources {
-    #[serde(default, rename = "num_threads", skip_serializing)]
-    pub __num_threads_deprecated: IgnoredAny, // DEPRECATED
+    /// Number of indexing threads of the index writer of each split. The heap is shared among
+    /// the threads. Defaults to a single thread.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub num_threads: Option<usize>,
     #[serde(default = "IndexingResources::default_heap_size")]
     pub heap_size: Byte,
 }
 
-impl PartialEq for IndexingResources {
-    fn eq(&self, other: &Self) -> bool {
-        self.heap_size == other.heap_size
-    }
-}
-
 impl IndexingResources {
     fn default_heap_size() -> Byte {
         Byte::from_bytes(2_000_000_000) // 2GB
     }
 
+    /// Returns the effective number of indexing threads.
+    pub fn num_threads(&self) -> usize {
+        self.num_threads.unwrap_or(1)
+    }
+
     #[cfg(any(test, feature = "testsuite"))]
     pub fn for_test() -> Self {
         Self {
-            __num_threads_deprecated: IgnoredAny,
+            num_threads: None,
             heap_size: Byte::from_bytes(20_000_000), // 20MB
         }
     }
@@ -89,7 +92,7 @@ impl IndexingResources {
 impl Default for IndexingResources {
     fn default() -> Self {
         Self {
-            __num_threads_deprecated: IgnoredAny,
+            num_threads: None,
             heap_size: Self::default_heap_size(),
         }
     }
@@ -104,6 +107,10 @@ pub struct MergePolicy {
     pub merge_factor: usize,
     #[serde(default = "MergePolicy::default_max_merge_factor")]
     pub max_merge_factor: usize,
+    /// If true, splits are grouped into tiers of similar sizes before being
+    /// merged, regardless of their time range.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub balance_split_sizes: bool,
 }
 
 impl MergePolicy {
@@ -126,6 +133,7 @@ impl Default for MergePolicy {
             demux_factor: Self::default_demux_factor(),
             merge_factor: Self::default_merge_factor(),
             max_merge_factor: Self::default_max_merge_factor(),
+            balance_split_sizes: false,
         }
     }
 }
@@ -144,6 +152,10 @@ pub struct IndexingSettings {
     pub sort_order: Option<SortOrder>,
     #[serde(default = "IndexingSettings::default_commit_timeout_secs")]
     pub commit_timeout_secs: usize,
+    /// When set, the indexer commits the documents it buffered as soon as it has not received
+    /// any new document for this number of seconds, without waiting for the commit timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_on_idle_after_secs: Option<usize>,
     #[serde(default = "IndexingSettings::default_docstore_compression_level")]
     pub docstore_compression_level: i32,
     #[serde(default = "IndexingSettings::default_docstore_blocksize")]
@@ -152,12 +164,37 @@ pub struct IndexingSettings {
     /// mature.
     #[serde(default = "IndexingSettings::default_split_num_docs_target")]
     pub split_num_docs_target: usize,
+    /// The indexer commits a split as soon as the documents it buffered reach this number of
+    /// bytes, even if the split has not reached `split_num_docs_target` yet.
+    /// If unset, splits are only committed on their number of documents or on timeout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_num_bytes_target: Option<u64>,
+    /// Hard cap on the number of documents of a split. Unlike `split_num_docs_target`, which is
+    /// only checked between two batches, the indexer starts a new split as soon as a split
+    /// reaches this number of documents, in the middle of a batch if need be.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_num_docs_per_split: Option<usize>,
     #[serde(default = "IndexingSettings::default_merge_enabled")]
     pub merge_enabled: bool,
     #[serde(default)]
     pub merge_policy: MergePolicy,
     #[serde(default)]
     pub resources: IndexingResources,
+    /// When enabled, the documents buffered by the indexer are committed when the pipeline is
+    /// killed rather than discarded.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub flush_on_shutdown: bool,
+    /// Maximum number of documents that can fail to parse before the indexing pipeline fails.
+    /// If unset, parse errors are counted but never abort indexing.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parse_errors: Option<u64>,
+    /// Precision of the values recorded for the timestamp field in the splits time range.
+    /// Defaults to seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_precision: Option<TimestampPrecision>,
+    /// Fields added to every indexed document that does not already set them, e.g. `env: prod`.
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub default_fields: serde_json::Map<String, serde_json::Value>,
 }
 
 impl IndexingSettings {
@@ -165,6 +202,11 @@ impl IndexingSettings {
         Duration::from_secs(self.commit_timeout_secs as u64)
     }
 
+    pub fn commit_on_idle_after(&self) -> Option<Duration> {
+        self.commit_on_idle_after_secs
+            .map(|commit_on_idle_after_secs| Duration::from_secs(commit_on_idle_after_secs as u64))
+    }
+
     fn default_commit_timeout_secs() -> usize {
         60
     }
@@ -211,12 +253,19 @@ impl Default for IndexingSettings {
             sort_field: None,
             sort_order: None,
             commit_timeout_secs: Self::default_commit_timeout_secs(),
+            commit_on_idle_after_secs: None,
             docstore_blocksize: Self::default_docstore_blocksize(),
             docstore_compression_level: Self::default_docstore_compression_level(),
             split_num_docs_target: Self::default_split_num_docs_target(),
+            split_num_bytes_target: None,
+            max_num_docs_per_split: None,
             merge_enabled: Self::default_merge_enabled(),
             merge_policy: MergePolicy::default(),
             resources: IndexingResources::default(),
+            flush_on_shutdown: false,
+            max_parse_errors: None,
+            timestamp_precision: None,
+            default_fields: serde_json::Map::new(),
         }
     }
 }
@@ -228,6 +277,92 @@ pub struct SearchSettings {
     pub default_search_fields: Vec<String>,
 }
 
+/// Overrides the storage settings otherwise sniffed from the environment for a given index. The
+/// credentials are referenced by the names of the environment variables holding them so that no
+/// secret ends up in the metastore.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct IndexStorageConfig {
+    /// Custom endpoint of an S3-compatible object storage, for instance `http://localhost:9000`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+    /// Region of the bucket, for instance `eu-west-1`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub region: Option<String>,
+    /// Name of the environment variable holding the access key ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_key_id_env_var: Option<String>,
+    /// Name of the environment variable holding the secret access key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret_access_key_env_var: Option<String>,
+    /// Server-side encryption algorithm applied to the objects written to the bucket, `aws:kms`
+    /// or `AES256`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_side_encryption: Option<String>,
+    /// ID of the KMS key used to encrypt the objects when `server_side_encryption` is `aws:kms`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sse_kms_key_id: Option<String>,
+    /// Timeout after which a request to the object storage is retried, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_timeout_secs: Option<u64>,
+    /// Timeout for establishing a connection to the object storage, in seconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connect_timeout_secs: Option<u64>,
+    /// Maximum number of attempts of a request to the object storage, including the first one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retry_attempts: Option<usize>,
+    /// Base delay of the exponential backoff between two attempts of a request to the object
+    /// storage, in milliseconds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_base_delay_ms: Option<u64>,
+}
+
+impl IndexStorageConfig {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if let Some(endpoint) = &self.endpoint {
+            if !endpoint.starts_with("http://") && !endpoint.starts_with("https://") {
+                bail!(
+                    "Index storage endpoint `{}` is invalid: it must start with `http://` or \
+                     `https://`.",
+                    endpoint
+                );
+            }
+        }
+        if self.access_key_id_env_var.is_some() != self.secret_access_key_env_var.is_some() {
+            bail!(
+                "Index storage config must define both `access_key_id_env_var` and \
+                 `secret_access_key_env_var` or none of them."
+            );
+        }
+        if let Some(server_side_encryption) = &self.server_side_encryption {
+            if server_side_encryption != "aws:kms" && server_side_encryption != "AES256" {
+                bail!(
+                    "Index storage server-side encryption `{}` is invalid: it must be `aws:kms` \
+                     or `AES256`.",
+                    server_side_encryption
+                );
+            }
+        }
+        if self.sse_kms_key_id.is_some()
+            && self.server_side_encryption.as_deref() != Some("aws:kms")
+        {
+            bail!(
+                "Index storage `sse_kms_key_id` requires `server_side_encryption` to be `aws:kms`."
+            );
+        }
+        if self.request_timeout_secs == Some(0) || self.connect_timeout_secs == Some(0) {
+            bail!(
+                "Index storage `request_timeout_secs` and `connect_timeout_secs` must be strictly \
+                 positive."
+            );
+        }
+        if self.max_retry_attempts == Some(0) {
+            bail!("Index storage `max_retry_attempts` must be strictly positive.");
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct IndexConfig {
@@ -243,6 +378,8 @@ pub struct IndexConfig {
     pub search_settings: SearchSettings,
     #[serde(default)]
     pub sources: Vec<SourceConfig>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub storage: Option<IndexStorageConfig>,
 }
 
 impl IndexConfig {
@@ -303,6 +440,9 @@ impl IndexConfig {
         for source in &self.sources {
             source.validate()?;
         }
+        if let Some(storage_config) = &self.storage {
+            storage_config.validate()?;
+        }
         // Validation is made by building the doc mapper.
         // Note: this needs a deep refactoring to separate the doc mapping configuration,
         // and doc mapper implementations.
@@ -319,6 +459,20 @@ impl IndexConfig {
                  `merge_factor`."
             )
         }
+        if self.indexing_settings.resources.num_threads == Some(0) {
+            bail!("Index config `resources.num_threads` must be strictly positive.")
+        }
+        if let Some(max_num_docs_per_split) = self.indexing_settings.max_num_docs_per_split {
+            if max_num_docs_per_split == 0 {
+                bail!("Index config `max_num_docs_per_split` must be strictly positive.")
+            }
+            if max_num_docs_per_split > self.indexing_settings.split_num_docs_target {
+                bail!(
+                    "Index config `max_num_docs_per_split` must be inferior or equal to \
+                     `split_num_docs_target`."
+                )
+            }
+        }
         Ok(())
     }
 }
@@ -337,19 +491,21 @@ pub fn build_doc_mapper(
         store_source: doc_mapping.store_source,
         default_search_fields: search_settings.default_search_fields.clone(),
         timestamp_field: indexing_settings.timestamp_field.clone(),
+        timestamp_precision: indexing_settings.timestamp_precision,
         sort_by,
         field_mappings: doc_mapping.field_mappings.clone(),
         tag_fields: doc_mapping.tag_fields.iter().cloned().collect(),
         demux_field: indexing_settings.demux_field.clone(),
         mode: doc_mapping.mode,
         dynamic_mapping: doc_mapping.dynamic_mapping.clone(),
+        type_conflict_policy: doc_mapping.type_conflict_policy,
         partition_key: doc_mapping.partition_key.clone(),
     };
     Ok(Arc::new(builder.try_build()?))
 }
 
 /// Deserializes and validates a [`Uri`].
-fn deser_and_validate_uri<'de, D>(deserializer: D) -> Result<Option<Uri>, D::Error>
+pub(crate) fn deser_and_validate_uri<'de, D>(deserializer: D) -> Result<Option<Uri>, D::Error>
 where D: Deserializer<'de> {
     let uri_opt: Option<String> = Deserialize::deserialize(deserializer)?;
     uri_opt
@@ -436,12 +592,13 @@ mod tests {
                         demux_factor: 7,
                         merge_factor: 9,
                         max_merge_factor: 11,
+                        balance_split_sizes: false,
                     }
                 );
                 assert_eq!(
                     index_config.indexing_settings.resources,
                     IndexingResources {
-                        __num_threads_deprecated: serde::de::IgnoredAny,
+                        num_threads: None,
                         heap_size: Byte::from_bytes(3_000_000_000)
                     }
                 );
@@ -533,7 +690,7 @@ mod tests {
                         ..Default::default()
                     },
                     resources: IndexingResources {
-                        __num_threads_deprecated: serde::de::IgnoredAny,
+                        num_threads: Some(3),
                         ..Default::default()
                     },
                     ..Default::default()
@@ -546,6 +703,7 @@ mod tests {
                 }
             );
             assert!(index_config.sources.is_empty());
+            assert!(index_config.storage.is_none());
         }
     }
 
@@ -608,6 +766,36 @@ mod tests {
                 .to_string()
                 .contains("must contain a `filepath`"));
         }
+        {
+            let mut invalid_index_config = index_config.clone();
+            invalid_index_config.indexing_settings.resources.num_threads = Some(0);
+            assert_eq!(
+                invalid_index_config.validate().unwrap_err().to_string(),
+                "Index config `resources.num_threads` must be strictly positive."
+            );
+        }
+        {
+            let mut invalid_index_config = index_config.clone();
+            invalid_index_config
+                .indexing_settings
+                .max_num_docs_per_split = Some(0);
+            assert_eq!(
+                invalid_index_config.validate().unwrap_err().to_string(),
+                "Index config `max_num_docs_per_split` must be strictly positive."
+            );
+        }
+        {
+            let mut invalid_index_config = index_config.clone();
+            invalid_index_config.indexing_settings.split_num_docs_target = 1_000;
+            invalid_index_config
+                .indexing_settings
+                .max_num_docs_per_split = Some(1_001);
+            assert_eq!(
+                invalid_index_config.validate().unwrap_err().to_string(),
+                "Index config `max_num_docs_per_split` must be inferior or equal to \
+                 `split_num_docs_target`."
+            );
+        }
         {
             // Add a demux field not declared in the mapping.
             let mut invalid_index_config = index_config;
@@ -643,4 +831,114 @@ mod tests {
         let minimal_config = serde_yaml::from_str::<IndexConfig>(config_yaml).unwrap();
         assert_eq!(minimal_config.doc_mapping.mode, ModeType::Lenient);
     }
+
+    #[test]
+    fn test_index_config_with_storage_config() {
+        let config_yaml = r#"
+            version: 0
+            index_id: hdfs-logs
+            index_uri: s3://tenant-bucket/hdfs-logs
+            doc_mapping: {}
+            storage:
+              endpoint: http://localhost:9000
+              region: eu-west-1
+              access_key_id_env_var: TENANT_ACCESS_KEY_ID
+              secret_access_key_env_var: TENANT_SECRET_ACCESS_KEY
+              server_side_encryption: aws:kms
+              sse_kms_key_id: tenant-kms-key
+              request_timeout_secs: 30
+              connect_timeout_secs: 5
+              max_retry_attempts: 5
+              retry_base_delay_ms: 100
+        "#;
+        let index_config = serde_yaml::from_str::<IndexConfig>(config_yaml).unwrap();
+        index_config.validate().unwrap();
+        assert_eq!(
+            index_config.storage.unwrap(),
+            IndexStorageConfig {
+                endpoint: Some("http://localhost:9000".to_string()),
+                region: Some("eu-west-1".to_string()),
+                access_key_id_env_var: Some("TENANT_ACCESS_KEY_ID".to_string()),
+                secret_access_key_env_var: Some("TENANT_SECRET_ACCESS_KEY".to_string()),
+                server_side_encryption: Some("aws:kms".to_string()),
+                sse_kms_key_id: Some("tenant-kms-key".to_string()),
+                request_timeout_secs: Some(30),
+                connect_timeout_secs: Some(5),
+                max_retry_attempts: Some(5),
+                retry_base_delay_ms: Some(100),
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_index_storage_config() {
+        IndexStorageConfig::default().validate().unwrap();
+        {
+            let storage_config = IndexStorageConfig {
+                endpoint: Some("localhost:9000".to_string()),
+                ..Default::default()
+            };
+            assert!(storage_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("must start with `http://` or `https://`"));
+        }
+        {
+            let storage_config = IndexStorageConfig {
+                access_key_id_env_var: Some("TENANT_ACCESS_KEY_ID".to_string()),
+                ..Default::default()
+            };
+            assert!(storage_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("must define both"));
+        }
+        {
+            let storage_config = IndexStorageConfig {
+                server_side_encryption: Some("aws:des".to_string()),
+                ..Default::default()
+            };
+            assert!(storage_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("must be `aws:kms` or `AES256`"));
+        }
+        {
+            let storage_config = IndexStorageConfig {
+                server_side_encryption: Some("AES256".to_string()),
+                sse_kms_key_id: Some("tenant-kms-key".to_string()),
+                ..Default::default()
+            };
+            assert!(storage_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("requires `server_side_encryption` to be `aws:kms`"));
+        }
+        {
+            let storage_config = IndexStorageConfig {
+                request_timeout_secs: Some(0),
+                ..Default::default()
+            };
+            assert!(storage_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("must be strictly positive"));
+        }
+        {
+            let storage_config = IndexStorageConfig {
+                max_retry_attempts: Some(0),
+                ..Default::default()
+            };
+            assert!(storage_config
+                .validate()
+                .unwrap_err()
+                .to_string()
+                .contains("`max_retry_attempts` must be strictly positive"));
+        }
+    }
 }
@@ -18,8 +18,7 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use anyhow::bail;
-use once_cell::sync::OnceCell;
-use regex::Regex;
+use thiserror::Error;
 
 mod config;
 mod index_config;
@@ -27,38 +26,80 @@ mod source_config;
 mod templating;
 
 pub use config::{
-    get_searcher_config_instance, IndexerConfig, QuickwitConfig, SearcherConfig,
-    DEFAULT_QW_CONFIG_PATH, SEARCHER_CONFIG_INSTANCE,
+    get_searcher_config_instance, AggregationBucketsLimitPolicy, ApiKeyConfig, IndexerConfig,
+    PeerSeedAddrs, PeerSeedResolutionRetryParams, QuickwitConfig, RestConfig, SearcherConfig,
+    TlsConfig, DEFAULT_QW_CONFIG_PATH, SEARCHER_CONFIG_INSTANCE,
 };
 pub use index_config::{
-    build_doc_mapper, DocMapping, IndexConfig, IndexingResources, IndexingSettings, MergePolicy,
-    SearchSettings,
+    build_doc_mapper, DocMapping, IndexConfig, IndexStorageConfig, IndexingResources,
+    IndexingSettings, MergePolicy, SearchSettings,
 };
 pub use source_config::{
-    FileSourceParams, IngestApiSourceParams, KafkaSourceParams, KinesisSourceParams,
-    RegionOrEndpoint, SourceConfig, SourceParams, VecSourceParams, VoidSourceParams,
-    CLI_INGEST_SOURCE_ID,
+    FileSourceCompression, FileSourceParams, HttpSourceParams, IngestApiSourceParams,
+    KafkaSourceParams, KinesisSourceParams, PulsarSourceParams, RegionOrEndpoint, SourceConfig,
+    SourceParams, VecSourceParams, VoidSourceParams, CLI_INGEST_SOURCE_ID,
 };
 
 fn is_false(val: &bool) -> bool {
     !*val
 }
 
-fn validate_identifier(label: &str, value: &str) -> anyhow::Result<()> {
-    static IDENTIFIER_REGEX: OnceCell<Regex> = OnceCell::new();
+/// Minimum length of an identifier (cluster ID, node ID, index ID, source ID).
+pub const IDENTIFIER_MIN_LEN: usize = 3;
+
+/// Default maximum length of an identifier (cluster ID, node ID, index ID, source ID).
+pub const IDENTIFIER_MAX_LEN: usize = 255;
+
+/// Reason why an identifier was rejected by [`validate_identifier_with_max_len`].
+#[derive(Debug, Clone, Copy, Error, PartialEq, Eq)]
+pub enum IdentifierError {
+    #[error("must be at least {min_len} characters long")]
+    TooShort { min_len: usize },
+    #[error("must be at most {max_len} characters long")]
+    TooLong { max_len: usize },
+    #[error("illegal character `{ch}` at position {position}, only ASCII letters, digits, `-`, and `_` are allowed")]
+    IllegalCharacter { position: usize, ch: char },
+    #[error("must start with a letter")]
+    MustStartWithLetter,
+}
 
-    if IDENTIFIER_REGEX
-        .get_or_init(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9-_]{2,254}$").expect("Failed to compile regular expression. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues."))
-        .is_match(value)
-    {
-        return Ok(());
+/// Checks that `value` starts with an ASCII letter, only contains ASCII letters, digits, `-`, and
+/// `_`, and is between [`IDENTIFIER_MIN_LEN`] and `max_len` characters long.
+pub fn validate_identifier_with_max_len(
+    value: &str,
+    max_len: usize,
+) -> Result<(), IdentifierError> {
+    let mut num_chars = 0;
+    for (position, ch) in value.chars().enumerate() {
+        if position == 0 && !ch.is_ascii_alphabetic() {
+            return Err(IdentifierError::MustStartWithLetter);
+        }
+        if !ch.is_ascii_alphanumeric() && ch != '-' && ch != '_' {
+            return Err(IdentifierError::IllegalCharacter { position, ch });
+        }
+        num_chars += 1;
     }
-    bail!("{} `{}` is invalid.", label, value);
+    if num_chars < IDENTIFIER_MIN_LEN {
+        return Err(IdentifierError::TooShort {
+            min_len: IDENTIFIER_MIN_LEN,
+        });
+    }
+    if num_chars > max_len {
+        return Err(IdentifierError::TooLong { max_len });
+    }
+    Ok(())
+}
+
+fn validate_identifier(label: &str, value: &str) -> anyhow::Result<()> {
+    if let Err(error) = validate_identifier_with_max_len(value, IDENTIFIER_MAX_LEN) {
+        bail!("{} `{}` is invalid: {}.", label, value, error);
+    }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::validate_identifier;
+    use crate::{validate_identifier, validate_identifier_with_max_len, IdentifierError};
 
     #[test]
     fn test_validate_identifier() {
@@ -71,12 +112,54 @@ mod tests {
         validate_identifier("Cluster ID", "_foo").unwrap_err();
         validate_identifier("Cluster ID", "foo").unwrap();
         validate_identifier("Cluster ID", "f-_").unwrap();
+        validate_identifier("Cluster ID", &"f".repeat(255)).unwrap();
+        validate_identifier("Cluster ID", &"f".repeat(256)).unwrap_err();
 
         assert_eq!(
             validate_identifier("Cluster ID", "foo!")
                 .unwrap_err()
                 .to_string(),
-            "Cluster ID `foo!` is invalid."
+            "Cluster ID `foo!` is invalid: illegal character `!` at position 3, only ASCII \
+             letters, digits, `-`, and `_` are allowed."
+        );
+        assert_eq!(
+            validate_identifier("Node ID", "9foo")
+                .unwrap_err()
+                .to_string(),
+            "Node ID `9foo` is invalid: must start with a letter."
+        );
+    }
+
+    #[test]
+    fn test_validate_identifier_with_max_len() {
+        assert_eq!(
+            validate_identifier_with_max_len("", 8),
+            Err(IdentifierError::TooShort { min_len: 3 })
+        );
+        assert_eq!(
+            validate_identifier_with_max_len("fo", 8),
+            Err(IdentifierError::TooShort { min_len: 3 })
+        );
+        assert_eq!(
+            validate_identifier_with_max_len("_foo", 8),
+            Err(IdentifierError::MustStartWithLetter)
+        );
+        assert_eq!(
+            validate_identifier_with_max_len("9foo", 8),
+            Err(IdentifierError::MustStartWithLetter)
+        );
+        assert_eq!(
+            validate_identifier_with_max_len("fo.o", 8),
+            Err(IdentifierError::IllegalCharacter {
+                position: 2,
+                ch: '.'
+            })
+        );
+        assert_eq!(
+            validate_identifier_with_max_len("foobarbaz", 8),
+            Err(IdentifierError::TooLong { max_len: 8 })
         );
+        assert_eq!(validate_identifier_with_max_len("foobarba", 8), Ok(()));
+        assert_eq!(validate_identifier_with_max_len("f-_", 8), Ok(()));
     }
 }
@@ -17,6 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 
 use anyhow::{bail, Context};
@@ -30,6 +31,9 @@ use crate::{is_false, validate_identifier};
 /// Reserved source ID for the `quickwit index ingest` CLI command.
 pub const CLI_INGEST_SOURCE_ID: &str = ".cli-ingest-source";
 
+/// Upper bound of the Kafka source `batch_num_bytes_target` parameter.
+const MAX_KAFKA_BATCH_NUM_BYTES_TARGET: u64 = 100_000_000;
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SourceConfig {
     pub source_id: String,
@@ -104,10 +108,59 @@ impl SourceConfig {
                 }
                 Ok(())
             }
-            SourceParams::Kafka(_) | SourceParams::Kinesis(_) => {
+            SourceParams::Kafka(kafka_params) => {
+                if let Some(batch_num_bytes_target) = kafka_params.batch_num_bytes_target {
+                    if batch_num_bytes_target == 0
+                        || batch_num_bytes_target > MAX_KAFKA_BATCH_NUM_BYTES_TARGET
+                    {
+                        bail!(
+                            "Source `{}` of type `kafka` has an invalid `batch_num_bytes_target` \
+                             `{}`: value must be between 1 and {}.",
+                            self.source_id,
+                            batch_num_bytes_target,
+                            MAX_KAFKA_BATCH_NUM_BYTES_TARGET
+                        )
+                    }
+                }
+                Ok(())
+            }
+            SourceParams::Kinesis(_) => {
                 // TODO consider any validation opportunity
                 Ok(())
             }
+            SourceParams::Pulsar(pulsar_params) => {
+                if !pulsar_params.service_url.starts_with("pulsar://")
+                    && !pulsar_params.service_url.starts_with("pulsar+ssl://")
+                {
+                    bail!(
+                        "Source `{}` of type `pulsar` has an invalid `service_url` `{}`: the URL \
+                         must start with `pulsar://` or `pulsar+ssl://`.",
+                        self.source_id,
+                        pulsar_params.service_url
+                    )
+                }
+                Ok(())
+            }
+            SourceParams::Http(http_params) => {
+                if !http_params.url.starts_with("http://")
+                    && !http_params.url.starts_with("https://")
+                {
+                    bail!(
+                        "Source `{}` of type `http` has an invalid `url` `{}`: the URL must start \
+                         with `http://` or `https://`.",
+                        self.source_id,
+                        http_params.url
+                    )
+                }
+                if http_params.poll_interval_secs == 0 {
+                    bail!(
+                        "Source `{}` of type `http` has an invalid `poll_interval_secs`: value \
+                         must be strictly positive.",
+                        self.source_id
+                    )
+                }
+                Ok(())
+            }
             SourceParams::Vec(_) | SourceParams::Void(_) | SourceParams::IngestApi(_) => Ok(()),
         }
     }
@@ -117,6 +170,8 @@ impl SourceConfig {
             SourceParams::File(_) => "file",
             SourceParams::Kafka(_) => "kafka",
             SourceParams::Kinesis(_) => "kinesis",
+            SourceParams::Pulsar(_) => "pulsar",
+            SourceParams::Http(_) => "http",
             SourceParams::Vec(_) => "vec",
             SourceParams::Void(_) => "void",
             SourceParams::IngestApi(_) => "ingest-api",
@@ -129,6 +184,8 @@ impl SourceConfig {
             SourceParams::File(params) => serde_json::to_value(params),
             SourceParams::Kafka(params) => serde_json::to_value(params),
             SourceParams::Kinesis(params) => serde_json::to_value(params),
+            SourceParams::Pulsar(params) => serde_json::to_value(params),
+            SourceParams::Http(params) => serde_json::to_value(params),
             SourceParams::Vec(params) => serde_json::to_value(params),
             SourceParams::Void(params) => serde_json::to_value(params),
             SourceParams::IngestApi(params) => serde_json::to_value(params),
@@ -146,6 +203,10 @@ pub enum SourceParams {
     Kafka(KafkaSourceParams),
     #[serde(rename = "kinesis")]
     Kinesis(KinesisSourceParams),
+    #[serde(rename = "pulsar")]
+    Pulsar(PulsarSourceParams),
+    #[serde(rename = "http")]
+    Http(HttpSourceParams),
     #[serde(rename = "vec")]
     Vec(VecSourceParams),
     #[serde(rename = "void")]
@@ -176,6 +237,31 @@ pub struct FileSourceParams {
     #[serde(default)]
     #[serde(deserialize_with = "absolute_filepath_from_str")]
     pub filepath: Option<PathBuf>, //< If None read from stdin.
+    /// Compression of the file. If None, the compression is inferred from the file
+    /// extension.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub compression: Option<FileSourceCompression>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileSourceCompression {
+    /// Gzip compression, inferred from the `.gz` and `.gzip` extensions.
+    Gzip,
+    /// Zstandard compression, inferred from the `.zst` and `.zstd` extensions.
+    Zstd,
+}
+
+impl FileSourceCompression {
+    /// Infers the compression from the extension of the file, e.g. `logs.json.gz`.
+    pub fn from_extension(filepath: &Path) -> Option<Self> {
+        match filepath.extension()?.to_str()? {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
 }
 
 // Deserializing a filepath string into an absolute filepath.
@@ -194,11 +280,25 @@ impl FileSourceParams {
     pub fn file<P: AsRef<Path>>(filepath: P) -> Self {
         FileSourceParams {
             filepath: Some(filepath.as_ref().to_path_buf()),
+            compression: None,
         }
     }
 
     pub fn stdin() -> Self {
-        FileSourceParams { filepath: None }
+        FileSourceParams {
+            filepath: None,
+            compression: None,
+        }
+    }
+
+    /// Returns the compression of the file, either explicitly set or inferred from the file
+    /// extension.
+    pub fn compression(&self) -> Option<FileSourceCompression> {
+        self.compression.or_else(|| {
+            self.filepath
+                .as_deref()
+                .and_then(FileSourceCompression::from_extension)
+        })
     }
 }
 
@@ -214,6 +314,13 @@ pub struct KafkaSourceParams {
     #[serde(default = "serde_json::Value::default")]
     #[serde(skip_serializing_if = "serde_json::Value::is_null")]
     pub client_params: serde_json::Value,
+    /// Maximum size of a message payload in bytes. Larger messages are skipped.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_message_bytes: Option<u64>,
+    /// Target size of the batches of documents emitted by the source in bytes. Smaller batches
+    /// lower indexing latency while larger batches improve throughput.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub batch_num_bytes_target: Option<u64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -266,6 +373,46 @@ impl TryFrom<KinesisSourceParamsInner> for KinesisSourceParams {
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PulsarSourceParams {
+    /// URL of the Pulsar service, e.g. `pulsar://localhost:6650`.
+    pub service_url: String,
+    /// Name of the topic that the source consumes.
+    pub topic: String,
+    /// Name of the subscription used to consume the topic.
+    pub subscription_name: String,
+    /// Token used to authenticate with the Pulsar service.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<String>,
+    /// When enabled, the source stops once the subscription has reached the end of the topic.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub backfill_mode: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HttpSourceParams {
+    /// URL of the NDJSON resource, e.g. `https://example.com/logs.ndjson`.
+    pub url: String,
+    /// Headers sent along with every request, e.g. `Authorization`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+    /// Number of seconds to wait between two fetches of the resource.
+    #[serde(default = "HttpSourceParams::default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// When enabled, requests carry the `If-None-Match` and `If-Modified-Since` headers so that
+    /// the server can answer `304 Not Modified` when the resource has not changed.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub conditional_fetch: bool,
+}
+
+impl HttpSourceParams {
+    pub fn default_poll_interval_secs() -> u64 {
+        60
+    }
+}
+
 #[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct VecSourceParams {
@@ -294,7 +441,9 @@ mod tests {
 
     use super::*;
     use crate::source_config::RegionOrEndpoint;
-    use crate::{FileSourceParams, IngestApiSourceParams, KinesisSourceParams};
+    use crate::{
+        FileSourceCompression, FileSourceParams, IngestApiSourceParams, KinesisSourceParams,
+    };
 
     fn get_source_config_filepath(source_config_filename: &str) -> String {
         format!(
@@ -318,6 +467,8 @@ mod tests {
                 topic: "cloudera-cluster-logs".to_string(),
                 client_log_level: None,
                 client_params: json! {{"bootstrap.servers": "host:9092"}},
+                max_message_bytes: None,
+                batch_num_bytes_target: None,
             }),
         };
         assert_eq!(source_config, expected_source_config);
@@ -350,11 +501,173 @@ mod tests {
             "#;
             let file_params = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap();
             let uri = Uri::try_new("source-path.json").unwrap();
+            assert!(file_params.compression().is_none());
             assert_eq!(
                 file_params.filepath.unwrap().as_path(),
                 uri.filepath().unwrap()
             )
         }
+        {
+            let yaml = r#"
+                filepath: source-path.json.gz
+            "#;
+            let file_params = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap();
+            assert!(file_params.compression.is_none());
+            assert_eq!(file_params.compression(), Some(FileSourceCompression::Gzip));
+        }
+        {
+            let yaml = r#"
+                filepath: source-path.json
+                compression: zstd
+            "#;
+            let file_params = serde_yaml::from_str::<FileSourceParams>(yaml).unwrap();
+            assert_eq!(file_params.compression(), Some(FileSourceCompression::Zstd));
+        }
+    }
+
+    #[test]
+    fn test_kafka_source_params_deserialization() {
+        {
+            let yaml = r#"
+                topic: my-topic
+            "#;
+            let kafka_params = serde_yaml::from_str::<KafkaSourceParams>(yaml).unwrap();
+            assert_eq!(kafka_params.topic, "my-topic");
+            assert!(kafka_params.max_message_bytes.is_none());
+        }
+        {
+            let yaml = r#"
+                topic: my-topic
+                max_message_bytes: 1000000
+            "#;
+            let kafka_params = serde_yaml::from_str::<KafkaSourceParams>(yaml).unwrap();
+            assert_eq!(kafka_params.max_message_bytes, Some(1_000_000));
+        }
+    }
+
+    #[test]
+    fn test_validate_kafka_source_batch_num_bytes_target() {
+        let source_config_for_target = |batch_num_bytes_target: Option<u64>| SourceConfig {
+            source_id: "kafka-source".to_string(),
+            source_params: SourceParams::Kafka(KafkaSourceParams {
+                topic: "my-topic".to_string(),
+                client_log_level: None,
+                client_params: json!({}),
+                max_message_bytes: None,
+                batch_num_bytes_target,
+            }),
+        };
+        source_config_for_target(None).validate().unwrap();
+        source_config_for_target(Some(1_000_000))
+            .validate()
+            .unwrap();
+        source_config_for_target(Some(MAX_KAFKA_BATCH_NUM_BYTES_TARGET))
+            .validate()
+            .unwrap();
+        assert!(source_config_for_target(Some(0)).validate().is_err());
+        assert!(
+            source_config_for_target(Some(MAX_KAFKA_BATCH_NUM_BYTES_TARGET + 1))
+                .validate()
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_load_pulsar_source_config() {
+        let source_config_filepath = get_source_config_filepath("pulsar-source.yaml");
+        let file_content = std::fs::read_to_string(&source_config_filepath).unwrap();
+        let source_config_uri = Uri::try_new(&source_config_filepath).unwrap();
+        let source_config = SourceConfig::load(&source_config_uri, file_content.as_bytes())
+            .await
+            .unwrap();
+        let expected_source_config = SourceConfig {
+            source_id: "hdfs-logs-pulsar-source".to_string(),
+            source_params: SourceParams::Pulsar(PulsarSourceParams {
+                service_url: "pulsar://localhost:6650".to_string(),
+                topic: "cloudera-cluster-logs".to_string(),
+                subscription_name: "quickwit".to_string(),
+                auth_token: None,
+                backfill_mode: false,
+            }),
+        };
+        assert_eq!(source_config, expected_source_config);
+    }
+
+    #[test]
+    fn test_validate_pulsar_source_service_url() {
+        let source_config = SourceConfig {
+            source_id: "pulsar-source".to_string(),
+            source_params: SourceParams::Pulsar(PulsarSourceParams {
+                service_url: "http://localhost:6650".to_string(),
+                topic: "my-topic".to_string(),
+                subscription_name: "quickwit".to_string(),
+                auth_token: None,
+                backfill_mode: false,
+            }),
+        };
+        assert!(source_config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_load_http_source_config() {
+        let source_config_filepath = get_source_config_filepath("http-source.yaml");
+        let file_content = std::fs::read_to_string(&source_config_filepath).unwrap();
+        let source_config_uri = Uri::try_new(&source_config_filepath).unwrap();
+        let source_config = SourceConfig::load(&source_config_uri, file_content.as_bytes())
+            .await
+            .unwrap();
+        let expected_source_config = SourceConfig {
+            source_id: "hdfs-logs-http-source".to_string(),
+            source_params: SourceParams::Http(HttpSourceParams {
+                url: "https://example.com/hdfs-logs.ndjson".to_string(),
+                headers: BTreeMap::from([(
+                    "Authorization".to_string(),
+                    "Bearer my-token".to_string(),
+                )]),
+                poll_interval_secs: 30,
+                conditional_fetch: true,
+            }),
+        };
+        assert_eq!(source_config, expected_source_config);
+    }
+
+    #[test]
+    fn test_http_source_params_deserialization() {
+        let yaml = r#"
+            url: http://localhost:8080/logs.ndjson
+        "#;
+        let http_params = serde_yaml::from_str::<HttpSourceParams>(yaml).unwrap();
+        assert_eq!(
+            http_params,
+            HttpSourceParams {
+                url: "http://localhost:8080/logs.ndjson".to_string(),
+                headers: BTreeMap::new(),
+                poll_interval_secs: 60,
+                conditional_fetch: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_validate_http_source_params() {
+        let source_config_for = |url: &str, poll_interval_secs: u64| SourceConfig {
+            source_id: "http-source".to_string(),
+            source_params: SourceParams::Http(HttpSourceParams {
+                url: url.to_string(),
+                headers: BTreeMap::new(),
+                poll_interval_secs,
+                conditional_fetch: false,
+            }),
+        };
+        assert!(source_config_for("https://localhost/logs.ndjson", 1)
+            .validate()
+            .is_ok());
+        assert!(source_config_for("ftp://localhost/logs.ndjson", 1)
+            .validate()
+            .is_err());
+        assert!(source_config_for("http://localhost/logs.ndjson", 0)
+            .validate()
+            .is_err());
     }
 
     #[test]
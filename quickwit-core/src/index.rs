@@ -134,6 +134,7 @@ impl IndexService {
             doc_mapping: index_config.doc_mapping,
             indexing_settings: index_config.indexing_settings,
             search_settings: index_config.search_settings,
+            storage_config: index_config.storage,
             create_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
             update_timestamp: OffsetDateTime::now_utc().unix_timestamp(),
         };
@@ -156,8 +157,11 @@ impl IndexService {
         index_id: &str,
         dry_run: bool,
     ) -> Result<Vec<FileEntry>, IndexServiceError> {
-        let index_uri = self.metastore.index_metadata(index_id).await?.index_uri;
-        let storage = self.storage_resolver.resolve(&index_uri)?;
+        let index_metadata = self.metastore.index_metadata(index_id).await?;
+        let storage = self.storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?;
 
         if dry_run {
             let all_splits = self
@@ -217,15 +221,21 @@ impl IndexService {
     ///
     /// * `index_id` - The target index Id.
     /// * `grace_period` -  Threshold period after which a staged split can be garbage collected.
+    /// * `delete_orphans` - Should the split files that belong to no split of the metastore and
+    ///   were last modified before `grace_period` be deleted as well.
     /// * `dry_run` - Should this only return a list of affected files without performing deletion.
     pub async fn garbage_collect_index(
         &self,
         index_id: &str,
         grace_period: Duration,
+        delete_orphans: bool,
         dry_run: bool,
     ) -> anyhow::Result<Vec<FileEntry>> {
-        let index_uri = self.metastore.index_metadata(index_id).await?.index_uri;
-        let storage = self.storage_resolver.resolve(&index_uri)?;
+        let index_metadata = self.metastore.index_metadata(index_id).await?;
+        let storage = self.storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?;
         let split_store = IndexingSplitStore::create_with_no_local_store(storage);
 
         let deleted_entries = run_garbage_collect(
@@ -236,6 +246,7 @@ impl IndexService {
             // deletion_grace_period of zero, so that a cli call directly deletes splits after
             // marking to be deleted.
             Duration::ZERO,
+            delete_orphans.then(|| grace_period),
             dry_run,
             None,
         )
@@ -254,7 +265,10 @@ impl IndexService {
     /// * `storage_resolver` - A storage resolver object to access the storage.
     pub async fn reset_index(&self, index_id: &str) -> anyhow::Result<()> {
         let index_metadata = self.metastore.index_metadata(index_id).await?;
-        let storage = self.storage_resolver.resolve(&index_metadata.index_uri)?;
+        let storage = self.storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?;
         let splits = self.metastore.list_all_splits(index_id).await?;
         let split_ids: Vec<&str> = splits.iter().map(|split| split.split_id()).collect();
         self.metastore
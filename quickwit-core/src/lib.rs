@@ -106,6 +106,7 @@ mod tests {
             indexing_settings: IndexingSettings::default(),
             search_settings: SearchSettings::default(),
             sources: Vec::new(),
+            storage: None,
         };
         let metastore_uri = Uri::new("ram:///metastore".to_string());
         let metastore = quickwit_metastore_uri_resolver()
@@ -34,11 +34,14 @@ use super::DefaultDocMapperBuilder;
 use crate::default_doc_mapper::mapping_tree::{build_mapping_tree, MappingNode, MappingTree};
 pub use crate::default_doc_mapper::QuickwitJsonOptions;
 use crate::doc_mapper::Partition;
-use crate::query_builder::build_query;
+use crate::query_builder::{
+    build_query, expand_field_name_pattern, explain_query, is_field_name_pattern,
+};
 use crate::routing_expression::RoutingExpr;
 use crate::sort_by::{validate_sort_by_field_name, SortBy, SortOrder};
 use crate::{
-    DocMapper, DocParsingError, ModeType, QueryParserError, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
+    DocMapper, DocParsingError, ModeType, QueryExplanation, QueryParserError, TimestampPrecision,
+    TypeConflictPolicy, DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
 };
 
 /// Specifies the name of the sort field and the sort order for an index.
@@ -101,8 +104,12 @@ pub struct DefaultDocMapper {
     dynamic_field: Option<Field>,
     /// Default list of field names used for search.
     default_search_field_names: Vec<String>,
-    /// Timestamp field name.
+    /// Timestamp field name. Fields of objects are addressed by their dotted path, e.g. `meta.ts`.
     timestamp_field_name: Option<String>,
+    /// Timestamp field, resolved from `timestamp_field_name`.
+    timestamp_field: Option<Field>,
+    /// Unit of the timestamps extracted from the timestamp field.
+    timestamp_precision: TimestampPrecision,
     /// Sort field name and order.
     sort_by: SortBy,
     /// Root node of the field mapping tree.
@@ -121,10 +128,35 @@ pub struct DefaultDocMapper {
     required_fields: Vec<Field>,
     /// Defines how unmapped fields should be handle.
     mode: Mode,
+    /// Defines how values conflicting with the mapped field type should be handled.
+    type_conflict_policy: TypeConflictPolicy,
 }
 
 impl DefaultDocMapper {
+    /// Returns the default search fields, including the dynamic field when dynamic fields are
+    /// indexed.
+    fn tantivy_default_search_field_names(&self) -> Vec<String> {
+        let mut tantivy_default_search_field_names = self.default_search_field_names.clone();
+        if let Mode::Dynamic(default_mapping_options) = &self.mode {
+            if default_mapping_options.indexed {
+                tantivy_default_search_field_names.push(DYNAMIC_FIELD_NAME.to_string());
+            }
+        }
+        tantivy_default_search_field_names
+    }
+
     fn check_missing_required_fields(&self, doc: &Document) -> Result<(), DocParsingError> {
+        // The timestamp field is a required fast field too, but its missing value is reported
+        // with the full path it was configured with.
+        if let (Some(timestamp_field), Some(timestamp_field_name)) =
+            (self.timestamp_field, self.timestamp_field_name.as_ref())
+        {
+            if doc.get_first(timestamp_field).is_none() {
+                return Err(DocParsingError::MissingTimestamp(
+                    timestamp_field_name.clone(),
+                ));
+            }
+        }
         for &required_field in &self.required_fields {
             if doc.get_first(required_field).is_none() {
                 let missing_field_name = self.schema.get_field_name(required_field);
@@ -183,50 +215,65 @@ fn list_required_fields(field_mappings: &MappingTree) -> Vec<Field> {
 
 fn resolve_timestamp_field(
     timestamp_field_name_opt: Option<&String>,
+    timestamp_precision: TimestampPrecision,
     schema: &Schema,
-) -> anyhow::Result<()> {
-    if let Some(ref timestamp_field_name) = timestamp_field_name_opt {
-        let timestamp_field = schema
-            .get_field(timestamp_field_name)
-            .with_context(|| format!("Unknown timestamp field: `{}`", timestamp_field_name))?;
-
-        let timestamp_field_entry = schema.get_field_entry(timestamp_field);
-        if !timestamp_field_entry.is_fast() {
-            bail!(
-                "Timestamp field must be a fast field, please add the fast property to your field \
-                 `{}`.",
-                timestamp_field_name
-            )
-        }
-        match timestamp_field_entry.field_type() {
-            FieldType::I64(options) => {
-                if options.get_fastfield_cardinality() == Some(Cardinality::MultiValues) {
-                    bail!(
-                        "Timestamp field cannot be an array, please change your field `{}` from \
-                         an array to a single value.",
-                        timestamp_field_name
-                    )
-                }
+) -> anyhow::Result<Option<Field>> {
+    if timestamp_field_name_opt.is_none() && timestamp_precision != TimestampPrecision::Seconds {
+        bail!("`timestamp_precision` is only allowed when a timestamp field is defined.");
+    }
+    let timestamp_field_name = match timestamp_field_name_opt {
+        Some(timestamp_field_name) => timestamp_field_name,
+        None => return Ok(None),
+    };
+    // Fields of objects are flattened in the schema under their dotted path, so `meta.ts`
+    // resolves to the field `ts` of the object `meta`.
+    let timestamp_field = schema
+        .get_field(timestamp_field_name)
+        .with_context(|| format!("Unknown timestamp field: `{}`", timestamp_field_name))?;
+    let timestamp_field_entry = schema.get_field_entry(timestamp_field);
+    if !timestamp_field_entry.is_fast() {
+        bail!(
+            "Timestamp field must be a fast field, please add the fast property to your field \
+             `{}`.",
+            timestamp_field_name
+        )
+    }
+    match timestamp_field_entry.field_type() {
+        FieldType::I64(options) => {
+            if options.get_fastfield_cardinality() == Some(Cardinality::MultiValues) {
+                bail!(
+                    "Timestamp field cannot be an array, please change your field `{}` from \
+                     an array to a single value.",
+                    timestamp_field_name
+                )
             }
-            FieldType::Date(options) => {
-                if options.get_fastfield_cardinality() == Some(Cardinality::MultiValues) {
-                    bail!(
-                        "Timestamp field cannot be an array, please change your field `{}` from \
-                         an array to a single value.",
-                        timestamp_field_name
-                    )
-                }
+            if timestamp_precision != TimestampPrecision::Seconds {
+                bail!(
+                    "Timestamp precision `{:?}` is only allowed for datetime timestamp \
+                     fields, please change your field `{}` to a datetime field.",
+                    timestamp_precision,
+                    timestamp_field_name
+                )
             }
-            _ => {
+        }
+        FieldType::Date(options) => {
+            if options.get_fastfield_cardinality() == Some(Cardinality::MultiValues) {
                 bail!(
-                    "Timestamp field must be of type i64, please change your field type `{}` to \
-                     i64.",
+                    "Timestamp field cannot be an array, please change your field `{}` from \
+                     an array to a single value.",
                     timestamp_field_name
                 )
             }
         }
+        _ => {
+            bail!(
+                "Timestamp field must be of type i64, please change your field type `{}` to \
+                 i64.",
+                timestamp_field_name
+            )
+        }
     }
-    Ok(())
+    Ok(Some(timestamp_field))
 }
 
 fn resolve_sort_field(
@@ -291,6 +338,7 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
 
     fn try_from(builder: DefaultDocMapperBuilder) -> anyhow::Result<DefaultDocMapper> {
         let mode = builder.mode()?;
+        let type_conflict_policy = builder.type_conflict_policy.unwrap_or_default();
         let mut schema_builder = Schema::builder();
         let field_mappings = build_mapping_tree(&builder.field_mappings, &mut schema_builder)?;
         let source_field = if builder.store_source {
@@ -316,13 +364,27 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             if default_search_field_names.contains(field_name) {
                 bail!("Duplicated default search field: `{}`", field_name)
             }
-            schema
-                .get_field(field_name)
-                .with_context(|| format!("Unknown default search field: `{}`", field_name))?;
+            if is_field_name_pattern(field_name) {
+                if expand_field_name_pattern(&schema, field_name)?.is_empty() {
+                    bail!(
+                        "Default search field pattern `{}` does not match any indexed text field.",
+                        field_name
+                    )
+                }
+            } else {
+                schema
+                    .get_field(field_name)
+                    .with_context(|| format!("Unknown default search field: `{}`", field_name))?;
+            }
             default_search_field_names.push(field_name.clone());
         }
 
-        resolve_timestamp_field(builder.timestamp_field.as_ref(), &schema)?;
+        let timestamp_precision = builder.timestamp_precision.unwrap_or_default();
+        let timestamp_field = resolve_timestamp_field(
+            builder.timestamp_field.as_ref(),
+            timestamp_precision,
+            &schema,
+        )?;
         resolve_demux_field(builder.demux_field.as_ref(), &schema)?;
         let sort_by = resolve_sort_field(builder.sort_by, &schema)?;
 
@@ -356,6 +418,8 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             dynamic_field,
             default_search_field_names,
             timestamp_field_name: builder.timestamp_field,
+            timestamp_field,
+            timestamp_precision,
             sort_by,
             field_mappings,
             tag_field_names,
@@ -363,6 +427,7 @@ impl TryFrom<DefaultDocMapperBuilder> for DefaultDocMapper {
             partition_key,
             demux_field_name: builder.demux_field,
             mode,
+            type_conflict_policy,
         })
     }
 }
@@ -382,9 +447,18 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             Mode::Dynamic(mapping_options) => Some(mapping_options.clone()),
             _ => None,
         };
+        let type_conflict_policy = match default_doc_mapper.type_conflict_policy {
+            TypeConflictPolicy::Error => None,
+            type_conflict_policy => Some(type_conflict_policy),
+        };
+        let timestamp_precision = match default_doc_mapper.timestamp_precision {
+            TimestampPrecision::Seconds => None,
+            timestamp_precision => Some(timestamp_precision),
+        };
         Self {
             store_source: default_doc_mapper.source_field.is_some(),
             timestamp_field: default_doc_mapper.timestamp_field_name(),
+            timestamp_precision,
             field_mappings: default_doc_mapper.field_mappings.into(),
             demux_field,
             sort_by: sort_by_config,
@@ -392,6 +466,7 @@ impl From<DefaultDocMapper> for DefaultDocMapperBuilder {
             default_search_fields: default_doc_mapper.default_search_field_names,
             mode,
             dynamic_mapping,
+            type_conflict_policy,
             partition_key: default_doc_mapper.partition_key.to_string(),
         }
     }
@@ -459,6 +534,7 @@ impl DocMapper for DefaultDocMapper {
         self.field_mappings.doc_from_json(
             json_obj,
             mode,
+            self.type_conflict_policy,
             &mut document,
             &mut field_path,
             &mut dynamic_json_obj,
@@ -499,13 +575,19 @@ impl DocMapper for DefaultDocMapper {
         split_schema: Schema,
         request: &SearchRequest,
     ) -> Result<Box<dyn Query>, QueryParserError> {
-        let mut tantivy_default_search_field_names = self.default_search_field_names.clone();
-        if let Mode::Dynamic(default_mapping_options) = &self.mode {
-            if default_mapping_options.indexed {
-                tantivy_default_search_field_names.push(DYNAMIC_FIELD_NAME.to_string());
-            }
-        }
-        build_query(split_schema, request, &tantivy_default_search_field_names)
+        build_query(
+            split_schema,
+            request,
+            &self.tantivy_default_search_field_names(),
+        )
+    }
+
+    fn explain_query(&self, split_schema: Schema, request: &SearchRequest) -> QueryExplanation {
+        explain_query(
+            split_schema,
+            request,
+            &self.tantivy_default_search_field_names(),
+        )
     }
 
     fn schema(&self) -> Schema {
@@ -516,6 +598,10 @@ impl DocMapper for DefaultDocMapper {
         self.timestamp_field_name.clone()
     }
 
+    fn timestamp_precision(&self) -> TimestampPrecision {
+        self.timestamp_precision
+    }
+
     fn demux_field_name(&self) -> Option<String> {
         self.demux_field_name.clone()
     }
@@ -527,6 +613,10 @@ impl DocMapper for DefaultDocMapper {
     fn tag_field_names(&self) -> BTreeSet<String> {
         self.tag_field_names.clone()
     }
+
+    fn mode_type(&self) -> ModeType {
+        self.mode.mode_type()
+    }
 }
 
 #[cfg(test)]
@@ -539,8 +629,8 @@ mod tests {
 
     use super::DefaultDocMapper;
     use crate::{
-        DefaultDocMapperBuilder, DocMapper, DocParsingError, SortBy, SortOrder, DYNAMIC_FIELD_NAME,
-        SOURCE_FIELD_NAME,
+        DefaultDocMapperBuilder, DocMapper, DocParsingError, SortBy, SortOrder, TimestampPrecision,
+        DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME,
     };
 
     fn example_json_doc_value() -> serde_json::Value {
@@ -780,6 +870,53 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_build_doc_mapper_with_timestamp_precision() -> anyhow::Result<()> {
+        let doc_mapper = r#"{
+            "timestamp_field": "timestamp",
+            "timestamp_precision": "milliseconds",
+            "field_mappings": [
+                {
+                    "name": "timestamp",
+                    "type": "datetime",
+                    "fast": true
+                }
+            ]
+        }"#;
+        let builder = serde_json::from_str::<DefaultDocMapperBuilder>(doc_mapper)?;
+        let doc_mapper = builder.try_build()?;
+        assert_eq!(
+            doc_mapper.timestamp_precision(),
+            TimestampPrecision::Milliseconds
+        );
+        let doc_mapper_json = serde_json::to_string(&doc_mapper)?;
+        let doc_mapper_after_serialization: DefaultDocMapper =
+            serde_json::from_str(&doc_mapper_json)?;
+        assert_eq!(
+            doc_mapper_after_serialization.timestamp_precision(),
+            TimestampPrecision::Milliseconds
+        );
+
+        let doc_mapper = r#"{
+            "timestamp_field": "timestamp",
+            "timestamp_precision": "milliseconds",
+            "field_mappings": [
+                {
+                    "name": "timestamp",
+                    "type": "i64",
+                    "fast": true
+                }
+            ]
+        }"#;
+        let builder = serde_json::from_str::<DefaultDocMapperBuilder>(doc_mapper)?;
+        let expected_msg = "Timestamp precision `Milliseconds` is only allowed for datetime \
+                            timestamp fields, please change your field `timestamp` to a datetime \
+                            field."
+            .to_string();
+        assert_eq!(builder.try_build().unwrap_err().to_string(), expected_msg);
+        Ok(())
+    }
+
     #[test]
     fn test_fail_with_field_name_equal_to_source() {
         let doc_mapper = r#"{
@@ -1329,6 +1466,92 @@ mod tests {
         }
     }
 
+    fn dynamic_doc_mapper_with_type_conflict_policy(
+        type_conflict_policy: &str,
+    ) -> DefaultDocMapper {
+        let doc_mapper = format!(
+            r#"{{
+            "field_mappings": [
+                {{
+                    "name": "count",
+                    "type": "i64"
+                }}
+            ],
+            "mode": "dynamic",
+            "type_conflict_policy": "{}"
+        }}"#,
+            type_conflict_policy
+        );
+        serde_json::from_str(&doc_mapper).unwrap()
+    }
+
+    #[test]
+    fn test_dynamic_mode_type_conflict_policy_error() {
+        let default_doc_mapper = dynamic_doc_mapper_with_type_conflict_policy("error");
+        let count_field = default_doc_mapper.schema().get_field("count").unwrap();
+        let (_, doc) = default_doc_mapper
+            .doc_from_json(r#"{ "count": 5 }"#.to_string())
+            .unwrap();
+        assert_eq!(doc.get_first(count_field).unwrap().as_i64(), Some(5));
+        let parsing_err = default_doc_mapper
+            .doc_from_json(r#"{ "count": "6" }"#.to_string())
+            .unwrap_err();
+        assert!(
+            matches!(parsing_err, DocParsingError::ValueError(field_name, _) if field_name == "count")
+        );
+    }
+
+    #[test]
+    fn test_dynamic_mode_type_conflict_policy_coerce() {
+        let default_doc_mapper = dynamic_doc_mapper_with_type_conflict_policy("coerce");
+        let count_field = default_doc_mapper.schema().get_field("count").unwrap();
+        let (_, doc) = default_doc_mapper
+            .doc_from_json(r#"{ "count": 5 }"#.to_string())
+            .unwrap();
+        assert_eq!(doc.get_first(count_field).unwrap().as_i64(), Some(5));
+        let (_, doc) = default_doc_mapper
+            .doc_from_json(r#"{ "count": "6" }"#.to_string())
+            .unwrap();
+        assert_eq!(doc.get_first(count_field).unwrap().as_i64(), Some(6));
+        let parsing_err = default_doc_mapper
+            .doc_from_json(r#"{ "count": "six" }"#.to_string())
+            .unwrap_err();
+        assert!(
+            matches!(parsing_err, DocParsingError::ValueError(field_name, _) if field_name == "count")
+        );
+    }
+
+    #[test]
+    fn test_dynamic_mode_type_conflict_policy_skip_field() {
+        let default_doc_mapper = dynamic_doc_mapper_with_type_conflict_policy("skip-field");
+        let schema = default_doc_mapper.schema();
+        let count_field = schema.get_field("count").unwrap();
+        let dynamic_field = schema.get_field(DYNAMIC_FIELD_NAME).unwrap();
+        let (_, doc) = default_doc_mapper
+            .doc_from_json(r#"{ "count": 5 }"#.to_string())
+            .unwrap();
+        assert_eq!(doc.get_first(count_field).unwrap().as_i64(), Some(5));
+        let (_, doc) = default_doc_mapper
+            .doc_from_json(r#"{ "count": "6", "other": "hello" }"#.to_string())
+            .unwrap();
+        assert!(doc.get_first(count_field).is_none());
+        assert!(doc.get_first(dynamic_field).is_some());
+    }
+
+    #[test]
+    fn test_type_conflict_policy_requires_dynamic_mode() {
+        let error = serde_json::from_str::<DefaultDocMapper>(
+            r#"{
+            "mode": "lenient",
+            "type_conflict_policy": "coerce"
+        }"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("`type_conflict_policy` is only allowed with mode=dynamic"));
+    }
+
     #[test]
     fn test_json_object_in_mapping() {
         let default_doc_mapper: DefaultDocMapper = serde_json::from_str(
@@ -1462,4 +1685,42 @@ mod tests {
             r#"TermQuery(Term(type=Str, field=1, "toto"))"#
         );
     }
+
+    #[test]
+    fn test_doc_mapper_nested_timestamp_field() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_str(
+            r#"{
+            "timestamp_field": "meta.ts",
+            "field_mappings": [
+                {
+                    "name": "meta",
+                    "type": "object",
+                    "field_mappings": [{"type": "i64", "name": "ts", "fast": true}]
+                },
+                {"type": "text", "name": "body"}
+            ]
+        }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            doc_mapper.timestamp_field_name(),
+            Some("meta.ts".to_string())
+        );
+        let (_, document) = doc_mapper
+            .doc_from_json(r#"{"meta": {"ts": 1}, "body": "hello"}"#.to_string())
+            .unwrap();
+        let timestamp_field = doc_mapper.schema().get_field("meta.ts").unwrap();
+        assert_eq!(
+            document.get_first(timestamp_field).unwrap().as_i64(),
+            Some(1)
+        );
+        let error = doc_mapper
+            .doc_from_json(r#"{"meta": {}, "body": "hello"}"#.to_string())
+            .unwrap_err();
+        assert_eq!(
+            error,
+            DocParsingError::MissingTimestamp("meta.ts".to_string())
+        );
+        assert_eq!(error.field_name(), Some("meta.ts"));
+    }
 }
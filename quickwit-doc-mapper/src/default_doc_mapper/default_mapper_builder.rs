@@ -19,6 +19,7 @@
 
 use anyhow::bail;
 use serde::{Deserialize, Serialize};
+use tantivy::DateTime;
 
 use super::FieldMappingEntry;
 use crate::default_doc_mapper::default_mapper::Mode;
@@ -44,6 +45,10 @@ pub struct DefaultDocMapperBuilder {
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub timestamp_field: Option<String>,
+    /// Unit of the timestamps extracted from the timestamp field.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp_precision: Option<TimestampPrecision>,
     /// Specifies the name of the sort field and the sort order.
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -69,6 +74,11 @@ pub struct DefaultDocMapperBuilder {
     /// how the unmapped fields should be handled.
     #[serde(default)]
     pub dynamic_mapping: Option<QuickwitJsonOptions>,
+    /// If mode is set to dynamic, `type_conflict_policy` defines how values
+    /// whose type conflicts with the mapped field type should be handled.
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub type_conflict_policy: Option<TypeConflictPolicy>,
 }
 
 /// `Mode` describing how the unmapped field should be handled.
@@ -90,6 +100,68 @@ impl Default for ModeType {
     }
 }
 
+/// `TypeConflictPolicy` describing how a value whose type conflicts with the
+/// mapped field type should be handled in dynamic mode.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TypeConflictPolicy {
+    /// Error policy: the document is rejected with a parsing error.
+    Error,
+    /// Coerce policy: the value is coerced into the field type, e.g. the string
+    /// `"42"` for an `i64` field or the number `42` for a `text` field. An error is
+    /// yielded if the value cannot be coerced.
+    Coerce,
+    /// Skip-field policy: the conflicting value is dropped and the rest of the
+    /// document is indexed.
+    SkipField,
+}
+
+impl Default for TypeConflictPolicy {
+    fn default() -> Self {
+        TypeConflictPolicy::Error
+    }
+}
+
+/// `TimestampPrecision` describing the unit of the timestamps extracted from the timestamp field.
+/// The time range of the splits is recorded, and pruned, at this precision.
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum TimestampPrecision {
+    /// Seconds precision.
+    Seconds,
+    /// Milliseconds precision. Only allowed for `datetime` timestamp fields.
+    Milliseconds,
+    /// Microseconds precision. Only allowed for `datetime` timestamp fields.
+    Microseconds,
+}
+
+impl Default for TimestampPrecision {
+    fn default() -> Self {
+        TimestampPrecision::Seconds
+    }
+}
+
+impl TimestampPrecision {
+    /// Converts a datetime into a timestamp expressed at this precision.
+    pub fn timestamp_from_date_time(&self, date_time: DateTime) -> i64 {
+        match self {
+            TimestampPrecision::Seconds => date_time.into_timestamp_secs(),
+            TimestampPrecision::Milliseconds => date_time.into_timestamp_millis(),
+            TimestampPrecision::Microseconds => date_time.into_timestamp_micros(),
+        }
+    }
+
+    /// Converts a timestamp expressed in seconds into a timestamp expressed at this precision.
+    /// Saturates on overflow.
+    pub fn timestamp_from_secs(&self, timestamp_secs: i64) -> i64 {
+        match self {
+            TimestampPrecision::Seconds => timestamp_secs,
+            TimestampPrecision::Milliseconds => timestamp_secs.saturating_mul(1_000),
+            TimestampPrecision::Microseconds => timestamp_secs.saturating_mul(1_000_000),
+        }
+    }
+}
+
 #[cfg(test)]
 impl Default for DefaultDocMapperBuilder {
     fn default() -> Self {
@@ -105,6 +177,12 @@ impl DefaultDocMapperBuilder {
                 self.mode
             );
         }
+        if self.mode != ModeType::Dynamic && self.type_conflict_policy.is_some() {
+            bail!(
+                "`type_conflict_policy` is only allowed with mode=dynamic. (Here mode=`{:?}`)",
+                self.mode
+            );
+        }
         Ok(match self.mode {
             ModeType::Lenient => Mode::Lenient,
             ModeType::Strict => Mode::Strict,
@@ -132,6 +210,7 @@ mod tests {
         assert!(default_mapper_builder.tag_fields.is_empty());
         assert_eq!(default_mapper_builder.mode, ModeType::Lenient);
         assert!(default_mapper_builder.dynamic_mapping.is_none());
+        assert!(default_mapper_builder.type_conflict_policy.is_none());
         assert!(default_mapper_builder.demux_field.is_none());
         assert!(default_mapper_builder.sort_by.is_none());
         assert_eq!(default_mapper_builder.store_source, false);
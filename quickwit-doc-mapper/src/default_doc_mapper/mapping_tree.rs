@@ -34,7 +34,7 @@ use crate::default_doc_mapper::field_mapping_entry::{
     QuickwitNumericOptions, QuickwitObjectOptions, QuickwitTextOptions,
 };
 use crate::default_doc_mapper::{FieldMappingType, QuickwitJsonOptions};
-use crate::{DocParsingError, FieldMappingEntry, ModeType};
+use crate::{DocParsingError, FieldMappingEntry, ModeType, TypeConflictPolicy};
 
 #[derive(Copy, Clone, PartialEq, Eq)]
 enum JsonType {
@@ -83,6 +83,53 @@ impl LeafType {
         }
     }
 
+    /// Attempts to coerce a JSON value into the JSON type expected by the leaf type.
+    ///
+    /// Returns the original value if no coercion applies.
+    fn coerce_json(&self, json_val: JsonValue) -> JsonValue {
+        match (self, json_val) {
+            (LeafType::Text(_), JsonValue::Number(number)) => JsonValue::String(number.to_string()),
+            (LeafType::Text(_), JsonValue::Bool(val)) => JsonValue::String(val.to_string()),
+            (LeafType::I64(_), JsonValue::String(text)) => match text.trim().parse::<i64>() {
+                Ok(val) => JsonValue::from(val),
+                Err(_) => JsonValue::String(text),
+            },
+            (LeafType::U64(_), JsonValue::String(text)) => match text.trim().parse::<u64>() {
+                Ok(val) => JsonValue::from(val),
+                Err(_) => JsonValue::String(text),
+            },
+            (LeafType::F64(_), JsonValue::String(text)) => match text.trim().parse::<f64>() {
+                Ok(val) => serde_json::Number::from_f64(val)
+                    .map(JsonValue::Number)
+                    .unwrap_or(JsonValue::String(text)),
+                Err(_) => JsonValue::String(text),
+            },
+            (LeafType::Bool(_), JsonValue::String(text)) => match text.trim().parse::<bool>() {
+                Ok(val) => JsonValue::Bool(val),
+                Err(_) => JsonValue::String(text),
+            },
+            (_, json_val) => json_val,
+        }
+    }
+
+    /// Converts a JSON value into a tantivy value, handling type conflicts according to
+    /// the given policy.
+    ///
+    /// Returns `Ok(None)` if the value should be skipped.
+    fn value_from_json_with_policy(
+        &self,
+        json_val: JsonValue,
+        type_conflict_policy: TypeConflictPolicy,
+    ) -> Result<Option<Value>, String> {
+        match type_conflict_policy {
+            TypeConflictPolicy::Error => self.value_from_json(json_val).map(Some),
+            TypeConflictPolicy::Coerce => {
+                self.value_from_json(self.coerce_json(json_val)).map(Some)
+            }
+            TypeConflictPolicy::SkipField => Ok(self.value_from_json(json_val).ok()),
+        }
+    }
+
     fn value_from_json(&self, json_val: serde_json::Value) -> Result<Value, String> {
         match self {
             LeafType::Text(_) => {
@@ -153,6 +200,7 @@ impl MappingLeaf {
     pub fn doc_from_json(
         &self,
         json_val: serde_json::Value,
+        type_conflict_policy: TypeConflictPolicy,
         document: &mut Document,
         path: &mut [String],
     ) -> Result<(), DocParsingError> {
@@ -169,19 +217,23 @@ impl MappingLeaf {
                     // We just ignore `null`.
                     continue;
                 }
-                let value = self
+                let value_opt = self
                     .typ
-                    .value_from_json(el_json_val)
+                    .value_from_json_with_policy(el_json_val, type_conflict_policy)
                     .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
-                document.add_field_value(self.field, value);
+                if let Some(value) = value_opt {
+                    document.add_field_value(self.field, value);
+                }
             }
             return Ok(());
         }
-        let value = self
+        let value_opt = self
             .typ
-            .value_from_json(json_val)
+            .value_from_json_with_policy(json_val, type_conflict_policy)
             .map_err(|err_msg| DocParsingError::ValueError(path.join("."), err_msg))?;
-        document.add_field_value(self.field, value);
+        if let Some(value) = value_opt {
+            document.add_field_value(self.field, value);
+        }
         Ok(())
     }
 
@@ -362,6 +414,7 @@ impl MappingNode {
         &self,
         json_obj: serde_json::Map<String, JsonValue>,
         mode: ModeType,
+        type_conflict_policy: TypeConflictPolicy,
         document: &mut Document,
         path: &mut Vec<String>,
         dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
@@ -369,7 +422,14 @@ impl MappingNode {
         for (field_name, val) in json_obj {
             if let Some(child_tree) = self.branches.get(&field_name) {
                 path.push(field_name);
-                child_tree.doc_from_json(val, mode, document, path, dynamic_json_obj)?;
+                child_tree.doc_from_json(
+                    val,
+                    mode,
+                    type_conflict_policy,
+                    document,
+                    path,
+                    dynamic_json_obj,
+                )?;
                 path.pop();
             } else {
                 match mode {
@@ -449,17 +509,27 @@ impl MappingTree {
         &self,
         json_value: serde_json::Value,
         mode: ModeType,
+        type_conflict_policy: TypeConflictPolicy,
         document: &mut Document,
         path: &mut Vec<String>,
         dynamic_json_obj: &mut serde_json::Map<String, JsonValue>,
     ) -> Result<(), DocParsingError> {
         match self {
             MappingTree::Leaf(mapping_leaf) => {
-                mapping_leaf.doc_from_json(json_value, document, path)
+                mapping_leaf.doc_from_json(json_value, type_conflict_policy, document, path)
             }
             MappingTree::Node(mapping_node) => {
                 if let JsonValue::Object(json_obj) = json_value {
-                    mapping_node.doc_from_json(json_obj, mode, document, path, dynamic_json_obj)
+                    mapping_node.doc_from_json(
+                        json_obj,
+                        mode,
+                        type_conflict_policy,
+                        document,
+                        path,
+                        dynamic_json_obj,
+                    )
+                } else if type_conflict_policy == TypeConflictPolicy::SkipField {
+                    Ok(())
                 } else {
                     Err(DocParsingError::ValueError(
                         path.join("."),
@@ -850,7 +920,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(json!([true, false, true]), &mut document, &mut path)
+            .doc_from_json(
+                json!([true, false, true]),
+                TypeConflictPolicy::Error,
+                &mut document,
+                &mut path,
+            )
             .unwrap();
         assert_eq!(document.len(), 3);
         let values: Vec<bool> = document.get_all(field).flat_map(Value::as_bool).collect();
@@ -869,7 +944,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(serde_json::json!([10u64, 20u64]), &mut document, &mut path)
+            .doc_from_json(
+                serde_json::json!([10u64, 20u64]),
+                TypeConflictPolicy::Error,
+                &mut document,
+                &mut path,
+            )
             .unwrap();
         assert_eq!(document.len(), 2);
         let values: Vec<i64> = document.get_all(field).flat_map(Value::as_i64).collect();
@@ -888,7 +968,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(serde_json::json!(null), &mut document, &mut path)
+            .doc_from_json(
+                serde_json::json!(null),
+                TypeConflictPolicy::Error,
+                &mut document,
+                &mut path,
+            )
             .unwrap();
         assert_eq!(document.len(), 0);
     }
@@ -905,7 +990,12 @@ mod tests {
         let mut document = Document::default();
         let mut path = Vec::new();
         leaf_entry
-            .doc_from_json(serde_json::json!(10u64), &mut document, &mut path)
+            .doc_from_json(
+                serde_json::json!(10u64),
+                TypeConflictPolicy::Error,
+                &mut document,
+                &mut path,
+            )
             .unwrap();
         assert_eq!(document.len(), 1);
         assert_eq!(document.get_first(field).unwrap().as_i64().unwrap(), 10i64);
@@ -925,6 +1015,7 @@ mod tests {
         let parse_err = leaf_entry
             .doc_from_json(
                 serde_json::json!([10u64, [1u64, 2u64]]),
+                TypeConflictPolicy::Error,
                 &mut document,
                 &mut path,
             )
@@ -937,6 +1028,49 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_coerce_json() {
+        let typ = LeafType::I64(QuickwitNumericOptions::default());
+        assert_eq!(typ.coerce_json(json!("-12")), json!(-12));
+        assert_eq!(typ.coerce_json(json!("twelve")), json!("twelve"));
+        let typ = LeafType::U64(QuickwitNumericOptions::default());
+        assert_eq!(typ.coerce_json(json!(" 12 ")), json!(12u64));
+        let typ = LeafType::F64(QuickwitNumericOptions::default());
+        assert_eq!(typ.coerce_json(json!("1.5")), json!(1.5));
+        let typ = LeafType::Bool(QuickwitNumericOptions::default());
+        assert_eq!(typ.coerce_json(json!("true")), json!(true));
+        let typ = LeafType::Text(QuickwitTextOptions::default());
+        assert_eq!(typ.coerce_json(json!(12)), json!("12"));
+        assert_eq!(typ.coerce_json(json!(false)), json!("false"));
+        assert_eq!(typ.coerce_json(json!([1, 2])), json!([1, 2]));
+    }
+
+    #[test]
+    fn test_parse_with_type_conflict_policy() {
+        let typ = LeafType::I64(QuickwitNumericOptions::default());
+        assert!(typ
+            .value_from_json_with_policy(json!("5"), TypeConflictPolicy::Error)
+            .is_err());
+        assert_eq!(
+            typ.value_from_json_with_policy(json!("5"), TypeConflictPolicy::Coerce)
+                .unwrap(),
+            Some(Value::I64(5))
+        );
+        assert!(typ
+            .value_from_json_with_policy(json!("five"), TypeConflictPolicy::Coerce)
+            .is_err());
+        assert_eq!(
+            typ.value_from_json_with_policy(json!("5"), TypeConflictPolicy::SkipField)
+                .unwrap(),
+            None
+        );
+        assert_eq!(
+            typ.value_from_json_with_policy(json!(5), TypeConflictPolicy::SkipField)
+                .unwrap(),
+            Some(Value::I64(5))
+        );
+    }
+
     #[test]
     fn test_parse_text() {
         let typ = LeafType::Text(QuickwitTextOptions::default());
@@ -1028,6 +1162,7 @@ mod tests {
                     "dGhpcyBpcyBhIGJhc2U2NCBlbmNvZGVkIHN0cmluZw==",
                     "dGhpcyBpcyBhIGJhc2U2NCBlbmNvZGVkIHN0cmluZw=="
                 ]),
+                TypeConflictPolicy::Error,
                 &mut document,
                 &mut path,
             )
@@ -29,7 +29,9 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 
 pub use self::default_mapper::{DefaultDocMapper, SortByConfig};
-pub use self::default_mapper_builder::{DefaultDocMapperBuilder, ModeType};
+pub use self::default_mapper_builder::{
+    DefaultDocMapperBuilder, ModeType, TimestampPrecision, TypeConflictPolicy,
+};
 pub use self::field_mapping_entry::{
     FieldMappingEntry, QuickwitJsonOptions, QuickwitNumericOptions, QuickwitTextOptions,
 };
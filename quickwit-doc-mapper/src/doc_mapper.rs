@@ -29,7 +29,9 @@ use tantivy::Document;
 
 pub type Partition = u64;
 
-use crate::{DocParsingError, QueryParserError, SortBy};
+use crate::{
+    DocParsingError, ModeType, QueryExplanation, QueryParserError, SortBy, TimestampPrecision,
+};
 
 /// The `DocMapper` trait defines the way of defining how a (json) document,
 /// and the fields it contains, are stored and indexed.
@@ -77,6 +79,10 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         request: &SearchRequest,
     ) -> Result<Box<dyn Query>, QueryParserError>;
 
+    /// Explains how the query of `request` is parsed and rewritten against `split_schema`,
+    /// without failing on syntax errors or unknown fields.
+    fn explain_query(&self, split_schema: Schema, request: &SearchRequest) -> QueryExplanation;
+
     /// Returns the default sort
     fn sort_by(&self) -> SortBy {
         SortBy::DocId
@@ -95,6 +101,11 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
         None
     }
 
+    /// Returns the unit of the timestamps extracted from the timestamp field.
+    fn timestamp_precision(&self) -> TimestampPrecision {
+        TimestampPrecision::default()
+    }
+
     /// Returns the tag field names
     fn tag_field_names(&self) -> BTreeSet<String> {
         Default::default()
@@ -104,6 +115,11 @@ pub trait DocMapper: Send + Sync + Debug + DynClone + 'static {
     fn demux_field_name(&self) -> Option<String> {
         None
     }
+
+    /// Returns how the fields that are not mapped are handled.
+    fn mode_type(&self) -> ModeType {
+        ModeType::default()
+    }
 }
 
 clone_trait_object!(DocMapper);
@@ -200,14 +216,8 @@ mod tests {
         let search_request = SearchRequest {
             index_id: "quickwit-index".to_string(),
             query: "json_field.toto.titi:hello".to_string(),
-            search_fields: vec![],
-            start_timestamp: None,
-            end_timestamp: None,
             max_hits: 10,
-            start_offset: 0,
-            sort_order: None,
-            sort_by_field: None,
-            aggregation_request: None,
+            ..Default::default()
         };
         let query = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -236,14 +246,9 @@ mod tests {
         let search_request = SearchRequest {
             index_id: "quickwit-index".to_string(),
             query: "text_field:hello".to_string(),
-            search_fields: vec![],
-            start_timestamp: None,
-            end_timestamp: None,
             max_hits: 10,
-            start_offset: 0,
-            sort_order: None,
             sort_by_field: Some("text_field".to_string()),
-            aggregation_request: None,
+            ..Default::default()
         };
         let query = doc_mapper.query(schema, &search_request).unwrap_err();
         assert_eq!(
@@ -270,14 +275,8 @@ mod tests {
         let search_request = SearchRequest {
             index_id: "quickwit-index".to_string(),
             query: "toto.titi:hello".to_string(),
-            search_fields: vec![],
-            start_timestamp: None,
-            end_timestamp: None,
             max_hits: 10,
-            start_offset: 0,
-            sort_order: None,
-            sort_by_field: None,
-            aggregation_request: None,
+            ..Default::default()
         };
         let query = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -304,14 +303,8 @@ mod tests {
         let search_request = SearchRequest {
             index_id: "quickwit-index".to_string(),
             query: "toto:5".to_string(),
-            search_fields: vec![],
-            start_timestamp: None,
-            end_timestamp: None,
             max_hits: 10,
-            start_offset: 0,
-            sort_order: None,
-            sort_by_field: None,
-            aggregation_request: None,
+            ..Default::default()
         };
         let query = doc_mapper.query(schema, &search_request).unwrap();
         assert_eq!(
@@ -50,6 +50,24 @@ pub enum DocParsingError {
     /// The document does not contains a field that is required.
     #[error("The document must contain field {0:?}. As a fast field, it is implicitly required.")]
     RequiredFastField(String),
+    /// The document does not contain the timestamp field. The field is identified by its full
+    /// path, e.g. `meta.ts` for the field `ts` of the object `meta`.
+    #[error("The document must contain the timestamp field `{0}`.")]
+    MissingTimestamp(String),
+}
+
+impl DocParsingError {
+    /// Returns the path of the field that could not be parsed, if the error relates to a field.
+    pub fn field_name(&self) -> Option<&str> {
+        match self {
+            DocParsingError::NotJsonObject(_) => None,
+            DocParsingError::ValueError(field_name, _)
+            | DocParsingError::NoSuchFieldInSchema(field_name)
+            | DocParsingError::MultiValuesNotSupported(field_name)
+            | DocParsingError::RequiredFastField(field_name)
+            | DocParsingError::MissingTimestamp(field_name) => Some(field_name),
+        }
+    }
 }
 
 impl From<TantivyDocParsingError> for DocParsingError {
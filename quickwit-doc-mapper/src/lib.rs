@@ -37,10 +37,11 @@ pub mod tag_pruning;
 
 pub use default_doc_mapper::{
     DefaultDocMapper, DefaultDocMapperBuilder, FieldMappingEntry, ModeType, QuickwitJsonOptions,
-    SortByConfig,
+    SortByConfig, TimestampPrecision, TypeConflictPolicy,
 };
 pub use doc_mapper::DocMapper;
 pub use error::{DocParsingError, QueryParserError};
+pub use query_builder::{compute_query_complexity, QueryComplexity, QueryExplanation};
 pub use sort_by::{SortBy, SortByField, SortOrder};
 pub use tokenizers::QUICKWIT_TOKENIZER_MANAGER;
 
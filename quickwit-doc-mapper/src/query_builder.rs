@@ -18,8 +18,10 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use quickwit_proto::SearchRequest;
+use regex::Regex;
+use serde::Serialize;
 use tantivy::query::{Query, QueryParser, QueryParserError as TantivyQueryParserError};
-use tantivy::schema::{Field, Schema};
+use tantivy::schema::{Field, FieldType, Schema};
 use tantivy_query_grammar::{UserInputAst, UserInputLeaf, UserInputLiteral};
 
 use crate::sort_by::validate_sort_by_field_name;
@@ -51,11 +53,14 @@ pub(crate) fn build_query(
         );
     }
 
-    let search_fields = if request.search_fields.is_empty() {
-        resolve_fields(&schema, default_field_names)?
-    } else {
-        resolve_fields(&schema, &request.search_fields)?
-    };
+    let search_fields =
+        resolve_search_fields(&schema, &request.search_fields, default_field_names)?;
+    if search_fields.is_empty() && needs_default_search_field(&user_input_ast) {
+        return Err(anyhow::anyhow!(
+            "No search field left after excluding fields and no field specified in query."
+        )
+        .into());
+    }
 
     let mut query_parser =
         QueryParser::new(schema, search_fields, QUICKWIT_TOKENIZER_MANAGER.clone());
@@ -64,6 +69,111 @@ pub(crate) fn build_query(
     Ok(query)
 }
 
+/// Describes how a user query is parsed and rewritten before being run by the searchers.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct QueryExplanation {
+    /// User query, as submitted.
+    pub query: String,
+    /// Abstract syntax tree of the parsed user query.
+    pub query_ast: Option<String>,
+    /// Fields searched by the clauses of the query that do not target a field explicitly. These
+    /// are the default search fields of the index merged with the request search fields.
+    pub search_fields: Vec<String>,
+    /// Fields referenced by the query or by the request search fields that do not exist in the
+    /// schema.
+    pub unknown_fields: Vec<String>,
+    /// Query actually run by the searchers.
+    pub rewritten_query: Option<String>,
+    /// Error that would make the search fail, if any.
+    pub error: Option<String>,
+}
+
+/// Explains how `build_query` parses and rewrites the request query. Unlike `build_query`, it
+/// does not fail: syntax errors and unknown fields are reported in the explanation.
+pub(crate) fn explain_query(
+    schema: Schema,
+    request: &SearchRequest,
+    default_field_names: &[String],
+) -> QueryExplanation {
+    let mut explanation = QueryExplanation {
+        query: request.query.clone(),
+        ..Default::default()
+    };
+    let user_input_ast = match tantivy_query_grammar::parse_query(&request.query) {
+        Ok(user_input_ast) => user_input_ast,
+        Err(_) => {
+            let error = TantivyQueryParserError::SyntaxError(request.query.to_string());
+            explanation.error = Some(QueryParserError::from(error).to_string());
+            return explanation;
+        }
+    };
+    explanation.query_ast = Some(format!("{:?}", user_input_ast));
+
+    let mut referenced_field_names = Vec::new();
+    collect_field_names(&user_input_ast, &mut referenced_field_names);
+    referenced_field_names.extend(
+        request
+            .search_fields
+            .iter()
+            .map(|search_field_name| search_field_name.trim_start_matches('-').to_string())
+            .filter(|search_field_name| !is_field_name_pattern(search_field_name)),
+    );
+    for field_name in referenced_field_names {
+        if schema.get_field(&field_name).is_none()
+            && !explanation.unknown_fields.contains(&field_name)
+        {
+            explanation.unknown_fields.push(field_name);
+        }
+    }
+    match resolve_search_fields(&schema, &request.search_fields, default_field_names) {
+        Ok(search_fields) => {
+            explanation.search_fields = search_fields
+                .into_iter()
+                .map(|field| schema.get_field_name(field).to_string())
+                .collect();
+        }
+        Err(error) => {
+            explanation.error = Some(error.to_string());
+            return explanation;
+        }
+    }
+    if !explanation.unknown_fields.is_empty() {
+        explanation.error = Some(format!(
+            "Field(s) `{}` do not exist in the schema.",
+            explanation.unknown_fields.join("`, `")
+        ));
+        return explanation;
+    }
+    match build_query(schema, request, default_field_names) {
+        Ok(query) => explanation.rewritten_query = Some(format!("{:?}", query)),
+        Err(error) => explanation.error = Some(error.to_string()),
+    }
+    explanation
+}
+
+/// Collects the names of the fields explicitly targeted by the clauses of the query.
+fn collect_field_names(user_input_ast: &UserInputAst, field_names: &mut Vec<String>) {
+    match user_input_ast {
+        UserInputAst::Clause(sub_queries) => {
+            for (_, sub_ast) in sub_queries {
+                collect_field_names(sub_ast, field_names);
+            }
+        }
+        UserInputAst::Boost(ast, _) => collect_field_names(ast, field_names),
+        UserInputAst::Leaf(leaf) => match &**leaf {
+            UserInputLeaf::Literal(UserInputLiteral {
+                field_name: Some(field_name),
+                ..
+            })
+            | UserInputLeaf::Range {
+                field: Some(field_name),
+                ..
+            } => field_names.push(field_name.clone()),
+            _ => {}
+        },
+    }
+}
+
 fn has_range_clause(user_input_ast: &UserInputAst) -> bool {
     match user_input_ast {
         UserInputAst::Clause(sub_queries) => {
@@ -79,6 +189,43 @@ fn has_range_clause(user_input_ast: &UserInputAst) -> bool {
     }
 }
 
+/// Depth and number of clauses of a parsed user query.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct QueryComplexity {
+    /// Number of nested levels of the query tree. A query made of a single term has a depth of 1.
+    pub depth: usize,
+    /// Number of leaf clauses (terms, phrases, ranges, ...) of the query tree.
+    pub num_clauses: usize,
+}
+
+/// Parses the user query and walks the resulting query tree to compute its depth and number of
+/// clauses.
+pub fn compute_query_complexity(user_query: &str) -> Result<QueryComplexity, QueryParserError> {
+    let user_input_ast = tantivy_query_grammar::parse_query(user_query)
+        .map_err(|_| TantivyQueryParserError::SyntaxError(user_query.to_string()))?;
+    Ok(query_complexity(&user_input_ast))
+}
+
+fn query_complexity(user_input_ast: &UserInputAst) -> QueryComplexity {
+    match user_input_ast {
+        UserInputAst::Clause(sub_queries) => {
+            let mut complexity = QueryComplexity::default();
+            for (_, sub_ast) in sub_queries {
+                let sub_complexity = query_complexity(sub_ast);
+                complexity.depth = complexity.depth.max(sub_complexity.depth);
+                complexity.num_clauses += sub_complexity.num_clauses;
+            }
+            complexity.depth += 1;
+            complexity
+        }
+        UserInputAst::Boost(ast, _) => query_complexity(ast),
+        UserInputAst::Leaf(_) => QueryComplexity {
+            depth: 1,
+            num_clauses: 1,
+        },
+    }
+}
+
 /// Tells if the query has a Term or Range node which does not
 /// specify a search field.
 fn needs_default_search_field(user_input_ast: &UserInputAst) -> bool {
@@ -100,9 +247,97 @@ fn needs_default_search_field(user_input_ast: &UserInputAst) -> bool {
     }
 }
 
+/// Returns true if the field name is a glob pattern, e.g. `attr_*`.
+pub(crate) fn is_field_name_pattern(field_name: &str) -> bool {
+    field_name.contains('*')
+}
+
+/// Returns the names of the indexed text fields of the schema matching the glob pattern. Reserved
+/// fields, i.e. fields starting with `_`, are never matched, nor are fields of other types: a
+/// pattern such as `server.*` only expands to the fields the query parser can search for free
+/// text.
+pub(crate) fn expand_field_name_pattern(
+    schema: &Schema,
+    field_name_pattern: &str,
+) -> anyhow::Result<Vec<String>> {
+    let regex_pattern = field_name_pattern
+        .split('*')
+        .map(regex::escape)
+        .collect::<Vec<_>>()
+        .join(".*");
+    let field_name_regex = Regex::new(&format!("^{regex_pattern}$"))?;
+    let field_names = schema
+        .fields()
+        .filter(|(_, field_entry)| {
+            matches!(
+                field_entry.field_type(),
+                FieldType::Str(text_options) if text_options.get_indexing_options().is_some()
+            )
+        })
+        .map(|(_, field_entry)| field_entry.name())
+        .filter(|field_name| !field_name.starts_with('_') && field_name_regex.is_match(field_name))
+        .map(|field_name| field_name.to_string())
+        .collect();
+    Ok(field_names)
+}
+
+/// Resolves the fields searched by the query. Search field names prefixed with `-` are excluded.
+/// Without exclusion, the search fields replace the default fields. With exclusions, they are
+/// resolved against the default fields instead: `title -body` searches the default fields plus
+/// `title` minus `body`.
+fn resolve_search_fields(
+    schema: &Schema,
+    search_field_names: &[String],
+    default_field_names: &[String],
+) -> anyhow::Result<Vec<Field>> {
+    let mut included_field_names = Vec::new();
+    let mut excluded_field_names = Vec::new();
+    for search_field_name in search_field_names {
+        if let Some(excluded_field_name) = search_field_name.strip_prefix('-') {
+            excluded_field_names.push(excluded_field_name.to_string());
+        } else {
+            included_field_names.push(search_field_name.clone());
+        }
+    }
+    if excluded_field_names.is_empty() {
+        if included_field_names.is_empty() {
+            return resolve_fields(schema, default_field_names);
+        }
+        return resolve_fields(schema, &included_field_names);
+    }
+    let field_names: Vec<String> = default_field_names
+        .iter()
+        .cloned()
+        .chain(included_field_names)
+        .collect();
+    let excluded_fields = resolve_fields(schema, &excluded_field_names)?;
+    let mut search_fields = Vec::new();
+    for field in resolve_fields(schema, &field_names)? {
+        if !excluded_fields.contains(&field) && !search_fields.contains(&field) {
+            search_fields.push(field);
+        }
+    }
+    Ok(search_fields)
+}
+
 fn resolve_fields(schema: &Schema, field_names: &[String]) -> anyhow::Result<Vec<Field>> {
     let mut fields = vec![];
     for field_name in field_names {
+        if is_field_name_pattern(field_name) {
+            let matching_field_names = expand_field_name_pattern(schema, field_name)?;
+            if matching_field_names.is_empty() {
+                return Err(TantivyQueryParserError::FieldDoesNotExist(field_name.clone()).into());
+            }
+            for matching_field_name in matching_field_names {
+                let field = schema
+                    .get_field(&matching_field_name)
+                    .expect("The field should exist in the schema.");
+                if !fields.contains(&field) {
+                    fields.push(field);
+                }
+            }
+            continue;
+        }
         let field = schema
             .get_field(field_name)
             .ok_or_else(|| TantivyQueryParserError::FieldDoesNotExist(field_name.clone()))?;
@@ -116,7 +351,9 @@ mod test {
     use quickwit_proto::SearchRequest;
     use tantivy::schema::{Schema, FAST, INDEXED, STORED, TEXT};
 
-    use super::build_query;
+    use super::{
+        build_query, compute_query_complexity, explain_query, resolve_fields, resolve_search_fields,
+    };
     use crate::{DYNAMIC_FIELD_NAME, SOURCE_FIELD_NAME};
 
     enum TestExpectation {
@@ -131,6 +368,10 @@ mod test {
         schema_builder.add_text_field("server.name", TEXT);
         schema_builder.add_text_field("server.mem", TEXT);
         schema_builder.add_bool_field("server.running", FAST | STORED | INDEXED);
+        schema_builder.add_u64_field("server.port", INDEXED);
+        schema_builder.add_text_field("server.comment", STORED);
+        schema_builder.add_text_field("attr_color", TEXT);
+        schema_builder.add_text_field("attr_size", TEXT);
         schema_builder.add_text_field(SOURCE_FIELD_NAME, TEXT);
         schema_builder.add_json_field(DYNAMIC_FIELD_NAME, TEXT);
         schema_builder.build()
@@ -144,16 +385,11 @@ mod test {
         expected: TestExpectation,
     ) -> anyhow::Result<()> {
         let request = SearchRequest {
-            aggregation_request: None,
             index_id: "test_index".to_string(),
             query: query_str.to_string(),
             search_fields,
-            start_timestamp: None,
-            end_timestamp: None,
             max_hits: 20,
-            start_offset: 0,
-            sort_order: None,
-            sort_by_field: None,
+            ..Default::default()
         };
 
         let default_field_names =
@@ -287,6 +523,110 @@ mod test {
         .unwrap();
     }
 
+    #[test]
+    fn test_resolve_fields_with_wildcard() {
+        let schema = make_schema();
+        let fields = resolve_fields(&schema, &["attr_*".to_string()]).unwrap();
+        let field_names: Vec<&str> = fields
+            .into_iter()
+            .map(|field| schema.get_field_name(field))
+            .collect();
+        assert_eq!(field_names, &["attr_color", "attr_size"]);
+
+        let fields = resolve_fields(&schema, &["server.*".to_string()]).unwrap();
+        let field_names: Vec<&str> = fields
+            .into_iter()
+            .map(|field| schema.get_field_name(field))
+            .collect();
+        // Only the indexed text fields of the object are searched: the bool, numeric, and
+        // non-indexed text fields are left out.
+        assert_eq!(field_names, &["server.name", "server.mem"]);
+
+        let error = resolve_fields(&schema, &["server.r*".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("'server.r*'"));
+
+        let fields = resolve_fields(&schema, &["*".to_string()]).unwrap();
+        assert!(fields
+            .iter()
+            .all(|&field| !schema.get_field_name(field).starts_with('_')));
+
+        let error = resolve_fields(&schema, &["foo_*".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("'foo_*'"));
+    }
+
+    #[test]
+    fn test_resolve_search_fields_with_exclusion() {
+        let schema = make_schema();
+        let default_field_names = vec!["title".to_string(), "desc".to_string()];
+        let resolve_field_names = |search_field_names: &[&str]| -> Vec<String> {
+            let search_field_names: Vec<String> = search_field_names
+                .iter()
+                .map(|search_field_name| search_field_name.to_string())
+                .collect();
+            resolve_search_fields(&schema, &search_field_names, &default_field_names)
+                .unwrap()
+                .into_iter()
+                .map(|field| schema.get_field_name(field).to_string())
+                .collect()
+        };
+        assert_eq!(resolve_field_names(&[]), &["title", "desc"]);
+        assert_eq!(resolve_field_names(&["attr_color"]), &["attr_color"]);
+        assert_eq!(resolve_field_names(&["-desc"]), &["title"]);
+        assert_eq!(
+            resolve_field_names(&["attr_*", "-desc", "-attr_size"]),
+            &["title", "attr_color"]
+        );
+        assert_eq!(resolve_field_names(&["desc", "-title"]), &["desc"]);
+        assert!(resolve_field_names(&["-title", "-desc"]).is_empty());
+
+        let error = resolve_search_fields(&schema, &["-foo".to_string()], &default_field_names)
+            .unwrap_err();
+        assert!(error.to_string().contains("'foo'"));
+    }
+
+    #[test]
+    fn test_build_query_with_excluded_search_fields() {
+        check_build_query(
+            "red",
+            vec!["-title".to_string(), "-desc".to_string()],
+            None,
+            TestExpectation::Err("No search field left"),
+        )
+        .unwrap();
+        check_build_query(
+            "red",
+            vec!["-title".to_string()],
+            None,
+            TestExpectation::Ok("TermQuery"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_build_query_with_wildcard_search_fields() {
+        check_build_query(
+            "red",
+            vec!["attr_*".to_string()],
+            None,
+            TestExpectation::Ok("TermQuery"),
+        )
+        .unwrap();
+        check_build_query(
+            "red",
+            vec![],
+            Some(vec!["attr_*".to_string()]),
+            TestExpectation::Ok("TermQuery"),
+        )
+        .unwrap();
+        check_build_query(
+            "red",
+            vec!["foo_*".to_string()],
+            None,
+            TestExpectation::Err("Field does not exists: 'foo_*'"),
+        )
+        .unwrap();
+    }
+
     #[test]
     #[should_panic(expected = "provided string was not `true` or `false`")]
     fn test_build_query_not_bool_should_fail() {
@@ -298,4 +638,74 @@ mod test {
         )
         .unwrap();
     }
+
+    #[test]
+    fn test_compute_query_complexity() {
+        let complexity = compute_query_complexity("title:foo").unwrap();
+        assert_eq!(complexity.depth, 1);
+        assert_eq!(complexity.num_clauses, 1);
+
+        let complexity = compute_query_complexity("title:foo AND desc:bar").unwrap();
+        assert_eq!(complexity.depth, 2);
+        assert_eq!(complexity.num_clauses, 2);
+
+        let shallow_complexity = compute_query_complexity("title:foo AND desc:bar").unwrap();
+        let deep_complexity =
+            compute_query_complexity("title:foo AND (desc:bar OR (title:baz AND desc:qux))")
+                .unwrap();
+        assert!(deep_complexity.depth > shallow_complexity.depth);
+        assert_eq!(deep_complexity.num_clauses, 4);
+
+        assert!(compute_query_complexity(":>").is_err());
+    }
+
+    fn explain_query_for_test(query: &str, search_fields: &[&str]) -> super::QueryExplanation {
+        let request = SearchRequest {
+            index_id: "test_index".to_string(),
+            query: query.to_string(),
+            search_fields: search_fields
+                .iter()
+                .map(|search_field| search_field.to_string())
+                .collect(),
+            ..Default::default()
+        };
+        let default_field_names = vec!["title".to_string(), "desc".to_string()];
+        explain_query(make_schema(), &request, &default_field_names)
+    }
+
+    #[test]
+    fn test_explain_query() {
+        let explanation = explain_query_for_test("foo AND title:bar", &[]);
+        assert!(explanation.query_ast.is_some());
+        assert_eq!(explanation.search_fields, ["title", "desc"]);
+        assert!(explanation.unknown_fields.is_empty());
+        assert!(explanation
+            .rewritten_query
+            .unwrap()
+            .contains("BooleanQuery"));
+        assert!(explanation.error.is_none());
+
+        let explanation = explain_query_for_test("foo", &["-desc", "attr_*"]);
+        assert_eq!(
+            explanation.search_fields,
+            ["title", "attr_color", "attr_size"]
+        );
+        assert!(explanation.error.is_none());
+
+        let explanation = explain_query_for_test("foo AND missing:bar", &[]);
+        assert!(explanation.query_ast.is_some());
+        assert_eq!(explanation.search_fields, ["title", "desc"]);
+        assert_eq!(explanation.unknown_fields, ["missing"]);
+        assert!(explanation.rewritten_query.is_none());
+        assert!(explanation.error.unwrap().contains("`missing`"));
+
+        let explanation = explain_query_for_test("foo", &["nosuchfield"]);
+        assert!(explanation.search_fields.is_empty());
+        assert_eq!(explanation.unknown_fields, ["nosuchfield"]);
+        assert!(explanation.error.is_some());
+
+        let explanation = explain_query_for_test(":>", &[]);
+        assert!(explanation.query_ast.is_none());
+        assert!(explanation.error.is_some());
+    }
 }
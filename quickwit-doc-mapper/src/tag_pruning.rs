@@ -20,6 +20,7 @@
 use std::collections::BTreeSet;
 use std::fmt::Display;
 
+use anyhow::bail;
 use tantivy::query::QueryParserError as TantivyQueryParserError;
 use tantivy_query_grammar::{Occur, UserInputAst, UserInputLeaf, UserInputLiteral};
 
@@ -50,6 +51,33 @@ pub fn extract_tags_from_query(user_query: &str) -> Result<Option<TagFilterAst>,
     Ok(user_input_ast_to_tags_filter_ast(user_input_ast))
 }
 
+/// Builds a TagFilterAst checking that a split may hold documents matching all the
+/// `required_tags`, formatted as `{field_name}:{value}`.
+///
+/// As for the tags extracted from a query, a split for which `field_name` is not a tag field
+/// always passes the filter.
+pub fn required_tags_filter(required_tags: &[String]) -> anyhow::Result<Option<TagFilterAst>> {
+    if required_tags.is_empty() {
+        return Ok(None);
+    }
+    let mut tag_filters = Vec::with_capacity(required_tags.len());
+    for required_tag in required_tags {
+        let (field_name, value) = match required_tag.split_once(':') {
+            Some((field_name, value)) if !field_name.is_empty() => (field_name, value),
+            _ => bail!(
+                "Tag `{}` must be formatted as `field_name:value`.",
+                required_tag
+            ),
+        };
+        tag_filters.push(expand_to_tag_ast(TermFilterAst::Term {
+            is_present: true,
+            field: field_name.to_string(),
+            value: value.to_string(),
+        }));
+    }
+    Ok(Some(TagFilterAst::And(tag_filters)))
+}
+
 /// Intermediary AST that may contain leaf that are
 /// equivalent to the "Uninformative" predicate.
 #[derive(Debug, PartialEq, Eq, Clone)]
@@ -465,6 +493,26 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_required_tags_filter() -> anyhow::Result<()> {
+        assert_eq!(super::required_tags_filter(&[])?, None);
+        assert_eq!(
+            super::required_tags_filter(&["user:bart".to_string()])?
+                .unwrap()
+                .to_string(),
+            "(¬user! ∨ user:bart)"
+        );
+        assert_eq!(
+            super::required_tags_filter(&["user:bart".to_string(), "lang:fr".to_string()])?
+                .unwrap()
+                .to_string(),
+            "(¬user! ∨ user:bart) ∧ (¬lang! ∨ lang:fr)"
+        );
+        assert!(super::required_tags_filter(&["user".to_string()]).is_err());
+        assert!(super::required_tags_filter(&[":bart".to_string()]).is_err());
+        Ok(())
+    }
+
     #[test]
     fn test_match_tag_field_name() {
         assert!(super::match_tag_field_name("tagfield", "tagfield:val"));
@@ -26,7 +26,7 @@ use quickwit_actors::{Actor, ActorContext, Handler};
 use quickwit_metastore::Metastore;
 use tracing::info;
 
-use crate::garbage_collection::run_garbage_collect;
+use crate::garbage_collection::{run_garbage_collect, FileEntry, SplitDeletionError};
 use crate::split_store::IndexingSplitStore;
 
 const RUN_INTERVAL: Duration = Duration::from_secs(60); // 1 minutes
@@ -76,6 +76,23 @@ impl GarbageCollector {
             counters: GarbageCollectorCounters::default(),
         }
     }
+
+    fn record_deleted_file_entries(&mut self, deleted_file_entries: &[FileEntry]) {
+        if deleted_file_entries.is_empty() {
+            return;
+        }
+        let deleted_files: HashSet<&str> = deleted_file_entries
+            .iter()
+            .map(|deleted_entry| deleted_entry.file_name.as_str())
+            .collect();
+        info!(deleted_files=?deleted_files, "gc-delete");
+
+        self.counters.num_deleted_files += deleted_file_entries.len();
+        self.counters.num_deleted_bytes += deleted_file_entries
+            .iter()
+            .map(|entry| entry.file_size_in_bytes as usize)
+            .sum::<usize>();
+    }
 }
 
 #[async_trait]
@@ -110,30 +127,32 @@ impl Handler<Loop> for GarbageCollector {
         info!("garbage-collect-operation");
         self.counters.num_passes += 1;
 
-        let deleted_file_entries = run_garbage_collect(
+        let gc_result = run_garbage_collect(
             &self.index_id,
             self.split_store.clone(),
             self.metastore.clone(),
             STAGED_GRACE_PERIOD,
             DELETION_GRACE_PERIOD,
+            None,
             false,
             Some(ctx),
         )
-        .await?;
-
-        if !deleted_file_entries.is_empty() {
-            let deleted_files: HashSet<&str> = deleted_file_entries
-                .iter()
-                .map(|deleted_entry| deleted_entry.file_name.as_str())
-                .collect();
-            info!(deleted_files=?deleted_files, "gc-delete");
-
-            self.counters.num_deleted_files += deleted_file_entries.len();
-            self.counters.num_deleted_bytes += deleted_file_entries
-                .iter()
-                .map(|entry| entry.file_size_in_bytes as usize)
-                .sum::<usize>();
-        }
+        .await;
+        let deleted_file_entries = match gc_result {
+            Ok(deleted_file_entries) => deleted_file_entries,
+            Err(error) => {
+                // The splits deleted before the failure are gone nonetheless.
+                if let Some(SplitDeletionError::StorageFailure {
+                    deleted_file_entries,
+                    ..
+                }) = error.downcast_ref::<SplitDeletionError>()
+                {
+                    self.record_deleted_file_entries(deleted_file_entries);
+                }
+                return Err(error.into());
+            }
+        };
+        self.record_deleted_file_entries(&deleted_file_entries);
 
         ctx.schedule_self_msg(RUN_INTERVAL, Loop).await;
         Ok(())
@@ -171,14 +190,16 @@ mod tests {
         let foo_index = "foo-index";
 
         let mut mock_storage = MockStorage::default();
-        mock_storage.expect_delete().times(3).returning(|path| {
-            assert!(
-                path == Path::new("a.split")
-                    || path == Path::new("b.split")
-                    || path == Path::new("c.split")
-            );
-            Ok(())
-        });
+        mock_storage
+            .expect_bulk_delete()
+            .times(2)
+            .returning(|paths: &[&Path]| {
+                assert!(
+                    paths == [Path::new("a.split"), Path::new("b.split")]
+                        || paths == [Path::new("c.split")]
+                );
+                Ok(())
+            });
 
         let mut mock_metastore = MockMetastore::default();
         mock_metastore.expect_list_splits().times(2).returning(
@@ -231,10 +252,13 @@ mod tests {
         let foo_index = "foo-index";
 
         let mut mock_storage = MockStorage::default();
-        mock_storage.expect_delete().times(4).returning(|path| {
-            assert!(path == Path::new("a.split") || path == Path::new("b.split"));
-            Ok(())
-        });
+        mock_storage
+            .expect_bulk_delete()
+            .times(2)
+            .returning(|paths: &[&Path]| {
+                assert_eq!(paths, [Path::new("a.split"), Path::new("b.split")]);
+                Ok(())
+            });
 
         let mut mock_metastore = MockMetastore::default();
         mock_metastore.expect_list_splits().times(4).returning(
@@ -19,10 +19,11 @@
 
 use std::collections::hash_map::Entry;
 use std::ops::RangeInclusive;
+use std::path::Path;
 use std::sync::Arc;
 use std::time::Instant;
 
-use anyhow::Context;
+use anyhow::{bail, Context};
 use async_trait::async_trait;
 use fail::fail_point;
 use fnv::FnvHashMap;
@@ -30,24 +31,38 @@ use itertools::Itertools;
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox, QueueCapacity};
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_config::IndexingSettings;
-use quickwit_doc_mapper::{DocMapper, DocParsingError, SortBy, QUICKWIT_TOKENIZER_MANAGER};
+use quickwit_doc_mapper::{
+    DocMapper, DocParsingError, SortBy, TimestampPrecision, QUICKWIT_TOKENIZER_MANAGER,
+};
 use quickwit_metastore::checkpoint::{IndexCheckpointDelta, SourceCheckpointDelta};
 use quickwit_metastore::Metastore;
+use quickwit_storage::Storage;
 use tantivy::schema::{Field, Schema, Value};
 use tantivy::store::{Compressor, ZstdCompressor};
 use tantivy::{Document, IndexBuilder, IndexSettings, IndexSortByField};
 use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
 use tracing::{info, warn};
 use ulid::Ulid;
 
 use crate::actors::Packager;
-use crate::models::{IndexedSplit, IndexedSplitBatch, IndexingDirectory, RawDocBatch};
+use crate::models::{
+    DocstoreCompression, IndexedSplit, IndexedSplitBatch, IndexingDirectory, RawDocBatch,
+};
 
 #[derive(Debug)]
 struct CommitTimeout {
     workbench_id: Ulid,
 }
 
+/// Scheduled after each batch when `commit_on_idle_after_secs` is set. It only triggers a commit
+/// if no other batch was added to the workbench in the meantime.
+#[derive(Debug)]
+struct CommitOnIdle {
+    workbench_id: Ulid,
+    num_batches: u64,
+}
+
 #[derive(Clone, Default, Debug, Eq, PartialEq)]
 pub struct IndexerCounters {
     /// Overall number of documents received, partitioned
@@ -72,9 +87,18 @@ pub struct IndexerCounters {
     /// Includes both valid and invalid documents.
     pub overall_num_bytes: u64,
 
+    /// Number of invalid documents per offending field, for the parse errors and the missing
+    /// fields that can be attributed to a field.
+    pub num_errors_per_field: FnvHashMap<String, u64>,
+
     /// Number of (valid) documents in the current workbench.
     /// This value is used to trigger commit and for observation.
     pub num_docs_in_workbench: u64,
+
+    /// Number of bytes of the (valid) documents in the current workbench.
+    /// Unlike `overall_num_bytes`, it is reset on commit. This value is used to trigger commit
+    /// and for observation.
+    pub num_bytes_in_workbench: u64,
 }
 
 impl IndexerCounters {
@@ -98,13 +122,17 @@ struct IndexerState {
     indexing_directory: IndexingDirectory,
     indexing_settings: IndexingSettings,
     timestamp_field_opt: Option<Field>,
+    timestamp_precision: TimestampPrecision,
     schema: Schema,
     index_settings: IndexSettings,
+    dead_letter_storage_opt: Option<Arc<dyn Storage>>,
+    /// If set, documents are only parsed and counted: no split is ever built.
+    dry_run: bool,
 }
 
 enum PrepareDocumentOutcome {
-    ParsingError,
-    MissingField,
+    ParsingError(DocParsingError),
+    MissingField(DocParsingError),
     Document {
         document: Document,
         timestamp_opt: Option<i64>,
@@ -126,7 +154,11 @@ impl IndexerState {
             ctx.progress().clone(),
             ctx.kill_switch().clone(),
         )?;
-        info!(split_id = %indexed_split.split_id, "new-split");
+        info!(
+            split_id = %indexed_split.split_id,
+            num_threads = self.indexing_settings.resources.num_threads(),
+            "new-split"
+        );
         Ok(indexed_split)
     }
 
@@ -152,7 +184,10 @@ impl IndexerState {
                 source_delta: SourceCheckpointDelta::default(),
             },
             indexed_splits: FnvHashMap::with_capacity_and_hasher(250, Default::default()),
+            full_indexed_splits: Vec::new(),
             workbench_id: Ulid::new(),
+            num_batches: 0,
+            dead_letters: Vec::new(),
             date_of_birth: Instant::now(),
         };
         Ok(workbench)
@@ -186,6 +221,8 @@ impl IndexerState {
     }
 
     fn prepare_document(&self, doc_json: String) -> PrepareDocumentOutcome {
+        // Merge the default fields before parsing so that the partition key can reference them.
+        let doc_json = apply_default_fields(doc_json, &self.indexing_settings.default_fields);
         // Parse the document
         let doc_parsing_result = self.doc_mapper.doc_from_json(doc_json);
         let (partition, document) = match doc_parsing_result {
@@ -193,8 +230,11 @@ impl IndexerState {
             Err(doc_parsing_error) => {
                 warn!(err=?doc_parsing_error);
                 return match doc_parsing_error {
-                    DocParsingError::RequiredFastField(_) => PrepareDocumentOutcome::MissingField,
-                    _ => PrepareDocumentOutcome::ParsingError,
+                    DocParsingError::RequiredFastField(_)
+                    | DocParsingError::MissingTimestamp(_) => {
+                        PrepareDocumentOutcome::MissingField(doc_parsing_error)
+                    }
+                    _ => PrepareDocumentOutcome::ParsingError(doc_parsing_error),
                 };
             }
         };
@@ -212,7 +252,10 @@ impl IndexerState {
         let timestamp_opt = document
             .get_first(timestamp_field)
             .and_then(|value| match value {
-                Value::Date(date_time) => Some(date_time.into_timestamp_secs()),
+                Value::Date(date_time) => Some(
+                    self.timestamp_precision
+                        .timestamp_from_date_time(*date_time),
+                ),
                 value => value.as_i64(),
             });
         assert!(
@@ -227,6 +270,35 @@ impl IndexerState {
         }
     }
 
+    /// Returns an error if the number of parse errors exceeds the configured `max_parse_errors`.
+    ///
+    /// `doc_byte_offset_opt` is the byte offset of the last document that failed to parse, if
+    /// the source provides one.
+    fn check_max_parse_errors(
+        &self,
+        counters: &IndexerCounters,
+        doc_byte_offset_opt: Option<u64>,
+    ) -> anyhow::Result<()> {
+        let max_parse_errors = match self.indexing_settings.max_parse_errors {
+            Some(max_parse_errors) => max_parse_errors,
+            None => return Ok(()),
+        };
+        if counters.num_parse_errors <= max_parse_errors {
+            return Ok(());
+        }
+        let location = doc_byte_offset_opt
+            .map(|doc_byte_offset| format!(" at approximate byte offset {}", doc_byte_offset))
+            .unwrap_or_default();
+        bail!(
+            "Too many parse errors for source `{}`: {} documents failed to parse, which exceeds \
+             `max_parse_errors` ({}). Last parse error{}.",
+            self.source_id,
+            counters.num_parse_errors,
+            max_parse_errors,
+            location
+        );
+    }
+
     async fn process_batch(
         &self,
         batch: RawDocBatch,
@@ -234,30 +306,61 @@ impl IndexerState {
         counters: &mut IndexerCounters,
         ctx: &ActorContext<Indexer>,
     ) -> Result<(), ActorExitStatus> {
+        if self.dry_run {
+            self.validate_batch(batch, counters, ctx);
+            return Ok(());
+        }
         let IndexingWorkbench {
             checkpoint_delta,
             indexed_splits,
+            full_indexed_splits,
+            num_batches,
+            dead_letters,
+            date_of_birth,
             ..
         } = self
             .get_or_create_workbench(indexing_workbench_opt, ctx)
             .await?;
+        *num_batches += 1;
+        *date_of_birth = (*date_of_birth).min(batch.received_at);
         checkpoint_delta
             .source_delta
             .extend(batch.checkpoint_delta)
             .context("Batch delta does not follow indexer checkpoint")?;
+        let mut doc_byte_offset_opt = batch.start_byte_offset;
         for doc_json in batch.docs {
             let doc_json_num_bytes = doc_json.len() as u64;
             counters.overall_num_bytes += doc_json_num_bytes;
+            let current_doc_byte_offset_opt = doc_byte_offset_opt;
+            doc_byte_offset_opt = doc_byte_offset_opt.map(|offset| offset + doc_json_num_bytes);
+            // The raw document is only kept around if it may end up in the dead letters.
+            let dead_letter_doc_json_opt = self
+                .dead_letter_storage_opt
+                .as_ref()
+                .map(|_| doc_json.clone());
             let prepared_doc = {
                 let _protect_zone = ctx.protect_zone();
                 self.prepare_document(doc_json)
             };
             match prepared_doc {
-                PrepareDocumentOutcome::ParsingError => {
+                PrepareDocumentOutcome::ParsingError(doc_parsing_error) => {
                     counters.num_parse_errors += 1;
+                    record_invalid_doc(
+                        &doc_parsing_error,
+                        dead_letter_doc_json_opt,
+                        counters,
+                        dead_letters,
+                    );
+                    self.check_max_parse_errors(counters, current_doc_byte_offset_opt)?;
                 }
-                PrepareDocumentOutcome::MissingField => {
+                PrepareDocumentOutcome::MissingField(doc_parsing_error) => {
                     counters.num_missing_fields += 1;
+                    record_invalid_doc(
+                        &doc_parsing_error,
+                        dead_letter_doc_json_opt,
+                        counters,
+                        dead_letters,
+                    );
                 }
                 PrepareDocumentOutcome::Document {
                     document,
@@ -268,42 +371,114 @@ impl IndexerState {
                         self.get_or_create_indexed_split(partition, indexed_splits, ctx)?;
                     indexed_split.docs_size_in_bytes += doc_json_num_bytes;
                     counters.num_docs_in_workbench += 1;
+                    counters.num_bytes_in_workbench += doc_json_num_bytes;
                     counters.num_valid_docs += 1;
                     indexed_split.num_docs += 1;
                     if let Some(timestamp) = timestamp_opt {
                         record_timestamp(timestamp, &mut indexed_split.time_range);
                     }
-                    let _protect_guard = ctx.protect_zone();
-                    indexed_split
-                        .index_writer
-                        .add_document(document)
-                        .context("Failed to add document.")?;
+                    {
+                        let _protect_guard = ctx.protect_zone();
+                        indexed_split
+                            .index_writer
+                            .add_document(document)
+                            .context("Failed to add document.")?;
+                    }
+                    let is_split_full = self
+                        .indexing_settings
+                        .max_num_docs_per_split
+                        .map(|max_num_docs_per_split| {
+                            indexed_split.num_docs as usize >= max_num_docs_per_split
+                        })
+                        .unwrap_or(false);
+                    if is_split_full {
+                        let mut full_indexed_split = indexed_splits
+                            .remove(&partition)
+                            .expect("The indexed split should be in the workbench.");
+                        {
+                            // Committing releases the memory held by the index writer.
+                            let _protect_guard = ctx.protect_zone();
+                            full_indexed_split
+                                .index_writer
+                                .commit()
+                                .context("Failed to commit full split.")?;
+                        }
+                        // The split is set aside rather than sent downstream right away: it is
+                        // published with the other splits of the workbench, together with the
+                        // checkpoint delta of the whole workbench, so no document is accounted
+                        // for twice.
+                        full_indexed_splits.push(full_indexed_split);
+                    }
                 }
             }
             ctx.record_progress();
         }
         Ok(())
     }
+
+    /// Parses the documents of a batch and updates the counters without creating any workbench,
+    /// so that nothing is ever sent to the packager. `max_parse_errors` is not enforced: a dry
+    /// run reports every invalid document.
+    fn validate_batch(
+        &self,
+        batch: RawDocBatch,
+        counters: &mut IndexerCounters,
+        ctx: &ActorContext<Indexer>,
+    ) {
+        for doc_json in batch.docs {
+            counters.overall_num_bytes += doc_json.len() as u64;
+            let prepared_doc = {
+                let _protect_zone = ctx.protect_zone();
+                self.prepare_document(doc_json)
+            };
+            match prepared_doc {
+                PrepareDocumentOutcome::ParsingError(doc_parsing_error) => {
+                    counters.num_parse_errors += 1;
+                    record_invalid_doc(&doc_parsing_error, None, counters, &mut Vec::new());
+                }
+                PrepareDocumentOutcome::MissingField(doc_parsing_error) => {
+                    counters.num_missing_fields += 1;
+                    record_invalid_doc(&doc_parsing_error, None, counters, &mut Vec::new());
+                }
+                PrepareDocumentOutcome::Document { .. } => {
+                    counters.num_valid_docs += 1;
+                }
+            }
+            ctx.record_progress();
+        }
+    }
 }
 
 /// A workbench hosts the set of `IndexedSplit` that will are being built.
 struct IndexingWorkbench {
     checkpoint_delta: IndexCheckpointDelta,
     indexed_splits: FnvHashMap<u64, IndexedSplit>,
+    /// Splits that reached `max_num_docs_per_split` and no longer accept documents. Their index
+    /// writers are already committed.
+    full_indexed_splits: Vec<IndexedSplit>,
     workbench_id: Ulid,
-    // TODO create this Instant on the source side to be more accurate.
-    // Right now this instant is used to compute time-to-search, but this
-    // does not include the amount of time a document could have been
-    // staying in the indexer queue or in the push api queue.
+    /// Number of batches added to the workbench, used to detect idleness.
+    num_batches: u64,
+    /// Invalid documents of the workbench, as JSON lines, written to the dead letter storage on
+    /// commit, or as soon as they exceed `DEAD_LETTERS_FLUSH_NUM_BYTES`.
+    dead_letters: Vec<u8>,
+    /// Earliest instant at which the sources received the documents of the workbench, used to
+    /// compute the time-to-search.
     date_of_birth: Instant,
 }
 
+/// Number of bytes of buffered dead letters beyond which they are written to the dead letter
+/// storage without waiting for the workbench to be committed.
+const DEAD_LETTERS_FLUSH_NUM_BYTES: usize = 10_000_000;
+
 pub struct Indexer {
     indexer_state: IndexerState,
     packager_mailbox: Mailbox<Packager>,
     indexing_workbench_opt: Option<IndexingWorkbench>,
     metastore: Arc<dyn Metastore>,
     counters: IndexerCounters,
+    /// Writes of dead letters to the dead letter storage that may still be in progress.
+    dead_letter_uploads: Vec<JoinHandle<()>>,
 }
 
 #[async_trait]
@@ -332,19 +507,84 @@ impl Actor for Indexer {
         ctx: &ActorContext<Self>,
     ) -> anyhow::Result<()> {
         match exit_status {
-            ActorExitStatus::DownstreamClosed
-            | ActorExitStatus::Killed
-            | ActorExitStatus::Failure(_)
-            | ActorExitStatus::Panicked => return Ok(()),
+            ActorExitStatus::DownstreamClosed | ActorExitStatus::Panicked => {}
+            ActorExitStatus::Failure(_) => {
+                // The workbench is dropped, but its invalid documents are still written: they are
+                // most needed when they are what made the pipeline fail.
+                let dead_letters = self
+                    .indexing_workbench_opt
+                    .as_mut()
+                    .map(|indexing_workbench| std::mem::take(&mut indexing_workbench.dead_letters))
+                    .unwrap_or_default();
+                self.flush_dead_letters(dead_letters);
+            }
+            ActorExitStatus::Killed => {
+                if self.indexer_state.indexing_settings.flush_on_shutdown {
+                    // The downstream actors may have been killed as well, in which case the
+                    // buffered documents are lost. This should not turn the exit into a panic.
+                    if let Err(error) = self.send_to_packager(CommitTrigger::Shutdown, ctx).await {
+                        warn!(error = ?error, "Failed to flush the indexer workbench on shutdown.");
+                    }
+                }
+            }
             ActorExitStatus::Quit | ActorExitStatus::Success => {
                 self.send_to_packager(CommitTrigger::NoMoreDocs, ctx)
                     .await?;
             }
         }
+        for dead_letter_upload in self.dead_letter_uploads.drain(..) {
+            let _ = dead_letter_upload.await;
+        }
         Ok(())
     }
 }
 
+/// Counts an invalid document against its offending field and, if dead letters are enabled,
+/// buffers the raw document along with its parse error as a JSON line.
+fn record_invalid_doc(
+    doc_parsing_error: &DocParsingError,
+    dead_letter_doc_json_opt: Option<String>,
+    counters: &mut IndexerCounters,
+    dead_letters: &mut Vec<u8>,
+) {
+    if let Some(field_name) = doc_parsing_error.field_name() {
+        *counters
+            .num_errors_per_field
+            .entry(field_name.to_string())
+            .or_default() += 1;
+    }
+    if let Some(doc_json) = dead_letter_doc_json_opt {
+        let dead_letter = serde_json::json!({
+            "error": doc_parsing_error.to_string(),
+            "doc": doc_json,
+        });
+        dead_letters.extend_from_slice(dead_letter.to_string().as_bytes());
+        dead_letters.push(b'\n');
+    }
+}
+
+/// Adds the default fields the document does not set. Documents that are not JSON objects are
+/// returned untouched and left to the doc mapper to reject.
+fn apply_default_fields(
+    doc_json: String,
+    default_fields: &serde_json::Map<String, serde_json::Value>,
+) -> String {
+    if default_fields.is_empty() {
+        return doc_json;
+    }
+    let mut doc_obj: serde_json::Map<String, serde_json::Value> =
+        match serde_json::from_str(&doc_json) {
+            Ok(doc_obj) => doc_obj,
+            Err(_) => return doc_json,
+        };
+    for (field_name, default_value) in default_fields {
+        doc_obj
+            .entry(field_name.clone())
+            .or_insert_with(|| default_value.clone());
+    }
+    serde_json::to_string(&doc_obj).expect("Serializing a JSON object should never fail.")
+}
+
 fn record_timestamp(timestamp: i64, time_range: &mut Option<RangeInclusive<i64>>) {
     let new_timestamp_range = match time_range.as_ref() {
         Some(range) => {
@@ -376,6 +616,31 @@ impl Handler<CommitTimeout> for Indexer {
     }
 }
 
+#[async_trait]
+impl Handler<CommitOnIdle> for Indexer {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        commit_on_idle: CommitOnIdle,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        let is_idle = match self.indexing_workbench_opt.as_ref() {
+            // A batch was received after this message was scheduled, or the workbench it was
+            // scheduled for has already been committed.
+            Some(indexing_workbench) => {
+                indexing_workbench.workbench_id == commit_on_idle.workbench_id
+                    && indexing_workbench.num_batches == commit_on_idle.num_batches
+            }
+            None => false,
+        };
+        if is_idle {
+            self.send_to_packager(CommitTrigger::Idle, ctx).await?;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<RawDocBatch> for Indexer {
     type Reply = ();
@@ -392,8 +657,11 @@ impl Handler<RawDocBatch> for Indexer {
 #[derive(Debug, Clone, Copy)]
 enum CommitTrigger {
     Timeout,
+    Idle,
     NoMoreDocs,
     NumDocsLimit,
+    NumBytesLimit,
+    Shutdown,
 }
 
 impl Indexer {
@@ -404,10 +672,12 @@ impl Indexer {
         metastore: Arc<dyn Metastore>,
         indexing_directory: IndexingDirectory,
         indexing_settings: IndexingSettings,
+        dead_letter_storage_opt: Option<Arc<dyn Storage>>,
         packager_mailbox: Mailbox<Packager>,
     ) -> Self {
         let schema = doc_mapper.schema();
         let timestamp_field_opt = doc_mapper.timestamp_field(&schema);
+        let timestamp_precision = doc_mapper.timestamp_precision();
         let sort_by_field_opt = match indexing_settings.sort_by() {
             SortBy::DocId => None,
             SortBy::FastField { field_name, order } => Some(IndexSortByField {
@@ -431,16 +701,44 @@ impl Indexer {
                 indexing_directory,
                 indexing_settings,
                 timestamp_field_opt,
+                timestamp_precision,
                 schema,
                 index_settings,
+                dead_letter_storage_opt,
+                dry_run: false,
             },
             packager_mailbox,
             indexing_workbench_opt: None,
             metastore,
             counters: IndexerCounters::default(),
+            dead_letter_uploads: Vec::new(),
         }
     }
 
+    /// Turns the indexer into a validator: documents are parsed and counted, but neither
+    /// indexed nor sent downstream. Merges, uploads and checkpoint updates never happen.
+    pub fn set_dry_run(mut self, dry_run: bool) -> Self {
+        self.indexer_state.dry_run = dry_run;
+        self
+    }
+
+    /// Overrides the docstore compression of the index config for the splits built by this
+    /// indexer.
+    pub fn set_docstore_compression(mut self, docstore_compression: DocstoreCompression) -> Self {
+        let default_compression_level = self
+            .indexer_state
+            .indexing_settings
+            .docstore_compression_level;
+        self.indexer_state.index_settings.docstore_compression = match docstore_compression {
+            DocstoreCompression::None => Compressor::None,
+            DocstoreCompression::Lz4 => Compressor::Lz4,
+            DocstoreCompression::Zstd { compression_level } => Compressor::Zstd(ZstdCompressor {
+                compression_level: Some(compression_level.unwrap_or(default_compression_level)),
+            }),
+        };
+        self
+    }
+
     async fn process_batch(
         &mut self,
         batch: RawDocBatch,
@@ -455,16 +753,68 @@ impl Indexer {
                 ctx,
             )
             .await?;
-        if self.counters.num_docs_in_workbench
-            >= self.indexer_state.indexing_settings.split_num_docs_target as u64
-        {
+        let dead_letters_opt = self
+            .indexing_workbench_opt
+            .as_mut()
+            .filter(|indexing_workbench| {
+                indexing_workbench.dead_letters.len() >= DEAD_LETTERS_FLUSH_NUM_BYTES
+            })
+            .map(|indexing_workbench| std::mem::take(&mut indexing_workbench.dead_letters));
+        if let Some(dead_letters) = dead_letters_opt {
+            self.flush_dead_letters(dead_letters);
+        }
+        let indexing_settings = &self.indexer_state.indexing_settings;
+        if self.counters.num_docs_in_workbench >= indexing_settings.split_num_docs_target as u64 {
             self.send_to_packager(CommitTrigger::NumDocsLimit, ctx)
                 .await?;
+        } else if let Some(split_num_bytes_target) = indexing_settings.split_num_bytes_target {
+            if self.counters.num_bytes_in_workbench >= split_num_bytes_target {
+                self.send_to_packager(CommitTrigger::NumBytesLimit, ctx)
+                    .await?;
+            }
+        }
+        if let Some(commit_on_idle_after) =
+            self.indexer_state.indexing_settings.commit_on_idle_after()
+        {
+            // If the workbench was just committed, there is nothing left to commit on idle.
+            if let Some(indexing_workbench) = self.indexing_workbench_opt.as_ref() {
+                let commit_on_idle_message = CommitOnIdle {
+                    workbench_id: indexing_workbench.workbench_id,
+                    num_batches: indexing_workbench.num_batches,
+                };
+                ctx.schedule_self_msg(commit_on_idle_after, commit_on_idle_message)
+                    .await;
+            }
         }
         fail_point!("indexer:batch:after");
         Ok(())
     }
 
+    /// Writes invalid documents to a new file of the dead letter storage. The write runs in a
+    /// separate task so that it does not hold up the indexing of the next documents, and is only
+    /// awaited when the indexer exits. A failure to write is logged but does not fail the
+    /// pipeline: the dead letters are lost.
+    fn flush_dead_letters(&mut self, dead_letters: Vec<u8>) {
+        let dead_letter_storage = match &self.indexer_state.dead_letter_storage_opt {
+            Some(dead_letter_storage) if !dead_letters.is_empty() => dead_letter_storage.clone(),
+            _ => return,
+        };
+        let dead_letter_path = Path::new(&self.indexer_state.index_id)
+            .join(&self.indexer_state.source_id)
+            .join(format!("{}.ndjson", Ulid::new()));
+        self.dead_letter_uploads
+            .retain(|dead_letter_upload| !dead_letter_upload.is_finished());
+        let dead_letter_upload = RuntimeType::NonBlocking.get_runtime_handle().spawn(async move {
+            if let Err(error) = dead_letter_storage
+                .put(&dead_letter_path, Box::new(dead_letters))
+                .await
+            {
+                warn!(error = ?error, path = %dead_letter_path.display(), "Failed to write the dead letters.");
+            }
+        });
+        self.dead_letter_uploads.push(dead_letter_upload);
+    }
+
     /// Extract the indexed split and send it to the Packager.
     async fn send_to_packager(
         &mut self,
@@ -474,6 +824,8 @@ impl Indexer {
         let IndexingWorkbench {
             checkpoint_delta,
             indexed_splits,
+            full_indexed_splits,
+            dead_letters,
             date_of_birth,
             ..
         } = if let Some(indexing_workbench) = self.indexing_workbench_opt.take() {
@@ -481,8 +833,12 @@ impl Indexer {
         } else {
             return Ok(());
         };
+        self.flush_dead_letters(dead_letters);
 
-        let splits: Vec<IndexedSplit> = indexed_splits.into_values().collect();
+        let splits: Vec<IndexedSplit> = full_indexed_splits
+            .into_iter()
+            .chain(indexed_splits.into_values())
+            .collect();
 
         // Avoid producing empty split, but still update the checkpoint to avoid
         // reprocessing the same faulty documents.
@@ -507,7 +863,7 @@ impl Indexer {
 
         let num_splits = splits.len() as u64;
         let split_ids = splits.iter().map(|split| &split.split_id).join(",");
-        info!(commit_trigger=?commit_trigger, split_ids=%split_ids, num_docs=self.counters.num_docs_in_workbench, "send-to-packager");
+        info!(commit_trigger=?commit_trigger, split_ids=%split_ids, num_docs=self.counters.num_docs_in_workbench, num_bytes=self.counters.num_bytes_in_workbench, "send-to-packager");
         ctx.send_message(
             &self.packager_mailbox,
             IndexedSplitBatch {
@@ -518,6 +874,7 @@ impl Indexer {
         )
         .await?;
         self.counters.num_docs_in_workbench = 0;
+        self.counters.num_bytes_in_workbench = 0;
         self.counters.num_splits_emitted += num_splits;
         self.counters.num_split_batches_emitted += 1;
         Ok(())
@@ -526,6 +883,7 @@ impl Indexer {
 
 #[cfg(test)]
 mod tests {
+    use std::ops::Range;
     use std::sync::Arc;
     use std::time::Duration;
 
@@ -533,9 +891,10 @@ mod tests {
     use quickwit_doc_mapper::{DefaultDocMapper, SortOrder};
     use quickwit_metastore::checkpoint::SourceCheckpointDelta;
     use quickwit_metastore::MockMetastore;
+    use quickwit_storage::RamStorage;
 
     use super::*;
-    use crate::actors::indexer::{record_timestamp, IndexerCounters};
+    use crate::actors::indexer::{apply_default_fields, record_timestamp, IndexerCounters};
     use crate::models::{IndexingDirectory, RawDocBatch};
 
     #[test]
@@ -575,6 +934,7 @@ mod tests {
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
         );
         let universe = Universe::new();
@@ -588,6 +948,8 @@ mod tests {
                         "{".to_string(),                    // invalid json
                     ],
                 checkpoint_delta: SourceCheckpointDelta::from(0..4),
+                start_byte_offset: None,
+                received_at: Instant::now(),
             })
             .await?;
         let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
@@ -599,8 +961,11 @@ mod tests {
                 num_valid_docs: 2,
                 num_splits_emitted: 0,
                 num_split_batches_emitted: 0,
+                // The first document is missing the `timestamp` fast field.
+                num_errors_per_field: [("timestamp".to_string(), 1)].into_iter().collect(),
                 num_docs_in_workbench: 2, //< we have not reached the commit limit yet.
-                overall_num_bytes: 387
+                overall_num_bytes: 387,
+                num_bytes_in_workbench: 274,
             }
         );
         indexer_mailbox
@@ -608,6 +973,8 @@ mod tests {
                 RawDocBatch {
                     docs: vec![r#"{"body": "happy3", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
                     checkpoint_delta: SourceCheckpointDelta::from(4..5),
+                    start_byte_offset: None,
+                    received_at: Instant::now(),
                 }
             )
             .await?;
@@ -620,8 +987,10 @@ mod tests {
                 num_valid_docs: 3,
                 num_splits_emitted: 1,
                 num_split_batches_emitted: 1,
+                num_errors_per_field: [("timestamp".to_string(), 1)].into_iter().collect(),
                 num_docs_in_workbench: 0, //< the num docs in split counter has been reset.
-                overall_num_bytes: 525
+                overall_num_bytes: 525,
+                num_bytes_in_workbench: 0,
             }
         );
         let output_messages = inbox.drain_for_test();
@@ -659,6 +1028,7 @@ mod tests {
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
         );
         let universe = Universe::new();
@@ -668,6 +1038,8 @@ mod tests {
                 RawDocBatch {
                     docs: vec![r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
                     checkpoint_delta: SourceCheckpointDelta::from(0..1),
+                    start_byte_offset: None,
+                    received_at: Instant::now(),
                 }
             )
             .await?;
@@ -680,8 +1052,10 @@ mod tests {
                 num_valid_docs: 1,
                 num_splits_emitted: 0,
                 num_split_batches_emitted: 0,
+                num_errors_per_field: FnvHashMap::default(),
                 num_docs_in_workbench: 1,
-                overall_num_bytes: 137
+                overall_num_bytes: 137,
+                num_bytes_in_workbench: 137,
             }
         );
         universe.simulate_time_shift(Duration::from_secs(61)).await;
@@ -694,8 +1068,10 @@ mod tests {
                 num_valid_docs: 1,
                 num_splits_emitted: 1,
                 num_split_batches_emitted: 1,
+                num_errors_per_field: FnvHashMap::default(),
                 num_docs_in_workbench: 0,
-                overall_num_bytes: 137
+                overall_num_bytes: 137,
+                num_bytes_in_workbench: 0,
             }
         );
         let output_messages = inbox.drain_for_test();
@@ -707,6 +1083,117 @@ mod tests {
         Ok(())
     }
 
+    fn raw_doc_batch_for_test(body: &str, checkpoint_delta: Range<u64>) -> RawDocBatch {
+        let doc_json = serde_json::json!({
+            "body": body,
+            "timestamp": 1628837062,
+            "response_date": "2021-12-19T16:39:57+00:00",
+            "response_time": 12,
+            "response_payload": "YWJj",
+        });
+        RawDocBatch {
+            docs: vec![doc_json.to_string()],
+            checkpoint_delta: SourceCheckpointDelta::from(checkpoint_delta),
+            start_byte_offset: None,
+            received_at: Instant::now(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_indexer_commit_on_idle() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.commit_on_idle_after_secs = Some(5);
+        let (mailbox, inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(raw_doc_batch_for_test("happy", 0..1))
+            .await?;
+        indexer_handle.process_pending_and_observe().await;
+        universe.simulate_time_shift(Duration::from_secs(3)).await;
+        indexer_mailbox
+            .send_message(raw_doc_batch_for_test("happy2", 1..2))
+            .await?;
+        indexer_handle.process_pending_and_observe().await;
+        // The first batch was received 6 seconds ago, but the second one only 3 seconds ago.
+        universe.simulate_time_shift(Duration::from_secs(3)).await;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 2);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 0);
+
+        universe.simulate_time_shift(Duration::from_secs(3)).await;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 0);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+
+        // Nothing is left to commit.
+        universe.simulate_time_shift(Duration::from_secs(10)).await;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+
+        let output_messages = inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let indexed_split_batch = output_messages[0]
+            .downcast_ref::<IndexedSplitBatch>()
+            .unwrap();
+        assert_eq!(indexed_split_batch.splits[0].num_docs, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_commit_on_idle_after_num_docs_commit() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.split_num_docs_target = 2;
+        indexing_settings.commit_on_idle_after_secs = Some(5);
+        let (mailbox, inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(raw_doc_batch_for_test("happy", 0..1))
+            .await?;
+        indexer_mailbox
+            .send_message(raw_doc_batch_for_test("happy2", 1..2))
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+
+        // The message scheduled after the first batch targets a workbench that was already
+        // committed on its number of docs.
+        universe.simulate_time_shift(Duration::from_secs(6)).await;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+        assert_eq!(inbox.drain_for_test().len(), 1);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_indexer_eof() -> anyhow::Result<()> {
         quickwit_common::setup_logging_for_tests();
@@ -728,6 +1215,7 @@ mod tests {
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
         );
         let universe = Universe::new();
@@ -737,6 +1225,8 @@ mod tests {
                 RawDocBatch {
                     docs: vec![r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
                     checkpoint_delta: SourceCheckpointDelta::from(0..1),
+                    start_byte_offset: None,
+                    received_at: Instant::now(),
                 }
             )
             .await?;
@@ -751,8 +1241,10 @@ mod tests {
                 num_valid_docs: 1,
                 num_splits_emitted: 1,
                 num_split_batches_emitted: 1,
+                num_errors_per_field: FnvHashMap::default(),
                 num_docs_in_workbench: 0,
-                overall_num_bytes: 137
+                overall_num_bytes: 137,
+                num_bytes_in_workbench: 0,
             }
         );
         let output_messages = inbox.drain_for_test();
@@ -768,33 +1260,16 @@ mod tests {
         Ok(())
     }
 
-    const DOCMAPPER_WITH_PARTITION_JSON: &str = r#"
-        {
-            "tag_fields": ["tenant"],
-            "partition_key": "tenant",
-            "field_mappings": [
-                { "name": "tenant", "type": "text", "tokenizer": "raw", "indexed": true },
-                { "name": "body", "type": "text" }
-            ]
-        }"#;
-
     #[tokio::test]
-    async fn test_indexer_partitioning() -> anyhow::Result<()> {
+    async fn test_indexer_dry_run() -> anyhow::Result<()> {
         quickwit_common::setup_logging_for_tests();
-        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
-            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_PARTITION_JSON).unwrap(),
-        );
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
         let indexing_directory = IndexingDirectory::for_test().await?;
-        let indexing_settings = IndexingSettings::for_test();
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.split_num_docs_target = 1;
+        indexing_settings.max_parse_errors = Some(0);
         let (mailbox, inbox) = create_test_mailbox();
-        let mut metastore = MockMetastore::default();
-        metastore
-            .expect_publish_splits()
-            .returning(move |_, splits, _, _| {
-                assert!(splits.is_empty());
-                Ok(())
-            });
-
+        let metastore = MockMetastore::default();
         let indexer = Indexer::new(
             "test-index".to_string(),
             doc_mapper,
@@ -802,58 +1277,674 @@ mod tests {
             Arc::new(metastore),
             indexing_directory,
             indexing_settings,
+            None,
             mailbox,
-        );
+        )
+        .set_dry_run(true);
         let universe = Universe::new();
         let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
         indexer_mailbox
             .send_message(RawDocBatch {
                 docs: vec![
-                    r#"{"tenant": "tenant_1", "body": "first doc for tenant 1"}"#.to_string(),
-                    r#"{"tenant": "tenant_2", "body": "first doc for tenant 2"}"#.to_string(),
-                    r#"{"tenant": "tenant_1", "body": "second doc for tenant 1"}"#.to_string(),
-                ],
-                checkpoint_delta: SourceCheckpointDelta::from(0..2),
+                        r#"{"body": "happy", "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string(), // missing timestamp
+                        r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:59+00:00", "response_time": 2, "response_payload": "YWJj"}"#.to_string(), // ok
+                        r#"{"body": "happy2", "timestamp": 1628837062, "response_date": "2021-12-19T16:40:57+00:00", "response_time": 13, "response_payload": "YWJj"}"#.to_string(), // ok
+                        "{".to_string(),                    // invalid json
+                    ],
+                checkpoint_delta: SourceCheckpointDelta::from(0..4),
+                start_byte_offset: None,
+                received_at: Instant::now(),
             })
             .await?;
-
-        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
-        assert_eq!(
-            indexer_counters,
-            IndexerCounters {
-                num_parse_errors: 0,
-                num_missing_fields: 0,
-                num_valid_docs: 3,
-                num_docs_in_workbench: 3,
-                num_splits_emitted: 0,
-                num_split_batches_emitted: 0,
-                overall_num_bytes: 169
-            }
-        );
         universe.send_exit_with_success(&indexer_mailbox).await?;
         let (exit_status, indexer_counters) = indexer_handle.join().await;
-        assert!(matches!(exit_status, ActorExitStatus::Success));
+        assert!(exit_status.is_success());
         assert_eq!(
             indexer_counters,
             IndexerCounters {
-                num_parse_errors: 0,
-                num_missing_fields: 0,
-                num_valid_docs: 3,
+                num_parse_errors: 1,
+                num_missing_fields: 1,
+                num_valid_docs: 2,
+                num_splits_emitted: 0,
+                num_split_batches_emitted: 0,
+                num_errors_per_field: [("timestamp".to_string(), 1)].into_iter().collect(),
                 num_docs_in_workbench: 0,
-                num_splits_emitted: 2,
-                num_split_batches_emitted: 1,
-                overall_num_bytes: 169
+                overall_num_bytes: 387,
+                num_bytes_in_workbench: 0,
             }
         );
+        assert!(inbox.drain_for_test().is_empty());
+        Ok(())
+    }
 
-        let output_messages = inbox.drain_for_test();
-        assert_eq!(output_messages.len(), 1);
-
-        let indexed_split_batch = output_messages[0]
-            .downcast_ref::<IndexedSplitBatch>()
-            .unwrap();
-        assert_eq!(indexed_split_batch.splits.len(), 2);
+    #[tokio::test]
+    async fn test_indexer_set_docstore_compression() -> anyhow::Result<()> {
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.docstore_compression_level = 5;
+        let (mailbox, _inbox) = create_test_mailbox();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(MockMetastore::default()),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let indexer = indexer.set_docstore_compression(DocstoreCompression::Lz4);
+        assert_eq!(
+            indexer.indexer_state.index_settings.docstore_compression,
+            Compressor::Lz4
+        );
+        let indexer = indexer.set_docstore_compression(DocstoreCompression::Zstd {
+            compression_level: None,
+        });
+        assert_eq!(
+            indexer.indexer_state.index_settings.docstore_compression,
+            Compressor::Zstd(ZstdCompressor {
+                compression_level: Some(5)
+            })
+        );
+        let indexer = indexer.set_docstore_compression(DocstoreCompression::Zstd {
+            compression_level: Some(9),
+        });
+        assert_eq!(
+            indexer.indexer_state.index_settings.docstore_compression,
+            Compressor::Zstd(ZstdCompressor {
+                compression_level: Some(9)
+            })
+        );
+        let indexer = indexer.set_docstore_compression(DocstoreCompression::None);
+        assert_eq!(
+            indexer.indexer_state.index_settings.docstore_compression,
+            Compressor::None
+        );
+        Ok(())
+    }
 
+    #[tokio::test]
+    async fn test_indexer_flush_on_shutdown() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.flush_on_shutdown = true;
+        let (mailbox, inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(
+                RawDocBatch {
+                    docs: vec![r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
+                    checkpoint_delta: SourceCheckpointDelta::from(0..1),
+                    start_byte_offset: None,
+                    received_at: Instant::now(),
+                }
+            )
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 1);
+        let (exit_status, indexer_counters) = indexer_handle.kill().await;
+        assert!(matches!(exit_status, ActorExitStatus::Killed));
+        assert_eq!(indexer_counters.num_docs_in_workbench, 0);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+        let output_messages = inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let indexed_split_batch = output_messages[0]
+            .downcast_ref::<IndexedSplitBatch>()
+            .unwrap();
+        assert_eq!(indexed_split_batch.splits[0].num_docs, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_num_bytes_limit() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.split_num_docs_target = 10;
+        indexing_settings.split_num_bytes_target = Some(200);
+        let (mailbox, inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(
+                RawDocBatch {
+                    docs: vec![r#"{"body": "happy", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
+                    checkpoint_delta: SourceCheckpointDelta::from(0..1),
+                    start_byte_offset: None,
+                    received_at: Instant::now(),
+                }
+            )
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_docs_in_workbench, 1);
+        assert_eq!(indexer_counters.num_bytes_in_workbench, 137);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 0);
+        indexer_mailbox
+            .send_message(
+                RawDocBatch {
+                    docs: vec![r#"{"body": "happy2", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string()],
+                    checkpoint_delta: SourceCheckpointDelta::from(1..2),
+                    start_byte_offset: None,
+                    received_at: Instant::now(),
+                }
+            )
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(
+            indexer_counters,
+            IndexerCounters {
+                num_parse_errors: 0,
+                num_missing_fields: 0,
+                num_valid_docs: 2,
+                num_splits_emitted: 1,
+                num_split_batches_emitted: 1,
+                num_errors_per_field: FnvHashMap::default(),
+                num_docs_in_workbench: 0, //< the commit fired before reaching 10 docs.
+                overall_num_bytes: 275,
+                num_bytes_in_workbench: 0,
+            }
+        );
+        let output_messages = inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let indexed_split_batch = output_messages[0]
+            .downcast_ref::<IndexedSplitBatch>()
+            .unwrap();
+        assert_eq!(indexed_split_batch.splits[0].num_docs, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_max_num_docs_per_split() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let received_at = Instant::now();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.split_num_docs_target = 5;
+        indexing_settings.max_num_docs_per_split = Some(2);
+        let (mailbox, inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        let docs = (0..5)
+            .map(|doc_ord| {
+                format!(
+                    r#"{{"body": "happy{doc_ord}", "timestamp": 1628837062, "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}}"#
+                )
+            })
+            .collect();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs,
+                checkpoint_delta: SourceCheckpointDelta::from(0..5),
+                start_byte_offset: None,
+                received_at,
+            })
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_valid_docs, 5);
+        assert_eq!(indexer_counters.num_docs_in_workbench, 0);
+        assert_eq!(indexer_counters.num_splits_emitted, 3);
+        assert_eq!(indexer_counters.num_split_batches_emitted, 1);
+        let indexed_split_batches = inbox.drain_for_test_typed::<IndexedSplitBatch>();
+        assert_eq!(indexed_split_batches.len(), 1);
+        let indexed_split_batch = &indexed_split_batches[0];
+        let split_num_docs: Vec<u64> = indexed_split_batch
+            .splits
+            .iter()
+            .map(|split| split.num_docs)
+            .collect();
+        assert_eq!(split_num_docs, [2, 2, 1]);
+        // The time-to-search is measured from the reception of the batch by the source.
+        assert_eq!(indexed_split_batch.date_of_birth, received_at);
+        // The checkpoint delta of the batch is carried once by the whole batch of splits.
+        let checkpoint_delta = indexed_split_batch.checkpoint_delta.as_ref().unwrap();
+        assert_eq!(checkpoint_delta.source_id, "source-id");
+        assert_eq!(
+            checkpoint_delta.source_delta,
+            SourceCheckpointDelta::from(0..5)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_max_parse_errors() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.max_parse_errors = Some(1);
+        let (mailbox, _inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec!["{\n".to_string(), "}\n".to_string()],
+                checkpoint_delta: SourceCheckpointDelta::from(0..2),
+                start_byte_offset: Some(100),
+                received_at: Instant::now(),
+            })
+            .await?;
+        let (exit_status, indexer_counters) = indexer_handle.join().await;
+        assert_eq!(indexer_counters.num_parse_errors, 2);
+        let error = match exit_status {
+            ActorExitStatus::Failure(error) => error,
+            exit_status => panic!("Expected a failure, got `{:?}`.", exit_status),
+        };
+        assert!(error
+            .to_string()
+            .contains("2 documents failed to parse, which exceeds `max_parse_errors` (1)"));
+        assert!(error.to_string().contains("approximate byte offset 102"));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_dead_letters() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let indexing_settings = IndexingSettings::for_test();
+        let (mailbox, _inbox) = create_test_mailbox();
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_publish_splits()
+            .returning(move |_, splits, _, _| {
+                assert!(splits.is_empty());
+                Ok(())
+            });
+        let dead_letter_storage = Arc::new(RamStorage::default());
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            Some(dead_letter_storage.clone()),
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec![
+                    r#"{"body": "happy", "response_date": "2021-12-19T16:39:57+00:00", "response_time": 12, "response_payload": "YWJj"}"#.to_string(), // missing timestamp
+                    "{".to_string(), // invalid json
+                ],
+                checkpoint_delta: SourceCheckpointDelta::from(0..2),
+                start_byte_offset: None,
+                received_at: Instant::now(),
+            })
+            .await?;
+        universe.send_exit_with_success(&indexer_mailbox).await?;
+        let (exit_status, indexer_counters) = indexer_handle.join().await;
+        assert!(exit_status.is_success());
+        assert_eq!(indexer_counters.num_invalid_docs(), 2);
+
+        let dead_letter_paths = dead_letter_storage.list_files().await;
+        assert_eq!(dead_letter_paths.len(), 1);
+        assert!(dead_letter_paths[0].starts_with("test-index/source-id"));
+        let dead_letters_bytes = dead_letter_storage.get_all(&dead_letter_paths[0]).await?;
+        let dead_letters_str = std::str::from_utf8(dead_letters_bytes.as_slice())?;
+        let dead_letters: Vec<serde_json::Value> = dead_letters_str
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        assert_eq!(dead_letters.len(), 2);
+        assert!(dead_letters[0]["error"]
+            .as_str()
+            .unwrap()
+            .contains("timestamp"));
+        assert!(dead_letters[0]["doc"]
+            .as_str()
+            .unwrap()
+            .starts_with(r#"{"body": "happy""#));
+        assert_eq!(dead_letters[1]["doc"], "{");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_indexer_dead_letters_on_max_parse_errors() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper = Arc::new(quickwit_doc_mapper::default_doc_mapper_for_tests());
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.max_parse_errors = Some(1);
+        let (mailbox, _inbox) = create_test_mailbox();
+        let metastore = MockMetastore::default();
+        let dead_letter_storage = Arc::new(RamStorage::default());
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            Some(dead_letter_storage.clone()),
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec!["{".to_string(), "}".to_string()],
+                checkpoint_delta: SourceCheckpointDelta::from(0..2),
+                start_byte_offset: None,
+                received_at: Instant::now(),
+            })
+            .await?;
+        let (exit_status, _indexer_counters) = indexer_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Failure(_)));
+        // The dead letters of the documents that made the indexer fail are written.
+        let dead_letter_paths = dead_letter_storage.list_files().await;
+        assert_eq!(dead_letter_paths.len(), 1);
+        let dead_letters_bytes = dead_letter_storage.get_all(&dead_letter_paths[0]).await?;
+        let dead_letters_str = std::str::from_utf8(dead_letters_bytes.as_slice())?;
+        let dead_letter_docs: Vec<String> = dead_letters_str
+            .lines()
+            .map(|dead_letter_str| {
+                let dead_letter: serde_json::Value = serde_json::from_str(dead_letter_str)?;
+                Ok(dead_letter["doc"].as_str().unwrap().to_string())
+            })
+            .collect::<anyhow::Result<_>>()?;
+        assert_eq!(dead_letter_docs, ["{", "}"]);
+        Ok(())
+    }
+
+    const DOCMAPPER_WITH_PARTITION_JSON: &str = r#"
+        {
+            "tag_fields": ["tenant"],
+            "partition_key": "tenant",
+            "field_mappings": [
+                { "name": "tenant", "type": "text", "tokenizer": "raw", "indexed": true },
+                { "name": "body", "type": "text" }
+            ]
+        }"#;
+
+    #[tokio::test]
+    async fn test_indexer_partitioning() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
+            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_PARTITION_JSON).unwrap(),
+        );
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let indexing_settings = IndexingSettings::for_test();
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_publish_splits()
+            .returning(move |_, splits, _, _| {
+                assert!(splits.is_empty());
+                Ok(())
+            });
+
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec![
+                    r#"{"tenant": "tenant_1", "body": "first doc for tenant 1"}"#.to_string(),
+                    r#"{"tenant": "tenant_2", "body": "first doc for tenant 2"}"#.to_string(),
+                    r#"{"tenant": "tenant_1", "body": "second doc for tenant 1"}"#.to_string(),
+                ],
+                checkpoint_delta: SourceCheckpointDelta::from(0..2),
+                start_byte_offset: None,
+                received_at: Instant::now(),
+            })
+            .await?;
+
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(
+            indexer_counters,
+            IndexerCounters {
+                num_parse_errors: 0,
+                num_missing_fields: 0,
+                num_valid_docs: 3,
+                num_docs_in_workbench: 3,
+                num_splits_emitted: 0,
+                num_split_batches_emitted: 0,
+                num_errors_per_field: FnvHashMap::default(),
+                overall_num_bytes: 169,
+                num_bytes_in_workbench: 169,
+            }
+        );
+        universe.send_exit_with_success(&indexer_mailbox).await?;
+        let (exit_status, indexer_counters) = indexer_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Success));
+        assert_eq!(
+            indexer_counters,
+            IndexerCounters {
+                num_parse_errors: 0,
+                num_missing_fields: 0,
+                num_valid_docs: 3,
+                num_docs_in_workbench: 0,
+                num_splits_emitted: 2,
+                num_split_batches_emitted: 1,
+                num_errors_per_field: FnvHashMap::default(),
+                overall_num_bytes: 169,
+                num_bytes_in_workbench: 0,
+            }
+        );
+
+        let output_messages = inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+
+        let indexed_split_batch = output_messages[0]
+            .downcast_ref::<IndexedSplitBatch>()
+            .unwrap();
+        assert_eq!(indexed_split_batch.splits.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_default_fields() {
+        let default_fields = serde_json::json!({"env": "prod", "region": "eu"})
+            .as_object()
+            .unwrap()
+            .clone();
+        let doc_json = apply_default_fields(
+            r#"{"body": "happy", "env": "dev"}"#.to_string(),
+            &default_fields,
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&doc_json).unwrap(),
+            serde_json::json!({"body": "happy", "env": "dev", "region": "eu"})
+        );
+        assert_eq!(apply_default_fields("{".to_string(), &default_fields), "{");
+        assert_eq!(
+            apply_default_fields(r#"{"body": "happy"}"#.to_string(), &serde_json::Map::new()),
+            r#"{"body": "happy"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_indexer_partitioning_on_default_field() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
+            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_PARTITION_JSON).unwrap(),
+        );
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings
+            .default_fields
+            .insert("tenant".to_string(), serde_json::json!("tenant_1"));
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_publish_splits()
+            .returning(move |_, splits, _, _| {
+                assert!(splits.is_empty());
+                Ok(())
+            });
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec![
+                    r#"{"body": "first doc for tenant 1"}"#.to_string(),
+                    r#"{"tenant": "tenant_2", "body": "first doc for tenant 2"}"#.to_string(),
+                    r#"{"body": "second doc for tenant 1"}"#.to_string(),
+                ],
+                checkpoint_delta: SourceCheckpointDelta::from(0..3),
+                start_byte_offset: None,
+                received_at: Instant::now(),
+            })
+            .await?;
+        universe.send_exit_with_success(&indexer_mailbox).await?;
+        let (exit_status, indexer_counters) = indexer_handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Success));
+        assert_eq!(indexer_counters.num_valid_docs, 3);
+        assert_eq!(indexer_counters.num_splits_emitted, 2);
+
+        let output_messages = inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let indexed_split_batch = output_messages[0]
+            .downcast_ref::<IndexedSplitBatch>()
+            .unwrap();
+        let mut split_num_docs: Vec<u64> = indexed_split_batch
+            .splits
+            .iter()
+            .map(|split| split.num_docs)
+            .collect();
+        split_num_docs.sort_unstable();
+        assert_eq!(split_num_docs, vec![1, 2]);
+        Ok(())
+    }
+
+    const DOCMAPPER_WITH_MILLIS_TIMESTAMP_JSON: &str = r#"
+        {
+            "timestamp_field": "ts",
+            "timestamp_precision": "milliseconds",
+            "field_mappings": [
+                { "name": "ts", "type": "datetime", "input_formats": ["rfc3339"], "fast": true },
+                { "name": "body", "type": "text" }
+            ]
+        }"#;
+
+    #[tokio::test]
+    async fn test_indexer_timestamp_precision_milliseconds() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let doc_mapper: Arc<dyn DocMapper> = Arc::new(
+            serde_json::from_str::<DefaultDocMapper>(DOCMAPPER_WITH_MILLIS_TIMESTAMP_JSON).unwrap(),
+        );
+        let indexing_directory = IndexingDirectory::for_test().await?;
+        let mut indexing_settings = IndexingSettings::for_test();
+        indexing_settings.split_num_docs_target = 2;
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_publish_splits()
+            .returning(move |_, splits, _, _| {
+                assert!(splits.is_empty());
+                Ok(())
+            });
+
+        let indexer = Indexer::new(
+            "test-index".to_string(),
+            doc_mapper,
+            "source-id".to_string(),
+            Arc::new(metastore),
+            indexing_directory,
+            indexing_settings,
+            None,
+            mailbox,
+        );
+        let universe = Universe::new();
+        let (indexer_mailbox, indexer_handle) = universe.spawn_actor(indexer).spawn();
+        indexer_mailbox
+            .send_message(RawDocBatch {
+                docs: vec![
+                    r#"{"ts": "2021-12-19T16:39:57.100Z", "body": "first doc"}"#.to_string(),
+                    r#"{"ts": "2021-12-19T16:39:57.900Z", "body": "second doc"}"#.to_string(),
+                ],
+                checkpoint_delta: SourceCheckpointDelta::from(0..2),
+                start_byte_offset: None,
+                received_at: Instant::now(),
+            })
+            .await?;
+        let indexer_counters = indexer_handle.process_pending_and_observe().await.state;
+        assert_eq!(indexer_counters.num_valid_docs, 2);
+        assert_eq!(indexer_counters.num_splits_emitted, 1);
+
+        let output_messages = inbox.drain_for_test();
+        assert_eq!(output_messages.len(), 1);
+        let indexed_split_batch = output_messages[0]
+            .downcast_ref::<IndexedSplitBatch>()
+            .unwrap();
+        assert_eq!(
+            indexed_split_batch.splits[0].time_range,
+            Some(1639931997100..=1639931997900)
+        );
         Ok(())
     }
 }
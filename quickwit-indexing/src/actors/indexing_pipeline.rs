@@ -42,7 +42,9 @@ use crate::actors::{
     GarbageCollector, Indexer, MergeExecutor, MergePlanner, NamedField, Packager, Publisher,
     Uploader,
 };
-use crate::models::{IndexingDirectory, IndexingStatistics, Observe};
+use crate::models::{
+    CancelMerges, DocstoreCompression, IndexingDirectory, IndexingStatistics, Observe,
+};
 use crate::source::{quickwit_supported_sources, SourceActor};
 use crate::split_store::{IndexingSplitStore, IndexingSplitStoreParams};
 use crate::{MergePolicy, StableMultitenantWithTimestampMergePolicy};
@@ -200,6 +202,11 @@ impl IndexingPipeline {
             merge_enabled: self.params.indexing_settings.merge_enabled,
             merge_factor: self.params.indexing_settings.merge_policy.merge_factor,
             max_merge_factor: self.params.indexing_settings.merge_policy.max_merge_factor,
+            balance_split_sizes: self
+                .params
+                .indexing_settings
+                .merge_policy
+                .balance_split_sizes,
             split_num_docs_target: self.params.indexing_settings.split_num_docs_target,
             ..Default::default()
         };
@@ -377,15 +384,20 @@ impl IndexingPipeline {
             .set_kill_switch(self.kill_switch.clone())
             .spawn();
         // Indexer
-        let indexer = Indexer::new(
+        let mut indexer = Indexer::new(
             self.params.index_id.clone(),
             self.params.doc_mapper.clone(),
             self.params.source.source_id.clone(),
             self.params.metastore.clone(),
             self.params.indexing_directory.clone(),
             self.params.indexing_settings.clone(),
+            self.params.dead_letter_storage_opt.clone(),
             packager_mailbox,
-        );
+        )
+        .set_dry_run(self.params.dry_run);
+        if let Some(docstore_compression) = self.params.docstore_compression_opt {
+            indexer = indexer.set_docstore_compression(docstore_compression);
+        }
         let (indexer_mailbox, indexer_handler) = ctx
             .spawn_actor(indexer)
             .set_kill_switch(self.kill_switch.clone())
@@ -502,6 +514,28 @@ impl Handler<Observe> for IndexingPipeline {
     }
 }
 
+#[async_trait]
+impl Handler<CancelMerges> for IndexingPipeline {
+    type Reply = ();
+
+    async fn handle(
+        &mut self,
+        _: CancelMerges,
+        ctx: &ActorContext<Self>,
+    ) -> Result<(), ActorExitStatus> {
+        if let Some(handlers) = self.handlers.as_ref() {
+            info!(index=%self.params.index_id, gen=self.generation(), "cancel-merges");
+            // The downstream actors exit on their own once they have processed their pending
+            // messages and all their mailboxes are dropped.
+            let _ = ctx
+                .send_exit_with_success(handlers.merge_planner.mailbox())
+                .await;
+            let _ = ctx.send_exit_with_success(handlers.source.mailbox()).await;
+        }
+        Ok(())
+    }
+}
+
 #[async_trait]
 impl Handler<Supervise> for IndexingPipeline {
     type Reply = ();
@@ -574,6 +608,13 @@ pub struct IndexingPipelineParams {
     pub split_store_max_num_splits: usize,
     pub metastore: Arc<dyn Metastore>,
     pub storage: Arc<dyn Storage>,
+    /// Storage receiving the documents that fail to parse, if any.
+    pub dead_letter_storage_opt: Option<Arc<dyn Storage>>,
+    /// If set, the indexer only parses and counts the documents read from the source: no split
+    /// is created and the source checkpoint is left untouched.
+    pub dry_run: bool,
+    /// Docstore compression used instead of the one of the index config, if any.
+    pub docstore_compression_opt: Option<DocstoreCompression>,
 }
 
 impl IndexingPipelineParams {
@@ -585,6 +626,7 @@ impl IndexingPipelineParams {
         split_store_max_num_splits: usize,
         metastore: Arc<dyn Metastore>,
         storage: Arc<dyn Storage>,
+        dead_letter_storage_opt: Option<Arc<dyn Storage>>,
     ) -> anyhow::Result<Self> {
         let doc_mapper = build_doc_mapper(
             &index_metadata.doc_mapping,
@@ -605,6 +647,9 @@ impl IndexingPipelineParams {
             split_store_max_num_splits,
             metastore,
             storage,
+            dead_letter_storage_opt,
+            dry_run: false,
+            docstore_compression_opt: None,
         })
     }
 }
@@ -710,6 +755,9 @@ mod tests {
             source: source_config,
             metastore: Arc::new(metastore),
             storage: Arc::new(RamStorage::default()),
+            dead_letter_storage_opt: None,
+            dry_run: false,
+            docstore_compression_opt: None,
         };
         let pipeline = IndexingPipeline::new(indexing_pipeline_params);
         let (_pipeline_mailbox, pipeline_handler) = universe.spawn_actor(pipeline).spawn();
@@ -792,6 +840,9 @@ mod tests {
             source,
             metastore: Arc::new(metastore),
             storage: Arc::new(RamStorage::default()),
+            dead_letter_storage_opt: None,
+            dry_run: false,
+            docstore_compression_opt: None,
         };
         let pipeline = IndexingPipeline::new(pipeline_params);
         let (_pipeline_mailbox, pipeline_handler) = universe.spawn_actor(pipeline).spawn();
@@ -802,4 +853,53 @@ mod tests {
         assert_eq!(pipeline_statistics.num_published_splits, 1);
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_indexing_pipeline_cancel_merges() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_index_metadata()
+            .withf(|index_id| index_id == "test-index")
+            .returning(|_| {
+                Ok(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+            });
+        metastore
+            .expect_list_splits()
+            .returning(|_, _, _, _| Ok(Vec::new()));
+        metastore
+            .expect_mark_splits_for_deletion()
+            .returning(|_, _| Ok(()));
+        let universe = Universe::new();
+        // The void source never terminates on its own.
+        let source = SourceConfig {
+            source_id: "test-source".to_string(),
+            source_params: SourceParams::void(),
+        };
+        let pipeline_params = IndexingPipelineParams {
+            index_id: "test-index".to_string(),
+            doc_mapper: Arc::new(default_doc_mapper_for_tests()),
+            indexing_directory: IndexingDirectory::for_test().await?,
+            indexing_settings: IndexingSettings::for_test(),
+            split_store_max_num_bytes: 10_000_000,
+            split_store_max_num_splits: 100,
+            source,
+            metastore: Arc::new(metastore),
+            storage: Arc::new(RamStorage::default()),
+            dead_letter_storage_opt: None,
+            dry_run: false,
+            docstore_compression_opt: None,
+        };
+        let pipeline = IndexingPipeline::new(pipeline_params);
+        let (pipeline_mailbox, pipeline_handler) = universe.spawn_actor(pipeline).spawn();
+        pipeline_mailbox.ask(CancelMerges).await?;
+        let (pipeline_exit_status, pipeline_statistics) =
+            tokio::time::timeout(Duration::from_secs(30), pipeline_handler.join()).await?;
+        assert!(pipeline_exit_status.is_success());
+        assert_eq!(pipeline_statistics.generation, 1);
+        assert_eq!(pipeline_statistics.num_published_splits, 0);
+        Ok(())
+    }
 }
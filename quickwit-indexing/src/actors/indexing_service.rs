@@ -26,8 +26,9 @@ use quickwit_actors::{
     Actor, ActorContext, ActorExitStatus, ActorHandle, Handler, Health, Mailbox, Observation,
     Supervisable,
 };
+use quickwit_common::uri::Uri;
 use quickwit_config::{
-    IndexerConfig, IngestApiSourceParams, SourceConfig, SourceParams, VecSourceParams,
+    IndexerConfig, IngestApiSourceParams, MergePolicy, SourceConfig, SourceParams, VecSourceParams,
 };
 use quickwit_ingest_api::IngestApiService;
 use quickwit_metastore::{IndexMetadata, Metastore, MetastoreError};
@@ -38,8 +39,8 @@ use thiserror::Error;
 use tracing::{error, info};
 
 use crate::models::{
-    DetachPipeline, IndexingPipelineId, Observe, ObservePipeline, ShutdownPipeline,
-    SpawnMergePipeline, SpawnPipeline, SpawnPipelinesForIndex,
+    DetachPipeline, DocstoreCompression, IndexingPipelineId, Observe, ObservePipeline,
+    ShutdownPipeline, SpawnMergePipeline, SpawnPipeline, SpawnPipelinesForIndex,
 };
 use crate::{IndexingPipeline, IndexingPipelineParams, IndexingStatistics};
 
@@ -73,6 +74,7 @@ pub struct IndexingService {
     indexing_dir_path: PathBuf,
     split_store_max_num_bytes: usize,
     split_store_max_num_splits: usize,
+    dead_letter_uri: Option<Uri>,
     metastore: Arc<dyn Metastore>,
     storage_resolver: StorageUriResolver,
     pipeline_handles: HashMap<IndexingPipelineId, ActorHandle<IndexingPipeline>>,
@@ -98,6 +100,7 @@ impl IndexingService {
             split_store_max_num_bytes: indexer_config.split_store_max_num_bytes.get_bytes()
                 as usize,
             split_store_max_num_splits: indexer_config.split_store_max_num_splits,
+            dead_letter_uri: indexer_config.dead_letter_uri,
             metastore,
             storage_resolver,
             pipeline_handles: Default::default(),
@@ -138,15 +141,29 @@ impl IndexingService {
         &mut self,
         index_id: String,
         source: SourceConfig,
+        dry_run: bool,
+        docstore_compression_opt: Option<DocstoreCompression>,
         ctx: &ActorContext<Self>,
     ) -> Result<IndexingPipelineId, IndexingServiceError> {
         let pipeline_id = IndexingPipelineId {
             index_id,
             source_id: source.source_id.clone(),
         };
-        let index_metadata = self.index_metadata(&pipeline_id.index_id, ctx).await?;
-        self.spawn_pipeline_inner(pipeline_id.clone(), index_metadata, source, ctx)
-            .await?;
+        let mut index_metadata = self.index_metadata(&pipeline_id.index_id, ctx).await?;
+        if dry_run {
+            // A dry run must leave the published splits of the index untouched.
+            index_metadata.indexing_settings.merge_enabled = false;
+            index_metadata.indexing_settings.demux_enabled = false;
+        }
+        self.spawn_pipeline_inner(
+            pipeline_id.clone(),
+            index_metadata,
+            source,
+            dry_run,
+            docstore_compression_opt,
+            ctx,
+        )
+        .await?;
         Ok(pipeline_id)
     }
 
@@ -171,6 +188,8 @@ impl IndexingService {
                 pipeline_id.clone(),
                 index_metadata.clone(),
                 source.clone(),
+                false,
+                None,
                 ctx,
             )
             .await?;
@@ -202,6 +221,8 @@ impl IndexingService {
         pipeline_id: IndexingPipelineId,
         index_metadata: IndexMetadata,
         source: SourceConfig,
+        dry_run: bool,
+        docstore_compression_opt: Option<DocstoreCompression>,
         ctx: &ActorContext<Self>,
     ) -> Result<(), IndexingServiceError> {
         if self.pipeline_handles.contains_key(&pipeline_id) {
@@ -210,8 +231,16 @@ impl IndexingService {
                 source_id: pipeline_id.source_id.clone(),
             });
         }
-        let storage = self.storage_resolver.resolve(&index_metadata.index_uri)?;
-        let pipeline_params = IndexingPipelineParams::try_new(
+        let storage = self.storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?;
+        let dead_letter_storage_opt = self
+            .dead_letter_uri
+            .as_ref()
+            .map(|dead_letter_uri| self.storage_resolver.resolve(dead_letter_uri))
+            .transpose()?;
+        let mut pipeline_params = IndexingPipelineParams::try_new(
             index_metadata,
             source,
             self.indexing_dir_path.clone(),
@@ -219,9 +248,12 @@ impl IndexingService {
             self.split_store_max_num_splits,
             self.metastore.clone(),
             storage,
+            dead_letter_storage_opt,
         )
         .await
         .map_err(IndexingServiceError::InvalidParams)?;
+        pipeline_params.dry_run = dry_run;
+        pipeline_params.docstore_compression_opt = docstore_compression_opt;
 
         let pipeline = IndexingPipeline::new(pipeline_params);
         let (_pipeline_mailbox, pipeline_handle) = ctx.spawn_actor(pipeline).spawn();
@@ -253,6 +285,8 @@ impl IndexingService {
             ingest_api_pipeline_id.clone(),
             index_metadata.clone(),
             ingest_api_source,
+            false,
+            None,
             ctx,
         )
         .await?;
@@ -265,6 +299,8 @@ impl IndexingService {
         index_id: String,
         merge_enabled: bool,
         demux_enabled: bool,
+        merge_policy_opt: Option<MergePolicy>,
+        split_num_docs_target_opt: Option<usize>,
         ctx: &ActorContext<Self>,
     ) -> Result<IndexingPipelineId, IndexingServiceError> {
         let pipeline_id = IndexingPipelineId {
@@ -274,13 +310,26 @@ impl IndexingService {
         let mut index_metadata = self.index_metadata(&pipeline_id.index_id, ctx).await?;
         index_metadata.indexing_settings.merge_enabled = merge_enabled;
         index_metadata.indexing_settings.demux_enabled = demux_enabled;
+        if let Some(merge_policy) = merge_policy_opt {
+            index_metadata.indexing_settings.merge_policy = merge_policy;
+        }
+        if let Some(split_num_docs_target) = split_num_docs_target_opt {
+            index_metadata.indexing_settings.split_num_docs_target = split_num_docs_target;
+        }
 
         let source = SourceConfig {
             source_id: pipeline_id.source_id.clone(),
             source_params: SourceParams::Vec(VecSourceParams::default()),
         };
-        self.spawn_pipeline_inner(pipeline_id.clone(), index_metadata, source, ctx)
-            .await?;
+        self.spawn_pipeline_inner(
+            pipeline_id.clone(),
+            index_metadata,
+            source,
+            false,
+            None,
+            ctx,
+        )
+        .await?;
         Ok(pipeline_id)
     }
 
@@ -385,6 +434,8 @@ impl Handler<SpawnMergePipeline> for IndexingService {
                 message.index_id,
                 message.merge_enabled,
                 message.demux_enabled,
+                message.merge_policy_opt,
+                message.split_num_docs_target_opt,
                 ctx,
             )
             .await)
@@ -400,7 +451,13 @@ impl Handler<SpawnPipeline> for IndexingService {
         ctx: &ActorContext<Self>,
     ) -> Result<Result<IndexingPipelineId, IndexingServiceError>, ActorExitStatus> {
         Ok(self
-            .spawn_pipeline(message.index_id, message.source, ctx)
+            .spawn_pipeline(
+                message.index_id,
+                message.source,
+                message.dry_run,
+                message.docstore_compression_opt,
+                ctx,
+            )
             .await)
     }
 }
@@ -503,6 +560,8 @@ mod tests {
         let spawn_pipeline_msg = SpawnPipeline {
             index_id: index_id.clone(),
             source: source_1.clone(),
+            dry_run: false,
+            docstore_compression_opt: None,
         };
         let pipeline_id1 = indexing_server_mailbox
             .ask_for_res(spawn_pipeline_msg.clone())
@@ -575,6 +634,8 @@ mod tests {
                 index_id: index_id.clone(),
                 merge_enabled: true,
                 demux_enabled: false,
+                merge_policy_opt: None,
+                split_num_docs_target_opt: None,
             })
             .await
             .unwrap();
@@ -604,6 +665,8 @@ mod tests {
             .ask_for_res(SpawnPipeline {
                 index_id: index_id.clone(),
                 source: source_3,
+                dry_run: false,
+                docstore_compression_opt: None,
             })
             .await
             .unwrap();
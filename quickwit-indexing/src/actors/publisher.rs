@@ -27,6 +27,7 @@ use quickwit_metastore::Metastore;
 use tracing::info;
 
 use crate::actors::{GarbageCollector, MergePlanner};
+use crate::metrics::INDEXING_METRICS;
 use crate::models::{NewSplits, SplitUpdate};
 use crate::source::{SourceActor, SuggestTruncate};
 
@@ -147,7 +148,14 @@ impl Handler<SplitUpdate> for Publisher {
             .await
             .context("Failed to publish splits.")?;
 
-        info!(new_splits=?split_ids, tts=%date_of_birth.elapsed().as_secs_f32(), checkpoint_delta=?checkpoint_delta_opt, "publish-new-splits");
+        let time_to_search = date_of_birth.elapsed();
+        info!(new_splits=?split_ids, tts=%time_to_search.as_secs_f32(), checkpoint_delta=?checkpoint_delta_opt, "publish-new-splits");
+        // Merged splits replace other splits and contain documents that were already searchable.
+        if replaced_split_ids.is_empty() {
+            INDEXING_METRICS
+                .time_to_search_seconds
+                .observe(time_to_search.as_secs_f64());
+        }
         if let Some(source_mailbox) = self.source_mailbox_opt.as_ref() {
             if let Some(checkpoint) = checkpoint_delta_opt {
                 // We voluntarily do not log anything here.
@@ -17,29 +17,95 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-use futures::StreamExt;
+use futures::{stream, StreamExt};
+use once_cell::sync::OnceCell;
 use quickwit_actors::ActorContext;
+use quickwit_config::IndexerConfig;
 use quickwit_metastore::{Metastore, MetastoreError, SplitMetadata, SplitState};
-use quickwit_storage::StorageError;
+use quickwit_storage::{FileMetadata, StorageError};
 use serde::Serialize;
 use thiserror::Error;
 use time::OffsetDateTime;
-use tracing::error;
+use tokio::sync::Semaphore;
+use tracing::{error, warn};
 
 use crate::actors::GarbageCollector;
 use crate::split_store::IndexingSplitStore;
 
-const MAX_CONCURRENT_STORAGE_REQUESTS: usize = if cfg!(test) { 2 } else { 10 };
+/// Maximum number of splits deleted with a single `bulk_delete` call, between which the progress
+/// of the garbage collection is recorded.
+const MAX_NUM_SPLITS_PER_BULK_DELETE: usize = if cfg!(test) { 2 } else { 1_000 };
+
+/// This semaphore bounds the number of delete operations that can happen concurrently across all
+/// the garbage collection runs of the node.
+///
+/// Its number of permits is set from the indexer config with [`set_max_concurrent_gc_deletes`]
+/// when the indexer service starts, and defaults to the indexer config default otherwise.
+static CONCURRENT_GC_DELETE_PERMITS: OnceCell<Semaphore> = OnceCell::new();
+
+/// Sets the maximum number of delete operations that can happen concurrently across all the
+/// garbage collection runs of the node. It has no effect once a garbage collection has run.
+pub fn set_max_concurrent_gc_deletes(max_concurrent_gc_deletes: usize) {
+    if CONCURRENT_GC_DELETE_PERMITS
+        .set(Semaphore::new(max_concurrent_gc_deletes))
+        .is_err()
+    {
+        warn!(
+            max_concurrent_gc_deletes = max_concurrent_gc_deletes,
+            "The garbage collection delete permits are already set."
+        );
+    }
+}
+
+fn concurrent_gc_delete_permits() -> &'static Semaphore {
+    CONCURRENT_GC_DELETE_PERMITS
+        .get_or_init(|| Semaphore::new(IndexerConfig::default().max_concurrent_gc_deletes))
+}
+
+/// Maximum number of delete operations a single garbage collection run issues concurrently, set
+/// from the indexer config with [`set_max_concurrent_deletes_per_gc`] when the indexer service
+/// starts.
+static MAX_CONCURRENT_DELETES_PER_GC: OnceCell<usize> = OnceCell::new();
+
+/// Sets the maximum number of delete operations a single garbage collection run issues
+/// concurrently. It has no effect once a garbage collection has run.
+pub fn set_max_concurrent_deletes_per_gc(max_concurrent_deletes_per_gc: usize) {
+    if MAX_CONCURRENT_DELETES_PER_GC
+        .set(max_concurrent_deletes_per_gc)
+        .is_err()
+    {
+        warn!(
+            max_concurrent_deletes_per_gc = max_concurrent_deletes_per_gc,
+            "The maximum number of concurrent deletes per garbage collection is already set."
+        );
+    }
+}
+
+fn max_concurrent_deletes_per_gc() -> usize {
+    *MAX_CONCURRENT_DELETES_PER_GC
+        .get_or_init(|| IndexerConfig::default().max_concurrent_deletes_per_gc)
+}
 
 /// SplitDeletionError denotes error that can happen when deleting split
 /// during garbage collection.
 #[derive(Error, Debug)]
 pub enum SplitDeletionError {
-    #[error("Failed to delete splits from storage: '{0:?}'.")]
-    StorageFailure(Vec<(String, StorageError)>),
+    #[error(
+        "Failed to delete splits from storage: '{failures:?}'. Number of splits deleted nonetheless: \
+         {}.",
+        .deleted_file_entries.len()
+    )]
+    StorageFailure {
+        /// The splits that could not be deleted along with their error.
+        failures: Vec<(String, StorageError)>,
+        /// The files of the splits that were deleted in spite of the failures.
+        deleted_file_entries: Vec<FileEntry>,
+    },
 
     #[error("Failed to delete splits from metastore: '{0:?}'.")]
     MetastoreFailure(MetastoreError),
@@ -54,6 +120,15 @@ pub struct FileEntry {
     pub file_size_in_bytes: u64, //< TODO switch to `byte_unit::Byte`.
 }
 
+impl From<&FileMetadata> for FileEntry {
+    fn from(file: &FileMetadata) -> Self {
+        FileEntry {
+            file_name: file.path.to_string_lossy().to_string(),
+            file_size_in_bytes: file.num_bytes,
+        }
+    }
+}
+
 impl From<&SplitMetadata> for FileEntry {
     fn from(split: &SplitMetadata) -> Self {
         FileEntry {
@@ -72,6 +147,9 @@ impl From<&SplitMetadata> for FileEntry {
 ///   collected.
 /// * `deletion_grace_period` -  Threshold period after which a marked as deleted split can be
 ///   safely deleted.
+/// * `orphan_grace_period_opt` - Threshold period after which a split file that belongs to no
+///   split of the metastore can be safely deleted. Orphan split files are left untouched if
+///   `None`.
 /// * `dry_run` - Should this only return a list of affected files without performing deletion.
 /// * `ctx_opt` - A context for reporting progress (only useful within quickwit actor).
 pub async fn run_garbage_collect(
@@ -80,9 +158,15 @@ pub async fn run_garbage_collect(
     metastore: Arc<dyn Metastore>,
     staged_grace_period: Duration,
     deletion_grace_period: Duration,
+    orphan_grace_period_opt: Option<Duration>,
     dry_run: bool,
     ctx_opt: Option<&ActorContext<GarbageCollector>>,
 ) -> anyhow::Result<Vec<FileEntry>> {
+    let orphan_split_files = if let Some(orphan_grace_period) = orphan_grace_period_opt {
+        list_orphan_split_files(index_id, &split_store, &*metastore, orphan_grace_period).await?
+    } else {
+        Vec::new()
+    };
     // Select staged splits with staging timestamp older than grace period timestamp.
     let grace_period_timestamp =
         OffsetDateTime::now_utc().unix_timestamp() - staged_grace_period.as_secs() as i64;
@@ -111,6 +195,7 @@ pub async fn run_garbage_collect(
         let candidate_entries: Vec<FileEntry> = splits_marked_for_deletion
             .iter()
             .map(FileEntry::from)
+            .chain(orphan_split_files.iter().map(FileEntry::from))
             .collect();
         return Ok(candidate_entries);
     }
@@ -136,7 +221,7 @@ pub async fn run_garbage_collect(
         .map(|meta| meta.split_metadata)
         .collect();
 
-    let deleted_files = delete_splits_with_files(
+    let mut deleted_files = delete_splits_with_files(
         index_id,
         split_store.clone(),
         metastore.clone(),
@@ -145,9 +230,88 @@ pub async fn run_garbage_collect(
     )
     .await?;
 
+    let deleted_orphan_files =
+        delete_orphan_split_files(index_id, &split_store, orphan_split_files, ctx_opt).await;
+    deleted_files.extend(deleted_orphan_files);
+
     Ok(deleted_files)
 }
 
+/// Lists the split files of the index storage that belong to no split of the metastore and were
+/// last modified before the grace period. These are typically left over by aborted uploads.
+async fn list_orphan_split_files(
+    index_id: &str,
+    split_store: &IndexingSplitStore,
+    metastore: &dyn Metastore,
+    orphan_grace_period: Duration,
+) -> anyhow::Result<Vec<FileMetadata>> {
+    // The storage is listed before the metastore so that a split staged in between is not
+    // mistaken for an orphan.
+    let split_files = split_store.list_split_files().await?;
+    let split_ids: HashSet<String> = metastore
+        .list_all_splits(index_id)
+        .await?
+        .into_iter()
+        .map(|split| split.split_metadata.split_id)
+        .collect();
+    let grace_period_timestamp =
+        OffsetDateTime::now_utc().unix_timestamp() - orphan_grace_period.as_secs() as i64;
+    let orphan_split_files = split_files
+        .into_iter()
+        .filter(|file| file.last_modified_timestamp <= grace_period_timestamp)
+        .filter(|file| {
+            split_id_from_path(&file.path).map_or(false, |split_id| !split_ids.contains(split_id))
+        })
+        .collect();
+    Ok(orphan_split_files)
+}
+
+fn split_id_from_path(path: &Path) -> Option<&str> {
+    path.file_stem().and_then(|file_stem| file_stem.to_str())
+}
+
+/// Deletes orphan split files from the storage and returns the ones that were deleted. The files
+/// that could not be deleted are logged and left for the next garbage collection run.
+async fn delete_orphan_split_files(
+    index_id: &str,
+    split_store: &IndexingSplitStore,
+    orphan_split_files: Vec<FileMetadata>,
+    ctx_opt: Option<&ActorContext<GarbageCollector>>,
+) -> Vec<FileEntry> {
+    let mut deleted_file_entries = Vec::new();
+
+    for orphan_split_files_chunk in orphan_split_files.chunks(MAX_NUM_SPLITS_PER_BULK_DELETE) {
+        let split_ids: Vec<&str> = orphan_split_files_chunk
+            .iter()
+            .filter_map(|file| split_id_from_path(&file.path))
+            .collect();
+        let delete_permit = match ctx_opt {
+            Some(ctx) => {
+                let _guard = ctx.protect_zone();
+                concurrent_gc_delete_permits().acquire().await
+            }
+            None => concurrent_gc_delete_permits().acquire().await,
+        }
+        .expect("The garbage collection semaphore is closed. (This should never happen.)");
+        let failures = match split_store.bulk_delete(&split_ids).await {
+            Ok(()) => HashMap::new(),
+            Err(bulk_delete_error) => bulk_delete_error.failures,
+        };
+        drop(delete_permit);
+        if let Some(ctx) = ctx_opt {
+            ctx.record_progress();
+        }
+        for file in orphan_split_files_chunk {
+            if let Some(error) = failures.get(&file.path) {
+                error!(error = ?error, index_id = ?index_id, path = ?file.path, "Failed to delete orphan split file.");
+            } else {
+                deleted_file_entries.push(FileEntry::from(file));
+            }
+        }
+    }
+    deleted_file_entries
+}
+
 /// Delete a list of splits from the storage and the metastore.
 /// It should leave the index and the metastore in good state.
 ///
@@ -162,51 +326,313 @@ pub async fn delete_splits_with_files(
     metastore: Arc<dyn Metastore>,
     splits: Vec<SplitMetadata>,
     ctx_opt: Option<&ActorContext<GarbageCollector>>,
+) -> anyhow::Result<Vec<FileEntry>, SplitDeletionError> {
+    delete_splits_with_files_with_permits(
+        index_id,
+        indexing_split_store,
+        metastore,
+        splits,
+        ctx_opt,
+        concurrent_gc_delete_permits(),
+        max_concurrent_deletes_per_gc(),
+    )
+    .await
+}
+
+async fn delete_splits_with_files_with_permits(
+    index_id: &str,
+    indexing_split_store: IndexingSplitStore,
+    metastore: Arc<dyn Metastore>,
+    splits: Vec<SplitMetadata>,
+    ctx_opt: Option<&ActorContext<GarbageCollector>>,
+    delete_permits: &Semaphore,
+    max_concurrent_deletes: usize,
 ) -> anyhow::Result<Vec<FileEntry>, SplitDeletionError> {
     let mut deleted_file_entries = Vec::new();
     let mut deleted_split_ids = Vec::new();
     let mut failed_split_ids_to_error = Vec::new();
 
-    let mut delete_splits_results_stream = tokio_stream::iter(splits.into_iter())
-        .map(|split| {
-            let moved_indexing_split_store = indexing_split_store.clone();
-            async move {
-                let file_entry = FileEntry::from(&split);
-                let delete_result = moved_indexing_split_store.delete(split.split_id()).await;
-                if let Some(ctx) = ctx_opt {
-                    ctx.record_progress();
+    let indexing_split_store = &indexing_split_store;
+    // A failed bulk delete does not stop the other ones: its failures are collected and reported
+    // once all the bulk deletes have completed.
+    let mut bulk_delete_outcomes = stream::iter(splits.chunks(MAX_NUM_SPLITS_PER_BULK_DELETE))
+        .map(|splits_chunk| async move {
+            let split_ids: Vec<&str> = splits_chunk.iter().map(|split| split.split_id()).collect();
+            let delete_permit = match ctx_opt {
+                Some(ctx) => {
+                    let _guard = ctx.protect_zone();
+                    delete_permits.acquire().await
                 }
-                (split.split_id().to_string(), file_entry, delete_result)
+                None => delete_permits.acquire().await,
             }
+            .expect("The garbage collection semaphore is closed. (This should never happen.)");
+            let failures = match indexing_split_store.bulk_delete(&split_ids).await {
+                Ok(()) => HashMap::new(),
+                Err(bulk_delete_error) => bulk_delete_error.failures,
+            };
+            drop(delete_permit);
+            (splits_chunk, failures)
         })
-        .buffer_unordered(MAX_CONCURRENT_STORAGE_REQUESTS);
+        .buffer_unordered(max_concurrent_deletes.max(1));
 
-    while let Some((split_id, file_entry, delete_split_res)) =
-        delete_splits_results_stream.next().await
-    {
-        if let Err(error) = delete_split_res {
-            error!(error = ?error, index_id = ?index_id, split_id = ?split_id, "Failed to delete split.");
-            failed_split_ids_to_error.push((split_id, error));
-        } else {
-            deleted_split_ids.push(split_id);
-            deleted_file_entries.push(file_entry);
-        };
-    }
-
-    if !failed_split_ids_to_error.is_empty() {
-        error!(index_id = ?index_id, failed_split_ids_to_error = ?failed_split_ids_to_error, "Failed to delete splits.");
-        return Err(SplitDeletionError::StorageFailure(
-            failed_split_ids_to_error,
-        ));
+    while let Some((splits_chunk, mut failures)) = bulk_delete_outcomes.next().await {
+        if let Some(ctx) = ctx_opt {
+            ctx.record_progress();
+        }
+        for split in splits_chunk {
+            let split_path = PathBuf::from(quickwit_common::split_file(split.split_id()));
+            if let Some(error) = failures.remove(&split_path) {
+                error!(error = ?error, index_id = ?index_id, split_id = ?split.split_id(), "Failed to delete split.");
+                failed_split_ids_to_error.push((split.split_id().to_string(), error));
+            } else {
+                deleted_split_ids.push(split.split_id());
+                deleted_file_entries.push(FileEntry::from(split));
+            }
+        }
     }
 
+    // The splits that were deleted from the storage are removed from the metastore even if some
+    // other splits could not be deleted, so that the next pass only retries the latter.
     if !deleted_split_ids.is_empty() {
-        let split_ids: Vec<&str> = deleted_split_ids.iter().map(String::as_str).collect();
         metastore
-            .delete_splits(index_id, &split_ids)
+            .delete_splits(index_id, &deleted_split_ids)
             .await
             .map_err(SplitDeletionError::MetastoreFailure)?;
     }
 
+    if !failed_split_ids_to_error.is_empty() {
+        error!(index_id = ?index_id, failed_split_ids_to_error = ?failed_split_ids_to_error, "Failed to delete splits.");
+        return Err(SplitDeletionError::StorageFailure {
+            failures: failed_split_ids_to_error,
+            deleted_file_entries,
+        });
+    }
+
     Ok(deleted_file_entries)
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use quickwit_metastore::{FileBackedMetastore, IndexMetadata, MockMetastore};
+    use quickwit_storage::{BulkDeleteError, MockStorage, RamStorage, Storage, StorageErrorKind};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_garbage_collect_orphan_split_files() -> anyhow::Result<()> {
+        let index_id = "test-index";
+        let metastore: Arc<dyn Metastore> = Arc::new(FileBackedMetastore::for_test(Arc::new(
+            RamStorage::default(),
+        )));
+        metastore
+            .create_index(IndexMetadata::for_test(
+                index_id,
+                "ram:///indexes/test-index",
+            ))
+            .await?;
+        let staged_split = SplitMetadata {
+            split_id: "split-staged".to_string(),
+            ..Default::default()
+        };
+        metastore.stage_split(index_id, staged_split).await?;
+
+        let storage = Arc::new(RamStorage::default());
+        for path in [
+            "split-staged.split",
+            "split-orphan.split",
+            "nested/split-nested.split",
+            "hotcache",
+        ] {
+            storage
+                .put(Path::new(path), Box::new(b"split".to_vec()))
+                .await?;
+        }
+        let split_store = IndexingSplitStore::create_with_no_local_store(storage.clone());
+        let staged_grace_period = Duration::from_secs(60 * 60);
+
+        let candidate_entries = run_garbage_collect(
+            index_id,
+            split_store.clone(),
+            metastore.clone(),
+            staged_grace_period,
+            Duration::ZERO,
+            Some(Duration::ZERO),
+            true,
+            None,
+        )
+        .await?;
+        assert_eq!(candidate_entries.len(), 1);
+        assert_eq!(candidate_entries[0].file_name, "split-orphan.split");
+        assert_eq!(candidate_entries[0].file_size_in_bytes, 5);
+        assert!(storage.exists(Path::new("split-orphan.split")).await?);
+
+        // The orphan split file is too recent to be deleted.
+        let deleted_entries = run_garbage_collect(
+            index_id,
+            split_store.clone(),
+            metastore.clone(),
+            staged_grace_period,
+            Duration::ZERO,
+            Some(Duration::from_secs(60 * 60)),
+            false,
+            None,
+        )
+        .await?;
+        assert!(deleted_entries.is_empty());
+        assert!(storage.exists(Path::new("split-orphan.split")).await?);
+
+        let deleted_entries = run_garbage_collect(
+            index_id,
+            split_store,
+            metastore.clone(),
+            staged_grace_period,
+            Duration::ZERO,
+            Some(Duration::ZERO),
+            false,
+            None,
+        )
+        .await?;
+        assert_eq!(deleted_entries.len(), 1);
+        assert_eq!(deleted_entries[0].file_name, "split-orphan.split");
+        assert!(!storage.exists(Path::new("split-orphan.split")).await?);
+        for path in [
+            "split-staged.split",
+            "nested/split-nested.split",
+            "hotcache",
+        ] {
+            assert!(storage.exists(Path::new(path)).await?);
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_delete_splits_with_files_reports_partial_failures() {
+        let mut mock_storage = MockStorage::default();
+        mock_storage
+            .expect_bulk_delete()
+            .times(2)
+            .returning(|paths: &[&Path]| {
+                if paths != [Path::new("a.split"), Path::new("b.split")] {
+                    assert_eq!(paths, [Path::new("c.split")]);
+                    return Ok(());
+                }
+                let mut bulk_delete_error = BulkDeleteError::default();
+                bulk_delete_error.successes.push(PathBuf::from("a.split"));
+                bulk_delete_error.failures.insert(
+                    PathBuf::from("b.split"),
+                    StorageErrorKind::Service.with_error(anyhow::anyhow!("Access denied.")),
+                );
+                Err(bulk_delete_error)
+            });
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_delete_splits()
+            .times(1)
+            .returning(|index_id, split_ids| {
+                assert_eq!(index_id, "foo-index");
+                // The bulk deletes complete in any order.
+                let mut split_ids = split_ids.to_vec();
+                split_ids.sort_unstable();
+                assert_eq!(split_ids, vec!["a", "c"]);
+                Ok(())
+            });
+        let splits = ["a", "b", "c"]
+            .iter()
+            .map(|split_id| SplitMetadata {
+                split_id: split_id.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let split_deletion_error = delete_splits_with_files(
+            "foo-index",
+            IndexingSplitStore::create_with_no_local_store(Arc::new(mock_storage)),
+            Arc::new(mock_metastore),
+            splits,
+            None,
+        )
+        .await
+        .unwrap_err();
+        assert!(split_deletion_error
+            .to_string()
+            .ends_with("Number of splits deleted nonetheless: 1."));
+        let (failures, deleted_file_entries) = match &split_deletion_error {
+            SplitDeletionError::StorageFailure {
+                failures,
+                deleted_file_entries,
+            } => (failures, deleted_file_entries),
+            SplitDeletionError::MetastoreFailure(_) => panic!("Expected a storage failure."),
+        };
+        let failed_split_ids: Vec<&str> = failures
+            .iter()
+            .map(|(split_id, _)| split_id.as_str())
+            .collect();
+        assert_eq!(failed_split_ids, vec!["b"]);
+        let mut deleted_file_names: Vec<&str> = deleted_file_entries
+            .iter()
+            .map(|file_entry| file_entry.file_name.as_str())
+            .collect();
+        deleted_file_names.sort_unstable();
+        assert_eq!(deleted_file_names, vec!["a.split", "c.split"]);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_gc_runs_share_delete_permits() {
+        let num_deletes_in_flight = Arc::new(AtomicUsize::new(0));
+        let max_num_deletes_in_flight = Arc::new(AtomicUsize::new(0));
+        let mut mock_storage = MockStorage::default();
+        let num_deletes_in_flight_clone = num_deletes_in_flight.clone();
+        let max_num_deletes_in_flight_clone = max_num_deletes_in_flight.clone();
+        mock_storage
+            .expect_bulk_delete()
+            .times(4)
+            .returning(move |_paths: &[&Path]| {
+                let num_deletes = num_deletes_in_flight_clone.fetch_add(1, Ordering::SeqCst) + 1;
+                max_num_deletes_in_flight_clone.fetch_max(num_deletes, Ordering::SeqCst);
+                // Blocks the worker thread so that concurrent deletes would overlap.
+                std::thread::sleep(Duration::from_millis(50));
+                num_deletes_in_flight_clone.fetch_sub(1, Ordering::SeqCst);
+                Ok(())
+            });
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_delete_splits()
+            .times(2)
+            .returning(|_index_id, _split_ids| Ok(()));
+        let split_store = IndexingSplitStore::create_with_no_local_store(Arc::new(mock_storage));
+        let metastore: Arc<dyn Metastore> = Arc::new(mock_metastore);
+        let delete_permits = Arc::new(Semaphore::new(1));
+
+        let gc_run_handles = ["foo-index", "bar-index"].map(|index_id| {
+            let split_store = split_store.clone();
+            let metastore = metastore.clone();
+            let delete_permits = delete_permits.clone();
+            tokio::spawn(async move {
+                let splits = ["a", "b", "c", "d"]
+                    .iter()
+                    .map(|split_id| SplitMetadata {
+                        split_id: format!("{}-{}", index_id, split_id),
+                        ..Default::default()
+                    })
+                    .collect();
+                delete_splits_with_files_with_permits(
+                    index_id,
+                    split_store,
+                    metastore,
+                    splits,
+                    None,
+                    &delete_permits,
+                    2,
+                )
+                .await
+                .map(|deleted_file_entries| deleted_file_entries.len())
+            })
+        });
+        for gc_run_handle in gc_run_handles {
+            let num_deleted_files = gc_run_handle.await.unwrap().unwrap();
+            assert_eq!(num_deleted_files, 4);
+        }
+        assert_eq!(max_num_deletes_in_flight.load(Ordering::SeqCst), 1);
+    }
+}
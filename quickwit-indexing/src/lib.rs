@@ -41,6 +41,7 @@ pub mod actors;
 mod controlled_directory;
 mod garbage_collection;
 pub mod merge_policy;
+mod metrics;
 pub mod models;
 pub mod source;
 mod split_store;
@@ -49,11 +50,36 @@ mod test_utils;
 pub use test_utils::{mock_split, mock_split_meta, TestSandbox};
 
 pub use self::garbage_collection::{
-    delete_splits_with_files, run_garbage_collect, FileEntry, SplitDeletionError,
+    delete_splits_with_files, run_garbage_collect, set_max_concurrent_deletes_per_gc,
+    set_max_concurrent_gc_deletes, FileEntry, SplitDeletionError,
 };
 use self::merge_policy::{MergePolicy, StableMultitenantWithTimestampMergePolicy};
 pub use self::source::check_source_connectivity;
 
+/// Returns the optional Cargo features of this crate enabled at build time.
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut enabled_features = Vec::new();
+    if cfg!(feature = "file-compression") {
+        enabled_features.push("file-compression");
+    }
+    if cfg!(feature = "http-source") {
+        enabled_features.push("http-source");
+    }
+    if cfg!(feature = "kafka") {
+        enabled_features.push("kafka");
+    }
+    if cfg!(feature = "vendored-kafka") {
+        enabled_features.push("vendored-kafka");
+    }
+    if cfg!(feature = "kinesis") {
+        enabled_features.push("kinesis");
+    }
+    if cfg!(feature = "pulsar") {
+        enabled_features.push("pulsar");
+    }
+    enabled_features
+}
+
 pub fn new_split_id() -> String {
     ulid::Ulid::new().to_string()
 }
@@ -66,6 +92,8 @@ pub async fn start_indexer_service(
     ingest_api_service: Option<Mailbox<IngestApiService>>,
 ) -> anyhow::Result<Mailbox<IndexingService>> {
     info!("Starting indexer service.");
+    set_max_concurrent_gc_deletes(config.indexer_config.max_concurrent_gc_deletes);
+    set_max_concurrent_deletes_per_gc(config.indexer_config.max_concurrent_deletes_per_gc);
     let indexing_server = IndexingService::new(
         config.data_dir_path.to_path_buf(),
         config.indexer_config.clone(),
@@ -135,6 +135,10 @@ pub trait MergePolicy: Send + Sync + fmt::Debug {
 /// Because we stop merging splits reaching a size larger than if it would result in a size larger
 /// than `target_num_docs`.
 ///
+/// If `balance_split_sizes` is set, the splits are sorted by increasing number of documents
+/// instead of by date, so that each level groups splits of similar sizes regardless of their
+/// time range (tiered merging).
+///
 /// 2. Build demux operations if `demux_field_name` is present
 /// We start by sorting the splits by date so that the oldest splits are first demuxed.
 /// This will avoid leaving an old split alone that will be demuxed with younger splits
@@ -152,6 +156,8 @@ pub struct StableMultitenantWithTimestampMergePolicy {
     pub merge_enabled: bool,
     pub merge_factor: usize,
     pub max_merge_factor: usize,
+    /// If true, the levels are built over the splits sorted by size rather than by date.
+    pub balance_split_sizes: bool,
     /// The merge policy aims to eventually produce mature splits that have a larger size but
     /// are within close range of `split_num_docs_target`.
     ///
@@ -170,6 +176,7 @@ impl Default for StableMultitenantWithTimestampMergePolicy {
             merge_enabled: true,
             merge_factor: 10,
             max_merge_factor: 12,
+            balance_split_sizes: false,
             split_num_docs_target: 10_000_000,
         }
     }
@@ -295,14 +302,20 @@ impl StableMultitenantWithTimestampMergePolicy {
             remove_matching_items(splits, |split| self.is_mature_for_merge(split));
 
         let mut merge_operations: Vec<MergeOperation> = Vec::new();
-        // We stable sort the splits, most recent first.
-        splits.sort_by_key(|split| {
-            let time_end = split
-                .time_range
-                .as_ref()
-                .map(|time_range| Reverse(*time_range.end()));
-            (time_end, split.num_docs)
-        });
+        if self.balance_split_sizes {
+            // We stable sort the splits, smallest first, so that levels group splits of similar
+            // sizes.
+            splits.sort_by_key(|split| split.num_docs);
+        } else {
+            // We stable sort the splits, most recent first.
+            splits.sort_by_key(|split| {
+                let time_end = split
+                    .time_range
+                    .as_ref()
+                    .map(|time_range| Reverse(*time_range.end()));
+                (time_end, split.num_docs)
+            });
+        }
         debug!(splits=?splits_short_debug(&splits[..]), "merge-policy-run");
 
         // Splits should naturally have an increasing num_merge
@@ -811,6 +824,38 @@ mod tests {
         assert!(matches!(merge_op, MergeOperation::Merge { .. }));
     }
 
+    #[test]
+    fn test_stable_multitenant_merge_policy_balance_split_sizes() {
+        let merge_policy = StableMultitenantWithTimestampMergePolicy {
+            balance_split_sizes: true,
+            ..Default::default()
+        };
+        // Small and medium splits alternate in time.
+        let num_docs_with_timestamp = (0..20)
+            .map(|split_ord: i64| {
+                let num_docs = if split_ord % 2 == 0 { 1_000 } else { 500_000 };
+                (num_docs, split_ord * 100..=split_ord * 100 + 99)
+            })
+            .collect();
+        let mut splits = create_splits_with_timestamps(num_docs_with_timestamp);
+        let merge_ops = merge_policy.operations(&mut splits);
+        assert!(splits.is_empty());
+        assert_eq!(merge_ops.len(), 2);
+        let mut merge_ops_num_docs: Vec<Vec<usize>> = merge_ops
+            .iter()
+            .map(|merge_op| {
+                assert!(matches!(merge_op, MergeOperation::Merge { .. }));
+                merge_op
+                    .splits()
+                    .iter()
+                    .map(|split| split.num_docs)
+                    .collect()
+            })
+            .collect();
+        merge_ops_num_docs.sort();
+        assert_eq!(merge_ops_num_docs, vec![vec![1_000; 10], vec![500_000; 10]]);
+    }
+
     #[test]
     fn test_stable_multitenant_merge_policy_splits_below_min_level() {
         let merge_policy = StableMultitenantWithTimestampMergePolicy::default();
@@ -948,6 +993,7 @@ mod tests {
             merge_enabled: true,
             merge_factor: 10,
             max_merge_factor: 12,
+            balance_split_sizes: false,
             split_num_docs_target: 10_000_000,
         };
         let mut demux_candidates = create_splits_with_tags(
@@ -0,0 +1,57 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+// See https://prometheus.io/docs/practices/naming/
+
+use once_cell::sync::Lazy;
+#[cfg(feature = "kafka")]
+use quickwit_common::metrics::{new_gauge_vec, IntGaugeVec};
+use quickwit_common::metrics::{new_histogram_with_buckets, Histogram};
+
+pub struct IndexingMetrics {
+    #[cfg(feature = "kafka")]
+    pub kafka_consumer_lag: IntGaugeVec,
+    pub time_to_search_seconds: Histogram,
+}
+
+impl Default for IndexingMetrics {
+    fn default() -> Self {
+        IndexingMetrics {
+            #[cfg(feature = "kafka")]
+            kafka_consumer_lag: new_gauge_vec(
+                "kafka_consumer_lag",
+                "Number of messages between the last consumed offset and the high watermark of a \
+                 Kafka partition.",
+                "quickwit_indexing",
+                &["topic", "partition"],
+            ),
+            time_to_search_seconds: new_histogram_with_buckets(
+                "time_to_search_seconds",
+                "Time elapsed between the reception of the documents by the source and the \
+                 publication of the split containing them.",
+                "quickwit_indexing",
+                vec![1.0, 2.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0],
+            ),
+        }
+    }
+}
+
+/// `INDEXING_METRICS` exposes a set of indexing related metrics through a prometheus
+/// endpoint.
+pub static INDEXING_METRICS: Lazy<IndexingMetrics> = Lazy::new(IndexingMetrics::default);
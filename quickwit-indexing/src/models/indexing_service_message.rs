@@ -17,7 +17,7 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use quickwit_config::SourceConfig;
+use quickwit_config::{MergePolicy, SourceConfig};
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct IndexingPipelineId {
@@ -43,6 +43,11 @@ pub struct SpawnMergePipeline {
     pub index_id: String,
     pub merge_enabled: bool,
     pub demux_enabled: bool,
+    /// Merge policy used instead of the one of the index config, for this pipeline only.
+    pub merge_policy_opt: Option<MergePolicy>,
+    /// Number of documents above which a split is mature and no longer merged, used instead of
+    /// the `split_num_docs_target` of the index config, for this pipeline only.
+    pub split_num_docs_target_opt: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -54,6 +59,24 @@ pub struct SpawnPipelinesForIndex {
 pub struct SpawnPipeline {
     pub index_id: String,
     pub source: SourceConfig,
+    /// Only parses the documents read from the source, see [`IndexingPipelineParams::dry_run`].
+    ///
+    /// [`IndexingPipelineParams::dry_run`]: crate::actors::IndexingPipelineParams::dry_run
+    pub dry_run: bool,
+    /// Docstore compression used instead of the one of the index config, for this pipeline only.
+    pub docstore_compression_opt: Option<DocstoreCompression>,
+}
+
+/// Compression applied to the docstore blocks of the splits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocstoreCompression {
+    None,
+    Lz4,
+    /// Zstd compression. When no level is provided, the `docstore_compression_level` of the
+    /// index config applies.
+    Zstd {
+        compression_level: Option<i32>,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -28,6 +28,8 @@ pub struct IndexingStatistics {
     pub num_docs: u64,
     /// Number of document parse error, or missing timestamps
     pub num_invalid_docs: u64,
+    /// Number of documents missing a required field, such as the timestamp
+    pub num_missing_fields: u64,
     /// Number of created split
     pub num_local_splits: u64,
     /// Number of staged splits
@@ -55,6 +57,7 @@ impl IndexingStatistics {
     ) -> Self {
         self.num_docs += indexer_counters.num_processed_docs();
         self.num_invalid_docs += indexer_counters.num_invalid_docs();
+        self.num_missing_fields += indexer_counters.num_missing_fields;
         self.num_local_splits += indexer_counters.num_splits_emitted;
         self.total_bytes_processed += indexer_counters.overall_num_bytes;
         self.num_staged_splits += uploader_counters.num_staged_splits.load(Ordering::SeqCst);
@@ -31,8 +31,8 @@ mod scratch_directory;
 pub use indexed_split::{IndexedSplit, IndexedSplitBatch};
 pub use indexing_directory::{IndexingDirectory, CACHE};
 pub use indexing_service_message::{
-    DetachPipeline, IndexingPipelineId, ObservePipeline, ShutdownPipeline, SpawnMergePipeline,
-    SpawnPipeline, SpawnPipelinesForIndex,
+    DetachPipeline, DocstoreCompression, IndexingPipelineId, ObservePipeline, ShutdownPipeline,
+    SpawnMergePipeline, SpawnPipeline, SpawnPipelinesForIndex,
 };
 pub use indexing_statistics::IndexingStatistics;
 pub use merge_planner_message::NewSplits;
@@ -44,3 +44,9 @@ pub use scratch_directory::ScratchDirectory;
 
 #[derive(Debug, Copy, Clone)]
 pub struct Observe;
+
+/// Stops a pipeline gracefully: the merge planner no longer plans new merges and the source no
+/// longer emits documents. The operations already underway are carried out to completion before
+/// the pipeline exits.
+#[derive(Debug, Copy, Clone)]
+pub struct CancelMerges;
@@ -29,7 +29,7 @@ pub struct SplitUpdate {
     pub new_splits: Vec<SplitMetadata>,
     pub replaced_split_ids: Vec<String>,
     pub checkpoint_delta_opt: Option<IndexCheckpointDelta>,
-    pub date_of_birth: Instant, // for logging
+    pub date_of_birth: Instant, // for logging and the time-to-search metric
 }
 
 impl fmt::Debug for SplitUpdate {
@@ -18,13 +18,21 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::fmt;
+use std::time::Instant;
 
 use quickwit_metastore::checkpoint::SourceCheckpointDelta;
 
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct RawDocBatch {
     pub docs: Vec<String>,
     pub checkpoint_delta: SourceCheckpointDelta,
+    /// Byte offset of the first document of the batch in the source, for the sources that read
+    /// documents from a byte stream (e.g. the file source). It is used to locate malformed
+    /// documents.
+    pub start_byte_offset: Option<u64>,
+    /// Instant at which the source started building the batch. It is used to measure the
+    /// time-to-search of the documents.
+    pub received_at: Instant,
 }
 
 impl RawDocBatch {
@@ -32,16 +40,26 @@ impl RawDocBatch {
         RawDocBatch {
             docs,
             checkpoint_delta,
+            start_byte_offset: None,
+            received_at: Instant::now(),
         }
     }
 }
 
+impl Default for RawDocBatch {
+    fn default() -> Self {
+        RawDocBatch::new(Vec::new(), SourceCheckpointDelta::default())
+    }
+}
+
 impl fmt::Debug for RawDocBatch {
     fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
         formatter
             .debug_struct("RawDocBatch")
             .field("docs_len", &self.docs.len())
             .field("checkpoint_delta", &self.checkpoint_delta)
+            .field("start_byte_offset", &self.start_byte_offset)
+            .field("received_at", &self.received_at)
             .finish()
     }
 }
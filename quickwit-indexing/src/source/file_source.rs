@@ -21,14 +21,17 @@ use std::io::SeekFrom;
 use std::time::Duration;
 use std::{fmt, io};
 
-use anyhow::Context;
+use anyhow::{bail, Context};
+#[cfg(feature = "file-compression")]
+use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
 use async_trait::async_trait;
 use quickwit_actors::{ActorExitStatus, Mailbox};
-use quickwit_config::FileSourceParams;
+use quickwit_config::{FileSourceCompression, FileSourceParams};
 use quickwit_metastore::checkpoint::{PartitionId, Position};
 use serde::Serialize;
+use sync_wrapper::SyncWrapper;
 use tokio::fs::File;
-use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncSeekExt, BufReader};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, BufReader};
 use tracing::info;
 
 use crate::actors::Indexer;
@@ -45,11 +48,15 @@ pub struct FileSourceCounters {
     pub num_lines_processed: u64,
 }
 
+type FileReader = BufReader<Box<dyn AsyncRead + Send + Unpin>>;
+
 pub struct FileSource {
     source_id: String,
     params: FileSourceParams,
     counters: FileSourceCounters,
-    reader: BufReader<Box<dyn AsyncRead + Send + Sync + Unpin>>,
+    // Decompressors are not `Sync`. The reader is only accessed through `&mut self`, which
+    // `SyncWrapper` relies on to make the source `Sync`.
+    reader: SyncWrapper<FileReader>,
 }
 
 impl fmt::Debug for FileSource {
@@ -68,11 +75,17 @@ impl Source for FileSource {
         // We collect batches of documents before sending them to the indexer.
         let limit_num_bytes = self.counters.previous_offset + BATCH_NUM_BYTES_THRESHOLD;
         let mut reached_eof = false;
-        let mut doc_batch = RawDocBatch::default();
+        let mut doc_batch = RawDocBatch {
+            start_byte_offset: Some(self.counters.current_offset),
+            ..Default::default()
+        };
+        let reader = self.reader.get_mut();
+        // For compressed files, offsets are expressed in decompressed bytes.
         while self.counters.current_offset < limit_num_bytes {
             let mut doc_line = String::new();
-            let num_bytes = self
-                .reader
+            // `read_line` also returns the last line of the file when it is not terminated by a
+            // newline, so the final record is never dropped.
+            let num_bytes = reader
                 .read_line(&mut doc_line)
                 .await
                 .map_err(|io_err: io::Error| anyhow::anyhow!(io_err))?;
@@ -134,23 +147,37 @@ impl TypedSourceFactory for FileSourceFactory {
         checkpoint: quickwit_metastore::checkpoint::SourceCheckpoint,
     ) -> anyhow::Result<FileSource> {
         let mut offset = 0;
-        let reader: Box<dyn AsyncRead + Send + Sync + Unpin> =
-            if let Some(filepath) = &params.filepath {
-                let mut file = File::open(&filepath).await.with_context(|| {
-                    format!("Failed to open source file `{}`.", filepath.display())
+        let reader: Box<dyn AsyncRead + Send + Unpin> = if let Some(filepath) = &params.filepath {
+            let mut file = File::open(&filepath)
+                .await
+                .with_context(|| format!("Failed to open source file `{}`.", filepath.display()))?;
+            let partition_id = PartitionId::from(filepath.to_string_lossy().to_string());
+            if let Some(Position::Offset(offset_str)) =
+                checkpoint.position_for_partition(&partition_id).cloned()
+            {
+                offset = offset_str.parse::<u64>()?;
+            }
+            if let Some(compression) = params.compression() {
+                // A compressed stream cannot be seeked: we decompress and skip the bytes that
+                // have already been processed instead.
+                let mut decoder = decompress(file, compression)?;
+                skip_bytes(&mut decoder, offset).await.with_context(|| {
+                    format!(
+                        "Failed to resume reading source file `{}`.",
+                        filepath.display()
+                    )
                 })?;
-                let partition_id = PartitionId::from(filepath.to_string_lossy().to_string());
-                if let Some(Position::Offset(offset_str)) =
-                    checkpoint.position_for_partition(&partition_id).cloned()
-                {
-                    offset = offset_str.parse::<u64>()?;
+                decoder
+            } else {
+                if offset > 0 {
                     file.seek(SeekFrom::Start(offset)).await?;
                 }
                 Box::new(file)
-            } else {
-                // We cannot use the checkpoint.
-                Box::new(tokio::io::stdin())
-            };
+            }
+        } else {
+            // We cannot use the checkpoint.
+            Box::new(tokio::io::stdin())
+        };
         let file_source = FileSource {
             source_id,
             counters: FileSourceCounters {
@@ -158,13 +185,58 @@ impl TypedSourceFactory for FileSourceFactory {
                 current_offset: offset,
                 num_lines_processed: 0,
             },
-            reader: BufReader::new(reader),
+            reader: SyncWrapper::new(BufReader::new(reader)),
             params,
         };
         Ok(file_source)
     }
 }
 
+/// Wraps the file into a decoder that transparently decompresses its content while streaming.
+#[cfg(feature = "file-compression")]
+fn decompress(
+    file: File,
+    compression: FileSourceCompression,
+) -> anyhow::Result<Box<dyn AsyncRead + Send + Unpin>> {
+    let buf_reader = BufReader::new(file);
+    match compression {
+        FileSourceCompression::Gzip => {
+            let mut decoder = GzipDecoder::new(buf_reader);
+            // Log archives are often made of several concatenated gzip members.
+            decoder.multiple_members(true);
+            Ok(Box::new(decoder))
+        }
+        FileSourceCompression::Zstd => {
+            let mut decoder = ZstdDecoder::new(buf_reader);
+            decoder.multiple_members(true);
+            Ok(Box::new(decoder))
+        }
+    }
+}
+
+#[cfg(not(feature = "file-compression"))]
+fn decompress(
+    _file: File,
+    _compression: FileSourceCompression,
+) -> anyhow::Result<Box<dyn AsyncRead + Send + Unpin>> {
+    bail!("Quickwit binary was not compiled with the `file-compression` feature.")
+}
+
+/// Reads and discards the first `num_bytes` bytes of the reader.
+async fn skip_bytes<R: AsyncRead + Unpin>(reader: &mut R, num_bytes: u64) -> anyhow::Result<()> {
+    let num_bytes_skipped = tokio::io::copy(&mut reader.take(num_bytes), &mut tokio::io::sink())
+        .await
+        .context("Failed to skip already processed bytes.")?;
+    if num_bytes_skipped < num_bytes {
+        bail!(
+            "Checkpoint offset `{}` is beyond the end of the decompressed file (`{}` bytes).",
+            num_bytes,
+            num_bytes_skipped
+        );
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use std::io::Write;
@@ -274,10 +346,144 @@ mod tests {
             &extract_position_delta(&batch2.checkpoint_delta).unwrap(),
             "00000000000000500010..00000000000000700000"
         );
+        assert_eq!(batch1.start_byte_offset, Some(0));
+        assert_eq!(batch2.start_byte_offset, Some(500_010));
         assert!(matches!(command, &Command::ExitWithSuccess));
         Ok(())
     }
 
+    #[cfg(feature = "file-compression")]
+    async fn compress(payload: &[u8], compression: FileSourceCompression) -> Vec<u8> {
+        use async_compression::tokio::bufread::{GzipEncoder, ZstdEncoder};
+
+        let mut compressed_payload = Vec::new();
+        match compression {
+            FileSourceCompression::Gzip => GzipEncoder::new(payload)
+                .read_to_end(&mut compressed_payload)
+                .await
+                .unwrap(),
+            FileSourceCompression::Zstd => ZstdEncoder::new(payload)
+                .read_to_end(&mut compressed_payload)
+                .await
+                .unwrap(),
+        };
+        compressed_payload
+    }
+
+    #[cfg(feature = "file-compression")]
+    async fn test_file_source_compressed_aux(
+        extension: &str,
+        compression: FileSourceCompression,
+    ) -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut payload = Vec::new();
+        for _ in 0..20_000 {
+            payload.extend_from_slice(r#"{"body": "hello happy tax payer!"}"#.as_bytes());
+            payload.push(b'\n');
+        }
+        let mut temp_file = tempfile::Builder::new().suffix(extension).tempfile()?;
+        temp_file.write_all(&compress(&payload, compression).await)?;
+        temp_file.flush()?;
+        let params = FileSourceParams::file(temp_file.path());
+        let source = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await?;
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_actor(file_source_actor).spawn();
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        // Offsets are expressed in decompressed bytes.
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 700_000u64,
+                "current_offset": 700_000u64,
+                "num_lines_processed": 20_000u64
+            })
+        );
+        let indexer_msgs = inbox.drain_for_test();
+        assert_eq!(indexer_msgs.len(), 3);
+        let batch1 = indexer_msgs[0].downcast_ref::<RawDocBatch>().unwrap();
+        assert_eq!(batch1.docs[0], "{\"body\": \"hello happy tax payer!\"}\n");
+        assert_eq!(
+            &extract_position_delta(&batch1.checkpoint_delta).unwrap(),
+            "00000000000000000000..00000000000000500010"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "file-compression")]
+    #[tokio::test]
+    async fn test_file_source_gzip() -> anyhow::Result<()> {
+        test_file_source_compressed_aux(".json.gz", FileSourceCompression::Gzip).await
+    }
+
+    #[cfg(feature = "file-compression")]
+    #[tokio::test]
+    async fn test_file_source_zstd() -> anyhow::Result<()> {
+        test_file_source_compressed_aux(".json.zst", FileSourceCompression::Zstd).await
+    }
+
+    #[cfg(feature = "file-compression")]
+    #[tokio::test]
+    async fn test_file_source_gzip_resume_from_checkpoint() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut payload = Vec::new();
+        for i in 0..100 {
+            payload.extend_from_slice(format!("{}\n", i).as_bytes());
+        }
+        let mut temp_file = tempfile::Builder::new().suffix(".gz").tempfile()?;
+        temp_file.write_all(&compress(&payload, FileSourceCompression::Gzip).await)?;
+        temp_file.flush()?;
+        let temp_file_path = temp_file.path().canonicalize()?;
+        let params = FileSourceParams::file(&temp_file_path);
+        let mut checkpoint = SourceCheckpoint::default();
+        let partition_id = PartitionId::from(temp_file_path.to_string_lossy().to_string());
+        let checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            partition_id,
+            Position::from(0u64),
+            Position::from(4u64),
+        );
+        checkpoint.try_apply_delta(checkpoint_delta)?;
+        let source = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            params,
+            checkpoint,
+        )
+        .await?;
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_actor(file_source_actor).spawn();
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 290u64,
+                "current_offset": 290u64,
+                "num_lines_processed": 98u64
+            })
+        );
+        let indexer_msgs = inbox.drain_for_test();
+        let received_batch = indexer_msgs[0].downcast_ref::<RawDocBatch>().unwrap();
+        assert!(received_batch.docs[0].starts_with("2\n"));
+        Ok(())
+    }
+
     fn extract_position_delta(checkpoint_delta: &SourceCheckpointDelta) -> Option<String> {
         let checkpoint_delta_str = format!("{:?}", checkpoint_delta);
         let (_left, right) =
@@ -285,6 +491,22 @@ mod tests {
         Some(right.to_string())
     }
 
+    #[cfg(not(feature = "file-compression"))]
+    #[tokio::test]
+    async fn test_file_source_compressed_requires_file_compression_feature() -> anyhow::Result<()> {
+        let temp_file = tempfile::Builder::new().suffix(".json.gz").tempfile()?;
+        let params = FileSourceParams::file(temp_file.path());
+        let error = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await
+        .unwrap_err();
+        assert!(error.to_string().contains("`file-compression` feature"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_file_source_resume_from_checkpoint() -> anyhow::Result<()> {
         quickwit_common::setup_logging_for_tests();
@@ -333,4 +555,45 @@ mod tests {
         assert!(received_batch.docs[0].starts_with("2\n"));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_file_source_last_line_without_newline() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        temp_file.write_all(b"{\"body\": \"first\"}\n")?;
+        temp_file.write_all(b"{\"body\": \"second\"}\n")?;
+        temp_file.write_all(b"{\"body\": \"last\"}")?;
+        temp_file.flush()?;
+        let params = FileSourceParams::file(temp_file.path());
+        let source = FileSourceFactory::typed_create_source(
+            "my-file-source".to_string(),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await?;
+        let file_source_actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: mailbox,
+        };
+        let (_file_source_mailbox, file_source_handle) =
+            universe.spawn_actor(file_source_actor).spawn();
+        let (actor_termination, counters) = file_source_handle.join().await;
+        assert!(actor_termination.is_success());
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "previous_offset": 53u64,
+                "current_offset": 53u64,
+                "num_lines_processed": 3u64
+            })
+        );
+        let indexer_msgs = inbox.drain_for_test();
+        assert_eq!(indexer_msgs.len(), 2);
+        let batch = indexer_msgs[0].downcast_ref::<RawDocBatch>().unwrap();
+        assert_eq!(batch.docs.len(), 3);
+        assert_eq!(batch.docs[2], "{\"body\": \"last\"}");
+        Ok(())
+    }
 }
@@ -0,0 +1,668 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::fmt;
+use std::hash::Hasher;
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use fnv::FnvHasher;
+use quickwit_actors::{ActorExitStatus, Mailbox};
+use quickwit_config::HttpSourceParams;
+use quickwit_metastore::checkpoint::{PartitionId, Position, SourceCheckpoint};
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode};
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::actors::Indexer;
+use crate::models::RawDocBatch;
+use crate::source::file_source::BATCH_NUM_BYTES_THRESHOLD;
+use crate::source::{Source, SourceContext, TypedSourceFactory};
+
+#[derive(Default, Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct HttpSourceCounters {
+    pub num_fetches: u64,
+    pub num_not_modified_fetches: u64,
+    pub num_failed_fetches: u64,
+    pub num_lines_processed: u64,
+    pub num_bytes_processed: u64,
+}
+
+/// Identifies the version of the resource being ingested and how much of it has been ingested.
+///
+/// It is serialized in the checkpoint as `{generation}:{num_lines}:{prefix_hash}`. The generation
+/// is incremented every time a new version of the resource is fetched, which keeps positions
+/// increasing even though line numbers start over.
+///
+/// The prefix hash is the hash of the lines already ingested. A fetched resource is a new version
+/// unless its first `num_lines` lines hash to the same value. This does not rely on the server
+/// sending an `ETag`, and a resource that was only appended to is resumed where it was left.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+struct ResourceVersion {
+    generation: u64,
+    /// Number of lines of this version of the resource that have already been ingested.
+    num_lines: u64,
+    /// FNV hash of the first `num_lines` lines of this version of the resource.
+    prefix_hash: u64,
+}
+
+impl ResourceVersion {
+    fn from_position(position: &Position) -> anyhow::Result<Self> {
+        let position_str = position.as_str();
+        if position_str.is_empty() {
+            return Ok(Self::default());
+        }
+        let parts: Vec<&str> = position_str.split(':').collect();
+        if let [generation_str, num_lines_str, prefix_hash_str] = parts[..] {
+            return Ok(Self {
+                generation: generation_str.parse::<u64>()?,
+                num_lines: num_lines_str.parse::<u64>()?,
+                prefix_hash: u64::from_str_radix(prefix_hash_str, 16)?,
+            });
+        }
+        bail!(
+            "Position `{}` is not a valid HTTP source position.",
+            position_str
+        )
+    }
+
+    fn to_position(&self) -> Position {
+        Position::from(format!(
+            "{:0>20}:{:0>20}:{:016x}",
+            self.generation, self.num_lines, self.prefix_hash
+        ))
+    }
+}
+
+/// Body of the resource, along with the byte offset of the first line that remains to be emitted.
+struct FetchedResource {
+    body: String,
+    cursor: usize,
+}
+
+pub struct HttpSource {
+    source_id: String,
+    params: HttpSourceParams,
+    client: Client,
+    partition_id: PartitionId,
+    current_position: Position,
+    current_version: ResourceVersion,
+    etag_opt: Option<String>,
+    last_modified_opt: Option<String>,
+    // Conditional requests are only sent once the current version has been entirely ingested:
+    // after a restart, we cannot tell whether the last fetched version was ingested in full.
+    is_fully_ingested: bool,
+    resource_opt: Option<FetchedResource>,
+    counters: HttpSourceCounters,
+}
+
+impl fmt::Debug for HttpSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "HttpSource {{ source_id: {}, url: {} }}",
+            self.source_id, self.params.url
+        )
+    }
+}
+
+impl HttpSource {
+    fn poll_interval(&self) -> Duration {
+        Duration::from_secs(self.params.poll_interval_secs)
+    }
+
+    /// Fetches the resource and skips the lines that have already been ingested.
+    ///
+    /// Returns `None` if the server reported the resource as not modified.
+    async fn fetch(&mut self) -> anyhow::Result<Option<FetchedResource>> {
+        let mut request = self.client.get(&self.params.url);
+        for (header_name, header_value) in &self.params.headers {
+            request = request.header(header_name, header_value);
+        }
+        if self.params.conditional_fetch && self.is_fully_ingested {
+            if let Some(etag) = &self.etag_opt {
+                request = request.header(IF_NONE_MATCH, etag);
+            }
+            if let Some(last_modified) = &self.last_modified_opt {
+                request = request.header(IF_MODIFIED_SINCE, last_modified);
+            }
+        }
+        self.counters.num_fetches += 1;
+        let response = request
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch `{}`.", self.params.url))?;
+        let status = response.status();
+        if status == StatusCode::NOT_MODIFIED {
+            self.counters.num_not_modified_fetches += 1;
+            return Ok(None);
+        }
+        if !status.is_success() {
+            bail!(
+                "Failed to fetch `{}`: server responded with status `{}`.",
+                self.params.url,
+                status
+            );
+        }
+        self.etag_opt = header_value(response.headers(), ETAG);
+        self.last_modified_opt = header_value(response.headers(), LAST_MODIFIED);
+        let body = response
+            .text()
+            .await
+            .with_context(|| format!("Failed to read the body of `{}`.", self.params.url))?;
+
+        // The lines already ingested are only skipped if the resource still starts with them.
+        let prefix_len = skip_lines(&body, self.current_version.num_lines);
+        let is_same_version = self.current_version.generation > 0
+            && extend_prefix_hash(empty_prefix_hash(), &body[..prefix_len])
+                == self.current_version.prefix_hash;
+        let cursor = if is_same_version {
+            prefix_len
+        } else {
+            self.current_version = ResourceVersion {
+                generation: self.current_version.generation + 1,
+                num_lines: 0,
+                prefix_hash: empty_prefix_hash(),
+            };
+            0
+        };
+        Ok(Some(FetchedResource { body, cursor }))
+    }
+}
+
+#[async_trait]
+impl Source for HttpSource {
+    async fn emit_batches(
+        &mut self,
+        batch_sink: &Mailbox<Indexer>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        if self.resource_opt.is_none() {
+            match ctx.protect_future(self.fetch()).await {
+                Ok(Some(resource)) => self.resource_opt = Some(resource),
+                Ok(None) => return Ok(self.poll_interval()),
+                Err(error) => {
+                    self.counters.num_failed_fetches += 1;
+                    warn!(source_id = %self.source_id, error = ?error, "Failed to fetch resource.");
+                    return Ok(self.poll_interval());
+                }
+            }
+        }
+        let resource = self
+            .resource_opt
+            .as_mut()
+            .expect("The resource should have been fetched.");
+        let mut doc_batch = RawDocBatch::default();
+        let mut num_bytes = 0;
+        for line in resource.body[resource.cursor..].split_inclusive('\n') {
+            if num_bytes as u64 >= BATCH_NUM_BYTES_THRESHOLD {
+                break;
+            }
+            num_bytes += line.len();
+            doc_batch.docs.push(line.to_string());
+        }
+        let batch_bytes = &resource.body[resource.cursor..resource.cursor + num_bytes];
+        let prefix_hash = extend_prefix_hash(self.current_version.prefix_hash, batch_bytes);
+        resource.cursor += num_bytes;
+        let reached_end_of_resource = resource.cursor == resource.body.len();
+
+        if !doc_batch.docs.is_empty() {
+            let num_lines = doc_batch.docs.len() as u64;
+            self.current_version.num_lines += num_lines;
+            self.current_version.prefix_hash = prefix_hash;
+            let new_position = self.current_version.to_position();
+            doc_batch
+                .checkpoint_delta
+                .record_partition_delta(
+                    self.partition_id.clone(),
+                    self.current_position.clone(),
+                    new_position.clone(),
+                )
+                .context("Failed to record checkpoint delta.")?;
+            self.current_position = new_position;
+            self.counters.num_lines_processed += num_lines;
+            self.counters.num_bytes_processed += num_bytes as u64;
+            ctx.send_message(batch_sink, doc_batch).await?;
+        }
+        if reached_end_of_resource {
+            info!(
+                source_id = %self.source_id,
+                generation = self.current_version.generation,
+                num_lines = self.current_version.num_lines,
+                "Reached end of resource."
+            );
+            self.resource_opt = None;
+            self.is_fully_ingested = true;
+            return Ok(self.poll_interval());
+        }
+        Ok(Duration::default())
+    }
+
+    fn name(&self) -> String {
+        format!("HttpSource{{source_id={}}}", self.source_id)
+    }
+
+    fn observable_state(&self) -> serde_json::Value {
+        serde_json::to_value(&self.counters).unwrap()
+    }
+}
+
+pub struct HttpSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for HttpSourceFactory {
+    type Source = HttpSource;
+    type Params = HttpSourceParams;
+
+    async fn typed_create_source(
+        source_id: String,
+        params: HttpSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<HttpSource> {
+        let partition_id = PartitionId::from(params.url.as_str());
+        let current_position = checkpoint
+            .position_for_partition(&partition_id)
+            .cloned()
+            .unwrap_or(Position::Beginning);
+        let current_version = ResourceVersion::from_position(&current_position)?;
+        let client = Client::builder()
+            .build()
+            .context("Failed to create HTTP client.")?;
+        Ok(HttpSource {
+            source_id,
+            params,
+            client,
+            partition_id,
+            current_position,
+            current_version,
+            etag_opt: None,
+            last_modified_opt: None,
+            is_fully_ingested: false,
+            resource_opt: None,
+            counters: HttpSourceCounters::default(),
+        })
+    }
+}
+
+fn header_value(headers: &HeaderMap, header_name: reqwest::header::HeaderName) -> Option<String> {
+    headers
+        .get(header_name)
+        .and_then(|header_value| header_value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// Returns the hash of an empty prefix, i.e. of a version of the resource of which no line has
+/// been ingested yet.
+fn empty_prefix_hash() -> u64 {
+    FnvHasher::default().finish()
+}
+
+/// Extends the hash of the lines already ingested with the lines that follow them. FNV is used
+/// because, unlike the standard library hasher, it is stable across releases: prefix hashes are
+/// stored in checkpoints.
+fn extend_prefix_hash(prefix_hash: u64, lines: &str) -> u64 {
+    let mut hasher = FnvHasher::with_key(prefix_hash);
+    hasher.write(lines.as_bytes());
+    hasher.finish()
+}
+
+/// Returns the byte offset right after the first `num_lines` lines of the body.
+fn skip_lines(body: &str, num_lines: u64) -> usize {
+    body.split_inclusive('\n')
+        .take(num_lines as usize)
+        .map(str::len)
+        .sum()
+}
+
+/// Checks whether the resource can be fetched.
+pub(super) async fn check_connectivity(params: &HttpSourceParams) -> anyhow::Result<()> {
+    let mut request = Client::new().head(&params.url);
+    for (header_name, header_value) in &params.headers {
+        request = request.header(header_name, header_value);
+    }
+    let response = request
+        .send()
+        .await
+        .with_context(|| format!("Failed to connect to `{}`.", params.url))?;
+    if !response.status().is_success() {
+        bail!(
+            "Failed to fetch `{}`: server responded with status `{}`.",
+            params.url,
+            response.status()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+
+    use quickwit_actors::{create_test_mailbox, Universe};
+    use quickwit_metastore::checkpoint::SourceCheckpointDelta;
+    use warp::http::StatusCode as WarpStatusCode;
+    use warp::{Filter, Reply};
+
+    use super::*;
+    use crate::source::SourceActor;
+
+    const POLL_INTERVAL_SECS: u64 = 3_600;
+
+    /// ETag, if any, and body of the resource served by the test server.
+    type SharedResource = Arc<Mutex<(Option<String>, String)>>;
+
+    fn start_ndjson_server(resource: SharedResource) -> SocketAddr {
+        let route = warp::path("logs.ndjson")
+            .and(warp::header::optional::<String>("if-none-match"))
+            .map(move |if_none_match_opt: Option<String>| {
+                let (etag_opt, body) = resource.lock().unwrap().clone();
+                let etag = match etag_opt {
+                    Some(etag) => etag,
+                    None => return body.into_response(),
+                };
+                if if_none_match_opt.as_deref() == Some(etag.as_str()) {
+                    return WarpStatusCode::NOT_MODIFIED.into_response();
+                }
+                warp::reply::with_header(body, "etag", etag).into_response()
+            });
+        let (addr, server) = warp::serve(route).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+        addr
+    }
+
+    fn http_source_params(addr: SocketAddr) -> HttpSourceParams {
+        HttpSourceParams {
+            url: format!("http://{}/logs.ndjson", addr),
+            headers: BTreeMap::new(),
+            poll_interval_secs: POLL_INTERVAL_SECS,
+            conditional_fetch: true,
+        }
+    }
+
+    fn resource_version(generation: u64, lines: &str) -> ResourceVersion {
+        ResourceVersion {
+            generation,
+            num_lines: lines.split_inclusive('\n').count() as u64,
+            prefix_hash: extend_prefix_hash(empty_prefix_hash(), lines),
+        }
+    }
+
+    #[test]
+    fn test_resource_version_position_round_trip() {
+        let version = ResourceVersion {
+            generation: 2,
+            num_lines: 17,
+            prefix_hash: 0x0123_4567_89ab_cdef,
+        };
+        let position = version.to_position();
+        assert_eq!(
+            position.as_str(),
+            "00000000000000000002:00000000000000000017:0123456789abcdef"
+        );
+        assert_eq!(ResourceVersion::from_position(&position).unwrap(), version);
+        assert_eq!(
+            ResourceVersion::from_position(&Position::Beginning).unwrap(),
+            ResourceVersion::default()
+        );
+        assert!(ResourceVersion::from_position(&Position::from("foo")).is_err());
+        // A new version of the resource must always sort after the previous one.
+        let next_version = ResourceVersion {
+            generation: 3,
+            num_lines: 1,
+            prefix_hash: 0,
+        };
+        assert!(next_version.to_position() > position);
+    }
+
+    #[test]
+    fn test_extend_prefix_hash() {
+        let prefix_hash = extend_prefix_hash(empty_prefix_hash(), "{\"id\": 1}\n");
+        assert_eq!(
+            extend_prefix_hash(prefix_hash, "{\"id\": 2}\n"),
+            extend_prefix_hash(empty_prefix_hash(), "{\"id\": 1}\n{\"id\": 2}\n")
+        );
+        assert_ne!(
+            prefix_hash,
+            extend_prefix_hash(empty_prefix_hash(), "{\"id\": 2}\n")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_http_source() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let resource: SharedResource = Arc::new(Mutex::new((
+            Some("\"v1\"".to_string()),
+            "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n".to_string(),
+        )));
+        let addr = start_ndjson_server(resource.clone());
+        let params = http_source_params(addr);
+        let partition_id = PartitionId::from(params.url.as_str());
+
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let http_source = HttpSourceFactory::typed_create_source(
+            "my-http-source".to_string(),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await?;
+        let http_source_actor = SourceActor {
+            source: Box::new(http_source),
+            batch_sink: mailbox,
+        };
+        let (_http_source_mailbox, http_source_handle) =
+            universe.spawn_actor(http_source_actor).spawn();
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(
+            counters,
+            serde_json::json!({
+                "num_fetches": 1u64,
+                "num_not_modified_fetches": 0u64,
+                "num_failed_fetches": 0u64,
+                "num_lines_processed": 3u64,
+                "num_bytes_processed": 30u64,
+            })
+        );
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs.len(), 3);
+        let first_position =
+            resource_version(1, "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n").to_position();
+        assert_eq!(
+            batches[0].checkpoint_delta,
+            SourceCheckpointDelta::from_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                first_position.clone(),
+            )
+        );
+
+        // The resource has not changed: the server answers `304 Not Modified`.
+        universe
+            .simulate_time_shift(Duration::from_secs(POLL_INTERVAL_SECS))
+            .await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_fetches"], 2);
+        assert_eq!(counters["num_not_modified_fetches"], 1);
+        assert!(inbox.drain_for_test_typed::<RawDocBatch>().is_empty());
+
+        // A new version of the resource is ingested from its first line.
+        *resource.lock().unwrap() = (Some("\"v2\"".to_string()), "{\"id\": 4}\n".to_string());
+        universe
+            .simulate_time_shift(Duration::from_secs(POLL_INTERVAL_SECS))
+            .await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_fetches"], 3);
+        assert_eq!(counters["num_lines_processed"], 4);
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs, vec!["{\"id\": 4}\n".to_string()]);
+        assert_eq!(
+            batches[0].checkpoint_delta,
+            SourceCheckpointDelta::from_partition_delta(
+                partition_id,
+                first_position,
+                resource_version(2, "{\"id\": 4}\n").to_position(),
+            )
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_source_without_etag() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let resource: SharedResource =
+            Arc::new(Mutex::new((None, "{\"id\": 1}\n{\"id\": 2}\n".to_string())));
+        let addr = start_ndjson_server(resource.clone());
+        let params = http_source_params(addr);
+        let partition_id = PartitionId::from(params.url.as_str());
+
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let http_source = HttpSourceFactory::typed_create_source(
+            "my-http-source".to_string(),
+            params,
+            SourceCheckpoint::default(),
+        )
+        .await?;
+        let http_source_actor = SourceActor {
+            source: Box::new(http_source),
+            batch_sink: mailbox,
+        };
+        let (_http_source_mailbox, http_source_handle) =
+            universe.spawn_actor(http_source_actor).spawn();
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_lines_processed"], 2);
+        assert_eq!(inbox.drain_for_test_typed::<RawDocBatch>().len(), 1);
+
+        // The resource has not changed: none of its lines is ingested again.
+        universe
+            .simulate_time_shift(Duration::from_secs(POLL_INTERVAL_SECS))
+            .await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_fetches"], 2);
+        assert_eq!(counters["num_lines_processed"], 2);
+        assert!(inbox.drain_for_test_typed::<RawDocBatch>().is_empty());
+
+        // Lines were appended to the resource: only these lines are ingested.
+        resource.lock().unwrap().1.push_str("{\"id\": 3}\n");
+        universe
+            .simulate_time_shift(Duration::from_secs(POLL_INTERVAL_SECS))
+            .await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_lines_processed"], 3);
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs, vec!["{\"id\": 3}\n".to_string()]);
+        let appended_position =
+            resource_version(1, "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n").to_position();
+        assert_eq!(
+            batches[0].checkpoint_delta,
+            SourceCheckpointDelta::from_partition_delta(
+                partition_id.clone(),
+                resource_version(1, "{\"id\": 1}\n{\"id\": 2}\n").to_position(),
+                appended_position.clone(),
+            )
+        );
+
+        // The resource was replaced: it is ingested from its first line.
+        resource.lock().unwrap().1 = "{\"id\": 4}\n".to_string();
+        universe
+            .simulate_time_shift(Duration::from_secs(POLL_INTERVAL_SECS))
+            .await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_lines_processed"], 4);
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].docs, vec!["{\"id\": 4}\n".to_string()]);
+        assert_eq!(
+            batches[0].checkpoint_delta,
+            SourceCheckpointDelta::from_partition_delta(
+                partition_id,
+                appended_position,
+                resource_version(2, "{\"id\": 4}\n").to_position(),
+            )
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_http_source_resume_from_checkpoint() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let resource: SharedResource = Arc::new(Mutex::new((
+            Some("\"v1\"".to_string()),
+            "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n".to_string(),
+        )));
+        let addr = start_ndjson_server(resource);
+        let params = http_source_params(addr);
+        let partition_id = PartitionId::from(params.url.as_str());
+
+        let checkpointed_position = resource_version(1, "{\"id\": 1}\n").to_position();
+        let mut checkpoint = SourceCheckpoint::default();
+        checkpoint
+            .try_apply_delta(SourceCheckpointDelta::from_partition_delta(
+                partition_id.clone(),
+                Position::Beginning,
+                checkpointed_position.clone(),
+            ))
+            .unwrap();
+
+        let universe = Universe::new();
+        let (mailbox, inbox) = create_test_mailbox();
+        let http_source = HttpSourceFactory::typed_create_source(
+            "my-http-source".to_string(),
+            params,
+            checkpoint,
+        )
+        .await?;
+        let http_source_actor = SourceActor {
+            source: Box::new(http_source),
+            batch_sink: mailbox,
+        };
+        let (_http_source_mailbox, http_source_handle) =
+            universe.spawn_actor(http_source_actor).spawn();
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let counters = http_source_handle.process_pending_and_observe().await.state;
+        assert_eq!(counters["num_lines_processed"], 2);
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].docs,
+            vec!["{\"id\": 2}\n".to_string(), "{\"id\": 3}\n".to_string()]
+        );
+        assert_eq!(
+            batches[0].checkpoint_delta,
+            SourceCheckpointDelta::from_partition_delta(
+                partition_id,
+                checkpointed_position,
+                resource_version(1, "{\"id\": 1}\n{\"id\": 2}\n{\"id\": 3}\n").to_position(),
+            )
+        );
+        Ok(())
+    }
+}
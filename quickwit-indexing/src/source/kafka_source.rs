@@ -20,7 +20,7 @@
 use std::collections::HashMap;
 use std::fmt;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::{bail, Context};
 use async_trait::async_trait;
@@ -42,11 +42,13 @@ use rdkafka::topic_partition_list::TopicPartitionList;
 use rdkafka::types::RDKafkaErrorCode;
 use rdkafka::util::Timeout;
 use rdkafka::{ClientContext, Message, Offset};
+use serde::Serialize;
 use serde_json::json;
 use tokio::task::spawn_blocking;
 use tracing::{debug, info, warn};
 
 use crate::actors::Indexer;
+use crate::metrics::INDEXING_METRICS;
 use crate::models::RawDocBatch;
 use crate::source::{Source, SourceContext, TypedSourceFactory};
 
@@ -58,9 +60,13 @@ use crate::source::{Source, SourceContext, TypedSourceFactory};
 /// - we will be needlessly occupying resident memory in the mailbox.
 /// - we will not have a precise control of the timeout before commit.
 ///
-/// 5MB seems like a good one size fits all value.
+/// 5MB seems like a good one size fits all value. It can be overridden with the
+/// `batch_num_bytes_target` source parameter.
 const TARGET_BATCH_NUM_BYTES: u64 = 5_000_000;
 
+/// Interval at which the consumer lag of each assigned partition is reported.
+const CONSUMER_LAG_REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Factory for instantiating a `KafkaSource`.
 pub struct KafkaSourceFactory;
 
@@ -98,6 +104,17 @@ impl ConsumerContext for RdKafkaContext {
 
 type RdKafkaConsumer = StreamConsumer<RdKafkaContext>;
 
+/// Reason why the source stopped consuming messages, reported in its observable state to help
+/// diagnose why a source stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KafkaSourceTerminationReason {
+    /// The mailbox of the actor receiving the batches was closed.
+    ChannelClosed,
+    /// The consumer returned an error.
+    ConsumerError,
+}
+
 #[derive(Default)]
 pub struct KafkaSourceState {
     /// Partitions IDs assigned to the source.
@@ -112,6 +129,10 @@ pub struct KafkaSourceState {
     pub num_messages_processed: u64,
     // Number of invalid messages, i.e., that were empty or could not be parsed.
     pub num_invalid_messages: u64,
+    /// Number of messages skipped because their payload exceeded `max_message_bytes`.
+    pub num_oversized_messages: u64,
+    /// Reason why the source stopped consuming messages, if it did.
+    pub termination_reason: Option<KafkaSourceTerminationReason>,
 }
 
 /// A `KafkaSource` consumes a topic and forwards its messages to an `Indexer`.
@@ -119,7 +140,10 @@ pub struct KafkaSource {
     source_id: String,
     topic: String,
     consumer: Arc<RdKafkaConsumer>,
+    max_message_bytes_opt: Option<u64>,
+    batch_num_bytes_target: u64,
     state: KafkaSourceState,
+    consumer_lag_reported_at: Option<Instant>,
 }
 
 impl fmt::Debug for KafkaSource {
@@ -140,6 +164,10 @@ impl KafkaSource {
         checkpoint: SourceCheckpoint,
     ) -> anyhow::Result<Self> {
         let topic = params.topic;
+        let max_message_bytes_opt = params.max_message_bytes;
+        let batch_num_bytes_target = params
+            .batch_num_bytes_target
+            .unwrap_or(TARGET_BATCH_NUM_BYTES);
         let consumer = create_consumer(&source_id, params.client_log_level, params.client_params)?;
         let partition_ids = fetch_partition_ids(consumer.clone(), &topic).await?;
         let assigned_partition_ids = partition_ids
@@ -156,6 +184,7 @@ impl KafkaSource {
         info!(
             topic = %topic,
             assignment = ?assignment,
+            batch_num_bytes_target = %batch_num_bytes_target,
             "Starting Kafka source."
         );
         consumer
@@ -171,9 +200,52 @@ impl KafkaSource {
             source_id,
             topic,
             consumer,
+            max_message_bytes_opt,
+            batch_num_bytes_target,
             state,
+            consumer_lag_reported_at: None,
         })
     }
+
+    /// Fetches the high watermarks of the assigned partitions and publishes the consumer lag of
+    /// each partition.
+    async fn report_consumer_lag(&self, ctx: &SourceContext) {
+        let partition_ids: Vec<i32> = self
+            .state
+            .assigned_partition_ids
+            .keys()
+            .copied()
+            .sorted()
+            .collect();
+        let timeout = Duration::from_secs(1);
+        let watermarks = match ctx
+            .protect_future(fetch_watermarks(
+                self.consumer.clone(),
+                &self.topic,
+                &partition_ids,
+                timeout,
+            ))
+            .await
+        {
+            Ok(watermarks) => watermarks,
+            Err(error) => {
+                warn!(topic = %self.topic, error = ?error, "Failed to report consumer lag.");
+                return;
+            }
+        };
+        for (partition_id, (low_watermark, high_watermark)) in watermarks {
+            let partition_label = partition_id.to_string();
+            let consumer_lag = compute_consumer_lag(
+                self.state.current_positions.get(&partition_id),
+                low_watermark,
+                high_watermark,
+            );
+            INDEXING_METRICS
+                .kafka_consumer_lag
+                .with_label_values(&[&self.topic, &partition_label])
+                .set(consumer_lag);
+        }
+    }
 }
 
 #[async_trait]
@@ -183,6 +255,7 @@ impl Source for KafkaSource {
         batch_sink: &Mailbox<Indexer>,
         ctx: &SourceContext,
     ) -> Result<Duration, ActorExitStatus> {
+        let received_at = Instant::now();
         let mut docs = Vec::new();
         let mut checkpoint_delta = SourceCheckpointDelta::default();
 
@@ -206,15 +279,35 @@ impl Source for KafkaSource {
                 }
                 // FIXME: This is assuming that Kafka errors are not recoverable, it may not be the
                 // case.
-                Err(err) => return Err(ActorExitStatus::from(anyhow::anyhow!(err))),
+                Err(err) => {
+                    self.state.termination_reason =
+                        Some(KafkaSourceTerminationReason::ConsumerError);
+                    return Err(ActorExitStatus::from(anyhow::anyhow!(err)));
+                }
             };
-            if let Some(doc) = parse_message_payload(&message) {
-                docs.push(doc);
+            let payload_len = message.payload_len() as u64;
+            let is_oversized = self
+                .max_message_bytes_opt
+                .map(|max_message_bytes| payload_len > max_message_bytes)
+                .unwrap_or(false);
+            if is_oversized {
+                warn!(
+                    topic = %self.topic,
+                    partition_id = ?message.partition(),
+                    offset = ?message.offset(),
+                    num_bytes = ?payload_len,
+                    "Skipping message exceeding `max_message_bytes`."
+                );
+                self.state.num_oversized_messages += 1;
             } else {
-                self.state.num_invalid_messages += 1;
+                if let Some(doc) = parse_message_payload(&message) {
+                    docs.push(doc);
+                } else {
+                    self.state.num_invalid_messages += 1;
+                }
+                batch_num_bytes += payload_len;
             }
-            batch_num_bytes += message.payload_len() as u64;
-            self.state.num_bytes_processed += message.payload_len() as u64;
+            self.state.num_bytes_processed += payload_len;
             self.state.num_messages_processed += 1;
 
             let partition_id = self
@@ -240,7 +333,7 @@ impl Source for KafkaSource {
                 .record_partition_delta(partition_id, previous_position, current_position)
                 .context("Failed to record partition delta.")?;
 
-            if batch_num_bytes >= TARGET_BATCH_NUM_BYTES {
+            if batch_num_bytes >= self.batch_num_bytes_target {
                 break;
             }
             ctx.record_progress();
@@ -249,12 +342,28 @@ impl Source for KafkaSource {
             let batch = RawDocBatch {
                 docs,
                 checkpoint_delta,
+                start_byte_offset: None,
+                received_at,
             };
-            ctx.send_message(batch_sink, batch).await?;
+            if let Err(send_error) = ctx.send_message(batch_sink, batch).await {
+                self.state.termination_reason = Some(KafkaSourceTerminationReason::ChannelClosed);
+                return Err(send_error.into());
+            }
+        }
+        if self
+            .consumer_lag_reported_at
+            .map(|reported_at| reported_at.elapsed() >= CONSUMER_LAG_REPORT_INTERVAL)
+            .unwrap_or(true)
+        {
+            self.report_consumer_lag(ctx).await;
+            self.consumer_lag_reported_at = Some(Instant::now());
         }
         if self.state.num_active_partitions == 0 {
             info!(topic = %self.topic, "Reached end of topic.");
-            ctx.send_exit_with_success(batch_sink).await?;
+            if let Err(send_error) = ctx.send_exit_with_success(batch_sink).await {
+                self.state.termination_reason = Some(KafkaSourceTerminationReason::ChannelClosed);
+                return Err(send_error.into());
+            }
             return Err(ActorExitStatus::Success);
         }
         Ok(Duration::default())
@@ -288,6 +397,8 @@ impl Source for KafkaSource {
             "num_bytes_processed": self.state.num_bytes_processed,
             "num_messages_processed": self.state.num_messages_processed,
             "num_invalid_messages": self.state.num_invalid_messages,
+            "num_oversized_messages": self.state.num_oversized_messages,
+            "termination_reason": self.state.termination_reason,
         })
     }
 }
@@ -301,6 +412,24 @@ fn previous_position_for_offset(offset: i64) -> Position {
     }
 }
 
+/// Computes the number of messages available for consumption in a partition after the current
+/// position, i.e. `high_watermark - current_offset - 1`. When no message has been consumed yet,
+/// all the messages between the low and high watermarks are pending.
+fn compute_consumer_lag(
+    current_position_opt: Option<&Position>,
+    low_watermark: i64,
+    high_watermark: i64,
+) -> i64 {
+    let current_offset = match current_position_opt {
+        Some(Position::Offset(offset_str)) => match offset_str.parse::<i64>() {
+            Ok(offset) => offset,
+            Err(_) => return 0,
+        },
+        Some(Position::Beginning) | None => return (high_watermark - low_watermark).max(0),
+    };
+    (high_watermark - current_offset - 1).max(0)
+}
+
 /// Checks whether we can establish a connection to the Kafka broker.
 pub(super) async fn check_connectivity(params: KafkaSourceParams) -> anyhow::Result<()> {
     let source_id = "quickwit-connectivity-check";
@@ -618,6 +747,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_compute_consumer_lag() {
+        // Empty partition.
+        assert_eq!(compute_consumer_lag(None, 0, 0), 0);
+        assert_eq!(compute_consumer_lag(Some(&Position::Beginning), 0, 0), 0);
+        // Nothing consumed yet.
+        assert_eq!(compute_consumer_lag(None, 0, 10), 10);
+        assert_eq!(compute_consumer_lag(Some(&Position::Beginning), 5, 10), 5);
+        // Consumer is behind.
+        assert_eq!(compute_consumer_lag(Some(&Position::from(3u64)), 0, 10), 6);
+        // Consumer is caught up.
+        assert_eq!(compute_consumer_lag(Some(&Position::from(9u64)), 0, 10), 0);
+    }
+
     #[test]
     fn test_compute_next_offset() -> anyhow::Result<()> {
         {
@@ -825,6 +968,8 @@ mod kafka_broker_tests {
                     "bootstrap.servers": bootstrap_servers,
                     "enable.partition.eof": true,
                 }),
+                max_message_bytes: None,
+                batch_num_bytes_target: None,
             }),
         };
 
@@ -861,6 +1006,8 @@ mod kafka_broker_tests {
                 "num_bytes_processed": 0u64,
                 "num_messages_processed": 0u64,
                 "num_invalid_messages": 0u64,
+                "num_oversized_messages": 0u64,
+                "termination_reason": null,
             });
             assert_eq!(exit_state, expected_state);
         }
@@ -933,6 +1080,8 @@ mod kafka_broker_tests {
                 "num_bytes_processed": 72u64,
                 "num_messages_processed": 9u64,
                 "num_invalid_messages": 3u64,
+                "num_oversized_messages": 0u64,
+                "termination_reason": null,
             });
             assert_eq!(state, expected_state);
         }
@@ -988,12 +1137,65 @@ mod kafka_broker_tests {
                 "num_bytes_processed": 36u64,
                 "num_messages_processed": 5u64,
                 "num_invalid_messages": 2u64,
+                "num_oversized_messages": 0u64,
+                "termination_reason": null,
             });
             assert_eq!(exit_state, expected_exit_state);
         }
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_kafka_source_channel_closed() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+
+        let universe = Universe::new();
+
+        let bootstrap_servers = "localhost:9092".to_string();
+        let topic = append_random_suffix("test-kafka-source-channel-closed-topic");
+
+        let admin_client = create_admin_client(&bootstrap_servers)?;
+        create_topic(&admin_client, &topic, 1).await?;
+        populate_topic(
+            &bootstrap_servers,
+            &topic,
+            3,
+            &key_fn,
+            &message_fn,
+            None,
+            None,
+        )
+        .await?;
+
+        let source_config = SourceConfig {
+            source_id: "test-kafka-source-channel-closed".to_string(),
+            source_params: SourceParams::Kafka(KafkaSourceParams {
+                topic: topic.clone(),
+                client_log_level: None,
+                client_params: json!({
+                    "bootstrap.servers": bootstrap_servers,
+                    "enable.partition.eof": true,
+                }),
+                max_message_bytes: None,
+                batch_num_bytes_target: None,
+            }),
+        };
+        let source = quickwit_supported_sources()
+            .load_source(source_config, SourceCheckpoint::default())
+            .await?;
+        let (sink, inbox) = create_test_mailbox();
+        drop(inbox);
+        let actor = SourceActor {
+            source,
+            batch_sink: sink,
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        let (exit_status, exit_state) = handle.join().await;
+        assert!(!exit_status.is_success());
+        assert_eq!(exit_state["termination_reason"], json!("channel_closed"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_fetch_partition_ids() -> anyhow::Result<()> {
         let bootstrap_servers = "localhost:9092".to_string();
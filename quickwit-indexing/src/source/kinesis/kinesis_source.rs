@@ -19,7 +19,7 @@
 
 use std::collections::HashMap;
 use std::fmt;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use async_trait::async_trait;
@@ -200,6 +200,7 @@ impl Source for KinesisSource {
         batch_sink: &Mailbox<Indexer>,
         ctx: &SourceContext,
     ) -> Result<Duration, ActorExitStatus> {
+        let received_at = Instant::now();
         let mut batch_num_bytes = 0;
         let mut docs = Vec::new();
         let mut checkpoint_delta = SourceCheckpointDelta::default();
@@ -304,6 +305,8 @@ impl Source for KinesisSource {
             let batch = RawDocBatch {
                 docs,
                 checkpoint_delta,
+                start_byte_offset: None,
+                received_at,
             };
             ctx.send_message(batch_sink, batch).await?;
         }
@@ -57,12 +57,20 @@
 //!   that file.
 //! - the kafka source: the partition id is a kafka topic partition id, and the position is a kafka
 //!   offset.
+//! - the pulsar source: the partition id is a pulsar topic partition name, and the position is
+//!   derived from the pulsar message id.
+//! - the http source: the partition id is the URL of the resource, and the position is derived
+//!   from the version of the resource and the number of lines ingested.
 mod file_source;
+#[cfg(feature = "http-source")]
+mod http_source;
 mod ingest_api_source;
 #[cfg(feature = "kafka")]
 mod kafka_source;
 #[cfg(feature = "kinesis")]
 mod kinesis;
+#[cfg(feature = "pulsar")]
+mod pulsar_source;
 mod source_factory;
 mod vec_source;
 mod void_source;
@@ -73,11 +81,15 @@ use std::time::Duration;
 use anyhow::bail;
 use async_trait::async_trait;
 pub use file_source::{FileSource, FileSourceFactory};
+#[cfg(feature = "http-source")]
+pub use http_source::{HttpSource, HttpSourceFactory};
 #[cfg(feature = "kafka")]
 pub use kafka_source::{KafkaSource, KafkaSourceFactory};
 #[cfg(feature = "kinesis")]
 pub use kinesis::kinesis_source::{KinesisSource, KinesisSourceFactory};
 use once_cell::sync::OnceCell;
+#[cfg(feature = "pulsar")]
+pub use pulsar_source::{PulsarSource, PulsarSourceFactory};
 use quickwit_actors::{Actor, ActorContext, ActorExitStatus, Handler, Mailbox};
 use quickwit_common::runtimes::RuntimeType;
 use quickwit_config::{SourceConfig, SourceParams};
@@ -244,6 +256,10 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
         source_factory.add_source("kafka", KafkaSourceFactory);
         #[cfg(feature = "kinesis")]
         source_factory.add_source("kinesis", KinesisSourceFactory);
+        #[cfg(feature = "pulsar")]
+        source_factory.add_source("pulsar", PulsarSourceFactory);
+        #[cfg(feature = "http-source")]
+        source_factory.add_source("http", HttpSourceFactory);
         source_factory.add_source("vec", VecSourceFactory);
         source_factory.add_source("void", VoidSourceFactory);
         source_factory.add_source("ingest-api", IngestApiSourceFactory);
@@ -254,6 +270,10 @@ pub fn quickwit_supported_sources() -> &'static SourceLoader {
 pub async fn check_source_connectivity(source_config: &SourceConfig) -> anyhow::Result<()> {
     match &source_config.source_params {
         SourceParams::File(params) => {
+            #[cfg(not(feature = "file-compression"))]
+            if params.compression().is_some() {
+                bail!("Quickwit binary was not compiled with the `file-compression` feature.");
+            }
             if let Some(filepath) = &params.filepath {
                 if !Path::new(filepath).exists() {
                     bail!("File `{}` does not exist.", filepath.display())
@@ -283,6 +303,28 @@ pub async fn check_source_connectivity(source_config: &SourceConfig) -> anyhow::
                 Ok(())
             }
         }
+        #[allow(unused_variables)]
+        SourceParams::Pulsar(params) => {
+            #[cfg(not(feature = "pulsar"))]
+            bail!("Quickwit binary was not compiled with the `pulsar` feature.");
+
+            #[cfg(feature = "pulsar")]
+            {
+                pulsar_source::check_connectivity(params.clone()).await?;
+                Ok(())
+            }
+        }
+        #[allow(unused_variables)]
+        SourceParams::Http(params) => {
+            #[cfg(not(feature = "http-source"))]
+            bail!("Quickwit binary was not compiled with the `http-source` feature.");
+
+            #[cfg(feature = "http-source")]
+            {
+                http_source::check_connectivity(params).await?;
+                Ok(())
+            }
+        }
         _ => Ok(()),
     }
 }
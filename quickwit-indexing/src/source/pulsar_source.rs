@@ -0,0 +1,699 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use futures::StreamExt;
+use itertools::Itertools;
+use pulsar::consumer::{ConsumerOptions, InitialPosition};
+use pulsar::proto::MessageIdData;
+use pulsar::{Authentication, Consumer, Pulsar, SubType, TokioExecutor};
+use quickwit_actors::{ActorContext, ActorExitStatus, Mailbox};
+use quickwit_config::PulsarSourceParams;
+use quickwit_metastore::checkpoint::{
+    PartitionId, Position, SourceCheckpoint, SourceCheckpointDelta,
+};
+use serde_json::json;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::actors::Indexer;
+use crate::models::RawDocBatch;
+use crate::source::{Source, SourceActor, SourceContext, TypedSourceFactory};
+
+/// We try to emit chewable batches for the indexer.
+/// One batch = one message to the indexer actor.
+///
+/// See the `KafkaSource` for the rationale behind this value.
+const TARGET_BATCH_NUM_BYTES: u64 = 5_000_000;
+
+/// Partition index of the messages of a non-partitioned topic.
+const NON_PARTITIONED_TOPIC_INDEX: i32 = -1;
+
+/// A message received from a Pulsar topic, stripped down to what the source needs.
+#[derive(Debug)]
+struct PulsarMessage {
+    topic: String,
+    message_id: MessageIdData,
+    payload: Vec<u8>,
+}
+
+/// The subset of the Pulsar consumer API used by the source, which allows the consumer to be
+/// mocked in tests.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+trait PulsarConsumer: Send + 'static {
+    /// Returns the next message of the subscription, or `None` if the consumer was closed.
+    async fn next_message(&mut self) -> Option<anyhow::Result<PulsarMessage>>;
+
+    /// Acknowledges all the messages of the topic up to the message `message_id` included.
+    async fn cumulative_ack(
+        &mut self,
+        topic: &str,
+        message_id: MessageIdData,
+    ) -> anyhow::Result<()>;
+}
+
+#[async_trait]
+impl PulsarConsumer for Consumer<Vec<u8>, TokioExecutor> {
+    async fn next_message(&mut self) -> Option<anyhow::Result<PulsarMessage>> {
+        let message = match self.next().await? {
+            Ok(message) => message,
+            Err(error) => return Some(Err(anyhow::anyhow!(error))),
+        };
+        let message_id = message.message_id().clone();
+        Some(Ok(PulsarMessage {
+            topic: message.topic,
+            message_id,
+            payload: message.payload.data,
+        }))
+    }
+
+    async fn cumulative_ack(
+        &mut self,
+        topic: &str,
+        message_id: MessageIdData,
+    ) -> anyhow::Result<()> {
+        self.cumulative_ack_with_id(topic, message_id).await?;
+        Ok(())
+    }
+}
+
+/// Factory for instantiating a `PulsarSource`.
+pub struct PulsarSourceFactory;
+
+#[async_trait]
+impl TypedSourceFactory for PulsarSourceFactory {
+    type Source = PulsarSource;
+    type Params = PulsarSourceParams;
+
+    async fn typed_create_source(
+        source_id: String,
+        params: PulsarSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self::Source> {
+        PulsarSource::try_new(source_id, params, checkpoint).await
+    }
+}
+
+#[derive(Default)]
+pub struct PulsarSourceState {
+    /// Position of the last message processed (or checkpointed) for each partition topic.
+    pub current_positions: BTreeMap<PartitionId, Position>,
+    /// Partition index of each partition topic, required to acknowledge messages.
+    pub partition_indices: BTreeMap<PartitionId, Option<i32>>,
+    /// Position of the last message of each partition when the source started. Only tracked in
+    /// backfill mode.
+    pub end_positions: HashMap<i32, Position>,
+    /// Position of the last message received for each partition index. Only tracked in backfill
+    /// mode.
+    pub reached_positions: HashMap<i32, Position>,
+    /// Number of bytes processed by the source.
+    pub num_bytes_processed: u64,
+    /// Number of messages processed by the source (including invalid messages).
+    pub num_messages_processed: u64,
+    // Number of invalid messages, i.e., that were empty or could not be parsed.
+    pub num_invalid_messages: u64,
+    /// Number of messages skipped because they were already covered by the checkpoint.
+    pub num_skipped_messages: u64,
+}
+
+/// A `PulsarSource` consumes a topic and forwards its messages to an `Indexer`.
+pub struct PulsarSource {
+    source_id: String,
+    topic: String,
+    subscription_name: String,
+    consumer: Mutex<Box<dyn PulsarConsumer>>,
+    backfill_mode: bool,
+    state: PulsarSourceState,
+}
+
+impl fmt::Debug for PulsarSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "PulsarSource {{ source_id: {}, topic: {} }}",
+            self.source_id, self.topic
+        )
+    }
+}
+
+impl PulsarSource {
+    /// Instantiates a new `PulsarSource`.
+    pub async fn try_new(
+        source_id: String,
+        params: PulsarSourceParams,
+        checkpoint: SourceCheckpoint,
+    ) -> anyhow::Result<Self> {
+        let pulsar = connect_pulsar(&params).await?;
+        let mut consumer: Consumer<Vec<u8>, TokioExecutor> = pulsar
+            .consumer()
+            .with_topic(&params.topic)
+            .with_subscription(&params.subscription_name)
+            .with_subscription_type(SubType::Exclusive)
+            .with_consumer_name(format!("quickwit-{}", source_id))
+            .with_options(ConsumerOptions {
+                initial_position: InitialPosition::Earliest,
+                ..Default::default()
+            })
+            .build()
+            .await
+            .with_context(|| {
+                format!(
+                    "Failed to create Pulsar consumer for topic `{}`.",
+                    params.topic
+                )
+            })?;
+        let end_positions = if params.backfill_mode {
+            fetch_end_positions(&mut consumer).await?
+        } else {
+            HashMap::new()
+        };
+        info!(
+            topic = %params.topic,
+            subscription_name = %params.subscription_name,
+            backfill_mode = %params.backfill_mode,
+            "Starting Pulsar source."
+        );
+        Ok(PulsarSource::new(
+            source_id,
+            params,
+            checkpoint,
+            Box::new(consumer),
+            end_positions,
+        ))
+    }
+
+    fn new(
+        source_id: String,
+        params: PulsarSourceParams,
+        checkpoint: SourceCheckpoint,
+        consumer: Box<dyn PulsarConsumer>,
+        end_positions: HashMap<i32, Position>,
+    ) -> Self {
+        let current_positions = checkpoint
+            .iter()
+            .filter(|(_, position)| *position != Position::Beginning)
+            .collect();
+        let state = PulsarSourceState {
+            current_positions,
+            end_positions,
+            ..Default::default()
+        };
+        PulsarSource {
+            source_id,
+            topic: params.topic,
+            subscription_name: params.subscription_name,
+            consumer: Mutex::new(consumer),
+            backfill_mode: params.backfill_mode,
+            state,
+        }
+    }
+
+    /// Returns true when every partition of the topic has been consumed up to the last message
+    /// that was available when the source started.
+    fn reached_end_of_topic(&self) -> bool {
+        self.state
+            .end_positions
+            .iter()
+            .all(|(partition_index, end_position)| {
+                self.state
+                    .reached_positions
+                    .get(partition_index)
+                    .map(|reached_position| reached_position >= end_position)
+                    .unwrap_or(false)
+            })
+    }
+}
+
+#[async_trait]
+impl Source for PulsarSource {
+    async fn emit_batches(
+        &mut self,
+        batch_sink: &Mailbox<Indexer>,
+        ctx: &SourceContext,
+    ) -> Result<Duration, ActorExitStatus> {
+        let received_at = Instant::now();
+        let mut docs = Vec::new();
+        let mut checkpoint_delta = SourceCheckpointDelta::default();
+        let mut batch_num_bytes = 0;
+
+        let deadline = tokio::time::sleep(quickwit_actors::HEARTBEAT / 2);
+        tokio::pin!(deadline);
+
+        let mut consumer = self.consumer.lock().await;
+
+        loop {
+            if self.backfill_mode && self.reached_end_of_topic() {
+                break;
+            }
+            let message = tokio::select! {
+                message_opt = consumer.next_message() => match message_opt {
+                    Some(Ok(message)) => message,
+                    Some(Err(error)) => return Err(ActorExitStatus::from(error)),
+                    None => break,
+                },
+                _ = &mut deadline => break,
+            };
+            let message_id = message.message_id;
+            let partition_id = PartitionId::from(message.topic.as_str());
+            let current_position = position_from_message_id(&message_id);
+            let partition_index = message_id.partition.unwrap_or(NON_PARTITIONED_TOPIC_INDEX);
+
+            if self.backfill_mode {
+                self.state
+                    .reached_positions
+                    .insert(partition_index, current_position.clone());
+            }
+            self.state
+                .partition_indices
+                .insert(partition_id.clone(), message_id.partition);
+
+            let previous_position = self
+                .state
+                .current_positions
+                .get(&partition_id)
+                .cloned()
+                .unwrap_or(Position::Beginning);
+            // The subscription cursor only moves forward once a split is published, so the
+            // messages already covered by the checkpoint may be redelivered.
+            if current_position <= previous_position {
+                self.state.num_skipped_messages += 1;
+                continue;
+            }
+            let payload_len = message.payload.len() as u64;
+            match String::from_utf8(message.payload) {
+                Ok(doc) if !doc.is_empty() => docs.push(doc),
+                Ok(_) => {
+                    debug!(topic = %message.topic, message_id = ?message_id, "Document is empty.");
+                    self.state.num_invalid_messages += 1;
+                }
+                Err(error) => {
+                    warn!(
+                        topic = %message.topic,
+                        message_id = ?message_id,
+                        error = ?error,
+                        "Failed to deserialize message payload."
+                    );
+                    self.state.num_invalid_messages += 1;
+                }
+            }
+            batch_num_bytes += payload_len;
+            self.state.num_bytes_processed += payload_len;
+            self.state.num_messages_processed += 1;
+
+            self.state
+                .current_positions
+                .insert(partition_id.clone(), current_position.clone());
+            checkpoint_delta
+                .record_partition_delta(partition_id, previous_position, current_position)
+                .context("Failed to record partition delta.")?;
+
+            if batch_num_bytes >= TARGET_BATCH_NUM_BYTES {
+                break;
+            }
+            ctx.record_progress();
+        }
+        drop(consumer);
+
+        if !checkpoint_delta.is_empty() {
+            let batch = RawDocBatch {
+                docs,
+                checkpoint_delta,
+                start_byte_offset: None,
+                received_at,
+            };
+            ctx.send_message(batch_sink, batch).await?;
+        }
+        if self.backfill_mode && self.reached_end_of_topic() {
+            info!(topic = %self.topic, "Reached end of topic.");
+            ctx.send_exit_with_success(batch_sink).await?;
+            return Err(ActorExitStatus::Success);
+        }
+        Ok(Duration::default())
+    }
+
+    async fn suggest_truncate(
+        &self,
+        checkpoint: SourceCheckpoint,
+        _ctx: &ActorContext<SourceActor>,
+    ) -> anyhow::Result<()> {
+        let mut consumer = self.consumer.lock().await;
+        for (partition_id, position) in checkpoint.iter() {
+            let partition_index = match self.state.partition_indices.get(&partition_id) {
+                Some(partition_index) => *partition_index,
+                None => continue,
+            };
+            let mut message_id = match message_id_from_position(&position)? {
+                Some(message_id) => message_id,
+                None => continue,
+            };
+            message_id.partition = partition_index;
+            consumer
+                .cumulative_ack(&partition_id.0, message_id)
+                .await
+                .with_context(|| {
+                    format!(
+                        "Failed to acknowledge messages up to position `{}` for topic `{}`.",
+                        position.as_str(),
+                        partition_id.0
+                    )
+                })?;
+        }
+        Ok(())
+    }
+
+    fn name(&self) -> String {
+        format!("PulsarSource{{source_id={}}}", self.source_id)
+    }
+
+    fn observable_state(&self) -> serde_json::Value {
+        let current_positions: Vec<(&str, &str)> = self
+            .state
+            .current_positions
+            .iter()
+            .map(|(partition_id, position)| (partition_id.0.as_str(), position.as_str()))
+            .sorted()
+            .collect();
+        json!({
+            "topic": self.topic,
+            "subscription_name": self.subscription_name,
+            "current_positions": current_positions,
+            "num_bytes_processed": self.state.num_bytes_processed,
+            "num_messages_processed": self.state.num_messages_processed,
+            "num_invalid_messages": self.state.num_invalid_messages,
+            "num_skipped_messages": self.state.num_skipped_messages,
+        })
+    }
+}
+
+/// Checks whether we can establish a connection to the Pulsar service and look up the topic.
+pub(super) async fn check_connectivity(params: PulsarSourceParams) -> anyhow::Result<()> {
+    let pulsar = connect_pulsar(&params).await?;
+    pulsar
+        .lookup_topic(params.topic.clone())
+        .await
+        .with_context(|| format!("Failed to look up topic `{}`.", params.topic))?;
+    Ok(())
+}
+
+/// Creates a new Pulsar client.
+async fn connect_pulsar(params: &PulsarSourceParams) -> anyhow::Result<Pulsar<TokioExecutor>> {
+    let mut pulsar_builder = Pulsar::builder(&params.service_url, TokioExecutor);
+    if let Some(auth_token) = &params.auth_token {
+        pulsar_builder = pulsar_builder.with_auth(Authentication {
+            name: "token".to_string(),
+            data: auth_token.clone().into_bytes(),
+        });
+    }
+    let pulsar = pulsar_builder.build().await.with_context(|| {
+        format!(
+            "Failed to connect to Pulsar service `{}`.",
+            params.service_url
+        )
+    })?;
+    Ok(pulsar)
+}
+
+/// Fetches the position of the last message of each partition of the topic. Empty partitions are
+/// ignored.
+async fn fetch_end_positions(
+    consumer: &mut Consumer<Vec<u8>, TokioExecutor>,
+) -> anyhow::Result<HashMap<i32, Position>> {
+    let last_message_ids = consumer
+        .get_last_message_id()
+        .await
+        .context("Failed to fetch the last message IDs of the topic.")?;
+    let mut end_positions = HashMap::with_capacity(last_message_ids.len());
+    for last_message_id_response in last_message_ids {
+        let last_message_id = last_message_id_response.last_message_id;
+        // The broker returns `-1` as entry ID when the partition is empty.
+        if last_message_id.entry_id == u64::MAX {
+            continue;
+        }
+        let partition_index = last_message_id
+            .partition
+            .unwrap_or(NON_PARTITIONED_TOPIC_INDEX);
+        end_positions.insert(partition_index, position_from_message_id(&last_message_id));
+    }
+    Ok(end_positions)
+}
+
+/// Encodes a Pulsar message ID as a `Position` whose lexicographical order matches the order of
+/// the messages within a partition.
+fn position_from_message_id(message_id: &MessageIdData) -> Position {
+    let batch_index = message_id.batch_index.unwrap_or(0).max(0);
+    let position_str = format!(
+        "{:0>20}:{:0>20}:{:0>10}",
+        message_id.ledger_id, message_id.entry_id, batch_index
+    );
+    Position::from(position_str)
+}
+
+/// Decodes a `Position` produced by `position_from_message_id`. Returns `None` for
+/// `Position::Beginning`.
+fn message_id_from_position(position: &Position) -> anyhow::Result<Option<MessageIdData>> {
+    let position_str = match position {
+        Position::Beginning => return Ok(None),
+        Position::Offset(position_str) => position_str,
+    };
+    let (ledger_id_str, entry_id_str, batch_index_str) =
+        match position_str.split(':').collect_tuple() {
+            Some(parts) => parts,
+            None => bail!("Failed to parse Pulsar position `{}`.", position_str),
+        };
+    let message_id = MessageIdData {
+        ledger_id: ledger_id_str
+            .parse()
+            .with_context(|| format!("Failed to parse ledger ID `{}`.", ledger_id_str))?,
+        entry_id: entry_id_str
+            .parse()
+            .with_context(|| format!("Failed to parse entry ID `{}`.", entry_id_str))?,
+        batch_index: Some(
+            batch_index_str
+                .parse()
+                .with_context(|| format!("Failed to parse batch index `{}`.", batch_index_str))?,
+        ),
+        ..Default::default()
+    };
+    Ok(Some(message_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use quickwit_actors::{create_test_mailbox, Universe};
+
+    use super::*;
+
+    const TOPIC: &str = "persistent://public/default/logs";
+
+    fn partition_topic(partition_index: i32) -> String {
+        format!("{}-partition-{}", TOPIC, partition_index)
+    }
+
+    fn message_id(partition_index: i32, entry_id: u64) -> MessageIdData {
+        MessageIdData {
+            ledger_id: partition_index as u64 + 1,
+            entry_id,
+            partition: Some(partition_index),
+            ..Default::default()
+        }
+    }
+
+    fn message(partition_index: i32, entry_id: u64, payload: &str) -> PulsarMessage {
+        PulsarMessage {
+            topic: partition_topic(partition_index),
+            message_id: message_id(partition_index, entry_id),
+            payload: payload.as_bytes().to_vec(),
+        }
+    }
+
+    fn mock_consumer(messages: Vec<PulsarMessage>) -> MockPulsarConsumer {
+        let mut messages = VecDeque::from(messages);
+        let mut consumer = MockPulsarConsumer::new();
+        consumer
+            .expect_next_message()
+            .returning(move || messages.pop_front().map(Ok));
+        consumer
+    }
+
+    #[tokio::test]
+    async fn test_pulsar_source_emit_batches() -> anyhow::Result<()> {
+        quickwit_common::setup_logging_for_tests();
+        let universe = Universe::new();
+        let (sink, inbox) = create_test_mailbox();
+
+        let params = PulsarSourceParams {
+            service_url: "pulsar://localhost:6650".to_string(),
+            topic: TOPIC.to_string(),
+            subscription_name: "quickwit".to_string(),
+            auth_token: None,
+            backfill_mode: true,
+        };
+        // The first message of partition 0 is covered by the checkpoint.
+        let checkpoint: SourceCheckpoint = vec![(
+            PartitionId::from(partition_topic(0).as_str()),
+            position_from_message_id(&message_id(0, 0)),
+        )]
+        .into_iter()
+        .collect();
+        let consumer = mock_consumer(vec![
+            message(0, 0, "{\"id\": 0}"),
+            message(1, 0, "{\"id\": 10}"),
+            message(0, 1, ""),
+            message(1, 1, "{\"id\": 11}"),
+            message(0, 2, "{\"id\": 2}"),
+        ]);
+        let end_positions = HashMap::from([
+            (0, position_from_message_id(&message_id(0, 2))),
+            (1, position_from_message_id(&message_id(1, 1))),
+        ]);
+        let source = PulsarSource::new(
+            "test-pulsar-source".to_string(),
+            params,
+            checkpoint,
+            Box::new(consumer),
+            end_positions,
+        );
+        let actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: sink,
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        let (exit_status, exit_state) = handle.join().await;
+        assert!(exit_status.is_success());
+
+        let batches = inbox.drain_for_test_typed::<RawDocBatch>();
+        assert_eq!(batches.len(), 1);
+        assert_eq!(
+            batches[0].docs,
+            vec!["{\"id\": 10}", "{\"id\": 11}", "{\"id\": 2}"]
+        );
+        let mut expected_checkpoint_delta = SourceCheckpointDelta::from_partition_delta(
+            PartitionId::from(partition_topic(0).as_str()),
+            position_from_message_id(&message_id(0, 0)),
+            position_from_message_id(&message_id(0, 2)),
+        );
+        expected_checkpoint_delta.record_partition_delta(
+            PartitionId::from(partition_topic(1).as_str()),
+            Position::Beginning,
+            position_from_message_id(&message_id(1, 1)),
+        )?;
+        assert_eq!(batches[0].checkpoint_delta, expected_checkpoint_delta);
+
+        let expected_exit_state = json!({
+            "topic": TOPIC,
+            "subscription_name": "quickwit",
+            "current_positions": vec![
+                (partition_topic(0), "00000000000000000001:00000000000000000002:0000000000"),
+                (partition_topic(1), "00000000000000000002:00000000000000000001:0000000000"),
+            ],
+            "num_bytes_processed": 29u64,
+            "num_messages_processed": 4u64,
+            "num_invalid_messages": 1u64,
+            "num_skipped_messages": 1u64,
+        });
+        assert_eq!(exit_state, expected_exit_state);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_pulsar_source_consumer_error() {
+        let universe = Universe::new();
+        let (sink, _inbox) = create_test_mailbox();
+
+        let params = PulsarSourceParams {
+            service_url: "pulsar://localhost:6650".to_string(),
+            topic: TOPIC.to_string(),
+            subscription_name: "quickwit".to_string(),
+            auth_token: None,
+            backfill_mode: false,
+        };
+        let mut consumer = MockPulsarConsumer::new();
+        consumer
+            .expect_next_message()
+            .returning(|| Some(Err(anyhow::anyhow!("Connection closed."))));
+        let source = PulsarSource::new(
+            "test-pulsar-source".to_string(),
+            params,
+            SourceCheckpoint::default(),
+            Box::new(consumer),
+            HashMap::new(),
+        );
+        let actor = SourceActor {
+            source: Box::new(source),
+            batch_sink: sink,
+        };
+        let (_mailbox, handle) = universe.spawn_actor(actor).spawn();
+        let (exit_status, _exit_state) = handle.join().await;
+        assert!(matches!(exit_status, ActorExitStatus::Failure(_)));
+    }
+
+    #[test]
+    fn test_position_from_message_id() {
+        let message_id = MessageIdData {
+            ledger_id: 7,
+            entry_id: 42,
+            batch_index: Some(3),
+            ..Default::default()
+        };
+        let position = position_from_message_id(&message_id);
+        assert_eq!(
+            position.as_str(),
+            "00000000000000000007:00000000000000000042:0000000003"
+        );
+        let decoded_message_id = message_id_from_position(&position).unwrap().unwrap();
+        assert_eq!(decoded_message_id.ledger_id, 7);
+        assert_eq!(decoded_message_id.entry_id, 42);
+        assert_eq!(decoded_message_id.batch_index, Some(3));
+
+        assert!(message_id_from_position(&Position::Beginning)
+            .unwrap()
+            .is_none());
+        assert!(message_id_from_position(&Position::from("foo".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_position_from_message_id_ordering() {
+        let message_ids = [
+            (1, 9, None),
+            (1, 10, Some(-1)),
+            (1, 10, Some(2)),
+            (2, 0, None),
+        ];
+        let positions: Vec<Position> = message_ids
+            .iter()
+            .map(|&(ledger_id, entry_id, batch_index)| {
+                position_from_message_id(&MessageIdData {
+                    ledger_id,
+                    entry_id,
+                    batch_index,
+                    ..Default::default()
+                })
+            })
+            .collect();
+        assert!(positions.windows(2).all(|window| window[0] < window[1]));
+    }
+}
@@ -25,7 +25,7 @@ use std::time::Instant;
 
 use anyhow::Context;
 use quickwit_metastore::SplitMetadata;
-use quickwit_storage::{PutPayload, Storage, StorageResult};
+use quickwit_storage::{BulkDeleteError, FileMetadata, PutPayload, Storage, StorageResult};
 use tantivy::Directory;
 use tokio::sync::Mutex;
 use tracing::info;
@@ -192,6 +192,63 @@ impl IndexingSplitStore {
         Ok(())
     }
 
+    /// Deletes a batch of splits.
+    ///
+    /// Failing to delete a split does not abort the deletion of the others. The returned error
+    /// reports the split files that were deleted and the ones that were not.
+    pub async fn bulk_delete(&self, split_ids: &[&str]) -> Result<(), BulkDeleteError> {
+        let split_paths: Vec<PathBuf> = split_ids
+            .iter()
+            .map(|split_id| PathBuf::from(quickwit_common::split_file(split_id)))
+            .collect();
+        let split_path_refs: Vec<&Path> = split_paths.iter().map(PathBuf::as_path).collect();
+        let mut bulk_delete_error = match self.remote_storage.bulk_delete(&split_path_refs).await {
+            Ok(()) => BulkDeleteError {
+                successes: split_paths.clone(),
+                ..Default::default()
+            },
+            Err(bulk_delete_error) => bulk_delete_error,
+        };
+        if let Some(local_split_store) = self.local_split_store.as_ref() {
+            let mut local_split_store_lock = local_split_store.lock().await;
+            let mut successes = Vec::with_capacity(bulk_delete_error.successes.len());
+            for (split_id, split_path) in split_ids.iter().zip(split_paths) {
+                if bulk_delete_error.failures.contains_key(&split_path) {
+                    continue;
+                }
+                match local_split_store_lock.remove_split(split_id).await {
+                    Ok(()) => successes.push(split_path),
+                    Err(error) => {
+                        bulk_delete_error.failures.insert(split_path, error);
+                    }
+                }
+            }
+            bulk_delete_error.successes = successes;
+        }
+        if bulk_delete_error.failures.is_empty() {
+            return Ok(());
+        }
+        Err(bulk_delete_error)
+    }
+
+    /// Lists the split files stored at the root of the remote storage.
+    pub async fn list_split_files(&self) -> StorageResult<Vec<FileMetadata>> {
+        let split_files = self
+            .remote_storage
+            .list(Path::new(""))
+            .await?
+            .into_iter()
+            .filter(|file| {
+                let is_split_file = file
+                    .path
+                    .extension()
+                    .map_or(false, |extension| extension == "split");
+                is_split_file && file.path.parent() == Some(Path::new(""))
+            })
+            .collect();
+        Ok(split_files)
+    }
+
     /// Gets a split from the split store, and makes it available to the given `output_path`.
     ///
     /// The output_path is expected to be a directory path.
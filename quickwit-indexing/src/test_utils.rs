@@ -137,6 +137,8 @@ impl TestSandbox {
             .ask_for_res(SpawnPipeline {
                 index_id: self.index_id.clone(),
                 source,
+                dry_run: false,
+                docstore_compression_opt: None,
             })
             .await?;
         let pipeline_handle = self
@@ -73,6 +73,10 @@ pub(crate) fn test_index_metadata_eq(
         expected_index_metadata.search_settings
     );
     assert_eq!(index_metadata.sources, expected_index_metadata.sources);
+    assert_eq!(
+        index_metadata.storage_config,
+        expected_index_metadata.storage_config
+    );
     assert_eq!(
         index_metadata.update_timestamp,
         expected_index_metadata.update_timestamp
@@ -142,15 +146,17 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
         store_source: true,
         mode: ModeType::Dynamic,
         dynamic_mapping: None,
+        type_conflict_policy: None,
         partition_key: "".to_string(),
     };
     let merge_policy = MergePolicy {
         demux_factor: 7,
         merge_factor: 9,
         max_merge_factor: 11,
+        balance_split_sizes: false,
     };
     let indexing_resources = IndexingResources {
-        __num_threads_deprecated: serde::de::IgnoredAny,
+        num_threads: None,
         heap_size: Byte::from_bytes(3),
     };
     let indexing_settings = IndexingSettings {
@@ -160,12 +166,19 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
         sort_field: Some("timestamp".to_string()),
         sort_order: Some(SortOrder::Asc),
         commit_timeout_secs: 301,
+        commit_on_idle_after_secs: None,
         split_num_docs_target: 10_000_001,
+        split_num_bytes_target: None,
+        max_num_docs_per_split: None,
         merge_enabled: true,
         merge_policy,
         resources: indexing_resources,
         docstore_blocksize: IndexingSettings::default_docstore_blocksize(),
         docstore_compression_level: IndexingSettings::default_docstore_compression_level(),
+        flush_on_shutdown: false,
+        max_parse_errors: None,
+        timestamp_precision: None,
+        default_fields: serde_json::Map::new(),
     };
     let search_settings = SearchSettings {
         default_search_fields: vec!["message".to_string()],
@@ -176,6 +189,8 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
             topic: "kafka-topic".to_string(),
             client_log_level: None,
             client_params: serde_json::json!({}),
+            max_message_bytes: None,
+            batch_num_bytes_target: None,
         }),
     };
     let mut sources = HashMap::default();
@@ -189,6 +204,7 @@ pub(crate) fn sample_index_metadata_for_regression() -> IndexMetadata {
         indexing_settings,
         search_settings,
         sources,
+        storage_config: None,
         create_timestamp: 1789,
         update_timestamp: 1789,
     }
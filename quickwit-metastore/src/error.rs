@@ -39,6 +39,9 @@ pub enum MetastoreError {
     #[error("Index `{index_id}` does not exist.")]
     IndexDoesNotExist { index_id: String },
 
+    #[error("Invalid argument: `{message}`.")]
+    InvalidArgument { message: String },
+
     /// Any generic internal error.
     /// The message can be helpful to users, but the detail of the error
     /// are judged uncoverable and not useful for error handling.
@@ -75,6 +78,16 @@ pub enum MetastoreError {
     #[error("Source `{source_id}` does not exist.")]
     SourceDoesNotExist { source_id: String },
 
+    #[error(
+        "Source `{source_id}` of type `{source_type}` cannot be changed to type \
+         `{new_source_type}`."
+    )]
+    SourceTypeMismatch {
+        source_id: String,
+        source_type: String,
+        new_source_type: String,
+    },
+
     #[cfg(feature = "postgres")]
     #[error("Database error: {0:?}.")]
     DbError(#[from] sqlx::Error),
@@ -42,12 +42,27 @@ pub use metastore::file_backed_metastore::FileBackedMetastore;
 pub use metastore::postgresql_metastore::PostgresqlMetastore;
 #[cfg(feature = "testsuite")]
 pub use metastore::MockMetastore;
-pub use metastore::{file_backed_metastore, IndexMetadata, Metastore};
+pub use metastore::{
+    file_backed_metastore, list_splits_stream, IndexMetadata, Metastore, MetastoreRetryParams,
+    RetryingMetastore, SplitsPage,
+};
 pub use metastore_resolver::{
     quickwit_metastore_uri_resolver, MetastoreFactory, MetastoreUriResolver,
 };
 pub use split_metadata::{Split, SplitMetadata, SplitState};
 pub(crate) use split_metadata_version::VersionedSplitMetadataDeserializeHelper;
 
+/// Returns the optional Cargo features of this crate enabled at build time.
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut enabled_features = Vec::new();
+    if cfg!(feature = "postgres") {
+        enabled_features.push("postgres");
+    }
+    if cfg!(feature = "azure") {
+        enabled_features.push("azure");
+    }
+    enabled_features
+}
+
 #[cfg(test)]
 mod backward_compatibility_tests;
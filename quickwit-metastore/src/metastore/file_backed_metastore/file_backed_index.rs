@@ -374,6 +374,11 @@ impl FileBackedIndex {
         Ok(true)
     }
 
+    pub(crate) fn update_source(&mut self, source: SourceConfig) -> MetastoreResult<bool> {
+        self.metadata.update_source(source)?;
+        Ok(true)
+    }
+
     pub(crate) fn delete_source(&mut self, source_id: &str) -> MetastoreResult<bool> {
         self.metadata.delete_source(source_id)?;
         Ok(true)
@@ -19,7 +19,6 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, Weak};
-use std::time::Duration;
 
 use async_trait::async_trait;
 use once_cell::sync::OnceCell;
@@ -27,8 +26,9 @@ use quickwit_common::uri::Uri;
 use quickwit_storage::{quickwit_storage_uri_resolver, StorageResolverError, StorageUriResolver};
 use regex::Regex;
 use tokio::sync::Mutex;
-use tracing::debug;
+use tracing::{debug, info};
 
+use crate::metastore_resolver::extract_polling_interval_from_uri;
 use crate::{
     FileBackedMetastore, Metastore, MetastoreError, MetastoreFactory, MetastoreResolverError,
 };
@@ -47,7 +47,10 @@ pub struct FileBackedMetastoreFactory {
     // here. This is judged to not be much of a problem however.
     //
     // In a normal run, this cache will contain a single Metastore.
-    cache: Arc<Mutex<HashMap<Uri, Weak<dyn Metastore>>>>,
+    //
+    // The metastores are keyed by URI and number of shards, so that opening a metastore with the
+    // wrong number of shards fails instead of returning the metastore opened beforehand.
+    cache: Arc<Mutex<HashMap<(Uri, usize), Weak<dyn Metastore>>>>,
 }
 
 impl Default for FileBackedMetastoreFactory {
@@ -59,28 +62,35 @@ impl Default for FileBackedMetastoreFactory {
     }
 }
 
-fn extract_polling_interval_from_uri(uri: &str) -> (String, Option<Duration>) {
+/// Extracts the `num_shards` option from the URI fragment, e.g. `file:///metastore#num_shards=8`.
+/// The option can be combined with the `polling_interval` option.
+fn extract_num_shards_from_uri(uri: &str) -> (String, Option<usize>) {
     static URI_FRAGMENT_PATTERN: OnceCell<Regex> = OnceCell::new();
     if let Some(captures) = URI_FRAGMENT_PATTERN
-        .get_or_init(|| Regex::new("(.*)#polling_interval=([1-9][0-9]{0,8})s").unwrap())
+        .get_or_init(|| Regex::new("^(.*)#num_shards=([1-9][0-9]{0,3})(#.*)?$").unwrap())
         .captures(uri)
     {
-        let uri_without_fragment = captures.get(1).unwrap().as_str().to_string();
-        let polling_interval_in_secs: u64 =
-            captures.get(2).unwrap().as_str().parse::<u64>().unwrap();
-        (
-            uri_without_fragment,
-            Some(Duration::from_secs(polling_interval_in_secs)),
-        )
+        let uri_without_option = format!(
+            "{}{}",
+            captures.get(1).unwrap().as_str(),
+            captures
+                .get(3)
+                .map(|other_fragments| other_fragments.as_str())
+                .unwrap_or("")
+        );
+        let num_shards: usize = captures.get(2).unwrap().as_str().parse::<usize>().unwrap();
+        (uri_without_option, Some(num_shards))
     } else {
         (uri.to_string(), None)
     }
 }
 
 impl FileBackedMetastoreFactory {
-    async fn get_from_cache(&self, uri: &Uri) -> Option<Arc<dyn Metastore>> {
+    async fn get_from_cache(&self, uri: &Uri, num_shards: usize) -> Option<Arc<dyn Metastore>> {
         let cache_lock = self.cache.lock().await;
-        cache_lock.get(uri).and_then(Weak::upgrade)
+        cache_lock
+            .get(&(uri.clone(), num_shards))
+            .and_then(Weak::upgrade)
     }
 
     /// If there is a valid entry in the cache to begin with, we ignore the new
@@ -88,14 +98,20 @@ impl FileBackedMetastoreFactory {
     ///
     /// This way we make sure that we keep only one instance associated
     /// to the key `uri` outside of this struct.
-    async fn cache_metastore(&self, uri: Uri, metastore: Arc<dyn Metastore>) -> Arc<dyn Metastore> {
+    async fn cache_metastore(
+        &self,
+        uri: Uri,
+        num_shards: usize,
+        metastore: Arc<dyn Metastore>,
+    ) -> Arc<dyn Metastore> {
         let mut cache_lock = self.cache.lock().await;
-        if let Some(metastore_weak) = cache_lock.get(&uri) {
+        let cache_key = (uri, num_shards);
+        if let Some(metastore_weak) = cache_lock.get(&cache_key) {
             if let Some(metastore_arc) = metastore_weak.upgrade() {
                 return metastore_arc.clone();
             }
         }
-        cache_lock.insert(uri, Arc::downgrade(&metastore));
+        cache_lock.insert(cache_key, Arc::downgrade(&metastore));
         metastore
     }
 }
@@ -103,9 +119,12 @@ impl FileBackedMetastoreFactory {
 #[async_trait]
 impl MetastoreFactory for FileBackedMetastoreFactory {
     async fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Metastore>, MetastoreResolverError> {
-        let (uri_stripped, polling_interval_opt) = extract_polling_interval_from_uri(uri.as_str());
+        let (uri_without_num_shards, num_shards_opt) = extract_num_shards_from_uri(uri.as_str());
+        let (uri_stripped, polling_interval_opt) =
+            extract_polling_interval_from_uri(&uri_without_num_shards)?;
         let uri = Uri::new(uri_stripped);
-        if let Some(metastore) = self.get_from_cache(&uri).await {
+        let num_shards = num_shards_opt.unwrap_or(1);
+        if let Some(metastore) = self.get_from_cache(&uri, num_shards).await {
             debug!("using metastore from cache");
             return Ok(metastore);
         }
@@ -127,11 +146,19 @@ impl MetastoreFactory for FileBackedMetastoreFactory {
                     })
                 }
             })?;
-        let file_backed_metastore = FileBackedMetastore::try_new(storage, polling_interval_opt)
-            .await
-            .map_err(MetastoreResolverError::FailedToOpenMetastore)?;
+        let file_backed_metastore =
+            FileBackedMetastore::try_new_with_num_shards(storage, polling_interval_opt, num_shards)
+                .await
+                .map_err(MetastoreResolverError::FailedToOpenMetastore)?;
+        if let Some(polling_interval) = polling_interval_opt {
+            info!(
+                metastore_uri = %uri,
+                polling_interval = ?polling_interval,
+                "Setting metastore polling interval."
+            );
+        }
         let unique_metastore_for_uri = self
-            .cache_metastore(uri, Arc::new(file_backed_metastore))
+            .cache_metastore(uri, num_shards, Arc::new(file_backed_metastore))
             .await;
         Ok(unique_metastore_for_uri)
     }
@@ -139,35 +166,29 @@ impl MetastoreFactory for FileBackedMetastoreFactory {
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
-
-    use crate::metastore::file_backed_metastore::file_backed_metastore_factory::extract_polling_interval_from_uri;
+    use crate::metastore::file_backed_metastore::file_backed_metastore_factory::extract_num_shards_from_uri;
 
     #[test]
-    fn test_extract_polling_interval_from_uri() {
+    fn test_extract_num_shards_from_uri() {
+        assert_eq!(
+            extract_num_shards_from_uri("file://some-uri#num_shards=8"),
+            ("file://some-uri".to_string(), Some(8))
+        );
         assert_eq!(
-            extract_polling_interval_from_uri("file://some-uri#polling_interval=23s"),
-            ("file://some-uri".to_string(), Some(Duration::from_secs(23)))
+            extract_num_shards_from_uri("file://some-uri#num_shards=8#polling_interval=10s"),
+            ("file://some-uri#polling_interval=10s".to_string(), Some(8))
         );
         assert_eq!(
-            extract_polling_interval_from_uri(
-                "file://some-uri#polling_interval=18446744073709551616s"
-            ),
-            (
-                "file://some-uri#polling_interval=18446744073709551616s".to_string(),
-                None
-            )
+            extract_num_shards_from_uri("file://some-uri#polling_interval=10s#num_shards=8"),
+            ("file://some-uri#polling_interval=10s".to_string(), Some(8))
         );
         assert_eq!(
-            extract_polling_interval_from_uri("file://some-uri#polling_interval=0s"),
-            ("file://some-uri#polling_interval=0s".to_string(), None)
+            extract_num_shards_from_uri("file://some-uri#num_shards=0"),
+            ("file://some-uri#num_shards=0".to_string(), None)
         );
         assert_eq!(
-            extract_polling_interval_from_uri("file://some-uri#otherfragment#polling_interval=10s"),
-            (
-                "file://some-uri#otherfragment".to_string(),
-                Some(Duration::from_secs(10))
-            )
+            extract_num_shards_from_uri("file://some-uri"),
+            ("file://some-uri".to_string(), None)
         );
     }
 }
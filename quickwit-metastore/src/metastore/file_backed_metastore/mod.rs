@@ -28,6 +28,7 @@ mod store_operations;
 
 use std::collections::HashMap;
 use std::ops::Range;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -43,8 +44,8 @@ use self::file_backed_index::FileBackedIndex;
 pub use self::file_backed_metastore_factory::FileBackedMetastoreFactory;
 use self::lazy_file_backed_index::LazyFileBackedIndex;
 use self::store_operations::{
-    delete_index, fetch_and_build_indexes_states, fetch_index, index_exists, put_index,
-    put_indexes_states,
+    check_num_shards, delete_index, fetch_and_build_indexes_states, fetch_index, index_exists,
+    indexes_states_path, put_index, put_indexes_states,
 };
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::{
@@ -88,6 +89,10 @@ pub(crate) enum IndexState {
 /// - on deletion, same story, the metastore deletes an index metadata file present on the storage
 ///   even if the index is not in the map.
 ///
+/// The indexes map can be sharded by index ID: each shard is then stored in its own
+/// `indexes_states/shard-{shard_ord}.json` file and guarded by its own lock, so that creating or
+/// deleting an index does not hold up the operations on the indexes of the other shards.
+///
 /// !!! Important note 2: it is strongly advised to restrict the `FileBackedMetastore`
 /// usage to the following use cases:
 /// - testing;
@@ -96,17 +101,39 @@ pub(crate) enum IndexState {
 ///   cautious and ensure that your readers are really readers.
 pub struct FileBackedMetastore {
     storage: Arc<dyn Storage>,
-    per_index_metastores: Arc<RwLock<HashMap<String, IndexState>>>,
+    shards: Vec<IndexesStatesShard>,
     polling_interval_opt: Option<Duration>,
 }
 
+/// Subset of the indexes map stored in its own indexes states file.
+struct IndexesStatesShard {
+    indexes_states_path: PathBuf,
+    per_index_metastores: RwLock<HashMap<String, IndexState>>,
+}
+
+/// Returns the ordinal of the shard in charge of the given index ID.
+///
+/// The assignment of the indexes to the shards is persisted, so the hash function (FNV-1a) must
+/// remain stable across releases.
+fn shard_ord(index_id: &str, num_shards: usize) -> usize {
+    let hash = index_id
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    (hash % num_shards as u64) as usize
+}
+
 impl FileBackedMetastore {
     /// Creates a [`FileBackedMetastore`] for tests.
     #[doc(hidden)]
     pub fn for_test(storage: Arc<dyn Storage>) -> Self {
         Self {
             storage,
-            per_index_metastores: Default::default(),
+            shards: vec![IndexesStatesShard {
+                indexes_states_path: indexes_states_path(0, 1),
+                per_index_metastores: Default::default(),
+            }],
             polling_interval_opt: None,
         }
     }
@@ -129,16 +156,52 @@ impl FileBackedMetastore {
         storage: Arc<dyn Storage>,
         polling_interval_opt: Option<Duration>,
     ) -> MetastoreResult<Self> {
-        let indexes_map =
-            fetch_and_build_indexes_states(storage.clone(), polling_interval_opt).await?;
-        let per_index_metastores = Arc::new(RwLock::new(indexes_map));
+        Self::try_new_with_num_shards(storage, polling_interval_opt, 1).await
+    }
+
+    /// Creates a [`FileBackedMetastore`] for a specified storage, with its indexes map split into
+    /// `num_shards` shards. Indexes states are immediately fetched from the storage.
+    ///
+    /// The number of shards of a given metastore cannot change over time: opening a metastore with
+    /// a different number of shards than the one it was created with fails.
+    pub async fn try_new_with_num_shards(
+        storage: Arc<dyn Storage>,
+        polling_interval_opt: Option<Duration>,
+        num_shards: usize,
+    ) -> MetastoreResult<Self> {
+        assert!(
+            num_shards > 0,
+            "The number of shards must be strictly positive."
+        );
+        check_num_shards(&*storage, num_shards).await?;
+        let shards = try_join_all((0..num_shards).map(|shard_ord| {
+            let storage = storage.clone();
+            async move {
+                let indexes_states_path = indexes_states_path(shard_ord, num_shards);
+                let indexes_map = fetch_and_build_indexes_states(
+                    storage,
+                    &indexes_states_path,
+                    polling_interval_opt,
+                )
+                .await?;
+                Ok::<_, MetastoreError>(IndexesStatesShard {
+                    indexes_states_path,
+                    per_index_metastores: RwLock::new(indexes_map),
+                })
+            }
+        }))
+        .await?;
         Ok(Self {
             storage,
-            per_index_metastores,
+            shards,
             polling_interval_opt,
         })
     }
 
+    fn shard(&self, index_id: &str) -> &IndexesStatesShard {
+        &self.shards[shard_ord(index_id, self.shards.len())]
+    }
+
     async fn mutate(
         &self,
         index_id: &str,
@@ -162,7 +225,8 @@ impl FileBackedMetastore {
                 // whether the content was written or not.
                 //
                 // Just to be sure, let's discard the cache.
-                let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
+                let mut per_index_metastores_wlock =
+                    self.shard(index_id).per_index_metastores.write().await;
 
                 // At this point, we hold both locks.
                 per_index_metastores_wlock.insert(
@@ -216,7 +280,7 @@ impl FileBackedMetastore {
         {
             // Happy path!
             // If the object is already in our cache then we just return a copy
-            let per_index_metastores_r = self.per_index_metastores.read().await;
+            let per_index_metastores_r = self.shard(index_id).per_index_metastores.read().await;
             if let Some(index_state) = per_index_metastores_r.get(index_id) {
                 return get_index_mutex(index_id, index_state).await;
             }
@@ -231,7 +295,8 @@ impl FileBackedMetastore {
         let index_result = fetch_index(&*self.storage, index_id).await;
 
         // Here we retake the lock, still no io ongoing.
-        let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
+        let mut per_index_metastores_wlock =
+            self.shard(index_id).per_index_metastores.write().await;
 
         // At this point, some other client might have added another instance of the Metadataet in
         // the map. We want to avoid two copies to exist in the application, so we keep only
@@ -272,9 +337,10 @@ impl Metastore for FileBackedMetastore {
     /// Mutations over the high-level index.
     async fn create_index(&self, index_metadata: IndexMetadata) -> MetastoreResult<()> {
         let index_id = index_metadata.index_id.clone();
+        let shard = self.shard(&index_id);
 
         // We pick the outer lock here, so that we enter a critical section.
-        let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
+        let mut per_index_metastores_wlock = shard.per_index_metastores.write().await;
 
         // Checking if index already exists is a bit tedious:
         // - first we check the index state: if it's `Alive`, return `IndexAlreadyExists` error, and
@@ -300,7 +366,13 @@ impl Metastore for FileBackedMetastore {
 
         // Set state to Creating` and rollback on metastore error.
         per_index_metastores_wlock.insert(index_id.clone(), IndexState::Creating);
-        if let Err(error) = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await {
+        if let Err(error) = put_indexes_states(
+            &*self.storage,
+            &shard.indexes_states_path,
+            &per_index_metastores_wlock,
+        )
+        .await
+        {
             per_index_metastores_wlock.remove(&index_id);
             return Err(error);
         }
@@ -320,7 +392,12 @@ impl Metastore for FileBackedMetastore {
         );
 
         // Set state to `Alive` and rollback on metastore error.
-        let put_res = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await;
+        let put_res = put_indexes_states(
+            &*self.storage,
+            &shard.indexes_states_path,
+            &per_index_metastores_wlock,
+        )
+        .await;
         if put_res.is_err() {
             per_index_metastores_wlock.insert(index_id.clone(), IndexState::Creating);
         }
@@ -328,8 +405,10 @@ impl Metastore for FileBackedMetastore {
     }
 
     async fn delete_index(&self, index_id: &str) -> MetastoreResult<()> {
+        let shard = self.shard(index_id);
+
         // We pick the outer lock here, so that we enter a critical section.
-        let mut per_index_metastores_wlock = self.per_index_metastores.write().await;
+        let mut per_index_metastores_wlock = shard.per_index_metastores.write().await;
 
         // If index is neither in `per_index_metastores_wlock` nor on the storage, it does not
         // exist.
@@ -346,7 +425,13 @@ impl Metastore for FileBackedMetastore {
         let index_state_opt =
             per_index_metastores_wlock.insert(index_id.to_string(), IndexState::Deleting);
         // On a put error, reinsert the previous state if any.
-        if let Err(error) = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await {
+        if let Err(error) = put_indexes_states(
+            &*self.storage,
+            &shard.indexes_states_path,
+            &per_index_metastores_wlock,
+        )
+        .await
+        {
             if let Some(index_state) = index_state_opt {
                 per_index_metastores_wlock.insert(index_id.to_string(), index_state);
             } else {
@@ -363,7 +448,7 @@ impl Metastore for FileBackedMetastore {
             // but it makes sense to ensure that the index state is removed.
             Err(MetastoreError::IndexDoesNotExist { .. }) => {
                 per_index_metastores_wlock.remove(index_id);
-                if let Err(error) = put_indexes_states(&*self.storage, &per_index_metastores_wlock).await {
+                if let Err(error) = put_indexes_states(&*self.storage, &shard.indexes_states_path, &per_index_metastores_wlock).await {
                     per_index_metastores_wlock.insert(index_id.to_string(), IndexState::Deleting);
                     return Err(error);
                 }
@@ -438,6 +523,11 @@ impl Metastore for FileBackedMetastore {
             .await
     }
 
+    async fn update_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()> {
+        self.mutate(index_id, |index| index.update_source(source))
+            .await
+    }
+
     async fn delete_source(&self, index_id: &str, source_id: &str) -> MetastoreResult<()> {
         self.mutate(index_id, |index| index.delete_source(source_id))
             .await
@@ -469,10 +559,14 @@ impl Metastore for FileBackedMetastore {
     }
 
     async fn list_indexes_metadatas(&self) -> MetastoreResult<Vec<IndexMetadata>> {
-        let per_index_metastores_rlock = self.per_index_metastores.read().await;
+        let mut per_index_metastores_rlocks = Vec::with_capacity(self.shards.len());
+        for shard in &self.shards {
+            per_index_metastores_rlocks.push(shard.per_index_metastores.read().await);
+        }
         try_join_all(
-            per_index_metastores_rlock
+            per_index_metastores_rlocks
                 .iter()
+                .flat_map(|per_index_metastores_rlock| per_index_metastores_rlock.iter())
                 .filter_map(|(index_id, index_state)| match index_state {
                     IndexState::Alive(_) => Some(index_id),
                     _ => None,
@@ -531,21 +625,30 @@ metastore_test_suite!(crate::FileBackedMetastore);
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
-    use std::ops::RangeInclusive;
-    use std::path::Path;
+    use std::ops::{Range, RangeInclusive};
+    use std::path::{Path, PathBuf};
     use std::sync::Arc;
 
+    use async_trait::async_trait;
     use futures::executor::block_on;
-    use quickwit_storage::{MockStorage, RamStorage, Storage, StorageErrorKind};
+    use quickwit_common::uri::Uri;
+    use quickwit_config::{SourceConfig, SourceParams};
+    use quickwit_doc_mapper::tag_pruning::tag;
+    use quickwit_storage::{
+        MockStorage, OwnedBytes, PutPayload, RamStorage, Storage, StorageErrorKind, StorageResult,
+    };
     use rand::Rng;
     use time::OffsetDateTime;
+    use tokio::sync::{Notify, Semaphore};
     use tokio::time::Duration;
 
     use super::lazy_file_backed_index::LazyFileBackedIndex;
     use super::store_operations::{
-        fetch_and_build_indexes_states, meta_path, put_index_given_index_id, put_indexes_states,
+        fetch_and_build_indexes_states, indexes_states_path, meta_path, put_index_given_index_id,
+        put_indexes_states,
     };
-    use super::{FileBackedIndex, FileBackedMetastore, IndexState};
+    use super::{shard_ord, FileBackedIndex, FileBackedMetastore, IndexState};
+    use crate::checkpoint::IndexCheckpointDelta;
     use crate::tests::test_suite::DefaultForTest;
     use crate::{IndexMetadata, Metastore, MetastoreError, SplitMetadata, SplitState};
 
@@ -690,7 +793,7 @@ mod tests {
                 None,
             )),
         );
-        put_indexes_states(&*storage, &indexes_states)
+        put_indexes_states(&*storage, &indexes_states_path(0, 1), &indexes_states)
             .await
             .unwrap();
 
@@ -708,6 +811,102 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_file_backed_metastore_calls_round_trip_through_storage(
+    ) -> crate::MetastoreResult<()> {
+        // Each call is checked against a metastore reloading its state from the storage, so that
+        // a field dropped when (de)serializing the metastore files fails the test.
+        let storage = Arc::new(RamStorage::default());
+        let metastore = FileBackedMetastore::try_new(storage.clone(), None).await?;
+        let reload = || FileBackedMetastore::try_new(storage.clone(), None);
+
+        let index_id = "test-index";
+        let index_metadata = IndexMetadata::for_test(index_id, "ram:///indexes/test-index");
+        metastore.create_index(index_metadata).await?;
+        let source_config = SourceConfig {
+            source_id: "test-source".to_string(),
+            source_params: SourceParams::file("data/docs.json"),
+        };
+        metastore.add_source(index_id, source_config).await?;
+
+        let split_metadata = SplitMetadata {
+            split_id: "test-split".to_string(),
+            num_docs: 42,
+            uncompressed_docs_size_in_bytes: 1_024,
+            time_range: Some(0..=99),
+            create_timestamp: 1_656_000_000,
+            tags: ["tenant:a".to_string()].into_iter().collect(),
+            demux_num_ops: 1,
+            footer_offsets: 1_000..2_000,
+        };
+        metastore.stage_split(index_id, split_metadata).await?;
+        metastore
+            .publish_splits(
+                index_id,
+                &["test-split"],
+                &[],
+                Some(IndexCheckpointDelta::for_test("test-source", 0..10)),
+            )
+            .await?;
+
+        let reloaded_metastore = reload().await?;
+        let expected_index_metadata = metastore.index_metadata(index_id).await?;
+        let reloaded_indexes_metadatas = reloaded_metastore.list_indexes_metadatas().await?;
+        assert_eq!(reloaded_indexes_metadatas.len(), 1);
+        let reloaded_index_metadata = &reloaded_indexes_metadatas[0];
+        assert_eq!(
+            reloaded_index_metadata.index_uri,
+            expected_index_metadata.index_uri
+        );
+        assert_eq!(
+            reloaded_index_metadata.sources,
+            expected_index_metadata.sources
+        );
+        assert_eq!(
+            reloaded_index_metadata.checkpoint,
+            expected_index_metadata.checkpoint
+        );
+        assert_eq!(
+            serde_json::to_value(reloaded_index_metadata).unwrap(),
+            serde_json::to_value(&expected_index_metadata).unwrap()
+        );
+        assert_eq!(
+            reloaded_metastore.list_all_splits(index_id).await?,
+            metastore.list_all_splits(index_id).await?
+        );
+        let reloaded_splits = reloaded_metastore
+            .list_splits(
+                index_id,
+                SplitState::Published,
+                Some(50..150),
+                Some(tag("tenant:a")),
+            )
+            .await?;
+        assert_eq!(reloaded_splits.len(), 1);
+
+        metastore
+            .mark_splits_for_deletion(index_id, &["test-split"])
+            .await?;
+        let reloaded_splits = reload().await?.list_all_splits(index_id).await?;
+        assert_eq!(reloaded_splits.len(), 1);
+        assert_eq!(
+            reloaded_splits[0].split_state,
+            SplitState::MarkedForDeletion
+        );
+
+        metastore.delete_splits(index_id, &["test-split"]).await?;
+        assert!(reload().await?.list_all_splits(index_id).await?.is_empty());
+
+        metastore.delete_source(index_id, "test-source").await?;
+        let reloaded_index_metadata = reload().await?.index_metadata(index_id).await?;
+        assert!(reloaded_index_metadata.sources.is_empty());
+
+        metastore.delete_index(index_id).await?;
+        let error = reload().await?.index_metadata(index_id).await.unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexDoesNotExist { .. }));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_file_backed_metastore_wrt_directly_visible() -> crate::MetastoreResult<()> {
         let metastore = FileBackedMetastore::default_for_test().await;
@@ -914,10 +1113,13 @@ mod tests {
             MetastoreError::InternalError { .. }
         ));
         // Check index state is in `Creating` in the states file.
-        let index_states =
-            fetch_and_build_indexes_states(Arc::new(ram_storage_clone_2.clone()), None)
-                .await
-                .unwrap();
+        let index_states = fetch_and_build_indexes_states(
+            Arc::new(ram_storage_clone_2.clone()),
+            &indexes_states_path(0, 1),
+            None,
+        )
+        .await
+        .unwrap();
         assert!(matches!(
             *index_states.get(index_id).unwrap(),
             IndexState::Creating
@@ -928,9 +1130,13 @@ mod tests {
             deleted_index_error,
             MetastoreError::IndexDoesNotExist { .. }
         ));
-        let index_states = fetch_and_build_indexes_states(Arc::new(ram_storage_clone_2), None)
-            .await
-            .unwrap();
+        let index_states = fetch_and_build_indexes_states(
+            Arc::new(ram_storage_clone_2),
+            &indexes_states_path(0, 1),
+            None,
+        )
+        .await
+        .unwrap();
         assert!(index_states.get(index_id).is_none());
         // Now we can expect an `IndexDoesNotExist` error.
         let created_index_error = metastore.get_index(index_id).await.unwrap_err();
@@ -1107,7 +1313,7 @@ mod tests {
             )),
         );
         indexes_states.insert(index_id_deleting.to_string(), IndexState::Deleting);
-        put_indexes_states(&*ram_storage, &indexes_states)
+        put_indexes_states(&*ram_storage, &indexes_states_path(0, 1), &indexes_states)
             .await
             .unwrap();
 
@@ -1146,4 +1352,198 @@ mod tests {
 
         Ok(())
     }
+
+    /// Storage whose writes to a given path hang until they are unblocked.
+    struct BlockingPutStorage {
+        underlying: RamStorage,
+        blocked_path: PathBuf,
+        put_started: Notify,
+        unblock_semaphore: Semaphore,
+    }
+
+    impl BlockingPutStorage {
+        fn new(underlying: RamStorage, blocked_path: PathBuf) -> Self {
+            Self {
+                underlying,
+                blocked_path,
+                put_started: Notify::new(),
+                unblock_semaphore: Semaphore::new(0),
+            }
+        }
+
+        fn unblock(&self) {
+            self.unblock_semaphore.add_permits(1);
+        }
+    }
+
+    #[async_trait]
+    impl Storage for BlockingPutStorage {
+        async fn check(&self) -> anyhow::Result<()> {
+            self.underlying.check().await
+        }
+
+        async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+            if path == self.blocked_path {
+                self.put_started.notify_one();
+                let _permit = self.unblock_semaphore.acquire().await.unwrap();
+            }
+            self.underlying.put(path, payload).await
+        }
+
+        async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+            self.underlying.copy_to_file(path, output_path).await
+        }
+
+        async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+            self.underlying.get_slice(path, range).await
+        }
+
+        async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+            self.underlying.get_all(path).await
+        }
+
+        async fn delete(&self, path: &Path) -> StorageResult<()> {
+            self.underlying.delete(path).await
+        }
+
+        async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+            self.underlying.file_num_bytes(path).await
+        }
+
+        fn uri(&self) -> &Uri {
+            self.underlying.uri()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_sharded_indexes_do_not_block_each_other(
+    ) -> crate::MetastoreResult<()> {
+        let blocked_index_id = "index-a";
+        let index_id = "index-b";
+        assert_ne!(shard_ord(blocked_index_id, 8), shard_ord(index_id, 8));
+
+        let ram_storage = RamStorage::default();
+        let storage = Arc::new(BlockingPutStorage::new(
+            ram_storage.clone(),
+            meta_path(blocked_index_id),
+        ));
+        let metastore =
+            Arc::new(FileBackedMetastore::try_new_with_num_shards(storage.clone(), None, 8).await?);
+        let index_metadata = IndexMetadata::for_test(index_id, "ram:///indexes/index-b");
+        metastore.create_index(index_metadata).await?;
+
+        // Creating `index-a` hangs while writing its metadata file...
+        let metastore_clone = metastore.clone();
+        let create_index_handle = tokio::spawn(async move {
+            let index_metadata =
+                IndexMetadata::for_test(blocked_index_id, "ram:///indexes/index-a");
+            metastore_clone.create_index(index_metadata).await
+        });
+        storage.put_started.notified().await;
+
+        // ... but does not prevent `index-b` from being updated.
+        let split_metadata = SplitMetadata {
+            split_id: "split-1".to_string(),
+            ..Default::default()
+        };
+        tokio::time::timeout(
+            Duration::from_secs(1),
+            metastore.stage_split(index_id, split_metadata),
+        )
+        .await
+        .expect("Staging a split on `index-b` should not wait for `index-a` to be created.")?;
+        let index_file_content = ram_storage.get_all(&meta_path(index_id)).await.unwrap();
+        assert!(String::from_utf8_lossy(&index_file_content[..]).contains("split-1"));
+        assert!(!ram_storage
+            .exists(&meta_path(blocked_index_id))
+            .await
+            .unwrap());
+
+        storage.unblock();
+        create_index_handle.await.unwrap()?;
+        assert!(ram_storage
+            .exists(&meta_path(blocked_index_id))
+            .await
+            .unwrap());
+        assert_eq!(metastore.list_indexes_metadatas().await?.len(), 2);
+
+        // The indexes states are persisted: a new metastore with the same number of shards finds
+        // both indexes.
+        let metastore = FileBackedMetastore::try_new_with_num_shards(storage, None, 8).await?;
+        assert_eq!(metastore.list_indexes_metadatas().await?.len(), 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_rejects_num_shards_changes() -> crate::MetastoreResult<()> {
+        let storage = Arc::new(RamStorage::default());
+        let metastore =
+            FileBackedMetastore::try_new_with_num_shards(storage.clone(), None, 8).await?;
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        metastore.create_index(index_metadata).await?;
+
+        let metastore =
+            FileBackedMetastore::try_new_with_num_shards(storage.clone(), None, 8).await?;
+        assert!(metastore.index_exists("test-index").await?);
+
+        for num_shards in [1, 4] {
+            let error =
+                FileBackedMetastore::try_new_with_num_shards(storage.clone(), None, num_shards)
+                    .await
+                    .err()
+                    .unwrap();
+            assert!(matches!(error, MetastoreError::InvalidArgument { .. }));
+        }
+
+        // An unsharded metastore cannot be sharded either.
+        let storage = Arc::new(RamStorage::default());
+        let metastore = FileBackedMetastore::try_new(storage.clone(), None).await?;
+        let index_metadata = IndexMetadata::for_test("test-index", "ram:///indexes/test-index");
+        metastore.create_index(index_metadata).await?;
+        let error = FileBackedMetastore::try_new_with_num_shards(storage.clone(), None, 8)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(error, MetastoreError::InvalidArgument { .. }));
+        let metastore = FileBackedMetastore::try_new(storage, None).await?;
+        assert!(metastore.index_exists("test-index").await?);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_file_backed_metastore_unsharded_indexes_block_each_other(
+    ) -> crate::MetastoreResult<()> {
+        let blocked_index_id = "index-a";
+        let index_id = "index-b";
+        let storage = Arc::new(BlockingPutStorage::new(
+            RamStorage::default(),
+            meta_path(blocked_index_id),
+        ));
+        let metastore = Arc::new(FileBackedMetastore::try_new(storage.clone(), None).await?);
+        let index_metadata = IndexMetadata::for_test(index_id, "ram:///indexes/index-b");
+        metastore.create_index(index_metadata).await?;
+
+        let metastore_clone = metastore.clone();
+        let create_index_handle = tokio::spawn(async move {
+            let index_metadata =
+                IndexMetadata::for_test(blocked_index_id, "ram:///indexes/index-a");
+            metastore_clone.create_index(index_metadata).await
+        });
+        storage.put_started.notified().await;
+
+        let split_metadata = SplitMetadata {
+            split_id: "split-1".to_string(),
+            ..Default::default()
+        };
+        assert!(tokio::time::timeout(
+            Duration::from_millis(200),
+            metastore.stage_split(index_id, split_metadata),
+        )
+        .await
+        .is_err());
+
+        storage.unblock();
+        create_index_handle.await.unwrap()?;
+        Ok(())
+    }
 }
@@ -32,9 +32,22 @@ use crate::{MetastoreError, MetastoreResult};
 /// Indexes states file managed by [`FileBackedMetastore`](crate::FileBackedMetastore).
 const INDEXES_STATES_FILENAME: &str = "indexes_states.json";
 
+/// Directory holding the indexes states files of a sharded
+/// [`FileBackedMetastore`](crate::FileBackedMetastore).
+const INDEXES_STATES_DIRNAME: &str = "indexes_states";
+
+/// Shards manifest of a sharded [`FileBackedMetastore`](crate::FileBackedMetastore).
+const SHARDS_MANIFEST_FILENAME: &str = "indexes_states_shards.json";
+
 /// Index metadata file managed by [`FileBackedMetastore`](crate::FileBackedMetastore).
 const META_FILENAME: &str = "metastore.json";
 
+/// Number of shards of a sharded metastore, persisted when the metastore is first opened.
+#[derive(Serialize, Deserialize)]
+struct ShardsManifest {
+    num_shards: usize,
+}
+
 /// Index state used for serialization/deserialization only.
 #[derive(Serialize, Deserialize)]
 enum IndexStateValue {
@@ -58,6 +71,76 @@ pub(crate) fn meta_path(index_id: &str) -> PathBuf {
     Path::new(index_id).join(META_FILENAME)
 }
 
+/// Path to the indexes states file of the given shard.
+///
+/// An unsharded metastore keeps its indexes states in a single `indexes_states.json` file.
+pub(crate) fn indexes_states_path(shard_ord: usize, num_shards: usize) -> PathBuf {
+    if num_shards == 1 {
+        return PathBuf::from(INDEXES_STATES_FILENAME);
+    }
+    Path::new(INDEXES_STATES_DIRNAME).join(format!("shard-{shard_ord}.json"))
+}
+
+/// Checks that the metastore is opened with the number of shards it was created with. The first
+/// opening of a sharded metastore persists its number of shards in the shards manifest.
+///
+/// A metastore without shards manifest is unsharded, and sharding a metastore that already holds
+/// an unsharded indexes states file is rejected: the indexes it lists would no longer be found.
+pub(crate) async fn check_num_shards(
+    storage: &dyn Storage,
+    num_shards: usize,
+) -> MetastoreResult<()> {
+    let manifest_path = Path::new(SHARDS_MANIFEST_FILENAME);
+    let manifest_exists = storage
+        .exists(manifest_path)
+        .await
+        .map_err(|storage_err| convert_error("indexes", storage_err))?;
+    let persisted_num_shards = if manifest_exists {
+        let content = storage
+            .get_all(manifest_path)
+            .await
+            .map_err(|storage_err| MetastoreError::InternalError {
+                message: format!("Failed to get {} file.", manifest_path.display()),
+                cause: anyhow::anyhow!(storage_err),
+            })?;
+        let manifest: ShardsManifest = serde_json::from_slice(&content[..])
+            .map_err(|serde_err| MetastoreError::InvalidManifest { cause: serde_err })?;
+        manifest.num_shards
+    } else if num_shards == 1
+        || storage
+            .exists(Path::new(INDEXES_STATES_FILENAME))
+            .await
+            .map_err(|storage_err| convert_error("indexes", storage_err))?
+    {
+        1
+    } else {
+        let content =
+            serde_json::to_vec_pretty(&ShardsManifest { num_shards }).map_err(|serde_err| {
+                MetastoreError::InternalError {
+                    message: "Failed to serialize shards manifest".to_string(),
+                    cause: anyhow::anyhow!(serde_err),
+                }
+            })?;
+        storage
+            .put(manifest_path, Box::new(content))
+            .await
+            .map_err(|storage_err| MetastoreError::InternalError {
+                message: format!("Failed to put {} file.", manifest_path.display()),
+                cause: anyhow::anyhow!(storage_err),
+            })?;
+        num_shards
+    };
+    if persisted_num_shards != num_shards {
+        return Err(MetastoreError::InvalidArgument {
+            message: format!(
+                "The metastore has {persisted_num_shards} shard(s) but was opened with \
+                 {num_shards} shard(s). The number of shards of a metastore cannot change."
+            ),
+        });
+    }
+    Ok(())
+}
+
 fn convert_error(index_id: &str, storage_err: StorageError) -> MetastoreError {
     match storage_err.kind() {
         StorageErrorKind::DoesNotExist => MetastoreError::IndexDoesNotExist {
@@ -73,13 +156,13 @@ fn convert_error(index_id: &str, storage_err: StorageError) -> MetastoreError {
     }
 }
 
-/// Fetch `INDEXES_STATES_FILENAME` file and build the map (index, state).
+/// Fetch the indexes states file located at `indexes_list_path` and build the map (index, state).
 /// If the file does not exist, return an empty map.
 pub(crate) async fn fetch_and_build_indexes_states(
     storage: Arc<dyn Storage>,
+    indexes_list_path: &Path,
     polling_interval_opt: Option<Duration>,
 ) -> MetastoreResult<HashMap<String, IndexState>> {
-    let indexes_list_path = Path::new(INDEXES_STATES_FILENAME);
     let exists = storage
         .exists(indexes_list_path)
         .await
@@ -91,7 +174,7 @@ pub(crate) async fn fetch_and_build_indexes_states(
         .get_all(indexes_list_path)
         .await
         .map_err(|storage_err| MetastoreError::InternalError {
-            message: format!("Failed to get {INDEXES_STATES_FILENAME} file."),
+            message: format!("Failed to get {} file.", indexes_list_path.display()),
             cause: anyhow::anyhow!(storage_err),
         })?;
     let indexes_states_deserialized: HashMap<String, IndexStateValue> =
@@ -117,13 +200,13 @@ pub(crate) async fn fetch_and_build_indexes_states(
 
 pub(crate) async fn put_indexes_states(
     storage: &dyn Storage,
+    indexes_list_path: &Path,
     indexes_states: &HashMap<String, IndexState>,
 ) -> MetastoreResult<()> {
     let indexes_states_serializable: HashMap<String, IndexStateValue> = indexes_states
         .iter()
         .map(|(index_id, index_state)| (index_id.clone(), IndexStateValue::from(index_state)))
         .collect();
-    let indexes_list_path = Path::new(INDEXES_STATES_FILENAME);
     let content: Vec<u8> =
         serde_json::to_vec_pretty(&indexes_states_serializable).map_err(|serde_err| {
             MetastoreError::InternalError {
@@ -135,7 +218,7 @@ pub(crate) async fn put_indexes_states(
         .put(indexes_list_path, Box::new(content))
         .await
         .map_err(|storage_err| MetastoreError::InternalError {
-            message: "Failed to put {INDEXES_STATES_FILENAME} file.".to_string(),
+            message: format!("Failed to put {} file.", indexes_list_path.display()),
             cause: anyhow::anyhow!(storage_err),
         })?;
     Ok(())
@@ -23,7 +23,8 @@ use std::collections::HashMap;
 use itertools::Itertools;
 use quickwit_common::uri::Uri;
 use quickwit_config::{
-    DocMapping, IndexingResources, IndexingSettings, SearchSettings, SourceConfig,
+    DocMapping, IndexStorageConfig, IndexingResources, IndexingSettings, SearchSettings,
+    SourceConfig,
 };
 use quickwit_doc_mapper::SortOrder;
 use serde::{Deserialize, Serialize};
@@ -53,6 +54,8 @@ pub struct IndexMetadata {
     pub search_settings: SearchSettings,
     /// Data sources keyed by their `source_id`.
     pub sources: HashMap<String, SourceConfig>,
+    /// Overrides the storage settings sniffed from the environment for this index.
+    pub storage_config: Option<IndexStorageConfig>,
     /// Time at which the index was created.
     pub create_timestamp: i64,
     /// Time at which the index was last updated.
@@ -146,6 +149,7 @@ impl IndexMetadata {
             indexing_settings,
             search_settings,
             sources: Default::default(),
+            storage_config: None,
             create_timestamp: now_timestamp,
             update_timestamp: now_timestamp,
         }
@@ -165,6 +169,25 @@ impl IndexMetadata {
         Ok(())
     }
 
+    /// Replaces the params of an existing source. The checkpoint of the source is kept, which is
+    /// why its type cannot change.
+    pub(crate) fn update_source(&mut self, source: SourceConfig) -> MetastoreResult<()> {
+        let current_source = self.sources.get_mut(&source.source_id).ok_or_else(|| {
+            MetastoreError::SourceDoesNotExist {
+                source_id: source.source_id.clone(),
+            }
+        })?;
+        if current_source.source_type() != source.source_type() {
+            return Err(MetastoreError::SourceTypeMismatch {
+                source_id: source.source_id.clone(),
+                source_type: current_source.source_type().to_string(),
+                new_source_type: source.source_type().to_string(),
+            });
+        }
+        *current_source = source;
+        Ok(())
+    }
+
     pub(crate) fn delete_source(&mut self, source_id: &str) -> MetastoreResult<()> {
         self.sources
             .remove(source_id)
@@ -209,6 +232,9 @@ pub(crate) struct IndexMetadataV1 {
     #[serde(default)]
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub sources: Vec<SourceConfig>,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_config: Option<IndexStorageConfig>,
     #[serde(default = "utc_now_timestamp")]
     pub create_timestamp: i64,
     #[serde(default = "utc_now_timestamp")]
@@ -230,6 +256,7 @@ impl From<IndexMetadata> for IndexMetadataV1 {
             indexing_settings: index_metadata.indexing_settings,
             search_settings: index_metadata.search_settings,
             sources,
+            storage_config: index_metadata.storage_config,
             create_timestamp: index_metadata.create_timestamp,
             update_timestamp: index_metadata.update_timestamp,
         }
@@ -251,6 +278,7 @@ impl From<IndexMetadataV1> for IndexMetadata {
             indexing_settings: v1.indexing_settings,
             search_settings: v1.search_settings,
             sources,
+            storage_config: v1.storage_config,
             create_timestamp: v1.create_timestamp,
             update_timestamp: v1.update_timestamp,
         }
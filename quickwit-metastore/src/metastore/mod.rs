@@ -23,17 +23,87 @@ mod index_metadata;
 pub mod postgresql_metastore;
 #[cfg(feature = "postgres")]
 mod postgresql_model;
+mod retrying_metastore;
 
 use std::ops::Range;
 
 use async_trait::async_trait;
+use futures::stream::{self, BoxStream};
+use futures::{future, StreamExt, TryStreamExt};
 pub use index_metadata::IndexMetadata;
 use quickwit_common::uri::Uri;
 use quickwit_config::SourceConfig;
 use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+pub use retrying_metastore::{MetastoreRetryParams, RetryingMetastore};
 
 use crate::checkpoint::IndexCheckpointDelta;
-use crate::{MetastoreResult, Split, SplitMetadata, SplitState};
+use crate::{MetastoreError, MetastoreResult, Split, SplitMetadata, SplitState};
+
+/// A page of splits returned by [`Metastore::list_splits_page`].
+#[derive(Clone, Debug)]
+pub struct SplitsPage {
+    /// Splits of the page, ordered by split ID.
+    pub splits: Vec<Split>,
+    /// Offset to pass to fetch the next page, `None` if this page is the last one.
+    pub next_page_offset: Option<usize>,
+}
+
+impl SplitsPage {
+    /// Rejects empty pages: a page limit of 0 would never make progress through the splits.
+    pub(crate) fn check_limit(limit: usize) -> MetastoreResult<()> {
+        if limit == 0 {
+            return Err(MetastoreError::InvalidArgument {
+                message: "The page limit must be strictly positive.".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Builds a page from splits fetched with a limit of `limit + 1`: the extra split, if any,
+    /// only tells that there is a next page.
+    pub(crate) fn from_splits(mut splits: Vec<Split>, offset: usize, limit: usize) -> Self {
+        let next_page_offset = if splits.len() > limit {
+            splits.truncate(limit);
+            Some(offset + limit)
+        } else {
+            None
+        };
+        SplitsPage {
+            splits,
+            next_page_offset,
+        }
+    }
+}
+
+/// Streams the splits in the given `split_state`, fetching them from the metastore page by page
+/// with [`Metastore::list_splits_page`].
+///
+/// A page is only fetched once the splits of the previous one have been consumed, so callers
+/// can process the splits of large indexes incrementally, and dropping the stream stops paging.
+/// A `page_size` of 0 yields a single [`MetastoreError::InvalidArgument`] error.
+pub fn list_splits_stream<'a>(
+    metastore: &'a dyn Metastore,
+    index_id: &'a str,
+    split_state: SplitState,
+    page_size: usize,
+) -> BoxStream<'a, MetastoreResult<Split>> {
+    if let Err(error) = SplitsPage::check_limit(page_size) {
+        return stream::once(future::ready(Err(error))).boxed();
+    }
+    stream::unfold(Some(0), move |offset_opt| async move {
+        let offset = offset_opt?;
+        match metastore
+            .list_splits_page(index_id, split_state, offset, page_size)
+            .await
+        {
+            Ok(splits_page) => Some((Ok(splits_page.splits), splits_page.next_page_offset)),
+            Err(error) => Some((Err(error), None)),
+        }
+    })
+    .map_ok(|splits| stream::iter(splits.into_iter().map(Ok)))
+    .try_flatten()
+    .boxed()
+}
 
 /// Metastore meant to manage Quickwit's indexes and their splits.
 ///
@@ -143,6 +213,30 @@ pub trait Metastore: Send + Sync + 'static {
     /// Returns a list of all splits currently known to the metastore regardless of their state.
     async fn list_all_splits(&self, index_id: &str) -> MetastoreResult<Vec<Split>>;
 
+    /// Lists a page of at most `limit` splits in the given `split_state`, skipping the first
+    /// `offset` splits.
+    ///
+    /// Splits are ordered by split ID so that consecutive pages neither overlap nor miss a split
+    /// as long as the set of splits does not change. `limit` must be greater than zero.
+    /// An error will occur if an index that does not exist in the storage is specified.
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        split_state: SplitState,
+        offset: usize,
+        limit: usize,
+    ) -> MetastoreResult<SplitsPage> {
+        SplitsPage::check_limit(limit)?;
+        let mut splits = self.list_splits(index_id, split_state, None, None).await?;
+        splits.sort_by(|left, right| {
+            left.split_metadata
+                .split_id
+                .cmp(&right.split_metadata.split_id)
+        });
+        let splits = splits.into_iter().skip(offset).take(limit + 1).collect();
+        Ok(SplitsPage::from_splits(splits, offset, limit))
+    }
+
     /// Marks a list of splits for deletion.
     ///
     /// This API will change the state to [`SplitState::MarkedForDeletion`] so that it is not
@@ -170,6 +264,12 @@ pub trait Metastore: Send + Sync + 'static {
     /// If a checkpoint is already registered for the source, it is kept.
     async fn add_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()>;
 
+    /// Replaces the params of a source, keeping its checkpoint. Fails with
+    /// [`SourceDoesNotExist`](crate::MetastoreError::SourceDoesNotExist) if the source does not
+    /// exist and with [`SourceTypeMismatch`](crate::MetastoreError::SourceTypeMismatch) if the
+    /// new params are of another source type, since the checkpoint would no longer make sense.
+    async fn update_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()>;
+
     /// Deletes a source. Fails with
     /// [`SourceDoesNotExist`](crate::MetastoreError::SourceDoesNotExist) if the specified source
     /// does not exist.
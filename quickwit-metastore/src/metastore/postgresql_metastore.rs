@@ -36,9 +36,10 @@ use tracing::{debug, error, instrument, warn};
 
 use crate::checkpoint::IndexCheckpointDelta;
 use crate::metastore::postgresql_model::{self, Index, IndexIdSplitIdRow};
+use crate::metastore_resolver::extract_polling_interval_from_uri;
 use crate::{
     IndexMetadata, Metastore, MetastoreError, MetastoreFactory, MetastoreResolverError,
-    MetastoreResult, Split, SplitMetadata, SplitState,
+    MetastoreResult, Split, SplitMetadata, SplitState, SplitsPage,
 };
 
 static MIGRATOR: Migrator = sqlx::migrate!("migrations/postgresql");
@@ -210,6 +211,7 @@ async fn list_splits_helper(
     state_opt: Option<SplitState>,
     time_range_opt: Option<Range<i64>>,
     tags_opt: Option<TagFilterAst>,
+    offset_limit_opt: Option<(usize, usize)>,
 ) -> MetastoreResult<Vec<Split>> {
     let mut sql = r#"
         SELECT *
@@ -237,6 +239,13 @@ async fn list_splits_helper(
         sql.push_str(") ");
     }
 
+    if let Some((offset, limit)) = offset_limit_opt {
+        sql.push_str(&format!(
+            " ORDER BY split_id LIMIT {} OFFSET {}",
+            limit, offset
+        ));
+    }
+
     let splits = sqlx::query_as::<_, postgresql_model::Split>(&sql)
         .bind(index_id)
         .fetch_all(&mut *tx)
@@ -593,17 +602,41 @@ impl Metastore for PostgresqlMetastore {
         tags: Option<TagFilterAst>,
     ) -> MetastoreResult<Vec<Split>> {
         run_with_tx!(self.connection_pool, tx, {
-            list_splits_helper(tx, index_id, Some(state), time_range_opt, tags).await
+            list_splits_helper(tx, index_id, Some(state), time_range_opt, tags, None).await
         })
     }
 
     #[instrument(skip(self))]
     async fn list_all_splits(&self, index_id: &str) -> MetastoreResult<Vec<Split>> {
         run_with_tx!(self.connection_pool, tx, {
-            list_splits_helper(tx, index_id, None, None, None).await
+            list_splits_helper(tx, index_id, None, None, None, None).await
         })
     }
 
+    #[instrument(skip(self))]
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        split_state: SplitState,
+        offset: usize,
+        limit: usize,
+    ) -> MetastoreResult<SplitsPage> {
+        SplitsPage::check_limit(limit)?;
+        let splits = run_with_tx!(self.connection_pool, tx, {
+            // Fetching one extra split tells whether there is a next page.
+            list_splits_helper(
+                tx,
+                index_id,
+                Some(split_state),
+                None,
+                None,
+                Some((offset, limit + 1)),
+            )
+            .await
+        })?;
+        Ok(SplitsPage::from_splits(splits, offset, limit))
+    }
+
     #[instrument(skip(self))]
     async fn mark_splits_for_deletion<'a>(
         &self,
@@ -696,6 +729,16 @@ impl Metastore for PostgresqlMetastore {
         })
     }
 
+    #[instrument(skip(self, source), fields(source_id=source.source_id.as_str()))]
+    async fn update_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()> {
+        run_with_tx!(self.connection_pool, tx, {
+            mutate_index_metadata(tx, index_id, |index_metadata| {
+                index_metadata.update_source(source)
+            })
+            .await
+        })
+    }
+
     #[instrument(skip(self))]
     async fn delete_source(&self, index_id: &str, source_id: &str) -> MetastoreResult<()> {
         run_with_tx!(self.connection_pool, tx, {
@@ -802,6 +845,17 @@ impl PostgresqlMetastoreFactory {
 #[async_trait]
 impl MetastoreFactory for PostgresqlMetastoreFactory {
     async fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Metastore>, MetastoreResolverError> {
+        // The PostgreSQL metastore queries the database on every call, so there is no state to
+        // poll: the `polling_interval` option is accepted, stripped from the connection URI, and
+        // reported as ineffective.
+        let (uri_stripped, polling_interval_opt) = extract_polling_interval_from_uri(uri.as_str())?;
+        let uri = &Uri::new(uri_stripped);
+        if polling_interval_opt.is_some() {
+            warn!(
+                metastore_uri = %uri,
+                "The `polling_interval` option has no effect on the PostgreSQL metastore."
+            );
+        }
         if let Some(metastore) = self.get_from_cache(uri).await {
             debug!("using metastore from cache");
             return Ok(metastore);
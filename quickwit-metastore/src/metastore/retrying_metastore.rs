@@ -0,0 +1,371 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use quickwit_config::SourceConfig;
+use quickwit_doc_mapper::tag_pruning::TagFilterAst;
+use tracing::debug;
+
+use crate::checkpoint::IndexCheckpointDelta;
+use crate::{
+    IndexMetadata, Metastore, MetastoreError, MetastoreResult, Split, SplitMetadata, SplitState,
+    SplitsPage,
+};
+
+/// Parameters of the timeout and bounded retry policy applied by [`RetryingMetastore`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MetastoreRetryParams {
+    /// Maximum number of attempts of a read request. Mutating requests are attempted once.
+    pub max_attempts: usize,
+    /// Delay before the first retry. The delay doubles after each failed attempt.
+    pub base_delay: Duration,
+    /// Maximum delay between two attempts.
+    pub max_delay: Duration,
+    /// Maximum duration of a single attempt. If unset, requests never time out.
+    pub request_timeout_opt: Option<Duration>,
+}
+
+impl Default for MetastoreRetryParams {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(2),
+            request_timeout_opt: None,
+        }
+    }
+}
+
+impl MetastoreRetryParams {
+    /// Returns the delay to wait for after the `num_attempts`-th failed attempt.
+    fn compute_delay(&self, num_attempts: usize) -> Duration {
+        let exponent = num_attempts.saturating_sub(1).min(31) as u32;
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(exponent))
+            .min(self.max_delay)
+    }
+}
+
+/// Returns whether the error may go away by simply retrying the request, e.g. when the
+/// database restarts.
+fn is_transient_error(error: &MetastoreError) -> bool {
+    match error {
+        MetastoreError::ConnectionError { .. } | MetastoreError::Io(_) => true,
+        #[cfg(feature = "postgres")]
+        MetastoreError::DbError(db_error) => matches!(
+            db_error,
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed
+        ),
+        _ => false,
+    }
+}
+
+/// Wraps a [`Metastore`] to bound the duration of its requests and to retry its idempotent
+/// read requests on transient errors.
+///
+/// Mutating requests such as `publish_splits` are never retried: a request that timed out may
+/// still have been applied.
+pub struct RetryingMetastore {
+    metastore: Arc<dyn Metastore>,
+    retry_params: MetastoreRetryParams,
+}
+
+impl RetryingMetastore {
+    /// Creates a new [`RetryingMetastore`] wrapping `metastore`.
+    pub fn new(metastore: Arc<dyn Metastore>, retry_params: MetastoreRetryParams) -> Self {
+        Self {
+            metastore,
+            retry_params,
+        }
+    }
+
+    /// Runs `request_fut`, failing with a connection error if it exceeds the request timeout.
+    async fn with_timeout<T>(
+        &self,
+        request_fut: impl Future<Output = MetastoreResult<T>>,
+    ) -> MetastoreResult<T> {
+        let request_timeout = match self.retry_params.request_timeout_opt {
+            Some(request_timeout) => request_timeout,
+            None => return request_fut.await,
+        };
+        tokio::time::timeout(request_timeout, request_fut)
+            .await
+            .map_err(|_| MetastoreError::ConnectionError {
+                message: format!(
+                    "Metastore request timed out after {} ms.",
+                    request_timeout.as_millis()
+                ),
+            })?
+    }
+
+    /// Runs the read request built by `request_fn`, retrying it with an exponential backoff on
+    /// transient errors.
+    async fn with_retry<T, F, Fut>(&self, request_name: &str, request_fn: F) -> MetastoreResult<T>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = MetastoreResult<T>>,
+    {
+        let mut num_attempts = 0;
+        loop {
+            num_attempts += 1;
+            match self.with_timeout(request_fn()).await {
+                Err(error)
+                    if is_transient_error(&error)
+                        && num_attempts < self.retry_params.max_attempts =>
+                {
+                    let delay = self.retry_params.compute_delay(num_attempts);
+                    debug!(request = request_name, num_attempts = num_attempts, delay_millis = delay.as_millis(), error = ?error, "Metastore request failed, retrying.");
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Metastore for RetryingMetastore {
+    async fn check_connectivity(&self) -> anyhow::Result<()> {
+        self.metastore.check_connectivity().await
+    }
+
+    async fn create_index(&self, index_metadata: IndexMetadata) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.create_index(index_metadata))
+            .await
+    }
+
+    async fn list_indexes_metadatas(&self) -> MetastoreResult<Vec<IndexMetadata>> {
+        self.with_retry("list_indexes_metadatas", || {
+            self.metastore.list_indexes_metadatas()
+        })
+        .await
+    }
+
+    async fn index_metadata(&self, index_id: &str) -> MetastoreResult<IndexMetadata> {
+        self.with_retry("index_metadata", || self.metastore.index_metadata(index_id))
+            .await
+    }
+
+    async fn delete_index(&self, index_id: &str) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.delete_index(index_id))
+            .await
+    }
+
+    async fn stage_split(
+        &self,
+        index_id: &str,
+        split_metadata: SplitMetadata,
+    ) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.stage_split(index_id, split_metadata))
+            .await
+    }
+
+    async fn publish_splits<'a>(
+        &self,
+        index_id: &str,
+        split_ids: &[&'a str],
+        replaced_split_ids: &[&'a str],
+        checkpoint_delta_opt: Option<IndexCheckpointDelta>,
+    ) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.publish_splits(
+            index_id,
+            split_ids,
+            replaced_split_ids,
+            checkpoint_delta_opt,
+        ))
+        .await
+    }
+
+    async fn list_splits(
+        &self,
+        index_id: &str,
+        split_state: SplitState,
+        time_range: Option<Range<i64>>,
+        tags: Option<TagFilterAst>,
+    ) -> MetastoreResult<Vec<Split>> {
+        self.with_retry("list_splits", || {
+            self.metastore
+                .list_splits(index_id, split_state, time_range.clone(), tags.clone())
+        })
+        .await
+    }
+
+    async fn list_all_splits(&self, index_id: &str) -> MetastoreResult<Vec<Split>> {
+        self.with_retry("list_all_splits", || {
+            self.metastore.list_all_splits(index_id)
+        })
+        .await
+    }
+
+    async fn list_splits_page(
+        &self,
+        index_id: &str,
+        split_state: SplitState,
+        offset: usize,
+        limit: usize,
+    ) -> MetastoreResult<SplitsPage> {
+        self.with_retry("list_splits_page", || {
+            self.metastore
+                .list_splits_page(index_id, split_state, offset, limit)
+        })
+        .await
+    }
+
+    async fn mark_splits_for_deletion<'a>(
+        &self,
+        index_id: &str,
+        split_ids: &[&'a str],
+    ) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.mark_splits_for_deletion(index_id, split_ids))
+            .await
+    }
+
+    async fn delete_splits<'a>(
+        &self,
+        index_id: &str,
+        split_ids: &[&'a str],
+    ) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.delete_splits(index_id, split_ids))
+            .await
+    }
+
+    async fn add_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.add_source(index_id, source))
+            .await
+    }
+
+    async fn update_source(&self, index_id: &str, source: SourceConfig) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.update_source(index_id, source))
+            .await
+    }
+
+    async fn delete_source(&self, index_id: &str, source_id: &str) -> MetastoreResult<()> {
+        self.with_timeout(self.metastore.delete_source(index_id, source_id))
+            .await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.metastore.uri()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::MockMetastore;
+
+    fn retry_params_for_test() -> MetastoreRetryParams {
+        MetastoreRetryParams {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(2),
+            request_timeout_opt: None,
+        }
+    }
+
+    fn connection_error() -> MetastoreError {
+        MetastoreError::ConnectionError {
+            message: "connection reset".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retrying_metastore_retries_reads_on_transient_errors() {
+        let num_calls = Arc::new(AtomicUsize::new(0));
+        let num_calls_clone = num_calls.clone();
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore.expect_list_all_splits().returning(move |_| {
+            if num_calls_clone.fetch_add(1, Ordering::SeqCst) < 2 {
+                return Err(connection_error());
+            }
+            Ok(Vec::new())
+        });
+        let metastore = RetryingMetastore::new(Arc::new(mock_metastore), retry_params_for_test());
+        assert!(metastore.list_all_splits("test-index").await.is_ok());
+        assert_eq!(num_calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retrying_metastore_gives_up_after_max_attempts() {
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_index_metadata()
+            .times(3)
+            .returning(|_| Err(connection_error()));
+        let metastore = RetryingMetastore::new(Arc::new(mock_metastore), retry_params_for_test());
+        let error = metastore.index_metadata("test-index").await.unwrap_err();
+        assert!(matches!(error, MetastoreError::ConnectionError { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_retrying_metastore_does_not_retry_permanent_errors() {
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_index_metadata()
+            .times(1)
+            .returning(|index_id| {
+                Err(MetastoreError::IndexDoesNotExist {
+                    index_id: index_id.to_string(),
+                })
+            });
+        let metastore = RetryingMetastore::new(Arc::new(mock_metastore), retry_params_for_test());
+        let error = metastore.index_metadata("test-index").await.unwrap_err();
+        assert!(matches!(error, MetastoreError::IndexDoesNotExist { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_retrying_metastore_does_not_retry_mutations() {
+        let mut mock_metastore = MockMetastore::default();
+        mock_metastore
+            .expect_publish_splits()
+            .times(1)
+            .returning(|_, _, _, _| Err(connection_error()));
+        let metastore = RetryingMetastore::new(Arc::new(mock_metastore), retry_params_for_test());
+        let error = metastore
+            .publish_splits("test-index", &["split"], &[], None)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::ConnectionError { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_retrying_metastore_times_out_requests() {
+        let retry_params = MetastoreRetryParams {
+            request_timeout_opt: Some(Duration::from_millis(10)),
+            ..retry_params_for_test()
+        };
+        let metastore = RetryingMetastore::new(Arc::new(MockMetastore::default()), retry_params);
+        let error = metastore
+            .with_timeout(async {
+                tokio::time::sleep(Duration::from_secs(1)).await;
+                Ok(())
+            })
+            .await
+            .unwrap_err();
+        assert!(matches!(error, MetastoreError::ConnectionError { .. }));
+    }
+}
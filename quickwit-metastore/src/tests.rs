@@ -22,6 +22,7 @@ pub mod test_suite {
     use std::collections::{BTreeSet, HashSet};
 
     use async_trait::async_trait;
+    use futures::{StreamExt, TryStreamExt};
     use itertools::Itertools;
     use quickwit_common::rand::append_random_suffix;
     use quickwit_config::{SourceConfig, SourceParams};
@@ -31,7 +32,10 @@ pub mod test_suite {
     use tracing::{error, info};
 
     use crate::checkpoint::{IndexCheckpointDelta, PartitionId, Position, SourceCheckpoint};
-    use crate::{IndexMetadata, Metastore, MetastoreError, SplitMetadata, SplitState};
+    use crate::{
+        list_splits_stream, IndexMetadata, Metastore, MetastoreError, MetastoreResult, Split,
+        SplitMetadata, SplitState,
+    };
 
     #[async_trait]
     pub trait DefaultForTest {
@@ -137,6 +141,89 @@ pub mod test_suite {
         cleanup_index(&metastore, &index_metadata.index_id).await;
     }
 
+    pub async fn test_metastore_update_source<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = "test-metastore-update-source";
+        let index_uri = format!("ram://indexes/{index_id}");
+        let source_id = "test-metastore-update-source--file-source-id";
+
+        let source = SourceConfig {
+            source_id: source_id.to_string(),
+            source_params: SourceParams::file("path/to/file-1"),
+        };
+        let index_metadata = IndexMetadata::for_test(index_id, index_uri.as_str());
+        metastore
+            .create_index(index_metadata.clone())
+            .await
+            .unwrap();
+        metastore.add_source(index_id, source).await.unwrap();
+        metastore
+            .publish_splits(
+                index_id,
+                &[],
+                &[],
+                Some(IndexCheckpointDelta::for_test(source_id, 0..10)),
+            )
+            .await
+            .unwrap();
+        let source_checkpoint = metastore
+            .index_metadata(index_id)
+            .await
+            .unwrap()
+            .checkpoint
+            .source_checkpoint(source_id)
+            .cloned()
+            .unwrap();
+
+        let updated_source = SourceConfig {
+            source_id: source_id.to_string(),
+            source_params: SourceParams::file("path/to/file-2"),
+        };
+        metastore
+            .update_source(index_id, updated_source.clone())
+            .await
+            .unwrap();
+        let index_metadata = metastore.index_metadata(index_id).await.unwrap();
+        assert_eq!(index_metadata.sources.get(source_id), Some(&updated_source));
+        assert_eq!(
+            index_metadata.checkpoint.source_checkpoint(source_id),
+            Some(&source_checkpoint)
+        );
+
+        let void_source = SourceConfig {
+            source_id: source_id.to_string(),
+            source_params: SourceParams::void(),
+        };
+        assert!(matches!(
+            metastore
+                .update_source(index_id, void_source)
+                .await
+                .unwrap_err(),
+            MetastoreError::SourceTypeMismatch { .. }
+        ));
+        let unknown_source = SourceConfig {
+            source_id: "source-id-does-not-exist".to_string(),
+            source_params: SourceParams::void(),
+        };
+        assert!(matches!(
+            metastore
+                .update_source(index_id, unknown_source)
+                .await
+                .unwrap_err(),
+            MetastoreError::SourceDoesNotExist { .. }
+        ));
+        assert!(matches!(
+            metastore
+                .update_source("index-id-does-not-exist", updated_source)
+                .await
+                .unwrap_err(),
+            MetastoreError::IndexDoesNotExist { .. }
+        ));
+
+        cleanup_index(&metastore, index_id).await;
+    }
+
     pub async fn test_metastore_delete_source<MetastoreToTest: Metastore + DefaultForTest>() {
         let _ = tracing_subscriber::fmt::try_init();
         let metastore = MetastoreToTest::default_for_test().await;
@@ -1368,6 +1455,120 @@ pub mod test_suite {
         }
     }
 
+    pub async fn test_metastore_list_splits_page<MetastoreToTest: Metastore + DefaultForTest>() {
+        let metastore = MetastoreToTest::default_for_test().await;
+
+        let index_id = "list-splits-page-index";
+        let index_uri = format!("ram://indexes/{index_id}");
+        let index_metadata = IndexMetadata::for_test(index_id, &index_uri);
+
+        // List a page of splits on a non-existent index
+        {
+            let result = metastore
+                .list_splits_page("non-existent-index", SplitState::Published, 0, 2)
+                .await
+                .unwrap_err();
+            assert!(matches!(result, MetastoreError::IndexDoesNotExist { .. }));
+        }
+
+        // List pages of splits on an index
+        {
+            metastore
+                .create_index(index_metadata.clone())
+                .await
+                .unwrap();
+            let split_ids = ["split-a", "split-b", "split-c", "split-d", "split-e"];
+            for split_id in split_ids {
+                let split_metadata = SplitMetadata {
+                    split_id: split_id.to_string(),
+                    ..Default::default()
+                };
+                metastore
+                    .stage_split(index_id, split_metadata)
+                    .await
+                    .unwrap();
+            }
+            // Staged splits are filtered out.
+            metastore
+                .stage_split(
+                    index_id,
+                    SplitMetadata {
+                        split_id: "split-staged".to_string(),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+            metastore
+                .publish_splits(index_id, &split_ids, &[], None)
+                .await
+                .unwrap();
+
+            let mut listed_split_ids = Vec::new();
+            let mut offset = 0;
+            let mut num_pages = 0;
+            loop {
+                let page = metastore
+                    .list_splits_page(index_id, SplitState::Published, offset, 2)
+                    .await
+                    .unwrap();
+                assert!(page.splits.len() <= 2);
+                listed_split_ids.extend(
+                    page.splits
+                        .into_iter()
+                        .map(|split| split.split_metadata.split_id),
+                );
+                num_pages += 1;
+                match page.next_page_offset {
+                    Some(next_page_offset) => offset = next_page_offset,
+                    None => break,
+                }
+            }
+            assert_eq!(num_pages, 3);
+            assert_eq!(listed_split_ids, split_ids);
+
+            let streamed_split_ids: Vec<String> =
+                list_splits_stream(&metastore, index_id, SplitState::Published, 2)
+                    .map_ok(|split| split.split_metadata.split_id)
+                    .try_collect()
+                    .await
+                    .unwrap();
+            assert_eq!(streamed_split_ids, split_ids);
+
+            let streamed_splits: Vec<MetastoreResult<Split>> =
+                list_splits_stream(&metastore, index_id, SplitState::Published, 0)
+                    .collect()
+                    .await;
+            assert_eq!(streamed_splits.len(), 1);
+            assert!(matches!(
+                streamed_splits[0],
+                Err(MetastoreError::InvalidArgument { .. })
+            ));
+
+            let page = metastore
+                .list_splits_page(index_id, SplitState::Published, 4, 1)
+                .await
+                .unwrap();
+            assert_eq!(page.splits.len(), 1);
+            assert!(page.next_page_offset.is_none());
+
+            let page = metastore
+                .list_splits_page(index_id, SplitState::Published, 10, 2)
+                .await
+                .unwrap();
+            assert!(page.splits.is_empty());
+            assert!(page.next_page_offset.is_none());
+
+            let error = metastore
+                .list_splits_page(index_id, SplitState::Published, 0, 0)
+                .await
+                .unwrap_err();
+            assert!(matches!(error, MetastoreError::InvalidArgument { .. }));
+
+            cleanup_index(&metastore, index_id).await;
+        }
+    }
+
     pub async fn test_metastore_list_splits<MetastoreToTest: Metastore + DefaultForTest>() {
         let _ = tracing_subscriber::fmt::try_init();
         let metastore = MetastoreToTest::default_for_test().await;
@@ -2113,6 +2314,12 @@ macro_rules! metastore_test_suite {
                 crate::tests::test_suite::test_metastore_list_all_splits::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_list_splits_page() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_list_splits_page::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_list_splits() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -2133,6 +2340,12 @@ macro_rules! metastore_test_suite {
                 crate::tests::test_suite::test_metastore_add_source::<$metastore_type>().await;
             }
 
+            #[tokio::test]
+            async fn test_metastore_update_source() {
+                let _ = tracing_subscriber::fmt::try_init();
+                crate::tests::test_suite::test_metastore_update_source::<$metastore_type>().await;
+            }
+
             #[tokio::test]
             async fn test_metastore_delete_source() {
                 let _ = tracing_subscriber::fmt::try_init();
@@ -27,6 +27,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .type_attribute(".", "#[derive(Serialize, Deserialize)]")
         .type_attribute("OutputFormat", "#[serde(rename_all = \"snake_case\")]")
+        .type_attribute("SplitOrder", "#[serde(rename_all = \"snake_case\")]")
         .out_dir("src/")
         .compile_with_config(
             prost_config,
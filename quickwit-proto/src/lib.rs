@@ -40,11 +40,8 @@ impl From<SearchStreamRequest> for SearchRequest {
             search_fields: item.search_fields,
             start_timestamp: item.start_timestamp,
             end_timestamp: item.end_timestamp,
-            max_hits: 0,
-            start_offset: 0,
-            sort_by_field: None,
-            sort_order: None,
-            aggregation_request: None,
+            split_order: item.split_order,
+            ..Default::default()
         }
     }
 }
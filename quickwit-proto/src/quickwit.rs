@@ -36,6 +36,67 @@ pub struct SearchRequest {
     /// json serialized aggregation_request
     #[prost(string, optional, tag="11")]
     pub aggregation_request: ::core::option::Option<::prost::alloc::string::String>,
+    /// If set, the response includes the average number of storage bytes
+    /// fetched per returned hit.
+    #[prost(bool, tag="12")]
+    pub explain_bytes_per_hit: bool,
+    /// Order in which the splits are searched.
+    #[prost(enumeration="SplitOrder", tag="13")]
+    pub split_order: i32,
+    /// If set, the response includes the order in which the splits were
+    /// selected for search.
+    #[prost(bool, tag="14")]
+    pub explain_split_selection_order: bool,
+    /// If set, the response includes the doc mapping mode of the index.
+    #[prost(bool, tag="15")]
+    pub explain_doc_mapper_mode: bool,
+    /// If set, the response includes the time spent by the leaf searches
+    /// awaiting storage IO and running CPU-intensive collection.
+    #[prost(bool, tag="16")]
+    pub explain_io_wait: bool,
+    /// If set, the response includes the depth and the number of clauses of
+    /// the parsed query.
+    #[prost(bool, tag="17")]
+    pub explain_query_depth: bool,
+    /// If set, the response includes the number of searched splits containing
+    /// the sort field as a fast field, and the number of splits missing it.
+    #[prost(bool, tag="18")]
+    pub explain_sort_field_coverage: bool,
+    /// If set, the fields captured by the dynamic mapping are left out of the
+    /// returned hits.
+    #[prost(bool, tag="19")]
+    pub exclude_dynamic_fields: bool,
+    /// Fields missing from the doc store to reconstruct in the returned hits from
+    /// their fast field values or from the positions of their terms. Only honored
+    /// by single node searches.
+    #[prost(string, repeated, tag="20")]
+    pub reconstruct_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Text fields for which highlighted snippets of the returned hits are
+    /// generated. Only honored by single node searches.
+    #[prost(string, repeated, tag="21")]
+    pub snippet_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Maximum number of fragments of a snippet. Defaults to 3.
+    #[prost(uint32, optional, tag="22")]
+    pub snippet_max_fragments: ::core::option::Option<u32>,
+    /// Maximum number of characters of a snippet fragment. Defaults to 100.
+    #[prost(uint32, optional, tag="23")]
+    pub snippet_fragment_size: ::core::option::Option<u32>,
+    /// Per-field overrides of `snippet_max_fragments`.
+    #[prost(map="string, uint32", tag="24")]
+    pub snippet_max_fragments_per_field: ::std::collections::HashMap<::prost::alloc::string::String, u32>,
+    /// Per-field overrides of `snippet_fragment_size`.
+    #[prost(map="string, uint32", tag="25")]
+    pub snippet_fragment_size_per_field: ::std::collections::HashMap<::prost::alloc::string::String, u32>,
+    /// If set, only the number of documents matching the query is computed: no
+    /// hits are collected nor fetched.
+    #[prost(bool, tag="26")]
+    pub count_only: bool,
+    /// Tags, formatted as `field_name:value`, that the searched splits must be able
+    /// to hold. Splits whose tags prove that none of their documents holds one of
+    /// these values are skipped. Documents of the remaining splits are not filtered
+    /// on these tags.
+    #[prost(string, repeated, tag="27")]
+    pub required_tags: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -56,6 +117,50 @@ pub struct SearchResponse {
     /// Serialized aggregation response
     #[prost(string, optional, tag="5")]
     pub aggregation: ::core::option::Option<::prost::alloc::string::String>,
+    /// Details about how the search was executed. Only set if explicitly
+    /// requested in the search request.
+    #[prost(message, optional, tag="6")]
+    pub explanation: ::core::option::Option<SearchExplanation>,
+    /// True if buckets were dropped from the aggregation response because it
+    /// exceeded the searcher `max_aggregation_buckets` limit.
+    #[prost(bool, tag="7")]
+    pub aggregation_truncated: bool,
+}
+#[derive(Serialize, Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SearchExplanation {
+    /// Total number of bytes fetched from the storage by the leaf searches and
+    /// the doc fetches. Bytes read from the searcher caches are not counted.
+    #[prost(uint64, tag="1")]
+    pub num_bytes_fetched: u64,
+    /// Average number of storage bytes fetched per returned hit.
+    /// Unset if no hit was returned.
+    #[prost(double, optional, tag="2")]
+    pub bytes_per_hit: ::core::option::Option<f64>,
+    /// Ids of the splits, in the order in which they were selected for search.
+    #[prost(string, repeated, tag="3")]
+    pub split_selection_order: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Doc mapping mode of the index (`lenient`, `strict` or `dynamic`).
+    #[prost(string, tag="4")]
+    pub doc_mapping_mode: ::prost::alloc::string::String,
+    /// Time spent by the leaf searches awaiting storage IO, in microseconds.
+    #[prost(uint64, tag="5")]
+    pub io_wait_micros: u64,
+    /// Time spent by the leaf searches in CPU-intensive collection, in microseconds.
+    #[prost(uint64, tag="6")]
+    pub cpu_micros: u64,
+    /// Number of nested levels of the parsed query tree.
+    #[prost(uint64, tag="7")]
+    pub query_depth: u64,
+    /// Number of leaf clauses of the parsed query tree.
+    #[prost(uint64, tag="8")]
+    pub query_num_clauses: u64,
+    /// Number of searched splits containing the sort field as a fast field.
+    #[prost(uint64, tag="9")]
+    pub num_splits_with_sort_field: u64,
+    /// Number of searched splits missing the sort field. The order of their hits is undefined.
+    #[prost(uint64, tag="10")]
+    pub num_splits_without_sort_field: u64,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -88,6 +193,9 @@ pub struct LeafSearchRequest {
     /// split files.
     #[prost(string, tag="6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// Per-index storage overrides, as a json serialized `IndexStorageConfig`.
+    #[prost(string, optional, tag="7")]
+    pub index_storage_config: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -102,6 +210,12 @@ pub struct SplitIdAndFooterOffsets {
     /// The offset of the end of the footer in split bundle. The footer contains the file bundle metada and the hotcache.
     #[prost(uint64, tag="3")]
     pub split_footer_end: u64,
+    /// The lowest timestamp appearing in the split, if the index has a timestamp field.
+    #[prost(int64, optional, tag="4")]
+    pub timestamp_start: ::core::option::Option<i64>,
+    /// The highest timestamp appearing in the split, if the index has a timestamp field.
+    #[prost(int64, optional, tag="5")]
+    pub timestamp_end: ::core::option::Option<i64>,
 }
 /// / Hits returned by a FetchDocRequest.
 /// /
@@ -135,6 +249,9 @@ pub struct Hit {
     /// The partial hit (ie: the sorting field + the document address)
     #[prost(message, optional, tag="2")]
     pub partial_hit: ::core::option::Option<PartialHit>,
+    /// The json serialized highlighted snippets of the hit, per snippet field.
+    #[prost(string, optional, tag="3")]
+    pub snippet_json: ::core::option::Option<::prost::alloc::string::String>,
 }
 /// A partial hit, is a hit for which we have not fetch the content yet.
 /// Instead, it holds a document_uri which is enough information to
@@ -184,6 +301,26 @@ pub struct LeafSearchResponse {
     /// json serialized intermediate aggregation_result.
     #[prost(string, optional, tag="5")]
     pub intermediate_aggregation_result: ::core::option::Option<::prost::alloc::string::String>,
+    /// Number of bytes fetched from the storage by the leaf(s). Bytes read from
+    /// the searcher caches are not counted.
+    #[prost(uint64, tag="6")]
+    pub num_bytes_fetched: u64,
+    /// Time spent by the leaf(s) awaiting storage IO, in microseconds.
+    #[prost(uint64, tag="7")]
+    pub io_wait_micros: u64,
+    /// Time spent by the leaf(s) in CPU-intensive collection, in microseconds.
+    #[prost(uint64, tag="8")]
+    pub cpu_micros: u64,
+    /// Number of splits containing the sort field as a fast field.
+    #[prost(uint64, tag="9")]
+    pub num_splits_with_sort_field: u64,
+    /// Number of splits missing the sort field as a fast field.
+    #[prost(uint64, tag="10")]
+    pub num_splits_without_sort_field: u64,
+    /// True if the leaf(s) dropped buckets from the intermediate aggregation result
+    /// because it exceeded the searcher `max_aggregation_buckets` limit.
+    #[prost(bool, tag="11")]
+    pub aggregation_truncated: bool,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -203,6 +340,9 @@ pub struct FetchDocsRequest {
     /// split files.
     #[prost(string, tag="4")]
     pub index_uri: ::prost::alloc::string::String,
+    /// Per-index storage overrides, as a json serialized `IndexStorageConfig`.
+    #[prost(string, optional, tag="5")]
+    pub index_storage_config: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -210,6 +350,10 @@ pub struct FetchDocsResponse {
     /// List of complete hits.
     #[prost(message, repeated, tag="1")]
     pub hits: ::prost::alloc::vec::Vec<LeafHit>,
+    /// Number of bytes fetched from the storage to fetch the docs. Bytes read
+    /// from the searcher caches are not counted.
+    #[prost(uint64, tag="2")]
+    pub num_bytes_fetched: u64,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -237,6 +381,13 @@ pub struct SearchStreamRequest {
     /// The field by which we want to partition
     #[prost(string, optional, tag="9")]
     pub partition_by_field: ::core::option::Option<::prost::alloc::string::String>,
+    /// Order in which the splits are streamed.
+    #[prost(enumeration="SplitOrder", tag="10")]
+    pub split_order: i32,
+    /// The fields by which we want to partition, forming a composite partition key.
+    /// Cannot be combined with `partition_by_field`.
+    #[prost(string, repeated, tag="11")]
+    pub partition_by_fields: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -256,6 +407,9 @@ pub struct LeafSearchStreamRequest {
     /// split files.
     #[prost(string, tag="6")]
     pub index_uri: ::prost::alloc::string::String,
+    /// Per-index storage overrides, as a json serialized `IndexStorageConfig`.
+    #[prost(string, optional, tag="7")]
+    pub index_storage_config: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Serialize, Deserialize)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -290,6 +444,36 @@ impl SortOrder {
         }
     }
 }
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum SplitOrder {
+    /// / Splits are searched in the order they are listed by the metastore.
+    ///
+    /// < This will be the default value;
+    Default = 0,
+    /// / Most recent splits first.
+    TimeDesc = 1,
+    /// / Oldest splits first.
+    TimeAsc = 2,
+    /// / Smallest splits first.
+    SizeAsc = 3,
+}
+impl SplitOrder {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            SplitOrder::Default => "DEFAULT",
+            SplitOrder::TimeDesc => "TIME_DESC",
+            SplitOrder::TimeAsc => "TIME_ASC",
+            SplitOrder::SizeAsc => "SIZE_ASC",
+        }
+    }
+}
 // -- Stream -------------------
 
 #[derive(Serialize, Deserialize)]
@@ -305,6 +489,9 @@ pub enum OutputFormat {
     /// / Format data by row in ClickHouse binary format.
     /// / <https://clickhouse.tech/docs/en/interfaces/formats/#rowbinary>
     ClickHouseRowBinary = 1,
+    /// / Format data by row in ClickHouse binary format, preceded by the names and types of the columns.
+    /// / <https://clickhouse.com/docs/en/interfaces/formats/#rowbinarywithnamesandtypes>
+    ClickHouseRowBinaryWithNamesAndTypes = 2,
 }
 impl OutputFormat {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -315,6 +502,7 @@ impl OutputFormat {
         match self {
             OutputFormat::Csv => "CSV",
             OutputFormat::ClickHouseRowBinary => "CLICK_HOUSE_ROW_BINARY",
+            OutputFormat::ClickHouseRowBinaryWithNamesAndTypes => "CLICK_HOUSE_ROW_BINARY_WITH_NAMES_AND_TYPES",
         }
     }
 }
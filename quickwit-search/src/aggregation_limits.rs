@@ -0,0 +1,383 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+
+use quickwit_config::{AggregationBucketsLimitPolicy, SearcherConfig};
+use quickwit_proto::LeafSearchResponse;
+use serde_json::Value as JsonValue;
+use tantivy::aggregation::agg_req::Aggregations;
+use tantivy::aggregation::agg_result::AggregationResults;
+use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
+
+use crate::SearchError;
+
+/// Final aggregation result, serialized as JSON.
+pub(crate) struct FinalAggregationResult {
+    pub aggregation_json: String,
+    /// True if buckets were dropped to enforce `max_aggregation_buckets`.
+    pub truncated: bool,
+}
+
+/// Turns the intermediate aggregation result merged from the leaves into the final aggregation
+/// result and enforces the `max_aggregation_buckets` limit of the searcher config.
+pub(crate) fn finalize_aggregation_result(
+    intermediate_aggregation_result: &str,
+    aggregation_request: &str,
+    searcher_config: &SearcherConfig,
+) -> crate::Result<FinalAggregationResult> {
+    let intermediate_aggregation_results: IntermediateAggregationResults =
+        serde_json::from_str(intermediate_aggregation_result)?;
+    let aggregations: Aggregations = serde_json::from_str(aggregation_request)?;
+    let aggregation_results: AggregationResults =
+        intermediate_aggregation_results.into_final_bucket_result(aggregations)?;
+    let mut aggregation_results_json = serde_json::to_value(&aggregation_results)?;
+    let truncated = enforce_max_aggregation_buckets(
+        &mut aggregation_results_json,
+        searcher_config.max_aggregation_buckets,
+        searcher_config.aggregation_buckets_limit_policy,
+    )?;
+    Ok(FinalAggregationResult {
+        aggregation_json: serde_json::to_string(&aggregation_results_json)?,
+        truncated,
+    })
+}
+
+/// Enforces the `max_aggregation_buckets` limit of the searcher config on the intermediate
+/// aggregation result of a leaf response, so that leaves do not build upon, merge, and ship more
+/// buckets than the final result may hold. With the `error` policy, the search fails as soon as
+/// the result of a split exceeds the limit.
+pub(crate) fn enforce_max_intermediate_aggregation_buckets(
+    leaf_search_response: &mut LeafSearchResponse,
+    searcher_config: &SearcherConfig,
+) -> crate::Result<()> {
+    let intermediate_aggregation_result = match leaf_search_response
+        .intermediate_aggregation_result
+        .as_mut()
+    {
+        Some(intermediate_aggregation_result) => intermediate_aggregation_result,
+        None => return Ok(()),
+    };
+    let mut intermediate_aggregation_results_json: JsonValue =
+        serde_json::from_str(intermediate_aggregation_result)?;
+    let max_aggregation_buckets = searcher_config.max_aggregation_buckets;
+    let mut num_buckets_left = max_aggregation_buckets;
+    let truncated = truncate_intermediate_aggregation_results(
+        &mut intermediate_aggregation_results_json,
+        &mut num_buckets_left,
+    );
+    if !truncated {
+        return Ok(());
+    }
+    if searcher_config.aggregation_buckets_limit_policy == AggregationBucketsLimitPolicy::Error {
+        return Err(exceeded_max_aggregation_buckets_error(
+            max_aggregation_buckets,
+        ));
+    }
+    *intermediate_aggregation_result =
+        serde_json::to_string(&intermediate_aggregation_results_json)?;
+    leaf_search_response.aggregation_truncated = true;
+    Ok(())
+}
+
+fn exceeded_max_aggregation_buckets_error(max_aggregation_buckets: usize) -> SearchError {
+    SearchError::InvalidAggregationRequest(format!(
+        "Aggregation result exceeds the maximum number of buckets (`max_aggregation_buckets: \
+         {max_aggregation_buckets}`)."
+    ))
+}
+
+/// Truncates the bucket lists of intermediate aggregation results, serialized as JSON, so that
+/// they do not contain more than `num_buckets_left` buckets overall, sub-aggregations included.
+/// Returns whether some buckets were dropped.
+fn truncate_intermediate_aggregation_results(
+    intermediate_aggregation_results: &mut JsonValue,
+    num_buckets_left: &mut usize,
+) -> bool {
+    // The bucket aggregation results are listed in `values`, their names in `keys`. Metric
+    // results do not have buckets.
+    let bucket_results = match intermediate_aggregation_results.pointer_mut("/buckets/values") {
+        Some(JsonValue::Array(bucket_results)) => bucket_results,
+        _ => return false,
+    };
+    let mut truncated = false;
+    for bucket_result in bucket_results.iter_mut() {
+        truncated |= truncate_intermediate_bucket_result(bucket_result, num_buckets_left);
+    }
+    truncated
+}
+
+fn truncate_intermediate_bucket_result(
+    bucket_result: &mut JsonValue,
+    num_buckets_left: &mut usize,
+) -> bool {
+    let mut truncated = false;
+    if let Some(term_bucket_result) = bucket_result.get_mut("Terms") {
+        truncated |= truncate_intermediate_term_buckets(term_bucket_result, *num_buckets_left);
+        if let Some(JsonValue::Object(entries)) = term_bucket_result.get_mut("entries") {
+            *num_buckets_left -= entries.len();
+            for entry in entries.values_mut() {
+                truncated |= truncate_intermediate_sub_aggregation(entry, num_buckets_left);
+            }
+        }
+    } else if let Some(JsonValue::Array(buckets)) = bucket_result.pointer_mut("/Histogram/buckets")
+    {
+        if buckets.len() > *num_buckets_left {
+            buckets.truncate(*num_buckets_left);
+            truncated = true;
+        }
+        *num_buckets_left -= buckets.len();
+        for bucket in buckets.iter_mut() {
+            truncated |= truncate_intermediate_sub_aggregation(bucket, num_buckets_left);
+        }
+    } else if let Some(JsonValue::Object(buckets)) = bucket_result.pointer_mut("/Range/buckets") {
+        if buckets.len() > *num_buckets_left {
+            let dropped_keys: Vec<String> =
+                buckets.keys().skip(*num_buckets_left).cloned().collect();
+            for dropped_key in dropped_keys {
+                buckets.remove(&dropped_key);
+            }
+            truncated = true;
+        }
+        *num_buckets_left -= buckets.len();
+        for bucket in buckets.values_mut() {
+            truncated |= truncate_intermediate_sub_aggregation(bucket, num_buckets_left);
+        }
+    }
+    truncated
+}
+
+/// Keeps the `max_num_entries` terms with the highest document counts. The documents of the
+/// dropped terms are accounted for in `sum_other_doc_count`, as the terms beyond the requested
+/// size are.
+fn truncate_intermediate_term_buckets(
+    term_bucket_result: &mut JsonValue,
+    max_num_entries: usize,
+) -> bool {
+    let entries = match term_bucket_result.get_mut("entries") {
+        Some(JsonValue::Object(entries)) if entries.len() > max_num_entries => entries,
+        _ => return false,
+    };
+    let doc_count = |entry: &JsonValue| entry["doc_count"].as_u64().unwrap_or(0);
+    let mut terms_by_doc_count: Vec<(String, u64)> = entries
+        .iter()
+        .map(|(term, entry)| (term.clone(), doc_count(entry)))
+        .collect();
+    terms_by_doc_count.sort_by_key(|(term, doc_count)| (Reverse(*doc_count), term.clone()));
+    let mut num_dropped_docs = 0;
+    for (dropped_term, dropped_doc_count) in terms_by_doc_count.into_iter().skip(max_num_entries) {
+        entries.remove(&dropped_term);
+        num_dropped_docs += dropped_doc_count;
+    }
+    if let Some(sum_other_doc_count) = term_bucket_result.get_mut("sum_other_doc_count") {
+        let num_other_docs = sum_other_doc_count.as_u64().unwrap_or(0) + num_dropped_docs;
+        *sum_other_doc_count = JsonValue::from(num_other_docs);
+    }
+    true
+}
+
+fn truncate_intermediate_sub_aggregation(
+    bucket: &mut JsonValue,
+    num_buckets_left: &mut usize,
+) -> bool {
+    match bucket.get_mut("sub_aggregation") {
+        Some(sub_aggregation) => {
+            truncate_intermediate_aggregation_results(sub_aggregation, num_buckets_left)
+        }
+        None => false,
+    }
+}
+
+/// Truncates the bucket lists of the aggregation results so that they do not contain more than
+/// `max_aggregation_buckets` buckets overall, sub-aggregations included. Buckets are counted
+/// level by level, in the order in which they are returned. Returns whether some buckets were
+/// dropped, or an error if the policy does not allow truncation.
+fn enforce_max_aggregation_buckets(
+    aggregation_results: &mut JsonValue,
+    max_aggregation_buckets: usize,
+    limit_policy: AggregationBucketsLimitPolicy,
+) -> crate::Result<bool> {
+    let mut num_buckets_left = max_aggregation_buckets;
+    let truncated = truncate_aggregation_results(aggregation_results, &mut num_buckets_left);
+
+    if truncated && limit_policy == AggregationBucketsLimitPolicy::Error {
+        return Err(exceeded_max_aggregation_buckets_error(
+            max_aggregation_buckets,
+        ));
+    }
+    Ok(truncated)
+}
+
+fn truncate_aggregation_results(
+    aggregation_results: &mut JsonValue,
+    num_buckets_left: &mut usize,
+) -> bool {
+    let mut truncated = false;
+    if let JsonValue::Object(aggregation_results) = aggregation_results {
+        for aggregation_result in aggregation_results.values_mut() {
+            truncated |= truncate_aggregation_result(aggregation_result, num_buckets_left);
+        }
+    }
+    truncated
+}
+
+fn truncate_aggregation_result(
+    aggregation_result: &mut JsonValue,
+    num_buckets_left: &mut usize,
+) -> bool {
+    // Metric results do not have buckets.
+    let buckets = match aggregation_result.get_mut("buckets") {
+        Some(buckets) => buckets,
+        None => return false,
+    };
+    let mut truncated = false;
+    match buckets {
+        JsonValue::Array(buckets) => {
+            if buckets.len() > *num_buckets_left {
+                buckets.truncate(*num_buckets_left);
+                truncated = true;
+            }
+            *num_buckets_left -= buckets.len();
+
+            for bucket in buckets.iter_mut() {
+                truncated |= truncate_aggregation_results(bucket, num_buckets_left);
+            }
+        }
+        // Keyed range aggregation.
+        JsonValue::Object(buckets) => {
+            if buckets.len() > *num_buckets_left {
+                let dropped_keys: Vec<String> =
+                    buckets.keys().skip(*num_buckets_left).cloned().collect();
+                for dropped_key in dropped_keys {
+                    buckets.remove(&dropped_key);
+                }
+                truncated = true;
+            }
+            *num_buckets_left -= buckets.len();
+
+            for bucket in buckets.values_mut() {
+                truncated |= truncate_aggregation_results(bucket, num_buckets_left);
+            }
+        }
+        _ => {}
+    }
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn terms_aggregation_results(num_buckets: usize) -> JsonValue {
+        let buckets: Vec<JsonValue> = (0..num_buckets)
+            .map(|i| {
+                json!({
+                    "key": format!("term-{i}"),
+                    "doc_count": 1,
+                    "price_stats": {"count": 1, "sum": 1.0},
+                    "by_size": {
+                        "buckets": [
+                            {"key": "small", "doc_count": 1},
+                            {"key": "large", "doc_count": 0}
+                        ]
+                    }
+                })
+            })
+            .collect();
+        json!({ "colors": { "buckets": buckets, "sum_other_doc_count": 0 } })
+    }
+
+    #[test]
+    fn test_enforce_max_aggregation_buckets_under_limit() {
+        let mut aggregation_results = terms_aggregation_results(3);
+        let expected_aggregation_results = aggregation_results.clone();
+        let truncated = enforce_max_aggregation_buckets(
+            &mut aggregation_results,
+            9,
+            AggregationBucketsLimitPolicy::Error,
+        )
+        .unwrap();
+        assert!(!truncated);
+        assert_eq!(aggregation_results, expected_aggregation_results);
+    }
+
+    #[test]
+    fn test_enforce_max_aggregation_buckets_truncate() {
+        let mut aggregation_results = terms_aggregation_results(3);
+        let truncated = enforce_max_aggregation_buckets(
+            &mut aggregation_results,
+            4,
+            AggregationBucketsLimitPolicy::Truncate,
+        )
+        .unwrap();
+        assert!(truncated);
+        let buckets = aggregation_results["colors"]["buckets"].as_array().unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(
+            buckets[0]["by_size"]["buckets"].as_array().unwrap().len(),
+            1
+        );
+        assert!(buckets[1]["by_size"]["buckets"]
+            .as_array()
+            .unwrap()
+            .is_empty());
+        assert_eq!(buckets[2]["price_stats"]["count"], 1);
+    }
+
+    #[test]
+    fn test_enforce_max_aggregation_buckets_keyed_buckets() {
+        let mut aggregation_results = json!({
+            "price_ranges": {
+                "buckets": {
+                    "*-10": {"doc_count": 2},
+                    "10-100": {"doc_count": 2},
+                    "100-*": {"doc_count": 1}
+                }
+            }
+        });
+        let truncated = enforce_max_aggregation_buckets(
+            &mut aggregation_results,
+            2,
+            AggregationBucketsLimitPolicy::Truncate,
+        )
+        .unwrap();
+        assert!(truncated);
+        assert_eq!(
+            aggregation_results["price_ranges"]["buckets"]
+                .as_object()
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_enforce_max_aggregation_buckets_error() {
+        let mut aggregation_results = terms_aggregation_results(3);
+        let error = enforce_max_aggregation_buckets(
+            &mut aggregation_results,
+            2,
+            AggregationBucketsLimitPolicy::Error,
+        )
+        .unwrap_err();
+        assert!(matches!(error, SearchError::InvalidAggregationRequest(_)));
+    }
+}
@@ -0,0 +1,180 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::RwLock;
+
+use once_cell::sync::OnceCell;
+use quickwit_storage::{fast_field_cache, MemorySizedCache};
+use serde::{Deserialize, Serialize};
+
+use crate::leaf::global_split_footer_cache;
+
+/// Effective capacities of the searcher caches, initialized from the searcher config and
+/// updatable at runtime with [`update_cache_capacities`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CacheCapacities {
+    /// Capacity of the fast field cache in bytes.
+    pub fast_field_cache_capacity_num_bytes: u64,
+    /// Capacity of the split footer cache in bytes.
+    pub split_footer_cache_capacity_num_bytes: u64,
+}
+
+/// Update of the searcher cache capacities. The capacities left unset are not modified.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CacheCapacitiesUpdate {
+    /// New capacity of the fast field cache in bytes.
+    #[serde(default)]
+    pub fast_field_cache_capacity_num_bytes: Option<u64>,
+    /// New capacity of the split footer cache in bytes.
+    #[serde(default)]
+    pub split_footer_cache_capacity_num_bytes: Option<u64>,
+}
+
+impl From<CacheCapacities> for CacheCapacitiesUpdate {
+    fn from(cache_capacities: CacheCapacities) -> Self {
+        CacheCapacitiesUpdate {
+            fast_field_cache_capacity_num_bytes: Some(
+                cache_capacities.fast_field_cache_capacity_num_bytes,
+            ),
+            split_footer_cache_capacity_num_bytes: Some(
+                cache_capacities.split_footer_cache_capacity_num_bytes,
+            ),
+        }
+    }
+}
+
+/// Caches of a searcher whose capacities can be updated at runtime.
+struct ResizableCaches<'a> {
+    fast_field_cache: &'a MemorySizedCache,
+    split_footer_cache: &'a MemorySizedCache<String>,
+    /// Guards the capacities of the caches so that readers always observe the capacities of both
+    /// caches as of the same update.
+    cache_capacities: RwLock<CacheCapacities>,
+}
+
+impl<'a> ResizableCaches<'a> {
+    fn new(
+        fast_field_cache: &'a MemorySizedCache,
+        split_footer_cache: &'a MemorySizedCache<String>,
+    ) -> Self {
+        let cache_capacities = CacheCapacities {
+            fast_field_cache_capacity_num_bytes: fast_field_cache
+                .capacity_in_bytes()
+                .unwrap_or(usize::MAX) as u64,
+            split_footer_cache_capacity_num_bytes: split_footer_cache
+                .capacity_in_bytes()
+                .unwrap_or(usize::MAX) as u64,
+        };
+        ResizableCaches {
+            fast_field_cache,
+            split_footer_cache,
+            cache_capacities: RwLock::new(cache_capacities),
+        }
+    }
+
+    fn cache_capacities(&self) -> CacheCapacities {
+        *self.cache_capacities.read().unwrap()
+    }
+
+    fn update_cache_capacities(&self, update: CacheCapacitiesUpdate) -> CacheCapacities {
+        let mut cache_capacities = self.cache_capacities.write().unwrap();
+        if let Some(capacity_num_bytes) = update.fast_field_cache_capacity_num_bytes {
+            self.fast_field_cache
+                .set_capacity_in_bytes(capacity_num_bytes as usize);
+            cache_capacities.fast_field_cache_capacity_num_bytes = capacity_num_bytes;
+        }
+        if let Some(capacity_num_bytes) = update.split_footer_cache_capacity_num_bytes {
+            self.split_footer_cache
+                .set_capacity_in_bytes(capacity_num_bytes as usize);
+            cache_capacities.split_footer_cache_capacity_num_bytes = capacity_num_bytes;
+        }
+        *cache_capacities
+    }
+}
+
+fn global_resizable_caches() -> &'static ResizableCaches<'static> {
+    static INSTANCE: OnceCell<ResizableCaches<'static>> = OnceCell::new();
+    INSTANCE.get_or_init(|| ResizableCaches::new(fast_field_cache(), global_split_footer_cache()))
+}
+
+/// Returns the current effective capacities of the searcher caches.
+pub fn cache_capacities() -> CacheCapacities {
+    global_resizable_caches().cache_capacities()
+}
+
+/// Resizes the searcher caches. Shrinking a cache evicts its least recently used items right away
+/// until it fits in its new capacity. Returns the resulting effective capacities.
+pub fn update_cache_capacities(update: CacheCapacitiesUpdate) -> CacheCapacities {
+    global_resizable_caches().update_cache_capacities(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use quickwit_storage::{OwnedBytes, STORAGE_METRICS};
+
+    use super::*;
+
+    #[test]
+    fn test_update_cache_capacities() {
+        // The global caches are shared with the other tests: we resize local caches instead.
+        let fast_field_cache =
+            MemorySizedCache::with_capacity_in_bytes(10_000, &STORAGE_METRICS.shortlived_cache);
+        let split_footer_cache = MemorySizedCache::<String>::with_capacity_in_bytes(
+            20_000,
+            &STORAGE_METRICS.shortlived_cache,
+        );
+        let resizable_caches = ResizableCaches::new(&fast_field_cache, &split_footer_cache);
+        let initial_cache_capacities = resizable_caches.cache_capacities();
+        assert_eq!(
+            initial_cache_capacities,
+            CacheCapacities {
+                fast_field_cache_capacity_num_bytes: 10_000,
+                split_footer_cache_capacity_num_bytes: 20_000,
+            }
+        );
+        fast_field_cache.put_slice(
+            PathBuf::from("split.split"),
+            0..5_000,
+            OwnedBytes::new(vec![0u8; 5_000]),
+        );
+        let cache_capacities = resizable_caches.update_cache_capacities(CacheCapacitiesUpdate {
+            fast_field_cache_capacity_num_bytes: Some(1_000),
+            split_footer_cache_capacity_num_bytes: None,
+        });
+        assert_eq!(
+            cache_capacities,
+            CacheCapacities {
+                fast_field_cache_capacity_num_bytes: 1_000,
+                split_footer_cache_capacity_num_bytes: 20_000,
+            }
+        );
+        assert_eq!(fast_field_cache.capacity_in_bytes(), Some(1_000));
+        assert_eq!(fast_field_cache.num_bytes(), 0);
+        assert_eq!(split_footer_cache.capacity_in_bytes(), Some(20_000));
+        assert_eq!(resizable_caches.cache_capacities(), cache_capacities);
+
+        let cache_capacities =
+            resizable_caches.update_cache_capacities(initial_cache_capacities.into());
+        assert_eq!(cache_capacities, initial_cache_capacities);
+        assert_eq!(fast_field_cache.capacity_in_bytes(), Some(10_000));
+    }
+}
@@ -179,6 +179,16 @@ fn merge_leaf_search_results(
                     + retry_response.num_attempted_splits,
                 failed_splits: retry_response.failed_splits,
                 partial_hits: initial_response.partial_hits,
+                num_bytes_fetched: initial_response.num_bytes_fetched
+                    + retry_response.num_bytes_fetched,
+                io_wait_micros: initial_response.io_wait_micros + retry_response.io_wait_micros,
+                cpu_micros: initial_response.cpu_micros + retry_response.cpu_micros,
+                num_splits_with_sort_field: initial_response.num_splits_with_sort_field
+                    + retry_response.num_splits_with_sort_field,
+                num_splits_without_sort_field: initial_response.num_splits_without_sort_field
+                    + retry_response.num_splits_without_sort_field,
+                aggregation_truncated: initial_response.aggregation_truncated
+                    || retry_response.aggregation_truncated,
             };
             Ok(merged_response)
         }
@@ -242,10 +252,13 @@ mod tests {
             partial_hits: vec![],
             index_id: "id".to_string(),
             index_uri: "uri".to_string(),
+            index_storage_config: None,
             split_offsets: vec![SplitIdAndFooterOffsets {
                 split_id: split_id.to_string(),
                 split_footer_end: 100,
                 split_footer_start: 0,
+                timestamp_start: None,
+                timestamp_end: None,
             }],
         }
     }
@@ -265,16 +278,21 @@ mod tests {
             search_request: Some(search_request),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            index_storage_config: None,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
             ],
         }
@@ -290,21 +308,28 @@ mod tests {
             fast_field: "fast".to_string(),
             output_format: 0,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         LeafSearchStreamRequest {
             request: Some(search_request),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            index_storage_config: None,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_start: 0,
                     split_footer_end: 100,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
             ],
         }
@@ -317,7 +342,7 @@ mod tests {
         mock_service
             .expect_fetch_docs()
             .return_once(|_: quickwit_proto::FetchDocsRequest| {
-                Ok(quickwit_proto::FetchDocsResponse { hits: vec![] })
+                Ok(quickwit_proto::FetchDocsResponse::default())
             });
         let client_pool = SearchClientPool::from_mocks(vec![Arc::new(mock_service)]).await?;
         let first_client =
@@ -341,7 +366,7 @@ mod tests {
         mock_service_2
             .expect_fetch_docs()
             .return_once(|_: quickwit_proto::FetchDocsRequest| {
-                Ok(quickwit_proto::FetchDocsResponse { hits: vec![] })
+                Ok(quickwit_proto::FetchDocsResponse::default())
             });
         let client_pool =
             SearchClientPool::from_mocks(vec![Arc::new(mock_service_1), Arc::new(mock_service_2)])
@@ -189,7 +189,11 @@ impl SegmentCollector for QuickwitSegmentCollector {
         }
 
         self.num_hits += 1;
-        self.collect_top_k(doc_id);
+        // Count-only searches do not collect hits, hence there is no need to compute the sorting
+        // field.
+        if self.max_hits > 0 {
+            self.collect_top_k(doc_id);
+        }
         if let Some(aggregation_collector) = self.aggregation.as_mut() {
             aggregation_collector.collect(doc_id, _score);
         }
@@ -226,6 +230,12 @@ impl SegmentCollector for QuickwitSegmentCollector {
             partial_hits,
             failed_splits: vec![],
             num_attempted_splits: 1,
+            num_bytes_fetched: 0,
+            io_wait_micros: 0,
+            cpu_micros: 0,
+            num_splits_with_sort_field: 0,
+            num_splits_without_sort_field: 0,
+            aggregation_truncated: false,
         })
     }
 }
@@ -242,16 +252,19 @@ pub struct QuickwitCollector {
     pub sort_by: SortBy,
     timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
     pub aggregation: Option<Aggregations>,
+    /// If set, the collector only counts the matching documents, see
+    /// `SearchRequest::count_only`.
+    pub count_only: bool,
 }
 
 impl QuickwitCollector {
     pub fn fast_field_names(&self) -> HashSet<String> {
         let mut fast_field_names = HashSet::default();
         match &self.sort_by {
-            SortBy::DocId => {}
-            SortBy::FastField { field_name, .. } => {
+            SortBy::FastField { field_name, .. } if !self.count_only => {
                 fast_field_names.insert(field_name.clone());
             }
+            _ => {}
         }
         if let Some(aggregate) = self.aggregation.as_ref() {
             fast_field_names.extend(get_fast_field_names(aggregate));
@@ -281,10 +294,18 @@ impl Collector for QuickwitCollector {
         segment_ord: SegmentOrdinal,
         segment_reader: &SegmentReader,
     ) -> tantivy::Result<Self::Child> {
-        let sort_by = resolve_sort_by(&self.sort_by, segment_reader)?;
+        let sort_by = if self.count_only {
+            SortingFieldComputer::SortByDocId
+        } else {
+            resolve_sort_by(&self.sort_by, segment_reader)?
+        };
         // Regardless of the start_offset, we need to collect top-K
         // starting from 0 for every leaves.
-        let leaf_max_hits = self.max_hits + self.start_offset;
+        let leaf_max_hits = if self.count_only {
+            0
+        } else {
+            self.max_hits + self.start_offset
+        };
 
         let timestamp_filter_opt =
             if let Some(timestamp_filter_builder) = &self.timestamp_filter_builder_opt {
@@ -331,7 +352,11 @@ impl Collector for QuickwitCollector {
         // We want the hits in [start_offset..start_offset + max_hits).
         // All leaves will return their top [0..max_hits) documents.
         // We compute the overall [0..start_offset + max_hits) documents ...
-        let num_hits = self.start_offset + self.max_hits;
+        let num_hits = if self.count_only {
+            0
+        } else {
+            self.start_offset + self.max_hits
+        };
         let mut merged_leaf_response = merge_leaf_responses(segment_fruits?, num_hits)?;
         // ... and drop the first [..start_offsets) hits.
         merged_leaf_response
@@ -377,10 +402,33 @@ fn merge_leaf_responses(
         .iter()
         .map(|leaf_response| leaf_response.num_attempted_splits)
         .sum();
+    let num_bytes_fetched = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.num_bytes_fetched)
+        .sum();
+    let io_wait_micros = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.io_wait_micros)
+        .sum();
+    let cpu_micros = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.cpu_micros)
+        .sum();
+    let num_splits_with_sort_field = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.num_splits_with_sort_field)
+        .sum();
+    let num_splits_without_sort_field = leaf_responses
+        .iter()
+        .map(|leaf_response| leaf_response.num_splits_without_sort_field)
+        .sum();
     let num_hits: u64 = leaf_responses
         .iter()
         .map(|leaf_response| leaf_response.num_hits)
         .sum();
+    let aggregation_truncated = leaf_responses
+        .iter()
+        .any(|leaf_response| leaf_response.aggregation_truncated);
     let failed_splits = leaf_responses
         .iter()
         .flat_map(|leaf_response| leaf_response.failed_splits.iter())
@@ -401,6 +449,12 @@ fn merge_leaf_responses(
         partial_hits: top_k_partial_hits,
         failed_splits,
         num_attempted_splits,
+        num_bytes_fetched,
+        io_wait_micros,
+        cpu_micros,
+        num_splits_with_sort_field,
+        num_splits_without_sort_field,
+        aggregation_truncated,
     })
 }
 
@@ -446,6 +500,7 @@ pub fn make_collector_for_split(
         sort_by: search_request.into(),
         timestamp_filter_builder_opt,
         aggregation,
+        count_only: search_request.count_only,
     })
 }
 
@@ -466,6 +521,7 @@ pub fn make_merge_collector(search_request: &SearchRequest) -> crate::Result<Qui
         sort_by: SortBy::DocId,
         timestamp_filter_builder_opt: None,
         aggregation,
+        count_only: search_request.count_only,
     })
 }
 
@@ -0,0 +1,254 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use quickwit_doc_mapper::{compute_query_complexity, DocMapper, ModeType};
+use quickwit_proto::{
+    LeafSearchResponse, SearchExplanation, SearchRequest, SplitIdAndFooterOffsets,
+};
+
+/// Costs and split statistics reported by the leaf searches.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LeafSearchCosts {
+    /// Number of bytes fetched from the storage.
+    pub num_bytes_fetched: u64,
+    /// Time spent awaiting storage IO, in microseconds.
+    pub io_wait_micros: u64,
+    /// Time spent in CPU-intensive collection, in microseconds.
+    pub cpu_micros: u64,
+    /// Number of searched splits containing the sort field as a fast field.
+    pub num_splits_with_sort_field: u64,
+    /// Number of searched splits missing the sort field as a fast field.
+    pub num_splits_without_sort_field: u64,
+}
+
+impl From<&LeafSearchResponse> for LeafSearchCosts {
+    fn from(leaf_search_response: &LeafSearchResponse) -> Self {
+        Self {
+            num_bytes_fetched: leaf_search_response.num_bytes_fetched,
+            io_wait_micros: leaf_search_response.io_wait_micros,
+            cpu_micros: leaf_search_response.cpu_micros,
+            num_splits_with_sort_field: leaf_search_response.num_splits_with_sort_field,
+            num_splits_without_sort_field: leaf_search_response.num_splits_without_sort_field,
+        }
+    }
+}
+
+/// Builds the [`SearchExplanation`] requested by the search request, if any.
+///
+/// `leaf_search_costs` are the costs reported by the leaf searches, `num_returned_hits` the
+/// number of hits actually returned to the client and `splits` the searched splits, in the
+/// order in which they were selected.
+pub(crate) fn build_search_explanation(
+    search_request: &SearchRequest,
+    leaf_search_costs: &LeafSearchCosts,
+    num_returned_hits: usize,
+    splits: &[SplitIdAndFooterOffsets],
+    doc_mapper: &dyn DocMapper,
+) -> Option<SearchExplanation> {
+    if !search_request.explain_bytes_per_hit
+        && !search_request.explain_split_selection_order
+        && !search_request.explain_doc_mapper_mode
+        && !search_request.explain_io_wait
+        && !search_request.explain_query_depth
+        && !search_request.explain_sort_field_coverage
+    {
+        return None;
+    }
+    let mut explanation = SearchExplanation::default();
+    if search_request.explain_bytes_per_hit {
+        let num_bytes_fetched = leaf_search_costs.num_bytes_fetched;
+        explanation.num_bytes_fetched = num_bytes_fetched;
+        explanation.bytes_per_hit = compute_bytes_per_hit(num_bytes_fetched, num_returned_hits);
+    }
+    if search_request.explain_split_selection_order {
+        explanation.split_selection_order =
+            splits.iter().map(|split| split.split_id.clone()).collect();
+    }
+    if search_request.explain_doc_mapper_mode {
+        explanation.doc_mapping_mode = mode_type_name(doc_mapper.mode_type()).to_string();
+    }
+    if search_request.explain_io_wait {
+        explanation.io_wait_micros = leaf_search_costs.io_wait_micros;
+        explanation.cpu_micros = leaf_search_costs.cpu_micros;
+    }
+    if search_request.explain_query_depth {
+        // The query has already been parsed successfully at this point.
+        if let Ok(query_complexity) = compute_query_complexity(&search_request.query) {
+            explanation.query_depth = query_complexity.depth as u64;
+            explanation.query_num_clauses = query_complexity.num_clauses as u64;
+        }
+    }
+    if search_request.explain_sort_field_coverage {
+        explanation.num_splits_with_sort_field = leaf_search_costs.num_splits_with_sort_field;
+        explanation.num_splits_without_sort_field = leaf_search_costs.num_splits_without_sort_field;
+    }
+    Some(explanation)
+}
+
+fn mode_type_name(mode_type: ModeType) -> &'static str {
+    match mode_type {
+        ModeType::Lenient => "lenient",
+        ModeType::Strict => "strict",
+        ModeType::Dynamic => "dynamic",
+    }
+}
+
+fn compute_bytes_per_hit(num_bytes_fetched: u64, num_returned_hits: usize) -> Option<f64> {
+    if num_returned_hits == 0 {
+        return None;
+    }
+    Some(num_bytes_fetched as f64 / num_returned_hits as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::default_doc_mapper_for_tests;
+
+    use super::*;
+
+    fn leaf_search_costs_for_test() -> LeafSearchCosts {
+        LeafSearchCosts {
+            num_bytes_fetched: 1_000,
+            io_wait_micros: 300,
+            cpu_micros: 200,
+            num_splits_with_sort_field: 3,
+            num_splits_without_sort_field: 1,
+        }
+    }
+
+    #[test]
+    fn test_build_search_explanation() {
+        let doc_mapper = default_doc_mapper_for_tests();
+        let leaf_search_costs = leaf_search_costs_for_test();
+        let search_request = SearchRequest::default();
+        assert!(
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &[], &doc_mapper)
+                .is_none()
+        );
+
+        let search_request = SearchRequest {
+            explain_bytes_per_hit: true,
+            ..Default::default()
+        };
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &[], &doc_mapper)
+                .unwrap();
+        assert_eq!(explanation.num_bytes_fetched, 1_000);
+        assert_eq!(explanation.bytes_per_hit, Some(250.0));
+
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 0, &[], &doc_mapper)
+                .unwrap();
+        assert_eq!(explanation.num_bytes_fetched, 1_000);
+        assert_eq!(explanation.bytes_per_hit, None);
+        assert!(explanation.split_selection_order.is_empty());
+    }
+
+    #[test]
+    fn test_build_search_explanation_split_selection_order() {
+        let doc_mapper = default_doc_mapper_for_tests();
+        let leaf_search_costs = leaf_search_costs_for_test();
+        let search_request = SearchRequest {
+            explain_split_selection_order: true,
+            ..Default::default()
+        };
+        let splits = vec![
+            SplitIdAndFooterOffsets {
+                split_id: "split-2".to_string(),
+                ..Default::default()
+            },
+            SplitIdAndFooterOffsets {
+                split_id: "split-1".to_string(),
+                ..Default::default()
+            },
+        ];
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &splits, &doc_mapper)
+                .unwrap();
+        assert_eq!(explanation.split_selection_order, ["split-2", "split-1"]);
+        assert_eq!(explanation.num_bytes_fetched, 0);
+        assert_eq!(explanation.bytes_per_hit, None);
+        assert!(explanation.doc_mapping_mode.is_empty());
+    }
+
+    #[test]
+    fn test_build_search_explanation_doc_mapper_mode() {
+        let doc_mapper = default_doc_mapper_for_tests();
+        let leaf_search_costs = leaf_search_costs_for_test();
+        let search_request = SearchRequest {
+            explain_doc_mapper_mode: true,
+            ..Default::default()
+        };
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &[], &doc_mapper)
+                .unwrap();
+        assert_eq!(explanation.doc_mapping_mode, "lenient");
+        assert!(explanation.split_selection_order.is_empty());
+    }
+
+    #[test]
+    fn test_build_search_explanation_io_wait() {
+        let doc_mapper = default_doc_mapper_for_tests();
+        let leaf_search_costs = leaf_search_costs_for_test();
+        let search_request = SearchRequest {
+            explain_io_wait: true,
+            ..Default::default()
+        };
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &[], &doc_mapper)
+                .unwrap();
+        assert_eq!(explanation.io_wait_micros, 300);
+        assert_eq!(explanation.cpu_micros, 200);
+        assert_eq!(explanation.num_bytes_fetched, 0);
+    }
+
+    #[test]
+    fn test_build_search_explanation_query_depth() {
+        let doc_mapper = default_doc_mapper_for_tests();
+        let leaf_search_costs = leaf_search_costs_for_test();
+        let search_request = SearchRequest {
+            query: "body:foo AND (body:bar OR body:baz)".to_string(),
+            explain_query_depth: true,
+            ..Default::default()
+        };
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &[], &doc_mapper)
+                .unwrap();
+        assert!(explanation.query_depth >= 3);
+        assert_eq!(explanation.query_num_clauses, 3);
+        assert_eq!(explanation.io_wait_micros, 0);
+    }
+
+    #[test]
+    fn test_build_search_explanation_sort_field_coverage() {
+        let doc_mapper = default_doc_mapper_for_tests();
+        let leaf_search_costs = leaf_search_costs_for_test();
+        let search_request = SearchRequest {
+            sort_by_field: Some("timestamp".to_string()),
+            explain_sort_field_coverage: true,
+            ..Default::default()
+        };
+        let explanation =
+            build_search_explanation(&search_request, &leaf_search_costs, 4, &[], &doc_mapper)
+                .unwrap();
+        assert_eq!(explanation.num_splits_with_sort_field, 3);
+        assert_eq!(explanation.num_splits_without_sort_field, 1);
+        assert_eq!(explanation.cpu_micros, 0);
+    }
+}
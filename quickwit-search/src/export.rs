@@ -0,0 +1,245 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Context;
+use quickwit_config::build_doc_mapper;
+use quickwit_doc_mapper::DocMapper;
+use quickwit_metastore::Metastore;
+use quickwit_proto::{PartialHit, SearchRequest, SplitIdAndFooterOffsets};
+use quickwit_storage::{Storage, StorageUriResolver};
+use tantivy::collector::{Collector, SegmentCollector};
+use tantivy::{DocAddress, DocId, ReloadPolicy, Score, SegmentOrdinal, SegmentReader};
+
+use crate::fetch_docs::fetch_docs;
+use crate::filters::{TimestampFilter, TimestampFilterBuilder};
+use crate::leaf::{open_index, warmup};
+use crate::{
+    convert_leaf_hit, extract_split_and_footer_offsets, list_relevant_splits, Result, SearchError,
+};
+
+/// Exports the documents matching a search request, one split after the other.
+///
+/// Unlike a search, an export does not rank the documents: the documents of a split are exported
+/// in the order of their address. Splits are immutable, so an export interrupted in the middle of
+/// a split can be resumed from the same document, provided it runs against the same list of
+/// splits.
+pub struct SplitExporter {
+    search_request: SearchRequest,
+    index_storage: Arc<dyn Storage>,
+    doc_mapper: Arc<dyn DocMapper>,
+}
+
+impl SplitExporter {
+    /// Creates an exporter of the documents matching `search_request`.
+    pub async fn new(
+        search_request: SearchRequest,
+        metastore: &dyn Metastore,
+        storage_resolver: StorageUriResolver,
+    ) -> Result<Self> {
+        let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
+        let index_storage = storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?;
+        let doc_mapper = build_doc_mapper(
+            &index_metadata.doc_mapping,
+            &index_metadata.search_settings,
+            &index_metadata.indexing_settings,
+        )
+        .map_err(|err| {
+            SearchError::InternalError(format!("Failed to build doc mapper. Cause: {}", err))
+        })?;
+        Ok(Self {
+            search_request,
+            index_storage,
+            doc_mapper,
+        })
+    }
+
+    /// Lists the published splits that may hold documents matching the request, sorted by split
+    /// ID.
+    pub async fn list_splits(
+        &self,
+        metastore: &dyn Metastore,
+    ) -> Result<Vec<SplitIdAndFooterOffsets>> {
+        let split_metas = list_relevant_splits(
+            &self.search_request,
+            self.doc_mapper.timestamp_precision(),
+            metastore,
+        )
+        .await?;
+        let mut splits: Vec<SplitIdAndFooterOffsets> = split_metas
+            .iter()
+            .map(extract_split_and_footer_offsets)
+            .collect();
+        splits.sort_by(|left, right| left.split_id.cmp(&right.split_id));
+        Ok(splits)
+    }
+
+    /// Returns the addresses of the documents of the split matching the request, in increasing
+    /// order. Only the addresses are held in memory, not the documents.
+    pub async fn collect_doc_addresses(
+        &self,
+        split: &SplitIdAndFooterOffsets,
+    ) -> Result<Vec<DocAddress>> {
+        let index = open_index(self.index_storage.clone(), split).await?;
+        let split_schema = index.schema();
+        let query = self
+            .doc_mapper
+            .query(split_schema.clone(), &self.search_request)?;
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::Manual)
+            .try_into()?;
+        let searcher = reader.searcher();
+        let timestamp_filter_builder_opt = TimestampFilterBuilder::new(
+            self.doc_mapper.timestamp_field_name(),
+            self.doc_mapper.timestamp_field(&split_schema),
+            self.search_request.start_timestamp,
+            self.search_request.end_timestamp,
+        );
+        let fast_field_names: HashSet<String> = timestamp_filter_builder_opt
+            .iter()
+            .map(|timestamp_filter_builder| timestamp_filter_builder.timestamp_field_name.clone())
+            .collect();
+        warmup(
+            &searcher,
+            query.as_ref(),
+            &fast_field_names,
+            &Default::default(),
+        )
+        .await?;
+        let collector = DocAddressCollector {
+            timestamp_filter_builder_opt,
+        };
+        let doc_addresses = crate::run_cpu_intensive(move || {
+            Result::<Vec<DocAddress>>::Ok(searcher.search(query.as_ref(), &collector)?)
+        })
+        .await
+        .map_err(|_| {
+            SearchError::InternalError(format!(
+                "Error when collecting the documents of split {}",
+                split.split_id
+            ))
+        })??;
+        Ok(doc_addresses)
+    }
+
+    /// Fetches the documents of the split at the given addresses, in the same order, as JSON.
+    pub async fn fetch_docs(
+        &self,
+        split: &SplitIdAndFooterOffsets,
+        doc_addresses: &[DocAddress],
+    ) -> Result<Vec<String>> {
+        let partial_hits = doc_addresses
+            .iter()
+            .map(|doc_address| PartialHit {
+                sorting_field_value: 0,
+                split_id: split.split_id.clone(),
+                segment_ord: doc_address.segment_ord,
+                doc_id: doc_address.doc_id,
+            })
+            .collect();
+        let fetch_docs_response = fetch_docs(
+            partial_hits,
+            self.index_storage.clone(),
+            std::slice::from_ref(split),
+        )
+        .await
+        .context("Failed to perform fetch docs.")?;
+        fetch_docs_response
+            .hits
+            .into_iter()
+            .map(|leaf_hit| {
+                let hit = convert_leaf_hit(
+                    leaf_hit,
+                    &*self.doc_mapper,
+                    self.search_request.exclude_dynamic_fields,
+                )?;
+                Ok(hit.json)
+            })
+            .collect()
+    }
+}
+
+/// Collects the addresses of the matching documents.
+struct DocAddressCollector {
+    timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
+}
+
+struct DocAddressSegmentCollector {
+    segment_ord: SegmentOrdinal,
+    timestamp_filter_opt: Option<TimestampFilter>,
+    doc_addresses: Vec<DocAddress>,
+}
+
+impl SegmentCollector for DocAddressSegmentCollector {
+    type Fruit = Vec<DocAddress>;
+
+    fn collect(&mut self, doc_id: DocId, _score: Score) {
+        if let Some(timestamp_filter) = &self.timestamp_filter_opt {
+            if !timestamp_filter.is_within_range(doc_id) {
+                return;
+            }
+        }
+        self.doc_addresses
+            .push(DocAddress::new(self.segment_ord, doc_id));
+    }
+
+    fn harvest(self) -> Vec<DocAddress> {
+        self.doc_addresses
+    }
+}
+
+impl Collector for DocAddressCollector {
+    type Child = DocAddressSegmentCollector;
+    type Fruit = Vec<DocAddress>;
+
+    fn for_segment(
+        &self,
+        segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let timestamp_filter_opt =
+            if let Some(timestamp_filter_builder) = &self.timestamp_filter_builder_opt {
+                timestamp_filter_builder.build(segment_reader)?
+            } else {
+                None
+            };
+        Ok(DocAddressSegmentCollector {
+            segment_ord,
+            timestamp_filter_opt,
+            doc_addresses: Vec::new(),
+        })
+    }
+
+    fn requires_scoring(&self) -> bool {
+        false
+    }
+
+    fn merge_fruits(&self, segment_fruits: Vec<Vec<DocAddress>>) -> tantivy::Result<Self::Fruit> {
+        let mut doc_addresses: Vec<DocAddress> = segment_fruits.into_iter().flatten().collect();
+        // The segments may be collected in any order.
+        doc_addresses.sort_unstable();
+        Ok(doc_addresses)
+    }
+}
@@ -123,7 +123,10 @@ pub async fn fetch_docs(
             }
         })
         .collect();
-    Ok(FetchDocsResponse { hits })
+    Ok(FetchDocsResponse {
+        hits,
+        ..Default::default()
+    })
 }
 
 const NUM_CONCURRENT_REQUESTS: usize = 10;
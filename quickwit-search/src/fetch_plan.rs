@@ -0,0 +1,150 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::ops::Range;
+
+use quickwit_doc_mapper::DocMapper;
+use quickwit_proto::{SearchRequest, SplitIdAndFooterOffsets};
+use serde::Serialize;
+use tantivy::query::Query;
+use tantivy::schema::Schema;
+use tantivy::Term;
+
+use crate::collector::make_collector_for_split;
+
+/// Storage fetches planned by the leaf search of a single split.
+///
+/// The plan is computed from the doc mapper schema, without reading the split.
+/// The byte ranges of the fast fields, term dictionaries and posting lists are
+/// only known once the split footer has been fetched, so the plan only reports
+/// the footer byte range along with the data that will be warmed up.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SplitFetchPlan {
+    /// Split ID.
+    pub split_id: String,
+    /// Byte range of the split footer, fetched to open the split.
+    pub footer_byte_range: Range<u64>,
+    /// Fast fields loaded by the collector.
+    pub fast_field_names: BTreeSet<String>,
+    /// Fields whose whole term dictionary is loaded.
+    pub term_dict_field_names: BTreeSet<String>,
+    /// Number of query terms whose posting list is loaded, per field.
+    pub num_query_terms_per_field: BTreeMap<String, usize>,
+}
+
+impl SplitFetchPlan {
+    pub(crate) fn new(
+        split: &SplitIdAndFooterOffsets,
+        schema: &Schema,
+        query: &dyn Query,
+        fast_field_names: HashSet<String>,
+        term_dict_field_names: HashSet<String>,
+    ) -> Self {
+        let mut terms: BTreeMap<Term, bool> = BTreeMap::new();
+        query.query_terms(&mut terms);
+        let mut num_query_terms_per_field = BTreeMap::new();
+        for term in terms.keys() {
+            let field_name = schema.get_field_name(term.field()).to_string();
+            *num_query_terms_per_field.entry(field_name).or_insert(0) += 1;
+        }
+        SplitFetchPlan {
+            split_id: split.split_id.clone(),
+            footer_byte_range: split.split_footer_start..split.split_footer_end,
+            fast_field_names: fast_field_names.into_iter().collect(),
+            term_dict_field_names: term_dict_field_names.into_iter().collect(),
+            num_query_terms_per_field,
+        }
+    }
+}
+
+/// Plans the storage fetches of the leaf search of `search_request` on each
+/// of the `splits`, without fetching anything.
+pub(crate) fn plan_leaf_search_fetches(
+    search_request: &SearchRequest,
+    doc_mapper: &dyn DocMapper,
+    splits: &[SplitIdAndFooterOffsets],
+) -> crate::Result<Vec<SplitFetchPlan>> {
+    let schema = doc_mapper.schema();
+    let query = doc_mapper.query(schema.clone(), search_request)?;
+    splits
+        .iter()
+        .map(|split| {
+            let quickwit_collector = make_collector_for_split(
+                split.split_id.clone(),
+                doc_mapper,
+                search_request,
+                &schema,
+            )?;
+            Ok(SplitFetchPlan::new(
+                split,
+                &schema,
+                query.as_ref(),
+                quickwit_collector.fast_field_names(),
+                quickwit_collector.term_dict_field_names(),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::DefaultDocMapper;
+
+    use super::*;
+
+    #[test]
+    fn test_plan_leaf_search_fetches() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_value(serde_json::json!({
+            "field_mappings": [
+                { "name": "body", "type": "text" },
+                { "name": "ts", "type": "i64", "fast": true }
+            ],
+            "timestamp_field": "ts"
+        }))
+        .unwrap();
+        let search_request = SearchRequest {
+            query: "body:hello OR body:world".to_string(),
+            sort_by_field: Some("ts".to_string()),
+            start_timestamp: Some(10),
+            ..Default::default()
+        };
+        let split = SplitIdAndFooterOffsets {
+            split_id: "split1".to_string(),
+            split_footer_start: 100,
+            split_footer_end: 150,
+            timestamp_start: None,
+            timestamp_end: None,
+        };
+        let fetch_plans = plan_leaf_search_fetches(&search_request, &doc_mapper, &[split]).unwrap();
+        assert_eq!(fetch_plans.len(), 1);
+        let fetch_plan = &fetch_plans[0];
+        assert_eq!(fetch_plan.split_id, "split1");
+        assert_eq!(fetch_plan.footer_byte_range, 100..150);
+        assert_eq!(
+            fetch_plan.fast_field_names,
+            BTreeSet::from(["ts".to_string()])
+        );
+        assert!(fetch_plan.term_dict_field_names.is_empty());
+        assert_eq!(
+            fetch_plan.num_query_terms_per_field,
+            BTreeMap::from([("body".to_string(), 2)])
+        );
+    }
+}
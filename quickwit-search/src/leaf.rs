@@ -21,13 +21,14 @@ use std::collections::{BTreeMap, HashSet};
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use anyhow::Context;
 use futures::future::try_join_all;
 use futures::Future;
 use itertools::{Either, Itertools};
 use once_cell::sync::OnceCell;
-use quickwit_config::get_searcher_config_instance;
+use quickwit_config::{get_searcher_config_instance, SearcherConfig};
 use quickwit_directories::{CachingDirectory, HotDirectory, StorageDirectory};
 use quickwit_doc_mapper::{DocMapper, QUICKWIT_TOKENIZER_MANAGER};
 use quickwit_proto::{
@@ -40,13 +41,15 @@ use tantivy::collector::Collector;
 use tantivy::directory::FileSlice;
 use tantivy::error::AsyncIoError;
 use tantivy::query::Query;
-use tantivy::schema::{Cardinality, FieldType};
+use tantivy::schema::{Cardinality, FieldType, Schema};
 use tantivy::{Index, ReloadPolicy, Searcher, Term};
 use tokio::sync::{Semaphore, SemaphorePermit};
 use tokio::task::spawn_blocking;
 use tracing::*;
 
+use crate::aggregation_limits::enforce_max_intermediate_aggregation_buckets;
 use crate::collector::{make_collector_for_split, make_merge_collector};
+use crate::split_order::sort_splits;
 use crate::SearchError;
 
 async fn get_leaf_search_split_semaphore() -> SemaphorePermit<'static> {
@@ -61,7 +64,23 @@ async fn get_leaf_search_split_semaphore() -> SemaphorePermit<'static> {
         .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
 }
 
-fn global_split_footer_cache() -> &'static MemorySizedCache<String> {
+/// Returns the semaphore bounding the number of split readers open at the same time, across leaf
+/// searches and leaf search streams.
+pub(crate) fn open_split_semaphore() -> &'static Semaphore {
+    static INSTANCE: OnceCell<Semaphore> = OnceCell::new();
+    INSTANCE.get_or_init(|| Semaphore::new(get_searcher_config_instance().max_open_splits))
+}
+
+/// Acquires the permit to open a split reader. The permit must be held as long as the reader is
+/// open.
+pub(crate) async fn acquire_open_split_permit(semaphore: &Semaphore) -> SemaphorePermit<'_> {
+    semaphore
+        .acquire()
+        .await
+        .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
+}
+
+pub(crate) fn global_split_footer_cache() -> &'static MemorySizedCache<String> {
     static INSTANCE: OnceCell<MemorySizedCache<String>> = OnceCell::new();
     INSTANCE.get_or_init(|| {
         let config = get_searcher_config_instance();
@@ -72,7 +91,13 @@ fn global_split_footer_cache() -> &'static MemorySizedCache<String> {
     })
 }
 
-async fn get_split_footer_from_cache_or_fetch(
+/// Returns whether the footer of the split is in the split footer cache.
+#[cfg(test)]
+pub(crate) fn split_footer_cache_contains(split_id: &str) -> bool {
+    global_split_footer_cache().get(split_id).is_some()
+}
+
+pub(crate) async fn get_split_footer_from_cache_or_fetch(
     index_storage: Arc<dyn Storage>,
     split_and_footer_offsets: &SplitIdAndFooterOffsets,
 ) -> anyhow::Result<OwnedBytes> {
@@ -243,7 +268,7 @@ fn fast_field_idxs(fast_field_cardinality: Cardinality) -> &'static [usize] {
     }
 }
 
-async fn warm_up_fastfields(
+pub(crate) async fn warm_up_fastfields(
     searcher: &Searcher,
     fast_field_names: &HashSet<String>,
 ) -> anyhow::Result<()> {
@@ -314,46 +339,91 @@ async fn warm_up_terms(searcher: &Searcher, query: &dyn Query) -> anyhow::Result
     Ok(())
 }
 
+/// Returns whether the split schema defines `sort_by_field` as a fast field.
+fn split_has_sort_field(split_schema: &Schema, sort_by_field: &str) -> bool {
+    split_schema
+        .get_field(sort_by_field)
+        .map(|field| split_schema.get_field_entry(field).is_fast())
+        .unwrap_or(false)
+}
+
 /// Apply a leaf search on a single split.
-#[instrument(skip(search_request, storage, split, doc_mapper))]
+#[instrument(skip(
+    search_request,
+    storage,
+    split,
+    doc_mapper,
+    searcher_config,
+    open_split_semaphore
+))]
 async fn leaf_search_single_split(
     search_request: &SearchRequest,
     storage: Arc<dyn Storage>,
     split: SplitIdAndFooterOffsets,
     doc_mapper: Arc<dyn DocMapper>,
+    searcher_config: &SearcherConfig,
     leaf_split_search_permit: SemaphorePermit<'static>,
+    open_split_semaphore: &Semaphore,
 ) -> crate::Result<LeafSearchResponse> {
+    let _open_split_permit = acquire_open_split_permit(open_split_semaphore).await;
     let split_id = split.split_id.to_string();
+    let mut io_wait = Duration::default();
+    let open_index_start = Instant::now();
     let index = open_index(storage, &split).await?;
+    io_wait += open_index_start.elapsed();
     let split_schema = index.schema();
+    let has_sort_field_opt = search_request
+        .sort_by_field
+        .as_ref()
+        .map(|sort_by_field| split_has_sort_field(&split_schema, sort_by_field));
     let quickwit_collector = make_collector_for_split(
         split_id.clone(),
         doc_mapper.as_ref(),
         search_request,
         &split_schema,
     )?;
+    let mut fast_field_names = quickwit_collector.fast_field_names();
+    if let Some(sort_by_field) = search_request.sort_by_field.as_ref() {
+        // Splits missing the sort field are sorted by doc id, there is nothing to warm up.
+        if split_schema.get_field(sort_by_field).is_none() {
+            fast_field_names.remove(sort_by_field);
+        }
+    }
     let query = doc_mapper.query(split_schema, search_request)?;
     let reader = index
         .reader_builder()
         .reload_policy(ReloadPolicy::Manual)
         .try_into()?;
     let searcher = reader.searcher();
+    let warmup_start = Instant::now();
     warmup(
         &searcher,
         &query,
-        &quickwit_collector.fast_field_names(),
+        &fast_field_names,
         &quickwit_collector.term_dict_field_names(),
     )
     .await?;
-    let leaf_search_response = crate::run_cpu_intensive(move || {
+    io_wait += warmup_start.elapsed();
+    let (leaf_search_response_res, cpu_time) = crate::run_cpu_intensive(move || {
         let span = info_span!( "search", split_id = %split.split_id);
         let _span_guard = span.enter();
-        searcher.search(&query, &quickwit_collector)
+        let search_start = Instant::now();
+        let leaf_search_response_res = searcher.search(&query, &quickwit_collector);
+        (leaf_search_response_res, search_start.elapsed())
     })
     .await
     .map_err(|_| {
         crate::SearchError::InternalError(format!("Leaf search panicked. split={}", split_id))
-    })??;
+    })?;
+    let mut leaf_search_response = leaf_search_response_res?;
+    leaf_search_response.io_wait_micros = io_wait.as_micros() as u64;
+    leaf_search_response.cpu_micros = cpu_time.as_micros() as u64;
+    match has_sort_field_opt {
+        Some(true) => leaf_search_response.num_splits_with_sort_field = 1,
+        Some(false) => leaf_search_response.num_splits_without_sort_field = 1,
+        None => {}
+    }
+    enforce_max_intermediate_aggregation_buckets(&mut leaf_search_response, searcher_config)?;
     Ok(leaf_search_response)
 }
 
@@ -369,6 +439,29 @@ pub async fn leaf_search(
     splits: &[SplitIdAndFooterOffsets],
     doc_mapper: Arc<dyn DocMapper>,
 ) -> Result<LeafSearchResponse, SearchError> {
+    leaf_search_with_open_split_semaphore(
+        request,
+        index_storage,
+        splits,
+        doc_mapper,
+        get_searcher_config_instance(),
+        open_split_semaphore(),
+    )
+    .await
+}
+
+/// Same as [`leaf_search`], with the given searcher config, bounding the number of split readers
+/// open at the same time with the given semaphore.
+pub(crate) async fn leaf_search_with_open_split_semaphore(
+    request: &SearchRequest,
+    index_storage: Arc<dyn Storage>,
+    splits: &[SplitIdAndFooterOffsets],
+    doc_mapper: Arc<dyn DocMapper>,
+    searcher_config: &SearcherConfig,
+    open_split_semaphore: &Semaphore,
+) -> Result<LeafSearchResponse, SearchError> {
+    let mut splits = splits.to_vec();
+    sort_splits(&mut splits, request.split_order());
     let leaf_search_single_split_futures: Vec<_> = splits
         .iter()
         .map(|split| {
@@ -385,7 +478,9 @@ pub async fn leaf_search(
                     index_storage_clone,
                     split.clone(),
                     doc_mapper_clone,
+                    searcher_config,
                     leaf_split_search_permit,
+                    open_split_semaphore,
                 )
                 .await;
                 timer.observe_duration();
@@ -406,6 +501,13 @@ pub async fn leaf_search(
             Ok(split_search_resp) => Either::Left(Ok(split_search_resp)),
             Err(err) => Either::Right(err),
         });
+    // Exceeding the aggregation buckets limit is not a split failure: the whole search fails.
+    if let Some((_, error)) = errors
+        .iter()
+        .find(|(_, error)| matches!(error, SearchError::InvalidAggregationRequest(_)))
+    {
+        return Err(error.clone());
+    }
 
     // Creates a collector which merges responses into one
     let merge_collector = make_merge_collector(request)?;
@@ -417,6 +519,7 @@ pub async fn leaf_search(
             .instrument(info_span!("merge_search_responses"))
             .await
             .context("Failed to merge split search responses.")??;
+    enforce_max_intermediate_aggregation_buckets(&mut merged_search_response, searcher_config)?;
 
     merged_search_response
         .failed_splits
@@ -21,20 +21,30 @@
 #![warn(missing_docs)]
 #![allow(clippy::bool_assert_comparison)]
 
+mod aggregation_limits;
+mod cache_capacities;
 mod client;
 mod cluster_client;
 mod collector;
 mod error;
+mod explain;
+mod export;
 mod fetch_docs;
+mod fetch_plan;
 mod filters;
 mod leaf;
+mod reconstruct_fields;
 mod rendezvous_hasher;
 mod retry;
 mod root;
+mod schema_compat;
 mod search_client_pool;
 mod search_response_rest;
 mod search_stream;
 mod service;
+mod snippets;
+mod split_order;
+mod split_warmup;
 mod thread_pool;
 
 mod metrics;
@@ -52,30 +62,53 @@ use std::ops::Range;
 use std::sync::Arc;
 
 use anyhow::Context;
+use futures::future::try_join_all;
 use itertools::Itertools;
 use quickwit_cluster::Cluster;
-use quickwit_config::{build_doc_mapper, QuickwitConfig, SEARCHER_CONFIG_INSTANCE};
-use quickwit_doc_mapper::tag_pruning::extract_tags_from_query;
-use quickwit_doc_mapper::DocMapper;
+use quickwit_config::{
+    build_doc_mapper, get_searcher_config_instance, QuickwitConfig, SearcherConfig,
+    SEARCHER_CONFIG_INSTANCE,
+};
+use quickwit_doc_mapper::tag_pruning::{
+    extract_tags_from_query, required_tags_filter, TagFilterAst,
+};
+use quickwit_doc_mapper::{DocMapper, QueryExplanation, TimestampPrecision, DYNAMIC_FIELD_NAME};
 use quickwit_metastore::{Metastore, SplitMetadata, SplitState};
 use quickwit_proto::{PartialHit, SearchRequest, SearchResponse, SplitIdAndFooterOffsets};
-use quickwit_storage::StorageUriResolver;
+use quickwit_storage::{ByteCountingStorage, LocalFileCache, Storage, StorageUriResolver};
 use serde_json::Value as JsonValue;
-use tantivy::aggregation::agg_req::Aggregations;
-use tantivy::aggregation::agg_result::AggregationResults;
-use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::DocAddress;
+use tracing::warn;
 
+use crate::aggregation_limits::finalize_aggregation_result;
+pub use crate::cache_capacities::{
+    cache_capacities, update_cache_capacities, CacheCapacities, CacheCapacitiesUpdate,
+};
 pub use crate::client::SearchServiceClient;
 pub use crate::cluster_client::ClusterClient;
 pub use crate::error::{parse_grpc_error, SearchError};
+use crate::explain::{build_search_explanation, LeafSearchCosts};
+pub use crate::export::SplitExporter;
 use crate::fetch_docs::fetch_docs;
-use crate::leaf::leaf_search;
+use crate::fetch_plan::plan_leaf_search_fetches;
+pub use crate::fetch_plan::SplitFetchPlan;
+use crate::leaf::{
+    leaf_search, leaf_search_with_open_split_semaphore, open_index, open_split_semaphore,
+};
+use crate::reconstruct_fields::{reconstruct_fields, validate_reconstruct_fields};
 pub use crate::root::root_search;
+use crate::root::validate_query_depth;
+use crate::schema_compat::find_schema_incompatibilities;
+pub use crate::schema_compat::{
+    FieldIndexingOptions, FieldIndexingVariant, FieldSchemaIncompatibility,
+};
 pub use crate::search_client_pool::SearchClientPool;
 pub use crate::search_response_rest::SearchResponseRest;
-pub use crate::search_stream::root_search_stream;
+pub use crate::search_stream::{plan_leaf_search_stream_fetches, root_search_stream};
 pub use crate::service::{MockSearchService, SearchService, SearchServiceImpl};
+use crate::snippets::{build_snippet_generators, generate_snippets, SnippetGenerator};
+use crate::split_order::sort_splits;
+use crate::split_warmup::warm_up_recent_splits;
 use crate::thread_pool::run_cpu_intensive;
 
 /// GlobalDocAddress serves as a hit address.
@@ -130,17 +163,46 @@ fn extract_split_and_footer_offsets(split_metadata: &SplitMetadata) -> SplitIdAn
         split_id: split_metadata.split_id.clone(),
         split_footer_start: split_metadata.footer_offsets.start as u64,
         split_footer_end: split_metadata.footer_offsets.end as u64,
+        timestamp_start: split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.start()),
+        timestamp_end: split_metadata
+            .time_range
+            .as_ref()
+            .map(|time_range| *time_range.end()),
     }
 }
 
 /// Extract the list of relevant splits for a given search request.
+///
+/// The request time bounds are expressed in seconds and are converted to the
+/// `timestamp_precision` used to record the splits time range.
 async fn list_relevant_splits(
     search_request: &SearchRequest,
+    timestamp_precision: TimestampPrecision,
     metastore: &dyn Metastore,
 ) -> crate::Result<Vec<SplitMetadata>> {
-    let time_range_opt =
-        extract_time_range(search_request.start_timestamp, search_request.end_timestamp);
-    let tags_filter = extract_tags_from_query(&search_request.query)?;
+    let time_range_opt = extract_time_range(
+        search_request
+            .start_timestamp
+            .map(|start_timestamp| timestamp_precision.timestamp_from_secs(start_timestamp)),
+        search_request
+            .end_timestamp
+            .map(|end_timestamp| timestamp_precision.timestamp_from_secs(end_timestamp)),
+    );
+    let query_tags_filter_opt = extract_tags_from_query(&search_request.query)?;
+    let required_tags_filter_opt = required_tags_filter(&search_request.required_tags)
+        .map_err(|error| SearchError::InvalidArgument(error.to_string()))?;
+    let tags_filter = match (query_tags_filter_opt, required_tags_filter_opt) {
+        (Some(query_tags_filter), Some(required_tags_filter)) => Some(TagFilterAst::And(vec![
+            query_tags_filter,
+            required_tags_filter,
+        ])),
+        (query_tags_filter_opt, required_tags_filter_opt) => {
+            query_tags_filter_opt.or(required_tags_filter_opt)
+        }
+    };
     let split_metas = metastore
         .list_splits(
             &search_request.index_id,
@@ -168,19 +230,27 @@ async fn list_relevant_splits(
 /// to multivalued, we do want the documents emitted from old splits to
 /// also serialize the fields values as a JsonArray.
 ///
+/// If `exclude_dynamic_fields` is set, the fields captured by the dynamic
+/// mapping are dropped from the document.
+///
 /// The `convert_leaf_hit` is critical and needs to be tested against
 /// allowed DocMapper changes.
 fn convert_leaf_hit(
     leaf_hit: quickwit_proto::LeafHit,
     doc_mapper: &dyn DocMapper,
+    exclude_dynamic_fields: bool,
 ) -> crate::Result<quickwit_proto::Hit> {
-    let hit_json: BTreeMap<String, Vec<JsonValue>> = serde_json::from_str(&leaf_hit.leaf_json)
+    let mut hit_json: BTreeMap<String, Vec<JsonValue>> = serde_json::from_str(&leaf_hit.leaf_json)
         .map_err(|_| SearchError::InternalError("Invalid leaf json.".to_string()))?;
+    if exclude_dynamic_fields {
+        hit_json.remove(DYNAMIC_FIELD_NAME);
+    }
     let doc = doc_mapper.doc_to_json(hit_json)?;
     let json = serde_json::to_string(&doc).expect("Json serialization should never fail.");
     Ok(quickwit_proto::Hit {
         json,
         partial_hit: leaf_hit.partial_hit,
+        snippet_json: None,
     })
 }
 
@@ -191,12 +261,108 @@ pub async fn single_node_search(
     metastore: &dyn Metastore,
     storage_resolver: StorageUriResolver,
 ) -> crate::Result<SearchResponse> {
-    let start_instant = tokio::time::Instant::now();
+    single_node_search_with_searcher_config(
+        search_request,
+        metastore,
+        storage_resolver,
+        get_searcher_config_instance(),
+    )
+    .await
+}
+
+/// Plans the storage fetches of a search on the current node, without running it.
+/// See [`SplitFetchPlan`].
+pub async fn single_node_fetch_plan(
+    search_request: &SearchRequest,
+    metastore: &dyn Metastore,
+) -> crate::Result<Vec<SplitFetchPlan>> {
     let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
-    let index_storage = storage_resolver.resolve(&index_metadata.index_uri)?;
-    let metas = list_relevant_splits(search_request, metastore).await?;
-    let split_metadata: Vec<SplitIdAndFooterOffsets> =
+    let doc_mapper = build_doc_mapper(
+        &index_metadata.doc_mapping,
+        &index_metadata.search_settings,
+        &index_metadata.indexing_settings,
+    )
+    .map_err(|err| {
+        SearchError::InternalError(format!("Failed to build doc mapper. Cause: {}", err))
+    })?;
+    let metas =
+        list_relevant_splits(search_request, doc_mapper.timestamp_precision(), metastore).await?;
+    let mut split_metadata: Vec<SplitIdAndFooterOffsets> =
+        metas.iter().map(extract_split_and_footer_offsets).collect();
+    sort_splits(&mut split_metadata, search_request.split_order());
+    plan_leaf_search_fetches(search_request, &*doc_mapper, &split_metadata)
+}
+
+/// Explains how the query of `search_request` is parsed and rewritten against the current schema
+/// of the index, without running the search. See [`QueryExplanation`].
+pub async fn single_node_explain_query(
+    search_request: &SearchRequest,
+    metastore: &dyn Metastore,
+) -> crate::Result<QueryExplanation> {
+    let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
+    let doc_mapper = build_doc_mapper(
+        &index_metadata.doc_mapping,
+        &index_metadata.search_settings,
+        &index_metadata.indexing_settings,
+    )
+    .map_err(|err| {
+        SearchError::InternalError(format!("Failed to build doc mapper. Cause: {}", err))
+    })?;
+    Ok(doc_mapper.explain_query(doc_mapper.schema(), search_request))
+}
+
+/// Reads the schema of each split searched by `search_request` on the current node and reports
+/// the fields indexed with different options across these splits, without running the search.
+/// See [`FieldSchemaIncompatibility`].
+pub async fn single_node_schema_compat(
+    search_request: &SearchRequest,
+    metastore: &dyn Metastore,
+    storage_resolver: StorageUriResolver,
+) -> crate::Result<Vec<FieldSchemaIncompatibility>> {
+    let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
+    let index_storage = storage_resolver.resolve_with_storage_config(
+        &index_metadata.index_uri,
+        index_metadata.storage_config.as_ref(),
+    )?;
+    let doc_mapper = build_doc_mapper(
+        &index_metadata.doc_mapping,
+        &index_metadata.search_settings,
+        &index_metadata.indexing_settings,
+    )
+    .map_err(|err| {
+        SearchError::InternalError(format!("Failed to build doc mapper. Cause: {}", err))
+    })?;
+    let metas =
+        list_relevant_splits(search_request, doc_mapper.timestamp_precision(), metastore).await?;
+    let mut split_metadata: Vec<SplitIdAndFooterOffsets> =
         metas.iter().map(extract_split_and_footer_offsets).collect();
+    sort_splits(&mut split_metadata, search_request.split_order());
+    let split_schemas = try_join_all(split_metadata.iter().map(|split| {
+        let index_storage = index_storage.clone();
+        async move {
+            let index = open_index(index_storage, split).await?;
+            Ok::<_, SearchError>((split.split_id.clone(), index.schema()))
+        }
+    }))
+    .await?;
+    Ok(find_schema_incompatibilities(&split_schemas))
+}
+
+pub(crate) async fn single_node_search_with_searcher_config(
+    search_request: &SearchRequest,
+    metastore: &dyn Metastore,
+    storage_resolver: StorageUriResolver,
+    searcher_config: &SearcherConfig,
+) -> crate::Result<SearchResponse> {
+    let start_instant = tokio::time::Instant::now();
+    let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
+    let byte_counting_storage = Arc::new(ByteCountingStorage::new(
+        storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?,
+    ));
+    let index_storage: Arc<dyn Storage> = byte_counting_storage.clone();
     let doc_mapper = build_doc_mapper(
         &index_metadata.doc_mapping,
         &index_metadata.search_settings,
@@ -205,40 +371,70 @@ pub async fn single_node_search(
     .map_err(|err| {
         SearchError::InternalError(format!("Failed to build doc mapper. Cause: {}", err))
     })?;
-    let leaf_search_response = leaf_search(
+    validate_query_depth(search_request, searcher_config.max_query_depth)?;
+    let reconstruct_field_names =
+        validate_reconstruct_fields(&doc_mapper.schema(), &search_request.reconstruct_fields)?;
+    let snippet_generators = build_snippet_generators(search_request, &*doc_mapper)?;
+    let metas =
+        list_relevant_splits(search_request, doc_mapper.timestamp_precision(), metastore).await?;
+    let mut split_metadata: Vec<SplitIdAndFooterOffsets> =
+        metas.iter().map(extract_split_and_footer_offsets).collect();
+    sort_splits(&mut split_metadata, search_request.split_order());
+    let leaf_search_response = leaf_search_with_open_split_semaphore(
         search_request,
         index_storage.clone(),
         &split_metadata[..],
         doc_mapper.clone(),
+        searcher_config,
+        open_split_semaphore(),
     )
     .await
     .context("Failed to perform leaf search.")?;
-    let fetch_docs_response = fetch_docs(
-        leaf_search_response.partial_hits,
-        index_storage,
-        &split_metadata,
-    )
-    .await
-    .context("Failed to perform fetch docs.")?;
-    let hits: Vec<quickwit_proto::Hit> = fetch_docs_response
-        .hits
-        .into_iter()
-        .map(|leaf_hit| crate::convert_leaf_hit(leaf_hit, &*doc_mapper))
-        .collect::<crate::Result<_>>()?;
-    let elapsed = start_instant.elapsed();
-    let aggregation = if let Some(intermediate_aggregation_result) =
-        leaf_search_response.intermediate_aggregation_result
-    {
-        let res: IntermediateAggregationResults =
-            serde_json::from_str(&intermediate_aggregation_result)?;
-        let req: Aggregations = serde_json::from_str(search_request.aggregation_request())?;
-        let res: AggregationResults = res.into_final_bucket_result(req)?;
-        Some(serde_json::to_string(&res)?)
+    let mut leaf_search_costs = LeafSearchCosts::from(&leaf_search_response);
+    let hits = if search_request.count_only {
+        // Count-only searches do not collect any hit: there is no document to fetch.
+        Vec::new()
     } else {
-        None
+        fetch_hits(
+            search_request,
+            leaf_search_response.partial_hits,
+            index_storage,
+            &split_metadata,
+            &*doc_mapper,
+            &reconstruct_field_names,
+            &snippet_generators,
+        )
+        .await?
     };
+    // The leaf search and the doc fetches share the same storage: this counts the bytes
+    // fetched by both.
+    leaf_search_costs.num_bytes_fetched = byte_counting_storage.num_bytes_fetched();
+    let elapsed = start_instant.elapsed();
+    let final_aggregation_result_opt = leaf_search_response
+        .intermediate_aggregation_result
+        .map(|intermediate_aggregation_result| {
+            finalize_aggregation_result(
+                &intermediate_aggregation_result,
+                search_request.aggregation_request(),
+                searcher_config,
+            )
+        })
+        .transpose()?;
+    let explanation = build_search_explanation(
+        search_request,
+        &leaf_search_costs,
+        hits.len(),
+        &split_metadata,
+        &*doc_mapper,
+    );
+    let aggregation_truncated = leaf_search_response.aggregation_truncated
+        || final_aggregation_result_opt
+            .as_ref()
+            .map(|final_aggregation_result| final_aggregation_result.truncated)
+            .unwrap_or(false);
     Ok(SearchResponse {
-        aggregation,
+        aggregation: final_aggregation_result_opt
+            .map(|final_aggregation_result| final_aggregation_result.aggregation_json),
         num_hits: leaf_search_response.num_hits,
         hits,
         elapsed_time_micros: elapsed.as_micros() as u64,
@@ -247,9 +443,50 @@ pub async fn single_node_search(
             .iter()
             .map(|error| format!("{:?}", error))
             .collect_vec(),
+        explanation,
+        aggregation_truncated,
     })
 }
 
+/// Fetches the documents of the partial hits and converts them into hits, along with their
+/// reconstructed fields and their snippets.
+async fn fetch_hits(
+    search_request: &SearchRequest,
+    partial_hits: Vec<PartialHit>,
+    index_storage: Arc<dyn Storage>,
+    split_metadata: &[SplitIdAndFooterOffsets],
+    doc_mapper: &dyn DocMapper,
+    reconstruct_field_names: &[String],
+    snippet_generators: &[SnippetGenerator],
+) -> crate::Result<Vec<quickwit_proto::Hit>> {
+    let mut fetch_docs_response = fetch_docs(partial_hits, index_storage.clone(), split_metadata)
+        .await
+        .context("Failed to perform fetch docs.")?;
+    reconstruct_fields(
+        &mut fetch_docs_response.hits,
+        reconstruct_field_names,
+        index_storage,
+        split_metadata,
+    )
+    .await
+    .context("Failed to reconstruct fields.")?;
+    let snippet_jsons = generate_snippets(&fetch_docs_response.hits, snippet_generators)?;
+    fetch_docs_response
+        .hits
+        .into_iter()
+        .zip(snippet_jsons)
+        .map(|(leaf_hit, snippet_json)| {
+            let mut hit = crate::convert_leaf_hit(
+                leaf_hit,
+                doc_mapper,
+                search_request.exclude_dynamic_fields,
+            )?;
+            hit.snippet_json = snippet_json;
+            Ok(hit)
+        })
+        .collect()
+}
+
 /// Starts a search node, aka a `searcher`.
 pub async fn start_searcher_service(
     quickwit_config: &QuickwitConfig,
@@ -262,11 +499,42 @@ pub async fn start_searcher_service(
         .expect("could not set searcher config in global once cell");
     let client_pool = SearchClientPool::create_and_keep_updated(cluster).await?;
     let cluster_client = ClusterClient::new(client_pool.clone());
-    let search_service = Arc::new(SearchServiceImpl::new(
-        metastore,
-        storage_uri_resolver,
-        cluster_client,
-        client_pool,
-    ));
-    Ok(search_service)
+    let searcher_config = &quickwit_config.searcher_config;
+    if !searcher_config.warmup_index_ids.is_empty() {
+        let index_ids = searcher_config.warmup_index_ids.clone();
+        let num_splits_per_index = searcher_config.warmup_num_splits;
+        let warm_up_fast_fields = searcher_config.warmup_fast_fields;
+        let metastore = metastore.clone();
+        let storage_uri_resolver = storage_uri_resolver.clone();
+        tokio::spawn(async move {
+            if let Err(error) = warm_up_recent_splits(
+                &index_ids,
+                num_splits_per_index,
+                warm_up_fast_fields,
+                &*metastore,
+                &storage_uri_resolver,
+            )
+            .await
+            {
+                warn!(error = ?error, "Failed to warm up recent splits.");
+            }
+        });
+    }
+    let mut search_service =
+        SearchServiceImpl::new(metastore, storage_uri_resolver, cluster_client, client_pool);
+    if let Some(local_cache_dir_path) = &searcher_config.local_cache_dir_path {
+        let local_file_cache = LocalFileCache::open(
+            local_cache_dir_path.clone(),
+            searcher_config.local_cache_capacity.get_bytes() as usize,
+            searcher_config.local_cache_path_suffixes.clone(),
+        )
+        .with_context(|| {
+            format!(
+                "Failed to open local cache directory `{}`.",
+                local_cache_dir_path.display()
+            )
+        })?;
+        search_service = search_service.with_local_file_cache(Arc::new(local_file_cache));
+    }
+    Ok(Arc::new(search_service))
 }
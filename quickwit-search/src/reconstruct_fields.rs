@@ -0,0 +1,335 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+
+use futures::future::try_join_all;
+use itertools::Itertools;
+use quickwit_proto::{LeafHit, SplitIdAndFooterOffsets};
+use quickwit_storage::Storage;
+use serde_json::Value as JsonValue;
+use tantivy::fastfield::FastFieldReader;
+use tantivy::postings::Postings;
+use tantivy::schema::{
+    Cardinality, Field, FieldEntry, FieldType, IndexRecordOption, Schema, Value,
+};
+use tantivy::{DocAddress, DocId, DocSet, ReloadPolicy, SegmentReader, Term};
+
+use crate::leaf::{open_index, warm_up_fastfields};
+use crate::{GlobalDocAddress, SearchError};
+
+/// How the value of a field missing from the doc store is reconstructed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Reconstruction {
+    /// The value is read from the single-valued fast field.
+    FastField,
+    /// The text is rebuilt from the positions of the terms of the field. The tokens are joined
+    /// with spaces, so the punctuation and, depending on the tokenizer, the case of the original
+    /// text are lost.
+    Positions,
+}
+
+fn reconstruction(field_entry: &FieldEntry) -> Option<Reconstruction> {
+    match field_entry.field_type() {
+        FieldType::U64(options)
+        | FieldType::I64(options)
+        | FieldType::F64(options)
+        | FieldType::Bool(options)
+            if options.get_fastfield_cardinality() == Some(Cardinality::SingleValue) =>
+        {
+            Some(Reconstruction::FastField)
+        }
+        FieldType::Date(options)
+            if options.get_fastfield_cardinality() == Some(Cardinality::SingleValue) =>
+        {
+            Some(Reconstruction::FastField)
+        }
+        FieldType::Str(text_options)
+            if text_options
+                .get_indexing_options()
+                .map(|text_field_indexing| text_field_indexing.index_option().has_positions())
+                .unwrap_or(false) =>
+        {
+            Some(Reconstruction::Positions)
+        }
+        _ => None,
+    }
+}
+
+/// Checks that each of the `field_names` can be returned in the hits and returns the names of
+/// the fields that are not stored, i.e. that need to be reconstructed.
+pub(crate) fn validate_reconstruct_fields(
+    schema: &Schema,
+    field_names: &[String],
+) -> crate::Result<Vec<String>> {
+    let mut reconstruct_field_names = Vec::new();
+    for field_name in field_names {
+        let field = schema.get_field(field_name).ok_or_else(|| {
+            SearchError::InvalidQuery(format!("Field `{}` does not exist in schema", field_name))
+        })?;
+        let field_entry = schema.get_field_entry(field);
+        if field_entry.is_stored() {
+            continue;
+        }
+        if reconstruction(field_entry).is_none() {
+            return Err(SearchError::InvalidQuery(format!(
+                "Field `{}` cannot be reconstructed: it is neither stored, nor a single-valued \
+                 fast field, nor a text field indexed with positions.",
+                field_name
+            )));
+        }
+        reconstruct_field_names.push(field_name.clone());
+    }
+    Ok(reconstruct_field_names)
+}
+
+/// Adds to the hits the values of the `field_names` fields, which are not stored in the doc
+/// store, reconstructed from their fast field values or from the positions of their terms.
+///
+/// The fields are expected to have been validated with [`validate_reconstruct_fields`].
+pub(crate) async fn reconstruct_fields(
+    leaf_hits: &mut [LeafHit],
+    field_names: &[String],
+    index_storage: Arc<dyn Storage>,
+    splits: &[SplitIdAndFooterOffsets],
+) -> anyhow::Result<()> {
+    if field_names.is_empty() {
+        return Ok(());
+    }
+    let mut doc_addrs_per_split: HashMap<String, Vec<DocAddress>> = HashMap::new();
+    for partial_hit in leaf_hits
+        .iter()
+        .flat_map(|leaf_hit| leaf_hit.partial_hit.as_ref())
+    {
+        let global_doc_addr = GlobalDocAddress::from_partial_hit(partial_hit);
+        doc_addrs_per_split
+            .entry(global_doc_addr.split)
+            .or_default()
+            .push(global_doc_addr.doc_addr);
+    }
+    let split_futures = splits.iter().flat_map(|split| {
+        let doc_addrs = doc_addrs_per_split.remove(&split.split_id)?;
+        Some(reconstruct_fields_in_split(
+            index_storage.clone(),
+            split,
+            doc_addrs,
+            field_names,
+        ))
+    });
+    let mut values_per_doc: HashMap<GlobalDocAddress, Vec<(String, JsonValue)>> = HashMap::new();
+    for split_values in try_join_all(split_futures).await? {
+        for (global_doc_addr, field_name, value) in split_values {
+            values_per_doc
+                .entry(global_doc_addr)
+                .or_default()
+                .push((field_name, value));
+        }
+    }
+    for leaf_hit in leaf_hits.iter_mut() {
+        let global_doc_addr = if let Some(partial_hit) = &leaf_hit.partial_hit {
+            GlobalDocAddress::from_partial_hit(partial_hit)
+        } else {
+            continue;
+        };
+        let values = if let Some(values) = values_per_doc.remove(&global_doc_addr) {
+            values
+        } else {
+            continue;
+        };
+        let mut hit_json: BTreeMap<String, Vec<JsonValue>> =
+            serde_json::from_str(&leaf_hit.leaf_json)?;
+        for (field_name, value) in values {
+            hit_json.entry(field_name).or_insert_with(|| vec![value]);
+        }
+        leaf_hit.leaf_json = serde_json::to_string(&hit_json)?;
+    }
+    Ok(())
+}
+
+async fn reconstruct_fields_in_split(
+    index_storage: Arc<dyn Storage>,
+    split: &SplitIdAndFooterOffsets,
+    doc_addrs: Vec<DocAddress>,
+    field_names: &[String],
+) -> anyhow::Result<Vec<(GlobalDocAddress, String, JsonValue)>> {
+    let index = open_index(index_storage, split).await?;
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    let searcher = reader.searcher();
+    let schema = searcher.schema();
+    let doc_ids_per_segment = doc_addrs
+        .into_iter()
+        .map(|doc_addr| (doc_addr.segment_ord, doc_addr.doc_id))
+        .into_group_map();
+    let mut reconstructed_values = Vec::new();
+    for field_name in field_names {
+        // The field may be missing or stored in the splits created with a former doc mapping.
+        let field = if let Some(field) = schema.get_field(field_name) {
+            field
+        } else {
+            continue;
+        };
+        let field_entry = schema.get_field_entry(field);
+        if field_entry.is_stored() {
+            continue;
+        }
+        let reconstruction = if let Some(reconstruction) = reconstruction(field_entry) {
+            reconstruction
+        } else {
+            continue;
+        };
+        if reconstruction == Reconstruction::FastField {
+            warm_up_fastfields(&searcher, &HashSet::from([field_name.clone()])).await?;
+        }
+        for (&segment_ord, doc_ids) in &doc_ids_per_segment {
+            let segment_reader = searcher.segment_reader(segment_ord);
+            let values = match reconstruction {
+                Reconstruction::FastField => fast_field_values(segment_reader, field, doc_ids)?,
+                Reconstruction::Positions => {
+                    text_values_from_positions(segment_reader, field, doc_ids).await?
+                }
+            };
+            for (&doc_id, value_opt) in doc_ids.iter().zip(values) {
+                if let Some(value) = value_opt {
+                    let global_doc_addr = GlobalDocAddress {
+                        split: split.split_id.clone(),
+                        doc_addr: DocAddress::new(segment_ord, doc_id),
+                    };
+                    reconstructed_values.push((
+                        global_doc_addr,
+                        field_name.clone(),
+                        serde_json::to_value(&value)?,
+                    ));
+                }
+            }
+        }
+    }
+    Ok(reconstructed_values)
+}
+
+fn fast_field_values(
+    segment_reader: &SegmentReader,
+    field: Field,
+    doc_ids: &[DocId],
+) -> anyhow::Result<Vec<Option<Value>>> {
+    let fast_fields = segment_reader.fast_fields();
+    let values = match segment_reader.schema().get_field_entry(field).field_type() {
+        FieldType::U64(_) => {
+            let reader = fast_fields.u64(field)?;
+            doc_ids
+                .iter()
+                .map(|&doc_id| Some(Value::U64(reader.get(doc_id))))
+                .collect()
+        }
+        FieldType::I64(_) => {
+            let reader = fast_fields.i64(field)?;
+            doc_ids
+                .iter()
+                .map(|&doc_id| Some(Value::I64(reader.get(doc_id))))
+                .collect()
+        }
+        FieldType::F64(_) => {
+            let reader = fast_fields.f64(field)?;
+            doc_ids
+                .iter()
+                .map(|&doc_id| Some(Value::F64(reader.get(doc_id))))
+                .collect()
+        }
+        FieldType::Bool(_) => {
+            let reader = fast_fields.bool(field)?;
+            doc_ids
+                .iter()
+                .map(|&doc_id| Some(Value::Bool(reader.get(doc_id))))
+                .collect()
+        }
+        FieldType::Date(_) => {
+            let reader = fast_fields.date(field)?;
+            doc_ids
+                .iter()
+                .map(|&doc_id| Some(Value::Date(reader.get(doc_id))))
+                .collect()
+        }
+        field_type => {
+            anyhow::bail!(
+                "Cannot reconstruct fast field of type `{:?}`.",
+                field_type.value_type()
+            );
+        }
+    };
+    Ok(values)
+}
+
+/// Rebuilds the text of the field for each of the docs by laying out the terms of the field at
+/// their positions. This walks the whole term dictionary of the field.
+async fn text_values_from_positions(
+    segment_reader: &SegmentReader,
+    field: Field,
+    doc_ids: &[DocId],
+) -> anyhow::Result<Vec<Option<Value>>> {
+    let inverted_index = segment_reader.inverted_index(field)?;
+    let term_dict = inverted_index.terms();
+    term_dict.warm_up_dictionary().await?;
+    let mut terms = Vec::new();
+    let mut term_stream = term_dict.stream()?;
+    while term_stream.advance() {
+        let term = Term::from_field_bytes(field, term_stream.key());
+        terms.push((term, term_stream.value().clone()));
+    }
+    try_join_all(
+        terms
+            .iter()
+            .map(|(term, _)| inverted_index.warm_postings(term, true)),
+    )
+    .await?;
+    // The docs are visited in increasing doc ID order, as required by `DocSet::seek`.
+    let mut doc_ords: Vec<usize> = (0..doc_ids.len()).collect();
+    doc_ords.sort_by_key(|&doc_ord| doc_ids[doc_ord]);
+    let mut tokens_per_doc: Vec<Vec<(u32, String)>> = vec![Vec::new(); doc_ids.len()];
+    let mut positions = Vec::new();
+    for (term, term_info) in &terms {
+        let token = String::from_utf8_lossy(term.value_bytes()).to_string();
+        let mut postings = inverted_index
+            .read_postings_from_terminfo(term_info, IndexRecordOption::WithFreqsAndPositions)?;
+        for &doc_ord in &doc_ords {
+            let doc_id = doc_ids[doc_ord];
+            if postings.doc() > doc_id || postings.seek(doc_id) != doc_id {
+                continue;
+            }
+            postings.positions(&mut positions);
+            for &position in &positions {
+                tokens_per_doc[doc_ord].push((position, token.clone()));
+            }
+        }
+    }
+    let values = tokens_per_doc
+        .into_iter()
+        .map(|mut tokens| {
+            if tokens.is_empty() {
+                return None;
+            }
+            tokens.sort_by_key(|(position, _)| *position);
+            let text = tokens.into_iter().map(|(_, token)| token).join(" ");
+            Some(Value::Str(text))
+        })
+        .collect();
+    Ok(values)
+}
@@ -97,8 +97,7 @@ mod tests {
     #[test]
     fn test_should_not_retry_if_result_is_ok() {
         let retry_policy = DefaultRetryPolicy {};
-        let response_res =
-            crate::Result::<FetchDocsResponse>::Ok(FetchDocsResponse { hits: vec![] });
+        let response_res = crate::Result::<FetchDocsResponse>::Ok(FetchDocsResponse::default());
         assert!(retry_policy.retry_request((), &response_res).is_none());
     }
 
@@ -117,6 +116,8 @@ mod tests {
             split_id: "split_1".to_string(),
             split_footer_end: 100,
             split_footer_start: 0,
+            timestamp_start: None,
+            timestamp_end: None,
         };
         let client_for_retry = retry_client(
             &client_pool,
@@ -77,16 +77,21 @@ mod tests {
             }),
             doc_mapper: "doc_mapper".to_string(),
             index_uri: "uri".to_string(),
+            index_storage_config: None,
             split_offsets: vec![
                 SplitIdAndFooterOffsets {
                     split_id: "split_1".to_string(),
                     split_footer_end: 100,
                     split_footer_start: 0,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
                 SplitIdAndFooterOffsets {
                     split_id: "split_2".to_string(),
                     split_footer_end: 100,
                     split_footer_start: 0,
+                    timestamp_start: None,
+                    timestamp_end: None,
                 },
             ],
         }
@@ -22,23 +22,25 @@ use std::collections::{HashMap, HashSet};
 
 use futures::future::try_join_all;
 use itertools::Itertools;
-use quickwit_config::build_doc_mapper;
+use quickwit_config::{build_doc_mapper, get_searcher_config_instance, IndexStorageConfig};
+use quickwit_doc_mapper::compute_query_complexity;
 use quickwit_metastore::{Metastore, SplitMetadata};
 use quickwit_proto::{
     FetchDocsRequest, FetchDocsResponse, LeafSearchRequest, LeafSearchResponse, PartialHit,
     SearchRequest, SearchResponse, SplitIdAndFooterOffsets,
 };
 use tantivy::aggregation::agg_req::Aggregations;
-use tantivy::aggregation::agg_result::AggregationResults;
-use tantivy::aggregation::intermediate_agg_result::IntermediateAggregationResults;
 use tantivy::collector::Collector;
 use tantivy::TantivyError;
 use tokio::task::spawn_blocking;
 use tracing::{debug, error, instrument};
 
+use crate::aggregation_limits::finalize_aggregation_result;
 use crate::cluster_client::ClusterClient;
 use crate::collector::make_merge_collector;
+use crate::explain::{build_search_explanation, LeafSearchCosts};
 use crate::search_client_pool::Job;
+use crate::split_order::sort_splits;
 use crate::{
     extract_split_and_footer_offsets, list_relevant_splits, SearchClientPool, SearchError,
     SearchServiceClient,
@@ -132,6 +134,21 @@ fn validate_request(search_request: &SearchRequest) -> crate::Result<()> {
     Ok(())
 }
 
+/// Rejects the search request if its query is nested deeper than `max_query_depth`.
+pub(crate) fn validate_query_depth(
+    search_request: &SearchRequest,
+    max_query_depth: usize,
+) -> crate::Result<()> {
+    let query_complexity = compute_query_complexity(&search_request.query)?;
+    if query_complexity.depth > max_query_depth {
+        return Err(SearchError::InvalidQuery(format!(
+            "query depth {} exceeds the maximum query depth of {} (`max_query_depth`)",
+            query_complexity.depth, max_query_depth
+        )));
+    }
+    Ok(())
+}
+
 /// Performs a distributed search.
 /// 1. Sends leaf request over gRPC to multiple leaf nodes.
 /// 2. Merges the search results.
@@ -158,6 +175,10 @@ pub async fn root_search(
     })?;
 
     validate_request(search_request)?;
+    validate_query_depth(
+        search_request,
+        get_searcher_config_instance().max_query_depth,
+    )?;
 
     // try to build query against current schema
     let _query = doc_mapper.query(doc_mapper.schema(), search_request)?;
@@ -165,9 +186,11 @@ pub async fn root_search(
     let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
         SearchError::InternalError(format!("Failed to serialize doc mapper: Cause {}", err))
     })?;
+    let index_storage_config_str_opt =
+        serialize_index_storage_config(index_metadata.storage_config.as_ref())?;
 
     let split_metadatas: Vec<SplitMetadata> =
-        list_relevant_splits(search_request, metastore).await?;
+        list_relevant_splits(search_request, doc_mapper.timestamp_precision(), metastore).await?;
 
     let split_offsets_map: HashMap<String, SplitIdAndFooterOffsets> = split_metadatas
         .iter()
@@ -190,6 +213,7 @@ pub async fn root_search(
                     search_request,
                     &doc_mapper_str,
                     index_metadata.index_uri.as_ref(),
+                    index_storage_config_str_opt.as_deref(),
                     client_jobs,
                 );
                 cluster_client.leaf_search(leaf_request, client)
@@ -249,11 +273,16 @@ pub async fn root_search(
                     index_id: search_request.index_id.to_string(),
                     split_offsets,
                     index_uri: index_metadata.index_uri.to_string(),
+                    index_storage_config: index_storage_config_str_opt.clone(),
                 };
                 cluster_client.fetch_docs(fetch_docs_req, client)
             });
 
     let fetch_docs_resps: Vec<FetchDocsResponse> = try_join_all(fetch_docs_resp_futures).await?;
+    let fetch_docs_num_bytes_fetched: u64 = fetch_docs_resps
+        .iter()
+        .map(|fetch_docs_resp| fetch_docs_resp.num_bytes_fetched)
+        .sum();
 
     // Merge the fetched docs.
     let leaf_hits = fetch_docs_resps
@@ -261,7 +290,13 @@ pub async fn root_search(
         .flat_map(|response| response.hits.into_iter());
 
     let mut hits: Vec<quickwit_proto::Hit> = leaf_hits
-        .map(|leaf_hit: quickwit_proto::LeafHit| crate::convert_leaf_hit(leaf_hit, &*doc_mapper))
+        .map(|leaf_hit: quickwit_proto::LeafHit| {
+            crate::convert_leaf_hit(
+                leaf_hit,
+                &*doc_mapper,
+                search_request.exclude_dynamic_fields,
+            )
+        })
         .collect::<crate::Result<_>>()?;
 
     hits.sort_unstable_by_key(|hit| {
@@ -275,24 +310,48 @@ pub async fn root_search(
 
     let elapsed = start_instant.elapsed();
 
-    let aggregation = if let Some(intermediate_aggregation_result) =
-        leaf_search_response.intermediate_aggregation_result
-    {
-        let res: IntermediateAggregationResults =
-            serde_json::from_str(&intermediate_aggregation_result)?;
-        let req: Aggregations = serde_json::from_str(search_request.aggregation_request())?;
-        let res: AggregationResults = res.into_final_bucket_result(req)?;
-        Some(serde_json::to_string(&res)?)
-    } else {
-        None
-    };
+    let mut leaf_search_costs = LeafSearchCosts::from(&leaf_search_response);
+    leaf_search_costs.num_bytes_fetched += fetch_docs_num_bytes_fetched;
+    let final_aggregation_result_opt = leaf_search_response
+        .intermediate_aggregation_result
+        .map(|intermediate_aggregation_result| {
+            finalize_aggregation_result(
+                &intermediate_aggregation_result,
+                search_request.aggregation_request(),
+                get_searcher_config_instance(),
+            )
+        })
+        .transpose()?;
 
+    // Each leaf searches its own splits in the requested order. The selection order reported
+    // here is the order of the splits across all leaves.
+    let mut selected_splits: Vec<SplitIdAndFooterOffsets> = split_metadatas
+        .iter()
+        .map(extract_split_and_footer_offsets)
+        .collect();
+    sort_splits(&mut selected_splits, search_request.split_order());
+    let explanation = build_search_explanation(
+        search_request,
+        &leaf_search_costs,
+        hits.len(),
+        &selected_splits,
+        &*doc_mapper,
+    );
+
+    let aggregation_truncated = leaf_search_response.aggregation_truncated
+        || final_aggregation_result_opt
+            .as_ref()
+            .map(|final_aggregation_result| final_aggregation_result.truncated)
+            .unwrap_or(false);
     Ok(SearchResponse {
-        aggregation,
+        aggregation: final_aggregation_result_opt
+            .map(|final_aggregation_result| final_aggregation_result.aggregation_json),
         num_hits: leaf_search_response.num_hits,
         hits,
         elapsed_time_micros: elapsed.as_micros() as u64,
         errors: vec![],
+        explanation,
+        aggregation_truncated,
     })
 }
 
@@ -338,10 +397,28 @@ fn compute_split_cost(_split_metadata: &SplitMetadata) -> u32 {
     1
 }
 
+/// Serializes the storage config of an index, so that the leaves resolve the index storage with
+/// the same overrides as the root.
+pub(crate) fn serialize_index_storage_config(
+    storage_config_opt: Option<&IndexStorageConfig>,
+) -> crate::Result<Option<String>> {
+    storage_config_opt
+        .map(|storage_config| {
+            serde_json::to_string(storage_config).map_err(|err| {
+                SearchError::InternalError(format!(
+                    "Failed to serialize index storage config: Cause {}",
+                    err
+                ))
+            })
+        })
+        .transpose()
+}
+
 fn jobs_to_leaf_request(
     request: &SearchRequest,
     doc_mapper_str: &str,
     index_uri: &str,
+    index_storage_config_str_opt: Option<&str>,
     jobs: Vec<SearchJob>,
 ) -> LeafSearchRequest {
     let mut request_with_offset_0 = request.clone();
@@ -352,6 +429,7 @@ fn jobs_to_leaf_request(
         split_offsets: jobs.into_iter().map(|job| job.offsets).collect(),
         doc_mapper: doc_mapper_str.to_string(),
         index_uri: index_uri.to_string(),
+        index_storage_config: index_storage_config_str_opt.map(str::to_string),
     }
 }
 
@@ -444,6 +522,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -466,6 +545,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -528,6 +608,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -585,6 +666,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -604,6 +686,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -670,6 +753,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -712,6 +796,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -795,6 +880,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -836,6 +922,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -912,6 +999,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -1028,6 +1116,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -1111,6 +1200,7 @@ mod tests {
             |fetch_docs_req: quickwit_proto::FetchDocsRequest| {
                 Ok(quickwit_proto::FetchDocsResponse {
                     hits: get_doc_for_fetch_req(fetch_docs_req),
+                    ..Default::default()
                 })
             },
         );
@@ -0,0 +1,183 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tantivy::schema::{FieldEntry, FieldType, Schema};
+
+/// Indexing options of a field that change which documents match a query.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct FieldIndexingOptions {
+    /// Whether the field is indexed.
+    pub indexed: bool,
+    /// Whether the field is a fast field.
+    pub fast: bool,
+    /// Tokenizer of the field, for indexed text and JSON fields.
+    pub tokenizer: Option<String>,
+}
+
+impl From<&FieldEntry> for FieldIndexingOptions {
+    fn from(field_entry: &FieldEntry) -> Self {
+        let text_field_indexing_opt = match field_entry.field_type() {
+            FieldType::Str(text_options) => text_options.get_indexing_options(),
+            FieldType::JsonObject(json_options) => json_options.get_text_indexing_options(),
+            _ => None,
+        };
+        FieldIndexingOptions {
+            indexed: field_entry.is_indexed(),
+            fast: field_entry.is_fast(),
+            tokenizer: text_field_indexing_opt
+                .map(|text_field_indexing| text_field_indexing.tokenizer().to_string()),
+        }
+    }
+}
+
+/// Splits in which a field is indexed with the same options.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldIndexingVariant {
+    /// Indexing options of the field in these splits.
+    #[serde(flatten)]
+    pub options: FieldIndexingOptions,
+    /// Split IDs.
+    pub split_ids: Vec<String>,
+}
+
+/// Field indexed with different options across the searched splits, usually because the doc
+/// mapping of the index evolved. Searches on such a field may return inconsistent results.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldSchemaIncompatibility {
+    /// Field name.
+    pub field_name: String,
+    /// The different indexing options of the field, with the splits using them.
+    pub variants: Vec<FieldIndexingVariant>,
+}
+
+/// Compares the schemas of the splits and returns the fields whose indexing options differ,
+/// sorted by field name. Fields missing from some of the splits are not reported.
+pub(crate) fn find_schema_incompatibilities(
+    split_schemas: &[(String, Schema)],
+) -> Vec<FieldSchemaIncompatibility> {
+    let mut split_ids_per_options_per_field: BTreeMap<
+        &str,
+        BTreeMap<FieldIndexingOptions, Vec<String>>,
+    > = BTreeMap::new();
+    for (split_id, schema) in split_schemas {
+        for (_, field_entry) in schema.fields() {
+            split_ids_per_options_per_field
+                .entry(field_entry.name())
+                .or_default()
+                .entry(FieldIndexingOptions::from(field_entry))
+                .or_default()
+                .push(split_id.clone());
+        }
+    }
+    split_ids_per_options_per_field
+        .into_iter()
+        .filter(|(_, split_ids_per_options)| split_ids_per_options.len() > 1)
+        .map(
+            |(field_name, split_ids_per_options)| FieldSchemaIncompatibility {
+                field_name: field_name.to_string(),
+                variants: split_ids_per_options
+                    .into_iter()
+                    .map(|(options, split_ids)| FieldIndexingVariant { options, split_ids })
+                    .collect(),
+            },
+        )
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tantivy::schema::{Schema, TextFieldIndexing, TextOptions, FAST, INDEXED, STORED, TEXT};
+
+    use super::*;
+
+    #[test]
+    fn test_find_schema_incompatibilities() {
+        let mut old_schema_builder = Schema::builder();
+        old_schema_builder.add_text_field("body", TEXT);
+        old_schema_builder.add_u64_field("tenant_id", INDEXED);
+        old_schema_builder.add_text_field("title", TEXT);
+        let old_schema = old_schema_builder.build();
+
+        let mut new_schema_builder = Schema::builder();
+        let raw_text_options = TextOptions::default()
+            .set_indexing_options(TextFieldIndexing::default().set_tokenizer("raw"));
+        new_schema_builder.add_text_field("body", raw_text_options);
+        new_schema_builder.add_u64_field("tenant_id", INDEXED | FAST);
+        new_schema_builder.add_text_field("title", TEXT | STORED);
+        new_schema_builder.add_i64_field("timestamp", FAST);
+        let new_schema = new_schema_builder.build();
+
+        let split_schemas = vec![
+            ("split1".to_string(), old_schema.clone()),
+            ("split2".to_string(), new_schema),
+            ("split3".to_string(), old_schema),
+        ];
+        let incompatibilities = find_schema_incompatibilities(&split_schemas);
+        assert_eq!(
+            incompatibilities,
+            vec![
+                FieldSchemaIncompatibility {
+                    field_name: "body".to_string(),
+                    variants: vec![
+                        FieldIndexingVariant {
+                            options: FieldIndexingOptions {
+                                indexed: true,
+                                fast: false,
+                                tokenizer: Some("default".to_string()),
+                            },
+                            split_ids: vec!["split1".to_string(), "split3".to_string()],
+                        },
+                        FieldIndexingVariant {
+                            options: FieldIndexingOptions {
+                                indexed: true,
+                                fast: false,
+                                tokenizer: Some("raw".to_string()),
+                            },
+                            split_ids: vec!["split2".to_string()],
+                        },
+                    ],
+                },
+                FieldSchemaIncompatibility {
+                    field_name: "tenant_id".to_string(),
+                    variants: vec![
+                        FieldIndexingVariant {
+                            options: FieldIndexingOptions {
+                                indexed: true,
+                                fast: false,
+                                tokenizer: None,
+                            },
+                            split_ids: vec!["split1".to_string(), "split3".to_string()],
+                        },
+                        FieldIndexingVariant {
+                            options: FieldIndexingOptions {
+                                indexed: true,
+                                fast: true,
+                                tokenizer: None,
+                            },
+                            split_ids: vec!["split2".to_string()],
+                        },
+                    ],
+                },
+            ]
+        );
+    }
+}
@@ -19,6 +19,7 @@
 
 use std::convert::TryFrom;
 
+use quickwit_proto::SearchExplanation;
 use serde::Serialize;
 
 use crate::error::SearchError;
@@ -31,6 +32,9 @@ pub struct SearchResponseRest {
     pub num_hits: u64,
     /// List of hits returned.
     pub hits: Vec<serde_json::Value>,
+    /// Highlighted snippets of the hits, in the same order, if snippet fields were requested.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub snippets: Vec<serde_json::Value>,
     /// Elapsed time.
     pub elapsed_time_micros: u64,
     /// Search errors.
@@ -38,12 +42,43 @@ pub struct SearchResponseRest {
     /// Aggregations.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub aggregations: Option<serde_json::Value>,
+    /// Whether aggregation buckets were dropped to honor `max_aggregation_buckets`.
+    #[serde(skip_serializing_if = "is_false")]
+    pub aggregations_truncated: bool,
+    /// Search explanation.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<SearchExplanation>,
+}
+
+fn is_false(val: &bool) -> bool {
+    !*val
 }
 
 impl TryFrom<quickwit_proto::SearchResponse> for SearchResponseRest {
     type Error = SearchError;
 
     fn try_from(search_response: quickwit_proto::SearchResponse) -> Result<Self, Self::Error> {
+        let snippets = if search_response
+            .hits
+            .iter()
+            .any(|hit| hit.snippet_json.is_some())
+        {
+            search_response
+                .hits
+                .iter()
+                .map(|hit| match &hit.snippet_json {
+                    Some(snippet_json) => serde_json::from_str(snippet_json).map_err(|err| {
+                        SearchError::InternalError(format!(
+                            "Failed to serialize snippets `{}` to JSON: `{}`.",
+                            snippet_json, err
+                        ))
+                    }),
+                    None => Ok(serde_json::json!({})),
+                })
+                .collect::<crate::Result<Vec<serde_json::Value>>>()?
+        } else {
+            Vec::new()
+        };
         let hits = search_response
             .hits
             .into_iter()
@@ -59,6 +94,7 @@ impl TryFrom<quickwit_proto::SearchResponse> for SearchResponseRest {
         Ok(SearchResponseRest {
             num_hits: search_response.num_hits,
             hits,
+            snippets,
             elapsed_time_micros: search_response.elapsed_time_micros,
             errors: search_response.errors,
             aggregations: search_response
@@ -66,6 +102,8 @@ impl TryFrom<quickwit_proto::SearchResponse> for SearchResponseRest {
                 .map(|agg| serde_json::from_str(&agg))
                 .transpose()
                 .map_err(|err| SearchError::InternalError(err.to_string()))?,
+            aggregations_truncated: search_response.aggregation_truncated,
+            explanation: search_response.explanation,
         })
     }
 }
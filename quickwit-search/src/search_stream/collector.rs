@@ -121,11 +121,46 @@ pub struct PartionnedFastFieldCollector<Item: FastValue, PartitionItem: FastValu
 }
 
 #[derive(Debug, PartialEq, Eq)]
-pub struct PartitionValues<Item: FastValue, PartitionItem: FastValue> {
+pub struct PartitionValues<Item: FastValue, PartitionItem> {
     pub partition_value: PartitionItem,
     pub fast_field_values: Vec<Item>,
 }
 
+/// Partition key made of the values of several partition-by fast fields, in the order in which
+/// the fields were requested. Each value is the `u64` representation of the fast field value.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CompositePartitionKey(pub Vec<u64>);
+
+/// A partition key as written in the partitioned `ClickHouseRowBinary` output: a sequence of
+/// little-endian `u64`.
+pub trait PartitionKey {
+    fn num_bytes(&self) -> usize;
+
+    fn write_le_bytes(&self, buffer: &mut Vec<u8>);
+}
+
+impl<T: FastValue> PartitionKey for T {
+    fn num_bytes(&self) -> usize {
+        std::mem::size_of::<T>()
+    }
+
+    fn write_le_bytes(&self, buffer: &mut Vec<u8>) {
+        buffer.extend(self.as_u64().to_le_bytes());
+    }
+}
+
+impl PartitionKey for CompositePartitionKey {
+    fn num_bytes(&self) -> usize {
+        std::mem::size_of::<u64>() * self.0.len()
+    }
+
+    fn write_le_bytes(&self, buffer: &mut Vec<u8>) {
+        for value in &self.0 {
+            buffer.extend(value.to_le_bytes());
+        }
+    }
+}
+
 impl<Item: FastValue, PartitionItem: FastValue + Eq + Hash> Collector
     for PartionnedFastFieldCollector<Item, PartitionItem>
 {
@@ -237,6 +272,124 @@ impl<Item: FastValue, PartitionItem: FastValue + Hash + Eq> SegmentCollector
     }
 }
 
+#[derive(Clone)]
+pub struct CompositePartitionedFastFieldCollector<Item: FastValue> {
+    pub fast_field_to_collect: String,
+    pub partition_by_fast_fields: Vec<String>,
+    pub timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
+    pub _marker: PhantomData<Item>,
+}
+
+impl<Item: FastValue> Collector for CompositePartitionedFastFieldCollector<Item> {
+    type Child = CompositePartitionedFastFieldSegmentCollector<Item>;
+    type Fruit = Vec<PartitionValues<Item, CompositePartitionKey>>;
+
+    fn for_segment(
+        &self,
+        _segment_ord: SegmentOrdinal,
+        segment_reader: &SegmentReader,
+    ) -> tantivy::Result<Self::Child> {
+        let timestamp_filter_opt =
+            if let Some(timestamp_filter_builder) = &self.timestamp_filter_builder_opt {
+                timestamp_filter_builder.build(segment_reader)?
+            } else {
+                None
+            };
+        let fast_field_reader =
+            helpers::make_fast_field_reader::<Item>(segment_reader, &self.fast_field_to_collect)?;
+
+        // Partition fields are read as raw `u64` so that fields of different types can be
+        // mixed in the same key.
+        let partition_by_fast_field_readers = self
+            .partition_by_fast_fields
+            .iter()
+            .map(|partition_by_fast_field| {
+                helpers::make_fast_field_reader::<u64>(segment_reader, partition_by_fast_field)
+            })
+            .collect::<tantivy::Result<Vec<_>>>()?;
+
+        Ok(CompositePartitionedFastFieldSegmentCollector::new(
+            fast_field_reader,
+            partition_by_fast_field_readers,
+            timestamp_filter_opt,
+        ))
+    }
+
+    fn requires_scoring(&self) -> bool {
+        // We do not need BM25 scoring in Quickwit.
+        false
+    }
+
+    fn merge_fruits(
+        &self,
+        segment_fruits: Vec<HashMap<CompositePartitionKey, Vec<Item>>>,
+    ) -> tantivy::Result<Self::Fruit> {
+        Ok(segment_fruits
+            .into_iter()
+            .flat_map(|e| e.into_iter())
+            .map(|(partition_value, values)| PartitionValues {
+                partition_value,
+                fast_field_values: values,
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone)]
+pub struct CompositePartitionedFastFieldSegmentCollector<Item: FastValue> {
+    fast_field_values: HashMap<CompositePartitionKey, Vec<Item>>,
+    fast_field_reader: DynamicFastFieldReader<Item>,
+    partition_by_fast_field_readers: Vec<DynamicFastFieldReader<u64>>,
+    timestamp_filter_opt: Option<TimestampFilter>,
+}
+
+impl<Item: FastValue> CompositePartitionedFastFieldSegmentCollector<Item> {
+    pub fn new(
+        fast_field_reader: DynamicFastFieldReader<Item>,
+        partition_by_fast_field_readers: Vec<DynamicFastFieldReader<u64>>,
+        timestamp_filter_opt: Option<TimestampFilter>,
+    ) -> Self {
+        Self {
+            fast_field_values: HashMap::default(),
+            fast_field_reader,
+            partition_by_fast_field_readers,
+            timestamp_filter_opt,
+        }
+    }
+
+    fn accept_document(&self, doc_id: DocId) -> bool {
+        if let Some(ref timestamp_filter) = self.timestamp_filter_opt {
+            return timestamp_filter.is_within_range(doc_id);
+        }
+        true
+    }
+}
+
+impl<Item: FastValue> SegmentCollector for CompositePartitionedFastFieldSegmentCollector<Item> {
+    type Fruit = HashMap<CompositePartitionKey, Vec<Item>>;
+
+    fn collect(&mut self, doc_id: DocId, _score: Score) {
+        if !self.accept_document(doc_id) {
+            return;
+        }
+        let fast_field_value = self.fast_field_reader.get(doc_id);
+        let partition_key = CompositePartitionKey(
+            self.partition_by_fast_field_readers
+                .iter()
+                .map(|reader| reader.get(doc_id))
+                .collect(),
+        );
+        self.fast_field_values
+            .entry(partition_key)
+            .or_default()
+            .push(fast_field_value);
+    }
+
+    fn harvest(self) -> Self::Fruit {
+        self.fast_field_values
+    }
+}
+
 mod helpers {
     use super::*;
 
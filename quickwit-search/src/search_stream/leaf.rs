@@ -24,7 +24,7 @@ use std::sync::Arc;
 
 use futures::{FutureExt, StreamExt};
 use once_cell::sync::OnceCell;
-use quickwit_config::get_searcher_config_instance;
+use quickwit_config::{get_searcher_config_instance, SearcherConfig};
 use quickwit_doc_mapper::DocMapper;
 use quickwit_proto::{
     LeafSearchStreamResponse, OutputFormat, SearchRequest, SearchStreamRequest,
@@ -39,24 +39,40 @@ use tokio::sync::{Semaphore, SemaphorePermit};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::*;
 
-use super::collector::{PartionnedFastFieldCollector, PartitionValues};
+use super::collector::{
+    CompositePartitionKey, CompositePartitionedFastFieldCollector, PartionnedFastFieldCollector,
+    PartitionValues,
+};
 use super::FastFieldCollector;
+use crate::fetch_plan::SplitFetchPlan;
 use crate::filters::TimestampFilterBuilder;
-use crate::leaf::{open_index, warmup};
+use crate::leaf::{acquire_open_split_permit, open_index, open_split_semaphore, warmup};
+use crate::split_order::sort_splits;
 use crate::{Result, SearchError};
 
 fn get_max_num_concurrent_split_streams() -> usize {
     get_searcher_config_instance().max_num_concurrent_split_streams
 }
 
-async fn get_split_stream_permit() -> SemaphorePermit<'static> {
+/// Builds the semaphore bounding the number of split streams running at the same time on the
+/// searcher, across all the search stream requests. It is independent from the semaphore bounding
+/// the leaf searches.
+fn split_stream_semaphore_from_config(searcher_config: &SearcherConfig) -> Semaphore {
+    Semaphore::new(searcher_config.max_num_concurrent_split_streams)
+}
+
+/// Returns the split stream semaphore of the searcher.
+///
+/// The semaphore is built on first use from the searcher config installed in
+/// `SEARCHER_CONFIG_INSTANCE`, so that both always agree. As a consequence, the searcher
+/// config must be installed before the first search stream is run.
+fn split_stream_semaphore() -> &'static Semaphore {
     static INSTANCE: OnceCell<Semaphore> = OnceCell::new();
-    INSTANCE
-        .get_or_init(|| {
-            let max_num_concurrent_split_streams =
-                get_max_num_concurrent_split_streams();
-            Semaphore::new(max_num_concurrent_split_streams)
-        })
+    INSTANCE.get_or_init(|| split_stream_semaphore_from_config(get_searcher_config_instance()))
+}
+
+async fn get_split_stream_permit() -> SemaphorePermit<'static> {
+    split_stream_semaphore()
         .acquire()
         .await
         .expect("Failed to acquire permit. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
@@ -98,10 +114,15 @@ pub async fn leaf_search_stream(
 async fn leaf_search_results_stream(
     request: SearchStreamRequest,
     storage: Arc<dyn Storage>,
-    splits: Vec<SplitIdAndFooterOffsets>,
+    mut splits: Vec<SplitIdAndFooterOffsets>,
     doc_mapper: Arc<dyn DocMapper>,
 ) -> impl futures::Stream<Item = crate::Result<LeafSearchStreamResponse>> + Sync + Send + 'static {
+    // `buffer_unordered` only bounds the number of split streams polled concurrently for this
+    // request. The actual concurrency across all the requests of the searcher is bounded by the
+    // split stream semaphore, whose permit is acquired in `leaf_search_stream_single_split`: both
+    // use the same limit, so a single request never holds more permits than the searcher allows.
     let max_num_concurrent_split_streams = get_max_num_concurrent_split_streams();
+    sort_splits(&mut splits, request.split_order());
     futures::stream::iter(splits)
         .map(move |split| {
             leaf_search_stream_single_split(
@@ -124,6 +145,7 @@ async fn leaf_search_stream_single_split(
     storage: Arc<dyn Storage>,
 ) -> crate::Result<LeafSearchStreamResponse> {
     let _leaf_split_stream_permit = get_split_stream_permit().await;
+    let _open_split_permit = acquire_open_split_permit(open_split_semaphore()).await;
 
     let index = open_index(storage, &split).await?;
     let split_schema = index.schema();
@@ -138,7 +160,7 @@ async fn leaf_search_stream_single_split(
         SearchError::InternalError("Invalid output format specified.".to_string())
     })?;
 
-    if request_fields.partition_by_fast_field.is_some()
+    if !request_fields.partition_by_fast_fields.is_empty()
         && output_format != OutputFormat::ClickHouseRowBinary
     {
         return Err(SearchError::InternalError(
@@ -183,8 +205,9 @@ async fn leaf_search_stream_single_split(
     let m_request_fields = request_fields.clone();
     let collect_handle = crate::run_cpu_intensive(move || {
         let mut buffer = Vec::new();
-        match m_request_fields.fast_field_types() {
-            (Type::I64, None) => {
+        let (fast_field_type, partition_fast_field_types) = m_request_fields.fast_field_types();
+        match (fast_field_type, partition_fast_field_types.as_slice()) {
+            (Type::I64, []) => {
                 let collected_values = collect_values::<i64>(
                     &m_request_fields,
                     timestamp_filter_builder_opt,
@@ -199,7 +222,7 @@ async fn leaf_search_stream_single_split(
                     },
                 )?;
             }
-            (Type::U64, None) => {
+            (Type::U64, []) => {
                 let collected_values = collect_values::<u64>(
                     &m_request_fields,
                     timestamp_filter_builder_opt,
@@ -214,7 +237,22 @@ async fn leaf_search_stream_single_split(
                     },
                 )?;
             }
-            (Type::Date, None) => {
+            (Type::F64, []) => {
+                let collected_values = collect_values::<f64>(
+                    &m_request_fields,
+                    timestamp_filter_builder_opt,
+                    &searcher,
+                    &query,
+                )?;
+                super::serialize::<f64>(&collected_values, &mut buffer, output_format).map_err(
+                    |_| {
+                        SearchError::InternalError(
+                            "Error when serializing f64 during export".to_owned(),
+                        )
+                    },
+                )?;
+            }
+            (Type::Date, []) => {
                 let collected_values = collect_values::<i64>(
                     &m_request_fields,
                     timestamp_filter_builder_opt,
@@ -229,7 +267,7 @@ async fn leaf_search_stream_single_split(
                     },
                 )?;
             }
-            (Type::I64, Some(Type::I64)) => {
+            (Type::I64, [Type::I64]) => {
                 let collected_values = collect_partitioned_values::<i64, i64>(
                     &m_request_fields,
                     timestamp_filter_builder_opt,
@@ -243,7 +281,7 @@ async fn leaf_search_stream_single_split(
                     )
                 })?;
             }
-            (Type::U64, Some(Type::U64)) => {
+            (Type::U64, [Type::U64]) => {
                 let collected_values = collect_partitioned_values::<u64, u64>(
                     &m_request_fields,
                     timestamp_filter_builder_opt,
@@ -257,17 +295,98 @@ async fn leaf_search_stream_single_split(
                     )
                 })?;
             }
-            (fast_field_type, None) => {
+            (Type::F64, [Type::I64]) => {
+                let collected_values = collect_partitioned_values::<f64, i64>(
+                    &m_request_fields,
+                    timestamp_filter_builder_opt,
+                    &searcher,
+                    &query,
+                )?;
+                super::serialize_partitions::<f64, i64>(collected_values.as_slice(), &mut buffer)
+                    .map_err(|_| {
+                    SearchError::InternalError(
+                        "Error when serializing f64 during export".to_owned(),
+                    )
+                })?;
+            }
+            (Type::F64, [Type::U64]) => {
+                let collected_values = collect_partitioned_values::<f64, u64>(
+                    &m_request_fields,
+                    timestamp_filter_builder_opt,
+                    &searcher,
+                    &query,
+                )?;
+                super::serialize_partitions::<f64, u64>(collected_values.as_slice(), &mut buffer)
+                    .map_err(|_| {
+                    SearchError::InternalError(
+                        "Error when serializing f64 during export".to_owned(),
+                    )
+                })?;
+            }
+            (Type::I64, partition_fast_field_types)
+                if is_composite_partition_key(partition_fast_field_types) =>
+            {
+                let collected_values = collect_composite_partitioned_values::<i64>(
+                    &m_request_fields,
+                    timestamp_filter_builder_opt,
+                    &searcher,
+                    &query,
+                )?;
+                super::serialize_partitions(collected_values.as_slice(), &mut buffer).map_err(
+                    |_| {
+                        SearchError::InternalError(
+                            "Error when serializing i64 during export".to_owned(),
+                        )
+                    },
+                )?;
+            }
+            (Type::U64, partition_fast_field_types)
+                if is_composite_partition_key(partition_fast_field_types) =>
+            {
+                let collected_values = collect_composite_partitioned_values::<u64>(
+                    &m_request_fields,
+                    timestamp_filter_builder_opt,
+                    &searcher,
+                    &query,
+                )?;
+                super::serialize_partitions(collected_values.as_slice(), &mut buffer).map_err(
+                    |_| {
+                        SearchError::InternalError(
+                            "Error when serializing u64 during export".to_owned(),
+                        )
+                    },
+                )?;
+            }
+            (Type::F64, partition_fast_field_types)
+                if is_composite_partition_key(partition_fast_field_types) =>
+            {
+                let collected_values = collect_composite_partitioned_values::<f64>(
+                    &m_request_fields,
+                    timestamp_filter_builder_opt,
+                    &searcher,
+                    &query,
+                )?;
+                super::serialize_partitions(collected_values.as_slice(), &mut buffer).map_err(
+                    |_| {
+                        SearchError::InternalError(
+                            "Error when serializing f64 during export".to_owned(),
+                        )
+                    },
+                )?;
+            }
+            (fast_field_type, []) => {
                 return Err(SearchError::InternalError(format!(
-                    "Search stream does not support fast field of type `{:?}`.",
+                    "Search stream does not support fast field of type `{:?}`. The `Csv`, \
+                     `ClickHouseRowBinary`, and `ClickHouseRowBinaryWithNamesAndTypes` output \
+                     formats support i64, u64, f64, and date fast fields.",
                     fast_field_type
                 )));
             }
-            (fast_field_type, Some(partition_fast_field_type)) => {
+            (fast_field_type, partition_fast_field_types) => {
                 return Err(SearchError::InternalError(format!(
                     "Search stream does not support the combination of fast field type `{:?}` and \
-                     partition fast field type `{:?}`.",
-                    fast_field_type, partition_fast_field_type
+                     partition fast field types `{:?}`.",
+                    fast_field_type, partition_fast_field_types
                 )));
             }
         };
@@ -307,7 +426,8 @@ fn collect_partitioned_values<TFastValue: FastValue, TPartitionValue: FastValue
     let collector = PartionnedFastFieldCollector::<TFastValue, TPartitionValue> {
         fast_field_to_collect: request_fields.fast_field_name().to_string(),
         partition_by_fast_field: request_fields
-            .partition_by_fast_field_name()
+            .partition_by_fast_field_names()
+            .first()
             .expect("`partition_by_fast_field` is not defined. This should never happen! Please, report on https://github.com/quickwit-oss/quickwit/issues.")
             .to_string(),
         timestamp_filter_builder_opt,
@@ -317,11 +437,77 @@ fn collect_partitioned_values<TFastValue: FastValue, TPartitionValue: FastValue
     Ok(result)
 }
 
+/// Returns true if the partition fast fields form a composite key, i.e. there is more than one
+/// of them and they are all of a type supported in a partition key.
+fn is_composite_partition_key(partition_fast_field_types: &[Type]) -> bool {
+    partition_fast_field_types.len() > 1
+        && partition_fast_field_types
+            .iter()
+            .all(|field_type| matches!(field_type, Type::I64 | Type::U64))
+}
+
+fn collect_composite_partitioned_values<TFastValue: FastValue>(
+    request_fields: &SearchStreamRequestFields,
+    timestamp_filter_builder_opt: Option<TimestampFilterBuilder>,
+    searcher: &Searcher,
+    query: &dyn Query,
+) -> crate::Result<Vec<PartitionValues<TFastValue, CompositePartitionKey>>> {
+    let collector = CompositePartitionedFastFieldCollector::<TFastValue> {
+        fast_field_to_collect: request_fields.fast_field_name().to_string(),
+        partition_by_fast_fields: request_fields
+            .partition_by_fast_field_names()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect(),
+        timestamp_filter_builder_opt,
+        _marker: PhantomData,
+    };
+    let result = searcher.search(query, &collector)?;
+    Ok(result)
+}
+
+/// Plans the storage fetches of the leaf search stream of `stream_request` on
+/// each of the `splits`, without fetching anything.
+pub fn plan_leaf_search_stream_fetches(
+    stream_request: &SearchStreamRequest,
+    doc_mapper: &dyn DocMapper,
+    splits: &[SplitIdAndFooterOffsets],
+) -> crate::Result<Vec<SplitFetchPlan>> {
+    let schema = doc_mapper.schema();
+    let request_fields =
+        SearchStreamRequestFields::from_request(stream_request, &schema, doc_mapper)?;
+    let search_request = SearchRequest::from(stream_request.clone());
+    let query = doc_mapper.query(schema.clone(), &search_request)?;
+    let timestamp_filter_builder_opt: Option<TimestampFilterBuilder> = TimestampFilterBuilder::new(
+        request_fields
+            .timestamp_field_name()
+            .map(ToString::to_string),
+        request_fields.timestamp_field,
+        search_request.start_timestamp,
+        search_request.end_timestamp,
+    );
+    let fast_field_names =
+        request_fields.fast_fields_for_request(timestamp_filter_builder_opt.as_ref());
+    let fetch_plans = splits
+        .iter()
+        .map(|split| {
+            SplitFetchPlan::new(
+                split,
+                &schema,
+                query.as_ref(),
+                fast_field_names.clone(),
+                HashSet::new(),
+            )
+        })
+        .collect();
+    Ok(fetch_plans)
+}
+
 #[derive(Debug)]
 // TODO move to owned values, implement Send + Sync
 struct SearchStreamRequestFields {
     fast_field: Field,
-    partition_by_fast_field: Option<Field>,
+    partition_by_fast_fields: Vec<Field>,
     timestamp_field: Option<Field>,
     schema: Schema,
 }
@@ -334,10 +520,15 @@ impl std::fmt::Display for SearchStreamRequestFields {
             "timestamp_field: {},",
             self.timestamp_field_name().unwrap_or("None")
         )?;
+        let partition_by_fast_field_names = self.partition_by_fast_field_names();
         write!(
             f,
-            "partition_by_fast_field: {}",
-            self.partition_by_fast_field_name().unwrap_or("None")
+            "partition_by_fast_fields: {}",
+            if partition_by_fast_field_names.is_empty() {
+                "None".to_string()
+            } else {
+                partition_by_fast_field_names.join(",")
+            }
         )
     }
 }
@@ -365,36 +556,66 @@ impl<'a> SearchStreamRequestFields {
         }
 
         let timestamp_field = doc_mapper.timestamp_field(schema);
-        let partition_by_fast_field = stream_request
-            .partition_by_field
-            .as_deref()
-            .and_then(|field_name| schema.get_field(field_name));
 
-        if partition_by_fast_field.is_some()
-            && !Self::is_fast_field(schema, &partition_by_fast_field.unwrap())
+        if stream_request.partition_by_field.is_some()
+            && !stream_request.partition_by_fields.is_empty()
         {
-            return Err(SearchError::InvalidQuery(format!(
-                "Field `{}` is not a fast field",
-                &stream_request.partition_by_field.as_deref().unwrap()
-            )));
+            return Err(SearchError::InvalidQuery(
+                "`partition_by_field` and `partition_by_fields` cannot be both set.".to_string(),
+            ));
+        }
+        let mut partition_by_fast_fields = Vec::new();
+        if let Some(partition_by_field_name) = stream_request.partition_by_field.as_deref() {
+            if let Some(partition_by_fast_field) = schema.get_field(partition_by_field_name) {
+                if !Self::is_fast_field(schema, &partition_by_fast_field) {
+                    return Err(SearchError::InvalidQuery(format!(
+                        "Field `{}` is not a fast field",
+                        partition_by_field_name
+                    )));
+                }
+                partition_by_fast_fields.push(partition_by_fast_field);
+            }
+        }
+        for partition_by_field_name in &stream_request.partition_by_fields {
+            let partition_by_fast_field =
+                schema.get_field(partition_by_field_name).ok_or_else(|| {
+                    SearchError::InvalidQuery(format!(
+                        "Field `{}` does not exist in schema",
+                        partition_by_field_name
+                    ))
+                })?;
+            if !Self::is_fast_field(schema, &partition_by_fast_field) {
+                return Err(SearchError::InvalidQuery(format!(
+                    "Field `{}` is not a fast field",
+                    partition_by_field_name
+                )));
+            }
+            partition_by_fast_fields.push(partition_by_fast_field);
         }
 
         Ok(SearchStreamRequestFields {
             schema: schema.to_owned(),
             fast_field,
-            partition_by_fast_field,
+            partition_by_fast_fields,
             timestamp_field,
         })
     }
 
-    pub fn fast_field_types(&self) -> (Type, Option<Type>) {
+    pub fn fast_field_types(&self) -> (Type, Vec<Type>) {
         (
             self.schema
                 .get_field_entry(self.fast_field)
                 .field_type()
                 .value_type(),
-            self.partition_by_fast_field
-                .map(|field| self.schema.get_field_entry(field).field_type().value_type()),
+            self.partition_by_fast_fields
+                .iter()
+                .map(|field| {
+                    self.schema
+                        .get_field_entry(*field)
+                        .field_type()
+                        .value_type()
+                })
+                .collect(),
         )
     }
 
@@ -407,7 +628,7 @@ impl<'a> SearchStreamRequestFields {
         if let Some(timestamp_filter_builder) = timestamp_filter_builder_opt {
             set.insert(timestamp_filter_builder.timestamp_field_name.clone());
         }
-        if let Some(partition_by_fast_field) = self.partition_by_fast_field_name() {
+        for partition_by_fast_field in self.partition_by_fast_field_names() {
             set.insert(partition_by_fast_field.to_string());
         }
         set
@@ -422,9 +643,11 @@ impl<'a> SearchStreamRequestFields {
         self.schema.get_field_name(self.fast_field)
     }
 
-    pub fn partition_by_fast_field_name(&self) -> Option<&str> {
-        self.partition_by_fast_field
-            .map(|field| self.schema.get_field_name(field))
+    pub fn partition_by_fast_field_names(&self) -> Vec<&str> {
+        self.partition_by_fast_fields
+            .iter()
+            .map(|field| self.schema.get_field_name(*field))
+            .collect()
     }
 
     fn is_fast_field(schema: &Schema, field: &Field) -> bool {
@@ -438,6 +661,7 @@ mod tests {
     use std::convert::TryInto;
     use std::str::from_utf8;
 
+    use quickwit_doc_mapper::DefaultDocMapper;
     use quickwit_indexing::TestSandbox;
     use serde_json::json;
     use tantivy::time::{Duration, OffsetDateTime};
@@ -487,6 +711,8 @@ mod tests {
             fast_field: "ts".to_string(),
             output_format: 0,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
         let splits_offsets = splits
@@ -495,6 +721,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
@@ -512,6 +740,158 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_split_stream_semaphore_from_config() {
+        let searcher_config = SearcherConfig {
+            max_num_concurrent_split_streams: 7,
+            ..Default::default()
+        };
+        let semaphore = split_stream_semaphore_from_config(&searcher_config);
+        assert_eq!(semaphore.available_permits(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_split_stream_semaphore_matches_searcher_config() {
+        let max_num_concurrent_split_streams =
+            get_searcher_config_instance().max_num_concurrent_split_streams;
+        let _permit = get_split_stream_permit().await;
+        assert!(split_stream_semaphore().available_permits() < max_num_concurrent_split_streams);
+    }
+
+    #[test]
+    fn test_plan_leaf_search_stream_fetches() -> anyhow::Result<()> {
+        let doc_mapper: DefaultDocMapper = serde_json::from_value(json!({
+            "field_mappings": [
+                { "name": "body", "type": "text" },
+                { "name": "ts", "type": "i64", "fast": true },
+                { "name": "tenant_id", "type": "u64", "fast": true },
+                { "name": "value", "type": "u64", "fast": true }
+            ],
+            "timestamp_field": "ts"
+        }))?;
+        let request = SearchStreamRequest {
+            index_id: "test-index".to_string(),
+            query: "body:info".to_string(),
+            search_fields: vec![],
+            start_timestamp: None,
+            end_timestamp: Some(20),
+            fast_field: "value".to_string(),
+            output_format: OutputFormat::ClickHouseRowBinary as i32,
+            partition_by_field: Some("tenant_id".to_string()),
+            split_order: 0,
+            partition_by_fields: vec![],
+        };
+        let splits = vec![
+            SplitIdAndFooterOffsets {
+                split_id: "split1".to_string(),
+                split_footer_start: 10,
+                split_footer_end: 20,
+                timestamp_start: None,
+                timestamp_end: None,
+            },
+            SplitIdAndFooterOffsets {
+                split_id: "split2".to_string(),
+                split_footer_start: 30,
+                split_footer_end: 45,
+                timestamp_start: None,
+                timestamp_end: None,
+            },
+        ];
+        let fetch_plans = plan_leaf_search_stream_fetches(&request, &doc_mapper, &splits)?;
+        assert_eq!(fetch_plans.len(), 2);
+        assert_eq!(fetch_plans[0].split_id, "split1");
+        assert_eq!(fetch_plans[0].footer_byte_range, 10..20);
+        assert_eq!(fetch_plans[1].split_id, "split2");
+        assert_eq!(fetch_plans[1].footer_byte_range, 30..45);
+        for fetch_plan in &fetch_plans {
+            let fast_field_names: Vec<&str> = fetch_plan
+                .fast_field_names
+                .iter()
+                .map(String::as_str)
+                .collect();
+            assert_eq!(fast_field_names, ["tenant_id", "ts", "value"]);
+            assert!(fetch_plan.term_dict_field_names.is_empty());
+            assert_eq!(fetch_plan.num_query_terms_per_field.get("body"), Some(&1));
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_leaf_search_stream_to_csv_output_with_f64_fast_field() -> anyhow::Result<()> {
+        let index_id = "single-node-simple-f64";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: i64
+                fast: true
+              - name: price
+                type: f64
+                fast: true
+        "#;
+        let indexing_settings_yaml = r#"
+            timestamp_field: ts
+        "#;
+        let test_sandbox = TestSandbox::create(
+            index_id,
+            doc_mapping_yaml,
+            indexing_settings_yaml,
+            &["body"],
+        )
+        .await?;
+
+        let mut docs = vec![];
+        let mut filtered_price_values = vec![];
+        let end_timestamp = 20;
+        for i in 0..30 {
+            let body = format!("info @ t:{}", i + 1);
+            let price = i as f64 + 0.5;
+            docs.push(json!({"body": body, "ts": i+1, "price": price}));
+            if i + 1 < end_timestamp {
+                filtered_price_values.push(price.to_string());
+            }
+        }
+        test_sandbox.add_documents(docs).await?;
+
+        let request = SearchStreamRequest {
+            index_id: index_id.to_string(),
+            query: "info".to_string(),
+            search_fields: vec![],
+            start_timestamp: None,
+            end_timestamp: Some(end_timestamp),
+            fast_field: "price".to_string(),
+            output_format: 0,
+            partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
+        };
+        let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
+        let splits_offsets = splits
+            .into_iter()
+            .map(|split_meta| SplitIdAndFooterOffsets {
+                split_id: split_meta.split_id().to_string(),
+                split_footer_start: split_meta.split_metadata.footer_offsets.start,
+                split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
+            })
+            .collect();
+        let mut single_node_stream = leaf_search_stream(
+            request,
+            test_sandbox.storage(),
+            splits_offsets,
+            test_sandbox.doc_mapper(),
+        )
+        .await;
+        let res = single_node_stream.next().await.expect("no leaf result")?;
+        assert_eq!(
+            from_utf8(&res.data)?,
+            format!("{}\n", filtered_price_values.join("\n"))
+        );
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_leaf_search_stream_filtering_with_datetime() -> anyhow::Result<()> {
         let index_id = "single-node-simple-datetime";
@@ -563,6 +943,8 @@ mod tests {
             fast_field: "ts".to_string(),
             output_format: 0,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
         let splits_offsets = splits
@@ -571,6 +953,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
@@ -616,6 +1000,8 @@ mod tests {
             fast_field: "app".to_string(),
             output_format: 0,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
         let splits_offsets = splits
@@ -624,6 +1010,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
@@ -711,6 +1099,8 @@ mod tests {
             fast_field: "fast_field".to_string(),
             output_format: 1,
             partition_by_field: Some(String::from("partition_by_fast_field")),
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
         let splits_offsets = splits
@@ -719,6 +1109,8 @@ mod tests {
                 split_id: split_meta.split_id().to_string(),
                 split_footer_start: split_meta.split_metadata.footer_offsets.start,
                 split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
             })
             .collect();
         let mut single_node_stream = leaf_search_stream(
@@ -736,6 +1128,147 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_leaf_search_stream_to_composite_partitioned_clickhouse_binary_output(
+    ) -> anyhow::Result<()> {
+        let index_id = "single-node-composite-partitions";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: tenant_id
+                type: u64
+                fast: true
+              - name: region
+                type: i64
+                fast: true
+              - name: fast_field
+                type: u64
+                fast: true
+        "#;
+        let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+
+        let mut docs = vec![];
+        let mut expected_output_tmp: HashMap<(u64, i64), Vec<u64>> = HashMap::new();
+        for i in 0..30u64 {
+            let tenant_id = i % 2;
+            let region = -((i % 3) as i64);
+            docs.push(json!({
+                "body": "info",
+                "tenant_id": tenant_id,
+                "region": region,
+                "fast_field": i,
+            }));
+            expected_output_tmp
+                .entry((tenant_id, region))
+                .or_default()
+                .push(i);
+        }
+        test_sandbox.add_documents(docs).await?;
+        let mut expected_output: Vec<(Vec<u64>, Vec<u64>)> = expected_output_tmp
+            .into_iter()
+            .map(|((tenant_id, region), values)| (vec![tenant_id, region.as_u64()], values))
+            .collect();
+
+        let request = SearchStreamRequest {
+            index_id: index_id.to_string(),
+            query: "info".to_string(),
+            search_fields: vec![],
+            start_timestamp: None,
+            end_timestamp: None,
+            fast_field: "fast_field".to_string(),
+            output_format: OutputFormat::ClickHouseRowBinary as i32,
+            partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec!["tenant_id".to_string(), "region".to_string()],
+        };
+        let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
+        let splits_offsets = splits
+            .into_iter()
+            .map(|split_meta| SplitIdAndFooterOffsets {
+                split_id: split_meta.split_id().to_string(),
+                split_footer_start: split_meta.split_metadata.footer_offsets.start,
+                split_footer_end: split_meta.split_metadata.footer_offsets.end,
+                timestamp_start: None,
+                timestamp_end: None,
+            })
+            .collect();
+        let mut single_node_stream = leaf_search_stream(
+            request,
+            test_sandbox.storage(),
+            splits_offsets,
+            test_sandbox.doc_mapper(),
+        )
+        .await;
+        let res = single_node_stream.next().await.expect("no leaf result")?;
+
+        let mut deserialized_output = Vec::new();
+        let mut cursor = 0;
+        let read_u64 =
+            |cursor: usize| u64::from_le_bytes(res.data[cursor..cursor + 8].try_into().unwrap());
+        while cursor < res.data.len() {
+            let partition_key = vec![read_u64(cursor), read_u64(cursor + 8)];
+            let num_values = read_u64(cursor + 16) as usize / 8;
+            cursor += 24;
+            let mut values: Vec<u64> = (0..num_values)
+                .map(|value_ord| read_u64(cursor + value_ord * 8))
+                .collect();
+            values.sort_unstable();
+            cursor += num_values * 8;
+            deserialized_output.push((partition_key, values));
+        }
+        expected_output.sort();
+        deserialized_output.sort();
+        assert_eq!(deserialized_output, expected_output);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_stream_request_fields_partition_by_fields_must_be_fast() -> anyhow::Result<()> {
+        let doc_mapper: DefaultDocMapper = serde_json::from_value(json!({
+            "field_mappings": [
+                { "name": "body", "type": "text" },
+                { "name": "tenant_id", "type": "u64", "fast": true },
+                { "name": "region", "type": "i64" },
+                { "name": "value", "type": "u64", "fast": true }
+            ]
+        }))?;
+        let schema = doc_mapper.schema();
+        let mut request = SearchStreamRequest {
+            index_id: "test-index".to_string(),
+            query: "body:info".to_string(),
+            search_fields: vec![],
+            start_timestamp: None,
+            end_timestamp: None,
+            fast_field: "value".to_string(),
+            output_format: OutputFormat::ClickHouseRowBinary as i32,
+            partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec!["tenant_id".to_string(), "region".to_string()],
+        };
+        let error =
+            SearchStreamRequestFields::from_request(&request, &schema, &doc_mapper).unwrap_err();
+        assert!(matches!(
+            error,
+            SearchError::InvalidQuery(message) if message == "Field `region` is not a fast field"
+        ));
+
+        request.partition_by_fields = vec!["tenant_id".to_string()];
+        let request_fields =
+            SearchStreamRequestFields::from_request(&request, &schema, &doc_mapper)?;
+        assert_eq!(
+            request_fields.partition_by_fast_field_names(),
+            ["tenant_id"]
+        );
+
+        request.partition_by_field = Some("tenant_id".to_string());
+        assert!(matches!(
+            SearchStreamRequestFields::from_request(&request, &schema, &doc_mapper),
+            Err(SearchError::InvalidQuery(_))
+        ));
+        Ok(())
+    }
+
     fn deserialize_partitions(buffer: Vec<u8>) -> Vec<PartitionValues<u64, u64>> {
         // Note: this function is only meant to be used with valid payloads for testing purposes
         let mut cursor = 0;
@@ -26,12 +26,13 @@ use std::io;
 use std::io::Write;
 
 pub use collector::FastFieldCollector;
-pub use leaf::leaf_search_stream;
+pub use leaf::{leaf_search_stream, plan_leaf_search_stream_fetches};
 use quickwit_proto::OutputFormat;
 pub use root::root_search_stream;
 use tantivy::fastfield::FastValue;
+use tantivy::schema::Type;
 
-use self::collector::PartitionValues;
+use self::collector::{PartitionKey, PartitionValues};
 
 /// Serialize the values into the `buffer` as bytes.
 ///
@@ -43,12 +44,57 @@ pub fn serialize<TFastValue: FastValue + Display>(
 ) -> io::Result<()> {
     match format {
         OutputFormat::Csv => serialize_csv(values, buffer),
-        OutputFormat::ClickHouseRowBinary => serialize_click_house_row_binary(values, buffer),
+        // The names and types of the columns are written once by the root.
+        OutputFormat::ClickHouseRowBinary | OutputFormat::ClickHouseRowBinaryWithNamesAndTypes => {
+            serialize_click_house_row_binary(values, buffer)
+        }
+    }
+}
+
+/// Returns the ClickHouse type of the values streamed for a fast field of the given type, if the
+/// type is supported by search stream.
+pub fn click_house_type_name(fast_field_type: Type) -> Option<&'static str> {
+    match fast_field_type {
+        // Dates are streamed as timestamps.
+        Type::I64 | Type::Date => Some("Int64"),
+        Type::U64 => Some("UInt64"),
+        _ => None,
+    }
+}
+
+/// Serializes the header of the ClickHouse `RowBinaryWithNamesAndTypes` format for a single
+/// column into the `buffer`: the number of columns, their names, and their types.
+///
+/// Please note that the `buffer` is always cleared.
+pub fn serialize_click_house_names_and_types(
+    column_name: &str,
+    column_type: &str,
+    buffer: &mut Vec<u8>,
+) {
+    buffer.clear();
+    write_click_house_var_uint(1, buffer);
+    write_click_house_string(column_name, buffer);
+    write_click_house_string(column_type, buffer);
+}
+
+/// Writes an unsigned integer in the LEB128 encoding used by ClickHouse.
+fn write_click_house_var_uint(mut value: u64, buffer: &mut Vec<u8>) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
     }
+    buffer.push(value as u8);
+}
+
+fn write_click_house_string(value: &str, buffer: &mut Vec<u8>) {
+    write_click_house_var_uint(value.len() as u64, buffer);
+    buffer.extend_from_slice(value.as_bytes());
 }
 
-pub fn serialize_partitions<TFastValue: FastValue + Display, TPartitionFastValue: FastValue>(
-    p_values: &[PartitionValues<TFastValue, TPartitionFastValue>],
+/// Serializes the partitions in the partitioned `ClickHouseRowBinary` format: for each partition,
+/// the partition key, the byte size of the values and the values.
+pub fn serialize_partitions<TFastValue: FastValue + Display, TPartitionKey: PartitionKey>(
+    p_values: &[PartitionValues<TFastValue, TPartitionKey>],
     buffer: &mut Vec<u8>,
 ) -> io::Result<()> {
     let buf_size = helpers::partitions_size_in_bytes(p_values);
@@ -58,7 +104,7 @@ pub fn serialize_partitions<TFastValue: FastValue + Display, TPartitionFastValue
         let values_byte_size =
             std::mem::size_of::<TFastValue>() * partition.fast_field_values.len();
 
-        buffer.extend(partition.partition_value.as_u64().to_le_bytes());
+        partition.partition_value.write_le_bytes(buffer);
         buffer.extend(values_byte_size.to_le_bytes());
 
         for value in &partition.fast_field_values {
@@ -96,14 +142,14 @@ mod helpers {
 
     use tantivy::fastfield::FastValue;
 
-    use super::collector::PartitionValues;
+    use super::collector::{PartitionKey, PartitionValues};
 
     #[inline(always)]
     pub fn partitions_size_in_bytes<
         TFastValue: FastValue + Display,
-        TPartitionFastValue: FastValue,
+        TPartitionKey: PartitionKey,
     >(
-        partitions: &[PartitionValues<TFastValue, TPartitionFastValue>],
+        partitions: &[PartitionValues<TFastValue, TPartitionKey>],
     ) -> usize {
         let mut size = 0;
         for partition in partitions {
@@ -113,19 +159,22 @@ mod helpers {
     }
 
     #[inline(always)]
-    fn partition_size_in_bytes<TFastValue: FastValue + Display, TPartitionFastValue: FastValue>(
-        partition: &PartitionValues<TFastValue, TPartitionFastValue>,
+    fn partition_size_in_bytes<TFastValue: FastValue + Display, TPartitionKey: PartitionKey>(
+        partition: &PartitionValues<TFastValue, TPartitionKey>,
     ) -> usize {
         std::mem::size_of::<TFastValue>() * partition.fast_field_values.len()
             + std::mem::size_of::<u64>()
-            + std::mem::size_of::<TPartitionFastValue>()
+            + partition.partition_value.num_bytes()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::search_stream::collector::PartitionValues;
-    use crate::search_stream::{serialize_click_house_row_binary, serialize_csv};
+    use crate::search_stream::collector::{CompositePartitionKey, PartitionValues};
+    use crate::search_stream::{
+        serialize_click_house_names_and_types, serialize_click_house_row_binary, serialize_csv,
+        write_click_house_var_uint,
+    };
 
     #[test]
     fn test_serialize_row_binary() {
@@ -138,6 +187,24 @@ mod tests {
         assert_eq!(buffer, (-10f64).to_le_bytes());
     }
 
+    #[test]
+    fn test_serialize_click_house_names_and_types() {
+        let mut buffer = vec![42u8];
+        serialize_click_house_names_and_types("ts", "Int64", &mut buffer);
+        assert_eq!(buffer, b"\x01\x02ts\x05Int64");
+    }
+
+    #[test]
+    fn test_write_click_house_var_uint() {
+        let mut buffer = Vec::new();
+        write_click_house_var_uint(127, &mut buffer);
+        assert_eq!(buffer, [0x7f]);
+
+        let mut buffer = Vec::new();
+        write_click_house_var_uint(300, &mut buffer);
+        assert_eq!(buffer, [0xac, 0x02]);
+    }
+
     #[test]
     fn test_serialize_csv() {
         let mut buffer = Vec::new();
@@ -171,4 +238,37 @@ mod tests {
         .collect();
         assert_eq!(buffer, expected_buffer);
     }
+
+    #[test]
+    fn test_serialize_composite_partitions() {
+        let mut buffer = Vec::new();
+        let partition_1 = PartitionValues {
+            partition_value: CompositePartitionKey(vec![1u64, 7u64]),
+            fast_field_values: vec![3u64, 4u64],
+        };
+        let partition_2 = PartitionValues {
+            partition_value: CompositePartitionKey(vec![2u64, 8u64]),
+            fast_field_values: vec![5u64],
+        };
+        super::serialize_partitions::<u64, CompositePartitionKey>(
+            &[partition_1, partition_2],
+            &mut buffer,
+        )
+        .unwrap();
+        let expected_buffer: Vec<u8> = vec![
+            1u64.to_le_bytes(),
+            7u64.to_le_bytes(),
+            16usize.to_le_bytes(),
+            3u64.to_le_bytes(),
+            4u64.to_le_bytes(),
+            2u64.to_le_bytes(),
+            8u64.to_le_bytes(),
+            8usize.to_le_bytes(),
+            5u64.to_le_bytes(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        assert_eq!(buffer, expected_buffer);
+    }
 }
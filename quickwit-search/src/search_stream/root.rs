@@ -22,13 +22,15 @@ use std::collections::HashSet;
 use bytes::Bytes;
 use futures::{StreamExt, TryStreamExt};
 use quickwit_config::build_doc_mapper;
+use quickwit_doc_mapper::DocMapper;
 use quickwit_metastore::Metastore;
-use quickwit_proto::{LeafSearchStreamRequest, SearchRequest, SearchStreamRequest};
+use quickwit_proto::{LeafSearchStreamRequest, OutputFormat, SearchRequest, SearchStreamRequest};
 use tokio_stream::StreamMap;
 use tracing::*;
 
+use super::{click_house_type_name, serialize_click_house_names_and_types};
 use crate::cluster_client::ClusterClient;
-use crate::root::SearchJob;
+use crate::root::{serialize_index_storage_config, SearchJob};
 use crate::{list_relevant_splits, SearchClientPool, SearchError, SearchServiceClient};
 
 /// Perform a distributed search stream.
@@ -44,7 +46,6 @@ pub async fn root_search_stream(
 
     let search_request = SearchRequest::from(search_stream_request.clone());
     let index_metadata = metastore.index_metadata(&search_request.index_id).await?;
-    let split_metadatas = list_relevant_splits(&search_request, metastore).await?;
     let doc_mapper = build_doc_mapper(
         &index_metadata.doc_mapping,
         &index_metadata.search_settings,
@@ -53,13 +54,19 @@ pub async fn root_search_stream(
     .map_err(|err| {
         SearchError::InternalError(format!("Failed to build doc mapper. Cause: {}", err))
     })?;
+    let split_metadatas =
+        list_relevant_splits(&search_request, doc_mapper.timestamp_precision(), metastore).await?;
 
     // try to build query against current schema
     let _query = doc_mapper.query(doc_mapper.schema(), &search_request)?;
 
+    let header_opt = stream_header(&search_stream_request, &*doc_mapper)?;
+
     let doc_mapper_str = serde_json::to_string(&doc_mapper).map_err(|err| {
         SearchError::InternalError(format!("Failed to serialize doc mapper: Cause {}", err))
     })?;
+    let index_storage_config_str_opt =
+        serialize_index_storage_config(index_metadata.storage_config.as_ref())?;
 
     let leaf_search_jobs: Vec<SearchJob> = split_metadatas.iter().map(SearchJob::from).collect();
 
@@ -73,6 +80,7 @@ pub async fn root_search_stream(
             &search_stream_request,
             &doc_mapper_str,
             index_metadata.index_uri.as_ref(),
+            index_storage_config_str_opt.as_deref(),
             client_jobs,
         );
         let leaf_stream = cluster_client
@@ -80,15 +88,62 @@ pub async fn root_search_stream(
             .await;
         stream_map.insert(leaf_ord, leaf_stream);
     }
-    Ok(stream_map
-        .map(|(_leaf_ord, result)| result)
-        .map_ok(|leaf_response| Bytes::from(leaf_response.data)))
+    let header_stream = futures::stream::iter(header_opt.map(Ok));
+    Ok(header_stream.chain(
+        stream_map
+            .map(|(_leaf_ord, result)| result)
+            .map_ok(|leaf_response| Bytes::from(leaf_response.data)),
+    ))
+}
+
+/// Returns the bytes written once before the data streamed by the leaves, if the output format
+/// has a header.
+fn stream_header(
+    search_stream_request: &SearchStreamRequest,
+    doc_mapper: &dyn DocMapper,
+) -> crate::Result<Option<Bytes>> {
+    let output_format =
+        OutputFormat::from_i32(search_stream_request.output_format).ok_or_else(|| {
+            SearchError::InternalError("Invalid output format specified.".to_string())
+        })?;
+    if output_format != OutputFormat::ClickHouseRowBinaryWithNamesAndTypes {
+        return Ok(None);
+    }
+    if search_stream_request.partition_by_field.is_some()
+        || !search_stream_request.partition_by_fields.is_empty()
+    {
+        return Err(SearchError::InvalidQuery(
+            "Output format `ClickHouseRowBinaryWithNamesAndTypes` does not support \
+             partitioned-by fields, use `ClickHouseRowBinary` instead."
+                .to_string(),
+        ));
+    }
+    let schema = doc_mapper.schema();
+    let fast_field_name = &search_stream_request.fast_field;
+    let fast_field = schema.get_field(fast_field_name).ok_or_else(|| {
+        SearchError::InvalidQuery(format!(
+            "Field `{}` does not exist in schema",
+            fast_field_name
+        ))
+    })?;
+    let fast_field_type = schema.get_field_entry(fast_field).field_type().value_type();
+    let column_type = click_house_type_name(fast_field_type).ok_or_else(|| {
+        SearchError::InvalidQuery(format!(
+            "Output format `ClickHouseRowBinaryWithNamesAndTypes` does not support fast field \
+             `{}` of type `{:?}`.",
+            fast_field_name, fast_field_type
+        ))
+    })?;
+    let mut header = Vec::new();
+    serialize_click_house_names_and_types(fast_field_name, column_type, &mut header);
+    Ok(Some(Bytes::from(header)))
 }
 
 fn jobs_to_leaf_request(
     request: &SearchStreamRequest,
     doc_mapper_str: &str,
     index_uri: &str,
+    index_storage_config_str_opt: Option<&str>,
     jobs: Vec<SearchJob>,
 ) -> LeafSearchStreamRequest {
     LeafSearchStreamRequest {
@@ -96,6 +151,7 @@ fn jobs_to_leaf_request(
         split_offsets: jobs.into_iter().map(Into::into).collect(),
         doc_mapper: doc_mapper_str.to_string(),
         index_uri: index_uri.to_string(),
+        index_storage_config: index_storage_config_str_opt.map(str::to_string),
     }
 }
 
@@ -123,6 +179,8 @@ mod tests {
             fast_field: "timestamp".to_string(),
             output_format: OutputFormat::Csv as i32,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let mut metastore = MockMetastore::new();
         metastore
@@ -169,6 +227,60 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_root_search_stream_click_house_names_and_types() -> anyhow::Result<()> {
+        let request = quickwit_proto::SearchStreamRequest {
+            index_id: "test-index".to_string(),
+            query: "test".to_string(),
+            search_fields: vec!["body".to_string()],
+            start_timestamp: None,
+            end_timestamp: None,
+            fast_field: "timestamp".to_string(),
+            output_format: OutputFormat::ClickHouseRowBinaryWithNamesAndTypes as i32,
+            partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
+        };
+        let mut metastore = MockMetastore::new();
+        metastore
+            .expect_index_metadata()
+            .returning(|_index_id: &str| {
+                Ok(IndexMetadata::for_test(
+                    "test-index",
+                    "ram:///indexes/test-index",
+                ))
+            });
+        metastore.expect_list_splits().returning(
+            |_index_id: &str, _split_state: SplitState, _time_range: Option<Range<i64>>, _tags| {
+                Ok(vec![mock_split("split1")])
+            },
+        );
+        let mut mock_search_service = MockSearchService::new();
+        let (result_sender, result_receiver) = tokio::sync::mpsc::unbounded_channel();
+        result_sender.send(Ok(quickwit_proto::LeafSearchStreamResponse {
+            data: 1i64.to_le_bytes().to_vec(),
+            split_id: "split_1".to_string(),
+        }))?;
+        mock_search_service.expect_leaf_search_stream().return_once(
+            |_leaf_search_req: quickwit_proto::LeafSearchStreamRequest| {
+                Ok(UnboundedReceiverStream::new(result_receiver))
+            },
+        );
+        drop(result_sender);
+        let client_pool = SearchClientPool::from_mocks(vec![Arc::new(mock_search_service)]).await?;
+
+        let cluster_client = ClusterClient::new(client_pool.clone());
+        let result: Vec<Bytes> =
+            root_search_stream(request, &metastore, cluster_client, &client_pool)
+                .await?
+                .try_collect()
+                .await?;
+        assert_eq!(result.len(), 2);
+        assert_eq!(&result[0], &b"\x01\x09timestamp\x05Int64"[..]);
+        assert_eq!(&result[1], &1i64.to_le_bytes()[..]);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_root_search_stream_single_split_partitionned() -> anyhow::Result<()> {
         let request = quickwit_proto::SearchStreamRequest {
@@ -180,6 +292,8 @@ mod tests {
             fast_field: "timestamp".to_string(),
             output_format: OutputFormat::Csv as i32,
             partition_by_field: Some("timestamp".to_string()),
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let mut metastore = MockMetastore::new();
         metastore
@@ -233,6 +347,8 @@ mod tests {
             fast_field: "timestamp".to_string(),
             output_format: OutputFormat::Csv as i32,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let mut metastore = MockMetastore::new();
         metastore
@@ -312,6 +428,8 @@ mod tests {
                 fast_field: "timestamp".to_string(),
                 output_format: OutputFormat::Csv as i32,
                 partition_by_field: Some("timestamp".to_string()),
+                split_order: 0,
+                partition_by_fields: vec![],
             },
             &metastore,
             ClusterClient::new(client_pool.clone()),
@@ -330,6 +448,8 @@ mod tests {
                 fast_field: "timestamp".to_string(),
                 output_format: OutputFormat::Csv as i32,
                 partition_by_field: Some("timestamp".to_string()),
+                split_order: 0,
+                partition_by_fields: vec![],
             },
             &metastore,
             ClusterClient::new(client_pool.clone()),
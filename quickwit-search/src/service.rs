@@ -23,6 +23,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use bytes::Bytes;
 use quickwit_common::uri::Uri;
+use quickwit_config::IndexStorageConfig;
 use quickwit_doc_mapper::DocMapper;
 use quickwit_metastore::Metastore;
 use quickwit_proto::{
@@ -30,7 +31,9 @@ use quickwit_proto::{
     LeafSearchStreamRequest, LeafSearchStreamResponse, SearchRequest, SearchResponse,
     SearchStreamRequest,
 };
-use quickwit_storage::StorageUriResolver;
+use quickwit_storage::{
+    ByteCountingStorage, CachingStorage, LocalFileCache, Storage, StorageUriResolver,
+};
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::info;
 
@@ -44,6 +47,7 @@ pub struct SearchServiceImpl {
     storage_uri_resolver: StorageUriResolver,
     cluster_client: ClusterClient,
     client_pool: SearchClientPool,
+    local_file_cache_opt: Option<Arc<LocalFileCache>>,
 }
 
 /// Trait representing a search service.
@@ -101,8 +105,15 @@ impl SearchServiceImpl {
             storage_uri_resolver,
             cluster_client,
             client_pool,
+            local_file_cache_opt: None,
         }
     }
+
+    /// Caches the slices read from the index storages in the given local file cache.
+    pub fn with_local_file_cache(mut self, local_file_cache: Arc<LocalFileCache>) -> Self {
+        self.local_file_cache_opt = Some(local_file_cache);
+        self
+    }
 }
 
 fn deserialize_doc_mapper(doc_mapper_str: &str) -> crate::Result<Arc<dyn DocMapper>> {
@@ -112,6 +123,45 @@ fn deserialize_doc_mapper(doc_mapper_str: &str) -> crate::Result<Arc<dyn DocMapp
     Ok(doc_mapper)
 }
 
+fn deserialize_index_storage_config(
+    index_storage_config_str_opt: Option<&str>,
+) -> crate::Result<Option<IndexStorageConfig>> {
+    index_storage_config_str_opt
+        .map(|index_storage_config_str| {
+            serde_json::from_str::<IndexStorageConfig>(index_storage_config_str).map_err(|err| {
+                SearchError::InternalError(format!(
+                    "Failed to deserialize index storage config: `{err}`"
+                ))
+            })
+        })
+        .transpose()
+}
+
+impl SearchServiceImpl {
+    /// Resolves the storage of the index, on top of the local file cache if any.
+    ///
+    /// The returned [`ByteCountingStorage`] sits below the local file cache: it only counts the
+    /// bytes actually fetched from the index storage.
+    fn resolve_index_storage(
+        &self,
+        index_uri: String,
+        index_storage_config_str_opt: Option<&str>,
+    ) -> crate::Result<(Arc<dyn Storage>, Arc<ByteCountingStorage>)> {
+        let index_storage_config_opt =
+            deserialize_index_storage_config(index_storage_config_str_opt)?;
+        let storage = self
+            .storage_uri_resolver
+            .resolve_with_storage_config(&Uri::new(index_uri), index_storage_config_opt.as_ref())?;
+        let byte_counting_storage = Arc::new(ByteCountingStorage::new(storage));
+        if let Some(local_file_cache) = &self.local_file_cache_opt {
+            let caching_storage =
+                CachingStorage::new(byte_counting_storage.clone(), local_file_cache.clone());
+            return Ok((Arc::new(caching_storage), byte_counting_storage));
+        }
+        Ok((byte_counting_storage.clone(), byte_counting_storage))
+    }
+}
+
 #[async_trait]
 impl SearchService for SearchServiceImpl {
     async fn root_search(&self, search_request: SearchRequest) -> crate::Result<SearchResponse> {
@@ -134,14 +184,16 @@ impl SearchService for SearchServiceImpl {
             .search_request
             .ok_or_else(|| SearchError::InternalError("No search request.".to_string()))?;
         info!(index=?search_request.index_id, splits=?leaf_search_request.split_offsets, "leaf_search");
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::new(leaf_search_request.index_uri))?;
+        let (storage, byte_counting_storage) = self.resolve_index_storage(
+            leaf_search_request.index_uri,
+            leaf_search_request.index_storage_config.as_deref(),
+        )?;
         let split_ids = leaf_search_request.split_offsets;
         let doc_mapper = deserialize_doc_mapper(&leaf_search_request.doc_mapper)?;
 
-        let leaf_search_response =
+        let mut leaf_search_response =
             leaf_search(&search_request, storage.clone(), &split_ids[..], doc_mapper).await?;
+        leaf_search_response.num_bytes_fetched = byte_counting_storage.num_bytes_fetched();
 
         Ok(leaf_search_response)
     }
@@ -150,16 +202,18 @@ impl SearchService for SearchServiceImpl {
         &self,
         fetch_docs_request: FetchDocsRequest,
     ) -> crate::Result<FetchDocsResponse> {
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::new(fetch_docs_request.index_uri))?;
+        let (storage, byte_counting_storage) = self.resolve_index_storage(
+            fetch_docs_request.index_uri,
+            fetch_docs_request.index_storage_config.as_deref(),
+        )?;
 
-        let fetch_docs_response = fetch_docs(
+        let mut fetch_docs_response = fetch_docs(
             fetch_docs_request.partial_hits,
             storage,
             &fetch_docs_request.split_offsets,
         )
         .await?;
+        fetch_docs_response.num_bytes_fetched = byte_counting_storage.num_bytes_fetched();
 
         Ok(fetch_docs_response)
     }
@@ -186,9 +240,10 @@ impl SearchService for SearchServiceImpl {
             .request
             .ok_or_else(|| SearchError::InternalError("No search request.".to_string()))?;
         info!(index=?stream_request.index_id, splits=?leaf_stream_request.split_offsets, "leaf_search");
-        let storage = self
-            .storage_uri_resolver
-            .resolve(&Uri::new(leaf_stream_request.index_uri))?;
+        let (storage, _byte_counting_storage) = self.resolve_index_storage(
+            leaf_stream_request.index_uri,
+            leaf_stream_request.index_storage_config.as_deref(),
+        )?;
         let doc_mapper = deserialize_doc_mapper(&leaf_stream_request.doc_mapper)?;
         let leaf_receiver = leaf_search_stream(
             stream_request,
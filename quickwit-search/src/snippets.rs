@@ -0,0 +1,443 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::Range;
+
+use quickwit_doc_mapper::{DocMapper, QUICKWIT_TOKENIZER_MANAGER};
+use quickwit_proto::{LeafHit, SearchRequest};
+use serde_json::Value as JsonValue;
+use tantivy::schema::FieldType;
+use tantivy::tokenizer::TextAnalyzer;
+use tantivy::Term;
+
+use crate::SearchError;
+
+/// Maximum number of fragments of a snippet, unless set in the search request.
+const DEFAULT_SNIPPET_MAX_FRAGMENTS: usize = 3;
+
+/// Maximum number of characters of a snippet fragment, unless set in the search request.
+const DEFAULT_SNIPPET_FRAGMENT_SIZE: usize = 100;
+
+/// Generates the highlighted snippets of a text field.
+pub(crate) struct SnippetGenerator {
+    field_name: String,
+    tokenizer: TextAnalyzer,
+    terms: HashSet<String>,
+    max_fragments: usize,
+    fragment_size: usize,
+}
+
+/// A run of consecutive tokens of a field value, at most `fragment_size` characters long.
+#[derive(Debug)]
+struct Fragment {
+    /// Position of the field value the fragment belongs to, for multivalued fields.
+    value_ord: usize,
+    /// Byte range of the fragment in the field value.
+    byte_range: Range<usize>,
+    /// Byte ranges of the tokens matching a query term.
+    highlighted: Vec<Range<usize>>,
+}
+
+impl Fragment {
+    fn to_html(&self, text: &str) -> String {
+        let mut html = String::new();
+        let mut cursor = self.byte_range.start;
+        for highlighted in &self.highlighted {
+            // Tokenizers emitting overlapping tokens would otherwise nest the highlights.
+            if highlighted.start < cursor {
+                continue;
+            }
+            push_escaped_html(&mut html, &text[cursor..highlighted.start]);
+            html.push_str("<b>");
+            push_escaped_html(&mut html, &text[highlighted.clone()]);
+            html.push_str("</b>");
+            cursor = highlighted.end;
+        }
+        push_escaped_html(&mut html, &text[cursor..self.byte_range.end]);
+        html
+    }
+}
+
+fn push_escaped_html(html: &mut String, text: &str) {
+    for character in text.chars() {
+        match character {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#x27;"),
+            _ => html.push(character),
+        }
+    }
+}
+
+impl SnippetGenerator {
+    /// Returns the fragments of the `values` of the field containing the most query terms, in
+    /// the order in which they appear, highlighted with `<b>` tags. If none of the values contains
+    /// a query term, the leading fragment of the first value is returned instead.
+    fn snippet(&self, values: &[&str]) -> Vec<String> {
+        let mut fragments = Vec::new();
+        for (value_ord, value) in values.iter().enumerate() {
+            self.split_into_fragments(value_ord, value, &mut fragments);
+        }
+        let mut best_fragments: Vec<&Fragment> = fragments
+            .iter()
+            .filter(|fragment| !fragment.highlighted.is_empty())
+            .collect();
+        if best_fragments.is_empty() {
+            return self.leading_fragment(values).into_iter().collect();
+        }
+        best_fragments.sort_by_key(|fragment| {
+            (
+                Reverse(fragment.highlighted.len()),
+                fragment.value_ord,
+                fragment.byte_range.start,
+            )
+        });
+        best_fragments.truncate(self.max_fragments);
+        best_fragments.sort_by_key(|fragment| (fragment.value_ord, fragment.byte_range.start));
+        best_fragments
+            .into_iter()
+            .map(|fragment| fragment.to_html(values[fragment.value_ord]))
+            .collect()
+    }
+
+    fn split_into_fragments(&self, value_ord: usize, text: &str, fragments: &mut Vec<Fragment>) {
+        let mut token_stream = self.tokenizer.token_stream(text);
+        let mut fragment_opt: Option<Fragment> = None;
+        while token_stream.advance() {
+            let token = token_stream.token();
+            let is_fragment_full = fragment_opt
+                .as_ref()
+                .map(|fragment| {
+                    text[fragment.byte_range.start..token.offset_to]
+                        .chars()
+                        .count()
+                        > self.fragment_size
+                })
+                .unwrap_or(false);
+            if is_fragment_full {
+                fragments.extend(fragment_opt.take());
+            }
+            let fragment = fragment_opt.get_or_insert_with(|| Fragment {
+                value_ord,
+                byte_range: token.offset_from..token.offset_from,
+                highlighted: Vec::new(),
+            });
+            fragment.byte_range.end = token.offset_to;
+            if self.terms.contains(&token.text) {
+                fragment
+                    .highlighted
+                    .push(token.offset_from..token.offset_to);
+            }
+        }
+        fragments.extend(fragment_opt);
+    }
+
+    fn leading_fragment(&self, values: &[&str]) -> Option<String> {
+        let text = values.iter().find(|value| !value.is_empty())?;
+        let mut fragments = Vec::new();
+        self.split_into_fragments(0, text, &mut fragments);
+        if let Some(fragment) = fragments.first() {
+            return Some(fragment.to_html(text));
+        }
+        // The text is made of characters dropped by the tokenizer, such as punctuation.
+        let prefix_len = text
+            .char_indices()
+            .nth(self.fragment_size)
+            .map(|(byte_offset, _)| byte_offset)
+            .unwrap_or(text.len());
+        let mut html = String::new();
+        push_escaped_html(&mut html, &text[..prefix_len]);
+        Some(html)
+    }
+}
+
+/// Returns the value of a snippet option for a field, the per-field override taking precedence
+/// over the value set for all the fields.
+fn snippet_option(
+    option_name: &str,
+    value_opt: Option<u32>,
+    per_field_value_opt: Option<u32>,
+    default_value: usize,
+) -> crate::Result<usize> {
+    match per_field_value_opt.or(value_opt) {
+        Some(0) => Err(SearchError::InvalidQuery(format!(
+            "Snippet option `{}` must be strictly positive.",
+            option_name
+        ))),
+        Some(value) => Ok(value as usize),
+        None => Ok(default_value),
+    }
+}
+
+/// Builds a snippet generator for each of the snippet fields of the search request, checking that
+/// they are text fields that are either stored or reconstructed.
+pub(crate) fn build_snippet_generators(
+    search_request: &SearchRequest,
+    doc_mapper: &dyn DocMapper,
+) -> crate::Result<Vec<SnippetGenerator>> {
+    if search_request.snippet_fields.is_empty() {
+        return Ok(Vec::new());
+    }
+    for field_name in search_request
+        .snippet_max_fragments_per_field
+        .keys()
+        .chain(search_request.snippet_fragment_size_per_field.keys())
+    {
+        if !search_request.snippet_fields.contains(field_name) {
+            return Err(SearchError::InvalidQuery(format!(
+                "Snippet options are overridden for field `{}`, which is not a snippet field.",
+                field_name
+            )));
+        }
+    }
+    let schema = doc_mapper.schema();
+    let query = doc_mapper.query(schema.clone(), search_request)?;
+    let mut query_terms: BTreeMap<Term, bool> = BTreeMap::new();
+    query.query_terms(&mut query_terms);
+
+    let mut snippet_generators = Vec::with_capacity(search_request.snippet_fields.len());
+    for field_name in &search_request.snippet_fields {
+        let field = schema.get_field(field_name).ok_or_else(|| {
+            SearchError::InvalidQuery(format!("Field `{}` does not exist in schema", field_name))
+        })?;
+        let field_entry = schema.get_field_entry(field);
+        let text_field_indexing_opt = match field_entry.field_type() {
+            FieldType::Str(text_options) => text_options.get_indexing_options(),
+            _ => None,
+        };
+        let text_field_indexing = text_field_indexing_opt.ok_or_else(|| {
+            SearchError::InvalidQuery(format!(
+                "Field `{}` cannot be used for snippets: it is not an indexed text field.",
+                field_name
+            ))
+        })?;
+        if !field_entry.is_stored() && !search_request.reconstruct_fields.contains(field_name) {
+            return Err(SearchError::InvalidQuery(format!(
+                "Field `{}` cannot be used for snippets: it is neither stored nor reconstructed.",
+                field_name
+            )));
+        }
+        let tokenizer = QUICKWIT_TOKENIZER_MANAGER
+            .get(text_field_indexing.tokenizer())
+            .ok_or_else(|| {
+                SearchError::InternalError(format!(
+                    "Tokenizer `{}` of field `{}` is not registered.",
+                    text_field_indexing.tokenizer(),
+                    field_name
+                ))
+            })?;
+        let terms: HashSet<String> = query_terms
+            .keys()
+            .filter(|term| term.field() == field)
+            .map(|term| String::from_utf8_lossy(term.value_bytes()).to_string())
+            .collect();
+        let max_fragments = snippet_option(
+            "snippet_max_fragments",
+            search_request.snippet_max_fragments,
+            search_request
+                .snippet_max_fragments_per_field
+                .get(field_name)
+                .copied(),
+            DEFAULT_SNIPPET_MAX_FRAGMENTS,
+        )?;
+        let fragment_size = snippet_option(
+            "snippet_fragment_size",
+            search_request.snippet_fragment_size,
+            search_request
+                .snippet_fragment_size_per_field
+                .get(field_name)
+                .copied(),
+            DEFAULT_SNIPPET_FRAGMENT_SIZE,
+        )?;
+        snippet_generators.push(SnippetGenerator {
+            field_name: field_name.clone(),
+            tokenizer,
+            terms,
+            max_fragments,
+            fragment_size,
+        });
+    }
+    Ok(snippet_generators)
+}
+
+/// Generates the json serialized snippets of each of the hits, keyed by snippet field.
+pub(crate) fn generate_snippets(
+    leaf_hits: &[LeafHit],
+    snippet_generators: &[SnippetGenerator],
+) -> crate::Result<Vec<Option<String>>> {
+    if snippet_generators.is_empty() {
+        return Ok(vec![None; leaf_hits.len()]);
+    }
+    leaf_hits
+        .iter()
+        .map(|leaf_hit| {
+            let hit_json: BTreeMap<String, Vec<JsonValue>> =
+                serde_json::from_str(&leaf_hit.leaf_json)
+                    .map_err(|_| SearchError::InternalError("Invalid leaf json.".to_string()))?;
+            let snippets: BTreeMap<&str, Vec<String>> = snippet_generators
+                .iter()
+                .map(|snippet_generator| {
+                    let values: Vec<&str> = hit_json
+                        .get(&snippet_generator.field_name)
+                        .map(|values| values.iter().flat_map(JsonValue::as_str).collect())
+                        .unwrap_or_default();
+                    (
+                        snippet_generator.field_name.as_str(),
+                        snippet_generator.snippet(&values),
+                    )
+                })
+                .collect();
+            let snippet_json =
+                serde_json::to_string(&snippets).expect("Json serialization should never fail.");
+            Ok(Some(snippet_json))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_doc_mapper::DefaultDocMapper;
+
+    use super::*;
+
+    fn snippet_generator(
+        terms: &[&str],
+        max_fragments: usize,
+        fragment_size: usize,
+    ) -> SnippetGenerator {
+        SnippetGenerator {
+            field_name: "body".to_string(),
+            tokenizer: QUICKWIT_TOKENIZER_MANAGER.get("default").unwrap(),
+            terms: terms.iter().map(|term| term.to_string()).collect(),
+            max_fragments,
+            fragment_size,
+        }
+    }
+
+    #[test]
+    fn test_snippet_highlights_query_terms() {
+        let snippet_generator = snippet_generator(&["fox"], 3, 100);
+        assert_eq!(
+            snippet_generator.snippet(&["The quick brown fox jumps over the lazy dog"]),
+            vec!["The quick brown <b>fox</b> jumps over the lazy dog"]
+        );
+        assert_eq!(
+            snippet_generator.snippet(&["a lazy cat", "a <red> fox & co"]),
+            vec!["a &lt;red&gt; <b>fox</b> &amp; co"]
+        );
+    }
+
+    #[test]
+    fn test_snippet_selects_best_fragments() {
+        let text = "Fox one two, three fox fox four five six seven eight nine fox";
+        assert_eq!(
+            snippet_generator(&["fox"], 2, 15).snippet(&[text]),
+            vec!["<b>Fox</b> one two", "three <b>fox</b> <b>fox</b>"]
+        );
+        assert_eq!(
+            snippet_generator(&["fox"], 3, 15).snippet(&[text]),
+            vec![
+                "<b>Fox</b> one two",
+                "three <b>fox</b> <b>fox</b>",
+                "nine <b>fox</b>"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_snippet_without_query_term_returns_leading_fragment() {
+        let snippet_generator = snippet_generator(&["cat"], 3, 15);
+        assert_eq!(
+            snippet_generator.snippet(&["", "The quick brown fox jumps over the lazy dog"]),
+            vec!["The quick brown"]
+        );
+        assert_eq!(
+            snippet_generator.snippet(&["!!!!!!!!!!!!!!!!!!!!"]),
+            vec!["!!!!!!!!!!!!!!!"]
+        );
+        assert!(snippet_generator.snippet(&[]).is_empty());
+    }
+
+    fn invalid_query_message(search_request: &SearchRequest, doc_mapper: &dyn DocMapper) -> String {
+        match build_snippet_generators(search_request, doc_mapper) {
+            Err(SearchError::InvalidQuery(message)) => message,
+            Err(error) => panic!("Expected an invalid query error, got `{:?}`.", error),
+            Ok(_) => panic!("Expected an invalid query error."),
+        }
+    }
+
+    #[test]
+    fn test_build_snippet_generators() {
+        let doc_mapper: DefaultDocMapper = serde_json::from_value(serde_json::json!({
+            "field_mappings": [
+                { "name": "title", "type": "text" },
+                { "name": "body", "type": "text", "stored": false },
+                { "name": "views", "type": "u64" }
+            ],
+            "default_search_fields": ["title", "body"]
+        }))
+        .unwrap();
+        let mut search_request = SearchRequest {
+            query: "title:fox OR body:dog".to_string(),
+            snippet_fields: vec!["title".to_string()],
+            snippet_max_fragments: Some(2),
+            ..Default::default()
+        };
+        search_request
+            .snippet_fragment_size_per_field
+            .insert("title".to_string(), 50);
+        let snippet_generators = build_snippet_generators(&search_request, &doc_mapper).unwrap();
+        assert_eq!(snippet_generators.len(), 1);
+        assert_eq!(snippet_generators[0].field_name, "title");
+        assert_eq!(
+            snippet_generators[0].terms,
+            HashSet::from(["fox".to_string()])
+        );
+        assert_eq!(snippet_generators[0].max_fragments, 2);
+        assert_eq!(snippet_generators[0].fragment_size, 50);
+
+        search_request.snippet_fields = vec!["body".to_string()];
+        assert_eq!(
+            invalid_query_message(&search_request, &doc_mapper),
+            "Snippet options are overridden for field `title`, which is not a snippet field."
+        );
+        search_request.snippet_fragment_size_per_field.clear();
+        assert_eq!(
+            invalid_query_message(&search_request, &doc_mapper),
+            "Field `body` cannot be used for snippets: it is neither stored nor reconstructed."
+        );
+        search_request.reconstruct_fields = vec!["body".to_string()];
+        assert!(build_snippet_generators(&search_request, &doc_mapper).is_ok());
+        search_request.snippet_fields = vec!["views".to_string()];
+        assert_eq!(
+            invalid_query_message(&search_request, &doc_mapper),
+            "Field `views` cannot be used for snippets: it is not an indexed text field."
+        );
+        search_request.snippet_fields = vec!["title".to_string()];
+        search_request.snippet_max_fragments = Some(0);
+        assert_eq!(
+            invalid_query_message(&search_request, &doc_mapper),
+            "Snippet option `snippet_max_fragments` must be strictly positive."
+        );
+    }
+}
@@ -0,0 +1,103 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+
+use quickwit_proto::{SplitIdAndFooterOffsets, SplitOrder};
+
+/// Sorts the splits in the order in which they should be searched.
+///
+/// Splits without a time range are searched last when ordering by time. Ties are broken by
+/// split id so that the resulting order is deterministic.
+pub(crate) fn sort_splits(splits: &mut [SplitIdAndFooterOffsets], split_order: SplitOrder) {
+    match split_order {
+        SplitOrder::Default => {}
+        SplitOrder::TimeDesc => splits.sort_by(|left, right| {
+            let left_key = (left.timestamp_end.is_none(), Reverse(left.timestamp_end));
+            let right_key = (right.timestamp_end.is_none(), Reverse(right.timestamp_end));
+            left_key
+                .cmp(&right_key)
+                .then_with(|| left.split_id.cmp(&right.split_id))
+        }),
+        SplitOrder::TimeAsc => splits.sort_by(|left, right| {
+            let left_key = (left.timestamp_start.is_none(), left.timestamp_start);
+            let right_key = (right.timestamp_start.is_none(), right.timestamp_start);
+            left_key
+                .cmp(&right_key)
+                .then_with(|| left.split_id.cmp(&right.split_id))
+        }),
+        // The footer is located at the end of the split file, so its end offset is the split
+        // size.
+        SplitOrder::SizeAsc => splits.sort_by(|left, right| {
+            left.split_footer_end
+                .cmp(&right.split_footer_end)
+                .then_with(|| left.split_id.cmp(&right.split_id))
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_split(
+        split_id: &str,
+        time_range_opt: Option<(i64, i64)>,
+        split_size: u64,
+    ) -> SplitIdAndFooterOffsets {
+        SplitIdAndFooterOffsets {
+            split_id: split_id.to_string(),
+            split_footer_start: split_size / 2,
+            split_footer_end: split_size,
+            timestamp_start: time_range_opt.map(|(start, _)| start),
+            timestamp_end: time_range_opt.map(|(_, end)| end),
+        }
+    }
+
+    fn sorted_split_ids(split_order: SplitOrder) -> Vec<String> {
+        let mut splits = vec![
+            mock_split("split-1", Some((10, 20)), 300),
+            mock_split("split-2", None, 100),
+            mock_split("split-3", Some((0, 30)), 200),
+            mock_split("split-4", Some((5, 15)), 400),
+        ];
+        sort_splits(&mut splits, split_order);
+        splits.into_iter().map(|split| split.split_id).collect()
+    }
+
+    #[test]
+    fn test_sort_splits() {
+        assert_eq!(
+            sorted_split_ids(SplitOrder::Default),
+            ["split-1", "split-2", "split-3", "split-4"]
+        );
+        assert_eq!(
+            sorted_split_ids(SplitOrder::TimeDesc),
+            ["split-3", "split-1", "split-4", "split-2"]
+        );
+        assert_eq!(
+            sorted_split_ids(SplitOrder::TimeAsc),
+            ["split-3", "split-4", "split-1", "split-2"]
+        );
+        assert_eq!(
+            sorted_split_ids(SplitOrder::SizeAsc),
+            ["split-2", "split-3", "split-1", "split-4"]
+        );
+    }
+}
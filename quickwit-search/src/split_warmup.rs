@@ -0,0 +1,190 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use futures::{stream, StreamExt};
+use quickwit_metastore::{Metastore, SplitMetadata, SplitState};
+use quickwit_proto::SplitIdAndFooterOffsets;
+use quickwit_storage::{Storage, StorageUriResolver};
+use tantivy::ReloadPolicy;
+use tracing::{info, warn};
+
+use crate::extract_split_and_footer_offsets;
+use crate::leaf::{
+    acquire_open_split_permit, get_split_footer_from_cache_or_fetch, open_index,
+    open_split_semaphore, warm_up_fastfields,
+};
+
+/// Maximum number of splits warmed up concurrently.
+const MAX_CONCURRENT_SPLIT_WARMUPS: usize = 10;
+
+/// Warms up the split footer cache, and optionally the fast field cache, with the
+/// `num_splits_per_index` most recent published splits of each index of `index_ids`.
+///
+/// Splits that fail to warm up are skipped. Returns the number of splits warmed up.
+pub(crate) async fn warm_up_recent_splits(
+    index_ids: &[String],
+    num_splits_per_index: usize,
+    warm_up_fast_fields: bool,
+    metastore: &dyn Metastore,
+    storage_resolver: &StorageUriResolver,
+) -> anyhow::Result<usize> {
+    let mut num_warmed_up_splits = 0;
+    for index_id in index_ids {
+        let index_metadata = metastore.index_metadata(index_id).await?;
+        let index_storage = storage_resolver.resolve_with_storage_config(
+            &index_metadata.index_uri,
+            index_metadata.storage_config.as_ref(),
+        )?;
+        let split_metadatas: Vec<SplitMetadata> = metastore
+            .list_splits(index_id, SplitState::Published, None, None)
+            .await?
+            .into_iter()
+            .map(|split| split.split_metadata)
+            .collect();
+        let recent_splits = select_recent_splits(split_metadatas, num_splits_per_index);
+        let num_index_warmed_up_splits = stream::iter(recent_splits.iter().map(|split| {
+            let index_storage = index_storage.clone();
+            async move {
+                if let Err(error) = warm_up_split(index_storage, split, warm_up_fast_fields).await {
+                    warn!(
+                        index_id = %index_id,
+                        split_id = %split.split_id,
+                        error = ?error,
+                        "Failed to warm up split."
+                    );
+                    return false;
+                }
+                true
+            }
+        }))
+        .buffer_unordered(MAX_CONCURRENT_SPLIT_WARMUPS)
+        .filter(|warmed_up| futures::future::ready(*warmed_up))
+        .count()
+        .await;
+        info!(
+            index_id = %index_id,
+            num_splits = num_index_warmed_up_splits,
+            "Warmed up recent splits."
+        );
+        num_warmed_up_splits += num_index_warmed_up_splits;
+    }
+    Ok(num_warmed_up_splits)
+}
+
+/// Returns the footer offsets of the `num_splits` most recent splits. Splits are ordered by the
+/// end of their time range, then by creation timestamp.
+fn select_recent_splits(
+    mut split_metadatas: Vec<SplitMetadata>,
+    num_splits: usize,
+) -> Vec<SplitIdAndFooterOffsets> {
+    split_metadatas.sort_by_key(|split_metadata| {
+        Reverse((
+            split_metadata
+                .time_range
+                .as_ref()
+                .map(|time_range| *time_range.end()),
+            split_metadata.create_timestamp,
+        ))
+    });
+    split_metadatas
+        .iter()
+        .take(num_splits)
+        .map(extract_split_and_footer_offsets)
+        .collect()
+}
+
+async fn warm_up_split(
+    index_storage: Arc<dyn Storage>,
+    split: &SplitIdAndFooterOffsets,
+    warm_up_fast_fields: bool,
+) -> anyhow::Result<()> {
+    if !warm_up_fast_fields {
+        get_split_footer_from_cache_or_fetch(index_storage, split).await?;
+        return Ok(());
+    }
+    let _open_split_permit = acquire_open_split_permit(open_split_semaphore()).await;
+    let index = open_index(index_storage, split).await?;
+    let fast_field_names: HashSet<String> = index
+        .schema()
+        .fields()
+        .filter(|(_, field_entry)| field_entry.is_fast())
+        .map(|(_, field_entry)| field_entry.name().to_string())
+        .collect();
+    let reader = index
+        .reader_builder()
+        .reload_policy(ReloadPolicy::Manual)
+        .try_into()?;
+    warm_up_fastfields(&reader.searcher(), &fast_field_names).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use quickwit_indexing::TestSandbox;
+    use serde_json::json;
+
+    use super::*;
+    use crate::leaf::split_footer_cache_contains;
+
+    #[tokio::test]
+    async fn test_warm_up_recent_splits() -> anyhow::Result<()> {
+        let index_id = "warm-up-recent-splits";
+        let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: i64
+                fast: true
+        "#;
+        let indexing_settings_yaml = "timestamp_field: ts";
+        let test_sandbox = TestSandbox::create(
+            index_id,
+            doc_mapping_yaml,
+            indexing_settings_yaml,
+            &["body"],
+        )
+        .await?;
+        for timestamp in [1_000, 2_000, 3_000] {
+            test_sandbox
+                .add_documents(vec![json!({ "body": "hello", "ts": timestamp })])
+                .await?;
+        }
+        let mut splits = test_sandbox.metastore().list_all_splits(index_id).await?;
+        assert_eq!(splits.len(), 3);
+        splits.sort_by_key(|split| split.split_metadata.time_range.clone().map(|r| *r.start()));
+        let num_warmed_up_splits = warm_up_recent_splits(
+            &[index_id.to_string()],
+            2,
+            false,
+            &*test_sandbox.metastore(),
+            &test_sandbox.storage_uri_resolver(),
+        )
+        .await?;
+        assert_eq!(num_warmed_up_splits, 2);
+        assert!(!split_footer_cache_contains(splits[0].split_id()));
+        assert!(split_footer_cache_contains(splits[1].split_id()));
+        assert!(split_footer_cache_contains(splits[2].split_id()));
+        Ok(())
+    }
+}
@@ -17,16 +17,28 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
-use std::collections::BTreeSet;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
 
 use assert_json_diff::assert_json_include;
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+use quickwit_config::AggregationBucketsLimitPolicy;
 use quickwit_doc_mapper::DefaultDocMapper;
 use quickwit_indexing::TestSandbox;
-use quickwit_proto::{LeafHit, SearchRequest, SortOrder};
+use quickwit_proto::{
+    FetchDocsRequest, LeafHit, LeafSearchRequest, SearchRequest, SortOrder, SplitOrder,
+};
+use quickwit_storage::{LocalFileCache, OwnedBytes, PutPayload, Storage, StorageResult};
 use serde_json::json;
+use tokio::sync::Semaphore;
 
 use super::*;
-use crate::single_node_search;
+use crate::leaf::leaf_search_with_open_split_semaphore;
+use crate::{single_node_search, single_node_search_with_searcher_config};
 
 #[tokio::test]
 async fn test_single_node_simple() -> anyhow::Result<()> {
@@ -72,6 +84,510 @@ async fn test_single_node_simple() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_dynamic_fields() -> anyhow::Result<()> {
+    let index_id = "single-node-dynamic-fields";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+            mode: dynamic
+            dynamic_mapping:
+              stored: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![json!({"body": "a red bike", "owner": {"name": "fulmicoton"}})];
+    test_sandbox.add_documents(docs).await?;
+    let mut search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "bike".to_string(),
+        max_hits: 1,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    let hit_json: serde_json::Value = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_eq!(
+        hit_json,
+        json!({"body": "a red bike", "owner": {"name": "fulmicoton"}})
+    );
+
+    search_request.exclude_dynamic_fields = true;
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    let hit_json: serde_json::Value = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_eq!(hit_json, json!({"body": "a red bike"}));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_reconstruct_fields() -> anyhow::Result<()> {
+    let index_id = "single-node-reconstruct-fields";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+                stored: false
+                record: position
+              - name: body
+                type: text
+                stored: false
+              - name: views
+                type: u64
+                stored: false
+                fast: true
+              - name: owner
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["title"]).await?;
+    let docs = vec![
+        json!({"title": "The quick brown fox", "body": "jumps", "views": 42, "owner": "paul"}),
+        json!({"title": "A lazy dog", "body": "sleeps", "views": 7, "owner": "fulmicoton"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let mut search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "fox".to_string(),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let hit_json: serde_json::Value = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_eq!(hit_json, json!({"owner": "paul"}));
+
+    search_request.reconstruct_fields = vec![
+        "title".to_string(),
+        "views".to_string(),
+        "owner".to_string(),
+    ];
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let hit_json: serde_json::Value = serde_json::from_str(&single_node_result.hits[0].json)?;
+    assert_eq!(
+        hit_json,
+        json!({"title": "the quick brown fox", "views": 42, "owner": "paul"})
+    );
+
+    search_request.reconstruct_fields = vec!["body".to_string()];
+    let single_node_error = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(
+        single_node_error,
+        SearchError::InvalidQuery(message) if message.starts_with("Field `body` cannot be reconstructed")
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_snippets() -> anyhow::Result<()> {
+    let index_id = "single-node-snippets";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "Foxes", "body": "The quick brown fox jumps over the lazy dog. The fox runs away."}),
+        json!({"title": "Dogs", "body": "A lazy dog sleeps all day long under the warm sun."}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let mut search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "fox".to_string(),
+        max_hits: 10,
+        snippet_fields: vec!["body".to_string()],
+        snippet_fragment_size: Some(20),
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let snippet_json: serde_json::Value =
+        serde_json::from_str(single_node_result.hits[0].snippet_json.as_ref().unwrap())?;
+    assert_eq!(
+        snippet_json,
+        json!({"body": ["The quick brown <b>fox</b>", "dog. The <b>fox</b> runs"]})
+    );
+
+    search_request.query = "title:dogs".to_string();
+    search_request
+        .snippet_max_fragments_per_field
+        .insert("body".to_string(), 1);
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.hits.len(), 1);
+    let snippet_json: serde_json::Value =
+        serde_json::from_str(single_node_result.hits[0].snippet_json.as_ref().unwrap())?;
+    assert_eq!(snippet_json, json!({"body": ["A lazy dog sleeps"]}));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_explain_bytes_per_hit() -> anyhow::Result<()> {
+    let index_id = "single-node-explain-bytes-per-hit";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "one", "body": "a red bike"}),
+        json!({"title": "two", "body": "a small blue bike"}),
+        json!({"title": "three", "body": "a small, rusty, and yellow bike"}),
+        json!({"title": "four", "body": "a tiny shelter"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "bike".to_string(),
+        search_fields: vec!["body".to_string()],
+        max_hits: 10,
+        explain_bytes_per_hit: true,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 3);
+    assert_eq!(single_node_result.hits.len(), 3);
+    let explanation = single_node_result.explanation.unwrap();
+    assert!(explanation.num_bytes_fetched > 0);
+    assert_eq!(
+        explanation.bytes_per_hit,
+        Some(explanation.num_bytes_fetched as f64 / 3.0)
+    );
+
+    let search_request = SearchRequest {
+        explain_bytes_per_hit: false,
+        ..search_request
+    };
+    let single_node_result = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert!(single_node_result.explanation.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_search_service_does_not_count_local_file_cache_hits_as_bytes_fetched(
+) -> anyhow::Result<()> {
+    let index_id = "search-service-bytes-fetched";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: title
+                type: text
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    let docs = vec![
+        json!({"title": "one", "body": "a red bike"}),
+        json!({"title": "two", "body": "a small blue bike"}),
+        json!({"title": "three", "body": "a tiny shelter"}),
+    ];
+    test_sandbox.add_documents(docs).await?;
+    let metastore = test_sandbox.metastore();
+    let index_metadata = metastore.index_metadata(index_id).await?;
+    let split_offsets: Vec<SplitIdAndFooterOffsets> = metastore
+        .list_all_splits(index_id)
+        .await?
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    let local_cache_dir = tempfile::tempdir()?;
+    let local_file_cache = LocalFileCache::open(
+        local_cache_dir.path().to_path_buf(),
+        10_000_000,
+        vec![".split".to_string()],
+    )?;
+    let client_pool = SearchClientPool::from_mocks(Vec::new()).await?;
+    let search_service = SearchServiceImpl::new(
+        metastore,
+        test_sandbox.storage_uri_resolver(),
+        ClusterClient::new(client_pool.clone()),
+        client_pool,
+    )
+    .with_local_file_cache(Arc::new(local_file_cache));
+
+    let leaf_search_request = LeafSearchRequest {
+        search_request: Some(SearchRequest {
+            index_id: index_id.to_string(),
+            query: "bike".to_string(),
+            max_hits: 10,
+            ..Default::default()
+        }),
+        split_offsets: split_offsets.clone(),
+        doc_mapper: serde_json::to_string(&test_sandbox.doc_mapper())?,
+        index_uri: index_metadata.index_uri.to_string(),
+        index_storage_config: None,
+    };
+    let leaf_search_response = search_service.leaf_search(leaf_search_request).await?;
+    assert_eq!(leaf_search_response.partial_hits.len(), 2);
+    assert!(leaf_search_response.num_bytes_fetched > 0);
+
+    let fetch_docs_request = FetchDocsRequest {
+        partial_hits: leaf_search_response.partial_hits,
+        index_id: index_id.to_string(),
+        split_offsets,
+        index_uri: index_metadata.index_uri.to_string(),
+        index_storage_config: None,
+    };
+    let fetch_docs_response = search_service
+        .fetch_docs(fetch_docs_request.clone())
+        .await?;
+    assert_eq!(fetch_docs_response.hits.len(), 2);
+    assert!(fetch_docs_response.num_bytes_fetched > 0);
+
+    // The doc store blocks are now served by the local file cache.
+    let fetch_docs_response = search_service.fetch_docs(fetch_docs_request).await?;
+    assert_eq!(fetch_docs_response.hits.len(), 2);
+    assert_eq!(fetch_docs_response.num_bytes_fetched, 0);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_split_exporter() -> anyhow::Result<()> {
+    let index_id = "split-exporter";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: i64
+                fast: true
+        "#;
+    let indexing_settings_json = r#"{
+            "timestamp_field": "ts"
+        }"#;
+    let test_sandbox = TestSandbox::create(
+        index_id,
+        doc_mapping_yaml,
+        indexing_settings_json,
+        &["body"],
+    )
+    .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"body": "info", "ts": 100}),
+            json!({"body": "error", "ts": 101}),
+            json!({"body": "info", "ts": 102}),
+        ])
+        .await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({"body": "info", "ts": 200}),
+            json!({"body": "info", "ts": 201}),
+        ])
+        .await?;
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "info".to_string(),
+        start_timestamp: Some(101),
+        ..Default::default()
+    };
+    let split_exporter = SplitExporter::new(
+        search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    let splits = split_exporter
+        .list_splits(&*test_sandbox.metastore())
+        .await?;
+    assert_eq!(splits.len(), 2);
+    assert!(splits[0].split_id < splits[1].split_id);
+
+    let mut exported_timestamps = Vec::new();
+    for split in &splits {
+        let doc_addresses = split_exporter.collect_doc_addresses(split).await?;
+        assert!(doc_addresses.windows(2).all(|window| window[0] < window[1]));
+        // Fetching the documents page by page yields them in the order of their address.
+        for doc_addresses_page in doc_addresses.chunks(1) {
+            let docs = split_exporter.fetch_docs(split, doc_addresses_page).await?;
+            assert_eq!(docs.len(), doc_addresses_page.len());
+            for doc in docs {
+                let doc_json: serde_json::Value = serde_json::from_str(&doc)?;
+                exported_timestamps.push(doc_json["ts"].as_i64().unwrap());
+            }
+        }
+    }
+    exported_timestamps.sort_unstable();
+    assert_eq!(exported_timestamps, [102, 200, 201]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_explain_split_selection_order() -> anyhow::Result<()> {
+    let index_id = "single-node-explain-split-selection-order";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: i64
+                fast: true
+        "#;
+    let indexing_settings_json = r#"{
+            "timestamp_field": "ts"
+        }"#;
+    let test_sandbox = TestSandbox::create(
+        index_id,
+        doc_mapping_yaml,
+        indexing_settings_json,
+        &["body"],
+    )
+    .await?;
+    // Each batch of documents lands in its own split.
+    for (start_ts, num_docs) in [(100, 3), (300, 1), (200, 10)] {
+        let docs: Vec<serde_json::Value> = (0..num_docs)
+            .map(|i| json!({"body": format!("info @ t:{}", start_ts + i), "ts": start_ts + i}))
+            .collect();
+        test_sandbox.add_documents(docs).await?;
+    }
+    let splits = test_sandbox.metastore().list_all_splits(index_id).await?;
+    assert_eq!(splits.len(), 3);
+    let split_id_for_time_start = |time_start: i64| {
+        splits
+            .iter()
+            .find(|split| {
+                split
+                    .split_metadata
+                    .time_range
+                    .as_ref()
+                    .map(|range| *range.start())
+                    == Some(time_start)
+            })
+            .unwrap()
+            .split_id()
+            .to_string()
+    };
+    let (split_100, split_200, split_300) = (
+        split_id_for_time_start(100),
+        split_id_for_time_start(200),
+        split_id_for_time_start(300),
+    );
+    let mut splits_by_size = splits.clone();
+    splits_by_size.sort_by_key(|split| split.split_metadata.footer_offsets.end);
+    let expected_size_order: Vec<String> = splits_by_size
+        .iter()
+        .map(|split| split.split_id().to_string())
+        .collect();
+
+    for (split_order, expected_order) in [
+        (
+            SplitOrder::TimeDesc,
+            vec![split_300.clone(), split_200.clone(), split_100.clone()],
+        ),
+        (SplitOrder::TimeAsc, vec![split_100, split_200, split_300]),
+        (SplitOrder::SizeAsc, expected_size_order),
+    ] {
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query: "info".to_string(),
+            max_hits: 20,
+            split_order: split_order as i32,
+            explain_split_selection_order: true,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            &search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_uri_resolver(),
+        )
+        .await?;
+        assert_eq!(single_node_result.num_hits, 14);
+        let explanation = single_node_result.explanation.unwrap();
+        assert_eq!(explanation.split_selection_order, expected_order);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_explain_doc_mapper_mode() -> anyhow::Result<()> {
+    for (index_id, mode) in [
+        ("single-node-explain-doc-mapper-mode-strict", "strict"),
+        ("single-node-explain-doc-mapper-mode-dynamic", "dynamic"),
+    ] {
+        let doc_mapping_yaml = format!(
+            r#"
+            field_mappings:
+              - name: body
+                type: text
+            mode: {}
+        "#,
+            mode
+        );
+        let test_sandbox =
+            TestSandbox::create(index_id, &doc_mapping_yaml, "{}", &["body"]).await?;
+        test_sandbox
+            .add_documents(vec![json!({"body": "a red bike"})])
+            .await?;
+        let search_request = SearchRequest {
+            index_id: index_id.to_string(),
+            query: "bike".to_string(),
+            search_fields: vec!["body".to_string()],
+            max_hits: 10,
+            explain_doc_mapper_mode: true,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search(
+            &search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_uri_resolver(),
+        )
+        .await?;
+        assert_eq!(single_node_result.num_hits, 1);
+        let explanation = single_node_result.explanation.unwrap();
+        assert_eq!(explanation.doc_mapping_mode, mode);
+        assert!(explanation.split_selection_order.is_empty());
+    }
+    Ok(())
+}
+
 async fn slop_search_and_check(
     test_sandbox: &TestSandbox,
     index_id: &str,
@@ -317,6 +833,71 @@ async fn test_single_node_filtering() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_count_only() -> anyhow::Result<()> {
+    let index_id = "single-node-count-only";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: ts
+                type: i64
+                fast: true
+        "#;
+    let indexing_settings_json = r#"{
+            "timestamp_field": "ts",
+            "sort_field": "ts",
+            "sort_order": "desc"
+        }"#;
+    let test_sandbox = TestSandbox::create(
+        index_id,
+        doc_mapping_yaml,
+        indexing_settings_json,
+        &["body"],
+    )
+    .await?;
+    let mut docs = vec![];
+    for i in 0..30 {
+        let body = format!("info @ t:{}", i + 1);
+        docs.push(json!({"body": body, "ts": i+1}));
+    }
+    test_sandbox.add_documents(docs).await?;
+
+    let mut search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "info".to_string(),
+        max_hits: 10,
+        sort_by_field: Some("ts".to_string()),
+        count_only: true,
+        ..Default::default()
+    };
+    let single_node_response = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_response.num_hits, 30);
+    assert!(single_node_response.hits.is_empty());
+
+    search_request.start_timestamp = Some(10);
+    search_request.end_timestamp = Some(20);
+    let single_node_response = single_node_search(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_response.num_hits, 10);
+    assert!(single_node_response.hits.is_empty());
+
+    let search_response_rest = SearchResponseRest::try_from(single_node_response)?;
+    let search_response_json = serde_json::to_value(&search_response_rest)?;
+    assert_eq!(search_response_json["num_hits"], 10);
+    assert_eq!(search_response_json["hits"], json!([]));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_sorting_with_query() -> anyhow::Result<()> {
     let index_id = "single-node-sorting";
@@ -423,17 +1004,89 @@ async fn test_single_node_invalid_sorting_with_query() -> anyhow::Result<()> {
     let single_node_response = single_node_search(
         &search_request,
         &*test_sandbox.metastore(),
-        test_sandbox.storage_uri_resolver(),
+        test_sandbox.storage_uri_resolver(),
+    )
+    .await?;
+    assert_eq!(single_node_response.errors.len(), 1);
+    assert!(single_node_response.errors[0]
+        .contains("Sort by field on type text is currently not supported"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_split_pruning_by_tags() -> anyhow::Result<()> {
+    let doc_mapping_yaml = r#"
+            tag_fields:
+              - owner
+            field_mappings:
+              - name: owner
+                type: text
+                tokenizer: raw
+        "#;
+    let index_id = "single-node-pruning-by-tags";
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[]).await?;
+    let owners = ["paul", "adrien"];
+    for owner in owners {
+        let mut docs = vec![];
+        for i in 0..10 {
+            docs.push(json!({"body": format!("content num #{}", i + 1), "owner": owner}));
+        }
+        test_sandbox.add_documents(docs).await?;
+    }
+
+    let selected_splits = list_relevant_splits(
+        &SearchRequest {
+            index_id: index_id.to_string(),
+            query: "owner:francois".to_string(),
+            ..Default::default()
+        },
+        TimestampPrecision::Seconds,
+        &*test_sandbox.metastore(),
+    )
+    .await?;
+    assert!(selected_splits.is_empty());
+
+    let selected_splits = list_relevant_splits(
+        &SearchRequest {
+            index_id: index_id.to_string(),
+            query: "".to_string(),
+            ..Default::default()
+        },
+        TimestampPrecision::Seconds,
+        &*test_sandbox.metastore(),
+    )
+    .await?;
+    assert_eq!(selected_splits.len(), 2);
+
+    let selected_splits = list_relevant_splits(
+        &SearchRequest {
+            index_id: index_id.to_string(),
+            query: "owner:francois OR owner:paul OR owner:adrien".to_string(),
+            ..Default::default()
+        },
+        TimestampPrecision::Seconds,
+        &*test_sandbox.metastore(),
     )
     .await?;
-    assert_eq!(single_node_response.errors.len(), 1);
-    assert!(single_node_response.errors[0]
-        .contains("Sort by field on type text is currently not supported"));
+    assert_eq!(selected_splits.len(), 2);
+
+    let split_tags: BTreeSet<String> = selected_splits
+        .iter()
+        .flat_map(|split| split.tags.clone())
+        .collect();
+    assert_eq!(
+        split_tags
+            .iter()
+            .map(|tag| tag.as_str())
+            .collect::<Vec<&str>>(),
+        vec!["owner!", "owner:adrien", "owner:paul"]
+    );
+
     Ok(())
 }
 
 #[tokio::test]
-async fn test_single_node_split_pruning_by_tags() -> anyhow::Result<()> {
+async fn test_single_node_split_pruning_by_required_tags() -> anyhow::Result<()> {
     let doc_mapping_yaml = r#"
             tag_fields:
               - owner
@@ -442,9 +1095,9 @@ async fn test_single_node_split_pruning_by_tags() -> anyhow::Result<()> {
                 type: text
                 tokenizer: raw
         "#;
-    let index_id = "single-node-pruning-by-tags";
+    let index_id = "single-node-pruning-by-required-tags";
     let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &[]).await?;
-    let owners = ["paul", "adrien"];
+    let owners = ["paul", "adrien", "francois"];
     for owner in owners {
         let mut docs = vec![];
         for i in 0..10 {
@@ -456,48 +1109,72 @@ async fn test_single_node_split_pruning_by_tags() -> anyhow::Result<()> {
     let selected_splits = list_relevant_splits(
         &SearchRequest {
             index_id: index_id.to_string(),
-            query: "owner:francois".to_string(),
+            query: "*".to_string(),
+            required_tags: vec!["owner:paul".to_string()],
             ..Default::default()
         },
+        TimestampPrecision::Seconds,
         &*test_sandbox.metastore(),
     )
     .await?;
-    assert!(selected_splits.is_empty());
+    assert_eq!(selected_splits.len(), 1);
+    assert!(selected_splits[0].tags.contains("owner:paul"));
 
+    // Required tags are combined with the tags extracted from the query.
     let selected_splits = list_relevant_splits(
         &SearchRequest {
             index_id: index_id.to_string(),
-            query: "".to_string(),
+            query: "owner:paul OR owner:adrien".to_string(),
+            required_tags: vec!["owner:adrien".to_string()],
             ..Default::default()
         },
+        TimestampPrecision::Seconds,
         &*test_sandbox.metastore(),
     )
     .await?;
-    assert_eq!(selected_splits.len(), 2);
+    assert_eq!(selected_splits.len(), 1);
+    assert!(selected_splits[0].tags.contains("owner:adrien"));
 
     let selected_splits = list_relevant_splits(
         &SearchRequest {
             index_id: index_id.to_string(),
-            query: "owner:francois OR owner:paul OR owner:adrien".to_string(),
+            query: "*".to_string(),
+            required_tags: vec!["owner:paul".to_string(), "owner:adrien".to_string()],
             ..Default::default()
         },
+        TimestampPrecision::Seconds,
         &*test_sandbox.metastore(),
     )
     .await?;
-    assert_eq!(selected_splits.len(), 2);
+    assert!(selected_splits.is_empty());
 
-    let split_tags: BTreeSet<String> = selected_splits
-        .iter()
-        .flat_map(|split| split.tags.clone())
-        .collect();
-    assert_eq!(
-        split_tags
-            .iter()
-            .map(|tag| tag.as_str())
-            .collect::<Vec<&str>>(),
-        vec!["owner!", "owner:adrien", "owner:paul"]
-    );
+    // Splits are not pruned on fields that are not tag fields.
+    let selected_splits = list_relevant_splits(
+        &SearchRequest {
+            index_id: index_id.to_string(),
+            query: "*".to_string(),
+            required_tags: vec!["body:foo".to_string()],
+            ..Default::default()
+        },
+        TimestampPrecision::Seconds,
+        &*test_sandbox.metastore(),
+    )
+    .await?;
+    assert_eq!(selected_splits.len(), 3);
 
+    let error = list_relevant_splits(
+        &SearchRequest {
+            index_id: index_id.to_string(),
+            query: "*".to_string(),
+            required_tags: vec!["owner".to_string()],
+            ..Default::default()
+        },
+        TimestampPrecision::Seconds,
+        &*test_sandbox.metastore(),
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(error, SearchError::InvalidArgument(_)));
     Ok(())
 }
 
@@ -515,6 +1192,8 @@ async fn test_search_dynamic_util(test_sandbox: &TestSandbox, query: &str) -> Ve
             split_id: split_meta.split_id().to_string(),
             split_footer_start: split_meta.split_metadata.footer_offsets.start,
             split_footer_end: split_meta.split_metadata.footer_offsets.end,
+            timestamp_start: None,
+            timestamp_end: None,
         })
         .collect();
     let request = quickwit_proto::SearchRequest {
@@ -571,11 +1250,282 @@ async fn test_search_dynamic_mode() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Storage wrapper keeping track of the maximum number of splits read at the same time.
+struct OpenSplitCountingStorage {
+    underlying: Arc<dyn Storage>,
+    num_ongoing_reads_per_split: Mutex<HashMap<PathBuf, usize>>,
+    max_num_splits_read_concurrently: AtomicUsize,
+}
+
+impl OpenSplitCountingStorage {
+    fn new(underlying: Arc<dyn Storage>) -> Self {
+        Self {
+            underlying,
+            num_ongoing_reads_per_split: Mutex::default(),
+            max_num_splits_read_concurrently: AtomicUsize::new(0),
+        }
+    }
+
+    fn start_read(&self, path: &Path) {
+        let mut num_ongoing_reads_per_split = self.num_ongoing_reads_per_split.lock().unwrap();
+        *num_ongoing_reads_per_split
+            .entry(path.to_path_buf())
+            .or_default() += 1;
+        self.max_num_splits_read_concurrently
+            .fetch_max(num_ongoing_reads_per_split.len(), Ordering::SeqCst);
+    }
+
+    fn end_read(&self, path: &Path) {
+        let mut num_ongoing_reads_per_split = self.num_ongoing_reads_per_split.lock().unwrap();
+        let num_ongoing_reads = num_ongoing_reads_per_split.get_mut(path).unwrap();
+        *num_ongoing_reads -= 1;
+        if *num_ongoing_reads == 0 {
+            num_ongoing_reads_per_split.remove(path);
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for OpenSplitCountingStorage {
+    async fn check(&self) -> anyhow::Result<()> {
+        self.underlying.check().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.underlying.put(path, payload).await
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        self.underlying.copy_to_file(path, output_path).await
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        self.start_read(path);
+        // Slow reads down so that the reads of different splits overlap.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let get_slice_res = self.underlying.get_slice(path, range).await;
+        self.end_read(path);
+        get_slice_res
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        self.start_read(path);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        let get_all_res = self.underlying.get_all(path).await;
+        self.end_read(path);
+        get_all_res
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.underlying.delete(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.underlying.file_num_bytes(path).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.underlying.uri()
+    }
+}
+
+#[tokio::test]
+async fn test_leaf_search_max_open_splits() -> anyhow::Result<()> {
+    let index_id = "leaf-search-max-open-splits";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    for split_ord in 0..6u32 {
+        test_sandbox
+            .add_documents(vec![json!({ "body": format!("hello {}", split_ord) })])
+            .await?;
+    }
+    let splits_offsets: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_all_splits(index_id)
+        .await?
+        .into_iter()
+        .map(|split| SplitIdAndFooterOffsets {
+            split_id: split.split_id().to_string(),
+            split_footer_start: split.split_metadata.footer_offsets.start,
+            split_footer_end: split.split_metadata.footer_offsets.end,
+            timestamp_start: None,
+            timestamp_end: None,
+        })
+        .collect();
+    assert_eq!(splits_offsets.len(), 6);
+
+    let request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "hello".to_string(),
+        max_hits: 10,
+        ..Default::default()
+    };
+    let counting_storage = Arc::new(OpenSplitCountingStorage::new(test_sandbox.storage()));
+    let open_split_semaphore = Semaphore::new(2);
+    let leaf_search_response = leaf_search_with_open_split_semaphore(
+        &request,
+        counting_storage.clone(),
+        &splits_offsets,
+        test_sandbox.doc_mapper(),
+        &SearcherConfig::default(),
+        &open_split_semaphore,
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 6);
+
+    let max_num_splits_read_concurrently = counting_storage
+        .max_num_splits_read_concurrently
+        .load(Ordering::SeqCst);
+    assert!(max_num_splits_read_concurrently >= 1);
+    assert!(max_num_splits_read_concurrently <= 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_io_wait_cold_vs_warm() -> anyhow::Result<()> {
+    let index_id = "leaf-search-io-wait";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    test_sandbox
+        .add_documents(vec![json!({ "body": "hello" })])
+        .await?;
+    let splits_offsets: Vec<SplitIdAndFooterOffsets> = test_sandbox
+        .metastore()
+        .list_all_splits(index_id)
+        .await?
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    let request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "hello".to_string(),
+        max_hits: 10,
+        explain_io_wait: true,
+        ..Default::default()
+    };
+    // Every read is slowed down, so skipping the split footer fetch on the warm run saves IO
+    // wait time.
+    let slow_storage = Arc::new(OpenSplitCountingStorage::new(test_sandbox.storage()));
+    let cold_leaf_search_response = leaf_search(
+        &request,
+        slow_storage.clone(),
+        &splits_offsets,
+        test_sandbox.doc_mapper(),
+    )
+    .await?;
+    let warm_leaf_search_response = leaf_search(
+        &request,
+        slow_storage,
+        &splits_offsets,
+        test_sandbox.doc_mapper(),
+    )
+    .await?;
+    assert_eq!(cold_leaf_search_response.num_hits, 1);
+    assert_eq!(warm_leaf_search_response.num_hits, 1);
+    assert!(cold_leaf_search_response.io_wait_micros >= 20_000);
+    assert!(cold_leaf_search_response.io_wait_micros > warm_leaf_search_response.io_wait_micros);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_leaf_search_sort_field_coverage() -> anyhow::Result<()> {
+    let index_id = "leaf-search-sort-field-coverage";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+              - name: response_time
+                type: u64
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    test_sandbox
+        .add_documents(vec![json!({ "body": "hello", "response_time": 12 })])
+        .await?;
+    // The splits of this second index are copied into the storage of the first index to simulate
+    // splits created before the sort field was added to the doc mapping.
+    let legacy_index_id = "leaf-search-sort-field-coverage-legacy";
+    let legacy_doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let legacy_test_sandbox =
+        TestSandbox::create(legacy_index_id, legacy_doc_mapping_yaml, "{}", &["body"]).await?;
+    legacy_test_sandbox
+        .add_documents(vec![json!({ "body": "hello" })])
+        .await?;
+    let mut splits = test_sandbox.metastore().list_all_splits(index_id).await?;
+    let legacy_splits = legacy_test_sandbox
+        .metastore()
+        .list_all_splits(legacy_index_id)
+        .await?;
+    for legacy_split in &legacy_splits {
+        let split_file = quickwit_common::split_file(legacy_split.split_id());
+        let split_bytes = legacy_test_sandbox
+            .storage()
+            .get_all(Path::new(&split_file))
+            .await?;
+        test_sandbox
+            .storage()
+            .put(Path::new(&split_file), Box::new(split_bytes.to_vec()))
+            .await?;
+    }
+    splits.extend(legacy_splits);
+    let splits_offsets: Vec<SplitIdAndFooterOffsets> = splits
+        .iter()
+        .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+        .collect();
+    let request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "hello".to_string(),
+        max_hits: 10,
+        sort_by_field: Some("response_time".to_string()),
+        explain_sort_field_coverage: true,
+        ..Default::default()
+    };
+    let leaf_search_response = leaf_search(
+        &request,
+        test_sandbox.storage(),
+        &splits_offsets,
+        test_sandbox.doc_mapper(),
+    )
+    .await?;
+    assert_eq!(leaf_search_response.num_hits, 2);
+    assert!(leaf_search_response.failed_splits.is_empty());
+    assert_eq!(leaf_search_response.num_splits_with_sort_field, 1);
+    assert_eq!(leaf_search_response.num_splits_without_sort_field, 1);
+    Ok(())
+}
+
 #[track_caller]
 fn test_convert_leaf_hit_aux(
     default_doc_mapper_json: serde_json::Value,
     leaf_hit_json: serde_json::Value,
     expected_hit_json: serde_json::Value,
+) {
+    test_convert_leaf_hit_aux_with_exclusion(
+        default_doc_mapper_json,
+        leaf_hit_json,
+        false,
+        expected_hit_json,
+    );
+}
+
+#[track_caller]
+fn test_convert_leaf_hit_aux_with_exclusion(
+    default_doc_mapper_json: serde_json::Value,
+    leaf_hit_json: serde_json::Value,
+    exclude_dynamic_fields: bool,
+    expected_hit_json: serde_json::Value,
 ) {
     let default_doc_mapper: DefaultDocMapper =
         serde_json::from_value(default_doc_mapper_json).unwrap();
@@ -585,6 +1535,7 @@ fn test_convert_leaf_hit_aux(
             partial_hit: Default::default(),
         },
         &default_doc_mapper,
+        exclude_dynamic_fields,
     )
     .unwrap();
     let hit_json: serde_json::Value = serde_json::from_str(&hit.json).unwrap();
@@ -714,6 +1665,27 @@ fn test_convert_leaf_hit_with_source() {
     );
 }
 
+#[test]
+fn test_convert_leaf_hit_exclude_dynamic_fields() {
+    let doc_mapper_json = json!({
+        "field_mappings": [ {"name": "username", "type": "text"} ],
+        "mode": "dynamic"
+    });
+    let leaf_hit_json = json!({ "_dynamic": [{ "city": "Paris" }], "username": ["fulmicoton"] });
+    test_convert_leaf_hit_aux_with_exclusion(
+        doc_mapper_json.clone(),
+        leaf_hit_json.clone(),
+        false,
+        json!({ "username": "fulmicoton", "city": "Paris" }),
+    );
+    test_convert_leaf_hit_aux_with_exclusion(
+        doc_mapper_json,
+        leaf_hit_json,
+        true,
+        json!({ "username": "fulmicoton" }),
+    );
+}
+
 #[tokio::test]
 async fn test_single_node_aggregation() -> anyhow::Result<()> {
     let index_id = "single-node-agg-1";
@@ -788,6 +1760,187 @@ async fn test_single_node_aggregation() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_single_node_aggregation_max_buckets() -> anyhow::Result<()> {
+    let index_id = "single-node-agg-max-buckets";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: color
+                type: text
+                tokenizer: raw
+                fast: true
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["color"]).await?;
+    let docs = (0..50)
+        .map(|color_id| json!({ "color": format!("color-{}", color_id) }))
+        .collect();
+    test_sandbox.add_documents(docs).await?;
+    let agg_req = r#"
+ {
+   "colors": {
+     "terms": {
+       "field": "color",
+       "size": 50
+     }
+   }
+ }"#;
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "*".to_string(),
+        search_fields: vec!["color".to_string()],
+        max_hits: 0,
+        aggregation_request: Some(agg_req.to_string()),
+        ..Default::default()
+    };
+    {
+        let searcher_config = SearcherConfig {
+            max_aggregation_buckets: 5,
+            ..Default::default()
+        };
+        let single_node_result = single_node_search_with_searcher_config(
+            &search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_uri_resolver(),
+            &searcher_config,
+        )
+        .await?;
+        assert!(single_node_result.aggregation_truncated);
+        let agg_res_json: serde_json::Value =
+            serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+        assert_eq!(
+            agg_res_json["colors"]["buckets"].as_array().unwrap().len(),
+            5
+        );
+    }
+    {
+        let searcher_config = SearcherConfig {
+            max_aggregation_buckets: 5,
+            aggregation_buckets_limit_policy: AggregationBucketsLimitPolicy::Error,
+            ..Default::default()
+        };
+        let single_node_error = single_node_search_with_searcher_config(
+            &search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_uri_resolver(),
+            &searcher_config,
+        )
+        .await
+        .unwrap_err();
+        assert!(matches!(
+            single_node_error,
+            SearchError::InvalidAggregationRequest(_)
+        ));
+    }
+    {
+        // The leaves truncate their intermediate results before shipping them.
+        let splits_offsets: Vec<SplitIdAndFooterOffsets> = test_sandbox
+            .metastore()
+            .list_all_splits(index_id)
+            .await?
+            .iter()
+            .map(|split| extract_split_and_footer_offsets(&split.split_metadata))
+            .collect();
+        let searcher_config = SearcherConfig {
+            max_aggregation_buckets: 5,
+            ..Default::default()
+        };
+        let leaf_search_response = leaf_search_with_open_split_semaphore(
+            &search_request,
+            test_sandbox.storage(),
+            &splits_offsets,
+            test_sandbox.doc_mapper(),
+            &searcher_config,
+            &Semaphore::new(1),
+        )
+        .await?;
+        assert!(leaf_search_response.aggregation_truncated);
+        let intermediate_aggregation_result = leaf_search_response
+            .intermediate_aggregation_result
+            .unwrap();
+        let intermediate_agg_res_json: serde_json::Value =
+            serde_json::from_str(&intermediate_aggregation_result)?;
+        let term_entries = intermediate_agg_res_json
+            .pointer("/buckets/values/0/Terms/entries")
+            .and_then(serde_json::Value::as_object)
+            .unwrap();
+        assert_eq!(term_entries.len(), 5);
+    }
+    {
+        let single_node_result = single_node_search(
+            &search_request,
+            &*test_sandbox.metastore(),
+            test_sandbox.storage_uri_resolver(),
+        )
+        .await?;
+        assert!(!single_node_result.aggregation_truncated);
+        let agg_res_json: serde_json::Value =
+            serde_json::from_str(&single_node_result.aggregation.unwrap())?;
+        assert_eq!(
+            agg_res_json["colors"]["buckets"].as_array().unwrap().len(),
+            50
+        );
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_single_node_max_query_depth() -> anyhow::Result<()> {
+    let index_id = "single-node-max-query-depth";
+    let doc_mapping_yaml = r#"
+            field_mappings:
+              - name: body
+                type: text
+        "#;
+    let test_sandbox = TestSandbox::create(index_id, doc_mapping_yaml, "{}", &["body"]).await?;
+    test_sandbox
+        .add_documents(vec![
+            json!({ "body": "hello" }),
+            json!({ "body": "happy world" }),
+        ])
+        .await?;
+    let searcher_config = SearcherConfig {
+        max_query_depth: 2,
+        ..Default::default()
+    };
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "body:hello OR body:world".to_string(),
+        max_hits: 10,
+        explain_query_depth: true,
+        ..Default::default()
+    };
+    let single_node_result = single_node_search_with_searcher_config(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+        &searcher_config,
+    )
+    .await?;
+    assert_eq!(single_node_result.num_hits, 2);
+    let explanation = single_node_result.explanation.unwrap();
+    assert_eq!(explanation.query_depth, 2);
+    assert_eq!(explanation.query_num_clauses, 2);
+
+    let search_request = SearchRequest {
+        index_id: index_id.to_string(),
+        query: "body:hello OR (body:happy AND (body:world OR body:sad))".to_string(),
+        max_hits: 10,
+        explain_query_depth: true,
+        ..Default::default()
+    };
+    let single_node_error = single_node_search_with_searcher_config(
+        &search_request,
+        &*test_sandbox.metastore(),
+        test_sandbox.storage_uri_resolver(),
+        &searcher_config,
+    )
+    .await
+    .unwrap_err();
+    assert!(matches!(single_node_error, SearchError::InvalidQuery(_)));
+    assert!(single_node_error.to_string().contains("max_query_depth"));
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_single_node_aggregation_missing_fast_field() -> anyhow::Result<()> {
     let index_id = "single-node-agg-2";
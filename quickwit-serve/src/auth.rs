@@ -0,0 +1,150 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::sync::Arc;
+
+use hyper::header::AUTHORIZATION;
+use quickwit_config::ApiKeyConfig;
+use subtle::ConstantTimeEq;
+use tracing::debug;
+use warp::filters::BoxedFilter;
+use warp::{Filter, Rejection};
+
+/// Rejection of a request that does not carry a valid API key.
+#[derive(Debug)]
+pub(crate) struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+/// Returns the filter rejecting the requests whose `Authorization` header does not carry one of
+/// the `api_keys` as a bearer token. Every request passes when no key is configured.
+pub(crate) fn require_api_key(api_keys: &[ApiKeyConfig]) -> BoxedFilter<()> {
+    if api_keys.is_empty() {
+        return warp::any().boxed();
+    }
+    let api_keys = Arc::new(api_keys.to_vec());
+    warp::header::optional::<String>(AUTHORIZATION.as_str())
+        .and_then(move |authorization_opt: Option<String>| {
+            let api_keys = api_keys.clone();
+            async move {
+                if let Some(api_key) = authenticate(&api_keys, authorization_opt.as_deref()) {
+                    debug!(api_key_id = %api_key.id, "Authenticated request.");
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+        .boxed()
+}
+
+/// Returns the API key matching the bearer token of the `Authorization` header, if any.
+///
+/// The token is compared in constant time against every key, so that the time taken does not
+/// reveal how much of a key matched nor which key matched.
+fn authenticate<'a>(
+    api_keys: &'a [ApiKeyConfig],
+    authorization_opt: Option<&str>,
+) -> Option<&'a ApiKeyConfig> {
+    let token = authorization_opt?.strip_prefix("Bearer ")?.trim();
+    let mut authenticated_api_key_opt = None;
+    for api_key in api_keys {
+        let is_match: bool = api_key.key.as_bytes().ct_eq(token.as_bytes()).into();
+        if is_match && authenticated_api_key_opt.is_none() {
+            authenticated_api_key_opt = Some(api_key);
+        }
+    }
+    authenticated_api_key_opt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recover_fn;
+
+    fn api_keys_for_test() -> Vec<ApiKeyConfig> {
+        vec![
+            ApiKeyConfig {
+                id: "dashboard".to_string(),
+                key: "dashboard-secret".to_string(),
+            },
+            ApiKeyConfig {
+                id: "ingestion".to_string(),
+                key: "ingestion-secret".to_string(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_authenticate() {
+        let api_keys = api_keys_for_test();
+        assert_eq!(
+            authenticate(&api_keys, Some("Bearer ingestion-secret")).unwrap(),
+            &api_keys[1]
+        );
+        assert_eq!(
+            authenticate(&api_keys, Some("Bearer dashboard-secret")).unwrap(),
+            &api_keys[0]
+        );
+        assert!(authenticate(&api_keys, Some("Bearer dashboard")).is_none());
+        assert!(authenticate(&api_keys, Some("dashboard-secret")).is_none());
+        assert!(authenticate(&api_keys, Some("Basic dashboard-secret")).is_none());
+        assert!(authenticate(&api_keys, None).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_require_api_key() {
+        let handler = require_api_key(&api_keys_for_test())
+            .and(warp::path!("api" / "v1" / "indexes"))
+            .map(warp::reply)
+            .recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/api/v1/indexes")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = warp::test::request()
+            .path("/api/v1/indexes")
+            .header("authorization", "Bearer wrong-secret")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 401);
+
+        let resp = warp::test::request()
+            .path("/api/v1/indexes")
+            .header("authorization", "Bearer dashboard-secret")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_require_api_key_disabled() {
+        let handler = require_api_key(&[])
+            .and(warp::path!("api" / "v1" / "indexes"))
+            .map(warp::reply);
+        let resp = warp::test::request()
+            .path("/api/v1/indexes")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+}
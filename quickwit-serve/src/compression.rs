@@ -0,0 +1,271 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::convert::Infallible;
+use std::io::Write;
+
+use flate2::write::{GzEncoder, ZlibEncoder};
+use flate2::Compression;
+use hyper::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, VARY};
+use hyper::Body;
+use tracing::error;
+use warp::reply::Response;
+use warp::{Filter, Rejection, Reply};
+
+/// Responses smaller than this are not worth compressing.
+const MIN_COMPRESSION_NUM_BYTES: usize = 1_024;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum ContentEncoding {
+    Gzip,
+    Deflate,
+}
+
+impl ContentEncoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ContentEncoding::Gzip => "gzip",
+            ContentEncoding::Deflate => "deflate",
+        }
+    }
+
+    fn encode(&self, bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            ContentEncoding::Gzip => {
+                let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+            ContentEncoding::Deflate => {
+                let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+                encoder.write_all(bytes)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+/// Picks the encoding of the response from the `Accept-Encoding` header of the request,
+/// preferring gzip over deflate. Encodings with a zero quality value are refused.
+fn negotiate_content_encoding(accept_encoding: &str) -> Option<ContentEncoding> {
+    let mut deflate_accepted = false;
+    for directive in accept_encoding.split(',') {
+        let mut directive_parts = directive.split(';');
+        let coding = directive_parts
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let is_refused = directive_parts.any(|param| {
+            param
+                .trim()
+                .strip_prefix("q=")
+                .and_then(|quality| quality.trim().parse::<f32>().ok())
+                .map(|quality| quality == 0.0)
+                .unwrap_or(false)
+        });
+        if is_refused {
+            continue;
+        }
+        match coding.as_str() {
+            "gzip" | "x-gzip" | "*" => return Some(ContentEncoding::Gzip),
+            "deflate" => deflate_accepted = true,
+            _ => {}
+        }
+    }
+    deflate_accepted.then(|| ContentEncoding::Deflate)
+}
+
+/// Wraps the filter so that its replies are compressed according to the `Accept-Encoding`
+/// header of the request. The replies are passed through untouched if `compression_enabled` is
+/// false.
+pub(crate) fn with_compression<F, R>(
+    filter: F,
+    compression_enabled: bool,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    filter
+        .and(warp::header::optional::<String>(ACCEPT_ENCODING.as_str()))
+        .and_then(move |reply: R, accept_encoding_opt: Option<String>| {
+            compress_reply(reply, accept_encoding_opt, compression_enabled)
+        })
+}
+
+async fn compress_reply<R: Reply>(
+    reply: R,
+    accept_encoding_opt: Option<String>,
+    compression_enabled: bool,
+) -> Result<Response, Infallible> {
+    let response = reply.into_response();
+    if !compression_enabled {
+        return Ok(response);
+    }
+    let content_encoding_opt = accept_encoding_opt
+        .as_deref()
+        .and_then(negotiate_content_encoding);
+    if let Some(content_encoding) = content_encoding_opt {
+        Ok(compress_response(response, content_encoding).await)
+    } else {
+        Ok(response)
+    }
+}
+
+async fn compress_response(response: Response, content_encoding: ContentEncoding) -> Response {
+    if response.headers().contains_key(CONTENT_ENCODING) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let body_bytes = match hyper::body::to_bytes(body).await {
+        Ok(body_bytes) => body_bytes,
+        Err(error) => {
+            error!(error = ?error, "Failed to read the response body to compress.");
+            parts.status = hyper::StatusCode::INTERNAL_SERVER_ERROR;
+            parts.headers.remove(CONTENT_LENGTH);
+            return Response::from_parts(parts, Body::empty());
+        }
+    };
+    if body_bytes.len() < MIN_COMPRESSION_NUM_BYTES {
+        return Response::from_parts(parts, Body::from(body_bytes));
+    }
+    match content_encoding.encode(&body_bytes) {
+        Ok(compressed_bytes) => {
+            parts.headers.remove(CONTENT_LENGTH);
+            parts.headers.insert(
+                CONTENT_ENCODING,
+                HeaderValue::from_static(content_encoding.as_str()),
+            );
+            parts
+                .headers
+                .append(VARY, HeaderValue::from_static("accept-encoding"));
+            Response::from_parts(parts, Body::from(compressed_bytes))
+        }
+        Err(error) => {
+            error!(error = ?error, "Failed to compress the response body.");
+            Response::from_parts(parts, Body::from(body_bytes))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use flate2::read::{GzDecoder, ZlibDecoder};
+
+    use super::*;
+
+    fn large_json_body() -> Vec<u8> {
+        let hits: Vec<serde_json::Value> = (0..1_000)
+            .map(|doc_id| serde_json::json!({ "doc_id": doc_id, "body": "hello world" }))
+            .collect();
+        serde_json::to_vec(&serde_json::json!({ "hits": hits })).unwrap()
+    }
+
+    #[test]
+    fn test_negotiate_content_encoding() {
+        assert_eq!(
+            negotiate_content_encoding("gzip"),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(
+            negotiate_content_encoding("deflate, gzip;q=1.0"),
+            Some(ContentEncoding::Gzip)
+        );
+        assert_eq!(
+            negotiate_content_encoding("deflate"),
+            Some(ContentEncoding::Deflate)
+        );
+        assert_eq!(
+            negotiate_content_encoding("gzip;q=0, deflate"),
+            Some(ContentEncoding::Deflate)
+        );
+        assert_eq!(negotiate_content_encoding("br, identity"), None);
+        assert_eq!(negotiate_content_encoding(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_with_compression_gzip() {
+        let handler = with_compression(warp::path!("search").map(large_json_body), true);
+        let resp = warp::test::request()
+            .path("/search")
+            .header("accept-encoding", "gzip, deflate")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert!(resp.body().len() < large_json_body().len());
+        let mut decoded_body = Vec::new();
+        GzDecoder::new(&resp.body()[..])
+            .read_to_end(&mut decoded_body)
+            .unwrap();
+        assert_eq!(decoded_body, large_json_body());
+    }
+
+    #[tokio::test]
+    async fn test_with_compression_deflate() {
+        let handler = with_compression(warp::path!("search").map(large_json_body), true);
+        let resp = warp::test::request()
+            .path("/search")
+            .header("accept-encoding", "deflate")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers().get(CONTENT_ENCODING).unwrap(), "deflate");
+        let mut decoded_body = Vec::new();
+        ZlibDecoder::new(&resp.body()[..])
+            .read_to_end(&mut decoded_body)
+            .unwrap();
+        assert_eq!(decoded_body, large_json_body());
+    }
+
+    #[tokio::test]
+    async fn test_with_compression_passthrough() {
+        let handler = with_compression(warp::path!("search").map(large_json_body), true);
+        let resp = warp::test::request().path("/search").reply(&handler).await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(resp.body().as_ref(), large_json_body());
+
+        let disabled_handler = with_compression(warp::path!("search").map(large_json_body), false);
+        let resp = warp::test::request()
+            .path("/search")
+            .header("accept-encoding", "gzip")
+            .reply(&disabled_handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(resp.body().as_ref(), large_json_body());
+    }
+
+    #[tokio::test]
+    async fn test_with_compression_skips_small_responses() {
+        let handler = with_compression(warp::path!("search").map(|| "hello"), true);
+        let resp = warp::test::request()
+            .path("/search")
+            .header("accept-encoding", "gzip")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert!(resp.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(resp.body().as_ref(), b"hello");
+    }
+}
@@ -39,6 +39,7 @@ pub enum ServiceErrorCode {
     MethodNotAllowed,
     UnsupportedMediaType,
     BadRequest,
+    Unauthorized,
 }
 
 impl ServiceErrorCode {
@@ -49,6 +50,7 @@ impl ServiceErrorCode {
             ServiceErrorCode::BadRequest => tonic::Code::InvalidArgument,
             ServiceErrorCode::MethodNotAllowed => tonic::Code::InvalidArgument,
             ServiceErrorCode::UnsupportedMediaType => tonic::Code::InvalidArgument,
+            ServiceErrorCode::Unauthorized => tonic::Code::Unauthenticated,
         }
     }
     pub(crate) fn to_http_status_code(self) -> http::StatusCode {
@@ -58,6 +60,7 @@ impl ServiceErrorCode {
             ServiceErrorCode::BadRequest => http::StatusCode::BAD_REQUEST,
             ServiceErrorCode::MethodNotAllowed => http::StatusCode::METHOD_NOT_ALLOWED,
             ServiceErrorCode::UnsupportedMediaType => http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            ServiceErrorCode::Unauthorized => http::StatusCode::UNAUTHORIZED,
         }
     }
 }
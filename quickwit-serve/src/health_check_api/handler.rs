@@ -17,15 +17,25 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::convert::Infallible;
 use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
+use quickwit_cluster::Cluster;
+use quickwit_common::uri::Uri;
+use quickwit_metastore::Metastore;
+use quickwit_storage::StorageUriResolver;
 use serde::Serialize;
 use serde_json::json;
+use tracing::warn;
 use warp::http::header::{HeaderMap, HeaderValue};
 use warp::hyper::StatusCode;
 use warp::reply::with_status;
 use warp::{Filter, Rejection};
 
+use crate::with_arg;
+
 /// A service status.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
 pub enum ServiceStatus {
@@ -48,6 +58,8 @@ pub fn liveness_check_handler() -> impl Filter<Extract = impl warp::Reply, Error
     let service_status = ServiceStatus::Alive;
 
     warp::path!("health" / "livez")
+        .or(warp::path!("health" / "live"))
+        .unify()
         .map(move || make_reply(live_predicate(service_status), service_status))
         .with(warp::reply::with::headers(headers))
 }
@@ -78,8 +90,131 @@ fn live_predicate(service_status: ServiceStatus) -> bool {
     matches!(service_status, ServiceStatus::Alive)
 }
 
+/// Checks whether the subsystems a node depends on to serve requests are available.
+#[derive(Clone)]
+pub struct ReadinessChecker {
+    metastore: Arc<dyn Metastore>,
+    storage_uri_resolver: StorageUriResolver,
+    default_index_root_uri: Uri,
+    cluster: Arc<Cluster>,
+    /// Set once the storage check has passed. Checking the storage issues requests to the object
+    /// storage, so it is not repeated on every probe once the storage is known to be reachable.
+    storage_checked: Arc<AtomicBool>,
+}
+
+impl ReadinessChecker {
+    pub fn new(
+        metastore: Arc<dyn Metastore>,
+        storage_uri_resolver: StorageUriResolver,
+        default_index_root_uri: Uri,
+        cluster: Arc<Cluster>,
+    ) -> Self {
+        ReadinessChecker {
+            metastore,
+            storage_uri_resolver,
+            default_index_root_uri,
+            cluster,
+            storage_checked: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Runs the readiness checks and returns the names of the failing subsystems.
+    async fn failing_subsystems(&self) -> Vec<&'static str> {
+        let (metastore_check_res, storage_check_res) =
+            tokio::join!(self.check_metastore(), self.check_storage());
+        let checks = [
+            ("metastore", metastore_check_res),
+            ("storage", storage_check_res),
+            ("gossip", check_gossip(&self.cluster)),
+        ];
+        let mut failing_subsystems = Vec::new();
+        for (subsystem, check_res) in checks {
+            if let Err(error) = check_res {
+                warn!(subsystem = subsystem, error = ?error, "Readiness check failed.");
+                failing_subsystems.push(subsystem);
+            }
+        }
+        failing_subsystems
+    }
+
+    async fn check_metastore(&self) -> anyhow::Result<()> {
+        self.metastore.check_connectivity().await
+    }
+
+    async fn check_storage(&self) -> anyhow::Result<()> {
+        if self.storage_checked.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        let storage = self
+            .storage_uri_resolver
+            .resolve(&self.default_index_root_uri)?;
+        storage.check().await?;
+        self.storage_checked.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// The node has joined the gossip once it is a member of the cluster it gossips with, until it
+/// leaves it. Discovering other members is not required: the first node of a cluster, or a node
+/// whose peers are not up yet, must be able to become ready on its own.
+fn check_gossip(cluster: &Cluster) -> anyhow::Result<()> {
+    if cluster.has_left() {
+        anyhow::bail!("The node has left the cluster.");
+    }
+    if !cluster.members().iter().any(|member| member.is_self) {
+        anyhow::bail!("The node has not joined the cluster yet.");
+    }
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ReadinessStatus {
+    ready: bool,
+    failing_subsystems: Vec<&'static str>,
+}
+
+/// Readiness handler: `/health/ready` returns 503 along with the names of the failing
+/// subsystems until the metastore and the default index storage are reachable and the node has
+/// joined the gossip.
+pub fn readiness_check_handler(
+    readiness_checker: ReadinessChecker,
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    warp::path!("health" / "ready")
+        .and(with_arg(readiness_checker))
+        .and_then(get_readiness)
+}
+
+async fn get_readiness(
+    readiness_checker: ReadinessChecker,
+) -> Result<impl warp::Reply, Infallible> {
+    let failing_subsystems = readiness_checker.failing_subsystems().await;
+    let status_code = if failing_subsystems.is_empty() {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    let readiness_status = ReadinessStatus {
+        ready: failing_subsystems.is_empty(),
+        failing_subsystems,
+    };
+    Ok(with_status(
+        warp::reply::json(&readiness_status),
+        status_code,
+    ))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use chitchat::transport::ChannelTransport;
+    use quickwit_cluster::create_cluster_for_test;
+    use quickwit_common::uri::Uri;
+    use quickwit_metastore::MockMetastore;
+    use quickwit_storage::StorageUriResolver;
+
+    use super::ReadinessChecker;
 
     #[tokio::test]
     async fn test_rest_search_api_health_check_livez() {
@@ -90,4 +225,74 @@ mod tests {
             .await;
         assert_eq!(resp.status(), 200);
     }
+
+    #[tokio::test]
+    async fn test_rest_search_api_health_check_live() {
+        let rest_search_api_filter = super::liveness_check_handler();
+        let resp = warp::test::request()
+            .path("/health/live")
+            .reply(&rest_search_api_filter)
+            .await;
+        assert_eq!(resp.status(), 200);
+    }
+
+    #[tokio::test]
+    async fn test_rest_health_check_ready() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_check_connectivity()
+            .times(1)
+            .returning(|| Ok(()));
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport).await?;
+        let readiness_checker = ReadinessChecker::new(
+            Arc::new(metastore),
+            StorageUriResolver::for_test(),
+            Uri::new("ram:///indexes".to_string()),
+            Arc::new(cluster),
+        );
+        let handler = super::readiness_check_handler(readiness_checker.clone());
+        let resp = warp::test::request()
+            .path("/health/ready")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        assert_eq!(
+            resp_json,
+            serde_json::json!({"ready": true, "failing_subsystems": []})
+        );
+        assert!(readiness_checker.storage_checked.load(Ordering::Relaxed));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rest_health_check_not_ready() -> anyhow::Result<()> {
+        let mut metastore = MockMetastore::default();
+        metastore
+            .expect_check_connectivity()
+            .times(1)
+            .returning(|| Err(anyhow::anyhow!("Connection refused.")));
+        let transport = ChannelTransport::default();
+        let cluster = create_cluster_for_test(Vec::new(), &["searcher"], &transport).await?;
+        cluster.leave().await;
+        let readiness_checker = ReadinessChecker::new(
+            Arc::new(metastore),
+            StorageUriResolver::for_test(),
+            Uri::new("ram:///indexes".to_string()),
+            Arc::new(cluster),
+        );
+        let handler = super::readiness_check_handler(readiness_checker);
+        let resp = warp::test::request()
+            .path("/health/ready")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 503);
+        let resp_json: serde_json::Value = serde_json::from_slice(resp.body())?;
+        assert_eq!(
+            resp_json,
+            serde_json::json!({"ready": false, "failing_subsystems": ["metastore", "gossip"]})
+        );
+        Ok(())
+    }
 }
@@ -19,4 +19,4 @@
 
 mod handler;
 
-pub use handler::liveness_check_handler;
+pub use handler::{liveness_check_handler, readiness_check_handler, ReadinessChecker};
@@ -18,6 +18,8 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 mod args;
+mod auth;
+mod compression;
 mod error;
 mod format;
 mod metrics;
@@ -32,6 +34,7 @@ mod indexing_api;
 mod ingest_api;
 mod node_info_handler;
 mod search_api;
+mod searcher_api;
 mod ui_handler;
 
 use std::collections::HashSet;
@@ -54,6 +57,7 @@ use serde::{Deserialize, Serialize};
 use warp::{Filter, Rejection};
 
 pub use crate::args::ServeArgs;
+use crate::health_check_api::ReadinessChecker;
 pub use crate::metrics::SERVE_METRICS;
 #[cfg(test)]
 use crate::rest::recover_fn;
@@ -88,6 +92,7 @@ struct QuickwitServices {
     pub indexer_service: Option<Mailbox<IndexingService>>,
     pub ingest_api_service: Option<Mailbox<IngestApiService>>,
     pub index_service: Arc<IndexService>,
+    pub readiness_checker: ReadinessChecker,
     pub services: HashSet<QuickwitService>,
 }
 
@@ -144,6 +149,13 @@ pub async fn serve_quickwit(
     )
     .await?;
 
+    let readiness_checker = ReadinessChecker::new(
+        metastore.clone(),
+        storage_resolver.clone(),
+        config.default_index_root_uri.clone(),
+        cluster.clone(),
+    );
+
     // Always instanciate index management service.
     let index_service = Arc::new(IndexService::new(
         metastore,
@@ -161,6 +173,7 @@ pub async fn serve_quickwit(
         search_service,
         indexer_service,
         index_service,
+        readiness_checker,
         services: services.clone(),
     };
     let grpc_server = grpc::start_grpc_server(grpc_listen_addr, &quickwit_services);
@@ -209,6 +222,9 @@ pub struct QuickwitBuildInfo {
     pub commit_short_hash: &'static str,
     pub commit_date: &'static str,
     pub version: &'static str,
+    /// Optional Cargo features enabled when building Quickwit, e.g. `kafka` or
+    /// `openssl-support`.
+    pub enabled_features: Vec<String>,
 }
 
 /// Builds QuickwitBuildInfo from env variables.
@@ -228,7 +244,25 @@ pub fn build_quickwit_build_info() -> QuickwitBuildInfo {
         commit_short_hash: env!("QW_COMMIT_SHORT_HASH"),
         commit_date: env!("QW_COMMIT_DATE"),
         version,
+        enabled_features: enabled_features(),
+    }
+}
+
+/// Collects the optional Cargo features enabled in the Quickwit crates that have some.
+fn enabled_features() -> Vec<String> {
+    let mut enabled_features: Vec<String> = quickwit_indexing::enabled_features()
+        .into_iter()
+        .chain(quickwit_metastore::enabled_features())
+        .map(|feature| feature.to_string())
+        .collect();
+    if cfg!(feature = "openssl-support") {
+        enabled_features.push("openssl-support".to_string());
+    }
+    if cfg!(feature = "tokio-console") {
+        enabled_features.push("tokio-console".to_string());
     }
+    enabled_features.sort();
+    enabled_features
 }
 
 #[cfg(test)]
@@ -279,6 +313,8 @@ mod tests {
             fast_field: "timestamp".to_string(),
             output_format: OutputFormat::Csv as i32,
             partition_by_field: None,
+            split_order: 0,
+            partition_by_fields: vec![],
         };
         let mut metastore = MockMetastore::new();
         metastore
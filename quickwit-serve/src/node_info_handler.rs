@@ -64,12 +64,16 @@ async fn get_config(config: Arc<QuickwitConfig>) -> Result<impl warp::Reply, Rej
             .to_string(),
     );
     config_to_serialize.metastore_uri = redacted_uri;
+    for api_key in &mut config_to_serialize.rest_config.api_keys {
+        api_key.key = "***redacted***".to_string();
+    }
     Ok(warp::reply::json(&config_to_serialize))
 }
 
 #[cfg(test)]
 mod tests {
     use assert_json_diff::assert_json_include;
+    use quickwit_config::ApiKeyConfig;
 
     use super::*;
     use crate::recover_fn;
@@ -83,9 +87,14 @@ mod tests {
             commit_short_hash: "commit_short_hash",
             commit_date: "commit_date",
             version: "version",
+            enabled_features: vec!["kafka".to_string(), "openssl-support".to_string()],
         };
         let mut config = QuickwitConfig::for_test();
         config.metastore_uri = Uri::for_test("postgresql://username:password@db");
+        config.rest_config.api_keys = vec![ApiKeyConfig {
+            id: "dashboard".to_string(),
+            key: "dashboard-secret".to_string(),
+        }];
         let handler =
             super::node_info_handler(Arc::new(build_info.clone()), Arc::new(config.clone()))
                 .recover(recover_fn);
@@ -95,6 +104,7 @@ mod tests {
         let expected_build_json = serde_json::json!({
             "commit_date": "commit_date",
             "version": "version",
+            "enabled_features": ["kafka", "openssl-support"],
         });
         assert_json_include!(actual: resp_build_info_json, expected: expected_build_json);
 
@@ -104,6 +114,9 @@ mod tests {
         let expected_response_json = serde_json::json!({
             "node_id": config.node_id,
             "metastore_uri": "postgresql://username:***redacted***@db",
+            "rest_config": {
+                "api_keys": [{"id": "dashboard", "key": "***redacted***"}],
+            },
         });
         assert_json_include!(actual: resp_json, expected: expected_response_json);
 
@@ -19,20 +19,29 @@
 
 use std::net::SocketAddr;
 
-use hyper::http;
+use anyhow::{bail, Context};
+use hyper::http::{self, Method};
 use quickwit_common::metrics;
+use quickwit_config::TlsConfig;
+use rustls_pemfile::Item;
 use tracing::{error, info};
+use warp::cors::{Cors, CorsForbidden};
+use warp::filters::BoxedFilter;
+use warp::reply::Response;
 use warp::{redirect, Filter, Rejection, Reply};
 
+use crate::auth::{require_api_key, Unauthorized};
 use crate::cluster_api::cluster_handler;
+use crate::compression::with_compression;
 use crate::error::ServiceErrorCode;
 use crate::format::FormatError;
-use crate::health_check_api::liveness_check_handler;
+use crate::health_check_api::{liveness_check_handler, readiness_check_handler};
 use crate::index_api::index_management_handlers;
 use crate::indexing_api::indexing_get_handler;
 use crate::ingest_api::{elastic_bulk_handler, ingest_handler, tail_handler};
 use crate::node_info_handler::node_info_handler;
 use crate::search_api::{search_get_handler, search_post_handler, search_stream_handler};
+use crate::searcher_api::searcher_cache_handlers;
 use crate::ui_handler::ui_handler;
 use crate::{Format, QuickwitServices};
 
@@ -45,10 +54,21 @@ pub(crate) async fn start_rest_server(
     let request_counter = warp::log::custom(|_| {
         crate::SERVE_METRICS.http_requests_total.inc();
     });
+    // The health endpoints are the only ones reachable without an API key when authentication is
+    // enabled.
+    let api_key_filter = require_api_key(&quickwit_services.config.rest_config.api_keys);
+    let metrics_enabled = quickwit_services.config.rest_config.metrics_enabled;
     let metrics_service = warp::path("metrics")
         .and(warp::get())
+        .and(require_enabled(metrics_enabled))
+        .and(api_key_filter.clone())
         .map(metrics::metrics_handler);
     let api_v1_root_url = warp::path!("api" / "v1" / ..);
+    // Search stream responses are not compressed: they are streamed chunk by chunk.
+    let compression_enabled = quickwit_services.config.rest_config.compression_enabled;
+    let search_routes = search_get_handler(quickwit_services.search_service.clone()).or(
+        search_post_handler(quickwit_services.search_service.clone()),
+    );
     let api_v1_routes = cluster_handler(quickwit_services.cluster.clone())
         .or(node_info_handler(
             quickwit_services.build_info.clone(),
@@ -57,37 +77,150 @@ pub(crate) async fn start_rest_server(
         .or(indexing_get_handler(
             quickwit_services.indexer_service.clone(),
         ))
-        .or(search_get_handler(quickwit_services.search_service.clone()))
-        .or(search_post_handler(
-            quickwit_services.search_service.clone(),
-        ))
+        .or(with_compression(search_routes, compression_enabled))
         .or(search_stream_handler(
             quickwit_services.search_service.clone(),
         ))
+        .or(searcher_cache_handlers())
         .or(ingest_handler(quickwit_services.ingest_api_service.clone()))
         .or(tail_handler(quickwit_services.ingest_api_service.clone()))
         .or(elastic_bulk_handler(
             quickwit_services.ingest_api_service.clone(),
         ))
-        .or(index_management_handlers(
-            quickwit_services.index_service.clone(),
+        .or(with_compression(
+            index_management_handlers(quickwit_services.index_service.clone()),
+            compression_enabled,
         ));
-    let api_v1_root_route = api_v1_root_url.and(api_v1_routes);
+    let api_v1_root_route = api_v1_root_url.and(api_key_filter).and(api_v1_routes);
     let redirect_root_to_ui_route =
         warp::path::end().map(|| redirect(http::Uri::from_static("/ui/search")));
     let rest_routes = api_v1_root_route
         .or(redirect_root_to_ui_route)
         .or(ui_handler())
         .or(liveness_check_handler())
+        .or(readiness_check_handler(
+            quickwit_services.readiness_checker.clone(),
+        ))
         .or(metrics_service)
-        .with(request_counter)
-        .recover(recover_fn);
+        .with(request_counter);
+    let cors_allow_origins = &quickwit_services.config.rest_config.cors_allow_origins;
+    let rest_routes: BoxedFilter<(Response,)> = if let Some(cors) = build_cors(cors_allow_origins) {
+        rest_routes
+            .with(cors)
+            .recover(recover_fn)
+            .map(Reply::into_response)
+            .boxed()
+    } else {
+        rest_routes
+            .recover(recover_fn)
+            .map(Reply::into_response)
+            .boxed()
+    };
 
-    info!("Searcher ready to accept requests at http://{rest_listen_addr}/");
-    warp::serve(rest_routes).run(rest_listen_addr).await;
+    let rest_server = warp::serve(rest_routes);
+    if let Some(tls_config) = &quickwit_services.config.rest_config.tls {
+        let tls_identity = TlsIdentity::load(tls_config)?;
+        info!("Searcher ready to accept requests at https://{rest_listen_addr}/");
+        rest_server
+            .tls()
+            .cert(tls_identity.cert)
+            .key(tls_identity.key)
+            .run(rest_listen_addr)
+            .await;
+    } else {
+        info!("Searcher ready to accept requests at http://{rest_listen_addr}/");
+        rest_server.run(rest_listen_addr).await;
+    }
     Ok(())
 }
 
+/// Returns a filter rejecting every request as not found when `enabled` is false.
+fn require_enabled(enabled: bool) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::any()
+        .and_then(move || async move {
+            if enabled {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+}
+
+/// Builds the CORS filter answering the preflight requests and adding the CORS headers to the
+/// responses for the configured origins. Returns `None` if no origin is configured, in which case
+/// no CORS header is sent.
+fn build_cors(cors_allow_origins: &[String]) -> Option<Cors> {
+    if cors_allow_origins.is_empty() {
+        return None;
+    }
+    let cors = warp::cors()
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([http::header::AUTHORIZATION, http::header::CONTENT_TYPE]);
+    let cors = if cors_allow_origins.iter().any(|origin| origin == "*") {
+        cors.allow_any_origin()
+    } else {
+        cors.allow_origins(cors_allow_origins.iter().map(String::as_str))
+    };
+    Some(cors.build())
+}
+
+/// PEM-encoded certificate chain and private key used to serve the REST API over TLS.
+struct TlsIdentity {
+    cert: Vec<u8>,
+    key: Vec<u8>,
+}
+
+impl TlsIdentity {
+    /// Reads the certificate chain and private key referenced by the TLS config and checks that
+    /// they contain at least one certificate and one private key respectively, so that a
+    /// misconfiguration is reported before the server starts.
+    fn load(tls_config: &TlsConfig) -> anyhow::Result<Self> {
+        let cert = std::fs::read(&tls_config.cert_path).with_context(|| {
+            format!(
+                "Failed to read REST TLS certificate file `{}`.",
+                tls_config.cert_path.display()
+            )
+        })?;
+        let key = std::fs::read(&tls_config.key_path).with_context(|| {
+            format!(
+                "Failed to read REST TLS private key file `{}`.",
+                tls_config.key_path.display()
+            )
+        })?;
+        let num_certs = rustls_pemfile::certs(&mut cert.as_slice())
+            .with_context(|| {
+                format!(
+                    "Failed to parse REST TLS certificate file `{}`.",
+                    tls_config.cert_path.display()
+                )
+            })?
+            .len();
+        if num_certs == 0 {
+            bail!(
+                "REST TLS certificate file `{}` does not contain any PEM-encoded certificate.",
+                tls_config.cert_path.display()
+            );
+        }
+        let has_private_key = rustls_pemfile::read_all(&mut key.as_slice())
+            .with_context(|| {
+                format!(
+                    "Failed to parse REST TLS private key file `{}`.",
+                    tls_config.key_path.display()
+                )
+            })?
+            .into_iter()
+            .any(|item| matches!(item, Item::PKCS8Key(_) | Item::RSAKey(_) | Item::ECKey(_)));
+        if !has_private_key {
+            bail!(
+                "REST TLS private key file `{}` does not contain any PEM-encoded private key.",
+                tls_config.key_path.display()
+            );
+        }
+        Ok(Self { cert, key })
+    }
+}
+
 /// This function returns a formatted error based on the given rejection reason.
 /// The ordering of rejection processing is very important, we need to start
 /// with the most specific rejections and end with the most generic. If not, Quickwit
@@ -156,6 +289,16 @@ fn get_status_with_error(rejection: Rejection) -> FormatError {
             code: ServiceErrorCode::BadRequest,
             error: error.to_string(),
         }
+    } else if rejection.find::<Unauthorized>().is_some() {
+        FormatError {
+            code: ServiceErrorCode::Unauthorized,
+            error: "Missing or invalid API key.".to_string(),
+        }
+    } else if let Some(error) = rejection.find::<CorsForbidden>() {
+        FormatError {
+            code: ServiceErrorCode::BadRequest,
+            error: error.to_string(),
+        }
     } else if let Some(error) = rejection.find::<crate::ingest_api::BulkApiError>() {
         FormatError {
             code: ServiceErrorCode::BadRequest,
@@ -169,3 +312,154 @@ fn get_status_with_error(rejection: Rejection) -> FormatError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn get_tls_filepath(tls_filename: &str) -> PathBuf {
+        PathBuf::from(format!(
+            "{}/resources/tests/tls/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            tls_filename
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_require_enabled() {
+        let handler = warp::path!("metrics")
+            .and(require_enabled(true))
+            .map(metrics::metrics_handler);
+        let resp = warp::test::request().path("/metrics").reply(&handler).await;
+        assert_eq!(resp.status(), 200);
+
+        let handler = warp::path!("metrics")
+            .and(require_enabled(false))
+            .map(metrics::metrics_handler)
+            .recover(recover_fn);
+        let resp = warp::test::request().path("/metrics").reply(&handler).await;
+        assert_eq!(resp.status(), 404);
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight() {
+        assert!(build_cors(&[]).is_none());
+
+        let cors = build_cors(&["https://dashboard.example.com".to_string()]).unwrap();
+        let handler = warp::path!("api" / "v1" / "search")
+            .map(warp::reply)
+            .with(cors)
+            .recover(recover_fn);
+        let resp = warp::test::request()
+            .method("OPTIONS")
+            .path("/api/v1/search")
+            .header("origin", "https://dashboard.example.com")
+            .header("access-control-request-method", "POST")
+            .header("access-control-request-headers", "content-type")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get("access-control-allow-origin").unwrap(),
+            "https://dashboard.example.com"
+        );
+
+        // Browsers sending an API key ask for the `Authorization` header in the preflight request.
+        let resp = warp::test::request()
+            .method("OPTIONS")
+            .path("/api/v1/search")
+            .header("origin", "https://dashboard.example.com")
+            .header("access-control-request-method", "GET")
+            .header("access-control-request-headers", "authorization")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let allowed_headers = resp
+            .headers()
+            .get("access-control-allow-headers")
+            .unwrap()
+            .to_str()
+            .unwrap();
+        assert!(allowed_headers.contains("authorization"));
+
+        let resp = warp::test::request()
+            .method("OPTIONS")
+            .path("/api/v1/search")
+            .header("origin", "https://evil.example.com")
+            .header("access-control-request-method", "POST")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+        assert!(resp.headers().get("access-control-allow-origin").is_none());
+
+        let cors = build_cors(&["*".to_string()]).unwrap();
+        let handler = warp::path!("api" / "v1" / "search")
+            .map(warp::reply)
+            .with(cors);
+        let resp = warp::test::request()
+            .path("/api/v1/search")
+            .header("origin", "https://dashboard.example.com")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        assert_eq!(
+            resp.headers().get("access-control-allow-origin").unwrap(),
+            "https://dashboard.example.com"
+        );
+    }
+
+    #[test]
+    fn test_tls_identity_load() {
+        let tls_config = TlsConfig {
+            cert_path: get_tls_filepath("cert.pem"),
+            key_path: get_tls_filepath("key.pem"),
+        };
+        let tls_identity = TlsIdentity::load(&tls_config).unwrap();
+        assert_eq!(
+            tls_identity.cert,
+            std::fs::read(&tls_config.cert_path).unwrap()
+        );
+        assert_eq!(
+            tls_identity.key,
+            std::fs::read(&tls_config.key_path).unwrap()
+        );
+        let _tls_server = warp::serve(warp::any().map(warp::reply))
+            .tls()
+            .cert(tls_identity.cert)
+            .key(tls_identity.key);
+    }
+
+    #[test]
+    fn test_tls_identity_load_invalid_files() {
+        let tls_config = TlsConfig {
+            cert_path: get_tls_filepath("does-not-exist.pem"),
+            key_path: get_tls_filepath("key.pem"),
+        };
+        let error = TlsIdentity::load(&tls_config).err().unwrap();
+        assert!(error
+            .to_string()
+            .starts_with("Failed to read REST TLS certificate file"));
+
+        // The key file does not contain any certificate.
+        let tls_config = TlsConfig {
+            cert_path: get_tls_filepath("key.pem"),
+            key_path: get_tls_filepath("key.pem"),
+        };
+        let error = TlsIdentity::load(&tls_config).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("does not contain any PEM-encoded certificate"));
+
+        // The certificate file does not contain any private key.
+        let tls_config = TlsConfig {
+            cert_path: get_tls_filepath("cert.pem"),
+            key_path: get_tls_filepath("cert.pem"),
+        };
+        let error = TlsIdentity::load(&tls_config).err().unwrap();
+        assert!(error
+            .to_string()
+            .contains("does not contain any PEM-encoded private key"));
+    }
+}
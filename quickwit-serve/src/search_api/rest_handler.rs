@@ -111,6 +111,9 @@ pub struct SearchRequestQueryString {
     #[serde(deserialize_with = "sort_by_field_mini_dsl")]
     #[serde(default)]
     sort_by_field: Option<SortByField>,
+    /// If set, the fields captured by the dynamic mapping are left out of the hits.
+    #[serde(default)]
+    pub exclude_dynamic_fields: bool,
 }
 
 fn get_proto_search_by(search_request: &SearchRequestQueryString) -> (Option<i32>, Option<String>) {
@@ -144,6 +147,8 @@ async fn search_endpoint(
             .map(|agg| serde_json::to_string(&agg).expect("could not serialize serde_json::Value")),
         sort_order,
         sort_by_field,
+        exclude_dynamic_fields: search_request.exclude_dynamic_fields,
+        ..Default::default()
     };
     let search_response = search_service.root_search(search_request).await?;
     let search_response_rest = SearchResponseRest::try_from(search_response)?;
@@ -230,6 +235,10 @@ struct SearchStreamRequestQueryString {
     pub output_format: OutputFormat,
     #[serde(default)]
     pub partition_by_field: Option<String>,
+    /// The fields by which to partition, forming a composite partition key.
+    #[serde(default)]
+    #[serde(deserialize_with = "from_simple_list")]
+    pub partition_by_fields: Option<Vec<String>>,
 }
 
 async fn search_stream_endpoint(
@@ -246,6 +255,8 @@ async fn search_stream_endpoint(
         fast_field: search_request.fast_field,
         output_format: search_request.output_format as i32,
         partition_by_field: search_request.partition_by_field,
+        split_order: 0,
+        partition_by_fields: search_request.partition_by_fields.unwrap_or_default(),
     };
     let mut data = search_service.root_search_stream(request).await?;
     let (mut sender, body) = hyper::Body::channel();
@@ -304,7 +315,9 @@ async fn search_stream(
 ) -> Result<impl warp::Reply, Infallible> {
     info!(index_id=%index_id,request=?request, "search_stream");
     let content_type = match request.output_format {
-        OutputFormat::ClickHouseRowBinary => "application/octet-stream",
+        OutputFormat::ClickHouseRowBinary | OutputFormat::ClickHouseRowBinaryWithNamesAndTypes => {
+            "application/octet-stream"
+        }
         OutputFormat::Csv => "text/csv",
     };
     let reply =
@@ -346,9 +359,12 @@ mod tests {
         let search_response = SearchResponseRest {
             num_hits: 55,
             hits: Vec::new(),
+            snippets: Vec::new(),
             elapsed_time_micros: 0u64,
             errors: Vec::new(),
             aggregations: None,
+            aggregations_truncated: false,
+            explanation: None,
         };
         let search_response_json: serde_json::Value = serde_json::to_value(&search_response)?;
         let expected_search_response_json: serde_json::Value = json!({
@@ -723,6 +739,7 @@ mod tests {
                 fast_field: "external_id".to_string(),
                 output_format: OutputFormat::Csv,
                 partition_by_field: None,
+                partition_by_fields: None,
             }
         );
     }
@@ -748,6 +765,33 @@ mod tests {
                 fast_field: "external_id".to_string(),
                 output_format: OutputFormat::ClickHouseRowBinary,
                 partition_by_field: None,
+                partition_by_fields: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rest_search_stream_api_partition_by_fields() {
+        let (index, req) = warp::test::request()
+            .path(
+                "/my-index/search/stream?query=obama&fast_field=external_id&\
+                 output_format=click_house_row_binary&partition_by_fields=tenant_id,region",
+            )
+            .filter(&super::search_stream_filter())
+            .await
+            .unwrap();
+        assert_eq!(&index, "my-index");
+        assert_eq!(
+            &req,
+            &super::SearchStreamRequestQueryString {
+                query: "obama".to_string(),
+                search_fields: None,
+                start_timestamp: None,
+                end_timestamp: None,
+                fast_field: "external_id".to_string(),
+                output_format: OutputFormat::ClickHouseRowBinary,
+                partition_by_field: None,
+                partition_by_fields: Some(vec!["tenant_id".to_string(), "region".to_string()]),
             }
         );
     }
@@ -765,7 +809,8 @@ mod tests {
         let parse_error = rejection.find::<serde_qs::Error>().unwrap();
         assert_eq!(
             parse_error.to_string(),
-            "unknown variant `ClickHouseRowBinary`, expected `csv` or `click_house_row_binary`"
+            "unknown variant `ClickHouseRowBinary`, expected one of `csv`, \
+             `click_house_row_binary`, `click_house_row_binary_with_names_and_types`"
         );
     }
 
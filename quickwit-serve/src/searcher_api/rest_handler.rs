@@ -0,0 +1,112 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::convert::Infallible;
+
+use quickwit_search::{
+    cache_capacities, update_cache_capacities, CacheCapacities, CacheCapacitiesUpdate,
+};
+use warp::{Filter, Rejection};
+
+use crate::Format;
+
+/// Searcher cache handlers: `GET /searcher/caches` returns the effective capacities of the
+/// searcher caches and `PUT /searcher/caches` resizes them without restarting the node.
+pub fn searcher_cache_handlers(
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    get_cache_capacities_handler().or(update_cache_capacities_handler())
+}
+
+fn get_cache_capacities_handler(
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    warp::path!("searcher" / "caches")
+        .and(warp::get())
+        .and_then(get_cache_capacities)
+}
+
+async fn get_cache_capacities() -> Result<impl warp::Reply, Infallible> {
+    let cache_capacities: Result<CacheCapacities, Infallible> = Ok(cache_capacities());
+    Ok(Format::default().make_rest_reply_non_serializable_error(cache_capacities))
+}
+
+fn update_cache_capacities_handler(
+) -> impl Filter<Extract = impl warp::Reply, Error = Rejection> + Clone {
+    warp::path!("searcher" / "caches")
+        .and(warp::put())
+        .and(warp::body::content_length_limit(1024))
+        .and(warp::body::json())
+        .and_then(put_cache_capacities)
+}
+
+async fn put_cache_capacities(
+    update: CacheCapacitiesUpdate,
+) -> Result<impl warp::Reply, Infallible> {
+    let cache_capacities: Result<CacheCapacities, Infallible> = Ok(update_cache_capacities(update));
+    Ok(Format::default().make_rest_reply_non_serializable_error(cache_capacities))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recover_fn;
+
+    #[tokio::test]
+    async fn test_rest_searcher_caches() -> anyhow::Result<()> {
+        let handler = searcher_cache_handlers().recover(recover_fn);
+        let resp = warp::test::request()
+            .path("/searcher/caches")
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let initial_cache_capacities: CacheCapacities = serde_json::from_slice(resp.body())?;
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/searcher/caches")
+            .json(&serde_json::json!({ "split_footer_cache_capacity_num_bytes": 1_000 }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        let cache_capacities: CacheCapacities = serde_json::from_slice(resp.body())?;
+        assert_eq!(
+            cache_capacities,
+            CacheCapacities {
+                split_footer_cache_capacity_num_bytes: 1_000,
+                ..initial_cache_capacities
+            }
+        );
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/searcher/caches")
+            .json(&serde_json::json!({ "unknown_cache_capacity_num_bytes": 1_000 }))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 400);
+
+        let resp = warp::test::request()
+            .method("PUT")
+            .path("/searcher/caches")
+            .json(&CacheCapacitiesUpdate::from(initial_cache_capacities))
+            .reply(&handler)
+            .await;
+        assert_eq!(resp.status(), 200);
+        Ok(())
+    }
+}
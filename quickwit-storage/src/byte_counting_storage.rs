@@ -0,0 +1,137 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use quickwit_common::uri::Uri;
+
+use crate::{BulkDeleteError, FileMetadata, OwnedBytes, PutPayload, Storage, StorageResult};
+
+/// This storage acts as a proxy to another storage and keeps track of the number of bytes
+/// fetched through `get_slice` and `get_all`.
+///
+/// It is used to report how many bytes a given request actually fetched from the
+/// underlying storage.
+pub struct ByteCountingStorage {
+    underlying: Arc<dyn Storage>,
+    num_bytes_fetched: AtomicU64,
+}
+
+impl ByteCountingStorage {
+    /// Creates a new [`ByteCountingStorage`] wrapping the given storage.
+    pub fn new(underlying: Arc<dyn Storage>) -> Self {
+        Self {
+            underlying,
+            num_bytes_fetched: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the number of bytes fetched so far from the underlying storage.
+    pub fn num_bytes_fetched(&self) -> u64 {
+        self.num_bytes_fetched.load(Ordering::Relaxed)
+    }
+
+    fn record_fetch(&self, bytes: &OwnedBytes) {
+        self.num_bytes_fetched
+            .fetch_add(bytes.len() as u64, Ordering::Relaxed);
+    }
+}
+
+#[async_trait]
+impl Storage for ByteCountingStorage {
+    async fn check(&self) -> anyhow::Result<()> {
+        self.underlying.check().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.underlying.put(path, payload).await
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        self.underlying.copy_to_file(path, output_path).await
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        let bytes = self.underlying.get_slice(path, range).await?;
+        self.record_fetch(&bytes);
+        Ok(bytes)
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        let bytes = self.underlying.get_all(path).await?;
+        self.record_fetch(&bytes);
+        Ok(bytes)
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.underlying.delete(path).await
+    }
+
+    async fn bulk_delete(&self, paths: &[&Path]) -> Result<(), BulkDeleteError> {
+        self.underlying.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.underlying.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.underlying.file_num_bytes(path).await
+    }
+
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        self.underlying.list(prefix).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.underlying.uri()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RamStorage;
+
+    #[tokio::test]
+    async fn test_byte_counting_storage() -> anyhow::Result<()> {
+        let ram_storage = RamStorage::builder()
+            .put("file1", b"hello world")
+            .put("file2", b"quickwit")
+            .build();
+        let byte_counting_storage = ByteCountingStorage::new(Arc::new(ram_storage));
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 0);
+        byte_counting_storage
+            .get_slice(Path::new("file1"), 0..5)
+            .await?;
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 5);
+        byte_counting_storage.get_all(Path::new("file2")).await?;
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 13);
+        assert!(byte_counting_storage
+            .get_all(Path::new("file3"))
+            .await
+            .is_err());
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 13);
+        Ok(())
+    }
+}
@@ -62,6 +62,13 @@ impl Capacity {
             Capacity::InBytes(capacity_in_bytes) => num_bytes > capacity_in_bytes,
         }
     }
+
+    fn capacity_in_bytes(&self) -> Option<usize> {
+        match *self {
+            Capacity::Unlimited => None,
+            Capacity::InBytes(capacity_in_bytes) => Some(capacity_in_bytes),
+        }
+    }
 }
 
 struct NeedMutMemorySizedCache<K: Hash + Eq> {
@@ -86,6 +93,11 @@ impl<K: Hash + Eq> Drop for NeedMutMemorySizedCache<K> {
 impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
     /// Creates a new NeedMutSliceCache with the given capacity.
     fn with_capacity(capacity: Capacity, cache_counters: &'static CacheMetrics) -> Self {
+        if let Some(capacity_in_bytes) = capacity.capacity_in_bytes() {
+            cache_counters
+                .capacity_num_bytes
+                .set(capacity_in_bytes as i64);
+        }
         NeedMutMemorySizedCache {
             // The limit will be decided by the amount of memory in the cache,
             // not the number of items in the cache.
@@ -128,6 +140,27 @@ impl<K: Hash + Eq> NeedMutMemorySizedCache<K> {
         }
     }
 
+    /// Updates the capacity of the cache and evicts the least recently used items until the
+    /// cache fits in the new capacity.
+    ///
+    /// Unlike `put`, the eviction ignores the last access time of the items: the cache is
+    /// shrunk right away.
+    fn set_capacity(&mut self, capacity: Capacity) {
+        self.capacity = capacity;
+        if let Some(capacity_in_bytes) = capacity.capacity_in_bytes() {
+            self.cache_counters
+                .capacity_num_bytes
+                .set(capacity_in_bytes as i64);
+        }
+        while self.capacity.exceeds_capacity(self.num_bytes as usize) {
+            if let Some((_, bytes)) = self.lru_cache.pop_lru() {
+                self.drop_item(bytes.len() as u64);
+            } else {
+                break;
+            }
+        }
+    }
+
     /// Attempt to put the given amount of data in the cache.
     /// This may fail silently if the owned_bytes slice is larger than the cache
     /// capacity.
@@ -220,6 +253,26 @@ impl<K: Hash + Eq> MemorySizedCache<K> {
     pub fn put(&self, val: K, bytes: OwnedBytes) {
         self.inner.lock().unwrap().put(val, bytes);
     }
+
+    /// Updates the capacity of the cache, evicting the least recently used items right away if
+    /// the cache holds more bytes than the new capacity.
+    pub fn set_capacity_in_bytes(&self, capacity_in_bytes: usize) {
+        self.inner
+            .lock()
+            .unwrap()
+            .set_capacity(Capacity::InBytes(capacity_in_bytes));
+    }
+
+    /// Returns the capacity of the cache in bytes, or `None` if the cache has an infinite
+    /// capacity.
+    pub fn capacity_in_bytes(&self) -> Option<usize> {
+        self.inner.lock().unwrap().capacity.capacity_in_bytes()
+    }
+
+    /// Returns the number of bytes currently held by the cache.
+    pub fn num_bytes(&self) -> u64 {
+        self.inner.lock().unwrap().num_bytes
+    }
 }
 
 impl MemorySizedCache<SliceAddress> {
@@ -302,6 +355,31 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_cache_set_capacity_evicts_down_to_new_capacity() {
+        tokio::time::pause();
+        let cache =
+            MemorySizedCache::<String>::with_capacity_in_bytes(10, &CACHE_METRICS_FOR_TESTS);
+        cache.put("1".to_string(), OwnedBytes::new(&b"abc"[..]));
+        cache.put("2".to_string(), OwnedBytes::new(&b"def"[..]));
+        cache.put("3".to_string(), OwnedBytes::new(&b"ghi"[..]));
+        assert_eq!(cache.num_bytes(), 9);
+        // Items were accessed recently, yet shrinking the cache evicts them right away.
+        cache.set_capacity_in_bytes(4);
+        assert_eq!(cache.capacity_in_bytes(), Some(4));
+        assert_eq!(cache.num_bytes(), 3);
+        assert!(cache.get(&"1".to_string()).is_none());
+        assert!(cache.get(&"2".to_string()).is_none());
+        assert_eq!(cache.get(&"3".to_string()).unwrap(), &b"ghi"[..]);
+
+        cache.set_capacity_in_bytes(10);
+        assert_eq!(cache.capacity_in_bytes(), Some(10));
+        cache.put("4".to_string(), OwnedBytes::new(&b"jkl"[..]));
+        assert_eq!(cache.num_bytes(), 6);
+        assert_eq!(cache.get(&"3".to_string()).unwrap(), &b"ghi"[..]);
+        assert_eq!(cache.get(&"4".to_string()).unwrap(), &b"jkl"[..]);
+    }
+
     #[test]
     fn test_cache() {
         let cache = MemorySizedCache::with_capacity_in_bytes(10_000, &CACHE_METRICS_FOR_TESTS);
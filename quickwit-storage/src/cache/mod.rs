@@ -31,6 +31,7 @@ use async_trait::async_trait;
 use once_cell::sync::OnceCell;
 
 pub use self::memory_sized_cache::MemorySizedCache;
+pub use self::quickwit_cache::fast_field_cache;
 use crate::cache::quickwit_cache::QuickwitCache;
 use crate::cache::storage_with_cache::StorageWithCache;
 use crate::{OwnedBytes, Storage};
@@ -22,6 +22,7 @@ use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use once_cell::sync::OnceCell;
 use quickwit_config::get_searcher_config_instance;
 
 use crate::cache::{Cache, MemorySizedCache};
@@ -43,21 +44,31 @@ impl From<Vec<(&'static str, Arc<dyn Cache>)>> for QuickwitCache {
 impl Default for QuickwitCache {
     fn default() -> Self {
         let mut quickwit_cache = QuickwitCache::empty();
-        let config = get_searcher_config_instance();
-        let fast_cache_cap = config.fast_field_cache_capacity.get_bytes();
-        let fast_field_cache_counters: &'static CacheMetrics =
-            &crate::STORAGE_METRICS.fast_field_cache;
         quickwit_cache.add_route(
             ".fast",
-            Arc::new(SimpleCache::with_capacity_in_bytes(
-                fast_cache_cap as usize,
-                fast_field_cache_counters,
-            )),
+            Arc::new(SimpleCache {
+                slice_cache: fast_field_cache(),
+            }),
         );
         quickwit_cache
     }
 }
 
+/// Returns the cache of the fast field files, shared by all of the storages wrapped with the
+/// long term cache. Its capacity can be updated at runtime.
+pub fn fast_field_cache() -> &'static MemorySizedCache {
+    static INSTANCE: OnceCell<MemorySizedCache> = OnceCell::new();
+    INSTANCE.get_or_init(|| {
+        let config = get_searcher_config_instance();
+        let fast_field_cache_counters: &'static CacheMetrics =
+            &crate::STORAGE_METRICS.fast_field_cache;
+        MemorySizedCache::with_capacity_in_bytes(
+            config.fast_field_cache_capacity.get_bytes() as usize,
+            fast_field_cache_counters,
+        )
+    })
+}
+
 impl QuickwitCache {
     pub fn empty() -> QuickwitCache {
         QuickwitCache::from(Vec::new())
@@ -116,21 +127,7 @@ impl Cache for QuickwitCache {
 /// HACK! We use `0..usize::MAX` to signify the "entire file".
 /// TODO fixme
 struct SimpleCache {
-    slice_cache: MemorySizedCache,
-}
-
-impl SimpleCache {
-    fn with_capacity_in_bytes(
-        capacity_in_bytes: usize,
-        cache_counters: &'static CacheMetrics,
-    ) -> Self {
-        SimpleCache {
-            slice_cache: MemorySizedCache::with_capacity_in_bytes(
-                capacity_in_bytes,
-                cache_counters,
-            ),
-        }
-    }
+    slice_cache: &'static MemorySizedCache,
 }
 
 #[async_trait]
@@ -25,7 +25,7 @@ use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 
 use crate::cache::Cache;
-use crate::{OwnedBytes, Storage, StorageResult};
+use crate::{FileMetadata, OwnedBytes, Storage, StorageResult};
 
 /// Use with care, StorageWithCache is read-only.
 pub(crate) struct StorageWithCache {
@@ -85,6 +85,10 @@ impl Storage for StorageWithCache {
         self.storage.file_num_bytes(path).await
     }
 
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        self.storage.list(prefix).await
+    }
+
     fn uri(&self) -> &Uri {
         self.storage.uri()
     }
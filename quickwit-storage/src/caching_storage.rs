@@ -0,0 +1,373 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use lru::LruCache;
+use quickwit_common::uri::Uri;
+use tracing::warn;
+
+use crate::{BulkDeleteError, FileMetadata, OwnedBytes, PutPayload, Storage, StorageResult};
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+struct CacheKey {
+    path: String,
+    byte_range: Range<usize>,
+}
+
+/// Subdirectory of the cache directory owned by the [`LocalFileCache`].
+const SLICES_DIRNAME: &str = "quickwit-slice-cache";
+
+struct CachedSlice {
+    file_id: u64,
+    num_bytes: usize,
+}
+
+struct LocalFileCacheState {
+    slices: LruCache<CacheKey, CachedSlice>,
+    num_bytes: usize,
+    next_file_id: u64,
+}
+
+impl LocalFileCacheState {
+    /// Inserts a slice and evicts the least recently used slices until the cache fits within
+    /// `capacity`. Returns the IDs of the files that are no longer referenced by the cache.
+    fn insert(&mut self, key: CacheKey, cached_slice: CachedSlice, capacity: usize) -> Vec<u64> {
+        let mut evicted_file_ids = Vec::new();
+        self.num_bytes += cached_slice.num_bytes;
+        if let Some(previous_slice) = self.slices.put(key, cached_slice) {
+            self.num_bytes -= previous_slice.num_bytes;
+            evicted_file_ids.push(previous_slice.file_id);
+        }
+        while self.num_bytes > capacity {
+            if let Some((_, evicted_slice)) = self.slices.pop_lru() {
+                self.num_bytes -= evicted_slice.num_bytes;
+                evicted_file_ids.push(evicted_slice.file_id);
+            } else {
+                break;
+            }
+        }
+        evicted_file_ids
+    }
+
+    fn remove(&mut self, key: &CacheKey, file_id: u64) {
+        if self
+            .slices
+            .peek(key)
+            .map(|cached_slice| cached_slice.file_id)
+            == Some(file_id)
+        {
+            if let Some(removed_slice) = self.slices.pop(key) {
+                self.num_bytes -= removed_slice.num_bytes;
+            }
+        }
+    }
+}
+
+/// A cache storing slices on the local disk within a byte budget. The least recently used
+/// slices are evicted first.
+///
+/// The cache is meant to be shared by the [`CachingStorage`] instances of a node.
+pub struct LocalFileCache {
+    /// Directory holding the slice files, owned by the cache.
+    slices_dir_path: PathBuf,
+    capacity: usize,
+    path_suffixes: Vec<String>,
+    state: Mutex<LocalFileCacheState>,
+}
+
+impl LocalFileCache {
+    /// Creates a cache holding at most `capacity` bytes of slices in the directory `root_path`.
+    /// Only the slices of the files whose path ends with one of `path_suffixes` are cached.
+    ///
+    /// The slices are written to a `quickwit-slice-cache` subdirectory of `root_path`, which is
+    /// wiped on creation so that the cache always starts empty. The other files of `root_path`
+    /// are left untouched.
+    pub fn open(
+        root_path: PathBuf,
+        capacity: usize,
+        path_suffixes: Vec<String>,
+    ) -> io::Result<LocalFileCache> {
+        let slices_dir_path = root_path.join(SLICES_DIRNAME);
+        if slices_dir_path.exists() {
+            std::fs::remove_dir_all(&slices_dir_path)?;
+        }
+        std::fs::create_dir_all(&slices_dir_path)?;
+        let state = LocalFileCacheState {
+            slices: LruCache::unbounded(),
+            num_bytes: 0,
+            next_file_id: 0,
+        };
+        Ok(LocalFileCache {
+            slices_dir_path,
+            capacity,
+            path_suffixes,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Returns the number of bytes currently held by the cache.
+    pub fn num_bytes(&self) -> usize {
+        self.state.lock().unwrap().num_bytes
+    }
+
+    fn is_cacheable(&self, path: &Path) -> bool {
+        let path_str = path.to_string_lossy();
+        self.path_suffixes
+            .iter()
+            .any(|path_suffix| path_str.ends_with(path_suffix.as_str()))
+    }
+
+    fn file_path(&self, file_id: u64) -> PathBuf {
+        self.slices_dir_path.join(format!("{file_id:020}.slice"))
+    }
+
+    async fn get(&self, key: &CacheKey) -> Option<OwnedBytes> {
+        let file_id = self.state.lock().unwrap().slices.get(key)?.file_id;
+        match tokio::fs::read(self.file_path(file_id)).await {
+            Ok(bytes) => Some(OwnedBytes::new(bytes)),
+            Err(error) => {
+                warn!(error = ?error, path = %key.path, "Failed to read slice from local cache.");
+                self.state.lock().unwrap().remove(key, file_id);
+                None
+            }
+        }
+    }
+
+    async fn put(&self, key: CacheKey, bytes: &OwnedBytes) {
+        if bytes.len() > self.capacity {
+            return;
+        }
+        let file_id = {
+            let mut state = self.state.lock().unwrap();
+            state.next_file_id += 1;
+            state.next_file_id
+        };
+        let file_path = self.file_path(file_id);
+        if let Err(error) = tokio::fs::write(&file_path, bytes.as_slice()).await {
+            warn!(error = ?error, path = %key.path, "Failed to write slice to local cache.");
+            let _ = tokio::fs::remove_file(&file_path).await;
+            return;
+        }
+        let cached_slice = CachedSlice {
+            file_id,
+            num_bytes: bytes.len(),
+        };
+        let evicted_file_ids = self
+            .state
+            .lock()
+            .unwrap()
+            .insert(key, cached_slice, self.capacity);
+        for evicted_file_id in evicted_file_ids {
+            if let Err(error) = tokio::fs::remove_file(self.file_path(evicted_file_id)).await {
+                warn!(error = ?error, "Failed to remove evicted slice from local cache.");
+            }
+        }
+    }
+}
+
+/// This storage acts as a proxy to another storage and caches the slices read with `get_slice`
+/// in a [`LocalFileCache`].
+///
+/// Writes and deletes are delegated straight to the underlying storage. Cached files are
+/// assumed to be immutable, which holds for split files.
+pub struct CachingStorage {
+    underlying: Arc<dyn Storage>,
+    cache: Arc<LocalFileCache>,
+}
+
+impl CachingStorage {
+    /// Creates a new [`CachingStorage`] wrapping the given storage.
+    pub fn new(underlying: Arc<dyn Storage>, cache: Arc<LocalFileCache>) -> Self {
+        Self { underlying, cache }
+    }
+
+    fn cache_key(&self, path: &Path, byte_range: Range<usize>) -> CacheKey {
+        CacheKey {
+            path: format!("{}/{}", self.underlying.uri(), path.display()),
+            byte_range,
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for CachingStorage {
+    async fn check(&self) -> anyhow::Result<()> {
+        self.underlying.check().await
+    }
+
+    async fn put(&self, path: &Path, payload: Box<dyn PutPayload>) -> StorageResult<()> {
+        self.underlying.put(path, payload).await
+    }
+
+    async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        self.underlying.copy_to_file(path, output_path).await
+    }
+
+    async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
+        if !self.cache.is_cacheable(path) {
+            return self.underlying.get_slice(path, range).await;
+        }
+        let cache_key = self.cache_key(path, range.clone());
+        if let Some(bytes) = self.cache.get(&cache_key).await {
+            return Ok(bytes);
+        }
+        let bytes = self.underlying.get_slice(path, range).await?;
+        self.cache.put(cache_key, &bytes).await;
+        Ok(bytes)
+    }
+
+    async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
+        self.underlying.get_all(path).await
+    }
+
+    async fn delete(&self, path: &Path) -> StorageResult<()> {
+        self.underlying.delete(path).await
+    }
+
+    async fn bulk_delete(&self, paths: &[&Path]) -> Result<(), BulkDeleteError> {
+        self.underlying.bulk_delete(paths).await
+    }
+
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.underlying.exists(path).await
+    }
+
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        self.underlying.file_num_bytes(path).await
+    }
+
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        self.underlying.list(prefix).await
+    }
+
+    fn uri(&self) -> &Uri {
+        self.underlying.uri()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ByteCountingStorage, RamStorage};
+
+    #[tokio::test]
+    async fn test_caching_storage_second_get_slice_hits_local_cache() -> anyhow::Result<()> {
+        let cache_dir = tempfile::tempdir()?;
+        let cache = Arc::new(LocalFileCache::open(
+            cache_dir.path().join("cache"),
+            10,
+            vec![".split".to_string()],
+        )?);
+        let ram_storage = RamStorage::builder()
+            .put("split1.split", b"hello world")
+            .put("split1.json", b"quickwit")
+            .build();
+        let byte_counting_storage = Arc::new(ByteCountingStorage::new(Arc::new(ram_storage)));
+        let caching_storage = CachingStorage::new(byte_counting_storage.clone(), cache.clone());
+
+        let bytes = caching_storage
+            .get_slice(Path::new("split1.split"), 0..5)
+            .await?;
+        assert_eq!(bytes.as_slice(), b"hello");
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 5);
+        assert_eq!(cache.num_bytes(), 5);
+
+        let bytes = caching_storage
+            .get_slice(Path::new("split1.split"), 0..5)
+            .await?;
+        assert_eq!(bytes.as_slice(), b"hello");
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 5);
+
+        // Files without a cached suffix always hit the underlying storage.
+        caching_storage
+            .get_slice(Path::new("split1.json"), 0..4)
+            .await?;
+        caching_storage
+            .get_slice(Path::new("split1.json"), 0..4)
+            .await?;
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 13);
+
+        // Inserting a new slice evicts the least recently used one to fit the budget.
+        caching_storage
+            .get_slice(Path::new("split1.split"), 6..11)
+            .await?;
+        caching_storage
+            .get_slice(Path::new("split1.split"), 5..11)
+            .await?;
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 24);
+        assert_eq!(cache.num_bytes(), 6);
+        caching_storage
+            .get_slice(Path::new("split1.split"), 0..5)
+            .await?;
+        assert_eq!(byte_counting_storage.num_bytes_fetched(), 29);
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_file_cache_open_only_wipes_its_own_directory() -> anyhow::Result<()> {
+        let cache_dir = tempfile::tempdir()?;
+        let other_file_path = cache_dir.path().join("other-file.json");
+        std::fs::write(&other_file_path, b"{}")?;
+        let stale_slice_path = cache_dir.path().join(SLICES_DIRNAME).join("stale.slice");
+        std::fs::create_dir_all(stale_slice_path.parent().unwrap())?;
+        std::fs::write(&stale_slice_path, b"stale")?;
+
+        let cache = LocalFileCache::open(
+            cache_dir.path().to_path_buf(),
+            1_000,
+            vec![".split".to_string()],
+        )?;
+        assert_eq!(cache.num_bytes(), 0);
+        assert!(other_file_path.exists());
+        assert!(!stale_slice_path.exists());
+        assert!(cache_dir.path().join(SLICES_DIRNAME).is_dir());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_caching_storage_delegates_writes() -> anyhow::Result<()> {
+        let cache_dir = tempfile::tempdir()?;
+        let cache = Arc::new(LocalFileCache::open(
+            cache_dir.path().to_path_buf(),
+            1_000,
+            vec![".split".to_string()],
+        )?);
+        let ram_storage = Arc::new(RamStorage::default());
+        let caching_storage = CachingStorage::new(ram_storage.clone(), cache.clone());
+        caching_storage
+            .put(Path::new("split1.split"), Box::new(b"hello".to_vec()))
+            .await?;
+        assert_eq!(
+            ram_storage
+                .get_all(Path::new("split1.split"))
+                .await?
+                .as_slice(),
+            b"hello"
+        );
+        assert_eq!(cache.num_bytes(), 0);
+        caching_storage.delete(Path::new("split1.split")).await?;
+        assert!(!ram_storage.exists(Path::new("split1.split")).await?);
+        Ok(())
+    }
+}
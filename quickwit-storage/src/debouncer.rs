@@ -29,7 +29,7 @@ use futures::{Future, FutureExt};
 use quickwit_common::uri::Uri;
 use tantivy::directory::OwnedBytes;
 
-use crate::{Storage, StorageResult};
+use crate::{BulkDeleteError, FileMetadata, Storage, StorageResult};
 
 /// The AsyncDebouncer debounces inflight Futures, so that concurrent async request to the same data
 /// source can be deduplicated.
@@ -159,6 +159,10 @@ impl<T: Storage> Storage for DebouncedStorage<T> {
         self.underlying.delete(path).await
     }
 
+    async fn bulk_delete(&self, paths: &[&Path]) -> Result<(), BulkDeleteError> {
+        self.underlying.bulk_delete(paths).await
+    }
+
     async fn get_all(&self, path: &Path) -> StorageResult<OwnedBytes> {
         let (debouncer, underlying) = (self.slice_debouncer.clone(), self.underlying.clone());
         let key = (path.to_owned(), 0..usize::MAX);
@@ -174,9 +178,17 @@ impl<T: Storage> Storage for DebouncedStorage<T> {
         self.underlying.uri()
     }
 
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        self.underlying.exists(path).await
+    }
+
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
         self.underlying.file_num_bytes(path).await
     }
+
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        self.underlying.list(prefix).await
+    }
 }
 
 #[cfg(test)]
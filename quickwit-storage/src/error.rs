@@ -17,6 +17,8 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::{fmt, io};
 
@@ -95,6 +97,23 @@ pub struct StorageError {
 /// Generic Result type for storage operations.
 pub type StorageResult<T> = Result<T, StorageError>;
 
+/// Error returned by `bulk_delete` when some files could not be deleted.
+///
+/// The deletion of a file does not abort the deletion of the others, so the error lists both the
+/// files that were deleted and the files that were not, along with the corresponding error.
+#[derive(Error, Debug, Default)]
+#[error(
+    "Failed to delete {} file(s) out of {}.",
+    .failures.len(),
+    .successes.len() + .failures.len()
+)]
+pub struct BulkDeleteError {
+    /// Files that were successfully deleted.
+    pub successes: Vec<PathBuf>,
+    /// Files that could not be deleted.
+    pub failures: HashMap<PathBuf, StorageError>,
+}
+
 impl StorageError {
     /// Add some context to the wrapper error.
     pub fn add_context<C>(self, ctx: C) -> Self
@@ -38,9 +38,11 @@ pub(crate) use debouncer::DebouncedStorage;
 
 pub use self::metrics::STORAGE_METRICS;
 pub use self::payload::PutPayload;
-pub use self::storage::Storage;
+pub use self::storage::{FileMetadata, Storage};
 
 mod bundle_storage;
+mod byte_counting_storage;
+mod caching_storage;
 mod error;
 mod local_file_storage;
 mod object_storage;
@@ -54,12 +56,16 @@ use quickwit_common::uri::Uri;
 pub use tantivy::directory::OwnedBytes;
 
 pub use self::bundle_storage::{BundleStorage, BundleStorageFileOffsets};
+pub use self::byte_counting_storage::ByteCountingStorage;
 #[cfg(any(test, feature = "testsuite"))]
 pub use self::cache::MockCache;
-pub use self::cache::{wrap_storage_with_long_term_cache, MemorySizedCache};
+pub use self::cache::{fast_field_cache, wrap_storage_with_long_term_cache, MemorySizedCache};
+pub use self::caching_storage::{CachingStorage, LocalFileCache};
 pub use self::local_file_storage::{LocalFileStorage, LocalFileStorageFactory};
 #[cfg(feature = "azure")]
 pub use self::object_storage::{AzureBlobStorage, AzureBlobStorageFactory};
+#[cfg(any(test, feature = "testsuite"))]
+pub use self::object_storage::{MockS3, MockS3Request, MockS3Response};
 pub use self::object_storage::{
     MultiPartPolicy, S3CompatibleObjectStorage, S3CompatibleObjectStorageFactory,
 };
@@ -74,9 +80,12 @@ pub use self::storage_resolver::{
 };
 #[cfg(feature = "testsuite")]
 pub use self::test_suite::{
-    storage_test_multi_part_upload, storage_test_single_part_upload, storage_test_suite,
+    storage_test_list, storage_test_multi_part_upload, storage_test_single_part_upload,
+    storage_test_suite,
+};
+pub use crate::error::{
+    BulkDeleteError, StorageError, StorageErrorKind, StorageResolverError, StorageResult,
 };
-pub use crate::error::{StorageError, StorageErrorKind, StorageResolverError, StorageResult};
 
 /// Loads an entire local or remote file into memory.
 pub async fn load_file(uri: &Uri) -> anyhow::Result<OwnedBytes> {
@@ -111,7 +120,7 @@ mod tests {
 #[cfg(any(test, feature = "testsuite"))]
 pub(crate) mod test_suite {
 
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     use anyhow::Context;
 
@@ -179,6 +188,26 @@ pub(crate) mod test_suite {
         Ok(())
     }
 
+    async fn test_write_and_bulk_delete(storage: &mut dyn Storage) -> anyhow::Result<()> {
+        let test_paths = [
+            Path::new("write_and_bulk_delete_1"),
+            Path::new("write_and_bulk_delete_2"),
+        ];
+        for test_path in test_paths {
+            storage
+                .put(test_path, Box::new(b"abcdef"[..].to_vec()))
+                .await?;
+        }
+        // Missing files are considered deleted.
+        storage
+            .bulk_delete(&[test_paths[0], test_paths[1], Path::new("missing_file")])
+            .await?;
+        for test_path in test_paths {
+            assert!(matches!(storage.exists(test_path).await, Ok(false)));
+        }
+        Ok(())
+    }
+
     async fn test_file_size(storage: &mut dyn Storage) -> anyhow::Result<()> {
         let test_path = Path::new("write_for_filesize");
         let payload_bytes = b"abcdefghijklmnopqrstuvwxyz".as_ref();
@@ -245,6 +274,9 @@ pub(crate) mod test_suite {
         test_write_and_delete(storage)
             .await
             .with_context(|| "write_and_delete")?;
+        test_write_and_bulk_delete(storage)
+            .await
+            .with_context(|| "write_and_bulk_delete")?;
         test_exists(storage).await.with_context(|| "exists")?;
         test_write_and_delete_with_dir_separator(storage)
             .await
@@ -269,9 +301,60 @@ pub(crate) mod test_suite {
     /// Generic multi-part upload test.
     pub async fn storage_test_multi_part_upload(storage: &mut dyn Storage) -> anyhow::Result<()> {
         let test_path = Path::new("hello_large.txt");
-        let test_buffer = vec![0u8; 15_000_000];
-        storage.put(test_path, Box::new(test_buffer)).await?;
+        let test_buffer: Vec<u8> = (0..15_000_000).map(|i| (i % 251) as u8).collect();
+        storage
+            .put(test_path, Box::new(test_buffer.clone()))
+            .await?;
         assert_eq!(storage.file_num_bytes(test_path).await?, 15_000_000);
+        let tempdir = tempfile::tempdir()?;
+        let local_copy = tempdir.path().join("hello_large_copy.txt");
+        storage.copy_to_file(test_path, &local_copy).await?;
+        let payload = std::fs::read(&local_copy)?;
+        assert!(payload == test_buffer);
+        Ok(())
+    }
+
+    /// Generic listing test. More than 1,000 files must be listed to exercise the pagination of
+    /// object storages.
+    pub async fn storage_test_list(
+        storage: &mut dyn Storage,
+        num_files: usize,
+    ) -> anyhow::Result<()> {
+        let mut expected_paths: Vec<PathBuf> = (0..num_files)
+            .map(|file_ord| PathBuf::from(format!("list/dir/file-{:05}", file_ord)))
+            .collect();
+        for path in &expected_paths {
+            storage.put(path, Box::new(b"abc"[..].to_vec())).await?;
+        }
+        let nested_path = PathBuf::from("list/dir/nested/file");
+        storage
+            .put(&nested_path, Box::new(b"abc"[..].to_vec()))
+            .await?;
+        // Shares a string prefix with `list/dir` but is not located under it.
+        let sibling_path = PathBuf::from("list/dir-sibling");
+        storage
+            .put(&sibling_path, Box::new(b"abc"[..].to_vec()))
+            .await?;
+
+        expected_paths.push(nested_path.clone());
+        expected_paths.sort();
+        let files = storage.list(Path::new("list/dir")).await?;
+        let mut paths: Vec<PathBuf> = files.iter().map(|file| file.path.clone()).collect();
+        paths.sort();
+        assert_eq!(paths, expected_paths);
+        assert!(files.iter().all(|file| file.num_bytes == 3));
+        assert!(files.iter().all(|file| file.last_modified_timestamp > 0));
+
+        assert_eq!(storage.list(Path::new("list")).await?.len(), num_files + 2);
+        assert!(storage
+            .list(Path::new("list/missing-dir"))
+            .await?
+            .is_empty());
+
+        expected_paths.push(sibling_path);
+        let path_refs: Vec<&Path> = expected_paths.iter().map(PathBuf::as_path).collect();
+        storage.bulk_delete(&path_refs).await?;
+        assert!(storage.list(Path::new("list")).await?.is_empty());
         Ok(())
     }
 }
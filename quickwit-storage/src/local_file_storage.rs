@@ -21,6 +21,7 @@ use std::io::{ErrorKind, SeekFrom};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::UNIX_EPOCH;
 use std::{fmt, io};
 
 use async_trait::async_trait;
@@ -31,8 +32,8 @@ use tokio::io::{AsyncReadExt, AsyncSeekExt};
 use tracing::warn;
 
 use crate::{
-    DebouncedStorage, OwnedBytes, Storage, StorageError, StorageErrorKind, StorageFactory,
-    StorageResolverError, StorageResult,
+    DebouncedStorage, FileMetadata, OwnedBytes, Storage, StorageError, StorageErrorKind,
+    StorageFactory, StorageResolverError, StorageResult,
 };
 
 /// File system compatible storage implementation.
@@ -123,6 +124,41 @@ fn delete_all_dirs(root: PathBuf, path: &Path) -> BoxFuture<'_, std::io::Result<
     .boxed()
 }
 
+/// Lists recursively the files located under the `{root}/{prefix}` directory. The returned paths
+/// are relative to `{root}`.
+async fn list_files_recursively(root: &Path, prefix: &Path) -> io::Result<Vec<FileMetadata>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.join(prefix)];
+    while let Some(dir) = dirs.pop() {
+        let mut dir_entries = match fs::read_dir(&dir).await {
+            Ok(dir_entries) => dir_entries,
+            // A directory that does not exist holds no file.
+            Err(io_err) if io_err.kind() == ErrorKind::NotFound => continue,
+            Err(io_err) => return Err(io_err),
+        };
+        while let Some(dir_entry) = dir_entries.next_entry().await? {
+            let path = dir_entry.path();
+            let metadata = dir_entry.metadata().await?;
+            if metadata.is_dir() {
+                dirs.push(path);
+            } else if let Ok(relative_path) = path.strip_prefix(root) {
+                let last_modified_timestamp = metadata
+                    .modified()?
+                    .duration_since(UNIX_EPOCH)
+                    .map(|duration| duration.as_secs() as i64)
+                    .unwrap_or_default();
+                files.push(FileMetadata {
+                    path: relative_path.to_path_buf(),
+                    num_bytes: metadata.len(),
+                    last_modified_timestamp,
+                });
+            }
+        }
+    }
+    files.sort_by(|left, right| left.path.cmp(&right.path));
+    Ok(files)
+}
+
 fn missing_file_is_ok(io_result: io::Result<()>) -> io::Result<()> {
     match io_result {
         Ok(()) => Ok(()),
@@ -202,6 +238,11 @@ impl Storage for LocalFileStorage {
         &self.uri
     }
 
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        let files = list_files_recursively(&self.root, prefix).await?;
+        Ok(files)
+    }
+
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
         let full_path = self.root.join(path);
         match fs::metadata(full_path).await {
@@ -246,13 +287,14 @@ mod tests {
     use tempfile::tempdir;
 
     use super::*;
-    use crate::test_suite::storage_test_suite;
+    use crate::test_suite::{storage_test_list, storage_test_suite};
 
     #[tokio::test]
     async fn test_storage() -> anyhow::Result<()> {
         let uri = Uri::try_new(&format!("{}", tempdir()?.path().display())).unwrap();
         let mut file_storage = LocalFileStorage::from_uri(&uri)?;
         storage_test_suite(&mut file_storage).await?;
+        storage_test_list(&mut file_storage, 1_001).await?;
         Ok(())
     }
 
@@ -20,7 +20,10 @@
 // See https://prometheus.io/docs/practices/naming/
 
 use once_cell::sync::Lazy;
-use quickwit_common::metrics::{new_counter, new_gauge, IntCounter, IntGauge};
+use quickwit_common::metrics::{
+    new_counter, new_counter_vec, new_gauge, new_histogram_vec, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge,
+};
 
 /// Counters associated to storage operations.
 pub struct StorageMetrics {
@@ -31,6 +34,10 @@ pub struct StorageMetrics {
     pub object_storage_put_total: IntCounter,
     pub object_storage_put_parts: IntCounter,
     pub object_storage_download_num_bytes: IntCounter,
+    pub object_storage_operations_total: IntCounterVec,
+    pub object_storage_requests_total: IntCounterVec,
+    pub object_storage_request_errors_total: IntCounterVec,
+    pub object_storage_request_duration_seconds: HistogramVec,
 }
 
 impl Default for StorageMetrics {
@@ -60,6 +67,32 @@ impl Default for StorageMetrics {
                 "Amount of data downloaded from an object storage.",
                 "quickwit_storage",
             ),
+            object_storage_operations_total: new_counter_vec(
+                "object_storage_operations_total",
+                "Number of operations performed on an object storage, by operation. An operation \
+                 issues one request per attempt.",
+                "quickwit_storage",
+                &["operation"],
+            ),
+            object_storage_requests_total: new_counter_vec(
+                "object_storage_requests_total",
+                "Number of requests sent to an object storage, retries included, by operation.",
+                "quickwit_storage",
+                &["operation"],
+            ),
+            object_storage_request_errors_total: new_counter_vec(
+                "object_storage_request_errors_total",
+                "Number of failed requests sent to an object storage, by operation and error kind.",
+                "quickwit_storage",
+                &["operation", "error_kind"],
+            ),
+            object_storage_request_duration_seconds: new_histogram_vec(
+                "object_storage_request_duration_seconds",
+                "Duration of the requests sent to an object storage, retries included, by \
+                 operation.",
+                "quickwit_storage",
+                &["operation"],
+            ),
         }
     }
 }
@@ -70,6 +103,7 @@ pub struct CacheMetrics {
     pub component_name: String,
     pub in_cache_count: IntGauge,
     pub in_cache_num_bytes: IntGauge,
+    pub capacity_num_bytes: IntGauge,
     pub hits_num_items: IntCounter,
     pub hits_num_bytes: IntCounter,
     pub misses_num_items: IntCounter,
@@ -90,6 +124,11 @@ impl CacheMetrics {
                 "Number of {component_name} bytes in cache",
                 &namespace,
             ),
+            capacity_num_bytes: new_gauge(
+                "capacity_num_bytes",
+                "Capacity of the {component_name} cache in bytes",
+                &namespace,
+            ),
             hits_num_items: new_counter(
                 "cache_hit_total",
                 "Number of {component_name} cache hits",
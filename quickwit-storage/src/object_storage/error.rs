@@ -22,7 +22,8 @@ use quickwit_aws::retry::Retryable;
 use rusoto_core::RusotoError;
 use rusoto_s3::{
     AbortMultipartUploadError, CompleteMultipartUploadError, CreateMultipartUploadError,
-    DeleteObjectError, GetObjectError, HeadObjectError, PutObjectError, UploadPartError,
+    DeleteObjectError, DeleteObjectsError, GetObjectError, HeadObjectError, ListObjectsV2Error,
+    PutObjectError, UploadPartError,
 };
 
 use crate::{StorageError, StorageErrorKind};
@@ -64,6 +65,12 @@ impl ToStorageErrorKind for DeleteObjectError {
     }
 }
 
+impl ToStorageErrorKind for DeleteObjectsError {
+    fn to_storage_error_kind(&self) -> StorageErrorKind {
+        StorageErrorKind::Service
+    }
+}
+
 impl ToStorageErrorKind for UploadPartError {
     fn to_storage_error_kind(&self) -> StorageErrorKind {
         StorageErrorKind::Service
@@ -99,3 +106,11 @@ impl ToStorageErrorKind for HeadObjectError {
         StorageErrorKind::Service
     }
 }
+
+impl ToStorageErrorKind for ListObjectsV2Error {
+    fn to_storage_error_kind(&self) -> StorageErrorKind {
+        match self {
+            ListObjectsV2Error::NoSuchBucket(_) => StorageErrorKind::DoesNotExist,
+        }
+    }
+}
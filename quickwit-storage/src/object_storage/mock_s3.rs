@@ -0,0 +1,148 @@
+// Copyright (C) 2022 Quickwit, Inc.
+//
+// Quickwit is offered under the AGPL v3.0 and as commercial software.
+// For commercial licensing, contact us at hello@quickwit.io.
+//
+// AGPL:
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as
+// published by the Free Software Foundation, either version 3 of the
+// License, or (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program. If not, see <http://www.gnu.org/licenses/>.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::future;
+use http::header::HeaderName;
+use http::{HeaderMap, StatusCode};
+use rusoto_core::request::{DispatchSignedRequest, DispatchSignedRequestFuture, HttpResponse};
+use rusoto_core::signature::SignedRequest;
+use rusoto_core::ByteStream;
+
+/// Request received by a [`MockS3`].
+#[derive(Clone, Debug)]
+pub struct MockS3Request {
+    /// HTTP method of the request, for instance `PUT`.
+    pub method: String,
+    /// Path of the request, made of the bucket and the key of the object.
+    pub path: String,
+    /// Query parameters of the request, for instance `uploadId`.
+    pub params: BTreeMap<String, Option<String>>,
+}
+
+impl MockS3Request {
+    /// Returns the value of the query parameter `name`, if any.
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .get(name)
+            .and_then(|value_opt| value_opt.as_deref())
+    }
+
+    /// Returns whether the request has the query parameter `name`, with or without a value.
+    pub fn has_param(&self, name: &str) -> bool {
+        self.params.contains_key(name)
+    }
+}
+
+/// Canned response served by a [`MockS3`].
+#[derive(Clone, Debug)]
+pub struct MockS3Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl MockS3Response {
+    /// Creates an empty response with the given HTTP status code.
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+}
+
+type MockS3Handler = dyn Fn(&MockS3Request) -> MockS3Response + Send + Sync;
+
+/// Request dispatcher standing in for S3 in tests. It records the requests it receives and
+/// answers them with the responses returned by its handler, so that the S3 client of a
+/// [`crate::S3CompatibleObjectStorage`] can be exercised without an S3 endpoint.
+#[derive(Clone)]
+pub struct MockS3 {
+    handler: Arc<MockS3Handler>,
+    requests: Arc<Mutex<Vec<MockS3Request>>>,
+}
+
+impl MockS3 {
+    /// Creates a mock answering each request with the response returned by `handler`.
+    pub fn new<F>(handler: F) -> Self
+    where F: Fn(&MockS3Request) -> MockS3Response + Send + Sync + 'static {
+        Self {
+            handler: Arc::new(handler),
+            requests: Default::default(),
+        }
+    }
+
+    /// Returns the requests received so far, in the order they were received.
+    pub fn requests(&self) -> Vec<MockS3Request> {
+        self.requests
+            .lock()
+            .expect("The lock should not be poisoned.")
+            .clone()
+    }
+}
+
+impl DispatchSignedRequest for MockS3 {
+    fn dispatch(
+        &self,
+        request: SignedRequest,
+        _timeout: Option<Duration>,
+    ) -> DispatchSignedRequestFuture {
+        let mock_request = MockS3Request {
+            method: request.method,
+            path: request.path,
+            params: request.params,
+        };
+        let mock_response = (self.handler)(&mock_request);
+        self.requests
+            .lock()
+            .expect("The lock should not be poisoned.")
+            .push(mock_request);
+
+        let mut headers = HeaderMap::<String>::default();
+        for (name, value) in mock_response.headers {
+            let header_name =
+                HeaderName::from_bytes(name.as_bytes()).expect("The header name should be valid.");
+            headers.insert(header_name, value);
+        }
+        let http_response = HttpResponse {
+            status: StatusCode::from_u16(mock_response.status)
+                .expect("The status code should be valid."),
+            body: ByteStream::from(mock_response.body),
+            headers,
+        };
+        Box::pin(future::ready(Ok(http_response)))
+    }
+}
@@ -26,6 +26,11 @@ pub use self::s3_compatible_storage_uri_resolver::S3CompatibleObjectStorageFacto
 mod policy;
 pub use crate::object_storage::policy::MultiPartPolicy;
 
+#[cfg(any(test, feature = "testsuite"))]
+mod mock_s3;
+#[cfg(any(test, feature = "testsuite"))]
+pub use self::mock_s3::{MockS3, MockS3Request, MockS3Response};
+
 mod s3_compatible_storage_uri_resolver;
 
 #[cfg(feature = "azure")]
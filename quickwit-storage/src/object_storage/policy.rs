@@ -34,6 +34,11 @@ pub struct MultiPartPolicy {
     pub max_object_num_bytes: u64,
     /// Maximum number of part to be upload concurrently.
     pub max_concurrent_upload: usize,
+    /// Maximum number of part to be downloaded concurrently.
+    pub max_concurrent_download: usize,
+    /// When enabled, the ETag of the assembled object is checked against the checksums of the
+    /// uploaded parts once a multipart upload completes. This costs an extra request per upload.
+    pub verify_multipart_checksum: bool,
 }
 
 impl MultiPartPolicy {
@@ -66,6 +71,16 @@ impl MultiPartPolicy {
     pub fn max_concurrent_upload(&self) -> usize {
         self.max_concurrent_upload
     }
+
+    /// Returns whether multipart uploads should be verified once completed.
+    pub fn verify_multipart_checksum(&self) -> bool {
+        self.verify_multipart_checksum
+    }
+
+    /// Limits the number of parts that can be concurrently downloaded.
+    pub fn max_concurrent_download(&self) -> usize {
+        self.max_concurrent_download
+    }
 }
 
 // Default values from https://github.com/apache/hadoop/blob/trunk/hadoop-tools/hadoop-aws/src/main/java/org/apache/hadoop/fs/s3a/Constants.java
@@ -80,6 +95,8 @@ impl Default for MultiPartPolicy {
             max_num_parts: 10_000,
             max_object_num_bytes: 5_000_000_000_000u64, // S3 allows up to 5TB objects
             max_concurrent_upload: 100,
+            max_concurrent_download: 100,
+            verify_multipart_checksum: true,
         }
     }
 }
@@ -17,43 +17,56 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
+use std::collections::HashMap;
 use std::fmt::{self, Debug};
-use std::io;
+use std::io::{self, SeekFrom};
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::time::Duration;
 
 use anyhow::Context;
 use async_trait::async_trait;
 use ec2_instance_metadata::InstanceMetadataClient;
-use futures::{stream, StreamExt};
+use futures::{stream, Future, StreamExt};
 use once_cell::sync::OnceCell;
 use quickwit_aws::error::RusotoErrorWrapper;
-use quickwit_aws::get_http_client;
 use quickwit_aws::retry::{retry, Retry, RetryParams, Retryable};
+use quickwit_aws::{get_http_client, get_http_client_with_connect_timeout};
 use quickwit_common::uri::Uri;
 use quickwit_common::{chunk_range, into_u64_range};
+use quickwit_config::IndexStorageConfig;
 use regex::Regex;
-use rusoto_core::credential::ProfileProvider;
+use rusoto_core::credential::{ProfileProvider, StaticProvider};
+use rusoto_core::request::HttpDispatchError;
 use rusoto_core::{ByteStream, Region, RusotoError};
 use rusoto_s3::{
     AbortMultipartUploadRequest, CompleteMultipartUploadRequest, CompletedMultipartUpload,
-    CompletedPart, CreateMultipartUploadError, CreateMultipartUploadRequest, DeleteObjectRequest,
-    GetObjectRequest, HeadObjectError, HeadObjectRequest, ListObjectsV2Request, PutObjectError,
-    PutObjectRequest, S3Client, UploadPartRequest, S3,
+    CompletedPart, CreateMultipartUploadError, CreateMultipartUploadRequest, Delete,
+    DeleteObjectRequest, DeleteObjectsRequest, GetObjectRequest, HeadObjectError, HeadObjectOutput,
+    HeadObjectRequest, ListObjectsV2Request, ObjectIdentifier, PutObjectError, PutObjectRequest,
+    S3Client, UploadPartRequest, S3,
 };
-use tokio::fs::File;
-use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+use tokio::fs::{File, OpenOptions};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader};
 use tracing::{debug, error, info, instrument, warn};
 
+#[cfg(any(test, feature = "testsuite"))]
+use crate::object_storage::MockS3;
 use crate::object_storage::MultiPartPolicy;
 use crate::{
-    OwnedBytes, Storage, StorageError, StorageErrorKind, StorageResolverError, StorageResult,
+    BulkDeleteError, FileMetadata, OwnedBytes, Storage, StorageError, StorageErrorKind,
+    StorageResolverError, StorageResult,
 };
 
 /// Default region to use, if none has been configured.
 const QUICKWIT_DEFAULT_REGION: Region = Region::UsEast1;
 
+/// Maximum number of keys that can be deleted with a single `DeleteObjects` request.
+const MAX_NUM_KEYS_PER_DELETE_OBJECTS_REQUEST: usize = 1_000;
+
 #[instrument]
 fn sniff_s3_region() -> anyhow::Result<Region> {
     // Attempt to read region from environment variable and return an error if malformed.
@@ -134,6 +147,40 @@ fn s3_region_env_var() -> Option<String> {
     None
 }
 
+/// Returns the custom region of a MinIO server from the region or endpoint set in the
+/// environment.
+fn minio_region(region_str_opt: Option<String>) -> anyhow::Result<Region> {
+    let region_str = region_str_opt.context(
+        "`s3+minio://` URIs require the endpoint of the MinIO server. Set the `QW_S3_ENDPOINT` \
+         environment variable (e.g. `QW_S3_ENDPOINT=http://localhost:9000`).",
+    )?;
+    let region = region_from_str(&region_str)?;
+    check_region_matches_flavor(S3Flavor::MinIO, &region)?;
+    Ok(region)
+}
+
+/// Returns the region to use for a given flavor of S3 URI when the index storage config does not
+/// define one.
+fn region_for_flavor(s3_flavor: S3Flavor) -> anyhow::Result<Region> {
+    match s3_flavor {
+        S3Flavor::Aws => sniff_s3_region_and_cache(),
+        S3Flavor::MinIO => minio_region(s3_region_env_var()),
+    }
+}
+
+/// MinIO servers are only reachable through a custom endpoint.
+fn check_region_matches_flavor(s3_flavor: S3Flavor, region: &Region) -> anyhow::Result<()> {
+    if s3_flavor == S3Flavor::MinIO && !matches!(region, Region::Custom { .. }) {
+        anyhow::bail!(
+            "`s3+minio://` URIs require a custom endpoint, but the AWS region `{}` was provided. \
+             Set `QW_S3_ENDPOINT` to the endpoint of the MinIO server (e.g. \
+             `http://localhost:9000`).",
+            region.name()
+        );
+    }
+    Ok(())
+}
+
 #[instrument]
 fn region_from_env_variables() -> anyhow::Result<Option<Region>> {
     if let Some(region_str) = s3_region_env_var() {
@@ -185,6 +232,57 @@ fn region_from_ec2_instance() -> anyhow::Result<Option<Region>> {
     }
 }
 
+/// Returns the region defined by an index storage config, if any. A custom endpoint takes
+/// precedence over the region code, which is then only used as the name of the custom region.
+fn region_from_storage_config(
+    storage_config: &IndexStorageConfig,
+) -> anyhow::Result<Option<Region>> {
+    if let Some(endpoint) = &storage_config.endpoint {
+        let region_name = storage_config
+            .region
+            .clone()
+            .unwrap_or_else(|| "qw-custom-endpoint".to_string());
+        return Ok(Some(Region::Custom {
+            name: region_name,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+        }));
+    }
+    if let Some(region_str) = &storage_config.region {
+        let region = Region::from_str(region_str)
+            .with_context(|| format!("Failed to parse region `{region_str}`."))?;
+        return Ok(Some(region));
+    }
+    Ok(None)
+}
+
+/// Returns the static credentials referenced by an index storage config, if any.
+fn credentials_from_storage_config(
+    storage_config: &IndexStorageConfig,
+) -> anyhow::Result<Option<StaticProvider>> {
+    let (access_key_id_env_var, secret_access_key_env_var) = match (
+        &storage_config.access_key_id_env_var,
+        &storage_config.secret_access_key_env_var,
+    ) {
+        (Some(access_key_id_env_var), Some(secret_access_key_env_var)) => {
+            (access_key_id_env_var, secret_access_key_env_var)
+        }
+        _ => return Ok(None),
+    };
+    let access_key_id = std::env::var(access_key_id_env_var).with_context(|| {
+        format!("Failed to read access key ID from environment variable `{access_key_id_env_var}`.")
+    })?;
+    let secret_access_key = std::env::var(secret_access_key_env_var).with_context(|| {
+        format!(
+            "Failed to read secret access key from environment variable \
+             `{secret_access_key_env_var}`."
+        )
+    })?;
+    Ok(Some(StaticProvider::new_minimal(
+        access_key_id,
+        secret_access_key,
+    )))
+}
+
 /// S3 Compatible object storage implementation.
 pub struct S3CompatibleObjectStorage {
     s3_client: S3Client,
@@ -193,6 +291,12 @@ pub struct S3CompatibleObjectStorage {
     prefix: PathBuf,
     multipart_policy: MultiPartPolicy,
     retry_params: RetryParams,
+    /// Server-side encryption algorithm (`aws:kms` or `AES256`) applied to the written objects.
+    server_side_encryption: Option<String>,
+    /// ID of the KMS key used when `server_side_encryption` is `aws:kms`.
+    sse_kms_key_id: Option<String>,
+    /// Timeout after which a request fails with a retryable error.
+    request_timeout_opt: Option<Duration>,
 }
 
 impl fmt::Debug for S3CompatibleObjectStorage {
@@ -223,23 +327,93 @@ impl S3CompatibleObjectStorage {
         bucket: String,
     ) -> anyhow::Result<S3CompatibleObjectStorage> {
         let s3_client = create_s3_client(region)?;
+        Ok(Self::from_s3_client(s3_client, uri, bucket))
+    }
+
+    fn from_s3_client(s3_client: S3Client, uri: Uri, bucket: String) -> S3CompatibleObjectStorage {
         let retry_params = RetryParams {
             max_attempts: 3,
             ..Default::default()
         };
-        Ok(S3CompatibleObjectStorage {
+        S3CompatibleObjectStorage {
             s3_client,
             uri,
             bucket,
             prefix: PathBuf::new(),
             multipart_policy: MultiPartPolicy::default(),
             retry_params,
-        })
+            server_side_encryption: None,
+            sse_kms_key_id: None,
+            request_timeout_opt: None,
+        }
     }
 
-    /// Creates an object storage given a region and an uri.
+    /// Creates an object storage given an uri and the storage config of the index. The endpoint,
+    /// region, and credentials defined in the storage config override the ones sniffed from the
+    /// environment. The server-side encryption settings apply to the objects written by the
+    /// storage, and the timeouts to every request.
+    pub fn from_uri_and_storage_config(
+        uri: &Uri,
+        storage_config: &IndexStorageConfig,
+    ) -> Result<S3CompatibleObjectStorage, StorageResolverError> {
+        let into_resolver_error = |kind: StorageErrorKind| {
+            move |err: anyhow::Error| StorageResolverError::FailedToOpenStorage {
+                kind,
+                message: err.to_string(),
+            }
+        };
+        let (s3_flavor, bucket, path) =
+            parse_s3_uri(uri).map_err(|err| StorageResolverError::InvalidUri {
+                message: err.to_string(),
+            })?;
+        let region = match region_from_storage_config(storage_config)
+            .map_err(into_resolver_error(StorageErrorKind::Service))?
+        {
+            Some(region) => region,
+            None => region_for_flavor(s3_flavor)
+                .map_err(into_resolver_error(StorageErrorKind::Service))?,
+        };
+        check_region_matches_flavor(s3_flavor, &region)
+            .map_err(into_resolver_error(StorageErrorKind::Service))?;
+        let connect_timeout_opt = storage_config.connect_timeout_secs.map(Duration::from_secs);
+        let http_client = get_http_client_with_connect_timeout(connect_timeout_opt);
+        let s3_client = match credentials_from_storage_config(storage_config)
+            .map_err(into_resolver_error(StorageErrorKind::Unauthorized))?
+        {
+            Some(credentials_provider) => {
+                S3Client::new_with(http_client, credentials_provider, region)
+            }
+            None => {
+                let credentials_provider = quickwit_aws::get_credentials_provider()
+                    .map_err(into_resolver_error(StorageErrorKind::Service))?;
+                S3Client::new_with(http_client, credentials_provider, region)
+            }
+        };
+        let mut s3_compatible_storage =
+            Self::from_s3_client(s3_client, uri.clone(), bucket).with_prefix(&path);
+        s3_compatible_storage.set_server_side_encryption(
+            storage_config.server_side_encryption.clone(),
+            storage_config.sse_kms_key_id.clone(),
+        );
+        s3_compatible_storage
+            .set_request_timeout(storage_config.request_timeout_secs.map(Duration::from_secs));
+        if let Some(max_retry_attempts) = storage_config.max_retry_attempts {
+            s3_compatible_storage.retry_params.max_attempts = max_retry_attempts;
+        }
+        if let Some(retry_base_delay_ms) = storage_config.retry_base_delay_ms {
+            s3_compatible_storage.retry_params.base_delay =
+                Duration::from_millis(retry_base_delay_ms);
+        }
+        Ok(s3_compatible_storage)
+    }
+
+    /// Creates an object storage given an uri.
     pub fn from_uri(uri: &Uri) -> Result<S3CompatibleObjectStorage, StorageResolverError> {
-        let region = sniff_s3_region_and_cache().map_err(|err| {
+        let (s3_flavor, _bucket, _path) =
+            parse_s3_uri(uri).map_err(|err| StorageResolverError::InvalidUri {
+                message: err.to_string(),
+            })?;
+        let region = region_for_flavor(s3_flavor).map_err(|err| {
             StorageResolverError::FailedToOpenStorage {
                 kind: StorageErrorKind::Service,
                 message: err.to_string(),
@@ -253,8 +427,15 @@ impl S3CompatibleObjectStorage {
         region: Region,
         uri: &Uri,
     ) -> Result<S3CompatibleObjectStorage, StorageResolverError> {
-        let (bucket, path) = parse_s3_uri(uri).ok_or_else(|| StorageResolverError::InvalidUri {
-            message: format!("URI `{uri}` is not a valid AWS S3 URI."),
+        let (s3_flavor, bucket, path) =
+            parse_s3_uri(uri).map_err(|err| StorageResolverError::InvalidUri {
+                message: err.to_string(),
+            })?;
+        check_region_matches_flavor(s3_flavor, &region).map_err(|err| {
+            StorageResolverError::FailedToOpenStorage {
+                kind: StorageErrorKind::Service,
+                message: err.to_string(),
+            }
         })?;
         let s3_compatible_storage = S3CompatibleObjectStorage::new(region, uri.clone(), bucket)
             .map_err(|err| StorageResolverError::FailedToOpenStorage {
@@ -264,6 +445,28 @@ impl S3CompatibleObjectStorage {
         Ok(s3_compatible_storage.with_prefix(&path))
     }
 
+    /// Creates an object storage sending its requests to a [`MockS3`] instead of an S3 endpoint.
+    #[cfg(any(test, feature = "testsuite"))]
+    pub fn from_mock_s3(
+        mock_s3: MockS3,
+        uri: &Uri,
+    ) -> Result<S3CompatibleObjectStorage, StorageResolverError> {
+        let (_s3_flavor, bucket, path) =
+            parse_s3_uri(uri).map_err(|err| StorageResolverError::InvalidUri {
+                message: err.to_string(),
+            })?;
+        let region = Region::Custom {
+            name: "mock".to_string(),
+            endpoint: "http://mock-s3".to_string(),
+        };
+        let credentials_provider = StaticProvider::new_minimal(
+            "mock-access-key-id".to_string(),
+            "mock-secret-access-key".to_string(),
+        );
+        let s3_client = S3Client::new_with(mock_s3, credentials_provider, region);
+        Ok(Self::from_s3_client(s3_client, uri.clone(), bucket).with_prefix(&path))
+    }
+
     /// Sets a specific for all buckets.
     ///
     /// This method overrides any existing prefix. (It does NOT
@@ -276,6 +479,9 @@ impl S3CompatibleObjectStorage {
             prefix: prefix.to_path_buf(),
             multipart_policy: self.multipart_policy,
             retry_params: self.retry_params,
+            server_side_encryption: self.server_side_encryption,
+            sse_kms_key_id: self.sse_kms_key_id,
+            request_timeout_opt: self.request_timeout_opt,
         }
     }
 
@@ -285,27 +491,169 @@ impl S3CompatibleObjectStorage {
     pub fn set_policy(&mut self, multipart_policy: MultiPartPolicy) {
         self.multipart_policy = multipart_policy;
     }
+
+    /// Sets the server-side encryption algorithm (`aws:kms` or `AES256`) and the KMS key ID
+    /// applied to the objects written by the storage.
+    pub fn set_server_side_encryption(
+        &mut self,
+        server_side_encryption: Option<String>,
+        sse_kms_key_id: Option<String>,
+    ) {
+        self.server_side_encryption = server_side_encryption;
+        self.sse_kms_key_id = sse_kms_key_id;
+    }
+
+    /// Sets the timeout after which a request fails with a retryable error. When `None`, requests
+    /// never time out.
+    pub fn set_request_timeout(&mut self, request_timeout_opt: Option<Duration>) {
+        self.request_timeout_opt = request_timeout_opt;
+    }
+
+    /// Awaits an S3 request, failing with a retryable `HttpDispatch` error if it does not complete
+    /// within the request timeout.
+    async fn with_request_timeout<T, E, Fut>(
+        &self,
+        request: Fut,
+    ) -> Result<T, RusotoErrorWrapper<E>>
+    where
+        Fut: Future<Output = Result<T, RusotoError<E>>>,
+        E: Retryable + std::error::Error,
+    {
+        let request_timeout = match self.request_timeout_opt {
+            Some(request_timeout) => request_timeout,
+            None => return request.await.map_err(RusotoErrorWrapper::from),
+        };
+        match tokio::time::timeout(request_timeout, request).await {
+            Ok(request_res) => request_res.map_err(RusotoErrorWrapper::from),
+            Err(_elapsed) => Err(RusotoErrorWrapper::from(RusotoError::HttpDispatch(
+                HttpDispatchError::new(format!("Request timed out after {request_timeout:?}.")),
+            ))),
+        }
+    }
+
+    /// Sends a request to S3 and records its duration and outcome under the `operation` label.
+    /// Every attempt of an operation is recorded as a distinct request.
+    async fn send_request<T, E, Fut>(
+        &self,
+        operation: &'static str,
+        request: Fut,
+    ) -> Result<T, RusotoErrorWrapper<E>>
+    where
+        Fut: Future<Output = Result<T, RusotoError<E>>>,
+        E: Retryable + std::error::Error,
+    {
+        crate::STORAGE_METRICS
+            .object_storage_requests_total
+            .with_label_values(&[operation])
+            .inc();
+        let request_timer = crate::STORAGE_METRICS
+            .object_storage_request_duration_seconds
+            .with_label_values(&[operation])
+            .start_timer();
+        let request_res = self.with_request_timeout(request).await;
+        request_timer.observe_duration();
+        if let Err(error) = &request_res {
+            crate::STORAGE_METRICS
+                .object_storage_request_errors_total
+                .with_label_values(&[operation, rusoto_error_kind(&error.0)])
+                .inc();
+        }
+        request_res
+    }
+
+    /// Performs an operation, sending a new request built by `make_request` for every attempt
+    /// until it succeeds or fails with a permanent error.
+    async fn retry_request<T, E, F, Fut>(
+        &self,
+        operation: &'static str,
+        make_request: F,
+    ) -> Result<T, RusotoErrorWrapper<E>>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, RusotoError<E>>>,
+        E: Retryable + std::error::Error + 'static,
+    {
+        record_operation(operation);
+        retry(&self.retry_params, || {
+            self.send_request(operation, make_request())
+        })
+        .await
+    }
+
+    /// The ETag of objects encrypted with a KMS key is not derived from the MD5 digest of their
+    /// content, so it cannot be used to verify multipart uploads.
+    fn is_etag_md5_digest(&self) -> bool {
+        self.server_side_encryption.as_deref() != Some("aws:kms")
+    }
+}
+
+/// Returns whether a `HeadObject` request failed because the object does not exist.
+fn is_missing_key_error(error: &RusotoErrorWrapper<HeadObjectError>) -> bool {
+    match &error.0 {
+        RusotoError::Service(HeadObjectError::NoSuchKey(_)) => true,
+        // Also catching 404 until this issue is fixed: https://github.com/rusoto/rusoto/issues/716
+        RusotoError::Unknown(http_resp) => http_resp.status == 404,
+        _ => false,
+    }
+}
+
+/// Counts an operation performed on the object storage, regardless of its number of attempts.
+fn record_operation(operation: &'static str) {
+    crate::STORAGE_METRICS
+        .object_storage_operations_total
+        .with_label_values(&[operation])
+        .inc();
+}
+
+/// Returns the kind of a failed request, used to label the request errors.
+fn rusoto_error_kind<E>(rusoto_error: &RusotoError<E>) -> &'static str {
+    match rusoto_error {
+        RusotoError::Service(_) => "service",
+        RusotoError::HttpDispatch(_) => "http_dispatch",
+        RusotoError::Credentials(_) => "credentials",
+        RusotoError::Validation(_) => "validation",
+        RusotoError::ParseError(_) => "parse",
+        RusotoError::Unknown(_) => "unknown",
+        RusotoError::Blocking => "blocking",
+    }
 }
 
-pub fn parse_s3_uri(uri: &Uri) -> Option<(String, PathBuf)> {
+/// Flavor of S3-compatible object storage designated by the scheme of an S3 URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum S3Flavor {
+    /// `s3://` URIs. The region is sniffed from the environment.
+    Aws,
+    /// `s3+minio://` URIs. The endpoint of the MinIO server must be provided.
+    MinIO,
+}
+
+/// Parses an S3 URI into its flavor, bucket, and path.
+pub fn parse_s3_uri(uri: &Uri) -> anyhow::Result<(S3Flavor, String, PathBuf)> {
     static S3_URI_PTN: OnceCell<Regex> = OnceCell::new();
-    S3_URI_PTN
+    let captures = S3_URI_PTN
         .get_or_init(|| {
             // s3://bucket/path/to/object
-            Regex::new(r"s3(\+[^:]+)?://(?P<bucket>[^/]+)(/(?P<path>.+))?").unwrap()
+            Regex::new(r"^s3(\+(?P<flavor>[^:]+))?://(?P<bucket>[^/]+)(/(?P<path>.+))?").unwrap()
         })
         .captures(uri.as_str())
-        .and_then(|cap| {
-            cap.name("bucket").map(|bucket_match| {
-                (
-                    bucket_match.as_str().to_string(),
-                    cap.name("path").map_or_else(
-                        || PathBuf::from(""),
-                        |path_match| PathBuf::from(path_match.as_str()),
-                    ),
-                )
-            })
-        })
+        .with_context(|| format!("URI `{uri}` is not a valid AWS S3 URI."))?;
+    let s3_flavor = match captures
+        .name("flavor")
+        .map(|flavor_match| flavor_match.as_str())
+    {
+        None => S3Flavor::Aws,
+        Some("minio") => S3Flavor::MinIO,
+        Some(flavor) => anyhow::bail!(
+            "Unknown S3 flavor `{flavor}` in URI `{uri}`. Quickwit supports `s3://` and \
+             `s3+minio://` URIs."
+        ),
+    };
+    let bucket = captures["bucket"].to_string();
+    let path = captures.name("path").map_or_else(
+        || PathBuf::from(""),
+        |path_match| PathBuf::from(path_match.as_str()),
+    );
+    Ok((s3_flavor, bucket, path))
 }
 
 #[derive(Debug, Clone)]
@@ -324,6 +672,16 @@ impl Part {
     }
 }
 
+/// Computes the ETag S3 assigns to an object assembled from `parts`: the MD5 digest of the
+/// concatenated part digests, suffixed with the number of parts.
+fn compute_multipart_etag(parts: &[Part]) -> String {
+    let mut checksum = md5::Context::new();
+    for part in parts {
+        checksum.consume(part.md5.0);
+    }
+    format!("{:x}-{}", checksum.compute(), parts.len())
+}
+
 const MD5_CHUNK_SIZE: usize = 1_000_000;
 
 async fn compute_md5<T: AsyncRead + std::marker::Unpin>(mut read: T) -> io::Result<md5::Digest> {
@@ -351,16 +709,90 @@ impl S3CompatibleObjectStorage {
         len: u64,
     ) -> Result<(), RusotoErrorWrapper<PutObjectError>> {
         let body = payload.byte_stream().await?;
-        let request = PutObjectRequest {
+        let request = self.create_put_object_request(key, body, len);
+        crate::STORAGE_METRICS.object_storage_put_parts.inc();
+        self.send_request("put_object", self.s3_client.put_object(request))
+            .await?;
+        Ok(())
+    }
+
+    fn create_put_object_request(&self, key: &str, body: ByteStream, len: u64) -> PutObjectRequest {
+        PutObjectRequest {
             bucket: self.bucket.clone(),
             key: key.to_string(),
             body: Some(body),
             content_length: Some(len as i64),
+            server_side_encryption: self.server_side_encryption.clone(),
+            ssekms_key_id: self.sse_kms_key_id.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn create_multipart_upload_request(&self, key: &str) -> CreateMultipartUploadRequest {
+        CreateMultipartUploadRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            server_side_encryption: self.server_side_encryption.clone(),
+            ssekms_key_id: self.sse_kms_key_id.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// Deletes a batch of at most `MAX_NUM_KEYS_PER_DELETE_OBJECTS_REQUEST` objects with a single
+    /// `DeleteObjects` request, and records the outcome for each path in `bulk_delete_error`.
+    async fn delete_objects(&self, paths: &[&Path], bulk_delete_error: &mut BulkDeleteError) {
+        let mut key_to_path: HashMap<String, &Path> =
+            paths.iter().map(|path| (self.key(path), *path)).collect();
+        let objects: Vec<ObjectIdentifier> = key_to_path
+            .keys()
+            .map(|key| ObjectIdentifier {
+                key: key.clone(),
+                ..Default::default()
+            })
+            .collect();
+        let delete_objects_req = DeleteObjectsRequest {
+            bucket: self.bucket.clone(),
+            delete: Delete {
+                objects,
+                // In quiet mode, the response only lists the keys that could not be deleted.
+                quiet: Some(true),
+            },
             ..Default::default()
         };
-        crate::STORAGE_METRICS.object_storage_put_parts.inc();
-        self.s3_client.put_object(request).await?;
-        Ok(())
+        let delete_objects_res = self
+            .retry_request("delete_objects", || {
+                self.s3_client.delete_objects(delete_objects_req.clone())
+            })
+            .await;
+        let delete_objects_output = match delete_objects_res {
+            Ok(delete_objects_output) => delete_objects_output,
+            Err(error) => {
+                let storage_error = StorageError::from(error);
+                for path in key_to_path.into_values() {
+                    bulk_delete_error
+                        .failures
+                        .insert(path.to_path_buf(), storage_error.clone());
+                }
+                return;
+            }
+        };
+        for s3_error in delete_objects_output.errors.unwrap_or_default() {
+            let path = match s3_error.key.and_then(|key| key_to_path.remove(&key)) {
+                Some(path) => path,
+                None => continue,
+            };
+            let storage_error = StorageErrorKind::Service.with_error(anyhow::anyhow!(
+                "Failed to delete object `{}`: {}",
+                path.display(),
+                s3_error.message.unwrap_or_default()
+            ));
+            bulk_delete_error
+                .failures
+                .insert(path.to_path_buf(), storage_error);
+        }
+        bulk_delete_error
+            .successes
+            .extend(key_to_path.into_values().map(Path::to_path_buf));
     }
 
     async fn put_single_part<'a>(
@@ -369,6 +801,7 @@ impl S3CompatibleObjectStorage {
         payload: Box<dyn crate::PutPayload>,
         len: u64,
     ) -> StorageResult<()> {
+        record_operation("put_object");
         retry(&self.retry_params, || async {
             self.put_single_part_single_try(key, payload.clone(), len)
                 .await
@@ -381,22 +814,17 @@ impl S3CompatibleObjectStorage {
         &self,
         key: &str,
     ) -> Result<MultipartUploadId, RusotoErrorWrapper<CreateMultipartUploadError>> {
-        let create_upload_req = CreateMultipartUploadRequest {
-            bucket: self.bucket.clone(),
-            key: key.to_string(),
-            ..Default::default()
-        };
-        let upload_id = retry(&self.retry_params, || async {
-            self.s3_client
-                .create_multipart_upload(create_upload_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
-        })
-        .await?
-        .upload_id
-        .ok_or_else(|| {
-            RusotoError::ParseError("The returned multipart upload id was null.".to_string())
-        })?;
+        let create_upload_req = self.create_multipart_upload_request(key);
+        let upload_id = self
+            .retry_request("create_multipart_upload", || {
+                self.s3_client
+                    .create_multipart_upload(create_upload_req.clone())
+            })
+            .await?
+            .upload_id
+            .ok_or_else(|| {
+                RusotoError::ParseError("The returned multipart upload id was null.".to_string())
+            })?;
         Ok(MultipartUploadId(upload_id))
     }
 
@@ -443,6 +871,8 @@ impl S3CompatibleObjectStorage {
             .map_err(StorageError::from)
             .map_err(Retry::Permanent)?;
         let md5 = base64::encode(part.md5.0);
+        // Parts are encrypted with the settings given when the multipart upload was created:
+        // `UploadPart` does not accept server-side encryption headers other than SSE-C.
         let upload_part_req = UploadPartRequest {
             bucket: self.bucket.clone(),
             key: key.to_string(),
@@ -455,10 +885,8 @@ impl S3CompatibleObjectStorage {
         };
         crate::STORAGE_METRICS.object_storage_put_parts.inc();
         let upload_part_output = self
-            .s3_client
-            .upload_part(upload_part_req)
+            .send_request("upload_part", self.s3_client.upload_part(upload_part_req))
             .await
-            .map_err(RusotoErrorWrapper::from)
             .map_err(|rusoto_err| {
                 if rusoto_err.is_retryable() {
                     Retry::Transient(StorageError::from(rusoto_err))
@@ -486,11 +914,13 @@ impl S3CompatibleObjectStorage {
         let parts = self
             .create_multipart_requests(payload.clone(), total_len, part_len)
             .await?;
+        let expected_etag = compute_multipart_etag(&parts);
         let max_concurrent_upload = self.multipart_policy.max_concurrent_upload();
         let completed_parts_res: StorageResult<Vec<CompletedPart>> =
             stream::iter(parts.into_iter().map(|part| {
                 let payload = payload.clone();
                 let upload_id = upload_id.clone();
+                record_operation("upload_part");
                 retry(&self.retry_params, move || {
                     self.upload_part(upload_id.clone(), key, part.clone(), payload.clone())
                 })
@@ -504,7 +934,11 @@ impl S3CompatibleObjectStorage {
         match completed_parts_res {
             Ok(completed_parts) => {
                 self.complete_multipart_upload(key, completed_parts, &upload_id.0)
-                    .await
+                    .await?;
+                if self.multipart_policy.verify_multipart_checksum() && self.is_etag_md5_digest() {
+                    self.verify_multipart_upload(key, &expected_etag).await?;
+                }
+                Ok(())
             }
             Err(upload_error) => {
                 let abort_multipart_upload_res: StorageResult<()> =
@@ -537,16 +971,41 @@ impl S3CompatibleObjectStorage {
             upload_id: upload_id.to_string(),
             ..Default::default()
         };
-        retry(&self.retry_params, || async {
+        self.retry_request("complete_multipart_upload", || {
             self.s3_client
                 .complete_multipart_upload(complete_upload_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
         })
         .await?;
         Ok(())
     }
 
+    /// Checks that the ETag of the object stored under `key` matches the ETag expected from the
+    /// checksums of the uploaded parts.
+    async fn verify_multipart_upload(&self, key: &str, expected_etag: &str) -> StorageResult<()> {
+        let head_object_req = HeadObjectRequest {
+            bucket: self.bucket.clone(),
+            key: key.to_string(),
+            ..Default::default()
+        };
+        let head_object_output = self
+            .retry_request("head_object", || {
+                self.s3_client.head_object(head_object_req.clone())
+            })
+            .await?;
+        let etag = head_object_output.e_tag.unwrap_or_default();
+        // ETags are returned as quoted strings.
+        if etag.trim_matches('"') != expected_etag {
+            return Err(StorageErrorKind::Service.with_error(anyhow::anyhow!(
+                "Multipart upload of `{}` failed checksum verification: expected ETag `{}`, got \
+                 `{}`.",
+                key,
+                expected_etag,
+                etag
+            )));
+        }
+        Ok(())
+    }
+
     async fn abort_multipart_upload(&self, key: &str, upload_id: &str) -> StorageResult<()> {
         let abort_upload_req = AbortMultipartUploadRequest {
             bucket: self.bucket.clone(),
@@ -554,11 +1013,9 @@ impl S3CompatibleObjectStorage {
             upload_id: upload_id.to_string(),
             ..Default::default()
         };
-        retry(&self.retry_params, || async {
+        self.retry_request("abort_multipart_upload", || {
             self.s3_client
                 .abort_multipart_upload(abort_upload_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
         })
         .await?;
         Ok(())
@@ -587,13 +1044,11 @@ impl S3CompatibleObjectStorage {
     ) -> StorageResult<Vec<u8>> {
         let cap = range_opt.as_ref().map(Range::len).unwrap_or(0);
         let get_object_req = self.create_get_object_request(path, range_opt);
-        let get_object_output = retry(&self.retry_params, || async {
-            self.s3_client
-                .get_object(get_object_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
-        })
-        .await?;
+        let get_object_output = self
+            .retry_request("get_object", || {
+                self.s3_client.get_object(get_object_req.clone())
+            })
+            .await?;
         let mut body = get_object_output.body.ok_or_else(|| {
             StorageErrorKind::Service.with_error(anyhow::anyhow!("Returned object body was empty."))
         })?;
@@ -601,6 +1056,138 @@ impl S3CompatibleObjectStorage {
         download_all(&mut body, &mut buf).await?;
         Ok(buf)
     }
+
+    async fn copy_to_file_single_part(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
+        let get_object_req = self.create_get_object_request(path, None);
+        let get_object_output = self
+            .retry_request("get_object", || {
+                self.s3_client.get_object(get_object_req.clone())
+            })
+            .await?;
+        let body = get_object_output.body.ok_or_else(|| {
+            StorageErrorKind::Service.with_error(anyhow::anyhow!("Returned object body was empty."))
+        })?;
+        let mut body_read = BufReader::new(body.into_async_read());
+        let mut dest_file = File::create(output_path).await?;
+        tokio::io::copy_buf(&mut body_read, &mut dest_file).await?;
+        dest_file.flush().await?;
+        Ok(())
+    }
+
+    /// Downloads the `range` of the object and writes it at the same offset in the output file.
+    async fn download_part<'a>(
+        &'a self,
+        path: &'a Path,
+        range: Range<u64>,
+        output_path: &'a Path,
+    ) -> Result<(), Retry<StorageError>> {
+        let get_object_req =
+            self.create_get_object_request(path, Some(range.start as usize..range.end as usize));
+        let get_object_output = self
+            .send_request("get_object", self.s3_client.get_object(get_object_req))
+            .await
+            .map_err(|rusoto_err| {
+                if rusoto_err.is_retryable() {
+                    Retry::Transient(StorageError::from(rusoto_err))
+                } else {
+                    Retry::Permanent(StorageError::from(rusoto_err))
+                }
+            })?;
+        let body = get_object_output.body.ok_or_else(|| {
+            Retry::Transient(
+                StorageErrorKind::Service
+                    .with_error(anyhow::anyhow!("Returned object body was empty.")),
+            )
+        })?;
+        let mut dest_file = OpenOptions::new()
+            .write(true)
+            .open(output_path)
+            .await
+            .map_err(StorageError::from)
+            .map_err(Retry::Permanent)?;
+        dest_file
+            .seek(SeekFrom::Start(range.start))
+            .await
+            .map_err(StorageError::from)
+            .map_err(Retry::Permanent)?;
+        let mut body_read = BufReader::new(body.into_async_read());
+        // Errors occurring while streaming the body are most likely network errors.
+        let num_bytes_copied = tokio::io::copy_buf(&mut body_read, &mut dest_file)
+            .await
+            .map_err(StorageError::from)
+            .map_err(Retry::Transient)?;
+        dest_file
+            .flush()
+            .await
+            .map_err(StorageError::from)
+            .map_err(Retry::Permanent)?;
+        let expected_num_bytes = range.end - range.start;
+        if num_bytes_copied != expected_num_bytes {
+            return Err(Retry::Transient(StorageErrorKind::Service.with_error(
+                anyhow::anyhow!(
+                    "Downloaded {} bytes for range {:?}, expected {} bytes.",
+                    num_bytes_copied,
+                    range,
+                    expected_num_bytes
+                ),
+            )));
+        }
+        Ok(())
+    }
+
+    async fn head_object(
+        &self,
+        path: &Path,
+    ) -> Result<HeadObjectOutput, RusotoErrorWrapper<HeadObjectError>> {
+        let head_object_req = HeadObjectRequest {
+            bucket: self.bucket.clone(),
+            key: self.key(path),
+            ..Default::default()
+        };
+        self.retry_request("head_object", || {
+            self.s3_client.head_object(head_object_req.clone())
+        })
+        .await
+    }
+
+    async fn copy_to_file_multi_part(
+        &self,
+        path: &Path,
+        output_path: &Path,
+        part_len: u64,
+        total_len: u64,
+    ) -> StorageResult<()> {
+        // The parts are written at their offsets in a file created (or truncated) upfront.
+        File::create(output_path).await?;
+        let part_ranges = chunk_range(0..total_len as usize, part_len as usize)
+            .map(into_u64_range)
+            .collect::<Vec<_>>();
+        let max_concurrent_download = self.multipart_policy.max_concurrent_download();
+        let download_parts_res: StorageResult<Vec<()>> =
+            stream::iter(part_ranges.into_iter().map(|part_range| {
+                record_operation("get_object");
+                retry(&self.retry_params, move || {
+                    self.download_part(path, part_range.clone(), output_path)
+                })
+            }))
+            .buffer_unordered(max_concurrent_download)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .map(|res| res.map_err(|e| e.into_inner()))
+            .collect();
+        download_parts_res?;
+        let num_bytes = tokio::fs::metadata(output_path).await?.len();
+        if num_bytes != total_len {
+            return Err(StorageErrorKind::Service.with_error(anyhow::anyhow!(
+                "Downloaded file `{}` is {} bytes long, expected {} bytes.",
+                output_path.display(),
+                num_bytes,
+                total_len
+            )));
+        }
+        Ok(())
+    }
 }
 
 async fn download_all(byte_stream: &mut ByteStream, output: &mut Vec<u8>) -> io::Result<()> {
@@ -649,23 +1236,15 @@ impl Storage for S3CompatibleObjectStorage {
         Ok(())
     }
 
-    // TODO implement multipart
     async fn copy_to_file(&self, path: &Path, output_path: &Path) -> StorageResult<()> {
-        let get_object_req = self.create_get_object_request(path, None);
-        let get_object_output = retry(&self.retry_params, || async {
-            self.s3_client
-                .get_object(get_object_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
-        })
-        .await?;
-        let body = get_object_output.body.ok_or_else(|| {
-            StorageErrorKind::Service.with_error(anyhow::anyhow!("Returned object body was empty."))
-        })?;
-        let mut body_read = BufReader::new(body.into_async_read());
-        let mut dest_file = File::create(output_path).await?;
-        tokio::io::copy_buf(&mut body_read, &mut dest_file).await?;
-        dest_file.flush().await?;
+        let total_len = self.file_num_bytes(path).await?;
+        let part_num_bytes = self.multipart_policy.part_num_bytes(total_len);
+        if part_num_bytes >= total_len {
+            self.copy_to_file_single_part(path, output_path).await?;
+        } else {
+            self.copy_to_file_multi_part(path, output_path, part_num_bytes, total_len)
+                .await?;
+        }
         Ok(())
     }
 
@@ -676,16 +1255,29 @@ impl Storage for S3CompatibleObjectStorage {
             key,
             ..Default::default()
         };
-        retry(&self.retry_params, || async {
-            self.s3_client
-                .delete_object(delete_object_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
+        self.retry_request("delete_object", || {
+            self.s3_client.delete_object(delete_object_req.clone())
         })
         .await?;
         Ok(())
     }
 
+    async fn bulk_delete(&self, paths: &[&Path]) -> Result<(), BulkDeleteError> {
+        let mut bulk_delete_error = BulkDeleteError::default();
+        for chunk in paths.chunks(MAX_NUM_KEYS_PER_DELETE_OBJECTS_REQUEST) {
+            self.delete_objects(chunk, &mut bulk_delete_error).await;
+        }
+        if bulk_delete_error.failures.is_empty() {
+            return Ok(());
+        }
+        error!(
+            num_failures = bulk_delete_error.failures.len(),
+            num_successes = bulk_delete_error.successes.len(),
+            "Failed to delete some objects."
+        );
+        Err(bulk_delete_error)
+    }
+
     #[instrument(level = "debug", skip(self, range), fields(range.start = range.start, range.end = range.end))]
     async fn get_slice(&self, path: &Path, range: Range<usize>) -> StorageResult<OwnedBytes> {
         self.get_to_vec(path, Some(range.clone()))
@@ -718,22 +1310,16 @@ impl Storage for S3CompatibleObjectStorage {
         Ok(bytes)
     }
 
-    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
-        let key = self.key(path);
-        let head_object_req = HeadObjectRequest {
-            bucket: self.bucket.clone(),
-            key,
-            ..Default::default()
-        };
-        let head_object_output_res = retry(&self.retry_params, || async {
-            self.s3_client
-                .head_object(head_object_req.clone())
-                .await
-                .map_err(RusotoErrorWrapper::from)
-        })
-        .await;
+    async fn exists(&self, path: &Path) -> StorageResult<bool> {
+        match self.head_object(path).await {
+            Ok(_) => Ok(true),
+            Err(error) if is_missing_key_error(&error) => Ok(false),
+            Err(error) => Err(error.into()),
+        }
+    }
 
-        match head_object_output_res {
+    async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
+        match self.head_object(path).await {
             Ok(head_object_output) => {
                 let content_length = head_object_output
                     .content_length
@@ -751,17 +1337,8 @@ impl Storage for S3CompatibleObjectStorage {
                     })?;
                 Ok(content_length)
             }
-            Err(RusotoErrorWrapper(RusotoError::Service(HeadObjectError::NoSuchKey(_)))) => {
-                Err(StorageErrorKind::DoesNotExist
-                    .with_error(anyhow::anyhow!("Missing key in S3 `{}`", path.display())))
-            }
-            // Also catching 404 until this issue is fixed: https://github.com/rusoto/rusoto/issues/716
-            Err(RusotoErrorWrapper(RusotoError::Unknown(http_resp))) if http_resp.status == 404 => {
-                Err(StorageErrorKind::DoesNotExist.with_error(anyhow::anyhow!(
-                    "S3 returned a 404 for key `{}`",
-                    path.display()
-                )))
-            }
+            Err(error) if is_missing_key_error(&error) => Err(StorageErrorKind::DoesNotExist
+                .with_error(anyhow::anyhow!("Missing key in S3 `{}`", path.display()))),
             Err(err) => Err(err.into()),
         }
     }
@@ -769,11 +1346,70 @@ impl Storage for S3CompatibleObjectStorage {
     fn uri(&self) -> &Uri {
         &self.uri
     }
+
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        let mut key_prefix = self.key(prefix);
+        if !key_prefix.is_empty() && !key_prefix.ends_with('/') {
+            key_prefix.push('/');
+        }
+        let mut files = Vec::new();
+        let mut continuation_token_opt: Option<String> = None;
+        loop {
+            let list_objects_req = ListObjectsV2Request {
+                bucket: self.bucket.clone(),
+                prefix: Some(key_prefix.clone()),
+                continuation_token: continuation_token_opt.clone(),
+                ..Default::default()
+            };
+            let list_objects_output = self
+                .retry_request("list_objects_v2", || {
+                    self.s3_client.list_objects_v2(list_objects_req.clone())
+                })
+                .await?;
+            for object in list_objects_output.contents.unwrap_or_default() {
+                let key = match object.key {
+                    Some(key) => key,
+                    None => continue,
+                };
+                if let Ok(path) = Path::new(&key).strip_prefix(&self.prefix) {
+                    let last_modified_timestamp =
+                        parse_last_modified(object.last_modified.as_deref())
+                            .with_context(|| format!("Failed to list object `{}`.", key))
+                            .map_err(|error| StorageErrorKind::Service.with_error(error))?;
+                    files.push(FileMetadata {
+                        path: path.to_path_buf(),
+                        num_bytes: object.size.unwrap_or_default() as u64,
+                        last_modified_timestamp,
+                    });
+                }
+            }
+            // A truncated response carries the token from which the next page is fetched.
+            continuation_token_opt = match list_objects_output.next_continuation_token {
+                Some(next_continuation_token)
+                    if list_objects_output.is_truncated.unwrap_or(false) =>
+                {
+                    Some(next_continuation_token)
+                }
+                _ => break,
+            };
+        }
+        Ok(files)
+    }
+}
+
+/// Parses the RFC 3339 last modified date returned by `ListObjectsV2` into a Unix timestamp.
+fn parse_last_modified(last_modified_opt: Option<&str>) -> anyhow::Result<i64> {
+    let last_modified = last_modified_opt.context("Last modified date is missing.")?;
+    let last_modified_datetime = OffsetDateTime::parse(last_modified, &Rfc3339)
+        .with_context(|| format!("Last modified date `{}` is invalid.", last_modified))?;
+    Ok(last_modified_datetime.unix_timestamp())
 }
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_md5_calc() -> std::io::Result<()> {
@@ -784,6 +1420,35 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_compute_multipart_etag() {
+        let part_md5s = [md5::compute(b"hello"), md5::compute(b"world")];
+        let parts = part_md5s
+            .iter()
+            .enumerate()
+            .map(|(part_id, md5)| Part {
+                part_number: part_id + 1,
+                range: 0..5,
+                md5: *md5,
+            })
+            .collect::<Vec<_>>();
+        let expected_digest = md5::compute([part_md5s[0].0, part_md5s[1].0].concat());
+        assert_eq!(
+            compute_multipart_etag(&parts),
+            format!("{:x}-2", expected_digest)
+        );
+    }
+
+    #[test]
+    fn test_parse_last_modified() {
+        assert_eq!(
+            parse_last_modified(Some("2022-08-01T12:00:00.000Z")).unwrap(),
+            1_659_355_200
+        );
+        assert!(parse_last_modified(Some("yesterday")).is_err());
+        assert!(parse_last_modified(None).is_err());
+    }
+
     #[test]
     fn test_split_range_into_chunks_inexact() {
         assert_eq!(
@@ -804,38 +1469,80 @@ mod tests {
         assert_eq!(chunk_range(0..0, 1).collect::<Vec<_>>(), vec![]);
     }
 
+    use futures::future;
+    use quickwit_aws::retry::Retryable;
     use quickwit_common::chunk_range;
     use quickwit_common::uri::Uri;
-    use rusoto_core::Region;
+    use quickwit_config::IndexStorageConfig;
+    use rusoto_core::request::HttpDispatchError;
+    use rusoto_core::{Region, RusotoError};
+    use rusoto_s3::GetObjectError;
 
-    use super::{compute_md5, parse_s3_uri, region_from_str};
+    use super::{
+        check_region_matches_flavor, compute_md5, compute_multipart_etag,
+        credentials_from_storage_config, minio_region, parse_last_modified, parse_s3_uri,
+        region_from_storage_config, region_from_str, Part, S3CompatibleObjectStorage, S3Flavor,
+    };
+    use crate::object_storage::{MockS3, MockS3Response, MultiPartPolicy};
+    use crate::{Storage, StorageErrorKind};
 
     #[test]
     fn test_parse_uri() {
         assert_eq!(
-            parse_s3_uri(&Uri::new("s3://bucket/path/to/object".to_string())),
-            Some(("bucket".to_string(), PathBuf::from("path/to/object")))
-        );
-        assert_eq!(
-            parse_s3_uri(&Uri::new("s3://bucket/path".to_string())),
-            Some(("bucket".to_string(), PathBuf::from("path")))
+            parse_s3_uri(&Uri::new("s3://bucket/path/to/object".to_string())).unwrap(),
+            (
+                S3Flavor::Aws,
+                "bucket".to_string(),
+                PathBuf::from("path/to/object")
+            )
         );
         assert_eq!(
-            parse_s3_uri(&Uri::new("s3://bucket/path/to/object".to_string())),
-            Some(("bucket".to_string(), PathBuf::from("path/to/object")))
+            parse_s3_uri(&Uri::new("s3://bucket/path".to_string())).unwrap(),
+            (S3Flavor::Aws, "bucket".to_string(), PathBuf::from("path"))
         );
         assert_eq!(
-            parse_s3_uri(&Uri::new("s3://bucket/".to_string())),
-            Some(("bucket".to_string(), PathBuf::from("")))
+            parse_s3_uri(&Uri::new("s3://bucket/".to_string())).unwrap(),
+            (S3Flavor::Aws, "bucket".to_string(), PathBuf::from(""))
         );
         assert_eq!(
-            parse_s3_uri(&Uri::new("s3://bucket".to_string())),
-            Some(("bucket".to_string(), PathBuf::from("")))
+            parse_s3_uri(&Uri::new("s3://bucket".to_string())).unwrap(),
+            (S3Flavor::Aws, "bucket".to_string(), PathBuf::from(""))
         );
         assert_eq!(
-            parse_s3_uri(&Uri::new("ram://path/to/file".to_string())),
-            None
+            parse_s3_uri(&Uri::new("s3+minio://bucket/path/to/object".to_string())).unwrap(),
+            (
+                S3Flavor::MinIO,
+                "bucket".to_string(),
+                PathBuf::from("path/to/object")
+            )
         );
+        let error = parse_s3_uri(&Uri::try_new("s3+gcs://bucket/path").unwrap()).unwrap_err();
+        assert!(error.to_string().contains("Unknown S3 flavor `gcs`"));
+        assert!(parse_s3_uri(&Uri::new("ram://path/to/file".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_minio_region() {
+        assert!(matches!(
+            minio_region(Some("http://localhost:9000/".to_string())).unwrap(),
+            Region::Custom { endpoint, .. } if endpoint == "http://localhost:9000"
+        ));
+        let error = minio_region(None).unwrap_err();
+        assert!(error.to_string().contains("QW_S3_ENDPOINT"));
+        let error = minio_region(Some("us-east-1".to_string())).unwrap_err();
+        assert!(error.to_string().contains("require a custom endpoint"));
+    }
+
+    #[test]
+    fn test_check_region_matches_flavor() {
+        let custom_region = Region::Custom {
+            name: "qw-custom-endpoint".to_string(),
+            endpoint: "http://localhost:9000".to_string(),
+        };
+        check_region_matches_flavor(S3Flavor::Aws, &Region::UsEast1).unwrap();
+        check_region_matches_flavor(S3Flavor::Aws, &custom_region).unwrap();
+        check_region_matches_flavor(S3Flavor::MinIO, &custom_region).unwrap();
+        check_region_matches_flavor(S3Flavor::MinIO, &Region::UsEast1).unwrap_err();
     }
 
     #[test]
@@ -865,4 +1572,412 @@ mod tests {
         );
         assert!(region_from_str("us-eat-1").is_err());
     }
+
+    #[test]
+    fn test_region_from_storage_config() {
+        assert!(region_from_storage_config(&IndexStorageConfig::default())
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            region_from_storage_config(&IndexStorageConfig {
+                region: Some("eu-west-1".to_string()),
+                ..Default::default()
+            })
+            .unwrap(),
+            Some(Region::EuWest1)
+        );
+        assert_eq!(
+            region_from_storage_config(&IndexStorageConfig {
+                endpoint: Some("http://localhost:9000/".to_string()),
+                ..Default::default()
+            })
+            .unwrap(),
+            Some(Region::Custom {
+                name: "qw-custom-endpoint".to_string(),
+                endpoint: "http://localhost:9000".to_string()
+            })
+        );
+        assert_eq!(
+            region_from_storage_config(&IndexStorageConfig {
+                endpoint: Some("http://localhost:9000".to_string()),
+                region: Some("tenant-region".to_string()),
+                ..Default::default()
+            })
+            .unwrap(),
+            Some(Region::Custom {
+                name: "tenant-region".to_string(),
+                endpoint: "http://localhost:9000".to_string()
+            })
+        );
+        assert!(region_from_storage_config(&IndexStorageConfig {
+            region: Some("us-eat-1".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_credentials_from_storage_config() {
+        assert!(
+            credentials_from_storage_config(&IndexStorageConfig::default())
+                .unwrap()
+                .is_none()
+        );
+        let storage_config = IndexStorageConfig {
+            access_key_id_env_var: Some("QW_TEST_TENANT_ACCESS_KEY_ID".to_string()),
+            secret_access_key_env_var: Some("QW_TEST_TENANT_SECRET_ACCESS_KEY".to_string()),
+            ..Default::default()
+        };
+        assert!(credentials_from_storage_config(&storage_config).is_err());
+
+        std::env::set_var("QW_TEST_TENANT_ACCESS_KEY_ID", "tenant-access-key-id");
+        std::env::set_var(
+            "QW_TEST_TENANT_SECRET_ACCESS_KEY",
+            "tenant-secret-access-key",
+        );
+        assert!(credentials_from_storage_config(&storage_config)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn test_server_side_encryption_from_storage_config() {
+        let uri = Uri::new("s3://bucket/indexes".to_string());
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            ..Default::default()
+        };
+        let s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        let put_object_request =
+            s3_storage.create_put_object_request("split", Vec::new().into(), 0);
+        assert!(put_object_request.server_side_encryption.is_none());
+        assert!(put_object_request.ssekms_key_id.is_none());
+        let create_upload_request = s3_storage.create_multipart_upload_request("split");
+        assert!(create_upload_request.server_side_encryption.is_none());
+        assert!(create_upload_request.ssekms_key_id.is_none());
+        assert!(s3_storage.is_etag_md5_digest());
+
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            server_side_encryption: Some("aws:kms".to_string()),
+            sse_kms_key_id: Some("my-kms-key-id".to_string()),
+            ..Default::default()
+        };
+        let s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        let put_object_request =
+            s3_storage.create_put_object_request("split", Vec::new().into(), 0);
+        assert_eq!(
+            put_object_request.server_side_encryption.as_deref(),
+            Some("aws:kms")
+        );
+        assert_eq!(
+            put_object_request.ssekms_key_id.as_deref(),
+            Some("my-kms-key-id")
+        );
+        let create_upload_request = s3_storage.create_multipart_upload_request("split");
+        assert_eq!(
+            create_upload_request.server_side_encryption.as_deref(),
+            Some("aws:kms")
+        );
+        assert_eq!(
+            create_upload_request.ssekms_key_id.as_deref(),
+            Some("my-kms-key-id")
+        );
+        assert!(!s3_storage.is_etag_md5_digest());
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_from_storage_config() {
+        let uri = Uri::new("s3://bucket/indexes".to_string());
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            ..Default::default()
+        };
+        let s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        assert!(s3_storage.request_timeout_opt.is_none());
+        let request = future::ready(Ok::<_, RusotoError<GetObjectError>>(42));
+        assert_eq!(s3_storage.with_request_timeout(request).await.unwrap(), 42);
+
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            request_timeout_secs: Some(1),
+            connect_timeout_secs: Some(1),
+            ..Default::default()
+        };
+        let mut s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        assert_eq!(s3_storage.request_timeout_opt, Some(Duration::from_secs(1)));
+
+        s3_storage.set_request_timeout(Some(Duration::from_millis(10)));
+        let request = future::pending::<Result<(), RusotoError<GetObjectError>>>();
+        let error = s3_storage.with_request_timeout(request).await.unwrap_err();
+        assert!(error.is_retryable());
+        assert!(error.to_string().contains("Request timed out"));
+    }
+
+    #[tokio::test]
+    async fn test_retry_request_records_metrics() {
+        let uri = Uri::new("s3://bucket/indexes".to_string());
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            ..Default::default()
+        };
+        let s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        let num_attempts = AtomicUsize::new(0);
+        let request_res = s3_storage
+            .retry_request("test_operation", || {
+                let request_res = if num_attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    Err(RusotoError::<GetObjectError>::HttpDispatch(
+                        HttpDispatchError::new("Connection reset.".to_string()),
+                    ))
+                } else {
+                    Ok(42)
+                };
+                future::ready(request_res)
+            })
+            .await;
+        assert_eq!(request_res.unwrap(), 42);
+
+        let storage_metrics = &crate::STORAGE_METRICS;
+        assert_eq!(
+            storage_metrics
+                .object_storage_operations_total
+                .with_label_values(&["test_operation"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            storage_metrics
+                .object_storage_requests_total
+                .with_label_values(&["test_operation"])
+                .get(),
+            2
+        );
+        assert_eq!(
+            storage_metrics
+                .object_storage_request_errors_total
+                .with_label_values(&["test_operation", "http_dispatch"])
+                .get(),
+            1
+        );
+        assert_eq!(
+            storage_metrics
+                .object_storage_request_duration_seconds
+                .with_label_values(&["test_operation"])
+                .get_sample_count(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_retry_params_from_storage_config() {
+        let uri = Uri::new("s3://bucket/indexes".to_string());
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            ..Default::default()
+        };
+        let s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        assert_eq!(s3_storage.retry_params.max_attempts, 3);
+
+        let storage_config = IndexStorageConfig {
+            endpoint: Some("http://localhost:4566".to_string()),
+            max_retry_attempts: Some(5),
+            retry_base_delay_ms: Some(100),
+            ..Default::default()
+        };
+        let s3_storage =
+            S3CompatibleObjectStorage::from_uri_and_storage_config(&uri, &storage_config).unwrap();
+        assert_eq!(s3_storage.retry_params.max_attempts, 5);
+        assert_eq!(
+            s3_storage.retry_params.base_delay,
+            Duration::from_millis(100)
+        );
+    }
+
+    const INITIATE_MULTIPART_UPLOAD_RESULT: &str = "<InitiateMultipartUploadResult>\
+        <Bucket>bucket</Bucket>\
+        <Key>indexes/split.split</Key>\
+        <UploadId>upload-id</UploadId>\
+        </InitiateMultipartUploadResult>";
+
+    const COMPLETE_MULTIPART_UPLOAD_RESULT: &str = "<CompleteMultipartUploadResult>\
+        <Bucket>bucket</Bucket>\
+        <Key>indexes/split.split</Key>\
+        <ETag>\"multipart-etag\"</ETag>\
+        </CompleteMultipartUploadResult>";
+
+    const ACCESS_DENIED_ERROR: &str = "<Error>\
+        <Code>AccessDenied</Code>\
+        <Message>Access Denied</Message>\
+        </Error>";
+
+    /// Creates a storage backed by `mock_s3` that uploads and downloads objects of more than 10
+    /// bytes in parts of 5 bytes.
+    fn s3_storage_for_mock(mock_s3: &MockS3) -> S3CompatibleObjectStorage {
+        let uri = Uri::new("s3://bucket/indexes".to_string());
+        let mut s3_storage =
+            S3CompatibleObjectStorage::from_mock_s3(mock_s3.clone(), &uri).unwrap();
+        s3_storage.set_policy(MultiPartPolicy {
+            target_part_num_bytes: 5,
+            max_num_parts: 10,
+            multipart_threshold_num_bytes: 10,
+            max_object_num_bytes: 1_000,
+            max_concurrent_upload: 1,
+            max_concurrent_download: 1,
+            verify_multipart_checksum: false,
+        });
+        s3_storage
+    }
+
+    #[tokio::test]
+    async fn test_put_multi_part_completes_upload() {
+        let mock_s3 = MockS3::new(|request| match request.method.as_str() {
+            "POST" if request.has_param("uploads") => {
+                MockS3Response::new(200).body(INITIATE_MULTIPART_UPLOAD_RESULT)
+            }
+            "PUT" if request.has_param("partNumber") => {
+                let part_etag = format!("\"etag-{}\"", request.param("partNumber").unwrap());
+                MockS3Response::new(200).header("ETag", &part_etag)
+            }
+            "POST" if request.has_param("uploadId") => {
+                MockS3Response::new(200).body(COMPLETE_MULTIPART_UPLOAD_RESULT)
+            }
+            _ => MockS3Response::new(400),
+        });
+        let s3_storage = s3_storage_for_mock(&mock_s3);
+        s3_storage
+            .put(Path::new("split.split"), Box::new(vec![0u8; 20]))
+            .await
+            .unwrap();
+
+        let requests = mock_s3.requests();
+        assert_eq!(requests.len(), 6);
+        assert!(requests
+            .iter()
+            .all(|request| request.path.ends_with("/indexes/split.split")));
+        let part_numbers: Vec<&str> = requests[1..5]
+            .iter()
+            .map(|request| {
+                assert_eq!(request.method, "PUT");
+                assert_eq!(request.param("uploadId"), Some("upload-id"));
+                request.param("partNumber").unwrap()
+            })
+            .collect();
+        assert_eq!(part_numbers, vec!["1", "2", "3", "4"]);
+        assert_eq!(requests[5].method, "POST");
+        assert_eq!(requests[5].param("uploadId"), Some("upload-id"));
+    }
+
+    #[tokio::test]
+    async fn test_put_multi_part_aborts_upload_on_upload_part_failure() {
+        let mock_s3 = MockS3::new(|request| match request.method.as_str() {
+            "POST" if request.has_param("uploads") => {
+                MockS3Response::new(200).body(INITIATE_MULTIPART_UPLOAD_RESULT)
+            }
+            "PUT" if request.has_param("partNumber") => {
+                MockS3Response::new(403).body(ACCESS_DENIED_ERROR)
+            }
+            "DELETE" if request.has_param("uploadId") => MockS3Response::new(204),
+            _ => MockS3Response::new(400),
+        });
+        let s3_storage = s3_storage_for_mock(&mock_s3);
+        s3_storage
+            .put(Path::new("split.split"), Box::new(vec![0u8; 20]))
+            .await
+            .unwrap_err();
+
+        let requests = mock_s3.requests();
+        assert!(requests
+            .iter()
+            .any(|request| request.method == "PUT" && request.has_param("partNumber")));
+        // The upload is neither completed nor left dangling.
+        assert!(!requests
+            .iter()
+            .any(|request| request.method == "POST" && request.has_param("uploadId")));
+        let last_request = requests.last().unwrap();
+        assert_eq!(last_request.method, "DELETE");
+        assert_eq!(last_request.param("uploadId"), Some("upload-id"));
+    }
+
+    #[tokio::test]
+    async fn test_file_num_bytes_with_mock_s3() {
+        let mock_s3 = MockS3::new(|request| {
+            if request.method != "HEAD" {
+                return MockS3Response::new(400);
+            }
+            if request.path.ends_with("/missing.split") {
+                return MockS3Response::new(404);
+            }
+            MockS3Response::new(200).header("Content-Length", "3")
+        });
+        let s3_storage = s3_storage_for_mock(&mock_s3);
+        assert_eq!(
+            s3_storage
+                .file_num_bytes(Path::new("split.split"))
+                .await
+                .unwrap(),
+            3
+        );
+        let storage_error = s3_storage
+            .file_num_bytes(Path::new("missing.split"))
+            .await
+            .unwrap_err();
+        assert_eq!(storage_error.kind(), StorageErrorKind::DoesNotExist);
+    }
+
+    #[tokio::test]
+    async fn test_exists_with_mock_s3() {
+        let mock_s3 = MockS3::new(|request| {
+            if request.path.ends_with("/missing.split") {
+                return MockS3Response::new(404);
+            }
+            if request.path.ends_with("/forbidden.split") {
+                return MockS3Response::new(403);
+            }
+            MockS3Response::new(200).header("Content-Length", "3")
+        });
+        let s3_storage = s3_storage_for_mock(&mock_s3);
+        assert!(s3_storage.exists(Path::new("split.split")).await.unwrap());
+        assert!(!s3_storage.exists(Path::new("missing.split")).await.unwrap());
+        let storage_error = s3_storage
+            .exists(Path::new("forbidden.split"))
+            .await
+            .unwrap_err();
+        assert_ne!(storage_error.kind(), StorageErrorKind::DoesNotExist);
+        assert!(mock_s3
+            .requests()
+            .iter()
+            .all(|request| request.method == "HEAD"));
+    }
+
+    #[tokio::test]
+    async fn test_copy_to_file_with_mock_s3() {
+        let mock_s3 = MockS3::new(|request| match request.method.as_str() {
+            "HEAD" => MockS3Response::new(200).header("Content-Length", "3"),
+            "GET" => MockS3Response::new(200)
+                .header("Content-Length", "3")
+                .body(&b"abc"[..]),
+            _ => MockS3Response::new(400),
+        });
+        let s3_storage = s3_storage_for_mock(&mock_s3);
+        let temp_dir = tempfile::tempdir().unwrap();
+        let output_path = temp_dir.path().join("split.split");
+        s3_storage
+            .copy_to_file(Path::new("split.split"), &output_path)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"abc");
+
+        let methods: Vec<&str> = mock_s3
+            .requests()
+            .iter()
+            .map(|request| request.method.as_str())
+            .collect();
+        assert_eq!(methods, vec!["HEAD", "GET"]);
+    }
 }
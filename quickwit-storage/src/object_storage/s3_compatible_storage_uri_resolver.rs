@@ -20,6 +20,7 @@
 use std::sync::Arc;
 
 use quickwit_common::uri::{Protocol, Uri};
+use quickwit_config::IndexStorageConfig;
 pub use rusoto_core::Region;
 
 use crate::{
@@ -39,4 +40,13 @@ impl StorageFactory for S3CompatibleObjectStorageFactory {
         let storage = S3CompatibleObjectStorage::from_uri(uri)?;
         Ok(Arc::new(DebouncedStorage::new(storage)))
     }
+
+    fn resolve_with_storage_config(
+        &self,
+        uri: &Uri,
+        storage_config: &IndexStorageConfig,
+    ) -> Result<Arc<dyn Storage>, StorageResolverError> {
+        let storage = S3CompatibleObjectStorage::from_uri_and_storage_config(uri, storage_config)?;
+        Ok(Arc::new(DebouncedStorage::new(storage)))
+    }
 }
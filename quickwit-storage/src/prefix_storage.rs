@@ -24,7 +24,7 @@ use std::sync::Arc;
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 
-use crate::{OwnedBytes, Storage};
+use crate::{BulkDeleteError, FileMetadata, OwnedBytes, Storage};
 
 /// This storage acts as a proxy to another storage that simply modifies each API call
 /// by preceding each path with a given a prefix.
@@ -70,6 +70,34 @@ impl Storage for PrefixStorage {
         self.storage.delete(&self.prefix.join(path)).await
     }
 
+    async fn bulk_delete(&self, paths: &[&Path]) -> Result<(), BulkDeleteError> {
+        let prefixed_paths: Vec<PathBuf> =
+            paths.iter().map(|path| self.prefix.join(path)).collect();
+        let prefixed_path_refs: Vec<&Path> = prefixed_paths.iter().map(PathBuf::as_path).collect();
+        self.storage
+            .bulk_delete(&prefixed_path_refs)
+            .await
+            .map_err(|bulk_delete_error| {
+                let strip_prefix = |path: PathBuf| {
+                    path.strip_prefix(&self.prefix)
+                        .map(Path::to_path_buf)
+                        .unwrap_or(path)
+                };
+                BulkDeleteError {
+                    successes: bulk_delete_error
+                        .successes
+                        .into_iter()
+                        .map(strip_prefix)
+                        .collect(),
+                    failures: bulk_delete_error
+                        .failures
+                        .into_iter()
+                        .map(|(path, error)| (strip_prefix(path), error))
+                        .collect(),
+                }
+            })
+    }
+
     async fn exists(&self, path: &Path) -> crate::StorageResult<bool> {
         self.storage.exists(&self.prefix.join(path)).await
     }
@@ -81,6 +109,20 @@ impl Storage for PrefixStorage {
     async fn file_num_bytes(&self, path: &Path) -> crate::StorageResult<u64> {
         self.storage.file_num_bytes(&self.prefix.join(path)).await
     }
+
+    async fn list(&self, prefix: &Path) -> crate::StorageResult<Vec<FileMetadata>> {
+        let files = self
+            .storage
+            .list(&self.prefix.join(prefix))
+            .await?
+            .into_iter()
+            .filter_map(|file| {
+                let path = file.path.strip_prefix(&self.prefix).ok()?.to_path_buf();
+                Some(FileMetadata { path, ..file })
+            })
+            .collect();
+        Ok(files)
+    }
 }
 
 /// Creates a [`PrefixStorage`] using an underlying storage and a prefix.
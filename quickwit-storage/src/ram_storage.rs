@@ -22,6 +22,7 @@ use std::fmt;
 use std::ops::Range;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use async_trait::async_trait;
 use quickwit_common::uri::{Protocol, Uri};
@@ -31,16 +32,37 @@ use tokio::sync::RwLock;
 
 use crate::prefix_storage::add_prefix_to_storage;
 use crate::{
-    OwnedBytes, Storage, StorageErrorKind, StorageFactory, StorageResolverError, StorageResult,
+    FileMetadata, OwnedBytes, Storage, StorageErrorKind, StorageFactory, StorageResolverError,
+    StorageResult,
 };
 
+/// File held in memory by a [`RamStorage`].
+#[derive(Clone)]
+struct RamFile {
+    payload: OwnedBytes,
+    last_modified_timestamp: i64,
+}
+
+impl RamFile {
+    fn new(payload: OwnedBytes) -> Self {
+        let last_modified_timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or_default();
+        Self {
+            payload,
+            last_modified_timestamp,
+        }
+    }
+}
+
 /// In Ram implementation of quickwit's storage.
 ///
 /// This implementation is mostly useful in unit tests.
 #[derive(Clone)]
 pub struct RamStorage {
     uri: Uri,
-    files: Arc<RwLock<HashMap<PathBuf, OwnedBytes>>>,
+    files: Arc<RwLock<HashMap<PathBuf, RamFile>>>,
 }
 
 impl fmt::Debug for RamStorage {
@@ -68,11 +90,18 @@ impl RamStorage {
     }
 
     async fn put_data(&self, path: &Path, payload: OwnedBytes) {
-        self.files.write().await.insert(path.to_path_buf(), payload);
+        self.files
+            .write()
+            .await
+            .insert(path.to_path_buf(), RamFile::new(payload));
     }
 
     async fn get_data(&self, path: &Path) -> Option<OwnedBytes> {
-        self.files.read().await.get(path).cloned()
+        self.files
+            .read()
+            .await
+            .get(path)
+            .map(|file| file.payload.clone())
     }
 
     /// Returns the list of files that are present in the RamStorage.
@@ -133,9 +162,26 @@ impl Storage for RamStorage {
         &self.uri
     }
 
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        let mut files: Vec<FileMetadata> = self
+            .files
+            .read()
+            .await
+            .iter()
+            .filter(|(path, _)| path.starts_with(prefix))
+            .map(|(path, file)| FileMetadata {
+                path: path.clone(),
+                num_bytes: file.payload.len() as u64,
+                last_modified_timestamp: file.last_modified_timestamp,
+            })
+            .collect();
+        files.sort_by(|left, right| left.path.cmp(&right.path));
+        Ok(files)
+    }
+
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64> {
-        if let Some(file_bytes) = self.files.read().await.get(path) {
-            Ok(file_bytes.len() as u64)
+        if let Some(file) = self.files.read().await.get(path) {
+            Ok(file.payload.len() as u64)
         } else {
             let err = anyhow::anyhow!("Missing file `{}`", path.display());
             Err(StorageErrorKind::DoesNotExist.with_error(err))
@@ -146,14 +192,16 @@ impl Storage for RamStorage {
 /// Builder to create a prepopulated [`RamStorage`]. This is mostly useful for tests.
 #[derive(Default)]
 pub struct RamStorageBuilder {
-    files: HashMap<PathBuf, OwnedBytes>,
+    files: HashMap<PathBuf, RamFile>,
 }
 
 impl RamStorageBuilder {
     /// Adds a new file into the [`RamStorageBuilder`].
     pub fn put(mut self, path: &str, payload: &[u8]) -> Self {
-        self.files
-            .insert(PathBuf::from(path), OwnedBytes::new(payload.to_vec()));
+        self.files.insert(
+            PathBuf::from(path),
+            RamFile::new(OwnedBytes::new(payload.to_vec())),
+        );
         self
     }
 
@@ -204,12 +252,13 @@ impl StorageFactory for RamStorageFactory {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_suite::storage_test_suite;
+    use crate::test_suite::{storage_test_list, storage_test_suite};
 
     #[tokio::test]
     async fn test_storage() -> anyhow::Result<()> {
         let mut ram_storage = RamStorage::default();
         storage_test_suite(&mut ram_storage).await?;
+        storage_test_list(&mut ram_storage, 10).await?;
         Ok(())
     }
 
@@ -230,6 +279,27 @@ mod tests {
         assert_eq!(data_storage.uri(), data_storage_two.uri());
     }
 
+    #[tokio::test]
+    async fn test_ram_storage_factory_list() -> anyhow::Result<()> {
+        let ram_storage_factory = RamStorageFactory::default();
+        let data_storage = ram_storage_factory.resolve(&Uri::new("ram:///data".to_string()))?;
+        let home_storage = ram_storage_factory.resolve(&Uri::new("ram:///home".to_string()))?;
+        data_storage
+            .put(Path::new("split/file"), Box::new(b"abc".to_vec()))
+            .await?;
+        home_storage
+            .put(Path::new("split/other-file"), Box::new(b"abc".to_vec()))
+            .await?;
+        let data_files = data_storage.list(Path::new("")).await?;
+        assert_eq!(data_files.len(), 1);
+        assert_eq!(data_files[0].path, Path::new("split/file"));
+        assert_eq!(data_files[0].num_bytes, 3);
+        let home_files = home_storage.list(Path::new("split")).await?;
+        assert_eq!(home_files.len(), 1);
+        assert_eq!(home_files[0].path, Path::new("split/other-file"));
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_ram_storage_builder() -> anyhow::Result<()> {
         let storage = RamStorage::builder()
@@ -18,12 +18,23 @@
 // along with this program. If not, see <http://www.gnu.org/licenses/>.
 
 use std::ops::Range;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
 use quickwit_common::uri::Uri;
 
-use crate::{OwnedBytes, PutPayload, StorageErrorKind, StorageResult};
+use crate::{BulkDeleteError, OwnedBytes, PutPayload, StorageErrorKind, StorageResult};
+
+/// Metadata of a file listed by [`Storage::list`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileMetadata {
+    /// Path of the file, relative to the root of the storage.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub num_bytes: u64,
+    /// Time of the last modification of the file, as a Unix timestamp in seconds.
+    pub last_modified_timestamp: i64,
+}
 
 /// Storage meant to receive and serve quickwit's split.
 ///
@@ -62,6 +73,30 @@ pub trait Storage: Send + Sync + 'static {
     /// This method should return Ok(()) if the file did not exist.
     async fn delete(&self, path: &Path) -> StorageResult<()>;
 
+    /// Deletes a batch of files.
+    ///
+    /// Failing to delete a file does not abort the deletion of the others. Instead, the returned
+    /// error reports the files that were deleted and the ones that were not.
+    /// Files that did not exist are considered deleted.
+    ///
+    /// The default implementation deletes the files one by one. Storages supporting bulk deletion
+    /// should override it.
+    async fn bulk_delete(&self, paths: &[&Path]) -> Result<(), BulkDeleteError> {
+        let mut bulk_delete_error = BulkDeleteError::default();
+        for path in paths {
+            match self.delete(path).await {
+                Ok(()) => bulk_delete_error.successes.push(path.to_path_buf()),
+                Err(error) => {
+                    bulk_delete_error.failures.insert(path.to_path_buf(), error);
+                }
+            }
+        }
+        if bulk_delete_error.failures.is_empty() {
+            return Ok(());
+        }
+        Err(bulk_delete_error)
+    }
+
     /// Returns whether a file exists or not.
     async fn exists(&self, path: &Path) -> StorageResult<bool> {
         match self.file_num_bytes(path).await {
@@ -74,6 +109,20 @@ pub trait Storage: Send + Sync + 'static {
     /// Returns a file size.
     async fn file_num_bytes(&self, path: &Path) -> StorageResult<u64>;
 
+    /// Lists recursively the files located under the directory `prefix`, in lexicographic order.
+    /// The returned paths are relative to the root of the storage. Listing a directory that does
+    /// not exist returns an empty list.
+    ///
+    /// The default implementation returns an error: storages supporting listing should override
+    /// it.
+    async fn list(&self, prefix: &Path) -> StorageResult<Vec<FileMetadata>> {
+        Err(StorageErrorKind::InternalError.with_error(anyhow::anyhow!(
+            "Storage `{}` does not support listing files (prefix `{}`).",
+            self.uri(),
+            prefix.display()
+        )))
+    }
+
     /// Returns an URI identifying the storage
     fn uri(&self) -> &Uri;
 }
@@ -22,6 +22,7 @@ use std::sync::Arc;
 
 use once_cell::sync::OnceCell;
 use quickwit_common::uri::{Protocol, Uri};
+use quickwit_config::IndexStorageConfig;
 
 use crate::local_file_storage::LocalFileStorageFactory;
 use crate::ram_storage::RamStorageFactory;
@@ -63,6 +64,16 @@ pub trait StorageFactory: Send + Sync + 'static {
 
     /// Returns the appropriate [`Storage`] object for the URI.
     fn resolve(&self, uri: &Uri) -> Result<Arc<dyn Storage>, StorageResolverError>;
+
+    /// Returns the appropriate [`Storage`] object for the URI, taking into account the per-index
+    /// storage overrides. Factories that do not support any override ignore them.
+    fn resolve_with_storage_config(
+        &self,
+        uri: &Uri,
+        _storage_config: &IndexStorageConfig,
+    ) -> Result<Arc<dyn Storage>, StorageResolverError> {
+        self.resolve(uri)
+    }
 }
 
 /// A storage factory implementation for handling not supported features.
@@ -148,6 +159,27 @@ impl StorageUriResolver {
         let storage = resolver.resolve(uri)?;
         Ok(storage)
     }
+
+    /// Resolves the given URI with the storage config of the index, if any. Falls back to
+    /// [`StorageUriResolver::resolve`] when `storage_config_opt` is `None`.
+    pub fn resolve_with_storage_config(
+        &self,
+        uri: &Uri,
+        storage_config_opt: Option<&IndexStorageConfig>,
+    ) -> Result<Arc<dyn Storage>, StorageResolverError> {
+        let storage_config = match storage_config_opt {
+            Some(storage_config) => storage_config,
+            None => return self.resolve(uri),
+        };
+        let resolver = self
+            .per_protocol_resolver
+            .get(&uri.protocol())
+            .ok_or_else(|| StorageResolverError::ProtocolUnsupported {
+                protocol: uri.protocol().to_string(),
+            })?;
+        let storage = resolver.resolve_with_storage_config(uri, storage_config)?;
+        Ok(storage)
+    }
 }
 
 #[cfg(test)]
@@ -216,6 +248,44 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_storage_resolver_with_storage_config() -> anyhow::Result<()> {
+        let mut ram_storage_factory = MockStorageFactory::new();
+        ram_storage_factory
+            .expect_protocol()
+            .returning(|| Protocol::Ram);
+        ram_storage_factory.expect_resolve().returning(|_uri| {
+            Ok(Arc::new(
+                RamStorage::builder().put("hello", b"from_env").build(),
+            ))
+        });
+        ram_storage_factory
+            .expect_resolve_with_storage_config()
+            .returning(|_uri, storage_config| {
+                assert_eq!(storage_config.region.as_deref(), Some("eu-west-1"));
+                Ok(Arc::new(
+                    RamStorage::builder().put("hello", b"from_config").build(),
+                ))
+            });
+        let storage_resolver = StorageUriResolver::builder()
+            .register(ram_storage_factory)
+            .build();
+        let storage_uri = Uri::new("ram:///home".to_string());
+        let storage_config = IndexStorageConfig {
+            region: Some("eu-west-1".to_string()),
+            ..Default::default()
+        };
+        let storage =
+            storage_resolver.resolve_with_storage_config(&storage_uri, Some(&storage_config))?;
+        let data = storage.get_all(Path::new("hello")).await?;
+        assert_eq!(&data[..], b"from_config");
+
+        let storage = storage_resolver.resolve_with_storage_config(&storage_uri, None)?;
+        let data = storage.get_all(Path::new("hello")).await?;
+        assert_eq!(&data[..], b"from_env");
+        Ok(())
+    }
+
     #[test]
     fn test_storage_resolver_unsupported_protocol() {
         let storage_resolver = StorageUriResolver::for_test();
@@ -58,6 +58,8 @@ async fn test_suite_on_azure_storage() -> anyhow::Result<()> {
         multipart_threshold_num_bytes: 10_000_000,
         max_object_num_bytes: 5_000_000_000_000,
         max_concurrent_upload: 100,
+        max_concurrent_download: 100,
+        verify_multipart_checksum: true,
     });
     quickwit_storage::storage_test_multi_part_upload(&mut object_storage)
         .await
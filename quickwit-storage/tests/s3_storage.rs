@@ -41,6 +41,9 @@ async fn test_suite_on_s3_storage() -> anyhow::Result<()> {
     quickwit_storage::storage_test_single_part_upload(&mut object_storage)
         .await
         .context("test_single_part_upload")?;
+    quickwit_storage::storage_test_list(&mut object_storage, 1_100)
+        .await
+        .context("test_list")?;
 
     object_storage.set_policy(MultiPartPolicy {
         target_part_num_bytes: 5 * 1_024 * 1_024, //< the minimum on S3 is 5MB.
@@ -48,6 +51,8 @@ async fn test_suite_on_s3_storage() -> anyhow::Result<()> {
         multipart_threshold_num_bytes: 10_000_000,
         max_object_num_bytes: 5_000_000_000_000,
         max_concurrent_upload: 100,
+        max_concurrent_download: 100,
+        verify_multipart_checksum: true,
     });
     quickwit_storage::storage_test_multi_part_upload(&mut object_storage)
         .await